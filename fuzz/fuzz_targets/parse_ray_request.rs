@@ -0,0 +1,14 @@
+#![no_main]
+
+use std::time::UNIX_EPOCH;
+
+use libfuzzer_sys::fuzz_target;
+use raygun::{protocol::parse_ray_request, ui::detail::build_detail_view};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(request) = parse_ray_request(data) {
+        for payload in &request.payloads {
+            let _ = build_detail_view(payload, UNIX_EPOCH);
+        }
+    }
+});
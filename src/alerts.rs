@@ -0,0 +1,143 @@
+//! Getting attention when an event arrives: terminal bells or a sound command
+//! per payload kind, as configured under `[sounds]`. Shared by the TUI and
+//! plain output so both make the same noise for the same events.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use tracing::debug;
+
+use crate::{app::summarize_event, config::SoundConfig, state::TimelineEvent};
+
+/// Sounds closer together than this are dropped, so a burst of events makes
+/// one sound rather than a racket.
+const MIN_GAP: Duration = Duration::from_secs(1);
+
+/// Key in `[sounds]` for kinds without an entry of their own.
+pub const ANY_KIND: &str = "*";
+
+#[derive(Debug, Default)]
+pub struct Alerts {
+    sounds: BTreeMap<String, SoundConfig>,
+    last_sound: Option<Instant>,
+}
+
+impl Alerts {
+    pub fn new(sounds: &BTreeMap<String, SoundConfig>) -> Self {
+        Self {
+            sounds: sounds
+                .iter()
+                .map(|(kind, sound)| (kind.trim().to_ascii_lowercase(), sound.clone()))
+                .collect(),
+            last_sound: None,
+        }
+    }
+
+    /// The sound configured for `event`: that of its first payload type with
+    /// an entry, else of the kind the timeline shows (`query`), else `*`.
+    pub fn sound_for(&self, event: &TimelineEvent) -> Option<&SoundConfig> {
+        if self.sounds.is_empty() {
+            return None;
+        }
+        event
+            .request
+            .payloads
+            .iter()
+            .find_map(|payload| self.sounds.get(payload.kind.as_type()))
+            .or_else(|| self.sounds.get(&summarize_event(event).kind))
+            .or_else(|| self.sounds.get(ANY_KIND))
+    }
+
+    /// Plays the sound for an event that just arrived, writing bells to
+    /// `terminal`.
+    pub fn alert(&mut self, terminal: &mut impl Write, event: &TimelineEvent) -> io::Result<()> {
+        let Some(sound) = self.sound_for(event).cloned() else {
+            return Ok(());
+        };
+        if matches!(sound, SoundConfig::Bells(0)) {
+            return Ok(());
+        }
+        if self
+            .last_sound
+            .is_some_and(|played| played.elapsed() < MIN_GAP)
+        {
+            return Ok(());
+        }
+        self.last_sound = Some(Instant::now());
+
+        match sound {
+            SoundConfig::Bells(count) => {
+                terminal.write_all("\x07".repeat(count as usize).as_bytes())?;
+                terminal.flush()
+            }
+            SoundConfig::Command { command } => play(&command),
+        }
+    }
+}
+
+/// Runs a sound command such as `afplay /System/Library/Sounds/Glass.aiff`
+/// without waiting for it.
+fn play(command: &str) -> io::Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| io::Error::other("the sound command is empty"))?;
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || {
+        if let Err(err) = child.wait() {
+            debug!(?err, "failed to wait for the sound command");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn event(kind: &str) -> TimelineEvent {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{ "type": kind, "content": { "values": ["x"] } }],
+            "meta": {}
+        }))
+        .unwrap();
+        TimelineEvent::new(request, None)
+    }
+
+    #[test]
+    fn rings_for_configured_kinds_once_per_burst() {
+        let sounds = BTreeMap::from([
+            ("Exception".to_string(), SoundConfig::Bells(2)),
+            ("log".to_string(), SoundConfig::Bells(0)),
+        ]);
+        let mut alerts = Alerts::new(&sounds);
+        let mut out = Vec::new();
+
+        alerts.alert(&mut out, &event("log")).unwrap();
+        alerts.alert(&mut out, &event("table")).unwrap();
+        assert!(out.is_empty());
+        alerts.alert(&mut out, &event("exception")).unwrap();
+        assert_eq!(out, b"\x07\x07");
+        alerts.alert(&mut out, &event("exception")).unwrap();
+        assert_eq!(out, b"\x07\x07");
+
+        let sounds = BTreeMap::from([(ANY_KIND.to_string(), SoundConfig::Bells(1))]);
+        assert_eq!(
+            Alerts::new(&sounds).sound_for(&event("table")),
+            Some(&SoundConfig::Bells(1))
+        );
+    }
+}
@@ -1,9 +1,15 @@
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
-    io::ErrorKind,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs, io,
+    io::{ErrorKind, IsTerminal, Write},
     net::SocketAddr,
-    sync::Arc,
-    time::Duration,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use color_eyre::{
@@ -11,24 +17,73 @@ use color_eyre::{
     eyre::{Report, eyre},
 };
 use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
-use html_escape::decode_html_entities;
 use once_cell::sync::Lazy;
 use ratatui::layout::Rect;
 use regex::Regex;
-use serde_json::{Number, Value};
+use serde_json::Value;
 use tokio::{select, sync::mpsc};
 use tracing::{debug, info, warn};
 
 use crate::{
-    config::Config,
-    protocol::{Origin, Payload, PayloadKind},
-    server,
-    state::{AppState, PayloadLogger, TimelineEvent},
+    alerts::Alerts,
+    config::{
+        self, Config, DEFAULT_NOISE, HostAliases, LocaleConfig, PathMapping, Severity,
+        TerminalConfig, UnknownKindConfig, UnknownRenderer, VIEW_SLOTS, ViewConfig,
+    },
+    daemon,
+    delays::ProcessingDelays,
+    demo,
+    export::{har, html, markdown},
+    filter::{self, Filter},
+    import,
+    integrations::{self, Integrations},
+    keymap::{Action, Scope},
+    logs, problems,
+    protocol::{Payload, PayloadKind},
+    recovery::{self, SnapshotGuard},
+    scratch, server,
+    state::{
+        AppState, PayloadLogger, TimelineEvent,
+        bus::BusMessage,
+        routes::Routes,
+        rules::Rules,
+        store::{StoreQuery, TimelineStore},
+        summary::{
+            self, EventSummary, SUMMARY_MAX_CHARS, aggregated_log_payload, clip, flatten,
+            payload_kind_label, primary_payload, value_preview,
+        },
+    },
     tui::{
-        self, AppRenderMetadata, AppViewModel, DetailStateView, Event, LayoutConfig, OverlayArea,
-        TerminalGuard, TimelineEntry,
+        self, AppRenderMetadata, AppViewModel, DetailFilterView, DetailStateView, DiffView, Event,
+        LayoutConfig, OverlayArea, RangeMark, SourcePreview, TerminalGuard, TimelineEntry,
+        UiSettings, clipboard,
+        detail_rows::{DetailRows, DetailRowsKey},
+        diagnostics::{DiagnosticEntry, DiagnosticsView},
+        exceptions::{ExceptionRow, ExceptionsView},
+        history::HistoryView,
+        json_query::JsonQueryView,
+        log_viewer::{LogEntry, LogViewerView},
+        onboarding::{OnboardingSnippets, OnboardingView},
+        origins::{OriginRow, OriginsView},
+        problems::{ProblemEntry, ProblemsView},
+        profile::{ProfileRow, ProfileView},
+        prompt::PromptView,
+        quick_actions::{QuickAction, QuickActionItem, QuickActionsView},
+        stats::{DelayRow, StatsView},
+        title::TerminalStatus,
+        trash::TrashView,
+        unknown_kinds::{UnknownKindEntry, UnknownKindsView},
+    },
+    ui::{
+        copy::{self, CopyFormat},
+        detail::{
+            self, DetailLine, DetailSegment, RenderOverride, SegmentStyle,
+            build_labelled_detail_view,
+        },
+        diff,
+        query::Query,
+        schema,
     },
-    ui::detail::{self, build_detail_view},
 };
 use uuid::Uuid;
 
@@ -36,111 +91,702 @@ pub struct RaygunApp {
     tick_rate: Duration,
     state: Arc<AppState>,
     server: Option<server::ServerHandle>,
-    server_addr: SocketAddr,
+    /// Connection to the daemon whose timeline `state` mirrors (`raygun attach`).
+    daemon: Option<daemon::DaemonClient>,
+    /// Apply filters shared by other attached clients and share ours.
+    share_filters: bool,
+    /// Filters last sent to or received from the daemon.
+    shared_filters: daemon::SharedFilters,
+    /// Selected event last reported to the daemon.
+    shared_cursor: Option<Option<Uuid>>,
+    server_addrs: Vec<SocketAddr>,
+    public_url: Option<String>,
+    settings: Arc<UiSettings>,
+    path_mappings: Vec<PathMapping>,
+    host_aliases: Arc<HostAliases>,
+    /// `[collapse]` rules from the config, keyed by payload type.
+    collapse_rules: BTreeMap<String, Vec<String>>,
+    /// Renderers assigned to payload types Raygun doesn't know.
+    unknown_renderers: BTreeMap<String, UnknownRenderer>,
+    /// Renderers picked with `R` for single events, over the automatic one.
+    render_overrides: HashMap<Uuid, RenderOverride>,
+    /// Bumped on every config reload, which can change how any detail renders.
+    settings_generation: u64,
     selected: Option<usize>,
+    /// Selection a run of queued navigation keys leads to, each key moving on
+    /// from where the one before left it; applied once the run has been read.
+    /// `None` outside such a run, `Some(None)` until a key in it moves the
+    /// timeline.
+    timeline_target: Option<Option<usize>>,
     focus: Focus,
     detail_scroll: usize,
     layout: LayoutPreset,
     detail_states: HashMap<Uuid, DetailState>,
     visible_events: Vec<Uuid>,
+    /// Timeline indices of the events that close an HTTP request.
+    request_heads: Vec<usize>,
     color_filter: Option<String>,
+    event_filter: Option<Filter>,
+    /// Events `hide_noise` hides, from `noise` in the config.
+    noise: Filter,
+    hide_noise: bool,
+    prompt: Option<TextPrompt>,
     available_colors: Vec<String>,
     show_help: bool,
+    onboarding: Option<OnboardingView>,
     show_debug: bool,
     debug_scroll: usize,
+    show_schema: bool,
+    schema_scroll: usize,
+    /// The JSON query overlay, while it is open.
+    json_query: Option<JsonQueryState>,
+    /// The last query typed, offered again the next time the prompt opens.
+    last_json_query: String,
+    quick_actions: Option<QuickActionsMenu>,
+    pending_quick_action: Option<(QuickAction, Uuid)>,
+    /// A HAR export was requested; it runs once the key is handled.
+    pending_har_export: bool,
+    pending_markdown_export: bool,
+    /// The query in the history prompt was submitted; it runs once the key is
+    /// handled.
+    pending_history_search: bool,
+    diff: Option<DiffView>,
+    diff_scroll: usize,
+    status: Option<(String, Instant)>,
+    editor: Option<String>,
+    permalink: Option<config::PermalinkConfig>,
+    /// Target of the "Re-send payload" event action.
+    resend_url: Option<String>,
+    strict_protocol: bool,
+    show_diagnostics: bool,
+    diagnostics_scroll: usize,
+    show_logs: bool,
+    logs_scroll: usize,
+    /// Selected row of the unknown payload types overlay, while it is open.
+    unknown_kinds_cursor: Option<usize>,
+    /// Types the unknown payload types overlay listed, in its order.
+    unknown_kind_names: Vec<String>,
+    /// Selected row of the trash overlay, while it is open.
+    trash_cursor: Option<usize>,
+    /// Events the trash overlay listed, in its order.
+    trash_ids: Vec<Uuid>,
+    /// Selected row of the origins overlay, while it is open.
+    origins_cursor: Option<usize>,
+    /// Call sites the origins overlay listed, in its order, as sent by the
+    /// client.
+    origin_sites: Vec<(String, Option<u32>)>,
+    /// Selected row of the exceptions overlay, while it is open.
+    exceptions_cursor: Option<usize>,
+    /// Newest event of each group the exceptions overlay listed, in its order.
+    exception_events: Vec<Uuid>,
+    /// Deleted event to move back to the timeline.
+    pending_untrash: Option<Uuid>,
+    show_stats: bool,
+    /// Time from receiving events to drawing them, for the stats overlay.
+    delays: ProcessingDelays,
+    show_profile: bool,
+    /// Timings of the last frame, for the profiling panel.
+    frame_times: FrameTimes,
+    terminal: TerminalConfig,
+    /// Sounds made as events arrive.
+    alerts: Alerts,
+    /// Events recorded since the last key press, click or paste, oldest
+    /// first.
+    unread: Vec<Uuid>,
+    last_arrival: Option<Instant>,
+    problems_expanded: bool,
+    /// Problems recorded when the strip was last expanded or hidden.
+    problems_seen: u64,
+    /// Problems recorded as of the last render.
+    problems_recorded: u64,
+    focus_follow: bool,
+    /// Event whose focus line was last applied in focus-follow mode.
+    focus_followed: Option<Uuid>,
+    /// Last file read for the source preview, with its lines or read error.
+    source_cache: Option<(String, Result<Vec<String>, String>)>,
+    /// Detail of the selected event as last built, reused while its key holds.
+    detail_cache: Option<CachedDetail>,
+    /// Detail lines wrapped for the last frame, reused while they still fit.
+    detail_rows: Option<Arc<DetailRows>>,
+    /// Last known terminal size, updated on resize.
+    terminal_size: Option<(u16, u16)>,
+    /// Focused pane zoomed to the whole frame; the layout preset is untouched.
+    fullscreen: bool,
+    /// Store searched with the history search, when it keeps a history.
+    store: Option<Arc<dyn TimelineStore>>,
+    history: Option<HistorySearch>,
+    range: Option<MarkedRange>,
+    detail_filter: Option<DetailFilter>,
+    /// Detail line waiting for a copy format to be picked.
+    copy_prompt: Option<usize>,
+    /// The scratch pad is waiting for a paste.
+    scratch_open: bool,
+    /// Pasted text to render; it is recorded once the paste is handled.
+    pending_scratch: Option<String>,
+    /// Where the timeline is saved if the session ends unexpectedly.
+    recovery_path: Option<PathBuf>,
+    /// Events saved by an earlier session that ended unexpectedly, offered
+    /// for restoring until the offer is taken or dismissed.
+    recovery_offer: Option<usize>,
+    /// The saved session should be restored; it runs once the key is handled.
+    pending_restore: bool,
+    /// Addresses the HTTP server moves to once the key is handled.
+    pending_rebind: Option<Vec<SocketAddr>>,
+    /// Label typed for an event, applied on the next loop turn.
+    pending_label: Option<(Uuid, Option<String>)>,
+    /// Event whose color moves to the next Ray color on the next loop turn.
+    pending_color_cycle: Option<Uuid>,
+    pending_clear_matching: bool,
+    /// Screen change typed into a prompt, applied on the next loop turn.
+    pending_screen_edit: Option<ScreenEdit>,
+    /// How long quitting waits for requests in flight and queued writes.
+    drain_timeout: Duration,
+    /// Command line the session started with; reloads read the config file
+    /// it points at.
+    config: Config,
+    /// Integrations fed by this session's own server.
+    integrations: Option<Integrations>,
+    /// Saved filters, applied with the number key of their position.
+    views: Vec<ViewConfig>,
+    /// Config file that saved views are appended to.
+    config_path: Option<PathBuf>,
     last_render: Option<AppRenderMetadata>,
 }
 
+/// History search over the event store. Its results replace the live timeline
+/// until it is closed; the live timeline keeps recording underneath.
+#[derive(Debug, Default)]
+struct HistorySearch {
+    input: String,
+    /// The prompt is open.
+    editing: bool,
+    error: Option<String>,
+    /// Query and matches (oldest first) of the last search that ran.
+    results: Option<(String, Vec<TimelineEvent>)>,
+    truncated: bool,
+    /// Live timeline selection, restored when the search closes.
+    live_selected: Option<usize>,
+}
+
+/// Filter box narrowing the detail tree to matching lines and their ancestors.
+#[derive(Debug, Clone)]
+struct DetailFilter {
+    event_id: Uuid,
+    query: String,
+    /// The box has keyboard input.
+    editing: bool,
+}
+
+/// Timeline range marked with `mark_range`, by event id. Either end may be
+/// the older one.
+#[derive(Debug, Clone, Copy)]
+struct MarkedRange {
+    start: Uuid,
+    end: Option<Uuid>,
+}
+
+/// Open quick actions menu; `items` is refreshed on every frame.
+#[derive(Debug, Clone)]
+struct QuickActionsMenu {
+    event_id: Uuid,
+    cursor: usize,
+    items: Vec<QuickActionItem>,
+}
+
+/// A text prompt overlay and what its input is for.
+#[derive(Debug)]
+struct TextPrompt {
+    purpose: PromptPurpose,
+    input: String,
+    /// Why the last submission was rejected.
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PromptPurpose {
+    /// The timeline filter bar; the expression is applied on Enter.
+    Filter,
+    /// Names the current filters to save them as a view in the given slot.
+    SaveView(usize),
+    /// New addresses for the HTTP server.
+    Rebind,
+    /// Tracing filter directives for the log viewer.
+    LogFilter,
+    /// A new label for the given event.
+    Label(Uuid),
+    /// The screen to move the given event to.
+    MoveToScreen(Uuid),
+    /// A new name for the screen of the given event.
+    RenameScreen(Uuid),
+    /// A JSON query to run on the selected event.
+    JsonQuery,
+}
+
+/// A JSON query run on whichever event is selected, and its latest results.
+#[derive(Debug)]
+struct JsonQueryState {
+    query: Query,
+    /// The selected event's matches, pretty-printed.
+    results: Vec<String>,
+    scroll: usize,
+}
+
+/// A screen change made from the event actions menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScreenEdit {
+    /// Moves the event to the named screen, or off every screen.
+    Move(Uuid, Option<String>),
+    /// Renames the event's screen.
+    Rename(Uuid, String),
+    /// Clears every event on the event's screen.
+    Close(Uuid),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Focus {
     Timeline,
     Detail,
 }
 
+/// Loads and validates the config file; errors stop Raygun from starting.
+pub(crate) fn load_file_config(config: &Config) -> Result<config::FileConfig> {
+    let file_config = config
+        .load_file_config()
+        .map_err(|err| eyre!("{}. Run `raygun check` for details.", err))?;
+    let config_errors: Vec<String> = file_config
+        .validate()
+        .into_iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| issue.message)
+        .collect();
+    if !config_errors.is_empty() {
+        return Err(eyre!(
+            "Invalid config file:\n  {}\nRun `raygun check` for details.",
+            config_errors.join("\n  ")
+        ));
+    }
+    Ok(file_config)
+}
+
+/// Lets requests in flight finish and queued writes reach the disk, giving up
+/// after `timeout`.
+pub(crate) async fn drain(
+    state: &AppState,
+    server: Option<server::ServerHandle>,
+    timeout: Duration,
+) -> Result<()> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    if let Some(server) = server {
+        server.shutdown().await?;
+    }
+    if tokio::time::timeout_at(deadline, state.drain())
+        .await
+        .is_err()
+    {
+        warn!(
+            ?timeout,
+            "--debug-dump writes still pending after the drain timeout"
+        );
+    }
+    state.store().flush().await;
+    Ok(())
+}
+
+/// Creates the timeline state, starts the HTTP listeners feeding it and the
+/// integrations following it.
+pub(crate) async fn start_server(
+    config: &Config,
+    file_config: &config::FileConfig,
+) -> Result<(Arc<AppState>, server::ServerHandle, Integrations)> {
+    let payload_logger = config
+        .debug_dump
+        .as_ref()
+        .map(|path| PayloadLogger::new(path.clone()));
+    let mut state = AppState::with_logger(payload_logger);
+    if let Some(spec) = &config.store {
+        let store = spec.open(state.retention())?;
+        info!(session = ?store.session(), "persisting events to the store");
+        state = state.with_store(store);
+    }
+    state.set_rules(Rules::new(&file_config.rules)).await;
+    state
+        .set_routes(Routes::new(&file_config.routes, &file_config.hosts))
+        .await;
+    state.set_pin_errors(file_config.retention.pin_errors);
+    let state = Arc::new(state);
+    if config.demo {
+        demo::seed(&state).await;
+    }
+    for path in &config.imports {
+        let imported = import::import_file(&state, path).await?;
+        info!(
+            path = %path.display(),
+            requests = imported.requests,
+            skipped = imported.skipped,
+            "imported capture"
+        );
+    }
+    if config.stdin {
+        if io::stdin().is_terminal() {
+            return Err(eyre!(
+                "--stdin reads piped input, e.g. `cat payload.json | raygun --stdin`."
+            ));
+        }
+        import::spawn_stdin(Arc::clone(&state));
+    }
+    let server_config = server::ServerConfig {
+        bind_addrs: config.bind_addrs.clone(),
+        max_body_size: config.max_body_size,
+        dual_stack: config.dual_stack,
+        capture_fixtures: config.capture_fixtures.clone(),
+        strict_protocol: config.strict_protocol,
+        drain_timeout: config.drain_timeout(),
+        upstream: config.upstream.clone(),
+        trusted_proxies: config.trusted_proxies.clone(),
+    };
+    let server = server::spawn(Arc::clone(&state), server_config)
+        .await
+        .map_err(|err| match err {
+            server::ServerError::Bind { addr, source } if source.kind() == ErrorKind::AddrInUse => eyre!("Port {} is already in use. Pass --bind <addr:port> to choose a different address.", addr),
+            other => Report::from(other),
+        })?;
+
+    info!(addrs = ?server.addrs(), "HTTP server ready");
+    let integrations = integrations::spawn(&state, file_config);
+    Ok((state, server, integrations))
+}
+
 const TIMELINE_VIEW_LIMIT: usize = 200;
+const STATUS_DURATION: Duration = Duration::from_secs(4);
+/// How long the "Re-send payload" action waits for the target.
+const RESEND_TIMEOUT: Duration = Duration::from_secs(5);
+/// Lines shown above and below the focused line in the source preview.
+const PREVIEW_CONTEXT: usize = 3;
+/// What the JSON query prompt offers before anything was typed into it.
+const DEFAULT_JSON_QUERY: &str = "$.payloads[0].content";
+/// Lines PageUp and PageDown move a scrolling overlay by.
+const OVERLAY_PAGE: usize = 10;
 
 impl RaygunApp {
     pub async fn bootstrap(config: Config) -> Result<Self> {
-        let payload_logger = config
-            .debug_dump
-            .as_ref()
-            .map(|path| PayloadLogger::new(path.clone()));
-        let state = Arc::new(AppState::with_logger(payload_logger));
-        let bind_addr = config.bind_addr;
-        let server = server::spawn(Arc::clone(&state), server::ServerConfig { bind_addr })
-            .await
-            .map_err(|err| match err {
-                server::ServerError::Io(io_err) if io_err.kind() == ErrorKind::AddrInUse => eyre!("Port {} is already in use. Pass --bind <addr:port> to choose a different address.", bind_addr),
-                other => Report::from(other),
-            })?;
-        let server_addr = server.addr();
+        let file_config = load_file_config(&config)?;
+        let (state, server, integrations) = start_server(&config, &file_config).await?;
+        let server_addrs = server.addrs().to_vec();
+        // First launch: no config file yet, so walk the user through client setup.
+        let onboarding = (!config.config_file_exists()).then(|| OnboardingView {
+            snippets: OnboardingSnippets::new(&server_addrs),
+            copied: None,
+        });
+
+        let mut app = Self::new(&config, file_config, state, server_addrs);
+        app.server = Some(server);
+        app.integrations = Some(integrations);
+        app.onboarding = onboarding;
+        app.recovery_path = recovery::recovery_path();
+        app.recovery_offer = app
+            .recovery_path
+            .as_deref()
+            .and_then(recovery::saved_requests);
+        Ok(app)
+    }
+
+    /// Opens the UI on a running daemon's timeline instead of starting a server.
+    pub async fn attach(config: Config) -> Result<Self> {
+        let file_config = load_file_config(&config)?;
+        let path = config.socket_path();
+        let attachment = daemon::attach(&path).await.map_err(|err| {
+            eyre!(
+                "{}. Start one with `raygun daemon` or pass --socket <path>.",
+                err
+            )
+        })?;
+        info!(socket = %path.display(), "attached to daemon");
+
+        let mut app = Self::new(&config, file_config, attachment.state, attachment.addrs);
+        // The daemon records; a client can still search the same store.
+        if let Some(spec) = &config.store {
+            app.store = Some(spec.open_read_only()?);
+        }
+        app.public_url = config.public_url.clone().or(attachment.public_url);
+        app.daemon = Some(attachment.client);
+        app.share_filters = matches!(
+            config.command,
+            Some(config::Command::Attach {
+                share_filters: true
+            })
+        );
+        Ok(app)
+    }
 
-        info!(addr = %server_addr, "HTTP server ready");
+    fn new(
+        config: &Config,
+        file_config: config::FileConfig,
+        state: Arc<AppState>,
+        server_addrs: Vec<SocketAddr>,
+    ) -> Self {
+        let mut settings = UiSettings::new(&file_config);
+        settings.ascii = config.ascii;
+        let store = Some(Arc::clone(state.store())).filter(|store| store.keeps_history());
+        let unknown_renderers = file_config.unknown_renderers();
 
-        Ok(Self {
+        Self {
             tick_rate: Duration::from_millis(250),
             state,
-            server: Some(server),
-            server_addr,
+            server: None,
+            daemon: None,
+            share_filters: false,
+            shared_filters: daemon::SharedFilters::default(),
+            shared_cursor: None,
+            server_addrs,
+            public_url: config.public_url.clone(),
+            settings: Arc::new(settings),
+            path_mappings: file_config.path_mappings,
+            host_aliases: Arc::new(HostAliases::new(&file_config.hosts)),
+            unknown_renderers,
+            render_overrides: HashMap::new(),
+            settings_generation: 0,
+            collapse_rules: file_config.collapse,
             selected: None,
+            timeline_target: None,
             focus: Focus::Timeline,
             detail_scroll: 0,
             layout: LayoutPreset::DetailFocus,
             detail_states: HashMap::new(),
             visible_events: Vec::new(),
+            request_heads: Vec::new(),
             color_filter: None,
+            event_filter: None,
+            noise: noise_filter(
+                file_config.noise.as_deref(),
+                Arc::new(HostAliases::new(&file_config.hosts)),
+            ),
+            hide_noise: false,
+            prompt: None,
             available_colors: Vec::new(),
             show_help: false,
+            onboarding: None,
             show_debug: false,
             debug_scroll: 0,
+            show_schema: false,
+            schema_scroll: 0,
+            json_query: None,
+            last_json_query: DEFAULT_JSON_QUERY.to_string(),
+            quick_actions: None,
+            pending_quick_action: None,
+            pending_har_export: false,
+            pending_markdown_export: false,
+            pending_history_search: false,
+            diff: None,
+            diff_scroll: 0,
+            status: None,
+            editor: file_config.editor,
+            permalink: file_config.permalink,
+            resend_url: file_config.resend_url,
+            strict_protocol: config.strict_protocol,
+            show_diagnostics: false,
+            diagnostics_scroll: 0,
+            show_logs: false,
+            logs_scroll: 0,
+            unknown_kinds_cursor: None,
+            unknown_kind_names: Vec::new(),
+            trash_cursor: None,
+            trash_ids: Vec::new(),
+            origins_cursor: None,
+            origin_sites: Vec::new(),
+            exceptions_cursor: None,
+            exception_events: Vec::new(),
+            pending_untrash: None,
+            show_stats: false,
+            show_profile: false,
+            frame_times: FrameTimes::default(),
+            terminal: file_config.terminal,
+            alerts: Alerts::new(&file_config.sounds),
+            unread: Vec::new(),
+            last_arrival: None,
+            delays: ProcessingDelays::new(SystemTime::now()),
+            problems_expanded: false,
+            problems_seen: 0,
+            problems_recorded: 0,
+            focus_follow: false,
+            focus_followed: None,
+            source_cache: None,
+            detail_cache: None,
+            detail_rows: None,
+            terminal_size: None,
+            fullscreen: false,
+            store,
+            history: None,
+            range: None,
+            detail_filter: None,
+            copy_prompt: None,
+            scratch_open: false,
+            pending_scratch: None,
+            recovery_path: None,
+            recovery_offer: None,
+            pending_restore: false,
+            pending_rebind: None,
+            pending_label: None,
+            pending_color_cycle: None,
+            pending_clear_matching: false,
+            pending_screen_edit: None,
+            drain_timeout: config.drain_timeout(),
+            config: config.clone(),
+            integrations: None,
+            views: file_config.views,
+            config_path: config.config_path(),
             last_render: None,
-        })
+        }
     }
 
     pub async fn run(mut self) -> Result<()> {
         info!("starting Raygun placeholder UI");
 
         let mut terminal = TerminalGuard::new()?;
+        self.terminal_size = crossterm::terminal::size().ok();
         let (tx, mut rx) = mpsc::unbounded_channel();
         let event_handle = tui::spawn_event_loop(tx, self.tick_rate);
+        // Only a session running its own server holds the only copy of its
+        // timeline; a daemon keeps recording after a client dies.
+        let mut snapshot = self
+            .recovery_path
+            .clone()
+            .filter(|_| self.daemon.is_none())
+            .map(|path| SnapshotGuard::new(Arc::clone(&self.state), path));
+        let mut signalled = false;
+        let mut hangups = Hangups::new();
+        // Redraw as soon as the state changes rather than on the next tick.
+        let mut bus = self.state.bus().subscribe();
+        let mut terminal_status = TerminalStatus::default();
+        // An input event read while draining navigation keys, handled next.
+        let mut queued = None;
 
         loop {
-            let view_model = self.build_view_model().await;
+            let started = Instant::now();
+            let mut view_model = self.build_view_model().await;
+            self.frame_times.build = started.elapsed();
+            view_model.profile = self
+                .show_profile
+                .then(|| self.frame_times.view(self.settings.locale));
             let timeline_len = view_model.timeline.len();
 
             let detail_context = DetailContext::new(
-                view_model.detail.as_ref(),
+                view_model.detail.as_deref(),
                 view_model
                     .detail_state
                     .as_ref()
                     .map(|state| &state.collapsed),
             );
 
+            let started = Instant::now();
             let render_info = terminal.draw(|frame| tui::render_app(frame, &view_model))?;
+            self.frame_times.render = started.elapsed();
             self.last_render = Some(render_info);
+            self.delays.drawn(SystemTime::now());
+            if let Err(err) = terminal_status.update(
+                &mut io::stdout(),
+                self.terminal,
+                self.unread.len(),
+                self.last_arrival,
+            ) {
+                debug!(?err, "failed to update the terminal title");
+            }
 
             let exit_requested = select! {
-                maybe_event = rx.recv() => {
+                maybe_event = next_event(&mut rx, &mut queued) => {
                     match maybe_event {
-                        Some(event) => self.handle_event(event, timeline_len, &detail_context),
-                        None => true,
+                        Some(event) => self.handle_input(
+                            event,
+                            &mut rx,
+                            &mut queued,
+                            timeline_len,
+                            &detail_context,
+                        ),
+                        // The terminal went away, e.g. its window was closed;
+                        // keep the timeline for the next session.
+                        None => {
+                            signalled = true;
+                            true
+                        }
                     }
                 }
-                ctrl_c = tokio::signal::ctrl_c() => {
-                    if let Err(err) = ctrl_c {
-                        warn!(?err, "failed to listen for ctrl+c");
+                _ = hangups.recv() => {
+                    self.reload_config();
+                    false
+                }
+                // One redraw covers a burst; a lagged receiver just redraws.
+                // The state outlives the loop, so the bus never closes.
+                message = bus.recv() => {
+                    let mut message = message.ok();
+                    while let Some(received) = message {
+                        self.track_unread(&received);
+                        if let BusMessage::Recorded { event } = &received
+                            && let Err(err) = self.alerts.alert(&mut io::stdout(), event)
+                        {
+                            debug!(?err, "failed to play the sound for an event");
+                        }
+                        message = bus.try_recv().ok();
+                    }
+                    false
+                }
+                interrupt = exit_signal() => {
+                    if let Err(err) = interrupt {
+                        warn!(?err, "failed to listen for interrupt signals");
                     } else {
-                        info!("received ctrl+c");
+                        info!("received interrupt signal");
+                        signalled = true;
                     }
                     true
                 }
             };
 
+            if let Some((action, event_id)) = self.pending_quick_action.take() {
+                self.run_quick_action(action, event_id).await;
+            }
+            if std::mem::take(&mut self.pending_har_export) {
+                self.export_har().await;
+            }
+            if std::mem::take(&mut self.pending_markdown_export) {
+                self.export_markdown().await;
+            }
+            if std::mem::take(&mut self.pending_history_search) {
+                self.run_history_search().await;
+            }
+            if let Some(text) = self.pending_scratch.take() {
+                self.record_scratch(&text).await;
+            }
+            if std::mem::take(&mut self.pending_restore) {
+                self.restore_recovery().await;
+            }
+            if let Some(event_id) = self.pending_untrash.take() {
+                self.untrash(event_id).await;
+            }
+            if let Some(addrs) = self.pending_rebind.take() {
+                self.rebind(addrs).await;
+            }
+            if let Some((event_id, label)) = self.pending_label.take() {
+                self.relabel(event_id, label).await;
+            }
+            if let Some(event_id) = self.pending_color_cycle.take() {
+                self.cycle_event_color(event_id).await;
+            }
+            if std::mem::take(&mut self.pending_clear_matching) {
+                self.clear_matching().await;
+            }
+            if let Some(edit) = self.pending_screen_edit.take() {
+                self.edit_screen(edit).await;
+            }
+
             if exit_requested {
                 break;
             }
         }
 
+        if !signalled && let Some(snapshot) = snapshot.as_mut() {
+            snapshot.disarm();
+        }
+        if let Err(err) = terminal_status.reset(&mut io::stdout()) {
+            debug!(?err, "failed to restore the terminal title");
+        }
         drop(terminal);
         drop(rx);
 
@@ -148,16 +794,107 @@ impl RaygunApp {
             warn!(?err, "terminal event loop task ended unexpectedly");
         }
 
-        if let Some(server) = self.server.take() {
-            server.shutdown().await?;
-        }
+        drain(&self.state, self.server.take(), self.drain_timeout).await?;
 
         info!("Raygun shutting down");
         Ok(())
     }
 
     async fn build_view_model(&mut self) -> AppViewModel {
-        let events = self.state.timeline_snapshot().await;
+        let events = self.displayed_events().await;
+        if !events.is_empty() {
+            self.onboarding = None;
+        }
+        if !self.showing_history() {
+            self.delays.picked_up(&events, SystemTime::now());
+        }
+        if self
+            .status
+            .as_ref()
+            .is_some_and(|(_, shown_at)| shown_at.elapsed() > STATUS_DURATION)
+        {
+            self.status = None;
+        }
+        if self
+            .daemon
+            .as_ref()
+            .is_some_and(|daemon| !daemon.is_connected())
+        {
+            self.set_status("Lost the connection to the daemon; the timeline no longer updates");
+        }
+        if let Some(event_id) = self.quick_actions.as_ref().map(|menu| menu.event_id) {
+            match events.iter().position(|event| event.id == event_id) {
+                Some(position) => {
+                    let range_len = self
+                        .range_bounds(&events)
+                        .map(|(first, last)| last - first + 1);
+                    let items = self.quick_action_items(&events[position], position > 0, range_len);
+                    if let Some(menu) = self.quick_actions.as_mut() {
+                        menu.cursor = menu.cursor.min(items.len().saturating_sub(1));
+                        menu.items = items;
+                    }
+                }
+                None => self.quick_actions = None,
+            }
+        }
+        self.receive_shared_filters();
+        let unknown_kinds = self.unknown_kinds_cursor.map(|cursor| {
+            let entries = self.unknown_kind_entries(&events);
+            self.unknown_kind_names = entries.iter().map(|entry| entry.kind.clone()).collect();
+            let cursor = cursor.min(entries.len().saturating_sub(1));
+            self.unknown_kinds_cursor = Some(cursor);
+            UnknownKindsView { entries, cursor }
+        });
+        let trash = match self.trash_cursor {
+            Some(cursor) => {
+                let entries: Vec<TimelineEntry> = self
+                    .state
+                    .trash_snapshot()
+                    .await
+                    .iter()
+                    .map(summarize_event)
+                    .collect();
+                self.trash_ids = entries.iter().map(|entry| entry.id).collect();
+                let cursor = cursor.min(entries.len().saturating_sub(1));
+                self.trash_cursor = Some(cursor);
+                Some(TrashView { entries, cursor })
+            }
+            None => None,
+        };
+        let origins = match self.origins_cursor {
+            Some(cursor) => {
+                let events = self.state.timeline_snapshot().await;
+                let (sites, rows) = self.origin_rows(&events);
+                self.origin_sites = sites;
+                let cursor = cursor.min(rows.len().saturating_sub(1));
+                self.origins_cursor = Some(cursor);
+                Some(OriginsView {
+                    rows,
+                    total: events.len(),
+                    cursor,
+                })
+            }
+            None => None,
+        };
+        let exceptions = match self.exceptions_cursor {
+            Some(cursor) => {
+                let events = self.state.timeline_snapshot().await;
+                let (newest, rows) = exception_rows(&events);
+                self.exception_events = newest;
+                let cursor = cursor.min(rows.len().saturating_sub(1));
+                self.exceptions_cursor = Some(cursor);
+                Some(ExceptionsView { rows, cursor })
+            }
+            None => None,
+        };
+        let stats = if !self.show_stats {
+            None
+        } else if self.daemon.is_some() {
+            Some(self.stats_view(None))
+        } else {
+            Some(self.stats_view(Some(self.state.duplicates().await)))
+        };
+        let range_marks = self.range_marks(&events);
         let mut ordered_events: Vec<_> = events.into_iter().rev().collect();
         if ordered_events.len() > TIMELINE_VIEW_LIMIT {
             ordered_events.truncate(TIMELINE_VIEW_LIMIT);
@@ -171,19 +908,21 @@ impl RaygunApp {
         }
         self.available_colors = available_colors.into_iter().collect();
 
-        if let Some(filter) = &self.color_filter {
-            if !self.available_colors.iter().any(|value| value == filter) {
-                self.color_filter = None;
-            }
+        if let Some(filter) = &self.color_filter
+            && !self.available_colors.iter().any(|value| value == filter)
+        {
+            self.color_filter = None;
         }
 
-        if let Some(filter) = &self.color_filter {
-            ordered_events.retain(|event| event.color.as_deref() == Some(filter.as_str()));
-        }
+        let now = SystemTime::now();
+        ordered_events.retain(|event| self.matches_filters(event, now));
 
         if ordered_events.is_empty() {
             self.show_debug = false;
             self.debug_scroll = 0;
+            self.show_schema = false;
+            self.schema_scroll = 0;
+            self.json_query = None;
         }
 
         let previous_selection = self.selected;
@@ -201,31 +940,205 @@ impl RaygunApp {
             self.detail_scroll = 0;
         }
 
-        let timeline = ordered_events
+        let mut timeline = ordered_events
             .iter()
-            .map(|event| summarize_event(event))
+            .map(summarize_event)
             .collect::<Vec<_>>();
 
+        for (entry, event) in timeline.iter_mut().zip(&ordered_events) {
+            entry.range = range_marks.get(&entry.id).copied();
+            entry.host = event
+                .request
+                .meta
+                .get("hostname")
+                .and_then(Value::as_str)
+                .and_then(|hostname| self.host_aliases.get(hostname))
+                .map(str::to_string);
+            entry.origin = event_origin(event).map(|(file, line)| self.location(&file, line));
+            entry.unread = self.unread.contains(&entry.id);
+        }
         self.visible_events = timeline.iter().map(|entry| entry.id).collect();
+        self.request_heads = timeline
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.request.is_some())
+            .map(|(index, _)| index)
+            .collect();
+        if let Some(daemon) = &self.daemon {
+            let cursors = daemon.other_cursors();
+            for entry in &mut timeline {
+                entry.viewers = cursors.iter().filter(|id| **id == entry.id).count();
+            }
+        }
+        self.publish_session();
+
+        let mut detail_filter = None;
+        let mut parse_time = None;
+        let mut detail_build = None;
+        let detail = match self
+            .selected
+            .and_then(|index| Some((index, ordered_events.get(index)?)))
+        {
+            Some((index, event)) => {
+                let started = Instant::now();
+                let filter = self
+                    .detail_filter
+                    .as_ref()
+                    .filter(|filter| filter.event_id == event.id);
+                let key = DetailKey {
+                    event: event.id,
+                    revision: event.revision,
+                    settings_generation: self.settings_generation,
+                    width: self.detail_width(),
+                    render_override: self.render_overrides.get(&event.id).copied(),
+                    filter: filter.map(|filter| filter.query.clone()),
+                    readings: reading_history(event, &ordered_events[index + 1..]),
+                };
+                let cached = match self.detail_cache.take() {
+                    Some(cached) if cached.key == key => cached,
+                    _ => self.build_detail(event, key),
+                };
+                parse_time = Some(started.elapsed());
+                if let (Some(filter), Some((matches, total))) = (filter, cached.filter_counts) {
+                    detail_filter = Some(DetailFilterView {
+                        query: filter.query.clone(),
+                        editing: filter.editing,
+                        matches,
+                        total,
+                    });
+                }
+                detail_build = Some(cached.build);
+                let detail = Arc::clone(&cached.detail);
+                self.detail_cache = Some(cached);
+                Some(detail)
+            }
+            None => None,
+        };
+
+        self.frame_times.parse = parse_time;
 
-        let detail = self
+        let selected_kind = self
             .selected
             .and_then(|index| ordered_events.get(index))
-            .map(build_detail_view_for_event);
+            .and_then(primary_payload)
+            .map(|payload| payload.kind.as_type().to_string());
 
         let debug_json = if self.show_debug {
             self.selected
                 .and_then(|index| ordered_events.get(index))
-                .map(|event| format!("{:#?}", event))
+                .map(|event| {
+                    format!(
+                        "{}\n\n{:#?}",
+                        provenance_header(event, self.settings.locale),
+                        event
+                    )
+                })
+        } else {
+            None
+        };
+
+        let recorded_diagnostics = self.state.diagnostics_snapshot().await;
+        let diagnostic_count = recorded_diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.issues.len())
+            .sum();
+        let diagnostics = self.show_diagnostics.then(|| DiagnosticsView {
+            entries: recorded_diagnostics
+                .iter()
+                .rev()
+                .map(|diagnostic| DiagnosticEntry {
+                    age: format_elapsed(diagnostic.received_at.elapsed().unwrap_or_default()),
+                    request_uuid: diagnostic.request_uuid.clone(),
+                    rejected: diagnostic.rejected,
+                    issues: diagnostic.issues.clone(),
+                })
+                .collect(),
+            strict_protocol: self.strict_protocol,
+            scroll: self.diagnostics_scroll,
+        });
+
+        let logs = self.show_logs.then(|| LogViewerView {
+            entries: logs::snapshot()
+                .into_iter()
+                .rev()
+                .map(|line| LogEntry {
+                    time: format_clock(line.at, self.settings.locale),
+                    level: line.level,
+                    target: line.target,
+                    message: flatten(&line.message),
+                })
+                .collect(),
+            filter: logs::filter(),
+            scroll: self.logs_scroll,
+        });
+
+        let (recorded_problems, problems_recorded) = problems::snapshot();
+        self.problems_recorded = problems_recorded;
+        if self.problems_expanded {
+            self.problems_seen = problems_recorded;
+        }
+        let unseen = problems_recorded.saturating_sub(self.problems_seen) as usize;
+        let problems = (self.problems_expanded || unseen > 0).then(|| ProblemsView {
+            entries: recorded_problems
+                .iter()
+                .rev()
+                .map(|problem| ProblemEntry {
+                    time: format_clock(problem.at, self.settings.locale),
+                    error: problem.level == tracing::Level::ERROR,
+                    message: flatten(&problem.message),
+                })
+                .collect(),
+            unseen,
+            expanded: self.problems_expanded,
+        });
+
+        let schema = if self.show_schema {
+            self.selected
+                .and_then(|index| ordered_events.get(index))
+                .map(schema_for_event)
         } else {
             None
         };
 
+        let selected_event = self.selected.and_then(|index| ordered_events.get(index));
+        let json_query = match (self.json_query.as_mut(), selected_event) {
+            (Some(state), Some(event)) => {
+                let document = import::exported_request(event);
+                state.results = state
+                    .query
+                    .run(&document)
+                    .into_iter()
+                    .map(|value| serde_json::to_string_pretty(value).unwrap_or_default())
+                    .collect();
+                Some(JsonQueryView {
+                    query: state.query.source().to_string(),
+                    results: state.results.clone(),
+                    scroll: state.scroll,
+                })
+            }
+            _ => None,
+        };
+
         let mut detail_state_view = None;
+        let mut detail_breadcrumb = Vec::new();
+        let mut collapse_generation = None;
 
         if let Some(event_id) = self.current_event_id() {
-            let entry = self.detail_states.entry(event_id).or_default();
+            let entry = self.detail_states.entry(event_id).or_insert_with(|| {
+                let rules = selected_kind
+                    .as_deref()
+                    .and_then(|kind| self.collapse_rules.get(kind));
+                DetailState::new(match (&detail, rules) {
+                    (Some(detail), Some(rules)) => detail::default_collapsed(detail, rules),
+                    _ => HashSet::new(),
+                })
+            });
             if let Some(detail) = &detail {
+                if self.focus_follow && self.focus_followed != Some(event_id) {
+                    focus_detail_line(detail, entry);
+                    self.focus_followed = Some(event_id);
+                }
+
                 let (visible_indices, _) =
                     detail::visible_indices_with_children(detail, Some(&entry.collapsed));
                 let visible_len = visible_indices.len();
@@ -237,6 +1150,8 @@ impl RaygunApp {
                     let max = visible_len.saturating_sub(1);
                     entry.scroll = entry.scroll.min(max);
                     entry.cursor = entry.cursor.min(max);
+                    detail_breadcrumb =
+                        detail::breadcrumb(&detail.lines, visible_indices[entry.cursor]);
                 }
 
                 self.detail_scroll = entry.scroll;
@@ -246,6 +1161,7 @@ impl RaygunApp {
                 self.detail_scroll = 0;
             }
 
+            collapse_generation = Some(entry.collapse_generation);
             detail_state_view = Some(DetailStateView {
                 cursor: entry.cursor,
                 collapsed: entry.collapsed.clone(),
@@ -254,190 +1170,1871 @@ impl RaygunApp {
             self.detail_scroll = 0;
         }
 
+        let (detail_footer, source_preview) = match (&detail, &detail_state_view) {
+            (Some(detail), Some(state)) if self.focus_follow => self.follow_cursor(detail, state),
+            _ => (None, None),
+        };
+
+        // Wrapped for the width the pane had last frame; the renderer wraps
+        // again itself if that changed.
+        let collapsed = detail_state_view.as_ref().map(|state| &state.collapsed);
+        let rows_key = match (self.current_event_id(), detail_build, &self.last_render) {
+            (Some(event), Some(build), Some(layout)) => Some(DetailRowsKey {
+                event,
+                build,
+                collapse_generation: collapse_generation.unwrap_or_default(),
+                width: layout.detail_inner.width,
+            }),
+            _ => None,
+        };
+        self.detail_rows = match (&detail, rows_key) {
+            (Some(detail), Some(key)) => match self.detail_rows.take() {
+                Some(rows) if rows.is_current(&key) => Some(rows),
+                _ => Some(Arc::new(DetailRows::keyed(key, detail, collapsed))),
+            },
+            _ => None,
+        };
+
         AppViewModel {
             total_events: self.state.timeline_len().await,
-            bind_addr: self.server_addr,
+            noise_hidden: self.hide_noise,
+            unread: self.unread.len(),
+            bind_addrs: self.server_addrs.clone(),
+            public_url: match &self.public_url {
+                Some(url) => Some(url.clone()),
+                None => self.state.public_url().await,
+            },
             timeline,
             selected: self.selected,
             detail,
+            detail_footer,
             focus_detail: matches!(self.focus, Focus::Detail),
             detail_scroll: self.detail_scroll,
             layout: self.layout.config(),
             detail_state: detail_state_view,
+            detail_rows: self.detail_rows.clone(),
             active_color_filter: self.color_filter.clone(),
+            event_filter: self.event_filter.as_ref().map(|filter| filter.source().to_string()),
+            prompt: self.prompt_view(),
             available_colors: self.available_colors.clone(),
+            views: self
+                .views
+                .iter()
+                .take(VIEW_SLOTS)
+                .map(|view| view.name.clone())
+                .collect(),
             show_help: self.show_help,
+            onboarding: self.onboarding.clone(),
             debug_json,
             debug_scroll: self.debug_scroll,
+            schema,
+            schema_scroll: self.schema_scroll,
+            json_query,
+            quick_actions: self.quick_actions.as_ref().map(|menu| QuickActionsView {
+                items: menu.items.clone(),
+                cursor: menu.cursor,
+            }),
+            diff: self.diff.clone(),
+            diff_scroll: self.diff_scroll,
+            status: match self.copy_prompt {
+                Some(_) => Some(format!(
+                    "Copy value as: {} · Esc cancel",
+                    CopyFormat::ALL
+                        .map(|format| format!("{} {}", format.key(), format.label()))
+                        .join(" · ")
+                )),
+                None if self.recovery_offer.is_some() && self.status.is_none() => {
+                    self.recovery_offer.map(|count| {
+                        format!(
+                            "The last session ended unexpectedly: r restore its {} event{} · Esc discard",
+                            count,
+                            plural(count)
+                        )
+                    })
+                }
+                None if self.scratch_open => Some(
+                    "Scratch pad: paste JSON, a var-dumper dump or captured Ray requests · Esc cancel"
+                        .to_string(),
+                ),
+                None => self.status.as_ref().map(|(message, _)| message.clone()),
+            },
+            diagnostic_count,
+            diagnostics,
+            logs,
+            unknown_kinds,
+            trash,
+            origins,
+            exceptions,
+            stats,
+            // Filled in by `run`, which times this build.
+            profile: None,
+            problems,
+            source_preview,
+            fullscreen: self.fullscreen,
+            viewers: self.daemon.as_ref().map(daemon::DaemonClient::viewer_count),
+            history: self.history.as_ref().map(|history| HistoryView {
+                prompt: history.editing.then(|| history.input.clone()),
+                error: history.error.clone(),
+                results_for: history.results.as_ref().map(|(query, _)| query.clone()),
+                matches: history
+                    .results
+                    .as_ref()
+                    .map_or(0, |(_, events)| events.len()),
+                truncated: history.truncated,
+            }),
+            detail_filter,
+            detail_breadcrumb,
+            settings: Arc::clone(&self.settings),
         }
     }
 
-    fn handle_event(
-        &mut self,
-        event: Event,
-        timeline_len: usize,
-        detail_ctx: &DetailContext,
-    ) -> bool {
-        match event {
-            Event::Input(key) => {
-                if self.show_help {
-                    return match key.code {
-                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
-                        KeyCode::Char('q')
-                        | KeyCode::Char('Q')
-                        | KeyCode::Enter
-                        | KeyCode::Char('?')
-                        | KeyCode::Esc => {
-                            self.show_help = false;
-                            false
-                        }
-                        _ => false,
-                    };
-                }
+    /// History search results while a search is open, the live timeline otherwise.
+    async fn displayed_events(&self) -> Vec<TimelineEvent> {
+        match self
+            .history
+            .as_ref()
+            .and_then(|history| history.results.as_ref())
+        {
+            Some((_, events)) => events.clone(),
+            None => self.state.timeline_snapshot().await,
+        }
+    }
 
-                if self.show_debug {
-                    return match key.code {
-                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
-                        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            self.show_debug = false;
-                            self.debug_scroll = 0;
-                            false
-                        }
-                        KeyCode::Enter | KeyCode::Esc => {
-                            self.show_debug = false;
-                            self.debug_scroll = 0;
-                            false
-                        }
-                        KeyCode::Up => {
-                            self.debug_scroll = self.debug_scroll.saturating_sub(1);
-                            false
-                        }
-                        KeyCode::Down => {
-                            self.debug_scroll = self.debug_scroll.saturating_add(1);
-                            false
-                        }
-                        KeyCode::PageUp => {
-                            self.debug_scroll = self.debug_scroll.saturating_sub(10);
-                            false
-                        }
-                        KeyCode::PageDown => {
-                            self.debug_scroll = self.debug_scroll.saturating_add(10);
-                            false
-                        }
-                        KeyCode::Home => {
-                            self.debug_scroll = 0;
-                            false
-                        }
-                        _ => false,
-                    };
-                }
+    /// Whether `event` passes the color filter and the timeline filter, and
+    /// isn't noise while noise is hidden.
+    fn matches_filters(&self, event: &TimelineEvent, now: SystemTime) -> bool {
+        self.color_filter
+            .as_deref()
+            .is_none_or(|color| event.color.as_deref() == Some(color))
+            && self
+                .event_filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches_at(event, now))
+            && !(self.hide_noise && self.noise.matches_at(event, now))
+    }
 
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => true,
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
-                    KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        self.clear_local_timeline();
-                        false
-                    }
-                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if self.show_debug {
-                            self.show_debug = false;
-                            self.debug_scroll = 0;
-                        } else {
-                            self.show_debug = true;
-                            self.debug_scroll = 0;
-                        }
-                        false
-                    }
-                    KeyCode::Char('f') | KeyCode::Char('F') => {
-                        if !key.modifiers.contains(KeyModifiers::CONTROL) {
-                            self.store_detail_state(detail_ctx.visible_len());
-                            self.cycle_color_filter();
-                        }
-                        false
-                    }
-                    KeyCode::Char('?') => {
-                        self.show_help = true;
-                        false
-                    }
-                    KeyCode::Tab => {
-                        self.focus = match self.focus {
-                            Focus::Timeline => Focus::Detail,
-                            Focus::Detail => Focus::Timeline,
-                        };
-                        if let Some(state) = self.current_detail_state() {
-                            self.detail_scroll =
-                                state.scroll.min(detail_ctx.visible_len().saturating_sub(1));
-                        } else {
-                            self.detail_scroll = 0;
-                        }
-                        false
-                    }
-                    KeyCode::BackTab => {
-                        self.focus = Focus::Timeline;
-                        false
-                    }
-                    KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        self.layout = self.layout.next();
-                        false
-                    }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        if self.focus == Focus::Timeline {
-                            self.store_detail_state(detail_ctx.visible_len());
-                            if self.move_selection(1, timeline_len).is_some() {
-                                if let Some(state) = self.current_detail_state() {
-                                    self.detail_scroll = state.scroll;
-                                } else {
-                                    self.detail_scroll = 0;
-                                }
-                            }
-                        } else {
-                            self.advance_detail_cursor(1, detail_ctx);
-                        }
-                        false
-                    }
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        if self.focus == Focus::Timeline {
-                            self.store_detail_state(detail_ctx.visible_len());
-                            if self.move_selection(-1, timeline_len).is_some() {
-                                if let Some(state) = self.current_detail_state() {
-                                    self.detail_scroll = state.scroll;
-                                } else {
-                                    self.detail_scroll = 0;
-                                }
-                            }
-                        } else {
-                            self.advance_detail_cursor(-1, detail_ctx);
-                        }
-                        false
-                    }
-                    KeyCode::PageDown => {
-                        if self.focus == Focus::Timeline {
-                            self.store_detail_state(detail_ctx.visible_len());
-                            if self.move_selection(10, timeline_len).is_some() {
-                                if let Some(state) = self.current_detail_state() {
-                                    self.detail_scroll = state.scroll;
-                                } else {
-                                    self.detail_scroll = 0;
-                                }
-                            }
-                        } else {
-                            self.advance_detail_cursor(10, detail_ctx);
-                        }
-                        false
-                    }
-                    KeyCode::PageUp => {
-                        if self.focus == Focus::Timeline {
-                            self.store_detail_state(detail_ctx.visible_len());
-                            if self.move_selection(-10, timeline_len).is_some() {
-                                if let Some(state) = self.current_detail_state() {
-                                    self.detail_scroll = state.scroll;
-                                } else {
-                                    self.detail_scroll = 0;
-                                }
-                            }
+    /// Deletes every event the active filters match, on the daemon too when
+    /// attached, and keeps the filters.
+    async fn clear_matching(&mut self) {
+        let now = SystemTime::now();
+        let matching: Vec<Uuid> = self
+            .state
+            .timeline_snapshot()
+            .await
+            .iter()
+            .filter(|event| self.matches_filters(event, now))
+            .map(|event| event.id)
+            .collect();
+        for id in &matching {
+            if let Some(daemon) = &self.daemon {
+                daemon.send(daemon::ClientCommand::Remove { id: *id });
+            }
+            if self.state.remove_event(*id).await {
+                self.detail_states.remove(id);
+            }
+        }
+        self.selected = Some(0);
+        self.detail_scroll = 0;
+        self.set_status(format!(
+            "Cleared {} matching event{}",
+            matching.len(),
+            plural(matching.len())
+        ));
+    }
+
+    fn stats_view(&self, duplicates: Option<u64>) -> StatsView {
+        let rows = self.delays.summaries().map_or_else(Vec::new, |summaries| {
+            ["queueing", "render", "total"]
+                .into_iter()
+                .zip(summaries)
+                .map(|(name, summary)| DelayRow {
+                    name,
+                    p50: format_delay(summary.p50, self.settings.locale),
+                    p95: format_delay(summary.p95, self.settings.locale),
+                    max: format_delay(summary.max, self.settings.locale),
+                })
+                .collect()
+        });
+        StatsView {
+            samples: self.delays.len(),
+            rows,
+            duplicates,
+        }
+    }
+
+    fn showing_history(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|history| history.results.is_some())
+    }
+
+    /// Runs the query typed into the history prompt.
+    async fn run_history_search(&mut self) {
+        let (Some(store), Some(history)) = (&self.store, self.history.as_mut()) else {
+            return;
+        };
+        let query = match StoreQuery::parse(&history.input, SystemTime::now()) {
+            Ok(query) => StoreQuery {
+                limit: Some(TIMELINE_VIEW_LIMIT + 1),
+                ..query
+            },
+            Err(err) => {
+                history.error = Some(err);
+                return;
+            }
+        };
+
+        match store.search(&query).await {
+            Ok(found) => {
+                history.truncated = found.len() > TIMELINE_VIEW_LIMIT;
+                let mut events: Vec<TimelineEvent> = found
+                    .into_iter()
+                    .take(TIMELINE_VIEW_LIMIT)
+                    .map(|stored| stored.event)
+                    .collect();
+                // Oldest first, like the live timeline.
+                events.reverse();
+                history.results = Some((history.input.trim().to_string(), events));
+                history.editing = false;
+                history.error = None;
+                self.selected = Some(0);
+                self.detail_scroll = 0;
+                self.quick_actions = None;
+            }
+            Err(err) => history.error = Some(err.to_string()),
+        }
+    }
+
+    /// Selects the event closing the previous (older) or next (newer) HTTP
+    /// request; the newest events, whose request is still running, count as
+    /// the newest request.
+    fn jump_to_request(&mut self, previous: bool, detail_ctx: &DetailContext) {
+        if self.request_heads.is_empty() {
+            self.set_status(
+                "No request boundaries; Laravel reports handled requests after ray()->showRequests()",
+            );
+            return;
+        }
+        let selected = self.selected.unwrap_or(0);
+        let target = if previous {
+            self.request_heads
+                .iter()
+                .copied()
+                .find(|&head| head > selected)
+        } else {
+            let current = self
+                .request_heads
+                .iter()
+                .copied()
+                .rfind(|&head| head <= selected);
+            match current {
+                Some(current) => self
+                    .request_heads
+                    .iter()
+                    .copied()
+                    .rfind(|&head| head < current)
+                    .or((current > 0).then_some(0)),
+                None => None,
+            }
+        };
+        let Some(target) = target else {
+            self.set_status(if previous {
+                "This is the oldest request"
+            } else {
+                "This is the newest request"
+            });
+            return;
+        };
+
+        self.store_detail_state(detail_ctx.visible_len());
+        self.focus = Focus::Timeline;
+        self.selected = Some(target);
+        self.detail_scroll = self.current_detail_state().map_or(0, |state| state.scroll);
+    }
+
+    fn track_unread(&mut self, message: &BusMessage) {
+        match message {
+            BusMessage::Recorded { event } => {
+                self.unread.push(event.id);
+                self.last_arrival = Some(Instant::now());
+            }
+            BusMessage::Removed { id } => self.unread.retain(|unread| unread != id),
+            BusMessage::Cleared => self.unread.clear(),
+            BusMessage::Updated { .. } | BusMessage::LockChanged { .. } => {}
+        }
+    }
+
+    /// Selects the oldest unread event shown in the timeline and marks it
+    /// read, so repeated presses walk through the new events in order.
+    fn jump_to_unread(&mut self, detail_ctx: &DetailContext) {
+        let target = self.unread.iter().enumerate().find_map(|(position, id)| {
+            let index = self
+                .visible_events
+                .iter()
+                .position(|visible| visible == id)?;
+            Some((position, index))
+        });
+        let Some((position, index)) = target else {
+            self.set_status(if self.unread.is_empty() {
+                "No unread events"
+            } else {
+                "The unread events are hidden by the filter"
+            });
+            return;
+        };
+        self.unread.remove(position);
+
+        self.store_detail_state(detail_ctx.visible_len());
+        self.focus = Focus::Timeline;
+        self.selected = Some(index);
+        self.detail_scroll = self.current_detail_state().map_or(0, |state| state.scroll);
+    }
+
+    /// Marks the selected event as the start of a range or, once a start is
+    /// marked, as its end. A complete range is replaced by a new start.
+    fn mark_range(&mut self) {
+        let Some(event_id) = self.current_event_id() else {
+            return;
+        };
+        match self.range {
+            Some(MarkedRange { start, end: None }) => {
+                self.range = Some(MarkedRange {
+                    start,
+                    end: Some(event_id),
+                });
+                self.set_status("Range marked; press Enter on an event for range actions");
+            }
+            _ => {
+                self.range = Some(MarkedRange {
+                    start: event_id,
+                    end: None,
+                });
+                self.set_status(format!(
+                    "Range start marked; select its other end and press {}",
+                    self.settings.keymap.hint(Action::MarkRange)
+                ));
+            }
+        }
+    }
+
+    /// Positions of the oldest and newest event of the marked range in
+    /// `events` (oldest first), once both ends are marked.
+    fn range_bounds(&self, events: &[TimelineEvent]) -> Option<(usize, usize)> {
+        let range = self.range?;
+        let position = |id: Uuid| events.iter().position(|event| event.id == id);
+        let start = position(range.start)?;
+        let end = position(range.end?)?;
+        Some((start.min(end), start.max(end)))
+    }
+
+    /// Range marks of the events in `events`; forgets a range whose ends are
+    /// no longer shown.
+    fn range_marks(&mut self, events: &[TimelineEvent]) -> HashMap<Uuid, RangeMark> {
+        let mut marks = HashMap::new();
+        let Some(range) = self.range else {
+            return marks;
+        };
+        if range.end.is_none() {
+            if events.iter().any(|event| event.id == range.start) {
+                marks.insert(range.start, RangeMark::Pending);
+            } else {
+                self.range = None;
+            }
+            return marks;
+        }
+
+        let Some((first, last)) = self.range_bounds(events) else {
+            self.range = None;
+            return marks;
+        };
+        for (position, event) in events.iter().enumerate().take(last + 1).skip(first) {
+            let mark = if first == last {
+                RangeMark::Only
+            } else if position == first {
+                RangeMark::First
+            } else if position == last {
+                RangeMark::Last
+            } else {
+                RangeMark::Inside
+            };
+            marks.insert(event.id, mark);
+        }
+        marks
+    }
+
+    /// Writes the HTTP client calls among the shown events, filters applied,
+    /// to a HAR file in the working directory.
+    async fn export_har(&mut self) {
+        let mut events = self.displayed_events().await;
+        if let Some(color) = &self.color_filter {
+            events.retain(|event| event.color.as_deref() == Some(color.as_str()));
+        }
+        if let Some(filter) = &self.event_filter {
+            events.retain(|event| filter.matches(event));
+        }
+
+        let entries = har::entries(&events);
+        if entries.is_empty() {
+            self.set_status(
+                "No HTTP client calls to export; Laravel sends them after ray()->showHttpClientRequests()",
+            );
+            return;
+        }
+
+        let count = entries.len();
+        let path = PathBuf::from(timestamped_file_name("raygun", "har", SystemTime::now()));
+        let written = serde_json::to_string_pretty(&har::document(entries))
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(&path, json));
+        match written {
+            Ok(()) => self.set_status(format!(
+                "Exported {} HTTP call{} to {}",
+                count,
+                if count == 1 { "" } else { "s" },
+                path.display()
+            )),
+            Err(err) => self.set_status(format!("Failed to export HAR: {}", err)),
+        }
+    }
+
+    /// Writes the events the timeline shows, oldest first, to a Markdown
+    /// report in the working directory.
+    async fn export_markdown(&mut self) {
+        let now = SystemTime::now();
+        let mut events = self.displayed_events().await;
+        events.retain(|event| self.matches_filters(event, now));
+        if events.is_empty() {
+            self.set_status("No events to report; the filters hide everything");
+            return;
+        }
+
+        let mut filter = self.view_filter();
+        if self.hide_noise {
+            filter = match filter.as_str() {
+                "" => "noise hidden".to_string(),
+                _ => format!("{} · noise hidden", filter),
+            };
+        }
+        let path = PathBuf::from(timestamped_file_name("raygun", "md", now));
+        match fs::write(
+            &path,
+            markdown::report(&events, &filter, now, self.settings.locale),
+        ) {
+            Ok(()) => self.set_status(format!(
+                "Exported {} event{} to {}",
+                events.len(),
+                if events.len() == 1 { "" } else { "s" },
+                path.display()
+            )),
+            Err(err) => self.set_status(format!("Failed to export the report: {}", err)),
+        }
+    }
+
+    /// Records a scratch pad paste as new timeline events and shows the
+    /// newest one in the detail pane.
+    async fn record_scratch(&mut self, text: &str) {
+        let requests = scratch::scratch_requests(text);
+        if requests.is_empty() {
+            self.set_status("Nothing to inspect in the pasted text");
+            return;
+        }
+
+        if self.showing_history() {
+            self.close_history();
+        }
+        let mut recorded = 0;
+        for request in requests {
+            if self.state.record_request(request).await.is_some() {
+                recorded += 1;
+            }
+        }
+        if recorded == 0 {
+            self.set_status("The pasted requests have nothing to display");
+            return;
+        }
+        self.selected = Some(0);
+        self.focus = Focus::Detail;
+        self.detail_scroll = 0;
+        self.clear_detail_filter();
+        self.set_status(format!(
+            "Scratch pad: added {} event{}",
+            recorded,
+            if recorded == 1 { "" } else { "s" }
+        ));
+    }
+
+    /// Loads the session saved by [`SnapshotGuard`] back into the timeline.
+    async fn restore_recovery(&mut self) {
+        let Some(path) = self.recovery_path.clone() else {
+            return;
+        };
+        self.recovery_offer = None;
+        match import::import_file(&self.state, &path).await {
+            Ok(imported) => {
+                self.set_status(format!(
+                    "Restored {} event{} from the last session",
+                    imported.requests,
+                    plural(imported.requests)
+                ));
+                self.selected = Some(0);
+                self.detail_scroll = 0;
+                recovery::discard(&path);
+            }
+            Err(err) => self.set_status(format!("Failed to restore the last session: {}", err)),
+        }
+    }
+
+    fn discard_recovery(&mut self) {
+        self.recovery_offer = None;
+        if let Some(path) = self.recovery_path.clone() {
+            recovery::discard(&path);
+        }
+        self.set_status("Discarded the last session");
+    }
+
+    /// Closes the history search and returns to the live timeline.
+    /// Copies the key path of the detail line under the cursor, e.g.
+    /// `user.roles[0]`.
+    fn copy_detail_path(&mut self, ctx: &DetailContext) {
+        let path = match (ctx.detail, self.detail_cursor_line(ctx)) {
+            (Some(detail), Some(line)) => {
+                detail::dotted_path(&detail::breadcrumb(&detail.lines, line))
+            }
+            _ => String::new(),
+        };
+        if path.is_empty() {
+            self.set_status("No key path under the detail cursor");
+            return;
+        }
+        match clipboard::copy(&path) {
+            Ok(()) => self.set_status(format!("Copied {}", path)),
+            Err(err) => self.set_status(format!("Failed to copy: {}", err)),
+        }
+    }
+
+    /// Index of the detail line under the cursor.
+    fn detail_cursor_line(&self, ctx: &DetailContext) -> Option<usize> {
+        let cursor = self.current_detail_state().map_or(0, |state| {
+            state.cursor.min(ctx.visible_len().saturating_sub(1))
+        });
+        ctx.visible_indices.get(cursor).copied()
+    }
+
+    /// Opens the JSON query prompt with the last query, for the selected event.
+    fn open_json_query(&mut self) {
+        if self.current_event_id().is_none() {
+            self.set_status("Select an event to query its JSON");
+            return;
+        }
+        self.open_prompt(PromptPurpose::JsonQuery, self.last_json_query.clone());
+    }
+
+    /// Copies the JSON query's results, one per line; a single string result
+    /// is copied without its quotes.
+    fn copy_json_query_results(&mut self) {
+        let Some(state) = self.json_query.as_ref() else {
+            return;
+        };
+        let text = match state.results.as_slice() {
+            [] => {
+                self.set_status("Nothing to copy: the query matched nothing");
+                return;
+            }
+            [single] => serde_json::from_str::<String>(single).unwrap_or_else(|_| single.clone()),
+            results => results.join("\n"),
+        };
+        let count = state.results.len();
+        match clipboard::copy(&text) {
+            Ok(()) => self.set_status(match count {
+                1 => "Copied the query result".to_string(),
+                count => format!("Copied {} query results", count),
+            }),
+            Err(err) => self.set_status(format!("Failed to copy: {}", err)),
+        }
+    }
+
+    /// Copies the detail node at `line` and everything under it in `format`.
+    fn copy_detail_node(&mut self, ctx: &DetailContext, line: usize, format: CopyFormat) {
+        let Some(detail) = ctx.detail.filter(|detail| line < detail.lines.len()) else {
+            return;
+        };
+        let text = copy::format_node(&detail.lines, line, format);
+        match clipboard::copy(&text) {
+            Ok(()) => self.set_status(format!("Copied the value as {}", format.label())),
+            Err(err) => self.set_status(format!("Failed to copy: {}", err)),
+        }
+    }
+
+    /// The detail filter, if it belongs to the selected event.
+    fn active_detail_filter(&self) -> Option<&DetailFilter> {
+        let event_id = self.current_event_id()?;
+        self.detail_filter
+            .as_ref()
+            .filter(|filter| filter.event_id == event_id)
+    }
+
+    /// Opens the selected event's filter box, keeping its query if it has one.
+    fn open_detail_filter(&mut self) {
+        let Some(event_id) = self.current_event_id() else {
+            return;
+        };
+        match self.detail_filter.as_mut() {
+            Some(filter) if filter.event_id == event_id => filter.editing = true,
+            _ => {
+                self.detail_filter = Some(DetailFilter {
+                    event_id,
+                    query: String::new(),
+                    editing: true,
+                });
+            }
+        }
+    }
+
+    fn prompt_view(&self) -> Option<PromptView> {
+        let prompt = self.prompt.as_ref()?;
+        let (title, hint) = match prompt.purpose {
+            PromptPurpose::Filter => (
+                "Filter timeline (Enter apply · empty clears · Esc cancel)".to_string(),
+                "kind: color: label: screen: host: project: client: origin: · age<5m · AND OR NOT ( ) · words"
+                    .to_string(),
+            ),
+            PromptPurpose::SaveView(slot) => (
+                format!("Save view as {} (Enter save · Esc cancel)", slot),
+                match self.view_filter() {
+                    source if source.is_empty() => {
+                        "no filters: the view shows everything".to_string()
+                    }
+                    source => format!("filter: {}", source),
+                },
+            ),
+            PromptPurpose::Rebind => (
+                "Move the HTTP server (Enter rebind · Esc cancel)".to_string(),
+                "addr:port, comma-separated, or just a port to keep the hosts · the timeline is kept"
+                    .to_string(),
+            ),
+            PromptPurpose::LogFilter => (
+                "Log filter (Enter apply · Esc cancel)".to_string(),
+                "RUST_LOG syntax, e.g. info · raygun=debug · raygun::server=trace,warn".to_string(),
+            ),
+            PromptPurpose::Label(_) => (
+                "Label the event (Enter save · empty removes the label · Esc cancel)".to_string(),
+                "shown in the timeline and matched by label: filters".to_string(),
+            ),
+            PromptPurpose::MoveToScreen(_) => (
+                "Move the event to screen (Enter move · empty takes it off its screen · Esc cancel)"
+                    .to_string(),
+                "an existing screen's name, or a new one".to_string(),
+            ),
+            PromptPurpose::RenameScreen(_) => (
+                "Rename the screen (Enter rename · Esc cancel)".to_string(),
+                "renames it on every event in it, and for new events while it is current"
+                    .to_string(),
+            ),
+            PromptPurpose::JsonQuery => (
+                "Query the event's JSON (Enter run · Esc cancel)".to_string(),
+                "$.payloads[0].content · .meta · $..file · [*] every item · [-1] the last"
+                    .to_string(),
+            ),
+        };
+        Some(PromptView {
+            title,
+            hint,
+            input: prompt.input.clone(),
+            error: prompt.error.clone(),
+        })
+    }
+
+    fn open_prompt(&mut self, purpose: PromptPurpose, input: String) {
+        self.prompt = Some(TextPrompt {
+            purpose,
+            input,
+            error: None,
+        });
+    }
+
+    /// Runs the open prompt's input; the prompt stays open with the error if
+    /// it is rejected.
+    fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.as_ref() else {
+            return;
+        };
+        let result = match prompt.purpose {
+            PromptPurpose::Filter => match Filter::parse(&prompt.input) {
+                Ok(filter) => {
+                    let filter = filter.with_host_aliases(Arc::clone(&self.host_aliases));
+                    self.event_filter = (!filter.is_empty()).then_some(filter);
+                    self.selected = Some(0);
+                    self.detail_scroll = 0;
+                    Ok(())
+                }
+                Err(err) => Err(err.to_string()),
+            },
+            PromptPurpose::SaveView(slot) => {
+                let name = prompt.input.trim().to_string();
+                self.save_view(slot, name)
+            }
+            PromptPurpose::Rebind => parse_bind_addrs(&prompt.input, &self.server_addrs)
+                .map(|addrs| self.pending_rebind = Some(addrs)),
+            PromptPurpose::LogFilter => {
+                let directives = prompt.input.trim().to_string();
+                logs::set_filter(&directives).map(|()| {
+                    self.logs_scroll = 0;
+                    self.set_status(format!("Log filter set to {}", directives));
+                })
+            }
+            PromptPurpose::Label(event_id) => {
+                let label = prompt.input.trim();
+                self.pending_label =
+                    Some((event_id, (!label.is_empty()).then(|| label.to_string())));
+                Ok(())
+            }
+            PromptPurpose::MoveToScreen(event_id) => {
+                let screen = prompt.input.trim();
+                self.pending_screen_edit = Some(ScreenEdit::Move(
+                    event_id,
+                    (!screen.is_empty()).then(|| screen.to_string()),
+                ));
+                Ok(())
+            }
+            PromptPurpose::RenameScreen(event_id) => match prompt.input.trim() {
+                "" => Err("A screen needs a name".to_string()),
+                name => {
+                    self.pending_screen_edit = Some(ScreenEdit::Rename(event_id, name.to_string()));
+                    Ok(())
+                }
+            },
+            PromptPurpose::JsonQuery => match Query::parse(&prompt.input) {
+                Ok(query) => {
+                    self.last_json_query = query.source().to_string();
+                    self.json_query = Some(JsonQueryState {
+                        query,
+                        results: Vec::new(),
+                        scroll: 0,
+                    });
+                    Ok(())
+                }
+                Err(err) => Err(err.to_string()),
+            },
+        };
+        match result {
+            Ok(()) => self.prompt = None,
+            Err(err) => {
+                if let Some(prompt) = self.prompt.as_mut() {
+                    prompt.error = Some(err);
+                }
+            }
+        }
+    }
+
+    /// The active color and expression filters as one expression.
+    fn view_filter(&self) -> String {
+        let color = self
+            .color_filter
+            .as_deref()
+            .and_then(|color| Filter::field("color", color).ok());
+        match (color, &self.event_filter) {
+            (Some(color), Some(filter)) => format!("{} ({})", color.source(), filter.source()),
+            (Some(color), None) => color.source().to_string(),
+            (None, Some(filter)) => filter.source().to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn open_save_view(&mut self) {
+        if self.views.len() >= VIEW_SLOTS {
+            self.set_status(format!(
+                "All {} view keys are taken; remove a [[views]] entry from the config to save another",
+                VIEW_SLOTS
+            ));
+            return;
+        }
+        self.open_prompt(PromptPurpose::SaveView(self.views.len() + 1), String::new());
+    }
+
+    /// Appends the current filters to the config file as a view and binds
+    /// them to the number key `slot`.
+    fn save_view(&mut self, slot: usize, name: String) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("A view needs a name".to_string());
+        }
+        let Some(path) = self.config_path.clone() else {
+            return Err("No config file location; pass --config <path>".to_string());
+        };
+        let view = ViewConfig {
+            name,
+            filter: self.view_filter(),
+        };
+        append_table(&path, "views", &view)
+            .map_err(|err| format!("Failed to save the view: {}", err))?;
+        self.set_status(format!(
+            "Saved view {}: {} to {}",
+            slot,
+            view.name,
+            path.display()
+        ));
+        self.views.push(view);
+        Ok(())
+    }
+
+    /// Payload types Raygun doesn't know among `events` (oldest first), most
+    /// frequent first, each with its newest example.
+    fn unknown_kind_entries(&self, events: &[TimelineEvent]) -> Vec<UnknownKindEntry> {
+        let mut entries: Vec<UnknownKindEntry> = Vec::new();
+        for event in events.iter().rev() {
+            for payload in &event.request.payloads {
+                let PayloadKind::Unknown(kind) = &payload.kind else {
+                    continue;
+                };
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.kind == *kind) {
+                    entry.count += 1;
+                    continue;
+                }
+                entries.push(UnknownKindEntry {
+                    kind: kind.clone(),
+                    count: 1,
+                    client: event
+                        .request
+                        .meta
+                        .get("ray_package_version")
+                        .and_then(Value::as_str)
+                        .map(|version| format!("ray {}", version)),
+                    renderer: self
+                        .unknown_renderers
+                        .get(kind)
+                        .copied()
+                        .unwrap_or_default()
+                        .name(),
+                    example: serde_json::to_string_pretty(payload.content())
+                        .unwrap_or_default()
+                        .lines()
+                        .map(str::to_string)
+                        .collect(),
+                });
+            }
+        }
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.kind.cmp(&b.kind)));
+        entries
+    }
+
+    /// Draws the current event's payload with the next renderer in the
+    /// automatic, raw JSON, plain text and hex cycle.
+    fn cycle_render_override(&mut self) {
+        let Some(event_id) = self.current_event_id() else {
+            return;
+        };
+        let mode = self
+            .render_overrides
+            .get(&event_id)
+            .copied()
+            .unwrap_or_default()
+            .next();
+        if mode == RenderOverride::Auto {
+            self.render_overrides.remove(&event_id);
+        } else {
+            self.render_overrides.insert(event_id, mode);
+        }
+        // Collapsed lines and the cursor point into the old lines.
+        self.detail_states.remove(&event_id);
+        self.detail_scroll = 0;
+        self.set_status(format!(
+            "Drawing the event with the {} renderer",
+            mode.name()
+        ));
+    }
+
+    /// Moves the unknown type on row `index` of its overlay to the next
+    /// renderer and records the choice in the config file.
+    fn cycle_unknown_renderer(&mut self, index: usize) {
+        let Some(kind) = self.unknown_kind_names.get(index).cloned() else {
+            return;
+        };
+        let renderer = self
+            .unknown_renderers
+            .get(&kind)
+            .copied()
+            .unwrap_or_default()
+            .next();
+        self.unknown_renderers.insert(kind.clone(), renderer);
+
+        let Some(path) = self.config_path.clone() else {
+            self.set_status(format!(
+                "Drawing `{}` payloads as {} until Raygun exits; pass --config <path> to keep it",
+                kind,
+                renderer.name()
+            ));
+            return;
+        };
+        let entry = UnknownKindConfig {
+            kind: kind.clone(),
+            renderer,
+        };
+        match append_table(&path, "unknown_kinds", &entry) {
+            Ok(()) => self.set_status(format!(
+                "Drawing `{}` payloads as {} (saved to {})",
+                kind,
+                renderer.name(),
+                path.display()
+            )),
+            Err(err) => self.set_status(format!(
+                "Drawing `{}` payloads as {}, but failed to save it: {}",
+                kind,
+                renderer.name(),
+                err
+            )),
+        }
+    }
+
+    /// Applies the view bound to the number key `slot`.
+    fn apply_view(&mut self, slot: usize) {
+        let Some(view) = slot.checked_sub(1).and_then(|index| self.views.get(index)) else {
+            self.set_status(format!(
+                "No view on {}; press {} to save the current filters as one",
+                slot,
+                self.settings.keymap.hint(Action::SaveView)
+            ));
+            return;
+        };
+        let name = view.name.clone();
+        // Config views were validated at startup, so this only fails if the
+        // language changed under a file.
+        match Filter::parse(&view.filter) {
+            Ok(filter) => {
+                let filter = filter.with_host_aliases(Arc::clone(&self.host_aliases));
+                self.event_filter = (!filter.is_empty()).then_some(filter);
+                self.color_filter = None;
+                self.selected = Some(0);
+                self.detail_scroll = 0;
+                self.set_status(format!("View {}: {}", slot, name));
+            }
+            Err(err) => self.set_status(format!("View {} has an invalid filter: {}", slot, err)),
+        }
+    }
+
+    /// Re-reads the config file (SIGHUP). An invalid file is reported and
+    /// the current settings are kept.
+    fn reload_config(&mut self) {
+        let file_config = match load_file_config(&self.config) {
+            Ok(file_config) => file_config,
+            Err(err) => {
+                warn!(%err, "config not reloaded");
+                self.set_status(format!(
+                    "Config not reloaded: {}",
+                    flatten(&err.to_string())
+                ));
+                return;
+            }
+        };
+
+        let mut settings = UiSettings::new(&file_config);
+        settings.ascii = self.settings.ascii;
+        self.settings = Arc::new(settings);
+        self.settings_generation += 1;
+        if let Some(integrations) = self.integrations.take() {
+            integrations.stop();
+            self.integrations = Some(integrations::spawn(&self.state, &file_config));
+        }
+        self.unknown_renderers = file_config.unknown_renderers();
+        let state = Arc::clone(&self.state);
+        let rules = Rules::new(&file_config.rules);
+        let routes = Routes::new(&file_config.routes, &file_config.hosts);
+        tokio::spawn(async move {
+            state.set_rules(rules).await;
+            state.set_routes(routes).await;
+        });
+        self.state.set_pin_errors(file_config.retention.pin_errors);
+        self.terminal = file_config.terminal;
+        self.alerts = Alerts::new(&file_config.sounds);
+        self.path_mappings = file_config.path_mappings;
+        self.host_aliases = Arc::new(HostAliases::new(&file_config.hosts));
+        self.noise = noise_filter(file_config.noise.as_deref(), Arc::clone(&self.host_aliases));
+        self.event_filter = self
+            .event_filter
+            .take()
+            .map(|filter| filter.with_host_aliases(Arc::clone(&self.host_aliases)));
+        self.collapse_rules = file_config.collapse;
+        self.editor = file_config.editor;
+        self.permalink = file_config.permalink;
+        self.resend_url = file_config.resend_url;
+        self.views = file_config.views;
+        info!("reloaded the config");
+        self.set_status("Reloaded the config");
+    }
+
+    fn open_rebind(&mut self) {
+        let Some(server) = &self.server else {
+            self.set_status(if self.daemon.is_some() {
+                "The daemon owns the HTTP server; restart it with --bind to move it"
+            } else {
+                "The HTTP server is not running; restart Raygun with --bind"
+            });
+            return;
+        };
+        let input = server
+            .bind_addrs()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.open_prompt(PromptPurpose::Rebind, input);
+    }
+
+    /// Moves the HTTP server to `addrs`; the timeline is untouched.
+    async fn rebind(&mut self, addrs: Vec<SocketAddr>) {
+        let Some(server) = self.server.take() else {
+            return;
+        };
+        let server = match server.rebind(addrs).await {
+            Ok(server) => {
+                self.set_status(format!(
+                    "Listening on {}",
+                    server
+                        .addrs()
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+                server
+            }
+            Err(failure) => {
+                let (err, restored) = *failure;
+                match restored {
+                    Some(server) => {
+                        self.set_status(format!("Rebind failed, kept the old addresses: {}", err));
+                        server
+                    }
+                    None => {
+                        warn!(%err, "HTTP server could not be restored after a failed rebind");
+                        self.set_status(format!(
+                            "Rebind failed and the old addresses could not be restored; nothing is listening: {}",
+                            err
+                        ));
+                        self.server_addrs.clear();
+                        return;
+                    }
+                }
+            }
+        };
+        self.server_addrs = server.addrs().to_vec();
+        if let Some(onboarding) = self.onboarding.as_mut() {
+            onboarding.snippets = OnboardingSnippets::new(&self.server_addrs);
+        }
+        self.server = Some(server);
+    }
+
+    fn clear_detail_filter(&mut self) {
+        if self.detail_filter.take().is_some() {
+            self.reset_filtered_detail();
+        }
+    }
+
+    /// Filtering renumbers the detail lines, so the filtered event's cursor
+    /// and folds start over.
+    fn reset_filtered_detail(&mut self) {
+        let event_id = self
+            .detail_filter
+            .as_ref()
+            .map(|filter| filter.event_id)
+            .or_else(|| self.current_event_id());
+        if let Some(event_id) = event_id {
+            self.detail_states.remove(&event_id);
+        }
+        self.detail_scroll = 0;
+    }
+
+    fn close_history(&mut self) {
+        if let Some(history) = self.history.take()
+            && history.results.is_some()
+        {
+            self.selected = history.live_selected;
+            self.detail_scroll = 0;
+            self.quick_actions = None;
+        }
+    }
+
+    /// Applies filters another attached client shared (`--share-filters`).
+    fn receive_shared_filters(&mut self) {
+        let Some(daemon) = self.daemon.as_ref().filter(|_| self.share_filters) else {
+            return;
+        };
+        let Some(filters) = daemon.take_shared_filters() else {
+            return;
+        };
+
+        self.color_filter = filters.color.clone();
+        self.event_filter = filters
+            .filter
+            .as_deref()
+            .and_then(|source| Filter::parse(source).ok())
+            .map(|filter| filter.with_host_aliases(Arc::clone(&self.host_aliases)));
+        self.selected = Some(0);
+        self.shared_filters = filters;
+    }
+
+    /// Reports the selected event and, when sharing, changed filters to the daemon.
+    fn publish_session(&mut self) {
+        let Some(daemon) = &self.daemon else {
+            return;
+        };
+
+        // History results are not on the shared timeline.
+        let cursor = if self.showing_history() {
+            None
+        } else {
+            self.current_event_id()
+        };
+        if self.shared_cursor != Some(cursor) {
+            daemon.send(daemon::ClientCommand::Cursor { event: cursor });
+            self.shared_cursor = Some(cursor);
+        }
+
+        if self.share_filters {
+            let filters = daemon::SharedFilters {
+                color: self.color_filter.clone(),
+                filter: self
+                    .event_filter
+                    .as_ref()
+                    .map(|filter| filter.source().to_string()),
+            };
+            if filters != self.shared_filters {
+                daemon.send(daemon::ClientCommand::Filters {
+                    filters: filters.clone(),
+                });
+                self.shared_filters = filters;
+            }
+        }
+    }
+
+    /// Runs a remappable action; returns `true` when the app should exit.
+    fn perform_action(&mut self, action: Action, detail_ctx: &DetailContext) -> bool {
+        match action {
+            Action::Quit => return true,
+            Action::ClearTimeline if self.showing_history() => {
+                self.set_status(
+                    "History results are read-only; press Esc to return to the live timeline",
+                );
+            }
+            Action::ClearTimeline => self.clear_local_timeline(),
+            Action::ClearMatching if self.showing_history() => {
+                self.set_status(
+                    "History results are read-only; press Esc to return to the live timeline",
+                );
+            }
+            Action::ClearMatching if self.color_filter.is_none() && self.event_filter.is_none() => {
+                self.set_status(format!(
+                    "Set a color or timeline filter first; {} clears everything",
+                    self.settings.keymap.hint(Action::ClearTimeline)
+                ));
+            }
+            Action::ClearMatching => self.pending_clear_matching = true,
+            Action::HideNoise => {
+                self.store_detail_state(detail_ctx.visible_len());
+                self.hide_noise = !self.hide_noise;
+                self.selected = Some(0);
+                self.detail_scroll = 0;
+                self.set_status(if self.hide_noise {
+                    format!("Hiding noise: {}", self.noise.source())
+                } else {
+                    "Showing noise again".to_string()
+                });
+            }
+            Action::HistorySearch => match self.history.as_mut() {
+                Some(history) => history.editing = true,
+                None if self.store.is_some() => {
+                    self.history = Some(HistorySearch {
+                        editing: true,
+                        live_selected: self.selected,
+                        ..HistorySearch::default()
+                    });
+                }
+                None => self.set_status(
+                    "History search needs an event store; start Raygun with --store sqlite:<path>",
+                ),
+            },
+            Action::RawPayload => {
+                self.show_debug = !self.show_debug;
+                self.debug_scroll = 0;
+            }
+            Action::CycleColorFilter => {
+                self.store_detail_state(detail_ctx.visible_len());
+                self.cycle_color_filter();
+            }
+            Action::DetailFilter => self.open_detail_filter(),
+            Action::Help => self.show_help = true,
+            Action::ToggleFocus => {
+                self.focus = match self.focus {
+                    Focus::Timeline => Focus::Detail,
+                    Focus::Detail => Focus::Timeline,
+                };
+                if let Some(state) = self.current_detail_state() {
+                    self.detail_scroll =
+                        state.scroll.min(detail_ctx.visible_len().saturating_sub(1));
+                } else {
+                    self.detail_scroll = 0;
+                }
+            }
+            Action::CycleLayout => self.layout = self.layout.next(),
+            Action::Schema => {
+                self.show_schema = !self.show_schema;
+                self.schema_scroll = 0;
+            }
+            Action::JsonQuery => self.open_json_query(),
+            Action::Diagnostics => {
+                self.show_diagnostics = true;
+                self.diagnostics_scroll = 0;
+            }
+            Action::Logs => {
+                self.show_logs = true;
+                self.logs_scroll = 0;
+            }
+            Action::UnknownKinds => self.unknown_kinds_cursor = Some(0),
+            Action::Trash => self.trash_cursor = Some(0),
+            Action::Origins => self.origins_cursor = Some(0),
+            Action::Exceptions => self.exceptions_cursor = Some(0),
+            Action::Stats => self.show_stats = true,
+            Action::Profile => self.show_profile = !self.show_profile,
+            Action::EditLabel
+            | Action::CycleColor
+            | Action::MoveToScreen
+            | Action::RenameScreen
+            | Action::CloseScreen
+                if self.showing_history() =>
+            {
+                self.set_status(
+                    "History results are read-only; press Esc to return to the live timeline",
+                );
+            }
+            Action::EditLabel => {
+                if let Some(event_id) = self.current_event_id() {
+                    self.pending_quick_action = Some((QuickAction::EditLabel, event_id));
+                }
+            }
+            Action::MoveToScreen | Action::RenameScreen | Action::CloseScreen => {
+                let quick_action = match action {
+                    Action::MoveToScreen => QuickAction::MoveToScreen,
+                    Action::RenameScreen => QuickAction::RenameScreen,
+                    _ => QuickAction::CloseScreen,
+                };
+                if let Some(event_id) = self.current_event_id() {
+                    self.pending_quick_action = Some((quick_action, event_id));
+                }
+            }
+            Action::CycleColor => self.pending_color_cycle = self.current_event_id(),
+            Action::CycleRenderer => self.cycle_render_override(),
+            Action::Problems => {
+                self.problems_expanded = !self.problems_expanded;
+                // Hiding the strip dismisses what it showed.
+                self.problems_seen = self.problems_recorded;
+            }
+            Action::Fullscreen => self.fullscreen = !self.fullscreen,
+            Action::ExportHar => self.pending_har_export = true,
+            Action::ExportMarkdown => self.pending_markdown_export = true,
+            Action::MarkRange => self.mark_range(),
+            Action::PreviousRequest | Action::NextRequest => {
+                self.jump_to_request(action == Action::PreviousRequest, detail_ctx);
+            }
+            Action::NextUnread => self.jump_to_unread(detail_ctx),
+            Action::CopyPath => self.copy_detail_path(detail_ctx),
+            Action::CopyValue => match self.detail_cursor_line(detail_ctx) {
+                Some(line) => self.copy_prompt = Some(line),
+                None => self.set_status("No detail line to copy"),
+            },
+            Action::Scratch => self.scratch_open = true,
+            Action::SaveView => self.open_save_view(),
+            Action::Rebind => self.open_rebind(),
+            Action::FocusFollow => {
+                self.focus_follow = !self.focus_follow;
+                self.focus_followed = None;
+                self.set_status(if self.focus_follow {
+                    "Focus-follow on"
+                } else {
+                    "Focus-follow off"
+                });
+            }
+        }
+        false
+    }
+
+    /// Handles `event` and, when it is a navigation key, the navigation keys
+    /// queued behind it. A held arrow key queues events faster than frames are
+    /// drawn; taking the run in one go stops the cursor when the key is
+    /// released. The timeline moves once, to where the run leads, so only the
+    /// event the context was built for has its detail state saved.
+    fn handle_input(
+        &mut self,
+        event: Event,
+        rx: &mut mpsc::UnboundedReceiver<Event>,
+        queued: &mut Option<Event>,
+        timeline_len: usize,
+        detail_ctx: &DetailContext,
+    ) -> bool {
+        if !event.is_navigation() {
+            return self.handle_event(event, timeline_len, detail_ctx);
+        }
+
+        self.timeline_target = Some(None);
+        let mut exit = self.handle_event(event, timeline_len, detail_ctx);
+        while !exit {
+            match rx.try_recv() {
+                Ok(next) if next.is_navigation() => {
+                    exit = self.handle_event(next, timeline_len, detail_ctx);
+                }
+                Ok(next) => {
+                    *queued = Some(next);
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        if let Some(Some(target)) = self.timeline_target.take() {
+            let delta = target as i32 - self.selected.unwrap_or(0) as i32;
+            if delta != 0 {
+                self.move_timeline(delta, timeline_len, detail_ctx);
+            }
+        }
+        exit
+    }
+
+    fn handle_event(
+        &mut self,
+        event: Event,
+        timeline_len: usize,
+        detail_ctx: &DetailContext,
+    ) -> bool {
+        // Any interaction reads the new events, except stepping through them.
+        let interaction = match &event {
+            Event::Input(key) => {
+                self.settings.keymap.action_for(key, Scope::Global) != Some(Action::NextUnread)
+            }
+            Event::Paste(_) => true,
+            Event::Mouse(mouse) => !matches!(mouse.kind, MouseEventKind::Moved),
+            Event::Tick | Event::Resize(..) => false,
+        };
+        if interaction {
+            self.unread.clear();
+        }
+
+        match event {
+            Event::Input(key) => {
+                let scope = if self.focus == Focus::Detail && detail_ctx.detail.is_some() {
+                    Scope::Detail
+                } else {
+                    Scope::Global
+                };
+                let action = self.settings.keymap.action_for(&key, scope);
+
+                if let Some(history) = self.history.as_mut().filter(|history| history.editing) {
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        KeyCode::Esc if history.results.is_some() => {
+                            history.editing = false;
+                            history.error = None;
+                        }
+                        KeyCode::Esc => self.close_history(),
+                        KeyCode::Enter => self.pending_history_search = true,
+                        KeyCode::Backspace => {
+                            history.input.pop();
+                            history.error = None;
+                        }
+                        KeyCode::Char(ch)
+                            if !key
+                                .modifiers
+                                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                        {
+                            history.input.push(ch);
+                            history.error = None;
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if let Some(prompt) = self.prompt.as_mut() {
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        KeyCode::Esc => self.prompt = None,
+                        KeyCode::Enter => self.submit_prompt(),
+                        KeyCode::Backspace => {
+                            prompt.input.pop();
+                            prompt.error = None;
+                        }
+                        KeyCode::Char(ch)
+                            if !key
+                                .modifiers
+                                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                        {
+                            prompt.input.push(ch);
+                            prompt.error = None;
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if self.scratch_open {
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        KeyCode::Esc => self.scratch_open = false,
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if let Some(line) = self.copy_prompt.take() {
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        KeyCode::Char(ch) => {
+                            if let Some(format) = CopyFormat::ALL
+                                .into_iter()
+                                .find(|format| format.key() == ch)
+                            {
+                                self.copy_detail_node(detail_ctx, line, format);
+                            }
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if let Some(filter) = self.detail_filter.as_mut().filter(|filter| filter.editing) {
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        KeyCode::Esc => self.clear_detail_filter(),
+                        KeyCode::Enter => filter.editing = false,
+                        KeyCode::Backspace => {
+                            filter.query.pop();
+                            self.reset_filtered_detail();
+                        }
+                        KeyCode::Char(ch)
+                            if !key
+                                .modifiers
+                                .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                        {
+                            filter.query.push(ch);
+                            self.reset_filtered_detail();
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if self.recovery_offer.is_some() && key.modifiers.is_empty() {
+                    match key.code {
+                        KeyCode::Char('r') => {
+                            self.pending_restore = true;
+                            return false;
+                        }
+                        KeyCode::Esc => {
+                            self.discard_recovery();
+                            return false;
+                        }
+                        _ => {}
+                    }
+                }
+
+                if self.show_help {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        _ if matches!(action, Some(Action::Help | Action::Quit)) => {
+                            self.show_help = false;
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.show_help = false;
+                            false
+                        }
+                        _ => false,
+                    };
+                }
+
+                if let Some(onboarding) = self.onboarding.as_mut() {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        KeyCode::Char(digit @ '1'..='3') => {
+                            let index = digit as usize - '0' as usize;
+                            if let Some((name, snippet)) = onboarding.snippets.by_index(index) {
+                                match clipboard::copy(snippet) {
+                                    Ok(()) => onboarding.copied = Some(name),
+                                    Err(err) => warn!(?err, "failed to copy snippet"),
+                                }
+                            }
+                            false
+                        }
+                        _ if matches!(action, Some(Action::Help | Action::Quit)) => {
+                            self.onboarding = None;
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.onboarding = None;
+                            false
+                        }
+                        _ => false,
+                    };
+                }
+
+                if let Some(menu) = self.quick_actions.as_mut() {
+                    let chosen = match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        KeyCode::Esc => {
+                            self.quick_actions = None;
+                            return false;
+                        }
+                        KeyCode::Up => {
+                            menu.cursor = menu.cursor.saturating_sub(1);
+                            None
+                        }
+                        KeyCode::Down => {
+                            menu.cursor = (menu.cursor + 1).min(menu.items.len().saturating_sub(1));
+                            None
+                        }
+                        KeyCode::Enter => menu.items.get(menu.cursor).cloned(),
+                        KeyCode::Char(ch) => menu
+                            .items
+                            .iter()
+                            .find(|item| item.action.key() == ch)
+                            .cloned(),
+                        _ => None,
+                    };
+                    if let Some(item) = chosen
+                        && item.enabled
+                    {
+                        self.pending_quick_action = Some((item.action, menu.event_id));
+                        self.quick_actions = None;
+                    }
+                    return false;
+                }
+
+                if self.diff.is_some() {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.diff = None;
+                            false
+                        }
+                        code => {
+                            scroll_overlay(&mut self.diff_scroll, code, OVERLAY_PAGE);
+                            false
+                        }
+                    };
+                }
+
+                if self.show_diagnostics {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        _ if action == Some(Action::Diagnostics) => {
+                            self.show_diagnostics = false;
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.show_diagnostics = false;
+                            false
+                        }
+                        code => {
+                            scroll_overlay(&mut self.diagnostics_scroll, code, OVERLAY_PAGE);
+                            false
+                        }
+                    };
+                }
+
+                if self.show_logs {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        _ if action == Some(Action::Logs) => {
+                            self.show_logs = false;
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.show_logs = false;
+                            false
+                        }
+                        KeyCode::Char('/' | 'f') => {
+                            match logs::filter() {
+                                Some(filter) => self.open_prompt(PromptPurpose::LogFilter, filter),
+                                None => self.set_status("The log filter cannot be changed here"),
+                            }
+                            false
+                        }
+                        code => {
+                            scroll_overlay(&mut self.logs_scroll, code, OVERLAY_PAGE);
+                            false
+                        }
+                    };
+                }
+
+                if let Some(cursor) = self.unknown_kinds_cursor {
+                    let last = self.unknown_kind_names.len().saturating_sub(1);
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        _ if action == Some(Action::UnknownKinds) => {
+                            self.unknown_kinds_cursor = None;
+                        }
+                        KeyCode::Esc => self.unknown_kinds_cursor = None,
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            self.unknown_kinds_cursor = Some(cursor.saturating_sub(1));
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            self.unknown_kinds_cursor = Some((cursor + 1).min(last));
+                        }
+                        KeyCode::Enter | KeyCode::Char(' ') => self.cycle_unknown_renderer(cursor),
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if let Some(cursor) = self.trash_cursor {
+                    let last = self.trash_ids.len().saturating_sub(1);
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        _ if action == Some(Action::Trash) => self.trash_cursor = None,
+                        KeyCode::Esc => self.trash_cursor = None,
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            self.trash_cursor = Some(cursor.saturating_sub(1));
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            self.trash_cursor = Some((cursor + 1).min(last));
+                        }
+                        KeyCode::Enter | KeyCode::Char(' ') => {
+                            self.pending_untrash = self.trash_ids.get(cursor).copied();
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if let Some(cursor) = self.origins_cursor {
+                    let last = self.origin_sites.len().saturating_sub(1);
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        _ if action == Some(Action::Origins) => self.origins_cursor = None,
+                        KeyCode::Esc => self.origins_cursor = None,
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            self.origins_cursor = Some(cursor.saturating_sub(1));
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            self.origins_cursor = Some((cursor + 1).min(last));
+                        }
+                        KeyCode::Enter | KeyCode::Char(' ') => self.filter_origin(cursor, false),
+                        KeyCode::Char('h') => self.filter_origin(cursor, true),
+                        KeyCode::Char('o') => {
+                            if let Some((file, line)) = self.origin_sites.get(cursor).cloned() {
+                                let file = config::map_path(&self.path_mappings, &file);
+                                match open_in_editor(self.editor.as_deref(), &file, line) {
+                                    Ok(()) => self.set_status(format!("Opened {}", file)),
+                                    Err(err) => {
+                                        self.set_status(format!("Failed to open {}: {}", file, err))
+                                    }
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if let Some(cursor) = self.exceptions_cursor {
+                    let last = self.exception_events.len().saturating_sub(1);
+                    match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            return true;
+                        }
+                        _ if action == Some(Action::Exceptions) => self.exceptions_cursor = None,
+                        KeyCode::Esc => self.exceptions_cursor = None,
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            self.exceptions_cursor = Some(cursor.saturating_sub(1));
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            self.exceptions_cursor = Some((cursor + 1).min(last));
+                        }
+                        KeyCode::Enter | KeyCode::Char(' ') => {
+                            self.select_exception(cursor, detail_ctx);
+                        }
+                        _ => {}
+                    }
+                    return false;
+                }
+
+                if self.show_stats {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        _ if action == Some(Action::Stats) => {
+                            self.show_stats = false;
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.show_stats = false;
+                            false
+                        }
+                        _ => false,
+                    };
+                }
+
+                if let Some(state) = self.json_query.as_mut() {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        _ if action == Some(Action::JsonQuery) => {
+                            self.open_json_query();
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.json_query = None;
+                            false
+                        }
+                        KeyCode::Char('y') => {
+                            self.copy_json_query_results();
+                            false
+                        }
+                        code => {
+                            scroll_overlay(&mut state.scroll, code, OVERLAY_PAGE);
+                            false
+                        }
+                    };
+                }
+
+                if self.show_schema {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        _ if action == Some(Action::Schema) => {
+                            self.show_schema = false;
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.show_schema = false;
+                            false
+                        }
+                        code => {
+                            scroll_overlay(&mut self.schema_scroll, code, OVERLAY_PAGE);
+                            false
+                        }
+                    };
+                }
+
+                if self.show_debug {
+                    return match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                        _ if action == Some(Action::RawPayload) => {
+                            self.show_debug = false;
+                            self.debug_scroll = 0;
+                            false
+                        }
+                        KeyCode::Enter | KeyCode::Esc => {
+                            self.show_debug = false;
+                            self.debug_scroll = 0;
+                            false
+                        }
+                        code => {
+                            scroll_overlay(&mut self.debug_scroll, code, OVERLAY_PAGE);
+                            false
+                        }
+                    };
+                }
+
+                // `/` filters the focused pane.
+                if key.code == KeyCode::Char('/')
+                    && !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                {
+                    if scope == Scope::Detail {
+                        self.open_detail_filter();
+                        return false;
+                    }
+                    if self.focus == Focus::Timeline {
+                        let input = self
+                            .event_filter
+                            .as_ref()
+                            .map(|filter| filter.source().to_string())
+                            .unwrap_or_default();
+                        self.open_prompt(PromptPurpose::Filter, input);
+                        return false;
+                    }
+                }
+
+                if let Some(action) = action {
+                    return self.perform_action(action, detail_ctx);
+                }
+
+                match key.code {
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+                    KeyCode::Char(digit @ '1'..='9')
+                        if !key
+                            .modifiers
+                            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+                    {
+                        self.apply_view(digit as usize - '0' as usize);
+                        false
+                    }
+                    KeyCode::BackTab => {
+                        self.focus = Focus::Timeline;
+                        false
+                    }
+                    KeyCode::Esc if self.fullscreen => {
+                        self.fullscreen = false;
+                        false
+                    }
+                    KeyCode::Esc
+                        if self.focus == Focus::Detail && self.active_detail_filter().is_some() =>
+                    {
+                        self.clear_detail_filter();
+                        false
+                    }
+                    // In the compact layout the details cover the timeline.
+                    KeyCode::Esc if self.focus == Focus::Detail && self.is_compact() => {
+                        self.focus = Focus::Timeline;
+                        false
+                    }
+                    KeyCode::Esc if self.history.is_some() => {
+                        self.close_history();
+                        false
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        if self.focus == Focus::Timeline {
+                            self.step_timeline(1, timeline_len, detail_ctx);
+                        } else {
+                            self.advance_detail_cursor(1, detail_ctx);
+                        }
+                        false
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        if self.focus == Focus::Timeline {
+                            self.step_timeline(-1, timeline_len, detail_ctx);
                         } else {
-                            self.advance_detail_cursor(-10, detail_ctx);
+                            self.advance_detail_cursor(-1, detail_ctx);
+                        }
+                        false
+                    }
+                    KeyCode::PageDown => {
+                        let page = self.page_size();
+                        if self.focus == Focus::Timeline {
+                            self.step_timeline(page, timeline_len, detail_ctx);
+                        } else {
+                            self.advance_detail_cursor(page, detail_ctx);
+                        }
+                        false
+                    }
+                    KeyCode::PageUp => {
+                        let page = self.page_size();
+                        if self.focus == Focus::Timeline {
+                            self.step_timeline(-page, timeline_len, detail_ctx);
+                        } else {
+                            self.advance_detail_cursor(-page, detail_ctx);
                         }
                         false
                     }
@@ -450,12 +3047,12 @@ impl RaygunApp {
                             } else {
                                 self.detail_scroll = 0;
                             }
-                        } else if self.focus == Focus::Detail {
-                            if let Some(state) = self.current_detail_state_mut() {
-                                state.cursor = 0;
-                                state.scroll = 0;
-                                self.detail_scroll = 0;
-                            }
+                        } else if self.focus == Focus::Detail
+                            && let Some(state) = self.current_detail_state_mut()
+                        {
+                            state.cursor = 0;
+                            state.scroll = 0;
+                            self.detail_scroll = 0;
                         }
                         false
                     }
@@ -468,86 +3065,304 @@ impl RaygunApp {
                             } else {
                                 self.detail_scroll = 0;
                             }
-                        } else if self.focus == Focus::Detail {
-                            if detail_ctx.visible_len() > 0 {
-                                if let Some(state) = self.current_detail_state_mut() {
-                                    let max = detail_ctx.visible_len().saturating_sub(1);
-                                    state.cursor = max;
-                                    state.scroll = max;
-                                    self.detail_scroll = max;
-                                }
+                        } else if self.focus == Focus::Detail
+                            && detail_ctx.visible_len() > 0
+                            && let Some(state) = self.current_detail_state_mut()
+                        {
+                            let max = detail_ctx.visible_len().saturating_sub(1);
+                            state.cursor = max;
+                            state.scroll = max;
+                            self.detail_scroll = max;
+                        }
+                        false
+                    }
+                    KeyCode::Enter if self.focus == Focus::Timeline => {
+                        if let Some(event_id) = self.current_event_id() {
+                            self.quick_actions = Some(QuickActionsMenu {
+                                event_id,
+                                cursor: 0,
+                                items: Vec::new(),
+                            });
+                        }
+                        false
+                    }
+                    KeyCode::Right | KeyCode::Enter => {
+                        if self.focus == Focus::Detail && self.expand_current_node(detail_ctx) {
+                            self.store_detail_state(detail_ctx.visible_len());
+                        }
+                        false
+                    }
+                    KeyCode::Left => {
+                        if self.focus == Focus::Detail && self.collapse_current_node(detail_ctx) {
+                            self.store_detail_state(detail_ctx.visible_len());
+                        }
+                        false
+                    }
+                    KeyCode::Char(' ') => {
+                        if self.focus == Focus::Detail && self.toggle_current_node(detail_ctx) {
+                            self.store_detail_state(detail_ctx.visible_len());
+                        }
+                        false
+                    }
+                    _ => false,
+                }
+            }
+            Event::Mouse(mouse) => self.handle_mouse_event(mouse, timeline_len, detail_ctx),
+            Event::Paste(text) => {
+                self.handle_paste(text);
+                false
+            }
+            Event::Tick => false,
+            Event::Resize(width, height) => {
+                debug!(%width, %height, "terminal resized");
+                // The next frame rebuilds the detail view for the new width and
+                // re-clamps the scroll positions against the new pane heights.
+                self.terminal_size = Some((width, height));
+                false
+            }
+        }
+    }
+
+    /// Pastes go to the scratch pad when it is open, otherwise into whichever
+    /// prompt is taking input, flattened onto one line.
+    fn handle_paste(&mut self, text: String) {
+        if std::mem::take(&mut self.scratch_open) {
+            self.pending_scratch = Some(text);
+            return;
+        }
+
+        let flattened = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.input.push_str(&flattened);
+            prompt.error = None;
+        } else if let Some(history) = self.history.as_mut().filter(|history| history.editing) {
+            history.input.push_str(&flattened);
+            history.error = None;
+        } else if let Some(filter) = self.detail_filter.as_mut().filter(|filter| filter.editing) {
+            filter.query.push_str(&flattened);
+            self.reset_filtered_detail();
+        }
+    }
+
+    fn handle_mouse_event(
+        &mut self,
+        mouse: MouseEvent,
+        timeline_len: usize,
+        detail_ctx: &DetailContext,
+    ) -> bool {
+        let Some(layout) = self.last_render.clone() else {
+            return false;
+        };
+
+        let point_in_rect = |rect: Rect| -> bool {
+            if rect.width == 0 || rect.height == 0 {
+                return false;
+            }
+
+            let min_x = rect.x;
+            let max_x = rect.x.saturating_add(rect.width.saturating_sub(1));
+            let min_y = rect.y;
+            let max_y = rect.y.saturating_add(rect.height.saturating_sub(1));
+
+            mouse.column >= min_x
+                && mouse.column <= max_x
+                && mouse.row >= min_y
+                && mouse.row <= max_y
+        };
+
+        if let Some(overlay) = layout.overlay {
+            match overlay {
+                OverlayArea::Help(area) => {
+                    if point_in_rect(area)
+                        && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+                    {
+                        self.show_help = false;
+                    }
+                }
+                OverlayArea::Onboarding(area) => {
+                    if !point_in_rect(area)
+                        && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+                    {
+                        self.onboarding = None;
+                    }
+                }
+                OverlayArea::QuickActions(area) => {
+                    if !point_in_rect(area)
+                        && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+                    {
+                        self.quick_actions = None;
+                    }
+                }
+                OverlayArea::Prompt(area) => {
+                    if !point_in_rect(area)
+                        && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+                    {
+                        self.prompt = None;
+                    }
+                }
+                OverlayArea::History(area) => {
+                    if !point_in_rect(area)
+                        && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+                        && let Some(history) = self.history.as_mut()
+                    {
+                        history.editing = false;
+                        if history.results.is_none() {
+                            self.history = None;
+                        }
+                    }
+                }
+                OverlayArea::Diff(area) => {
+                    if point_in_rect(area) {
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                self.diff = None;
+                            }
+                            MouseEventKind::ScrollUp => {
+                                self.diff_scroll = self.diff_scroll.saturating_sub(1);
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.diff_scroll = self.diff_scroll.saturating_add(1);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                OverlayArea::Diagnostics(area) => {
+                    if point_in_rect(area) {
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                self.show_diagnostics = false;
+                            }
+                            MouseEventKind::ScrollUp => {
+                                self.diagnostics_scroll = self.diagnostics_scroll.saturating_sub(1);
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.diagnostics_scroll = self.diagnostics_scroll.saturating_add(1);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                OverlayArea::Logs(area) => {
+                    if point_in_rect(area) {
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                self.show_logs = false;
+                            }
+                            MouseEventKind::ScrollUp => {
+                                self.logs_scroll = self.logs_scroll.saturating_sub(1);
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.logs_scroll = self.logs_scroll.saturating_add(1);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                OverlayArea::UnknownKinds(area) => {
+                    if point_in_rect(area) {
+                        let cursor = self.unknown_kinds_cursor.unwrap_or_default();
+                        let last = self.unknown_kind_names.len().saturating_sub(1);
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                self.unknown_kinds_cursor = None;
+                            }
+                            MouseEventKind::ScrollUp => {
+                                self.unknown_kinds_cursor = Some(cursor.saturating_sub(1));
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.unknown_kinds_cursor = Some((cursor + 1).min(last));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                OverlayArea::Trash(area) => {
+                    if point_in_rect(area) {
+                        let cursor = self.trash_cursor.unwrap_or_default();
+                        let last = self.trash_ids.len().saturating_sub(1);
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => self.trash_cursor = None,
+                            MouseEventKind::ScrollUp => {
+                                self.trash_cursor = Some(cursor.saturating_sub(1));
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.trash_cursor = Some((cursor + 1).min(last));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                OverlayArea::Origins(area) => {
+                    if point_in_rect(area) {
+                        let cursor = self.origins_cursor.unwrap_or_default();
+                        let last = self.origin_sites.len().saturating_sub(1);
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => self.origins_cursor = None,
+                            MouseEventKind::ScrollUp => {
+                                self.origins_cursor = Some(cursor.saturating_sub(1));
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.origins_cursor = Some((cursor + 1).min(last));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                OverlayArea::Exceptions(area) => {
+                    if point_in_rect(area) {
+                        let cursor = self.exceptions_cursor.unwrap_or_default();
+                        let last = self.exception_events.len().saturating_sub(1);
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                self.exceptions_cursor = None;
+                            }
+                            MouseEventKind::ScrollUp => {
+                                self.exceptions_cursor = Some(cursor.saturating_sub(1));
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.exceptions_cursor = Some((cursor + 1).min(last));
                             }
+                            _ => {}
                         }
-                        false
                     }
-                    KeyCode::Right | KeyCode::Enter => {
-                        if self.focus == Focus::Detail {
-                            if self.expand_current_node(detail_ctx) {
-                                self.store_detail_state(detail_ctx.visible_len());
+                }
+                OverlayArea::JsonQuery(area) => {
+                    if point_in_rect(area)
+                        && let Some(state) = self.json_query.as_mut()
+                    {
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => self.json_query = None,
+                            MouseEventKind::ScrollUp => {
+                                state.scroll = state.scroll.saturating_sub(1);
                             }
-                        }
-                        false
-                    }
-                    KeyCode::Left => {
-                        if self.focus == Focus::Detail {
-                            if self.collapse_current_node(detail_ctx) {
-                                self.store_detail_state(detail_ctx.visible_len());
+                            MouseEventKind::ScrollDown => {
+                                state.scroll = state.scroll.saturating_add(1);
                             }
+                            _ => {}
                         }
-                        false
                     }
-                    KeyCode::Char(' ') => {
-                        if self.focus == Focus::Detail {
-                            if self.toggle_current_node(detail_ctx) {
-                                self.store_detail_state(detail_ctx.visible_len());
-                            }
-                        }
-                        false
+                }
+                OverlayArea::Stats(area) => {
+                    if point_in_rect(area)
+                        && let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+                    {
+                        self.show_stats = false;
                     }
-                    _ => false,
                 }
-            }
-            Event::Mouse(mouse) => self.handle_mouse_event(mouse, timeline_len, detail_ctx),
-            Event::Tick => false,
-            Event::Resize(width, height) => {
-                debug!(%width, %height, "terminal resized");
-                false
-            }
-        }
-    }
-
-    fn handle_mouse_event(
-        &mut self,
-        mouse: MouseEvent,
-        timeline_len: usize,
-        detail_ctx: &DetailContext,
-    ) -> bool {
-        let Some(layout) = self.last_render else {
-            return false;
-        };
-
-        let point_in_rect = |rect: Rect| -> bool {
-            if rect.width == 0 || rect.height == 0 {
-                return false;
-            }
-
-            let min_x = rect.x;
-            let max_x = rect.x.saturating_add(rect.width.saturating_sub(1));
-            let min_y = rect.y;
-            let max_y = rect.y.saturating_add(rect.height.saturating_sub(1));
-
-            mouse.column >= min_x
-                && mouse.column <= max_x
-                && mouse.row >= min_y
-                && mouse.row <= max_y
-        };
-
-        if let Some(overlay) = layout.overlay {
-            match overlay {
-                OverlayArea::Help(area) => {
+                OverlayArea::Schema(area) => {
                     if point_in_rect(area) {
-                        if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
-                            self.show_help = false;
+                        match mouse.kind {
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                self.show_schema = false;
+                            }
+                            MouseEventKind::ScrollUp => {
+                                self.schema_scroll = self.schema_scroll.saturating_sub(1);
+                            }
+                            MouseEventKind::ScrollDown => {
+                                self.schema_scroll = self.schema_scroll.saturating_add(1);
+                            }
+                            _ => {}
                         }
                     }
                 }
@@ -575,26 +3390,17 @@ impl RaygunApp {
         if point_in_rect(layout.timeline_inner) && timeline_len > 0 {
             match mouse.kind {
                 MouseEventKind::Down(MouseButton::Left) => {
-                    let inner = layout.timeline_inner;
-                    let relative_row = mouse.row.saturating_sub(inner.y) as usize;
-                    if relative_row < inner.height as usize {
-                        let view_height = inner.height as usize;
-                        let selected = self.selected.unwrap_or(0);
-                        let total = timeline_len;
-                        let max_start = total.saturating_sub(view_height);
-                        let start = selected
-                            .saturating_sub(view_height.saturating_sub(1))
-                            .min(max_start);
-                        let target = start + relative_row;
-                        if target < total {
-                            self.store_detail_state(detail_ctx.visible_len());
-                            self.focus = Focus::Timeline;
-                            self.selected = Some(target);
-                            if let Some(state) = self.current_detail_state() {
-                                self.detail_scroll = state.scroll;
-                            } else {
-                                self.detail_scroll = 0;
-                            }
+                    let relative_row = mouse.row.saturating_sub(layout.timeline_inner.y) as usize;
+                    if let Some(Some(target)) = layout.timeline_rows.get(relative_row).copied()
+                        && target < timeline_len
+                    {
+                        self.store_detail_state(detail_ctx.visible_len());
+                        self.focus = Focus::Timeline;
+                        self.selected = Some(target);
+                        if let Some(state) = self.current_detail_state() {
+                            self.detail_scroll = state.scroll;
+                        } else {
+                            self.detail_scroll = 0;
                         }
                     }
                     return false;
@@ -659,7 +3465,17 @@ impl RaygunApp {
                         return false;
                     }
 
-                    let detail_position = line_index - header_offset;
+                    // Wrapped lines take several rows; the cached rows know
+                    // which line each belongs to.
+                    let row = line_index - header_offset;
+                    let detail_position = match self
+                        .detail_rows
+                        .as_ref()
+                        .filter(|rows| rows.width() == inner.width)
+                    {
+                        Some(rows) => rows.rows().get(row).map_or(usize::MAX, |row| row.position),
+                        None => row,
+                    };
                     if detail_position >= detail_ctx.visible_len() {
                         self.focus = Focus::Detail;
                         return false;
@@ -686,7 +3502,7 @@ impl RaygunApp {
 
                     if has_children {
                         let detail_line = &detail.lines[line_index_actual];
-                        let indent_width = detail_line.indent.saturating_mul(2) as usize;
+                        let indent_width = detail_line.indent.saturating_mul(2);
                         let icon_width = 2usize;
                         let relative_col = mouse.column.saturating_sub(inner.x) as usize;
 
@@ -745,14 +3561,34 @@ impl RaygunApp {
         false
     }
 
+    /// Moves the timeline selection by `delta`, or moves the target of the
+    /// run of navigation keys being read.
+    fn step_timeline(&mut self, delta: i32, timeline_len: usize, detail_ctx: &DetailContext) {
+        match self.timeline_target.as_mut() {
+            Some(target) => {
+                let from = target.or(self.selected).unwrap_or(0);
+                *target = Some(stepped_index(from, delta, timeline_len));
+            }
+            None => self.move_timeline(delta, timeline_len, detail_ctx),
+        }
+    }
+
+    /// Saves the selected event's detail state, moves the selection by `delta`
+    /// and restores the scroll saved for the newly selected event.
+    fn move_timeline(&mut self, delta: i32, timeline_len: usize, detail_ctx: &DetailContext) {
+        self.store_detail_state(detail_ctx.visible_len());
+        if self.move_selection(delta, timeline_len).is_some() {
+            self.detail_scroll = self.current_detail_state().map_or(0, |state| state.scroll);
+        }
+    }
+
     fn move_selection(&mut self, delta: i32, len: usize) -> Option<usize> {
         if len == 0 {
             self.selected = None;
             return None;
         }
 
-        let current = self.selected.unwrap_or(0) as i32;
-        let new_index = (current + delta).clamp(0, len.saturating_sub(1) as i32) as usize;
+        let new_index = stepped_index(self.selected.unwrap_or(0), delta, len);
         let changed = self.selected != Some(new_index);
         self.selected = Some(new_index);
         if changed { Some(new_index) } else { None }
@@ -764,44 +3600,691 @@ impl RaygunApp {
             return;
         }
 
-        let next = match &self.color_filter {
-            None => Some(self.available_colors[0].clone()),
-            Some(current) => {
-                if let Some(position) = self
-                    .available_colors
-                    .iter()
-                    .position(|value| value == current)
-                {
-                    if position + 1 < self.available_colors.len() {
-                        Some(self.available_colors[position + 1].clone())
-                    } else {
-                        None
-                    }
-                } else {
-                    Some(self.available_colors[0].clone())
-                }
+        let next = match &self.color_filter {
+            None => Some(self.available_colors[0].clone()),
+            Some(current) => {
+                if let Some(position) = self
+                    .available_colors
+                    .iter()
+                    .position(|value| value == current)
+                {
+                    if position + 1 < self.available_colors.len() {
+                        Some(self.available_colors[position + 1].clone())
+                    } else {
+                        None
+                    }
+                } else {
+                    Some(self.available_colors[0].clone())
+                }
+            }
+        };
+
+        self.color_filter = next;
+        self.selected = Some(0);
+        self.detail_scroll = 0;
+    }
+
+    fn clear_local_timeline(&mut self) {
+        if let Some(daemon) = &self.daemon {
+            daemon.send(daemon::ClientCommand::Clear);
+        }
+        let state = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            state.clear_timeline().await;
+        });
+        self.selected = None;
+        self.detail_scroll = 0;
+        self.detail_states.clear();
+        self.visible_events.clear();
+        self.available_colors.clear();
+        self.color_filter = None;
+        self.show_help = false;
+        self.show_debug = false;
+        self.debug_scroll = 0;
+        self.show_schema = false;
+        self.schema_scroll = 0;
+        self.json_query = None;
+        self.quick_actions = None;
+        self.diff = None;
+        self.event_filter = None;
+        self.range = None;
+    }
+
+    /// Builds the detail of `event` the way `key` describes it.
+    fn build_detail(&self, event: &TimelineEvent, key: DetailKey) -> CachedDetail {
+        let mut detail = build_detail_view_for_event(event, key.width, self.settings.locale);
+        if let Some(payload) = primary_payload(event)
+            && let PayloadKind::Unknown(kind) = &payload.kind
+            && let Some(renderer) = self.unknown_renderers.get(kind)
+        {
+            detail.lines = detail::render_unknown(payload, *renderer, key.width);
+            detail
+                .lines
+                .extend(detail::annotation_lines(&event.annotations));
+        }
+        if let Some(mode) = key.render_override
+            && let Some(payload) = primary_payload(event)
+            && let Some(lines) = detail::render_override(payload, mode)
+        {
+            detail.lines = lines;
+            detail
+                .lines
+                .extend(detail::annotation_lines(&event.annotations));
+            detail.header = format!("{} • {}", detail.header, mode.name());
+        }
+        if !self.path_mappings.is_empty() && !detail.footer.is_empty() {
+            detail.footer = config::map_path(&self.path_mappings, &detail.footer);
+        }
+        let filter_counts = key.filter.as_deref().map(|query| {
+            let total = detail::content_line_count(&detail);
+            (detail::filter_lines(&mut detail, query), total)
+        });
+        if let Some(reading) = memory_reading(event)
+            && !key.readings.is_empty()
+        {
+            detail.lines.extend(detail::reading_history_lines(
+                &reading.label,
+                &key.readings,
+                self.settings.locale,
+            ));
+        }
+        CachedDetail {
+            key,
+            build: next_generation(),
+            detail: Arc::new(detail),
+            filter_counts,
+        }
+    }
+
+    /// The location of the frame under the cursor, to show in place of the
+    /// detail footer, and the source around it, falling back to the payload
+    /// origin, for the preview pane.
+    fn follow_cursor(
+        &mut self,
+        detail: &detail::DetailViewModel,
+        state: &DetailStateView,
+    ) -> (Option<String>, Option<SourcePreview>) {
+        let (visible_indices, _) =
+            detail::visible_indices_with_children(detail, Some(&state.collapsed));
+        let location = visible_indices
+            .get(state.cursor)
+            .and_then(|index| detail.origins.get(index));
+
+        let mut footer = None;
+        let (file, line) = match location {
+            Some(location) => {
+                let file = config::map_path(&self.path_mappings, &location.file);
+                footer = Some(match location.line {
+                    Some(line) => format!("{}:{}", file, line),
+                    None => file.clone(),
+                });
+                (file, location.line)
+            }
+            None if !detail.footer.is_empty() => match detail.footer.rsplit_once(':') {
+                Some((file, line)) if line.parse::<u32>().is_ok() => {
+                    (file.to_string(), line.parse().ok())
+                }
+                _ => (detail.footer.clone(), None),
+            },
+            None => return (None, None),
+        };
+
+        if self
+            .source_cache
+            .as_ref()
+            .is_none_or(|(cached, _)| *cached != file)
+        {
+            let contents = fs::read_to_string(&file)
+                .map(|text| text.lines().map(str::to_string).collect())
+                .map_err(|err| err.to_string());
+            self.source_cache = Some((file.clone(), contents));
+        }
+
+        let preview = self.source_cache.as_ref().map(|(_, contents)| {
+            source_preview(&file, line, contents.as_deref().map_err(String::as_str))
+        });
+        (footer, preview)
+    }
+
+    fn is_compact(&self) -> bool {
+        self.terminal_size
+            .is_some_and(|(width, height)| tui::is_compact(width, height))
+    }
+
+    /// Columns available to detail lines: the pane borders and the
+    /// expand/collapse icon take four.
+    fn detail_width(&self) -> Option<usize> {
+        self.terminal_size
+            .map(|(width, _)| usize::from(width).saturating_sub(4))
+    }
+
+    /// Rows moved by `PgUp`/`PgDn`: one less than the focused pane's height, so
+    /// the last visible row stays on screen as context.
+    fn page_size(&self) -> i32 {
+        self.last_render
+            .as_ref()
+            .map(|layout| match self.focus {
+                Focus::Timeline => layout.timeline_inner.height,
+                Focus::Detail => layout.detail_inner.height,
+            })
+            .filter(|height| *height > 1)
+            .map(|height| i32::from(height) - 1)
+            .unwrap_or(10)
+    }
+
+    fn set_status(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), Instant::now()));
+    }
+
+    /// Menu rows for `event`, with actions that don't apply disabled.
+    fn quick_action_items(
+        &self,
+        event: &TimelineEvent,
+        has_previous: bool,
+        range_len: Option<usize>,
+    ) -> Vec<QuickActionItem> {
+        let entry = summarize_event(event);
+        let origin = event_origin(event).map(|(file, line)| {
+            let file = config::map_path(&self.path_mappings, &file);
+            match line {
+                Some(line) => format!("{}:{}", file, line),
+                None => file,
+            }
+        });
+        let filtered = self.color_filter.is_some() || self.event_filter.is_some();
+
+        QuickAction::ALL
+            .iter()
+            .map(|&action| {
+                let (enabled, detail) = match action {
+                    QuickAction::CopyJson => (true, None),
+                    QuickAction::Export => (true, Some(export_file_name(event))),
+                    QuickAction::ExportHtml => (
+                        true,
+                        Some(match range_len {
+                            Some(len) => format!("marked range, {} event{}", len, plural(len)),
+                            None => "this event".to_string(),
+                        }),
+                    ),
+                    QuickAction::OpenOrigin => (origin.is_some(), origin.clone()),
+                    QuickAction::CopyPermalink => {
+                        (origin.is_some() && self.permalink.is_some(), None)
+                    }
+                    QuickAction::Resend => (self.resend_url.is_some(), self.resend_url.clone()),
+                    QuickAction::EditLabel => (!self.showing_history(), event.label.clone()),
+                    QuickAction::MoveToScreen => (!self.showing_history(), event.screen.clone()),
+                    QuickAction::RenameScreen | QuickAction::CloseScreen => (
+                        !self.showing_history() && event.screen.is_some(),
+                        event.screen.clone(),
+                    ),
+                    QuickAction::FilterColor => (event.color.is_some(), event.color.clone()),
+                    QuickAction::FilterLabel => (entry.label.is_some(), entry.label.clone()),
+                    QuickAction::FilterKind => (true, Some(entry.kind.clone())),
+                    QuickAction::ClearFilters => (filtered, None),
+                    QuickAction::DiffPrevious => (has_previous, None),
+                    QuickAction::Delete => (!self.showing_history(), None),
+                    QuickAction::RangeStats | QuickAction::RangeExport => (
+                        range_len.is_some(),
+                        range_len.map(|len| format!("{} event{}", len, plural(len))),
+                    ),
+                    QuickAction::RangeKeep => {
+                        (range_len.is_some() && !self.showing_history(), None)
+                    }
+                    QuickAction::RangeUnmark => (self.range.is_some(), None),
+                };
+                QuickActionItem {
+                    action,
+                    detail,
+                    enabled,
+                }
+            })
+            .collect()
+    }
+
+    async fn run_quick_action(&mut self, action: QuickAction, event_id: Uuid) {
+        let events = self.displayed_events().await;
+        let Some(position) = events.iter().position(|event| event.id == event_id) else {
+            self.set_status("The event is no longer in the timeline");
+            return;
+        };
+        let event = &events[position];
+
+        match action {
+            QuickAction::CopyJson => match clipboard::copy(&event_json(event)) {
+                Ok(()) => self.set_status("Copied the event JSON to the clipboard"),
+                Err(err) => self.set_status(format!("Failed to copy: {}", err)),
+            },
+            QuickAction::Export => {
+                let path = PathBuf::from(export_file_name(event));
+                match fs::write(&path, event_json(event)) {
+                    Ok(()) => self.set_status(format!("Exported the event to {}", path.display())),
+                    Err(err) => self.set_status(format!("Failed to export: {}", err)),
+                }
+            }
+            QuickAction::ExportHtml => {
+                // A marked range takes precedence, as it does in the menu.
+                let (page, path) = match self.range_bounds(&events) {
+                    Some((first, last)) => (
+                        &events[first..=last],
+                        PathBuf::from(timestamped_file_name(
+                            "raygun-range",
+                            "html",
+                            SystemTime::now(),
+                        )),
+                    ),
+                    None => (
+                        &events[position..=position],
+                        PathBuf::from(export_file_name(event)).with_extension("html"),
+                    ),
+                };
+                match fs::write(
+                    &path,
+                    html::document(page, SystemTime::now(), self.settings.locale),
+                ) {
+                    Ok(()) => self.set_status(format!(
+                        "Exported {} event{} as HTML to {}",
+                        page.len(),
+                        plural(page.len()),
+                        path.display()
+                    )),
+                    Err(err) => self.set_status(format!("Failed to export: {}", err)),
+                }
+            }
+            QuickAction::RangeStats | QuickAction::RangeExport | QuickAction::RangeKeep => {
+                let Some((first, last)) = self.range_bounds(&events) else {
+                    self.set_status("Mark both ends of a range first");
+                    return;
+                };
+                let range = &events[first..=last];
+                match action {
+                    QuickAction::RangeStats => {
+                        self.set_status(range_stats(range, self.settings.locale))
+                    }
+                    QuickAction::RangeExport => {
+                        let path = PathBuf::from(timestamped_file_name(
+                            "raygun-range",
+                            "jsonl",
+                            SystemTime::now(),
+                        ));
+                        let lines: String = range
+                            .iter()
+                            .filter_map(|event| {
+                                serde_json::to_string(&import::exported_request(event)).ok()
+                            })
+                            .map(|line| line + "\n")
+                            .collect();
+                        match fs::write(&path, lines) {
+                            Ok(()) => self.set_status(format!(
+                                "Exported {} event{} to {}",
+                                range.len(),
+                                plural(range.len()),
+                                path.display()
+                            )),
+                            Err(err) => self.set_status(format!("Failed to export: {}", err)),
+                        }
+                    }
+                    _ => {
+                        let outside: Vec<Uuid> = events[..first]
+                            .iter()
+                            .chain(&events[last + 1..])
+                            .map(|event| event.id)
+                            .collect();
+                        for id in &outside {
+                            if let Some(daemon) = &self.daemon {
+                                daemon.send(daemon::ClientCommand::Remove { id: *id });
+                            }
+                            if self.state.remove_event(*id).await {
+                                self.detail_states.remove(id);
+                            }
+                        }
+                        self.selected = Some(0);
+                        self.detail_scroll = 0;
+                        self.set_status(format!(
+                            "Cleared {} event{} outside the range",
+                            outside.len(),
+                            plural(outside.len())
+                        ));
+                    }
+                }
+            }
+            QuickAction::RangeUnmark => {
+                self.range = None;
+                self.set_status("Range unmarked");
+            }
+            QuickAction::OpenOrigin => {
+                let Some((file, line)) = event_origin(event) else {
+                    return;
+                };
+                let file = config::map_path(&self.path_mappings, &file);
+                match open_in_editor(self.editor.as_deref(), &file, line) {
+                    Ok(()) => self.set_status(format!("Opened {}", file)),
+                    Err(err) => self.set_status(format!("Failed to open {}: {}", file, err)),
+                }
+            }
+            QuickAction::CopyPermalink => {
+                let Some(permalink) = &self.permalink else {
+                    self.set_status("Set up [permalink] in the config file to copy permalinks");
+                    return;
+                };
+                let Some((file, line)) = event_origin(event) else {
+                    self.set_status("This event has no origin");
+                    return;
+                };
+                let file = config::map_path(&self.path_mappings, &file);
+                let revision = match &permalink.revision {
+                    Some(revision) => Ok(revision.clone()),
+                    None => head_commit(&permalink.root),
+                };
+                let link = match revision {
+                    Ok(revision) => permalink.link(&file, line, &revision),
+                    Err(err) => {
+                        self.set_status(format!(
+                            "Failed to read the HEAD commit of {}: {}",
+                            permalink.root, err
+                        ));
+                        return;
+                    }
+                };
+                match link {
+                    Some(link) => match clipboard::copy(&link) {
+                        Ok(()) => self.set_status(format!("Copied {}", link)),
+                        Err(err) => self.set_status(format!("Failed to copy: {}", err)),
+                    },
+                    None => self.set_status(format!(
+                        "{} is outside the permalink root {}",
+                        file, permalink.root
+                    )),
+                }
+            }
+            QuickAction::FilterColor => {
+                self.color_filter = event.color.clone();
+                self.selected = Some(0);
+            }
+            QuickAction::FilterLabel => {
+                self.event_filter = summarize_event(event)
+                    .label
+                    .and_then(|label| Filter::field("label", &label).ok());
+                self.selected = Some(0);
+            }
+            QuickAction::FilterKind => {
+                self.event_filter = Filter::field("kind", &summarize_event(event).kind).ok();
+                self.selected = Some(0);
+            }
+            QuickAction::ClearFilters => {
+                self.color_filter = None;
+                self.event_filter = None;
+            }
+            QuickAction::Resend => {
+                let Some(url) = self.resend_url.clone() else {
+                    return;
+                };
+                match resend(&url, event).await {
+                    Ok(status) if status.is_success() => {
+                        self.set_status(format!("Re-sent the payload to {} ({})", url, status))
+                    }
+                    Ok(status) => self.set_status(format!(
+                        "{} answered the re-sent payload with {}",
+                        url, status
+                    )),
+                    Err(err) => self.set_status(format!("Failed to re-send the payload: {}", err)),
+                }
+            }
+            QuickAction::DiffPrevious => {
+                let Some(previous) = position.checked_sub(1).map(|index| &events[index]) else {
+                    return;
+                };
+                let payloads_json = |event: &TimelineEvent| {
+                    serde_json::to_string_pretty(&event.request.payloads).unwrap_or_default()
+                };
+                self.diff = Some(DiffView {
+                    title: format!(
+                        "Diff: previous {} → this {}",
+                        summarize_event(previous).kind,
+                        summarize_event(event).kind
+                    ),
+                    lines: diff::line_diff(&payloads_json(previous), &payloads_json(event)),
+                });
+                self.diff_scroll = 0;
+            }
+            QuickAction::EditLabel => {
+                let input = event.label.clone().unwrap_or_default();
+                self.open_prompt(PromptPurpose::Label(event_id), input);
+            }
+            QuickAction::MoveToScreen => {
+                let input = event.screen.clone().unwrap_or_default();
+                self.open_prompt(PromptPurpose::MoveToScreen(event_id), input);
+            }
+            QuickAction::RenameScreen => match event.screen.clone() {
+                Some(input) => self.open_prompt(PromptPurpose::RenameScreen(event_id), input),
+                None => self.set_status("The event is not on a screen"),
+            },
+            QuickAction::CloseScreen => self.edit_screen(ScreenEdit::Close(event_id)).await,
+            QuickAction::Delete => {
+                if let Some(daemon) = &self.daemon {
+                    daemon.send(daemon::ClientCommand::Remove { id: event_id });
+                }
+                if self.state.remove_event(event_id).await {
+                    self.detail_states.remove(&event_id);
+                    self.set_status(format!(
+                        "Moved the event to the trash; {} restores it",
+                        self.settings.keymap.hint(Action::Trash)
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Moves a deleted event back to the timeline, on the daemon too when
+    /// attached.
+    /// Call sites of `events` with the number of events each sent, busiest
+    /// first: the sites as the client sent them, and the overlay rows.
+    /// `file:line` after path mappings.
+    fn location(&self, file: &str, line: Option<u32>) -> String {
+        let mapped = config::map_path(&self.path_mappings, file);
+        match line {
+            Some(line) => format!("{}:{}", mapped, line),
+            None => mapped,
+        }
+    }
+
+    fn origin_rows(
+        &self,
+        events: &[TimelineEvent],
+    ) -> (Vec<(String, Option<u32>)>, Vec<OriginRow>) {
+        let mut counts: HashMap<(String, Option<u32>), (usize, String)> = HashMap::new();
+        for event in events {
+            if let Some(site) = event_origin(event) {
+                let entry = counts.entry(site).or_default();
+                entry.0 += 1;
+                // Events are oldest first, so the last kind seen is the newest.
+                entry.1 = summarize_event(event).kind;
+            }
+        }
+        let mut sites: Vec<_> = counts.into_iter().collect();
+        sites.sort_by(|(a_site, (a_count, _)), (b_site, (b_count, _))| {
+            b_count.cmp(a_count).then_with(|| a_site.cmp(b_site))
+        });
+        sites
+            .into_iter()
+            .map(|((file, line), (count, kind))| {
+                let row = OriginRow {
+                    location: self.location(&file, line),
+                    kind,
+                    count,
+                };
+                ((file, line), row)
+            })
+            .unzip()
+    }
+
+    /// Shows only the events from the call site on row `index` of the origins
+    /// overlay, or with `hide` adds hiding them to the timeline filter.
+    fn filter_origin(&mut self, index: usize, hide: bool) {
+        let Some((file, line)) = self.origin_sites.get(index) else {
+            return;
+        };
+        let site = match line {
+            Some(line) => format!("{}:{}", file, line),
+            None => file.clone(),
+        };
+        let term = format!("origin:{}", filter::quote(&site));
+        let expression = match (&self.event_filter, hide) {
+            (Some(current), true) => format!("({}) -{}", current.source(), term),
+            (None, true) => format!("-{}", term),
+            (_, false) => term,
+        };
+        match Filter::parse(&expression) {
+            Ok(filter) => {
+                self.event_filter = Some(filter.with_host_aliases(Arc::clone(&self.host_aliases)));
+                self.origins_cursor = None;
+                self.selected = Some(0);
+                self.detail_scroll = 0;
+            }
+            Err(err) => self.set_status(format!("Failed to filter by origin: {}", err)),
+        }
+    }
+
+    /// Closes the exceptions overlay on the newest event of the group on row
+    /// `index`.
+    fn select_exception(&mut self, index: usize, detail_ctx: &DetailContext) {
+        let Some(event_id) = self.exception_events.get(index) else {
+            return;
+        };
+        let Some(position) = self
+            .visible_events
+            .iter()
+            .position(|visible| visible == event_id)
+        else {
+            self.set_status("The newest exception of that group is hidden by the filter");
+            return;
+        };
+
+        self.store_detail_state(detail_ctx.visible_len());
+        self.exceptions_cursor = None;
+        self.focus = Focus::Timeline;
+        self.selected = Some(position);
+        self.detail_scroll = self.current_detail_state().map_or(0, |state| state.scroll);
+    }
+
+    async fn untrash(&mut self, event_id: Uuid) {
+        if let Some(daemon) = &self.daemon {
+            daemon.send(daemon::ClientCommand::Restore { id: event_id });
+        }
+        if self.state.restore_event(event_id).await {
+            self.set_status("Restored the event");
+        } else {
+            self.set_status("The event is no longer in the trash");
+        }
+    }
+
+    /// Sets or removes an event's label, on the daemon too when attached.
+    async fn relabel(&mut self, event_id: Uuid, label: Option<String>) {
+        if let Some(daemon) = &self.daemon {
+            daemon.send(daemon::ClientCommand::Label {
+                id: event_id,
+                label: label.clone(),
+            });
+        }
+        if !self.state.set_label(event_id, label.clone()).await {
+            self.set_status("The event is no longer in the timeline");
+            return;
+        }
+        match label {
+            Some(label) => self.set_status(format!("Labelled the event {}", label)),
+            None => self.set_status("Removed the event's label"),
+        }
+    }
+
+    /// Applies a screen change, on the daemon too when attached.
+    async fn edit_screen(&mut self, edit: ScreenEdit) {
+        let event_id = match &edit {
+            ScreenEdit::Move(event_id, _)
+            | ScreenEdit::Rename(event_id, _)
+            | ScreenEdit::Close(event_id) => *event_id,
+        };
+        let events = self.state.timeline_snapshot().await;
+        let Some(event) = events.iter().find(|event| event.id == event_id) else {
+            self.set_status("The event is no longer in the timeline");
+            return;
+        };
+        let current = event.screen.clone();
+
+        let (command, status) = match edit {
+            ScreenEdit::Move(id, screen) => {
+                self.state.set_screen(id, screen.clone()).await;
+                let status = match &screen {
+                    Some(screen) => format!("Moved the event to screen {}", screen),
+                    None => "Took the event off its screen".to_string(),
+                };
+                (daemon::ClientCommand::Screen { id, screen }, status)
+            }
+            ScreenEdit::Rename(_, to) => {
+                let Some(from) = current else {
+                    return;
+                };
+                let renamed = self.state.rename_screen(&from, &to).await;
+                let status = format!(
+                    "Renamed screen {} to {} on {} event{}",
+                    from,
+                    to,
+                    renamed,
+                    plural(renamed)
+                );
+                (daemon::ClientCommand::RenameScreen { from, to }, status)
+            }
+            ScreenEdit::Close(_) => {
+                let Some(name) = current else {
+                    self.set_status("The event is not on a screen");
+                    return;
+                };
+                let closed = self.state.close_screen(&name).await;
+                self.selected = Some(0);
+                self.detail_scroll = 0;
+                let status = format!(
+                    "Closed screen {} and moved its {} event{} to the trash ({} restores)",
+                    name,
+                    closed,
+                    plural(closed),
+                    self.settings.keymap.hint(Action::Trash)
+                );
+                (daemon::ClientCommand::CloseScreen { name }, status)
             }
         };
-
-        self.color_filter = next;
-        self.selected = Some(0);
-        self.detail_scroll = 0;
+        if let Some(daemon) = &self.daemon {
+            daemon.send(command);
+        }
+        self.set_status(status);
     }
 
-    fn clear_local_timeline(&mut self) {
-        let state = Arc::clone(&self.state);
-        tokio::spawn(async move {
-            state.clear_timeline().await;
-        });
-        self.selected = None;
-        self.detail_scroll = 0;
-        self.detail_states.clear();
-        self.visible_events.clear();
-        self.available_colors.clear();
-        self.color_filter = None;
-        self.show_help = false;
-        self.show_debug = false;
-        self.debug_scroll = 0;
+    /// Moves an event to the next Ray color, and from the last one back to
+    /// no color, on the daemon too when attached.
+    async fn cycle_event_color(&mut self, event_id: Uuid) {
+        let events = self.state.timeline_snapshot().await;
+        let Some(event) = events.iter().find(|event| event.id == event_id) else {
+            self.set_status("The event is no longer in the timeline");
+            return;
+        };
+        let color = match event
+            .color
+            .as_deref()
+            .and_then(|color| config::RAY_COLORS.iter().position(|name| *name == color))
+        {
+            Some(index) => config::RAY_COLORS.get(index + 1),
+            None => config::RAY_COLORS.first(),
+        }
+        .map(|color| color.to_string());
+
+        if let Some(daemon) = &self.daemon {
+            daemon.send(daemon::ClientCommand::Color {
+                id: event_id,
+                color: color.clone(),
+            });
+        }
+        self.state.set_color(event_id, color.clone()).await;
+        match color {
+            Some(color) => self.set_status(format!("Colored the event {}", color)),
+            None => self.set_status("Removed the event's color"),
+        }
     }
 
     fn advance_detail_cursor(&mut self, delta: i32, ctx: &DetailContext) {
@@ -831,12 +4314,13 @@ impl RaygunApp {
         if let Some(state) = self.current_detail_state_mut() {
             let cursor = state.cursor.min(ctx.visible_len().saturating_sub(1));
             if let Some(&line_index) = ctx.visible_indices.get(cursor) {
-                if ctx.has_children.get(line_index).copied().unwrap_or(false) {
-                    if state.collapsed.remove(&line_index) {
-                        state.scroll = state.cursor.min(ctx.visible_len().saturating_sub(1));
-                        self.detail_scroll = state.scroll;
-                        return true;
-                    }
+                if ctx.has_children.get(line_index).copied().unwrap_or(false)
+                    && state.collapsed.remove(&line_index)
+                {
+                    state.collapsed_changed();
+                    state.scroll = state.cursor.min(ctx.visible_len().saturating_sub(1));
+                    self.detail_scroll = state.scroll;
+                    return true;
                 }
 
                 state.scroll = state.cursor.min(ctx.visible_len().saturating_sub(1));
@@ -854,15 +4338,16 @@ impl RaygunApp {
 
         if let Some(state) = self.current_detail_state_mut() {
             let cursor = state.cursor.min(ctx.visible_len().saturating_sub(1));
-            if let Some(&line_index) = ctx.visible_indices.get(cursor) {
-                if ctx.has_children.get(line_index).copied().unwrap_or(false) {
-                    if !state.collapsed.remove(&line_index) {
-                        state.collapsed.insert(line_index);
-                    }
-                    state.scroll = state.cursor.min(ctx.visible_len().saturating_sub(1));
-                    self.detail_scroll = state.scroll;
-                    return true;
+            if let Some(&line_index) = ctx.visible_indices.get(cursor)
+                && ctx.has_children.get(line_index).copied().unwrap_or(false)
+            {
+                if !state.collapsed.remove(&line_index) {
+                    state.collapsed.insert(line_index);
                 }
+                state.collapsed_changed();
+                state.scroll = state.cursor.min(ctx.visible_len().saturating_sub(1));
+                self.detail_scroll = state.scroll;
+                return true;
             }
         }
         false
@@ -898,12 +4383,13 @@ impl RaygunApp {
                             return true;
                         }
                     } else {
+                        state.collapsed_changed();
                         state.scroll = state.cursor.min(ctx.visible_len().saturating_sub(1));
                         self.detail_scroll = state.scroll;
                         return true;
                     }
-                } else if indent > 0 {
-                    if let Some((pos, _)) = ctx.visible_indices[..cursor]
+                } else if indent > 0
+                    && let Some((pos, _)) = ctx.visible_indices[..cursor]
                         .iter()
                         .enumerate()
                         .rev()
@@ -912,12 +4398,11 @@ impl RaygunApp {
                                 .map(|detail| detail.lines[**idx].indent < indent)
                                 .unwrap_or(false)
                         })
-                    {
-                        state.cursor = pos;
-                        state.scroll = pos;
-                        self.detail_scroll = pos;
-                        return true;
-                    }
+                {
+                    state.cursor = pos;
+                    state.scroll = pos;
+                    self.detail_scroll = pos;
+                    return true;
                 }
 
                 state.scroll = state.cursor.min(ctx.visible_len().saturating_sub(1));
@@ -958,7 +4443,11 @@ impl RaygunApp {
 
     fn current_detail_state_mut(&mut self) -> Option<&mut DetailState> {
         let id = self.current_event_id()?;
-        Some(self.detail_states.entry(id).or_default())
+        Some(
+            self.detail_states
+                .entry(id)
+                .or_insert_with(|| DetailState::new(HashSet::new())),
+        )
     }
 }
 
@@ -996,11 +4485,227 @@ impl LayoutPreset {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 struct DetailState {
     scroll: usize,
     cursor: usize,
     collapsed: HashSet<usize>,
+    /// Set afresh whenever `collapsed` changes, to key the wrapped rows on.
+    collapse_generation: u64,
+}
+
+impl DetailState {
+    fn new(collapsed: HashSet<usize>) -> Self {
+        Self {
+            scroll: 0,
+            cursor: 0,
+            collapsed,
+            collapse_generation: next_generation(),
+        }
+    }
+
+    fn collapsed_changed(&mut self) {
+        self.collapse_generation = next_generation();
+    }
+}
+
+/// Everything the selected event's detail is built from besides the event's
+/// content. Settings from the config file count as one input, changing with
+/// every reload.
+#[derive(Debug, Clone, PartialEq)]
+struct DetailKey {
+    event: Uuid,
+    revision: u64,
+    settings_generation: u64,
+    width: Option<usize>,
+    render_override: Option<RenderOverride>,
+    /// Query of the detail filter, when one is set for the event.
+    filter: Option<String>,
+    /// Byte counts of the event's label, oldest first, when it is a memory or
+    /// size reading that repeats an earlier one.
+    readings: Vec<f64>,
+}
+
+#[derive(Debug)]
+struct CachedDetail {
+    key: DetailKey,
+    /// Set afresh for every build, to key the wrapped rows on.
+    build: u64,
+    detail: Arc<detail::DetailViewModel>,
+    /// Lines the detail filter matched and the lines there were, when set.
+    filter_counts: Option<(usize, usize)>,
+}
+
+/// Process-wide, so a state recreated for an event or a detail rebuilt for it
+/// never reuses the generation of the one it replaced.
+fn next_generation() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Resolves on Ctrl+C delivered as a signal. Raw mode turns Ctrl+C into a key
+/// press, but on Windows Ctrl+Break (and closing the console) still arrive as
+/// signals.
+#[cfg(windows)]
+pub(crate) async fn interrupted() -> io::Result<()> {
+    let mut ctrl_break = tokio::signal::windows::ctrl_break()?;
+    let mut ctrl_close = tokio::signal::windows::ctrl_close()?;
+    select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = ctrl_break.recv() => Ok(()),
+        _ = ctrl_close.recv() => Ok(()),
+    }
+}
+
+/// Resolves on Ctrl+C delivered as a signal (raw mode turns the key itself
+/// into a key press).
+#[cfg(not(windows))]
+pub(crate) async fn interrupted() -> io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+/// The input event held back while draining navigation keys, or else the
+/// next one from the terminal.
+async fn next_event(
+    rx: &mut mpsc::UnboundedReceiver<Event>,
+    queued: &mut Option<Event>,
+) -> Option<Event> {
+    match queued.take() {
+        Some(event) => Some(event),
+        None => rx.recv().await,
+    }
+}
+
+/// Resolves on any signal that ends the UI: an interrupt or SIGTERM.
+#[cfg(unix)]
+pub(crate) async fn exit_signal() -> io::Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    select! {
+        result = interrupted() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn exit_signal() -> io::Result<()> {
+    interrupted().await
+}
+
+/// SIGHUP, which asks for the config file to be reloaded. Listening starts
+/// when this is created, so a signal between two `recv` calls is not lost.
+pub(crate) struct Hangups {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl Hangups {
+    pub(crate) fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+
+            let signal = signal(SignalKind::hangup())
+                .inspect_err(|err| warn!(?err, "failed to listen for SIGHUP"))
+                .ok();
+            Self { signal }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    /// Resolves on the next SIGHUP; never where there are none.
+    pub(crate) async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            signal.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+/// `from` moved by `delta` within a list of `len` entries, stopping at either
+/// end.
+fn stepped_index(from: usize, delta: i32, len: usize) -> usize {
+    (from as i32 + delta).clamp(0, len.saturating_sub(1) as i32) as usize
+}
+
+/// Scrolls an overlay for the arrow, page and Home keys; other keys leave it
+/// where it is.
+fn scroll_overlay(scroll: &mut usize, key: KeyCode, page: usize) {
+    *scroll = match key {
+        KeyCode::Up => scroll.saturating_sub(1),
+        KeyCode::Down => scroll.saturating_add(1),
+        KeyCode::PageUp => scroll.saturating_sub(page),
+        KeyCode::PageDown => scroll.saturating_add(page),
+        KeyCode::Home => 0,
+        _ => return,
+    };
+}
+
+/// Moves the cursor to the detail's focus line, expanding any collapsed
+/// section that hides it and scrolling it to the top.
+fn focus_detail_line(detail: &detail::DetailViewModel, state: &mut DetailState) {
+    let Some(target) = detail.focus_line else {
+        return;
+    };
+
+    // A collapsed line hides the target if every line up to it is nested deeper.
+    let collapsed = state.collapsed.len();
+    state.collapsed.retain(|&index| {
+        index >= target
+            || detail.lines[index + 1..=target]
+                .iter()
+                .any(|line| line.indent <= detail.lines[index].indent)
+    });
+    if state.collapsed.len() != collapsed {
+        state.collapsed_changed();
+    }
+
+    let (visible_indices, _) =
+        detail::visible_indices_with_children(detail, Some(&state.collapsed));
+    if let Some(position) = visible_indices.iter().position(|&index| index == target) {
+        state.cursor = position;
+        state.scroll = position;
+    }
+}
+
+/// Cuts `PREVIEW_CONTEXT` lines either side of `line` out of a source file.
+fn source_preview(
+    file: &str,
+    line: Option<u32>,
+    contents: Result<&[String], &str>,
+) -> SourcePreview {
+    let location = match line {
+        Some(line) => format!("{}:{}", file, line),
+        None => file.to_string(),
+    };
+
+    match contents {
+        Ok(lines) => {
+            let center = line
+                .map(|line| line.saturating_sub(1) as usize)
+                .unwrap_or(0);
+            let start = center.saturating_sub(PREVIEW_CONTEXT);
+            let end = (center + PREVIEW_CONTEXT + 1).min(lines.len());
+            SourcePreview {
+                location,
+                lines: (start..end)
+                    .map(|index| (index as u32 + 1, lines[index].clone()))
+                    .collect(),
+                highlight: line,
+                message: None,
+            }
+        }
+        Err(err) => SourcePreview {
+            location,
+            lines: Vec::new(),
+            highlight: None,
+            message: Some(format!("Source not available locally: {}", err)),
+        },
+    }
 }
 
 struct DetailContext<'a> {
@@ -1036,45 +4741,15 @@ impl<'a> DetailContext<'a> {
     }
 }
 
-fn summarize_event(event: &TimelineEvent) -> TimelineEntry {
+pub fn summarize_event(event: &TimelineEvent) -> TimelineEntry {
     let elapsed = event.received_at.elapsed().unwrap_or_default();
-
-    let aggregated = aggregated_log_payload(event);
-    let payload_ref = aggregated
-        .as_ref()
-        .map(|payload| payload as &Payload)
-        .or_else(|| primary_payload(event));
-
-    let mut timeline_label = aggregated
-        .as_ref()
-        .and_then(|payload| payload.content_string("label"))
-        .map(|label| label.to_string())
-        .or_else(|| event.label.clone());
-
-    let (kind, mut summary) = if let Some(payload) = payload_ref {
-        if timeline_label.is_none() {
-            timeline_label = payload
-                .content_string("label")
-                .map(|label| label.trim().to_string())
-                .filter(|label| !label.is_empty());
-        }
-
-        (payload_kind_label(payload), payload_summary(payload))
-    } else {
-        ("empty".to_string(), "Request without payloads".to_string())
-    };
-
-    if timeline_label
-        .as_deref()
-        .map(is_default_html_label)
-        .unwrap_or(false)
-    {
-        timeline_label = None;
-    }
-
-    if let Some(screen) = event.screen.as_deref() {
-        summary = format!("{} | {}", screen, summary);
-    }
+    let EventSummary {
+        kind,
+        summary,
+        label,
+        format,
+    } = summary::summarize(event);
+    let request = handled_request(event);
 
     TimelineEntry {
         id: event.id,
@@ -1082,31 +4757,97 @@ fn summarize_event(event: &TimelineEvent) -> TimelineEntry {
         summary,
         age: format_elapsed(elapsed),
         color: event.color.clone(),
-        label: timeline_label,
+        label,
+        viewers: 0,
+        range: None,
+        request,
+        host: None,
+        origin: None,
+        format,
+        unread: false,
     }
 }
 
-fn primary_payload(event: &TimelineEvent) -> Option<&Payload> {
-    event
-        .request
-        .payloads
-        .iter()
-        .find(|payload| is_primary_payload_kind(&payload.kind))
-        .or_else(|| event.request.payloads.first())
+/// A memory figure reported by an event: a measure's peak memory keyed by
+/// timer name, or a numeric `size` keyed by the event's label.
+struct MemoryReading {
+    kind: PayloadKind,
+    label: String,
+    bytes: f64,
 }
 
-fn build_detail_view_for_event(event: &TimelineEvent) -> detail::DetailViewModel {
-    if let Some(merged) = aggregated_log_payload(event) {
-        return build_detail_view(&merged, event.received_at);
+/// The readings of `event`'s label in `earlier` events (newest first) and
+/// then its own, oldest first; empty unless the event repeats a reading.
+fn reading_history(event: &TimelineEvent, earlier: &[TimelineEvent]) -> Vec<f64> {
+    let Some(reading) = memory_reading(event) else {
+        return Vec::new();
+    };
+    let mut readings: Vec<f64> = earlier
+        .iter()
+        .filter_map(memory_reading)
+        .filter(|earlier| earlier.kind == reading.kind && earlier.label == reading.label)
+        .map(|earlier| earlier.bytes)
+        .collect();
+    if readings.is_empty() {
+        return readings;
     }
+    readings.reverse();
+    readings.push(reading.bytes);
+    readings
+}
 
-    if let Some(payload) = primary_payload(event) {
-        return build_detail_view(payload, event.received_at);
-    }
+fn memory_reading(event: &TimelineEvent) -> Option<MemoryReading> {
+    let payload = primary_payload(event)?;
+    let (label, bytes) = match payload.kind {
+        PayloadKind::Measure => (
+            payload
+                .content_string("name")
+                .unwrap_or("default")
+                .to_string(),
+            payload
+                .content_object()?
+                .get("max_memory_usage_during_total_time")?
+                .as_f64()?,
+        ),
+        PayloadKind::Size => (
+            event.label.clone().unwrap_or_else(|| "size".to_string()),
+            detail::size_bytes(payload)?,
+        ),
+        _ => return None,
+    };
+    Some(MemoryReading {
+        kind: payload.kind.clone(),
+        label,
+        bytes,
+    })
+}
+
+/// Detail view of the event's primary payload; `width` is the space available
+/// for width-sensitive content such as tables, when known.
+pub fn build_detail_view_for_event(
+    event: &TimelineEvent,
+    width: Option<usize>,
+    locale: LocaleConfig,
+) -> detail::DetailViewModel {
+    let label = event.label.as_deref();
+    let mut view = if let Some(merged) = aggregated_log_payload(event) {
+        build_labelled_detail_view(&merged, event.received_at, width, label, locale)
+    } else if let Some(payload) = primary_payload(event) {
+        build_labelled_detail_view(payload, event.received_at, width, label, locale)
+    } else {
+        empty_detail_view()
+    };
+    view.lines
+        .extend(detail::annotation_lines(&event.annotations));
+    view
+}
 
+fn empty_detail_view() -> detail::DetailViewModel {
     detail::DetailViewModel {
         header: "no payloads".to_string(),
         footer: String::new(),
+        origins: Default::default(),
+        focus_line: None,
         lines: vec![detail::DetailLine {
             indent: 0,
             segments: vec![detail::DetailSegment {
@@ -1117,402 +4858,460 @@ fn build_detail_view_for_event(event: &TimelineEvent) -> detail::DetailViewModel
     }
 }
 
-static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
-static HTML_SCRIPT_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(?is)<script[^>]*>.*?</script>").unwrap());
-static HTML_IMG_SRC_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r##"(?is)<img[^>]*src\s*=\s*['"]([^'"]+)['"]"##).unwrap());
+static DIGITS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+").unwrap());
 
-fn is_default_html_label(label: &str) -> bool {
-    label.trim().eq_ignore_ascii_case("html")
+/// Content schema of every payload in the event's request, in send order.
+fn schema_for_event(event: &TimelineEvent) -> Vec<DetailLine> {
+    let mut lines = Vec::new();
+    for (index, payload) in event.request.payloads.iter().enumerate() {
+        lines.push(DetailLine {
+            indent: 0,
+            segments: vec![
+                DetailSegment {
+                    text: format!("payloads[{}] ", index),
+                    style: SegmentStyle::Key,
+                },
+                DetailSegment {
+                    text: payload_kind_label(payload),
+                    style: SegmentStyle::String,
+                },
+            ],
+        });
+        lines.extend(schema::describe_schema("content", payload.content(), 1));
+    }
+    lines
 }
 
-fn looks_like_html_snippet(text: &str) -> bool {
-    let trimmed = text.trim();
-    trimmed.starts_with('<') && trimmed.contains('>')
+fn event_json(event: &TimelineEvent) -> String {
+    serde_json::to_string_pretty(&import::exported_request(event)).unwrap_or_default()
 }
 
-fn looks_like_json_snippet(text: &str) -> bool {
-    let trimmed = text.trim();
-    (trimmed.starts_with('{') && trimmed.ends_with('}'))
-        || (trimmed.starts_with('[') && trimmed.ends_with(']'))
+fn export_file_name(event: &TimelineEvent) -> String {
+    format!("raygun-event-{}.json", &event.id.simple().to_string()[..8])
 }
 
-fn contains_image_tag(html: &str) -> bool {
-    HTML_IMG_SRC_RE.is_match(html)
+/// POSTs the event's original Ray request to `url` as a Ray client would.
+async fn resend(url: &str, event: &TimelineEvent) -> reqwest::Result<reqwest::StatusCode> {
+    let client = reqwest::Client::builder()
+        .timeout(RESEND_TIMEOUT)
+        .user_agent(concat!("raygun/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let response = client.post(url).json(&*event.request).send().await?;
+    Ok(response.status())
 }
 
-fn extract_image_src(html: &str) -> Option<&str> {
-    HTML_IMG_SRC_RE
-        .captures(html)
-        .and_then(|capture| capture.get(1))
-        .map(|m| m.as_str())
+/// `<stem>-<date>-<time>.<extension>` in UTC, e.g. `raygun-20240309-140501.har`.
+pub(crate) fn timestamped_file_name(stem: &str, extension: &str, time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!(
+        "{}-{:04}{:02}{:02}-{:02}{:02}{:02}.{}",
+        stem,
+        year,
+        month,
+        day,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+        extension
+    )
 }
 
-fn strip_html_tags(text: &str) -> String {
-    let without_script = HTML_SCRIPT_RE.replace_all(text, "");
-    let stripped = HTML_TAG_RE.replace_all(&without_script, " ").into_owned();
-    flatten(stripped.trim())
+/// Addresses typed into the rebind prompt. A bare port (`23518` or
+/// `:23518`) keeps the hosts of `current`.
+fn parse_bind_addrs(input: &str, current: &[SocketAddr]) -> Result<Vec<SocketAddr>, String> {
+    let mut addrs = Vec::new();
+    for part in input
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        if let Ok(addr) = part.parse::<SocketAddr>() {
+            addrs.push(addr);
+        } else if let Ok(port) = part.trim_start_matches(':').parse::<u16>() {
+            for addr in current {
+                let moved = SocketAddr::new(addr.ip(), port);
+                if !addrs.contains(&moved) {
+                    addrs.push(moved);
+                }
+            }
+        } else {
+            return Err(format!(
+                "`{}` is not an address like 127.0.0.1:23518 or a port",
+                part
+            ));
+        }
+    }
+    if addrs.is_empty() {
+        return Err("Enter an address or a port".to_string());
+    }
+    Ok(addrs)
 }
 
-fn contains_sf_dump(text: &str) -> bool {
-    text.contains("sf-dump")
+/// Appends `entry` to the config file at `path` as a `[[<key>]]` table,
+/// creating the file if needed.
+fn append_table<T: serde::Serialize>(path: &Path, key: &str, entry: &T) -> io::Result<()> {
+    let table = toml::to_string(&BTreeMap::from([(key, std::slice::from_ref(entry))]))
+        .map_err(io::Error::other)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    write!(file, "\n{}", table)
 }
 
-fn aggregated_log_payload(event: &TimelineEvent) -> Option<Payload> {
-    use serde_json::Map;
-
-    let mut values: Vec<Value> = Vec::new();
-    let mut label: Option<String> = None;
-    let mut meta_snapshot: Option<Value> = None;
-    let mut origin_snapshot: Option<&Origin> = None;
-
-    for payload in &event.request.payloads {
-        match payload.kind {
-            PayloadKind::Log => {
-                if origin_snapshot.is_none() {
-                    origin_snapshot = payload.origin.as_ref();
-                }
-
-                if let Some(object) = payload.content_object() {
-                    if let Some(array) = object.get("values").and_then(|value| value.as_array()) {
-                        values.extend(array.iter().cloned());
-                    }
+/// The `noise` filter of an already validated config.
+fn noise_filter(noise: Option<&str>, hosts: Arc<HostAliases>) -> Filter {
+    noise
+        .and_then(|noise| Filter::parse(noise).ok())
+        .unwrap_or_else(|| Filter::parse(DEFAULT_NOISE).expect("the default noise filter parses"))
+        .with_host_aliases(hosts)
+}
 
-                    if label.is_none() {
-                        if let Some(found) = object
-                            .get("label")
-                            .and_then(|value| value.as_str())
-                            .map(|text| text.trim())
-                            .filter(|text| !text.is_empty())
-                        {
-                            if !is_default_html_label(found) {
-                                label = Some(found.to_string());
-                            }
-                        }
-                    }
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
 
-                    if meta_snapshot.is_none() {
-                        if let Some(meta) = object.get("meta") {
-                            meta_snapshot = Some(meta.clone());
-                        }
-                    }
-                }
-            }
-            PayloadKind::Label => {
-                if label.is_none() {
-                    label = payload
-                        .content_object()
-                        .and_then(|map| map.get("label"))
-                        .and_then(|value| value.as_str())
-                        .map(|text| text.trim().to_string())
-                        .filter(|text| !text.is_empty())
-                        .filter(|text| !is_default_html_label(text));
-                }
-            }
-            _ => {}
-        }
+/// One-line summary of a slice of the timeline, e.g.
+/// `14 events over 1.32s · 6 log, 4 query, 1 exception`.
+fn range_stats(events: &[TimelineEvent], locale: LocaleConfig) -> String {
+    let mut kinds: HashMap<String, usize> = HashMap::new();
+    for event in events {
+        *kinds.entry(summarize_event(event).kind).or_default() += 1;
     }
+    let mut kinds: Vec<(String, usize)> = kinds.into_iter().collect();
+    kinds.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let span = match (events.first(), events.last()) {
+        (Some(first), Some(last)) => last
+            .received_at
+            .duration_since(first.received_at)
+            .unwrap_or_default(),
+        _ => Duration::ZERO,
+    };
+    let span = if span < Duration::from_secs(1) {
+        format!("{}ms", span.as_millis())
+    } else if span < Duration::from_secs(60) {
+        format!("{}s", locale.decimal(span.as_secs_f64(), 2))
+    } else {
+        format!("{}m {:02}s", span.as_secs() / 60, span.as_secs() % 60)
+    };
+
+    format!(
+        "{} event{} over {} · {}",
+        events.len(),
+        plural(events.len()),
+        span,
+        kinds
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
 
-    if values.is_empty() && label.is_none() {
-        return None;
+/// File and line of the first payload that carries an origin.
+/// Exception events grouped by class and by message with its numbers masked,
+/// so `Order 41 not found` and `Order 42 not found` count together. Returns
+/// the newest event of each group alongside its row, most frequent first.
+fn exception_rows(events: &[TimelineEvent]) -> (Vec<Uuid>, Vec<ExceptionRow>) {
+    struct Group {
+        newest: Uuid,
+        message: String,
+        count: usize,
+        first: SystemTime,
+        last: SystemTime,
     }
 
-    if label.is_none() {
-        label = event
+    let mut groups: HashMap<(String, String), Group> = HashMap::new();
+    for event in events {
+        let Some(content) = event
             .request
             .payloads
             .iter()
-            .find_map(|payload| payload.content_string("label"))
-            .map(|text| text.trim().to_string())
-            .filter(|text| !text.is_empty())
-            .filter(|text| !is_default_html_label(text));
-    }
-
-    let mut content = Map::new();
-    content.insert("values".to_string(), Value::Array(values));
-    if let Some(label_value) = label.clone().filter(|label| !is_default_html_label(label)) {
-        content.insert("label".to_string(), Value::String(label_value));
-    }
-
-    if let Some(meta) = meta_snapshot {
-        content.insert("meta".to_string(), meta);
-    }
-
-    let mut root = Map::new();
-    root.insert("type".to_string(), Value::String("log".to_string()));
-    root.insert("content".to_string(), Value::Object(content));
-
-    if let Some(origin) = origin_snapshot {
-        let mut origin_map = Map::new();
-        if let Some(file) = &origin.file {
-            origin_map.insert("file".to_string(), Value::String(file.clone()));
-        }
-        if let Some(line) = origin.line_number {
-            origin_map.insert("line_number".to_string(), Value::Number(Number::from(line)));
-        }
-        if let Some(host) = &origin.hostname {
-            origin_map.insert("hostname".to_string(), Value::String(host.clone()));
-        }
-        root.insert("origin".to_string(), Value::Object(origin_map));
-    }
-
-    serde_json::from_value(Value::Object(root)).ok()
-}
-
-fn is_primary_payload_kind(kind: &PayloadKind) -> bool {
-    !matches!(kind, PayloadKind::Color | PayloadKind::Label)
-}
-
-fn payload_kind_label(payload: &Payload) -> String {
-    match &payload.kind {
-        PayloadKind::Log => "log".to_string(),
-        PayloadKind::Custom => custom_payload_type(payload).unwrap_or_else(|| "custom".to_string()),
-        PayloadKind::CreateLock => "create_lock".to_string(),
-        PayloadKind::ClearAll => "clear_all".to_string(),
-        PayloadKind::Hide => "hide".to_string(),
-        PayloadKind::ShowApp => "show_app".to_string(),
-        PayloadKind::ShowBrowser => "show_browser".to_string(),
-        PayloadKind::Notify => "notify".to_string(),
-        PayloadKind::Separator => "separator".to_string(),
-        PayloadKind::Exception => "exception".to_string(),
-        PayloadKind::Table => "table".to_string(),
-        PayloadKind::Text => "text".to_string(),
-        PayloadKind::Image => "image".to_string(),
-        PayloadKind::JsonString => "json_string".to_string(),
-        PayloadKind::DecodedJson => "decoded_json".to_string(),
-        PayloadKind::Boolean => "boolean".to_string(),
-        PayloadKind::Size => "size".to_string(),
-        PayloadKind::Color => "color".to_string(),
-        PayloadKind::Label => "label".to_string(),
-        PayloadKind::Trace => "trace".to_string(),
-        PayloadKind::Caller => "caller".to_string(),
-        PayloadKind::Measure => "measure".to_string(),
-        PayloadKind::PhpInfo => "phpinfo".to_string(),
-        PayloadKind::NewScreen => "new_screen".to_string(),
-        PayloadKind::Remove => "remove".to_string(),
-        PayloadKind::HideApp => "hide_app".to_string(),
-        PayloadKind::Ban => "ban".to_string(),
-        PayloadKind::Charles => "charles".to_string(),
-        PayloadKind::Unknown(value) => value.as_str().to_string(),
-    }
-}
-
-fn payload_summary(payload: &Payload) -> String {
-    match &payload.kind {
-        PayloadKind::Log => summarize_log(payload).unwrap_or_else(|| "log payload".to_string()),
-        PayloadKind::Custom => summarize_custom(payload),
-        PayloadKind::Boolean => {
-            let label = payload.content_string("label");
-            let body = payload
-                .content_object()
-                .and_then(|map| map.get("content"))
-                .map(value_preview)
-                .unwrap_or_else(|| "custom payload".to_string());
-
-            match label {
-                Some(label) if !label.is_empty() => clip(&format!("{}: {}", label, body), 80),
-                _ => clip(&body, 80),
-            }
-        }
-        PayloadKind::CreateLock => {
-            let name = payload.content_string("name").unwrap_or("unknown");
-            format!("create lock `{}`", name)
-        }
-        PayloadKind::ClearAll => "clear all".to_string(),
-        PayloadKind::Hide => "hide payload".to_string(),
-        PayloadKind::ShowApp => "show app".to_string(),
-        PayloadKind::ShowBrowser => "show browser".to_string(),
-        PayloadKind::Notify => payload
-            .content_string("text")
-            .map(|text| clip(text, 80))
-            .unwrap_or_else(|| "notification".to_string()),
-        PayloadKind::Separator => "separator".to_string(),
-        PayloadKind::Exception => payload
-            .content_object()
-            .and_then(|map| map.get("message"))
+            .find(|payload| payload.kind == PayloadKind::Exception)
+            .and_then(Payload::content_object)
+        else {
+            continue;
+        };
+        let class = content
+            .get("class")
+            .and_then(Value::as_str)
+            .unwrap_or("Exception")
+            .to_string();
+        let message = content
+            .get("message")
             .map(value_preview)
-            .unwrap_or_else(|| "exception".to_string()),
-        PayloadKind::Table => payload
-            .content_string("label")
-            .map(|text| clip(text, 80))
-            .unwrap_or_else(|| "table".to_string()),
-        PayloadKind::Text => payload
-            .content_string("content")
-            .map(|text| clip(text, 80))
-            .unwrap_or_else(|| "text".to_string()),
-        PayloadKind::Image => "image".to_string(),
-        PayloadKind::JsonString => "json string".to_string(),
-        PayloadKind::DecodedJson => payload
-            .content_object()
-            .map(|map| {
-                let json = Value::Object(map.clone()).to_string();
-                clip(&flatten(&json), 80)
+            .unwrap_or_default();
+        let fingerprint = DIGITS_RE.replace_all(&message, "#").into_owned();
+        // Events are oldest first, so later events are the newest.
+        groups
+            .entry((class, fingerprint))
+            .and_modify(|group| {
+                group.newest = event.id;
+                group.message = message.clone();
+                group.count += 1;
+                group.last = event.received_at;
             })
-            .unwrap_or_else(|| "json".to_string()),
-        PayloadKind::Size => payload
-            .content_string("size")
-            .map(|value| format!("size {}", value))
-            .unwrap_or_else(|| "size".to_string()),
-        PayloadKind::Color => payload
-            .content_string("color")
-            .map(|value| format!("color {}", value))
-            .unwrap_or_else(|| "color".to_string()),
-        PayloadKind::Label => payload
-            .content_string("label")
-            .map(|value| format!("label {}", value))
-            .unwrap_or_else(|| "label".to_string()),
-        PayloadKind::Trace => "stack trace".to_string(),
-        PayloadKind::Caller => "caller".to_string(),
-        PayloadKind::Measure => payload
-            .content_object()
-            .and_then(|map| map.get("name"))
-            .map(value_preview)
-            .map(|name| format!("measure {}", name))
-            .unwrap_or_else(|| "measure".to_string()),
-        PayloadKind::PhpInfo => "phpinfo".to_string(),
-        PayloadKind::NewScreen => payload
-            .content_string("name")
-            .map(|name| format!("new screen `{}`", name))
-            .unwrap_or_else(|| "new screen".to_string()),
-        PayloadKind::Remove => "remove".to_string(),
-        PayloadKind::HideApp => "hide app".to_string(),
-        PayloadKind::Ban => "ban".to_string(),
-        PayloadKind::Charles => "charles".to_string(),
-        PayloadKind::Unknown(name) => format!("{} payload", name),
+            .or_insert(Group {
+                newest: event.id,
+                message,
+                count: 1,
+                first: event.received_at,
+                last: event.received_at,
+            });
     }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|(a_key, a), (b_key, b)| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| b.last.cmp(&a.last))
+            .then_with(|| a_key.cmp(b_key))
+    });
+    groups
+        .into_iter()
+        .map(|((class, _), group)| {
+            let row = ExceptionRow {
+                class,
+                message: clip(&flatten(&group.message), SUMMARY_MAX_CHARS),
+                count: group.count,
+                first_seen: format_elapsed(group.first.elapsed().unwrap_or_default()),
+                last_seen: format_elapsed(group.last.elapsed().unwrap_or_default()),
+            };
+            (group.newest, row)
+        })
+        .unzip()
 }
 
-fn custom_payload_type(payload: &Payload) -> Option<String> {
-    let raw_label = payload
-        .content_string("label")
-        .map(|label| label.trim())
-        .filter(|label| !label.is_empty());
+fn event_origin(event: &TimelineEvent) -> Option<(String, Option<u32>)> {
+    event.request.payloads.iter().find_map(|payload| {
+        let origin = payload.origin.as_ref()?;
+        Some((origin.file.clone()?, origin.line_number))
+    })
+}
 
-    if let Some(label) = raw_label {
-        if label.eq_ignore_ascii_case("image") {
-            return Some("image".to_string());
-        }
-        if label.eq_ignore_ascii_case("json") {
-            return Some("json".to_string());
-        }
-        if is_default_html_label(label) {
-            return Some("html".to_string());
-        }
-        return Some(label.to_string());
+/// Commit checked out in the git repository at `root`.
+fn head_commit(root: &str) -> io::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["rev-parse", "HEAD"])
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(stderr.trim().to_string()));
     }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
-    if let Some(content) = payload
-        .content_object()
-        .and_then(|map| map.get("content"))
-        .and_then(|value| value.as_str())
-    {
-        if contains_image_tag(content) {
-            return Some("image".to_string());
+/// Opens `file` with the configured `editor` command, substituting `{file}`
+/// and `{line}`, or with the platform's default opener.
+fn open_in_editor(editor: Option<&str>, file: &str, line: Option<u32>) -> io::Result<()> {
+    let line = line.unwrap_or(1).to_string();
+    let mut command = match editor {
+        Some(template) => {
+            let mut parts = template
+                .split_whitespace()
+                .map(|part| part.replace("{file}", file).replace("{line}", &line));
+            let program = parts
+                .next()
+                .ok_or_else(|| io::Error::other("`editor` is empty"))?;
+            let mut command = Command::new(program);
+            command.args(parts);
+            if !template.contains("{file}") {
+                command.arg(file);
+            }
+            command
         }
-        if contains_sf_dump(content) {
-            return Some("json".to_string());
+        None if cfg!(target_os = "macos") => {
+            let mut command = Command::new("open");
+            command.arg(file);
+            command
         }
-        if looks_like_html_snippet(content) {
-            return Some("html".to_string());
+        None if cfg!(windows) => {
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", "", file]);
+            command
         }
-        if looks_like_json_snippet(content) {
-            return Some("json".to_string());
+        None => {
+            let mut command = Command::new("xdg-open");
+            command.arg(file);
+            command
         }
-    }
+    };
 
-    None
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the process without blocking the UI.
+    std::thread::spawn(move || child.wait());
+    Ok(())
 }
 
-fn summarize_custom(payload: &Payload) -> String {
-    let type_hint = custom_payload_type(payload);
+/// `GET /cart 200` when the event reports a handled HTTP request: a table
+/// labelled `Request` with the request's method and URI, as Laravel sends
+/// after each request with `ray()->showRequests()`.
+fn handled_request(event: &TimelineEvent) -> Option<String> {
+    event.request.payloads.iter().find_map(|payload| {
+        if payload.kind != PayloadKind::Table
+            || !payload
+                .content_string("label")
+                .is_some_and(|label| label.trim().eq_ignore_ascii_case("request"))
+        {
+            return None;
+        }
+        let values = payload.content_object()?.get("values")?.as_object()?;
+        let field = |names: &[&str]| {
+            values
+                .iter()
+                .find(|(key, _)| names.iter().any(|name| key.eq_ignore_ascii_case(name)))
+                .and_then(|(_, value)| match value {
+                    Value::String(text) => Some(text.trim().to_string()),
+                    Value::Number(number) => Some(number.to_string()),
+                    _ => None,
+                })
+                .filter(|text| !text.is_empty())
+        };
 
-    let content_value = payload.content_object().and_then(|map| map.get("content"));
+        let method = field(&["method"])?;
+        let mut line = format!(
+            "{} {}",
+            method.to_ascii_uppercase(),
+            field(&["uri", "url", "path"])?
+        );
+        if let Some(status) = field(&["status", "status_code"]) {
+            line.push(' ');
+            line.push_str(&status);
+        }
+        Some(line)
+    })
+}
 
-    if type_hint.as_deref() == Some("image") {
-        let src = content_value
-            .and_then(|value| value.as_str())
-            .and_then(extract_image_src)
-            .or_else(|| content_value.and_then(|value| value.as_str()))
-            .unwrap_or("image payload");
-        return clip(&format!("image: {}", src), 80);
+/// Where and when an event arrived, shown above the raw payload in the debug
+/// overlay.
+fn provenance_header(event: &TimelineEvent, locale: LocaleConfig) -> String {
+    let mut lines = vec![
+        format!(
+            "received: {}",
+            format_timestamp_micros(event.received_at, locale)
+        ),
+        format!(
+            "from:     {}",
+            event.remote_addr.map_or_else(
+                || "- (not received over HTTP)".to_string(),
+                // Forwarded client addresses usually have no port.
+                |addr| match addr.port() {
+                    0 => addr.ip().to_string(),
+                    _ => addr.to_string(),
+                }
+            )
+        ),
+    ];
+    if let Some(proxy) = event.proxy_addr {
+        lines.push(format!("via:      {}", proxy));
     }
-
-    if type_hint.as_deref() == Some("json") {
-        return payload
-            .content_string("label")
-            .map(|label| clip(label, 80))
-            .unwrap_or_else(|| "json payload".to_string());
+    if let Some(latency) = event.latency_micros() {
+        let mut line = format!(
+            "latency:  {}{}.{:03} ms",
+            if latency < 0 { "-" } else { "" },
+            latency.unsigned_abs() / 1_000,
+            latency.unsigned_abs() % 1_000
+        );
+        if latency < 0 {
+            line.push_str(" (client clock is ahead)");
+        }
+        lines.push(line);
     }
-
-    let body = content_value
-        .map(|value| match (value, type_hint.as_deref()) {
-            (Value::String(text), Some("html")) => strip_html_tags(text),
-            (other, _) => value_preview(other),
-        })
-        .unwrap_or_else(|| "custom payload".to_string());
-
-    match type_hint.as_deref() {
-        Some("html") => clip(&body, 80),
-        Some(other) => clip(&format!("{}: {}", other, body), 80),
-        None => clip(&body, 80),
+    for (index, (name, value)) in event.headers.iter().enumerate() {
+        let label = if index == 0 { "headers:" } else { "" };
+        lines.push(format!("{:<10}{}: {}", label, name, value));
     }
+    lines.join("\n")
 }
 
-fn summarize_log(payload: &Payload) -> Option<String> {
-    let meta_clipboard = payload
-        .content_object()
-        .and_then(|map| map.get("meta"))
-        .and_then(|meta| meta.as_array())
-        .and_then(|meta| meta.first())
-        .and_then(|entry| entry.get("clipboard_data"))
-        .and_then(|value| value.as_str())
-        .map(flatten);
-
-    if let Some(clipboard) = meta_clipboard {
-        if !clipboard.is_empty() {
-            return Some(clip(&clipboard, 80));
-        }
-    }
+/// UTC timestamp with microseconds, e.g. `2024-03-09 14:05:01.250000 UTC`.
+fn format_timestamp_micros(time: SystemTime, locale: LocaleConfig) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let micros = format!("{:06}", since_epoch.subsec_micros());
+    format!(
+        "{:04}-{:02}-{:02} {} UTC",
+        year,
+        month,
+        day,
+        locale.time_of_day(secs, Some(&micros))
+    )
+}
 
-    payload
-        .content_object()
-        .and_then(|map| map.get("values"))
-        .and_then(|values| values.as_array())
-        .and_then(|values| {
-            let mut previews: Vec<String> = values.iter().map(value_preview).collect();
-            previews.retain(|value| !value.is_empty());
-            if previews.is_empty() {
-                None
-            } else {
-                let joined = previews.join(" | ");
-                Some(clip(&joined, 80))
-            }
-        })
+/// Time one frame may take to keep the UI at 60 FPS.
+const FRAME_BUDGET: Duration = Duration::from_millis(16);
+
+/// How long the parts of the last frame took, for the profiling panel.
+#[derive(Debug, Default, Clone, Copy)]
+struct FrameTimes {
+    /// Building the view model, including `parse`.
+    build: Duration,
+    /// Laying out and drawing the frame.
+    render: Duration,
+    /// Building the detail of the selected event; `None` without one.
+    parse: Option<Duration>,
 }
 
-fn value_preview(value: &Value) -> String {
-    match value {
-        Value::String(text) => clip(&flatten(text), 80),
-        Value::Bool(boolean) => boolean.to_string(),
-        Value::Number(number) => number.to_string(),
-        Value::Null => "null".to_string(),
-        Value::Array(_) | Value::Object(_) => clip(&flatten(&value.to_string()), 80),
+impl FrameTimes {
+    fn view(&self, locale: LocaleConfig) -> ProfileView {
+        let row = |name, time: Duration| ProfileRow {
+            name,
+            time: format_delay(time, locale),
+            over_budget: time > FRAME_BUDGET,
+        };
+        let mut rows = vec![row("view model", self.build), row("render", self.render)];
+        if let Some(parse) = self.parse {
+            rows.push(row("selected payload", parse));
+        }
+        rows.push(row("total", self.build + self.render));
+        ProfileView {
+            budget: format!("{} ms", FRAME_BUDGET.as_millis()),
+            rows,
+        }
     }
 }
 
-fn clip(text: &str, max_chars: usize) -> String {
-    if text.chars().count() <= max_chars {
-        return text.to_string();
-    }
+fn format_delay(delay: Duration, locale: LocaleConfig) -> String {
+    format!("{} ms", locale.decimal(delay.as_secs_f64() * 1_000.0, 1))
+}
 
-    let truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
-    format!("{}…", truncated)
+/// UTC time of day, e.g. `14:05:01`.
+fn format_clock(time: SystemTime, locale: LocaleConfig) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    locale.time_of_day(secs, None)
 }
 
-fn flatten(text: &str) -> String {
-    let decoded = decode_html_entities(text).into_owned();
-    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+/// Converts days since 1970-01-01 to a (year, month, day) date in the
+/// proleptic Gregorian calendar (Howard Hinnant's algorithm).
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
 
 fn format_elapsed(duration: Duration) -> String {
@@ -1531,3 +5330,162 @@ fn format_elapsed(duration: Duration) -> String {
         format!("{}h {:02}m ago", hours, minutes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use crossterm::event::KeyEvent;
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn exception_request(message: &str) -> RayRequest {
+        serde_json::from_value(json!({
+            "uuid": message,
+            "payloads": [{
+                "type": "exception",
+                "content": {
+                    "class": "RuntimeException",
+                    "message": message,
+                    "frames": [],
+                    "meta": { "php": "8.3" }
+                }
+            }],
+            "meta": {}
+        }))
+        .expect("request should deserialize")
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Input(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[tokio::test]
+    async fn held_arrow_keys_skip_the_events_they_pass_over() {
+        let state = Arc::new(AppState::default());
+        for message in ["one", "two", "three", "four"] {
+            state.record_request(exception_request(message)).await;
+        }
+        let file_config = config::FileConfig {
+            collapse: BTreeMap::from([("exception".to_string(), vec!["meta".to_string()])]),
+            ..Default::default()
+        };
+        let config = Config::parse_from(["raygun"]);
+        let mut app = RaygunApp::new(&config, file_config, state, Vec::new());
+
+        let view_model = app.build_view_model().await;
+        let first = app.current_event_id().expect("an event is selected");
+        let collapsed = view_model
+            .detail_state
+            .as_ref()
+            .map(|state| state.collapsed.clone());
+        assert!(collapsed.as_ref().is_some_and(|set| !set.is_empty()));
+        let context = DetailContext::new(view_model.detail.as_deref(), collapsed.as_ref());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for _ in 0..2 {
+            tx.send(key(KeyCode::Down)).unwrap();
+        }
+        tx.send(key(KeyCode::Tab)).unwrap();
+        let mut queued = None;
+        let exit = app.handle_input(
+            key(KeyCode::Down),
+            &mut rx,
+            &mut queued,
+            view_model.timeline.len(),
+            &context,
+        );
+        assert!(!exit);
+        assert!(matches!(
+            queued,
+            Some(Event::Input(KeyEvent {
+                code: KeyCode::Tab,
+                ..
+            }))
+        ));
+        assert_eq!(app.selected, Some(3));
+        let saved: Vec<_> = app.detail_states.keys().copied().collect();
+        assert_eq!(saved, vec![first]);
+
+        // An event passed over keeps its collapse rules when it is shown.
+        app.selected = Some(1);
+        let view_model = app.build_view_model().await;
+        let state = view_model.detail_state.expect("detail state");
+        assert!(!state.collapsed.is_empty());
+
+        // Each key stops at the ends by itself: Up, Up, Down from the top
+        // lands on the second row, not back on the first.
+        let len = view_model.timeline.len();
+        for (start, codes, end) in [
+            (0, [KeyCode::Up, KeyCode::Up, KeyCode::Down], 1),
+            (
+                len - 1,
+                [KeyCode::Down, KeyCode::Down, KeyCode::Up],
+                len - 2,
+            ),
+        ] {
+            app.selected = Some(start);
+            for code in &codes[1..] {
+                tx.send(key(*code)).unwrap();
+            }
+            app.handle_input(key(codes[0]), &mut rx, &mut queued, len, &context);
+            assert_eq!(app.selected, Some(end));
+        }
+    }
+
+    #[tokio::test]
+    async fn details_are_rebuilt_only_when_their_key_changes() {
+        let state = Arc::new(AppState::default());
+        state.record_request(exception_request("boom")).await;
+        let config = Config::parse_from(["raygun"]);
+        let mut app = RaygunApp::new(&config, Default::default(), state.clone(), Vec::new());
+        app.last_render = Some(AppRenderMetadata {
+            timeline_inner: Rect::default(),
+            timeline_rows: Vec::new(),
+            detail_inner: Rect::new(0, 0, 40, 20),
+            overlay: None,
+        });
+
+        let rows = |view_model: &AppViewModel| view_model.detail_rows.clone().expect("detail rows");
+        let detail = |view_model: &AppViewModel| view_model.detail.clone().expect("a detail");
+        let view_model = app.build_view_model().await;
+        let (first, first_detail) = (rows(&view_model), detail(&view_model));
+        let view_model = app.build_view_model().await;
+        let again = rows(&view_model);
+        assert!(Arc::ptr_eq(&first, &again));
+        assert!(Arc::ptr_eq(&first_detail, &detail(&view_model)));
+
+        // A reload may change the locale or renderers the detail was built with.
+        app.settings_generation += 1;
+        let view_model = app.build_view_model().await;
+        assert!(!Arc::ptr_eq(&first_detail, &detail(&view_model)));
+        let again = rows(&view_model);
+        assert!(!Arc::ptr_eq(&first, &again));
+
+        let event = app.current_event_id().expect("an event is selected");
+        state.set_label(event, Some("checkout".to_string())).await;
+        let relabeled = rows(&app.build_view_model().await);
+        assert!(!Arc::ptr_eq(&again, &relabeled));
+
+        app.current_detail_state_mut()
+            .expect("detail state")
+            .collapsed_changed();
+        let toggled = rows(&app.build_view_model().await);
+        assert!(!Arc::ptr_eq(&relabeled, &toggled));
+        assert!(Arc::ptr_eq(&toggled, &rows(&app.build_view_model().await)));
+    }
+
+    #[test]
+    fn overlay_scroll_stops_at_the_top() {
+        let mut scroll = 3;
+        scroll_overlay(&mut scroll, KeyCode::PageDown, OVERLAY_PAGE);
+        assert_eq!(scroll, 13);
+        scroll_overlay(&mut scroll, KeyCode::Char('x'), OVERLAY_PAGE);
+        assert_eq!(scroll, 13);
+        scroll_overlay(&mut scroll, KeyCode::Up, OVERLAY_PAGE);
+        scroll_overlay(&mut scroll, KeyCode::PageUp, OVERLAY_PAGE);
+        scroll_overlay(&mut scroll, KeyCode::PageUp, OVERLAY_PAGE);
+        assert_eq!(scroll, 0);
+    }
+}
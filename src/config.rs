@@ -1,30 +1,1523 @@
-use std::{net::SocketAddr, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    filter::Filter,
+    keymap::Keymap,
+    protocol::PayloadKind,
+    state::{rules::Rule, store::StoreSpec},
+    ui::color,
+};
+
+/// Default ceiling for a single `POST /` body (32 MiB).
+pub const DEFAULT_MAX_BODY_SIZE: usize = 32 * 1024 * 1024;
+
+/// Default time given to in-flight requests and pending writes on quit.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Clone, Parser)]
 pub struct Config {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Print the current Raygun version and exit.
     #[arg(short = 'v', long = "version", help = "Print Raygun version and exit")]
     pub show_version: bool,
 
-    /// Address Raygun listens on for Ray payloads.
+    /// Addresses Raygun listens on for Ray payloads. Repeat `--bind` (or pass a
+    /// comma separated `RAYGUN_BIND`) to listen on several interfaces at once.
     #[arg(
         long = "bind",
         alias = "bind-addr",
         env = "RAYGUN_BIND",
+        global = true,
         value_name = "ADDR",
+        value_delimiter = ',',
         default_value = "0.0.0.0:23517",
-        help = "Bind address for incoming Ray HTTP requests"
+        help = "Bind address for incoming Ray HTTP requests (repeatable)"
     )]
-    pub bind_addr: SocketAddr,
+    pub bind_addrs: Vec<SocketAddr>,
 
     /// Optional file path to dump raw Ray payloads for debugging.
     #[arg(
         long = "debug-dump",
         env = "RAYGUN_DEBUG_DUMP",
+        global = true,
         value_name = "FILE",
         help = "Append each incoming payload to FILE for offline inspection"
     )]
     pub debug_dump: Option<PathBuf>,
+
+    /// Bind the IPv4 and IPv6 variants of each wildcard/loopback address.
+    #[arg(
+        long = "dual-stack",
+        env = "RAYGUN_DUAL_STACK",
+        global = true,
+        help = "Listen on both IPv4 and IPv6 (e.g. 0.0.0.0 and [::]) for each bind address"
+    )]
+    pub dual_stack: bool,
+
+    /// Externally reachable URL (e.g. an ngrok tunnel) shown in the UI.
+    #[arg(
+        long = "public-url",
+        env = "RAYGUN_PUBLIC_URL",
+        global = true,
+        value_name = "URL",
+        help = "URL clients should target when Raygun runs behind a tunnel or proxy; without it the URL is only detected from requests of a --trusted-proxy"
+    )]
+    pub public_url: Option<String>,
+
+    /// Reverse proxies and tunnel agents whose forwarding headers are
+    /// believed: the public URL and, from `X-Forwarded-For`/`Forwarded`, the
+    /// real client.
+    #[arg(
+        long = "trusted-proxy",
+        env = "RAYGUN_TRUSTED_PROXIES",
+        global = true,
+        value_name = "IP[/PREFIX]",
+        value_delimiter = ',',
+        value_parser = parse_ip_network,
+        help = "Read the public URL and client address from forwarded headers of requests from this proxy or network (repeatable)"
+    )]
+    pub trusted_proxies: Vec<IpNet>,
+
+    /// Largest request body accepted on `POST /` before it is rejected.
+    #[arg(
+        long = "max-body-size",
+        env = "RAYGUN_MAX_BODY_SIZE",
+        global = true,
+        value_name = "SIZE",
+        default_value = "32MB",
+        value_parser = parse_byte_size,
+        help = "Reject payloads larger than SIZE (e.g. 512KB, 32MB, 1GB)"
+    )]
+    pub max_body_size: usize,
+
+    /// How long quitting waits for in-flight requests and pending writes.
+    #[arg(
+        long = "drain-timeout",
+        env = "RAYGUN_DRAIN_TIMEOUT",
+        global = true,
+        value_name = "SECS",
+        default_value_t = DEFAULT_DRAIN_TIMEOUT.as_secs(),
+        help = "Seconds to let in-flight requests finish and --debug-dump catch up on quit"
+    )]
+    pub drain_timeout_secs: u64,
+
+    /// Directory to collect one example request per payload type into.
+    #[arg(
+        long = "capture-fixtures",
+        env = "RAYGUN_CAPTURE_FIXTURES",
+        global = true,
+        value_name = "DIR",
+        help = "Write the first request seen for each payload type to DIR/<type>.json"
+    )]
+    pub capture_fixtures: Option<PathBuf>,
+
+    /// Validate every request against the Ray protocol.
+    #[arg(
+        long = "strict-protocol",
+        env = "RAYGUN_STRICT_PROTOCOL",
+        global = true,
+        help = "Reject malformed payloads and list protocol issues in a diagnostics panel"
+    )]
+    pub strict_protocol: bool,
+
+    /// Ray server every payload is passed on to, e.g. the Ray desktop app.
+    #[arg(
+        long = "upstream",
+        env = "RAYGUN_UPSTREAM",
+        global = true,
+        value_name = "URL",
+        value_parser = parse_upstream_url,
+        help = "Also forward every payload to the Ray app at URL and answer clients with its responses"
+    )]
+    pub upstream: Option<String>,
+
+    /// Draw the UI with ASCII characters only.
+    #[arg(
+        long = "ascii",
+        env = "RAYGUN_ASCII",
+        global = true,
+        help = "Replace box-drawing, bullet and ellipsis characters with ASCII"
+    )]
+    pub ascii: bool,
+
+    /// Print events as plain lines instead of drawing the UI.
+    #[arg(
+        long = "plain",
+        env = "RAYGUN_PLAIN",
+        global = true,
+        help = "Print new events as plain text lines for screen readers and captured sessions"
+    )]
+    pub plain: bool,
+
+    /// Seed the timeline with sample payloads on startup.
+    #[arg(
+        long = "demo",
+        env = "RAYGUN_DEMO",
+        global = true,
+        help = "Start with a set of sample payloads to explore the UI"
+    )]
+    pub demo: bool,
+
+    /// Captures loaded into the timeline on startup.
+    #[arg(
+        long = "import",
+        env = "RAYGUN_IMPORT",
+        global = true,
+        value_name = "FILE",
+        value_delimiter = ',',
+        help = "Load Ray requests saved as JSON or JSON Lines from FILE on startup (repeatable)"
+    )]
+    pub imports: Vec<PathBuf>,
+
+    /// Read requests piped into stdin.
+    #[arg(
+        long = "stdin",
+        env = "RAYGUN_STDIN",
+        global = true,
+        help = "Add Ray requests piped into stdin (JSON or JSON Lines) to the timeline as they arrive"
+    )]
+    pub stdin: bool,
+
+    /// TOML file with theme colors, key bindings and path mappings.
+    #[arg(
+        long = "config",
+        env = "RAYGUN_CONFIG",
+        value_name = "FILE",
+        global = true,
+        help = "Read settings from FILE instead of the default config location"
+    )]
+    pub config_file: Option<PathBuf>,
+
+    /// Control socket shared by `raygun daemon` and `raygun attach`.
+    #[arg(
+        long = "socket",
+        env = "RAYGUN_SOCKET",
+        value_name = "PATH",
+        global = true,
+        help = "Control socket of the daemon (defaults to a per-user path)"
+    )]
+    pub socket: Option<PathBuf>,
+
+    /// Backend persisting every event beyond the in-memory timeline.
+    #[arg(
+        long = "store",
+        env = "RAYGUN_STORE",
+        value_name = "BACKEND",
+        global = true,
+        value_parser = parse_store_spec,
+        help = "Keep every event for later search: sqlite:<path>, spill:<path> or memory"
+    )]
+    pub store: Option<StoreSpec>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Validate the config file and report problems without starting the UI.
+    Check,
+    /// Bind, send a test payload over loopback and report whether Ray clients can reach Raygun.
+    Selftest,
+    /// Collect payloads in the background without a UI; attach with `raygun attach`.
+    Daemon,
+    /// Open the UI on the timeline of a running `raygun daemon`.
+    Attach {
+        /// Follow filter changes of other clients that share theirs, and share yours.
+        #[arg(
+            long = "share-filters",
+            env = "RAYGUN_SHARE_FILTERS",
+            help = "Keep the color filter and the timeline filter in sync with other attached clients"
+        )]
+        share_filters: bool,
+    },
+}
+
+impl Config {
+    /// Explicit `--config` path, falling back to the per-user default location.
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.config_file.clone().or_else(default_config_path)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        Duration::from_secs(self.drain_timeout_secs)
+    }
+
+    /// Explicit `--socket` path, falling back to the per-user default.
+    pub fn socket_path(&self) -> PathBuf {
+        self.socket.clone().unwrap_or_else(default_socket_path)
+    }
+
+    /// Whether a config file exists at the explicit or default location.
+    pub fn config_file_exists(&self) -> bool {
+        self.config_path().is_some_and(|path| path.exists())
+    }
+
+    /// Loads the config file. A missing file at the default location is not an
+    /// error; a missing file passed via `--config` is.
+    pub fn load_file_config(&self) -> Result<FileConfig, ConfigError> {
+        match &self.config_file {
+            Some(path) => FileConfig::load(path),
+            None => match default_config_path() {
+                Some(path) if path.exists() => FileConfig::load(&path),
+                _ => Ok(FileConfig::default()),
+            },
+        }
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+
+    base.map(|dir| dir.join("raygun").join("config.toml"))
+}
+
+fn default_socket_path() -> PathBuf {
+    if cfg!(windows) {
+        return PathBuf::from(r"\\.\pipe\raygun");
+    }
+
+    match env::var_os("XDG_RUNTIME_DIR").filter(|value| !value.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("raygun.sock"),
+        // The daemon creates the directory private to the user.
+        None => {
+            let user = env::var("USER").unwrap_or_else(|_| "default".into());
+            env::temp_dir()
+                .join(format!("raygun-{}", user))
+                .join("raygun.sock")
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse config file {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+}
+
+/// Settings read from `config.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub theme: ThemeConfig,
+    pub keys: BTreeMap<String, KeyBinding>,
+    pub path_mappings: Vec<PathMapping>,
+    /// Command used to open payload origins, e.g. `code --goto {file}:{line}`.
+    pub editor: Option<String>,
+    /// Where the "Re-send payload" event action POSTs an event's original
+    /// request, e.g. another Raygun or a mock endpoint.
+    pub resend_url: Option<String>,
+    /// Sections collapsed when an event is first shown, per payload kind, e.g.
+    /// `exception = ["vendor_frames", "meta"]`.
+    pub collapse: BTreeMap<String, Vec<String>>,
+    /// Endpoints that receive matching events as they arrive.
+    pub webhooks: Vec<WebhookConfig>,
+    /// Exports events to an OpenTelemetry collector when set.
+    pub otlp: Option<OtlpConfig>,
+    /// Writes the timeline to rotating JSON Lines files when set.
+    pub archive: Option<ArchiveConfig>,
+    /// Repository that payload origins are turned into permalinks for.
+    pub permalink: Option<PermalinkConfig>,
+    /// Saved filters; the first nine are applied with the number keys.
+    pub views: Vec<ViewConfig>,
+    /// Filter expression for the events `hide_noise` hides; defaults to
+    /// [`DEFAULT_NOISE`].
+    pub noise: Option<String>,
+    /// Friendly names for origin hostnames, e.g. `"3f2a9c1b7d4e" = "api"`.
+    pub hosts: BTreeMap<String, String>,
+    /// Renderers for payload types Raygun doesn't know; later entries win.
+    pub unknown_kinds: Vec<UnknownKindConfig>,
+    /// Colors and labels given to incoming events that match.
+    pub rules: Vec<RuleConfig>,
+    /// Screens incoming events that match are sent to.
+    pub routes: Vec<RouteConfig>,
+    pub retention: RetentionConfig,
+    pub locale: LocaleConfig,
+    pub terminal: TerminalConfig,
+    /// Sound made when an event arrives, per payload type or timeline kind;
+    /// `*` covers kinds without an entry.
+    pub sounds: BTreeMap<String, SoundConfig>,
+}
+
+/// A `[sounds]` entry: `exception = 2` rings the terminal bell twice, `log = 0`
+/// keeps logs quiet and `notify = { command = "afplay Glass.aiff" }` runs a
+/// command instead.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum SoundConfig {
+    Bells(u8),
+    Command { command: String },
+}
+
+/// What Raygun tells the terminal emulator outside its own window contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerminalConfig {
+    /// Show the number of new events in the window or tab title (OSC 2).
+    pub title: bool,
+    /// Pulse the tab's progress indicator as events arrive (OSC 9;4, as
+    /// understood by Windows Terminal, ConEmu and some others).
+    pub progress: bool,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            title: true,
+            progress: false,
+        }
+    }
+}
+
+/// How numbers and times of day are written in the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocaleConfig {
+    /// Separates the decimals of durations and sizes, e.g. `,` for `1,50 MB`.
+    pub decimal_separator: char,
+    pub clock: ClockFormat,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            clock: ClockFormat::TwentyFourHour,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum ClockFormat {
+    /// `14:05:01`
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// `02:05:01 PM`
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+/// How the timeline makes room for new events.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Keep exceptions and failed jobs while older events of other kinds can
+    /// be evicted instead.
+    pub pin_errors: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self { pin_errors: true }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// Base palette for Ray colors, before `colors` overrides.
+    pub palette: Palette,
+    /// Overrides for Ray color names, e.g. `red = "#ff5555"`.
+    pub colors: BTreeMap<String, String>,
+    /// Built-in glyphs marking each timeline entry's kind.
+    pub glyphs: GlyphSet,
+    /// Overrides for the glyph of a timeline kind, e.g. `query = "Q"`.
+    pub kind_glyphs: BTreeMap<String, String>,
+}
+
+/// Built-in sets of per-kind timeline glyphs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GlyphSet {
+    #[default]
+    Unicode,
+    /// Icons from a patched Nerd Font.
+    NerdFont,
+    Ascii,
+    /// A plain bullet for every kind.
+    None,
+}
+
+/// Built-in palettes. The accessible ones also mark timeline entries with a
+/// per-color symbol so color is never the only cue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    #[default]
+    Default,
+    HighContrast,
+    /// Okabe-Ito colors, distinguishable with the common color vision deficiencies.
+    Colorblind,
+}
+
+/// One key (`quit = "x"`) or several (`quit = ["x", "ctrl+q"]`) for an action.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    pub fn keys(&self) -> &[String] {
+        match self {
+            KeyBinding::One(key) => std::slice::from_ref(key),
+            KeyBinding::Many(keys) => keys,
+        }
+    }
+}
+
+/// Rewrites file paths reported by a remote host (a container or VM) to the
+/// matching local checkout.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PathMapping {
+    pub remote: String,
+    pub local: String,
+}
+
+impl PathMapping {
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let remote = self.remote.trim_end_matches('/');
+        let rest = path.strip_prefix(remote)?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        Some(format!("{}{}", self.local.trim_end_matches('/'), rest))
+    }
+}
+
+/// A webhook rule: events matching `filter` are POSTed to `url`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// A filter expression such as `kind:exception -color:gray`; empty matches
+    /// every event.
+    #[serde(default)]
+    pub filter: String,
+    /// Body format; Slack URLs default to `slack`, everything else to `json`.
+    #[serde(default)]
+    pub format: Option<WebhookFormat>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebhookFormat {
+    /// The event summary plus the original Ray request.
+    Json,
+    /// A Slack incoming-webhook message.
+    Slack,
+}
+
+/// OTLP/HTTP export of timeline events as log records and spans.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OtlpConfig {
+    /// Collector base URL; `/v1/logs` and `/v1/traces` are appended.
+    pub endpoint: String,
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+    /// Extra request headers, e.g. an API key for a hosted collector.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn default_otlp_service_name() -> String {
+    "raygun".to_string()
+}
+
+/// Automatic export of the timeline to timestamped JSON Lines files that
+/// `--import` reads back.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// Directory the files are written to; defaults to the working directory.
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    /// Minutes between archives of the events received since the last one.
+    #[serde(default)]
+    pub every_minutes: Option<u64>,
+    /// Archive as soon as retention evicts, or a clear drops, an event that
+    /// has not been archived yet.
+    #[serde(default = "default_archive_on_evict")]
+    pub on_evict: bool,
+    /// Archive files kept in `dir`; the oldest are deleted beyond this.
+    #[serde(default)]
+    pub keep: Option<usize>,
+}
+
+fn default_archive_on_evict() -> bool {
+    true
+}
+
+/// Number keys that apply a saved view.
+pub const VIEW_SLOTS: usize = 9;
+
+/// Events hidden by `hide_noise` unless `noise` says otherwise.
+pub const DEFAULT_NOISE: &str = "kind:log OR kind:separator OR kind:caller";
+
+/// A named filter expression, applied with the number key of its position.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ViewConfig {
+    pub name: String,
+    /// Filter expression; empty shows everything.
+    #[serde(default)]
+    pub filter: String,
+}
+
+/// A generic renderer for a payload type Raygun doesn't know, assigned from
+/// the unknown payload types overlay.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnknownKindConfig {
+    pub kind: String,
+    pub renderer: UnknownRenderer,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnknownRenderer {
+    /// The content as pretty-printed JSON, as unknown types are drawn by
+    /// default.
+    #[default]
+    Json,
+    /// The content string, or its first `content`, `text`, `value` or
+    /// `message` field.
+    Text,
+    /// An array of objects as columns, or an object as key/value rows.
+    Table,
+}
+
+impl UnknownRenderer {
+    pub fn name(self) -> &'static str {
+        match self {
+            UnknownRenderer::Json => "json",
+            UnknownRenderer::Text => "text",
+            UnknownRenderer::Table => "table",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            UnknownRenderer::Json => UnknownRenderer::Text,
+            UnknownRenderer::Text => UnknownRenderer::Table,
+            UnknownRenderer::Table => UnknownRenderer::Json,
+        }
+    }
+}
+
+/// A classification rule: events with a payload matching every matcher set
+/// get `color` and/or `label`, unless the client sent its own.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleConfig {
+    /// Payload type, e.g. `exception`.
+    pub kind: Option<String>,
+    /// Glob over the origin file, e.g. `app/Payments/**`; relative globs may
+    /// match anywhere after a `/`.
+    pub origin: Option<String>,
+    /// Regex searched for in the payload content as JSON.
+    pub content: Option<String>,
+    pub color: Option<String>,
+    pub label: Option<String>,
+}
+
+/// A screen route: events matching `filter` land on `screen` unless their
+/// request names a screen itself.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteConfig {
+    pub screen: String,
+    /// Filter expression such as `host:worker` or `project:shop`.
+    pub filter: String,
+}
+
+/// A local checkout and the hosted repository its permalinks point to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermalinkConfig {
+    /// Checkout root; origins are made relative to it after `path_mappings`.
+    pub root: String,
+    /// Repository URL or git remote, e.g. `https://github.com/org/repo` or
+    /// `git@gitlab.com:org/repo.git`.
+    pub remote: String,
+    /// Commit, tag or branch to link to; defaults to the checkout's `HEAD`.
+    #[serde(default)]
+    pub revision: Option<String>,
+}
+
+impl PermalinkConfig {
+    /// Web URL of the repository, accepting https URLs and ssh remotes.
+    pub fn web_url(&self) -> Option<String> {
+        let remote = self.remote.trim().trim_end_matches('/');
+        let remote = remote.strip_suffix(".git").unwrap_or(remote);
+        if remote.starts_with("https://") || remote.starts_with("http://") {
+            return Some(remote.to_string());
+        }
+
+        let (host, path) = match remote.strip_prefix("ssh://") {
+            Some(rest) => {
+                let (authority, path) = rest.split_once('/')?;
+                let host = authority.rsplit('@').next()?;
+                (host.split(':').next()?, path)
+            }
+            None => {
+                let (user_host, path) = remote.split_once(':')?;
+                (user_host.rsplit('@').next()?, path)
+            }
+        };
+        (!host.is_empty() && !path.is_empty())
+            .then(|| format!("https://{}/{}", host, path.trim_start_matches('/')))
+    }
+
+    /// Link to `line` of the local file `path` at `revision`, in the URL
+    /// scheme of the hosting service (GitHub's unless the host looks like
+    /// GitLab or Bitbucket). `None` when `path` is outside `root`.
+    pub fn link(&self, path: &str, line: Option<u32>, revision: &str) -> Option<String> {
+        let web = self.web_url()?;
+        let path = path.replace('\\', "/");
+        let root = self.root.replace('\\', "/");
+        let relative = path.strip_prefix(root.trim_end_matches('/'))?;
+        let relative = relative.strip_prefix('/')?.replace(' ', "%20");
+
+        let host = web.split('/').nth(2).unwrap_or_default();
+        Some(if host.contains("gitlab") {
+            let anchor = line.map(|line| format!("#L{}", line)).unwrap_or_default();
+            format!("{}/-/blob/{}/{}{}", web, revision, relative, anchor)
+        } else if host.contains("bitbucket") {
+            let anchor = line
+                .map(|line| format!("#lines-{}", line))
+                .unwrap_or_default();
+            format!("{}/src/{}/{}{}", web, revision, relative, anchor)
+        } else {
+            let anchor = line.map(|line| format!("#L{}", line)).unwrap_or_default();
+            format!("{}/blob/{}/{}{}", web, revision, relative, anchor)
+        })
+    }
+}
+
+/// Friendly names for origin hostnames, from the `[hosts]` table. Hostnames
+/// are matched case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct HostAliases(BTreeMap<String, String>);
+
+impl HostAliases {
+    pub fn new(hosts: &BTreeMap<String, String>) -> Self {
+        Self(
+            hosts
+                .iter()
+                .map(|(host, name)| (host.trim().to_lowercase(), name.trim().to_string()))
+                .collect(),
+        )
+    }
+
+    /// The friendly name for `hostname`, if it has one.
+    pub fn get(&self, hostname: &str) -> Option<&str> {
+        self.0
+            .get(&hostname.trim().to_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// Applies the first matching mapping, leaving unmatched paths untouched.
+pub fn map_path(mappings: &[PathMapping], path: &str) -> String {
+    mappings
+        .iter()
+        .find_map(|mapping| mapping.apply(path))
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Ray's built-in color names, which theme overrides are keyed by.
+pub(crate) const RAY_COLORS: &[&str] = &["green", "orange", "red", "purple", "blue", "gray"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        toml::from_str(&contents).map_err(|err| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        })
+    }
+
+    /// The renderer assigned to each unknown payload type.
+    pub fn unknown_renderers(&self) -> BTreeMap<String, UnknownRenderer> {
+        self.unknown_kinds
+            .iter()
+            .map(|entry| (entry.kind.trim().to_string(), entry.renderer))
+            .collect()
+    }
+
+    /// Checks everything that parses but would misbehave at runtime.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        if let Err(errors) = Keymap::from_config(&self.keys) {
+            issues.extend(errors.into_iter().map(ConfigIssue::error));
+        }
+
+        for (name, value) in &self.theme.colors {
+            if !RAY_COLORS.contains(&name.as_str()) {
+                issues.push(ConfigIssue::warning(format!(
+                    "[theme.colors] `{}` is not a Ray color and will never be used (expected one of: {})",
+                    name,
+                    RAY_COLORS.join(", ")
+                )));
+            }
+            if color::parse(value).is_none() {
+                issues.push(ConfigIssue::error(format!(
+                    "[theme.colors] {}: `{}` is not a color; use a name like `red` or a hex value like `#ff5555`",
+                    name, value
+                )));
+            }
+        }
+
+        for (kind, glyph) in &self.theme.kind_glyphs {
+            if glyph.chars().count() != 1 {
+                issues.push(ConfigIssue::error(format!(
+                    "[theme.kind_glyphs] {}: `{}` must be a single character",
+                    kind, glyph
+                )));
+            }
+        }
+
+        let separator = self.locale.decimal_separator;
+        if separator.is_ascii_digit() || separator.is_control() {
+            issues.push(ConfigIssue::error(format!(
+                "[locale] decimal_separator: `{}` cannot separate decimals; use `.` or `,`",
+                separator.escape_default()
+            )));
+        }
+
+        for (index, mapping) in self.path_mappings.iter().enumerate() {
+            let position = index + 1;
+            if mapping.remote.trim().is_empty() {
+                issues.push(ConfigIssue::error(format!(
+                    "[[path_mappings]] #{}: `remote` must not be empty",
+                    position
+                )));
+            }
+            if mapping.local.trim().is_empty() {
+                issues.push(ConfigIssue::error(format!(
+                    "[[path_mappings]] #{}: `local` must not be empty",
+                    position
+                )));
+            } else if !Path::new(&mapping.local).exists() {
+                issues.push(ConfigIssue::warning(format!(
+                    "[[path_mappings]] #{}: local path `{}` does not exist on this machine",
+                    position, mapping.local
+                )));
+            }
+            if self.path_mappings[..index].iter().any(|earlier| {
+                earlier.remote.trim_end_matches('/') == mapping.remote.trim_end_matches('/')
+            }) {
+                issues.push(ConfigIssue::warning(format!(
+                    "[[path_mappings]] #{}: remote `{}` is already mapped by an earlier entry and will be ignored",
+                    position, mapping.remote
+                )));
+            }
+        }
+
+        for (index, entry) in self.unknown_kinds.iter().enumerate() {
+            let position = index + 1;
+            if entry.kind.trim().is_empty() {
+                issues.push(ConfigIssue::error(format!(
+                    "[[unknown_kinds]] #{}: `kind` must not be empty",
+                    position
+                )));
+            } else if !matches!(
+                PayloadKind::from_type(entry.kind.trim()),
+                PayloadKind::Unknown(_)
+            ) {
+                issues.push(ConfigIssue::warning(format!(
+                    "[[unknown_kinds]] #{}: Raygun already renders `{}` payloads, so the renderer is ignored",
+                    position, entry.kind
+                )));
+            }
+        }
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            let position = index + 1;
+            if let Err(err) = Rule::new(rule) {
+                issues.push(ConfigIssue::error(format!(
+                    "[[rules]] #{}: {}",
+                    position, err
+                )));
+            }
+            if rule.color.is_none() && rule.label.is_none() {
+                issues.push(ConfigIssue::error(format!(
+                    "[[rules]] #{}: set `color`, `label` or both",
+                    position
+                )));
+            }
+            if let Some(color) = &rule.color
+                && !RAY_COLORS.contains(&color.as_str())
+            {
+                issues.push(ConfigIssue::error(format!(
+                    "[[rules]] #{}: `{}` is not a Ray color (expected one of: {})",
+                    position,
+                    color,
+                    RAY_COLORS.join(", ")
+                )));
+            }
+            if rule
+                .label
+                .as_ref()
+                .is_some_and(|label| label.trim().is_empty())
+            {
+                issues.push(ConfigIssue::error(format!(
+                    "[[rules]] #{}: `label` must not be empty",
+                    position
+                )));
+            }
+            if rule.kind.is_none() && rule.origin.is_none() && rule.content.is_none() {
+                issues.push(ConfigIssue::warning(format!(
+                    "[[rules]] #{}: no `kind`, `origin` or `content`, so it matches every event",
+                    position
+                )));
+            }
+        }
+
+        for (index, route) in self.routes.iter().enumerate() {
+            let position = index + 1;
+            if route.screen.trim().is_empty() {
+                issues.push(ConfigIssue::error(format!(
+                    "[[routes]] #{}: `screen` must not be empty",
+                    position
+                )));
+            }
+            match Filter::parse(&route.filter) {
+                Err(err) => issues.push(ConfigIssue::error(format!(
+                    "[[routes]] #{}: invalid filter `{}`: {}",
+                    position, route.filter, err
+                ))),
+                Ok(filter) if filter.is_empty() => issues.push(ConfigIssue::warning(format!(
+                    "[[routes]] #{}: the filter is empty, so every event goes to `{}`",
+                    position, route.screen
+                ))),
+                Ok(_) => {}
+            }
+        }
+
+        for (kind, rules) in &self.collapse {
+            if let PayloadKind::Unknown(_) = PayloadKind::from_type(kind) {
+                issues.push(ConfigIssue::warning(format!(
+                    "[collapse] `{}` is not a Ray payload type and will never match",
+                    kind
+                )));
+            }
+            if rules.iter().any(|rule| rule.trim().is_empty()) {
+                issues.push(ConfigIssue::error(format!(
+                    "[collapse] {}: section names must not be empty",
+                    kind
+                )));
+            }
+        }
+
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            let position = index + 1;
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                issues.push(ConfigIssue::error(format!(
+                    "[[webhooks]] #{}: `url` must be an http:// or https:// URL",
+                    position
+                )));
+            }
+            if let Err(err) = Filter::parse(&webhook.filter) {
+                issues.push(ConfigIssue::error(format!(
+                    "[[webhooks]] #{}: invalid filter `{}`: {}",
+                    position, webhook.filter, err
+                )));
+            }
+        }
+
+        if let Some(otlp) = &self.otlp {
+            if !otlp.endpoint.starts_with("http://") && !otlp.endpoint.starts_with("https://") {
+                issues.push(ConfigIssue::error(
+                    "[otlp] `endpoint` must be an http:// or https:// URL".to_string(),
+                ));
+            }
+            for name in otlp.headers.keys() {
+                if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    issues.push(ConfigIssue::error(format!(
+                        "[otlp.headers] `{}` is not a valid header name",
+                        name
+                    )));
+                }
+            }
+        }
+
+        if let Some(url) = &self.resend_url
+            && !url.starts_with("http://")
+            && !url.starts_with("https://")
+        {
+            issues.push(ConfigIssue::error(
+                "`resend_url` must be an http:// or https:// URL".to_string(),
+            ));
+        }
+
+        if let Some(archive) = &self.archive {
+            if archive.every_minutes == Some(0) {
+                issues.push(ConfigIssue::error(
+                    "[archive] `every_minutes` must be at least 1".to_string(),
+                ));
+            }
+            if archive.keep == Some(0) {
+                issues.push(ConfigIssue::error(
+                    "[archive] `keep` must be at least 1".to_string(),
+                ));
+            }
+            if archive.every_minutes.is_none() && !archive.on_evict {
+                issues.push(ConfigIssue::warning(
+                    "[archive] neither `every_minutes` nor `on_evict` is set, so nothing is ever archived".to_string(),
+                ));
+            }
+        }
+
+        for (index, view) in self.views.iter().enumerate() {
+            let position = index + 1;
+            if view.name.trim().is_empty() {
+                issues.push(ConfigIssue::error(format!(
+                    "[[views]] #{}: `name` must not be empty",
+                    position
+                )));
+            }
+            if let Err(err) = Filter::parse(&view.filter) {
+                issues.push(ConfigIssue::error(format!(
+                    "[[views]] #{}: invalid filter `{}`: {}",
+                    position, view.filter, err
+                )));
+            }
+            if position > VIEW_SLOTS {
+                issues.push(ConfigIssue::warning(format!(
+                    "[[views]] #{}: only the first {} views have a number key, so `{}` cannot be applied",
+                    position, VIEW_SLOTS, view.name
+                )));
+            }
+        }
+
+        if let Some(noise) = &self.noise
+            && let Err(err) = Filter::parse(noise)
+        {
+            issues.push(ConfigIssue::error(format!(
+                "`noise`: invalid filter `{}`: {}",
+                noise, err
+            )));
+        }
+
+        for (kind, sound) in &self.sounds {
+            if let SoundConfig::Command { command } = sound
+                && command.trim().is_empty()
+            {
+                issues.push(ConfigIssue::error(format!(
+                    "[sounds] {}: `command` must not be empty",
+                    kind
+                )));
+            }
+        }
+
+        for (host, name) in &self.hosts {
+            if host.trim().is_empty() {
+                issues.push(ConfigIssue::error(format!(
+                    "[hosts] the hostname for `{}` must not be empty",
+                    name
+                )));
+            }
+            if name.trim().is_empty() {
+                issues.push(ConfigIssue::error(format!(
+                    "[hosts] {}: the name must not be empty",
+                    host
+                )));
+            }
+        }
+
+        if let Some(permalink) = &self.permalink {
+            if permalink.root.trim().is_empty() {
+                issues.push(ConfigIssue::error(
+                    "[permalink] `root` must not be empty".to_string(),
+                ));
+            } else if permalink.revision.is_none() && !Path::new(&permalink.root).exists() {
+                issues.push(ConfigIssue::warning(format!(
+                    "[permalink] root `{}` does not exist on this machine, so its HEAD cannot be read; set `revision`",
+                    permalink.root
+                )));
+            }
+            if permalink.web_url().is_none() {
+                issues.push(ConfigIssue::error(format!(
+                    "[permalink] `remote` = `{}` is neither an http(s) URL nor a git remote like `git@github.com:org/repo.git`",
+                    permalink.remote
+                )));
+            }
+        }
+
+        if let Some(editor) = &self.editor {
+            if editor.split_whitespace().next().is_none() {
+                issues.push(ConfigIssue::error("`editor` must not be empty".to_string()));
+            } else if !editor.contains("{file}") {
+                issues.push(ConfigIssue::warning(format!(
+                    "`editor` = `{}` has no `{{file}}` placeholder; the file is appended as the last argument",
+                    editor
+                )));
+            }
+        }
+
+        issues
+    }
+}
+
+/// Parses a human friendly byte size such as `4096`, `512KB` or `32MB`.
+///
+/// Units are binary (1 KB = 1024 bytes) to match how payload sizes are shown in
+/// the detail view.
+pub fn parse_byte_size(raw: &str) -> Result<usize, String> {
+    let trimmed = raw.trim();
+    let split = trimmed
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, unit) = trimmed.split_at(split);
+
+    let value: usize = digits
+        .parse()
+        .map_err(|_| format!("`{}` is not a valid size", raw))?;
+
+    let multiplier: usize = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        other => return Err(format!("unknown size unit `{}`", other)),
+    };
+
+    let bytes = value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("`{}` is too large", raw))?;
+
+    if bytes == 0 {
+        return Err("size must be greater than zero".to_string());
+    }
+
+    Ok(bytes)
+}
+
+/// Parses an `--upstream` base URL such as `http://127.0.0.1:23518`, without
+/// the trailing slash.
+pub fn parse_upstream_url(raw: &str) -> Result<String, String> {
+    let url = reqwest::Url::parse(raw.trim()).map_err(|err| format!("`{}`: {}", raw, err))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("`{}` is not an http:// or https:// URL", raw));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(format!("`{}` must not have a query or fragment", raw));
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+/// Parses a `--trusted-proxy` address (`10.0.0.2`) or network (`10.0.0.0/8`).
+pub fn parse_ip_network(raw: &str) -> Result<IpNet, String> {
+    let raw = raw.trim();
+    raw.parse::<IpNet>()
+        .or_else(|_| raw.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("`{}` is not an IP address or network like 10.0.0.0/8", raw))
+}
+
+/// Parses a `--store` backend such as `sqlite:/path/to/raygun.db`,
+/// `spill:/path/to/raygun.jsonl` or `memory`.
+pub fn parse_store_spec(raw: &str) -> Result<StoreSpec, String> {
+    if raw == "memory" {
+        return Ok(StoreSpec::Memory);
+    }
+    match raw.split_once(':') {
+        Some(("sqlite", path)) if !path.trim().is_empty() => {
+            Ok(StoreSpec::Sqlite(PathBuf::from(path)))
+        }
+        Some(("spill", path)) if !path.trim().is_empty() => {
+            Ok(StoreSpec::Spill(PathBuf::from(path)))
+        }
+        Some((backend @ ("sqlite" | "spill"), _)) => {
+            Err(format!("`{}:` needs a file path", backend))
+        }
+        _ => Err(format!(
+            "unknown store `{}`; expected `sqlite:<path>`, `spill:<path>` or `memory`",
+            raw
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_reports_bad_colors_and_mappings() {
+        let config: FileConfig = toml::from_str(
+            r##"
+            [theme.colors]
+            red = "#ff5555"
+            blue = "not-a-color"
+            teal = "cyan"
+
+            [keys]
+            help = "h"
+
+            [[path_mappings]]
+            remote = "/var/www/html"
+            local = ""
+            "##,
+        )
+        .expect("config parses");
+
+        let issues = config.validate();
+        let errors: Vec<_> = issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.message.as_str())
+            .collect();
+        let warnings = issues.len() - errors.len();
+
+        assert_eq!(errors.len(), 2, "unexpected errors: {:?}", errors);
+        assert!(errors[0].contains("`not-a-color` is not a color"));
+        assert!(errors[1].contains("`local` must not be empty"));
+        assert_eq!(warnings, 1);
+    }
+
+    #[test]
+    fn validate_reports_unknown_collapse_kinds() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [collapse]
+            exception = ["vendor_frames", "meta"]
+            quantum = ["meta"]
+            log = [""]
+            "#,
+        )
+        .expect("config parses");
+
+        let issues = config.validate();
+        let messages: Vec<(Severity, &str)> = issues
+            .iter()
+            .map(|issue| (issue.severity, issue.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    Severity::Error,
+                    "[collapse] log: section names must not be empty"
+                ),
+                (
+                    Severity::Warning,
+                    "[collapse] `quantum` is not a Ray payload type and will never match"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_reports_bad_webhooks() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [[webhooks]]
+            url = "https://hooks.slack.com/services/T000/B000/XXX"
+            filter = "kind:exception"
+
+            [[webhooks]]
+            url = "localhost:9000/hook"
+            filter = "severity:high"
+            format = "json"
+            "#,
+        )
+        .expect("config parses");
+
+        let messages: Vec<String> = config
+            .validate()
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "[[webhooks]] #2: `url` must be an http:// or https:// URL".to_string(),
+                "[[webhooks]] #2: invalid filter `severity:high`: unknown field `severity` \
+                 (expected kind, color, label, screen, host, project, client, origin or age)"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_permalinks_for_hosted_remotes() {
+        let permalink = |remote: &str| PermalinkConfig {
+            root: "/home/ada/shop/".to_string(),
+            remote: remote.to_string(),
+            revision: None,
+        };
+        let path = "/home/ada/shop/app/Http/Kernel.php";
+
+        assert_eq!(
+            permalink("git@github.com:acme/shop.git").link(path, Some(42), "3f2a1b"),
+            Some("https://github.com/acme/shop/blob/3f2a1b/app/Http/Kernel.php#L42".to_string())
+        );
+        assert_eq!(
+            permalink("ssh://git@gitlab.example.com:2222/acme/shop.git").link(path, None, "main"),
+            Some(
+                "https://gitlab.example.com/acme/shop/-/blob/main/app/Http/Kernel.php".to_string()
+            )
+        );
+        assert_eq!(
+            permalink("https://bitbucket.org/acme/shop/").link(path, Some(7), "v1.2"),
+            Some(
+                "https://bitbucket.org/acme/shop/src/v1.2/app/Http/Kernel.php#lines-7".to_string()
+            )
+        );
+        assert_eq!(
+            permalink("https://github.com/acme/shop").link(
+                "/home/ada/shopfront/x.php",
+                Some(1),
+                "main"
+            ),
+            None
+        );
+        assert_eq!(permalink("shop").web_url(), None);
+    }
+
+    #[test]
+    fn validate_reports_bad_views() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [[views]]
+            name = "Everything"
+
+            [[views]]
+            name = "Errors"
+            filter = "kind:exception OR (color:red"
+            "#,
+        )
+        .expect("config parses");
+
+        let issues = config.validate();
+        assert_eq!(config.views[0].filter, "");
+        assert_eq!(issues.len(), 1);
+        assert!(
+            issues[0]
+                .message
+                .starts_with("[[views]] #2: invalid filter")
+        );
+    }
+
+    #[test]
+    fn validate_reports_bad_routes() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [[routes]]
+            screen = "Queues"
+            filter = "host:worker"
+
+            [[routes]]
+            screen = " "
+            filter = "project:(shop"
+
+            [[routes]]
+            screen = "Everything"
+            filter = ""
+            "#,
+        )
+        .expect("config parses");
+
+        let messages: Vec<String> = config
+            .validate()
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert_eq!(messages[0], "[[routes]] #2: `screen` must not be empty");
+        assert!(messages[1].starts_with("[[routes]] #2: invalid filter"));
+        assert_eq!(
+            messages[2],
+            "[[routes]] #3: the filter is empty, so every event goes to `Everything`"
+        );
+    }
+
+    #[test]
+    fn validate_reports_a_bad_noise_filter() {
+        let config: FileConfig = toml::from_str(r#"noise = "kind:""#).expect("config parses");
+        let issues = config.validate();
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.starts_with("`noise`: invalid filter"));
+        assert!(FileConfig::default().validate().is_empty());
+        assert!(Filter::parse(DEFAULT_NOISE).is_ok());
+    }
+
+    #[test]
+    fn parses_sounds_and_reports_empty_commands() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [sounds]
+            exception = 2
+            log = 0
+            notify = { command = "afplay Glass.aiff" }
+            query = { command = " " }
+            "#,
+        )
+        .expect("config parses");
+        assert_eq!(config.sounds["exception"], SoundConfig::Bells(2));
+        assert_eq!(
+            config.sounds["notify"],
+            SoundConfig::Command {
+                command: "afplay Glass.aiff".into()
+            }
+        );
+        let issues = config.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].message,
+            "[sounds] query: `command` must not be empty"
+        );
+    }
+
+    #[test]
+    fn validate_reports_bad_rules() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [[rules]]
+            origin = "app/Payments/**"
+            color = "purple"
+
+            [[rules]]
+            content = "(unclosed"
+            color = "pink"
+
+            [[rules]]
+            kind = "exception"
+            "#,
+        )
+        .expect("config parses");
+
+        let messages: Vec<_> = config
+            .validate()
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].starts_with("[[rules]] #2: invalid `content` regex"));
+        assert!(messages[1].starts_with("[[rules]] #2: `pink` is not a Ray color"));
+        assert_eq!(messages[2], "[[rules]] #3: set `color`, `label` or both");
+    }
+
+    #[test]
+    fn later_unknown_kind_renderers_win() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [[unknown_kinds]]
+            kind = "hologram"
+            renderer = "text"
+
+            [[unknown_kinds]]
+            kind = "log"
+            renderer = "table"
+
+            [[unknown_kinds]]
+            kind = "hologram"
+            renderer = "table"
+            "#,
+        )
+        .expect("config parses");
+
+        assert_eq!(
+            config.unknown_renderers().get("hologram"),
+            Some(&UnknownRenderer::Table)
+        );
+        let issues = config.validate();
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("already renders `log`"));
+    }
+
+    #[test]
+    fn parses_store_backends() {
+        assert_eq!(
+            parse_store_spec("sqlite:/tmp/raygun.db"),
+            Ok(StoreSpec::Sqlite(PathBuf::from("/tmp/raygun.db")))
+        );
+        assert_eq!(
+            parse_store_spec("sqlite:C:\\raygun.db"),
+            Ok(StoreSpec::Sqlite(PathBuf::from("C:\\raygun.db")))
+        );
+        assert_eq!(
+            parse_store_spec("spill:raygun.jsonl"),
+            Ok(StoreSpec::Spill(PathBuf::from("raygun.jsonl")))
+        );
+        assert_eq!(parse_store_spec("memory"), Ok(StoreSpec::Memory));
+        assert!(parse_store_spec("sqlite:").is_err());
+        assert!(parse_store_spec("spill:").is_err());
+        assert!(parse_store_spec("postgres://localhost").is_err());
+    }
+
+    #[test]
+    fn path_mapping_only_matches_whole_segments() {
+        let mapping = PathMapping {
+            remote: "/var/www/html/".to_string(),
+            local: "/Users/me/site".to_string(),
+        };
+
+        assert_eq!(
+            mapping.apply("/var/www/html/app/User.php").as_deref(),
+            Some("/Users/me/site/app/User.php")
+        );
+        assert_eq!(mapping.apply("/var/www/html2/index.php"), None);
+    }
 }
@@ -0,0 +1,909 @@
+//! `raygun daemon` keeps the HTTP server and timeline running without a UI;
+//! `raygun attach` opens the UI on it over a local socket (a Unix domain socket,
+//! or a named pipe on Windows). Messages are newline-delimited JSON: the daemon
+//! sends a hello, a snapshot of the timeline and then every change, and clients
+//! send commands such as clearing the timeline. The daemon also relays each
+//! client's selected event (its cursor) and, between clients that opt in, the
+//! active filters.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{
+    io::{
+        AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+        BufReader, Lines,
+    },
+    select,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    app,
+    config::Config,
+    integrations,
+    state::{AppState, TimelineChange, TimelineEvent, routes::Routes, rules::Rules},
+};
+
+#[derive(Debug, Error)]
+pub enum DaemonError {
+    #[error("a daemon is already listening on {0}")]
+    AlreadyRunning(PathBuf),
+    #[error("failed to listen on {path}: {source}")]
+    Listen {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("no daemon is listening on {path} ({source})")]
+    Connect {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{0} belongs to another user; remove it or pass --socket <path>")]
+    NotOwned(PathBuf),
+    #[error("the daemon closed the connection before sending its timeline")]
+    Handshake,
+    #[error("a client sent a command longer than {0} bytes")]
+    LineTooLong(usize),
+    #[error("invalid message on the daemon socket: {0}")]
+    Message(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Session messages buffered per client before it has to resync.
+const SESSION_BUFFER: usize = 64;
+/// Longest command a client may send; a client sending more is disconnected.
+const MAX_COMMAND_BYTES: usize = 64 * 1024;
+
+/// Sent by the daemon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonMessage {
+    Hello {
+        /// Identifies the receiving client in `Viewers`.
+        client_id: u64,
+        addrs: Vec<SocketAddr>,
+        public_url: Option<String>,
+    },
+    /// The whole timeline; replaces whatever the client had.
+    Snapshot {
+        events: Vec<TimelineEvent>,
+    },
+    Change {
+        change: TimelineChange,
+    },
+    /// Every attached client's id and the event it has selected. A list
+    /// rather than a map: integer map keys don't survive the tagged enum.
+    Viewers {
+        cursors: Vec<(u64, Option<Uuid>)>,
+    },
+    /// Filters last set by a client sharing its filters.
+    Filters {
+        filters: SharedFilters,
+    },
+}
+
+/// Sent by attached clients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientCommand {
+    Clear,
+    Remove {
+        id: Uuid,
+    },
+    /// Moves a deleted event back from the trash.
+    Restore {
+        id: Uuid,
+    },
+    Label {
+        id: Uuid,
+        label: Option<String>,
+    },
+    Color {
+        id: Uuid,
+        color: Option<String>,
+    },
+    Screen {
+        id: Uuid,
+        screen: Option<String>,
+    },
+    RenameScreen {
+        from: String,
+        to: String,
+    },
+    CloseScreen {
+        name: String,
+    },
+    Cursor {
+        event: Option<Uuid>,
+    },
+    Filters {
+        filters: SharedFilters,
+    },
+}
+
+/// Timeline filters as exchanged between clients with `--share-filters`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SharedFilters {
+    pub color: Option<String>,
+    /// Timeline filter expression, as typed.
+    pub filter: Option<String>,
+}
+
+/// State the daemon shares between all attached clients.
+#[derive(Debug)]
+struct Session {
+    addrs: Vec<SocketAddr>,
+    public_url: Option<String>,
+    next_client_id: AtomicU64,
+    cursors: Mutex<BTreeMap<u64, Option<Uuid>>>,
+    filters: Mutex<Option<SharedFilters>>,
+    /// `Viewers` and `Filters` messages for every client.
+    updates: broadcast::Sender<DaemonMessage>,
+}
+
+impl Session {
+    fn new(addrs: Vec<SocketAddr>, public_url: Option<String>) -> Self {
+        Self {
+            addrs,
+            public_url,
+            next_client_id: AtomicU64::new(1),
+            cursors: Mutex::new(BTreeMap::new()),
+            filters: Mutex::new(None),
+            updates: broadcast::channel(SESSION_BUFFER).0,
+        }
+    }
+
+    fn viewers(&self) -> DaemonMessage {
+        let cursors = self.cursors.lock().expect("cursor lock poisoned");
+        DaemonMessage::Viewers {
+            cursors: cursors.iter().map(|(id, event)| (*id, *event)).collect(),
+        }
+    }
+
+    fn set_cursor(&self, client_id: u64, event: Option<Uuid>) {
+        self.cursors
+            .lock()
+            .expect("cursor lock poisoned")
+            .insert(client_id, event);
+        let _ = self.updates.send(self.viewers());
+    }
+
+    fn leave(&self, client_id: u64) {
+        self.cursors
+            .lock()
+            .expect("cursor lock poisoned")
+            .remove(&client_id);
+        let _ = self.updates.send(self.viewers());
+    }
+
+    fn share_filters(&self, filters: SharedFilters) {
+        *self.filters.lock().expect("filter lock poisoned") = Some(filters.clone());
+        let _ = self.updates.send(DaemonMessage::Filters { filters });
+    }
+
+    fn filters(&self) -> Option<DaemonMessage> {
+        let filters = self.filters.lock().expect("filter lock poisoned").clone();
+        filters.map(|filters| DaemonMessage::Filters { filters })
+    }
+}
+
+/// Removes a client from the session however its connection ends.
+struct Viewer<'a> {
+    session: &'a Session,
+    client_id: u64,
+}
+
+impl Drop for Viewer<'_> {
+    fn drop(&mut self) {
+        self.session.leave(self.client_id);
+    }
+}
+
+/// Runs the server without a UI until Ctrl+C (or SIGTERM), accepting clients on
+/// the control socket.
+pub async fn run(config: Config) -> Result<()> {
+    let path = config.socket_path();
+    // Claim the socket first so a second daemon fails before binding ports.
+    let mut listener = transport::listen(&path).await?;
+    let file_config = app::load_file_config(&config)?;
+    let (state, server, mut integrations) = app::start_server(&config, &file_config).await?;
+    let session = Arc::new(Session::new(
+        server.addrs().to_vec(),
+        config.public_url.clone(),
+    ));
+
+    info!(socket = %path.display(), "daemon ready; run `raygun attach` to open the UI");
+
+    let mut hangups = app::Hangups::new();
+    loop {
+        select! {
+            accepted = listener.accept() => match accepted {
+                Ok(stream) => {
+                    let state = Arc::clone(&state);
+                    let session = Arc::clone(&session);
+                    tokio::spawn(async move {
+                        if let Err(err) = serve_client(stream, state, session).await {
+                            debug!(?err, "client connection ended");
+                        }
+                    });
+                }
+                Err(err) => warn!(?err, "failed to accept a client"),
+            },
+            _ = hangups.recv() => match app::load_file_config(&config) {
+                // Only the integrations, rules and routes depend on the config file here.
+                Ok(file_config) => {
+                    integrations.stop();
+                    integrations = integrations::spawn(&state, &file_config);
+                    state.set_rules(Rules::new(&file_config.rules)).await;
+                    state
+                        .set_routes(Routes::new(&file_config.routes, &file_config.hosts))
+                        .await;
+                    state.set_pin_errors(file_config.retention.pin_errors);
+                    info!("reloaded the config");
+                }
+                Err(err) => warn!(%err, "config not reloaded"),
+            },
+            signal = terminated() => {
+                if let Err(err) = signal {
+                    warn!(?err, "failed to listen for shutdown signals");
+                }
+                break;
+            }
+        }
+    }
+
+    drop(listener);
+    app::drain(&state, Some(server), config.drain_timeout()).await?;
+    info!("daemon shutting down");
+    Ok(())
+}
+
+/// Streams the timeline and session to one client and applies its commands.
+async fn serve_client<S>(
+    stream: S,
+    state: Arc<AppState>,
+    session: Arc<Session>,
+) -> Result<(), DaemonError>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+
+    let public_url = match &session.public_url {
+        Some(url) => Some(url.clone()),
+        None => state.public_url().await,
+    };
+    let client_id = session.next_client_id.fetch_add(1, Ordering::Relaxed);
+    let hello = DaemonMessage::Hello {
+        client_id,
+        addrs: session.addrs.clone(),
+        public_url,
+    };
+    send(&mut writer, &hello).await?;
+
+    let (events, mut changes) = state.subscribe().await;
+    send(&mut writer, &DaemonMessage::Snapshot { events }).await?;
+
+    let mut updates = session.updates.subscribe();
+    if let Some(filters) = session.filters() {
+        send(&mut writer, &filters).await?;
+    }
+    let _viewer = Viewer {
+        session: &session,
+        client_id,
+    };
+    session.set_cursor(client_id, None);
+
+    loop {
+        select! {
+            change = changes.recv() => match change {
+                Ok(message) => {
+                    // Locks live in the daemon, which answers the polls itself.
+                    if let Some(change) = message.timeline_change() {
+                        send(&mut writer, &DaemonMessage::Change { change }).await?;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!(skipped, "client fell behind; resending the timeline");
+                    let (events, resubscribed) = state.subscribe().await;
+                    changes = resubscribed;
+                    send(&mut writer, &DaemonMessage::Snapshot { events }).await?;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            update = updates.recv() => match update {
+                Ok(message) => send(&mut writer, &message).await?,
+                Err(RecvError::Lagged(_)) => {
+                    // Both messages carry full state, so the latest is enough.
+                    send(&mut writer, &session.viewers()).await?;
+                    if let Some(filters) = session.filters() {
+                        send(&mut writer, &filters).await?;
+                    }
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            command = read_line(&mut reader, &mut line, MAX_COMMAND_BYTES) => match command? {
+                Some(command) => match serde_json::from_str(&command) {
+                    Ok(ClientCommand::Clear) => state.clear_timeline().await,
+                    Ok(ClientCommand::Remove { id }) => {
+                        state.remove_event(id).await;
+                    }
+                    Ok(ClientCommand::Restore { id }) => {
+                        state.restore_event(id).await;
+                    }
+                    Ok(ClientCommand::Label { id, label }) => {
+                        state.set_label(id, label).await;
+                    }
+                    Ok(ClientCommand::Color { id, color }) => {
+                        state.set_color(id, color).await;
+                    }
+                    Ok(ClientCommand::Screen { id, screen }) => {
+                        state.set_screen(id, screen).await;
+                    }
+                    Ok(ClientCommand::RenameScreen { from, to }) => {
+                        state.rename_screen(&from, &to).await;
+                    }
+                    Ok(ClientCommand::CloseScreen { name }) => {
+                        state.close_screen(&name).await;
+                    }
+                    Ok(ClientCommand::Cursor { event }) => session.set_cursor(client_id, event),
+                    Ok(ClientCommand::Filters { filters }) => session.share_filters(filters),
+                    Err(err) => warn!(?err, "ignoring an invalid client command"),
+                },
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+/// A daemon's timeline mirrored into a local [`AppState`].
+pub struct Attachment {
+    pub state: Arc<AppState>,
+    pub addrs: Vec<SocketAddr>,
+    pub public_url: Option<String>,
+    pub client: DaemonClient,
+}
+
+/// Sends commands to the daemon an [`Attachment`] mirrors and tracks the
+/// other clients attached to it.
+#[derive(Debug, Clone)]
+pub struct DaemonClient {
+    client_id: u64,
+    commands: mpsc::UnboundedSender<ClientCommand>,
+    connected: Arc<AtomicBool>,
+    session: Arc<Mutex<SessionView>>,
+}
+
+/// The latest session state received from the daemon.
+#[derive(Debug, Default)]
+struct SessionView {
+    cursors: Vec<(u64, Option<Uuid>)>,
+    /// Shared filters not yet picked up by the UI.
+    filters: Option<SharedFilters>,
+}
+
+impl DaemonClient {
+    pub fn send(&self, command: ClientCommand) {
+        // A closed channel means the connection is gone, which the UI reports.
+        let _ = self.commands.send(command);
+    }
+
+    /// Whether the daemon is still streaming changes.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Attached clients, including this one.
+    pub fn viewer_count(&self) -> usize {
+        self.session().cursors.len().max(1)
+    }
+
+    /// Events selected by the other attached clients.
+    pub fn other_cursors(&self) -> Vec<Uuid> {
+        self.session()
+            .cursors
+            .iter()
+            .filter(|(client_id, _)| *client_id != self.client_id)
+            .filter_map(|(_, event)| *event)
+            .collect()
+    }
+
+    /// Filters shared since the last call, if any.
+    pub fn take_shared_filters(&self) -> Option<SharedFilters> {
+        self.session().filters.take()
+    }
+
+    fn session(&self) -> std::sync::MutexGuard<'_, SessionView> {
+        self.session.lock().expect("session lock poisoned")
+    }
+}
+
+/// Connects to the daemon on `path` and starts mirroring its timeline.
+pub async fn attach(path: &Path) -> Result<Attachment, DaemonError> {
+    let stream = transport::connect(path).await?;
+    attach_stream(stream).await
+}
+
+async fn attach_stream<S>(stream: S) -> Result<Attachment, DaemonError>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+
+    let Some(DaemonMessage::Hello {
+        client_id,
+        addrs,
+        public_url,
+    }) = next_message(&mut lines).await?
+    else {
+        return Err(DaemonError::Handshake);
+    };
+
+    let state = Arc::new(AppState::default());
+    let connected = Arc::new(AtomicBool::new(true));
+    let session = Arc::new(Mutex::new(SessionView::default()));
+
+    let mirror = Arc::clone(&state);
+    let reader_connected = Arc::clone(&connected);
+    let reader_session = Arc::clone(&session);
+    tokio::spawn(async move {
+        loop {
+            match next_message(&mut lines).await {
+                Ok(Some(DaemonMessage::Snapshot { events })) => {
+                    mirror.replace_timeline(events).await
+                }
+                Ok(Some(DaemonMessage::Change { change })) => mirror.apply_change(change).await,
+                Ok(Some(DaemonMessage::Viewers { cursors })) => {
+                    reader_session
+                        .lock()
+                        .expect("session lock poisoned")
+                        .cursors = cursors;
+                }
+                Ok(Some(DaemonMessage::Filters { filters })) => {
+                    reader_session
+                        .lock()
+                        .expect("session lock poisoned")
+                        .filters = Some(filters);
+                }
+                Ok(Some(DaemonMessage::Hello { .. })) => {}
+                Ok(None) => break,
+                Err(err) => {
+                    warn!(?err, "lost the connection to the daemon");
+                    break;
+                }
+            }
+        }
+        reader_connected.store(false, Ordering::Relaxed);
+    });
+
+    let (commands, mut pending) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(command) = pending.recv().await {
+            if let Err(err) = send(&mut writer, &command).await {
+                warn!(?err, "failed to send a command to the daemon");
+                break;
+            }
+        }
+        // Every client handle is gone: tell the daemon this viewer left.
+        let _ = writer.shutdown().await;
+    });
+
+    Ok(Attachment {
+        state,
+        addrs,
+        public_url,
+        client: DaemonClient {
+            client_id,
+            commands,
+            connected,
+            session,
+        },
+    })
+}
+
+async fn send<W, T>(writer: &mut W, message: &T) -> Result<(), DaemonError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Reads the next line of at most `limit` bytes. `line` holds what was read of
+/// it so far, so a read cancelled by `select!` continues where it stopped.
+async fn read_line<R>(
+    reader: &mut R,
+    line: &mut Vec<u8>,
+    limit: usize,
+) -> Result<Option<String>, DaemonError>
+where
+    R: AsyncBufRead + Unpin,
+{
+    // One byte past the limit tells a line that is too long from one that
+    // just fits.
+    let remaining = (limit + 1).saturating_sub(line.len()) as u64;
+    (&mut *reader)
+        .take(remaining)
+        .read_until(b'\n', line)
+        .await?;
+    if line.last() == Some(&b'\n') {
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    } else if line.len() > limit {
+        return Err(DaemonError::LineTooLong(limit));
+    } else if line.is_empty() {
+        return Ok(None);
+    }
+    String::from_utf8(std::mem::take(line))
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err).into())
+}
+
+async fn next_message<R>(lines: &mut Lines<R>) -> Result<Option<DaemonMessage>, DaemonError>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    match lines.next_line().await? {
+        Some(line) => Ok(Some(serde_json::from_str(&line)?)),
+        None => Ok(None),
+    }
+}
+
+/// Resolves on Ctrl+C, or SIGTERM from a service manager.
+#[cfg(unix)]
+async fn terminated() -> io::Result<()> {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    select! {
+        result = app::interrupted() => result,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminated() -> io::Result<()> {
+    app::interrupted().await
+}
+
+#[cfg(unix)]
+mod transport {
+    use std::{
+        fs::{self, DirBuilder},
+        io,
+        os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+        path::{Path, PathBuf},
+    };
+
+    use tokio::net::{UnixListener, UnixStream};
+
+    use super::DaemonError;
+
+    /// Owns the socket file and removes it on drop.
+    pub struct Listener {
+        inner: UnixListener,
+        path: PathBuf,
+    }
+
+    impl Listener {
+        pub async fn accept(&mut self) -> io::Result<UnixStream> {
+            self.inner.accept().await.map(|(stream, _)| stream)
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    pub async fn listen(path: &Path) -> Result<Listener, DaemonError> {
+        let listen_error = |source| DaemonError::Listen {
+            path: path.to_path_buf(),
+            source,
+        };
+        // E.g. `raygun-$USER` in the temp directory, which anyone could have
+        // created first.
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            match DirBuilder::new().mode(0o700).create(dir) {
+                Err(err) if err.kind() != io::ErrorKind::AlreadyExists => {
+                    return Err(listen_error(err));
+                }
+                _ if !owned(dir).map_err(listen_error)? => {
+                    return Err(DaemonError::NotOwned(dir.to_path_buf()));
+                }
+                _ => {}
+            }
+        }
+
+        if path.exists() {
+            if UnixStream::connect(path).await.is_ok() {
+                return Err(DaemonError::AlreadyRunning(path.to_path_buf()));
+            }
+            // Left behind by a daemon that didn't shut down cleanly.
+            let _ = fs::remove_file(path);
+        }
+
+        let inner = UnixListener::bind(path).map_err(listen_error)?;
+        // The timeline may hold secrets; only the owner may attach.
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(listen_error)?;
+
+        Ok(Listener {
+            inner,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub async fn connect(path: &Path) -> Result<UnixStream, DaemonError> {
+        let connect_error = |source| DaemonError::Connect {
+            path: path.to_path_buf(),
+            source,
+        };
+        // Only talk to a daemon run by the same user.
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        for checked in dir.into_iter().chain([path]) {
+            if !owned(checked).map_err(connect_error)? {
+                return Err(DaemonError::NotOwned(checked.to_path_buf()));
+            }
+        }
+        UnixStream::connect(path).await.map_err(connect_error)
+    }
+
+    /// Whether `path` belongs to the current user, or to root like the temp
+    /// directory itself.
+    fn owned(path: &Path) -> io::Result<bool> {
+        let owner = fs::symlink_metadata(path)?.uid();
+        // SAFETY: geteuid has no preconditions and cannot fail.
+        let user = unsafe { libc::geteuid() };
+        Ok(owner == user || owner == 0)
+    }
+}
+
+#[cfg(windows)]
+mod transport {
+    use std::{
+        io,
+        path::{Path, PathBuf},
+    };
+
+    use tokio::net::windows::named_pipe::{
+        ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions,
+    };
+
+    use super::DaemonError;
+
+    /// Keeps one pipe instance waiting for the next client.
+    pub struct Listener {
+        next: NamedPipeServer,
+        path: PathBuf,
+    }
+
+    impl Listener {
+        pub async fn accept(&mut self) -> io::Result<NamedPipeServer> {
+            self.next.connect().await?;
+            let next = ServerOptions::new().create(&self.path)?;
+            Ok(std::mem::replace(&mut self.next, next))
+        }
+    }
+
+    pub async fn listen(path: &Path) -> Result<Listener, DaemonError> {
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(path)
+            .map_err(|source| {
+                if source.kind() == io::ErrorKind::PermissionDenied {
+                    DaemonError::AlreadyRunning(path.to_path_buf())
+                } else {
+                    DaemonError::Listen {
+                        path: path.to_path_buf(),
+                        source,
+                    }
+                }
+            })?;
+
+        Ok(Listener {
+            next,
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub async fn connect(path: &Path) -> Result<NamedPipeClient, DaemonError> {
+        ClientOptions::new()
+            .open(path)
+            .map_err(|source| DaemonError::Connect {
+                path: path.to_path_buf(),
+                source,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, time::Duration};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn log_request(value: &str) -> RayRequest {
+        RayRequest {
+            uuid: "test".into(),
+            payloads: vec![
+                serde_json::from_value(json!({
+                    "type": "log",
+                    "content": { "values": [value], "meta": [] }
+                }))
+                .unwrap(),
+            ],
+            meta: BTreeMap::new(),
+        }
+    }
+
+    fn test_session() -> Arc<Session> {
+        Arc::new(Session::new(
+            vec![SocketAddr::from(([127, 0, 0, 1], 23_517))],
+            None,
+        ))
+    }
+
+    async fn attach_to(state: &Arc<AppState>, session: &Arc<Session>) -> Attachment {
+        let (client_end, daemon_end) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(serve_client(
+            daemon_end,
+            Arc::clone(state),
+            Arc::clone(session),
+        ));
+        attach_stream(client_end).await.unwrap()
+    }
+
+    async fn wait_until(mut condition: impl FnMut() -> bool) {
+        for _ in 0..200 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not met in time");
+    }
+
+    async fn wait_for_len(state: &AppState, expected: usize) {
+        for _ in 0..200 {
+            if state.timeline_len().await == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!(
+            "timeline has {} events, expected {}",
+            state.timeline_len().await,
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn attached_clients_mirror_the_daemon_timeline() {
+        let daemon = Arc::new(AppState::default());
+        daemon.record_request(log_request("before")).await.unwrap();
+        let session = test_session();
+
+        let (client_end, daemon_end) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(serve_client(daemon_end, Arc::clone(&daemon), session));
+        let attachment = attach_stream(client_end).await.unwrap();
+        assert_eq!(attachment.addrs.len(), 1);
+
+        wait_for_len(&attachment.state, 1).await;
+        let added = daemon.record_request(log_request("after")).await.unwrap();
+        wait_for_len(&attachment.state, 2).await;
+        let mirrored = attachment.state.timeline_snapshot().await;
+        assert_eq!(mirrored[1].id, added.id);
+        assert_eq!(mirrored[1].received_at, added.received_at);
+
+        attachment
+            .client
+            .send(ClientCommand::Remove { id: added.id });
+        wait_for_len(&daemon, 1).await;
+        wait_for_len(&attachment.state, 1).await;
+
+        attachment.client.send(ClientCommand::Clear);
+        wait_for_len(&daemon, 0).await;
+        wait_for_len(&attachment.state, 0).await;
+        assert!(attachment.client.is_connected());
+    }
+
+    #[tokio::test]
+    async fn clients_see_each_other_and_share_filters() {
+        let daemon = Arc::new(AppState::default());
+        let event = daemon.record_request(log_request("shared")).await.unwrap();
+        let session = test_session();
+
+        let first = attach_to(&daemon, &session).await;
+        let second = attach_to(&daemon, &session).await;
+        wait_until(|| first.client.viewer_count() == 2 && second.client.viewer_count() == 2).await;
+
+        first.client.send(ClientCommand::Cursor {
+            event: Some(event.id),
+        });
+        wait_until(|| second.client.other_cursors() == vec![event.id]).await;
+        assert!(first.client.other_cursors().is_empty());
+
+        let filters = SharedFilters {
+            color: Some("red".into()),
+            ..SharedFilters::default()
+        };
+        first.client.send(ClientCommand::Filters {
+            filters: filters.clone(),
+        });
+        let mut received = None;
+        wait_until(|| {
+            received = second.client.take_shared_filters();
+            received.is_some()
+        })
+        .await;
+        assert_eq!(received, Some(filters.clone()));
+
+        // Late joiners start with the shared filters.
+        let third = attach_to(&daemon, &session).await;
+        wait_until(|| third.client.viewer_count() == 3).await;
+        assert_eq!(third.client.take_shared_filters(), Some(filters));
+
+        drop(first);
+        wait_until(|| second.client.viewer_count() == 2).await;
+        assert!(second.client.other_cursors().is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_commands_are_limited_in_length() {
+        let mut input: &[u8] = b"{\"type\":\"clear\"}\r\nxxxxxxxxxx\n";
+        let mut line = Vec::new();
+        assert_eq!(
+            read_line(&mut input, &mut line, 32)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(r#"{"type":"clear"}"#)
+        );
+        assert!(matches!(
+            read_line(&mut input, &mut line, 8).await,
+            Err(DaemonError::LineTooLong(8))
+        ));
+
+        let mut input: &[u8] = b"last";
+        let mut line = Vec::new();
+        assert_eq!(
+            read_line(&mut input, &mut line, 8)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("last")
+        );
+        assert_eq!(read_line(&mut input, &mut line, 8).await.unwrap(), None);
+    }
+}
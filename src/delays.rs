@@ -0,0 +1,154 @@
+//! How long events take from arriving over HTTP to showing in the UI, for the
+//! stats overlay. Each delay is split into the wait until the UI picked the
+//! event up from the timeline (queueing, including the tick it waited for)
+//! and the time to build and draw the frame that first showed it (render).
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+use crate::state::TimelineEvent;
+
+/// Samples kept before the oldest are dropped.
+const RETENTION: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelaySample {
+    pub queue: Duration,
+    pub render: Duration,
+}
+
+impl DelaySample {
+    pub fn total(&self) -> Duration {
+        self.queue + self.render
+    }
+}
+
+/// The 50th and 95th percentile and the maximum of one kind of delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DelaySummary {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl DelaySummary {
+    fn of(mut delays: Vec<Duration>) -> Option<Self> {
+        delays.sort_unstable();
+        let at = |percent: usize| delays[(delays.len() - 1) * percent / 100];
+        Some(Self {
+            p50: at(50),
+            p95: at(95),
+            max: *delays.last()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ProcessingDelays {
+    /// Events that arrived before this (imports, a recovered session) or
+    /// were already seen are not measured.
+    watermark: SystemTime,
+    /// Arrival and pick-up time of events not drawn yet.
+    pending: Vec<(SystemTime, SystemTime)>,
+    samples: VecDeque<DelaySample>,
+}
+
+impl ProcessingDelays {
+    pub fn new(started: SystemTime) -> Self {
+        Self {
+            watermark: started,
+            pending: Vec::new(),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Notes the events received over HTTP that the UI hasn't seen before.
+    pub fn picked_up(&mut self, events: &[TimelineEvent], now: SystemTime) {
+        let mut watermark = self.watermark;
+        for event in events {
+            if event.remote_addr.is_some() && event.received_at > self.watermark {
+                self.pending.push((event.received_at, now));
+                watermark = watermark.max(event.received_at);
+            }
+        }
+        self.watermark = watermark;
+    }
+
+    /// Records the events picked up since the last frame as drawn at `now`.
+    pub fn drawn(&mut self, now: SystemTime) {
+        for (received, picked_up) in self.pending.drain(..) {
+            self.samples.push_back(DelaySample {
+                queue: picked_up.duration_since(received).unwrap_or_default(),
+                render: now.duration_since(picked_up).unwrap_or_default(),
+            });
+            if self.samples.len() > RETENTION {
+                self.samples.pop_front();
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Queueing, render and total delay of the kept samples.
+    pub fn summaries(&self) -> Option<[DelaySummary; 3]> {
+        let collect = |delay: fn(&DelaySample) -> Duration| {
+            DelaySummary::of(self.samples.iter().map(delay).collect())
+        };
+        Some([
+            collect(|sample| sample.queue)?,
+            collect(|sample| sample.render)?,
+            collect(DelaySample::total)?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(received_at: SystemTime, remote: bool) -> TimelineEvent {
+        let request = serde_json::from_value(json!({ "uuid": "a", "payloads": [] })).unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.received_at = received_at;
+        event.remote_addr = remote.then(|| SocketAddr::from(([127, 0, 0, 1], 4000)));
+        event
+    }
+
+    #[test]
+    fn measures_each_new_http_event_once() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let ms = Duration::from_millis;
+        let mut delays = ProcessingDelays::new(start);
+
+        let imported = event(start - ms(500), true);
+        let local = event(start + ms(10), false);
+        let first = event(start + ms(20), true);
+        delays.picked_up(
+            &[imported.clone(), local.clone(), first.clone()],
+            start + ms(120),
+        );
+        delays.drawn(start + ms(125));
+
+        let second = event(start + ms(200), true);
+        delays.picked_up(&[imported, local, first, second], start + ms(210));
+        delays.drawn(start + ms(240));
+
+        assert_eq!(delays.len(), 2);
+        let [queue, render, total] = delays.summaries().unwrap();
+        assert_eq!((queue.p50, queue.max), (ms(10), ms(100)));
+        assert_eq!((render.p50, render.max), (ms(5), ms(30)));
+        assert_eq!(total.max, ms(105));
+    }
+}
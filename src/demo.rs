@@ -0,0 +1,160 @@
+use serde_json::{Value, json};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{protocol::RayRequest, state::AppState};
+
+/// Records a curated set of representative payloads so the UI can be explored
+/// without a PHP app sending anything.
+pub async fn seed(state: &AppState) {
+    for request in demo_requests() {
+        state.record_request(request).await;
+    }
+}
+
+fn demo_requests() -> Vec<RayRequest> {
+    let origin = |file: &str, line: u32| json!({ "file": file, "line_number": line, "hostname": "raygun-demo" });
+
+    let payloads: Vec<Value> = vec![
+        json!([
+            {
+                "type": "log",
+                "content": { "values": ["Welcome to the Raygun demo! Use ↑/↓ to browse these sample payloads."] },
+                "origin": origin("/var/www/app/routes/web.php", 12)
+            },
+            { "type": "color", "content": { "color": "green" } }
+        ]),
+        json!([
+            {
+                "type": "custom",
+                "content": {
+                    "content": concat!(
+                        "<pre class=\"sf-dump\">App\\Models\\User {#1284 ▼<br />",
+                        "  +id: 42<br />",
+                        "  +name: \"Ada Lovelace\"<br />",
+                        "  +email: \"ada@example.com\"<br />",
+                        "  +roles: array:2 [<br />",
+                        "    0 => \"admin\"<br />",
+                        "    1 => \"editor\"<br />",
+                        "  ]<br />",
+                        "  +email_verified_at: null<br />",
+                        "}<br /></pre>"
+                    ),
+                    "label": ""
+                },
+                "origin": origin("/var/www/app/app/Http/Controllers/UserController.php", 38)
+            },
+            { "type": "label", "content": { "label": "User model" } }
+        ]),
+        json!([
+            {
+                "type": "table",
+                "content": {
+                    "values": {
+                        "method": "POST",
+                        "uri": "/checkout",
+                        "status": 201,
+                        "cart_items": 3,
+                        "coupon": null
+                    },
+                    "label": "Request"
+                },
+                "origin": origin("/var/www/app/app/Http/Middleware/LogRequest.php", 21)
+            },
+            { "type": "color", "content": { "color": "blue" } }
+        ]),
+        json!([
+            {
+                "type": "table",
+                "content": {
+                    "values": {
+                        "sql": "select * from `orders` where `user_id` = ? and `status` = ? limit 10",
+                        "bindings": [42, "pending"],
+                        "connection_name": "mysql",
+                        "time": "3.42ms"
+                    },
+                    "label": "Query"
+                },
+                "origin": origin("/var/www/app/app/Repositories/OrderRepository.php", 57)
+            },
+            { "type": "color", "content": { "color": "purple" } }
+        ]),
+        json!([
+            {
+                "type": "measure",
+                "content": {
+                    "name": "import-products",
+                    "is_new_timer": false,
+                    "total_time": 1843.6,
+                    "max_memory_usage_during_total_time": 50331648,
+                    "time_since_last_call": 412.9,
+                    "max_memory_usage_since_last_call": 18874368
+                },
+                "origin": origin("/var/www/app/app/Console/Commands/ImportProducts.php", 64)
+            },
+            { "type": "color", "content": { "color": "orange" } }
+        ]),
+        json!([
+            {
+                "type": "exception",
+                "content": {
+                    "class": "Illuminate\\Database\\QueryException",
+                    "message": "SQLSTATE[23000]: Integrity constraint violation: 1062 Duplicate entry 'ada@example.com' for key 'users_email_unique'",
+                    "frames": [
+                        {
+                            "file_name": "/var/www/app/app/Actions/RegisterUser.php",
+                            "line_number": 29,
+                            "class": "App\\Actions\\RegisterUser",
+                            "method": "handle",
+                            "vendor_frame": false
+                        },
+                        {
+                            "file_name": "/var/www/app/app/Http/Controllers/RegisterController.php",
+                            "line_number": 18,
+                            "class": "App\\Http\\Controllers\\RegisterController",
+                            "method": "store",
+                            "vendor_frame": false
+                        },
+                        {
+                            "file_name": "/var/www/app/vendor/laravel/framework/src/Illuminate/Routing/Controller.php",
+                            "line_number": 54,
+                            "class": "Illuminate\\Routing\\Controller",
+                            "method": "callAction",
+                            "vendor_frame": true
+                        }
+                    ],
+                    "meta": []
+                },
+                "origin": origin("/var/www/app/app/Actions/RegisterUser.php", 29)
+            },
+            { "type": "color", "content": { "color": "red" } }
+        ]),
+    ];
+
+    payloads
+        .into_iter()
+        .filter_map(|payloads| {
+            let request = json!({
+                "uuid": Uuid::new_v4().to_string(),
+                "payloads": payloads,
+                "meta": { "php_version": "8.3.0", "ray_package_version": "1.41.0" }
+            });
+            serde_json::from_value::<RayRequest>(request)
+                .inspect_err(|error| warn!(?error, "failed to build demo payload"))
+                .ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn every_demo_payload_is_recorded() {
+        let state = AppState::new(100);
+        seed(&state).await;
+
+        assert_eq!(state.timeline_len().await, demo_requests().len());
+    }
+}
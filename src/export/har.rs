@@ -0,0 +1,435 @@
+//! HAR 1.2 export of the HTTP client calls Laravel reports to Ray
+//! (`ray()->showHttpClientRequests()`), so captured API traffic can be loaded
+//! into browser devtools or other HAR analyzers.
+//!
+//! Laravel sends each call as two `table` payloads labelled `Http`: one with
+//! the request's `Method`, `URL`, `Headers` and `Body`, and one with the
+//! response's `Status`, `Headers`, `Body` and timings. Arrays arrive either as
+//! JSON or as VarDumper HTML.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::http::StatusCode;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    app::civil_from_days, protocol::PayloadKind, state::TimelineEvent, ui::detail::sanitize_sf_dump,
+};
+
+/// One side of an HTTP call and the event that carried it.
+#[derive(Clone, Copy)]
+struct Call<'a> {
+    received_at: SystemTime,
+    values: &'a Map<String, Value>,
+}
+
+/// HAR entries for the HTTP calls in `events`, oldest first. Responses are
+/// paired with the oldest unanswered request for the same URL.
+pub fn entries(events: &[TimelineEvent]) -> Vec<Value> {
+    let mut calls: Vec<(Option<Call<'_>>, Option<Call<'_>>)> = Vec::new();
+
+    for event in events {
+        for payload in &event.request.payloads {
+            if payload.kind != PayloadKind::Table {
+                continue;
+            }
+            let Some(content) = payload.content_object() else {
+                continue;
+            };
+            let is_http = content
+                .get("label")
+                .and_then(Value::as_str)
+                .is_some_and(|label| label.trim().eq_ignore_ascii_case("http"));
+            if !is_http {
+                continue;
+            }
+            let Some(values) = content.get("values").and_then(Value::as_object) else {
+                continue;
+            };
+
+            let call = Call {
+                received_at: event.received_at,
+                values,
+            };
+            if field(values, "Status").is_some() {
+                let url = text_field(values, "URL");
+                let pending = calls.iter_mut().find(|(request, response)| {
+                    response.is_none()
+                        && request.is_some_and(|request| {
+                            url.is_none() || text_field(request.values, "URL") == url
+                        })
+                });
+                match pending {
+                    Some((_, response)) => *response = Some(call),
+                    None => calls.push((None, Some(call))),
+                }
+            } else if field(values, "Method").is_some() {
+                calls.push((Some(call), None));
+            }
+        }
+    }
+
+    calls
+        .into_iter()
+        .map(|(request, response)| entry(request, response))
+        .collect()
+}
+
+/// A HAR document holding `entries`.
+pub fn document(entries: Vec<Value>) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "Raygun", "version": env!("CARGO_PKG_VERSION") },
+            "pages": [],
+            "entries": entries,
+        }
+    })
+}
+
+fn entry(request: Option<Call<'_>>, response: Option<Call<'_>>) -> Value {
+    let started = request
+        .or(response)
+        .map(|call| call.received_at)
+        .unwrap_or(UNIX_EPOCH);
+    let time_ms = response
+        .and_then(|response| number_field(response.values, "Duration"))
+        .map(|seconds| seconds * 1_000.0)
+        .or_else(|| {
+            let elapsed = response?.received_at.duration_since(started).ok()?;
+            Some(elapsed.as_secs_f64() * 1_000.0)
+        })
+        .unwrap_or(0.0);
+    let connect_ms = response
+        .and_then(|response| number_field(response.values, "Connection time"))
+        .map(|seconds| seconds * 1_000.0);
+
+    let mut entry = json!({
+        "startedDateTime": iso_timestamp(started),
+        "time": time_ms,
+        "request": har_request(request, response),
+        "response": har_response(response),
+        "cache": {},
+        "timings": {
+            "connect": connect_ms.unwrap_or(-1.0),
+            "send": 0,
+            "wait": (time_ms - connect_ms.unwrap_or(0.0)).max(0.0),
+            "receive": 0,
+        },
+    });
+    if request.is_none() {
+        entry["comment"] = json!("Raygun received the response without its request");
+    }
+    entry
+}
+
+fn har_request(request: Option<Call<'_>>, response: Option<Call<'_>>) -> Value {
+    let values = request.map(|call| call.values);
+    let url = values
+        .and_then(|values| text_field(values, "URL"))
+        .or_else(|| response.and_then(|call| text_field(call.values, "URL")))
+        .unwrap_or_default();
+    let method = values
+        .and_then(|values| text_field(values, "Method"))
+        .map(|method| method.to_ascii_uppercase())
+        .unwrap_or_else(|| "GET".to_string());
+    let headers = values
+        .and_then(|values| field(values, "Headers"))
+        .map(headers)
+        .unwrap_or_default();
+
+    let mut har = json!({
+        "method": method,
+        "url": url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": header_list(&headers),
+        "queryString": query_string(&url),
+        "headersSize": -1,
+        "bodySize": 0,
+    });
+
+    let body = values.and_then(|values| {
+        text_field(values, "Body")
+            .filter(|body| !body.is_empty())
+            .or_else(|| field(values, "Data").and_then(value_text))
+    });
+    if let Some(body) = body.filter(|body| !body.is_empty() && body != "[]") {
+        let mime_type = header_value(&headers, "content-type").unwrap_or_else(|| {
+            match values
+                .and_then(|values| text_field(values, "Type"))
+                .map(|kind| kind.to_ascii_lowercase())
+                .as_deref()
+            {
+                Some("json") => "application/json",
+                Some("form") => "application/x-www-form-urlencoded",
+                Some("multipart") => "multipart/form-data",
+                _ => "text/plain",
+            }
+            .to_string()
+        });
+        har["bodySize"] = json!(body.len());
+        har["postData"] = json!({ "mimeType": mime_type, "text": body });
+    }
+    har
+}
+
+fn har_response(response: Option<Call<'_>>) -> Value {
+    let Some(values) = response.map(|call| call.values) else {
+        // HAR's convention for a call that never got a response.
+        return json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "x-unknown" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+        });
+    };
+
+    let status = number_field(values, "Status").unwrap_or(0.0) as u16;
+    let headers = field(values, "Headers").map(headers).unwrap_or_default();
+    let body = field(values, "Body");
+    let text = body.and_then(value_text).unwrap_or_default();
+    let mime_type = header_value(&headers, "content-type").unwrap_or_else(|| {
+        if body.is_some_and(|body| body.is_object() || body.is_array()) {
+            "application/json".to_string()
+        } else {
+            "text/plain".to_string()
+        }
+    });
+    let size = number_field(values, "Size")
+        .map(|size| size as i64)
+        .unwrap_or(text.len() as i64);
+
+    json!({
+        "status": status,
+        "statusText": StatusCode::from_u16(status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or(""),
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": header_list(&headers),
+        "content": { "size": size, "mimeType": mime_type, "text": text },
+        "redirectURL": header_value(&headers, "location").unwrap_or_default(),
+        "headersSize": -1,
+        "bodySize": size,
+    })
+}
+
+/// Looks a table row up by name, ignoring case.
+fn field<'a>(values: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    values
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_null())
+}
+
+fn text_field(values: &Map<String, Value>, name: &str) -> Option<String> {
+    field(values, name).and_then(value_text)
+}
+
+fn number_field(values: &Map<String, Value>, name: &str) -> Option<f64> {
+    let value = field(values, name)?;
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|text| text.trim().parse().ok()))
+}
+
+/// Text of a table cell: strings as they are (dumps without their markup),
+/// anything else as JSON.
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) if text.contains("sf-dump") => {
+            Some(sanitize_sf_dump(text).trim().to_string())
+        }
+        Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Header name/value pairs from a JSON object (values may be lists, as PSR-7
+/// returns them) or from a dump such as `"Accept" => array:1 [ 0 => "*/*" ]`.
+fn headers(value: &Value) -> Vec<(String, String)> {
+    let mut headers = Vec::new();
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                match value {
+                    Value::Array(items) => headers.extend(
+                        items
+                            .iter()
+                            .filter_map(value_text)
+                            .map(|item| (name.clone(), item)),
+                    ),
+                    other => headers.extend(value_text(other).map(|text| (name.clone(), text))),
+                }
+            }
+        }
+        Value::String(dump) => {
+            let text = sanitize_sf_dump(dump);
+            let mut current: Option<String> = None;
+            for line in text.lines() {
+                let Some((key, rest)) = line.trim().split_once("=>") else {
+                    continue;
+                };
+                let key = unquote(key);
+                let rest = rest.trim();
+                if key.chars().all(|ch| ch.is_ascii_digit()) {
+                    if let Some(name) = &current {
+                        headers.push((name.clone(), unquote(rest)));
+                    }
+                } else if rest.ends_with('[') {
+                    current = Some(key);
+                } else {
+                    headers.push((key, unquote(rest)));
+                }
+            }
+        }
+        _ => {}
+    }
+    headers
+}
+
+fn unquote(text: &str) -> String {
+    text.trim()
+        .trim_end_matches(',')
+        .trim_matches(|ch| ch == '"' || ch == '\'')
+        .to_string()
+}
+
+fn header_value(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+fn header_list(headers: &[(String, String)]) -> Value {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn query_string(url: &str) -> Value {
+    let Some((_, query)) = url.split_once('?') else {
+        return json!([]);
+    };
+    let query = query.split('#').next().unwrap_or_default();
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            json!({ "name": name, "value": value })
+        })
+        .collect()
+}
+
+/// ISO 8601 UTC timestamp with milliseconds, e.g. `2024-03-09T14:05:01.250Z`.
+fn iso_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn http_event(values: Value, at_ms: u64) -> TimelineEvent {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "x",
+            "payloads": [{ "type": "table", "content": { "values": values, "label": "Http" } }],
+            "meta": {}
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.received_at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_000 + at_ms);
+        event
+    }
+
+    #[test]
+    fn pairs_laravel_http_client_payloads_into_entries() {
+        let events = vec![
+            http_event(
+                json!({
+                    "Method": "post",
+                    "URL": "https://api.example.com/orders?page=2",
+                    "Headers": "<pre class=sf-dump>array:1 [<br />  \"Content-Type\" =&gt; array:1 [<br />    0 =&gt; \"application/json\"<br />  ]<br />]</pre>",
+                    "Data": { "sku": "A-1" },
+                    "Body": "{\"sku\":\"A-1\"}",
+                    "Type": "json"
+                }),
+                0,
+            ),
+            http_event(
+                json!({ "Method": "GET", "URL": "https://api.example.com/slow", "Headers": {} }),
+                5,
+            ),
+            http_event(
+                json!({
+                    "URL": "https://api.example.com/orders?page=2",
+                    "Real Request": true,
+                    "Success": true,
+                    "Status": 201,
+                    "Headers": { "Location": ["/orders/7"] },
+                    "Body": { "id": 7 },
+                    "Size": 8,
+                    "Connection time": 0.01,
+                    "Duration": 0.25
+                }),
+                260,
+            ),
+        ];
+
+        let entries = entries(&events);
+        assert_eq!(entries.len(), 2);
+
+        let order = &entries[0];
+        assert_eq!(order["startedDateTime"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(order["time"], 250.0);
+        assert_eq!(order["request"]["method"], "POST");
+        assert_eq!(
+            order["request"]["headers"],
+            json!([{ "name": "Content-Type", "value": "application/json" }])
+        );
+        assert_eq!(
+            order["request"]["queryString"],
+            json!([{ "name": "page", "value": "2" }])
+        );
+        assert_eq!(order["request"]["postData"]["text"], "{\"sku\":\"A-1\"}");
+        assert_eq!(order["response"]["status"], 201);
+        assert_eq!(order["response"]["statusText"], "Created");
+        assert_eq!(order["response"]["redirectURL"], "/orders/7");
+        assert_eq!(order["response"]["content"]["text"], "{\"id\":7}");
+        assert_eq!(order["response"]["content"]["mimeType"], "application/json");
+
+        let unanswered = &entries[1];
+        assert_eq!(unanswered["request"]["url"], "https://api.example.com/slow");
+        assert_eq!(unanswered["response"]["status"], 0);
+
+        let document = document(entries);
+        assert_eq!(document["log"]["version"], "1.2");
+    }
+}
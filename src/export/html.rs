@@ -0,0 +1,252 @@
+//! Standalone HTML page of one event or a marked range, for sharing with
+//! people who don't run Raygun: the details tree as the details pane draws it,
+//! with the same colors and every nested node collapsible. Styles are inline,
+//! so the file opens anywhere without network access.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use html_escape::encode_text;
+
+use crate::{
+    app::{build_detail_view_for_event, civil_from_days, summarize_event},
+    config::LocaleConfig,
+    state::TimelineEvent,
+    ui::detail::{DetailLine, SegmentStyle},
+};
+
+const STYLE: &str = "
+body { background: #1e1e1e; color: #d4d4d4; font: 14px/1.5 ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; margin: 2em; }
+h1 { font-size: 1.2em; color: #9cdcfe; }
+.meta { color: #808080; }
+section { border: 1px solid #3c3c3c; border-radius: 6px; margin: 1.5em 0; padding: 0.5em 1em 1em; }
+section > header { margin-bottom: 0.75em; }
+.kind { font-weight: bold; }
+.label { background: #3c3c3c; border-radius: 3px; padding: 0 0.4em; }
+details { margin-left: 1.5em; }
+details > summary { margin-left: -1.5em; cursor: pointer; white-space: pre-wrap; }
+.line { white-space: pre-wrap; min-height: 1.5em; }
+.key { color: #4ec9b0; }
+.type { color: #dcdcaa; }
+.string { color: #6a9955; }
+.number { color: #d670d6; }
+.boolean { color: #569cd6; }
+.null { color: #808080; }
+.notice { color: #f14c4c; font-weight: bold; }
+.badge { background: #dcdcaa; color: #000; font-weight: bold; }
+.added { color: #23d18b; }
+.removed { color: #f14c4c; }
+";
+
+/// The page for `events`, oldest first, with times and numbers written for
+/// `locale`.
+pub fn document(
+    events: &[TimelineEvent],
+    generated_at: SystemTime,
+    locale: LocaleConfig,
+) -> String {
+    let title = match events {
+        [event] => summarize_event(event).summary,
+        _ => format!("{} events", events.len()),
+    };
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Raygun: {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        encode_text(&title),
+        STYLE
+    );
+    out.push_str(&format!(
+        "<h1>Raygun</h1>\n<p class=\"meta\">{} event{} · exported {}</p>\n",
+        events.len(),
+        if events.len() == 1 { "" } else { "s" },
+        timestamp(generated_at, locale)
+    ));
+    for event in events {
+        out.push_str(&section(event, locale));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn section(event: &TimelineEvent, locale: LocaleConfig) -> String {
+    let summary = summarize_event(event);
+    let detail = build_detail_view_for_event(event, None, locale);
+    let color = event
+        .color
+        .as_deref()
+        .and_then(css_color)
+        .unwrap_or("#d4d4d4");
+
+    let mut header = format!(
+        "<span class=\"kind\" style=\"color: {}\">● {}</span> {}",
+        color,
+        encode_text(&summary.kind),
+        encode_text(&summary.summary)
+    );
+    if let Some(label) = &event.label {
+        header.push_str(&format!(
+            " <span class=\"label\">{}</span>",
+            encode_text(label)
+        ));
+    }
+    header.push_str(&format!(
+        "<br><span class=\"meta\">{}",
+        timestamp(event.received_at, locale)
+    ));
+    if !detail.footer.is_empty() {
+        header.push_str(&format!(" · {}", encode_text(&detail.footer)));
+    }
+    if let Some(screen) = &event.screen {
+        header.push_str(&format!(" · screen {}", encode_text(screen)));
+    }
+    header.push_str("</span>");
+
+    format!(
+        "<section>\n<header>{}</header>\n<div class=\"tree\">\n{}</div>\n</section>\n",
+        header,
+        tree(&detail.lines)
+    )
+}
+
+/// The detail lines, with every line that has more deeply indented lines
+/// under it turned into an open `<details>` element around them.
+fn tree(lines: &[DetailLine]) -> String {
+    let mut out = String::new();
+    let mut open: Vec<usize> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        while open.last().is_some_and(|indent| line.indent <= *indent) {
+            open.pop();
+            out.push_str("</details>\n");
+        }
+        let content = segments(line);
+        let is_parent = lines
+            .get(index + 1)
+            .is_some_and(|next| next.indent > line.indent);
+        if is_parent {
+            out.push_str(&format!("<details open><summary>{}</summary>\n", content));
+            open.push(line.indent);
+        } else {
+            out.push_str(&format!("<div class=\"line\">{}</div>\n", content));
+        }
+    }
+    for _ in open {
+        out.push_str("</details>\n");
+    }
+    out
+}
+
+fn segments(line: &DetailLine) -> String {
+    line.segments
+        .iter()
+        .map(|segment| {
+            let text = encode_text(&segment.text);
+            match class(segment.style) {
+                Some(class) => format!("<span class=\"{}\">{}</span>", class, text),
+                None => text.into_owned(),
+            }
+        })
+        .collect()
+}
+
+fn class(style: SegmentStyle) -> Option<&'static str> {
+    Some(match style {
+        SegmentStyle::Plain => return None,
+        SegmentStyle::Key => "key",
+        SegmentStyle::Type => "type",
+        SegmentStyle::String => "string",
+        SegmentStyle::Number => "number",
+        SegmentStyle::Boolean => "boolean",
+        SegmentStyle::Null => "null",
+        SegmentStyle::Notice => "notice",
+        SegmentStyle::Badge => "badge",
+        SegmentStyle::Added => "added",
+        SegmentStyle::Removed => "removed",
+    })
+}
+
+/// CSS color for a Ray color name.
+fn css_color(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "green" => "#23d18b",
+        "orange" => "#f5a623",
+        "red" => "#f14c4c",
+        "purple" => "#bc3fbc",
+        "blue" => "#3b8eea",
+        "gray" => "#808080",
+        _ => return None,
+    })
+}
+
+/// `2024-03-09 14:05:01 UTC`.
+fn timestamp(time: SystemTime, locale: LocaleConfig) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!(
+        "{:04}-{:02}-{:02} {} UTC",
+        year,
+        month,
+        day,
+        locale.time_of_day(secs, None)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{protocol::RayRequest, ui::detail::DetailSegment};
+
+    #[test]
+    fn renders_a_collapsible_escaped_tree() {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{
+                "type": "json_string",
+                "content": { "value": "{\"user\":{\"name\":\"<b>Ada</b>\",\"admin\":true}}" },
+                "origin": { "file": "app/User.php", "line_number": 9 }
+            }],
+            "meta": {}
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.color = Some("red".to_string());
+        event.label = Some("users & roles".to_string());
+
+        let page = document(&[event], UNIX_EPOCH, LocaleConfig::default());
+        assert!(page.starts_with("<!DOCTYPE html>"), "{}", page);
+        assert!(page.contains("style=\"color: #f14c4c\""), "{}", page);
+        assert!(page.contains("users &amp; roles"), "{}", page);
+        assert!(page.contains("app/User.php:9"), "{}", page);
+        assert!(page.contains("&lt;b&gt;Ada&lt;/b&gt;"), "{}", page);
+        assert!(!page.contains("<b>Ada"), "{}", page);
+        assert_eq!(
+            page.matches("<details open>").count(),
+            page.matches("</details>").count()
+        );
+        assert!(
+            page.contains("<details open><summary>\"user\": {</summary>"),
+            "{}",
+            page
+        );
+
+        let line = DetailLine {
+            indent: 0,
+            segments: vec![
+                DetailSegment {
+                    text: "admin: ".to_string(),
+                    style: SegmentStyle::Key,
+                },
+                DetailSegment {
+                    text: "true".to_string(),
+                    style: SegmentStyle::Boolean,
+                },
+            ],
+        };
+        assert_eq!(
+            segments(&line),
+            "<span class=\"key\">admin: </span><span class=\"boolean\">true</span>"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! Markdown report of the filtered timeline, for pasting into an issue or pull
+//! request: events grouped by screen, each with its summary and origin, and
+//! the start of its details folded into a `<details>` block.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    app::{build_detail_view_for_event, civil_from_days, summarize_event},
+    config::LocaleConfig,
+    state::TimelineEvent,
+    ui::detail::line_text,
+};
+
+/// Detail lines quoted per event; the rest is summarized as a count.
+const SNIPPET_LINES: usize = 20;
+
+/// The report for `events`, oldest first. `filter` names the filters that
+/// picked them, if any; times and numbers are written for `locale`.
+pub fn report(
+    events: &[TimelineEvent],
+    filter: &str,
+    generated_at: SystemTime,
+    locale: LocaleConfig,
+) -> String {
+    let mut out = String::from("# Raygun report\n\n");
+    out.push_str(&format!(
+        "{} event{} · generated {}",
+        events.len(),
+        if events.len() == 1 { "" } else { "s" },
+        timestamp(generated_at, locale)
+    ));
+    if !filter.is_empty() {
+        out.push_str(&format!(" · filter `{}`", filter.replace('`', "'")));
+    }
+    out.push('\n');
+
+    // Screens in the order their first event arrived.
+    let mut screens: Vec<Option<&str>> = Vec::new();
+    for event in events {
+        if !screens.contains(&event.screen.as_deref()) {
+            screens.push(event.screen.as_deref());
+        }
+    }
+    let grouped = screens.len() > 1 || screens.first().is_some_and(Option::is_some);
+
+    for screen in screens {
+        if grouped {
+            out.push_str(&format!(
+                "\n## {}\n",
+                screen.map_or_else(|| "No screen".to_string(), escape)
+            ));
+        }
+        for event in events
+            .iter()
+            .filter(|event| event.screen.as_deref() == screen)
+        {
+            out.push('\n');
+            out.push_str(&entry(event, locale));
+        }
+    }
+    out
+}
+
+fn entry(event: &TimelineEvent, locale: LocaleConfig) -> String {
+    let summary = summarize_event(event);
+    let detail = build_detail_view_for_event(event, None, locale);
+
+    let mut heading = format!(
+        "- **{}** {}",
+        escape(&summary.kind),
+        escape(&summary.summary)
+    );
+    if let Some(label) = event
+        .label
+        .as_deref()
+        .filter(|label| *label != summary.kind)
+    {
+        heading.push_str(&format!(" · _{}_", escape(label)));
+    }
+    heading.push_str(&format!(" · {}", clock(event.received_at, locale)));
+    if !detail.footer.is_empty() {
+        heading.push_str(&format!(" · `{}`", detail.footer.replace('`', "'")));
+    }
+
+    let lines: Vec<String> = detail
+        .lines
+        .iter()
+        .take(SNIPPET_LINES)
+        .map(|line| format!("{}{}", "  ".repeat(line.indent), line_text(line)))
+        .collect();
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        return format!("{}\n", heading);
+    }
+    let mut snippet = lines.join("\n");
+    let hidden = detail.lines.len().saturating_sub(SNIPPET_LINES);
+    if hidden > 0 {
+        snippet.push_str(&format!(
+            "\n… {} more line{}",
+            hidden,
+            if hidden == 1 { "" } else { "s" }
+        ));
+    }
+    // A fence longer than any backtick run inside keeps the snippet intact.
+    let fence = "`".repeat(longest_backtick_run(&snippet).max(2) + 1);
+
+    format!(
+        "{}\n\n  <details><summary>Details</summary>\n\n  {}\n{}\n  {}\n\n  </details>\n",
+        heading,
+        fence,
+        snippet
+            .lines()
+            .map(|line| format!("  {}", line))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        fence
+    )
+}
+
+/// `text` with the characters Markdown would format escaped, on one line.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\n' | '\r' => escaped.push(' '),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0)
+}
+
+fn clock(time: SystemTime, locale: LocaleConfig) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    locale.time_of_day(secs, None)
+}
+
+/// `2024-03-09 14:05:01 UTC`.
+fn timestamp(time: SystemTime, locale: LocaleConfig) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!(
+        "{:04}-{:02}-{:02} {} UTC",
+        year,
+        month,
+        day,
+        locale.time_of_day(secs, None)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn event(kind: &str, content: serde_json::Value, screen: Option<&str>) -> TimelineEvent {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{
+                "type": kind,
+                "content": content,
+                "origin": { "file": "app/Order.php", "line_number": 42 }
+            }],
+            "meta": {}
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, screen.map(str::to_string));
+        event.received_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        event
+    }
+
+    #[test]
+    fn groups_events_by_screen_with_folded_details() {
+        let events = vec![
+            event(
+                "log",
+                json!({ "values": ["order *42* ```ready```"] }),
+                Some("checkout"),
+            ),
+            event("log", json!({ "values": ["outside"] }), None),
+        ];
+        let generated = UNIX_EPOCH + Duration::from_secs(1_700_000_100);
+        let grouped = report(&events, "kind:log", generated, LocaleConfig::default());
+
+        assert!(
+            grouped.starts_with(
+                "# Raygun report\n\n2 events · generated 2023-11-14 22:15:00 UTC · filter `kind:log`\n"
+            ),
+            "{}",
+            grouped
+        );
+        let checkout = grouped.find("## checkout").expect("screen heading");
+        let unscreened = grouped.find("## No screen").expect("fallback heading");
+        assert!(checkout < unscreened);
+        assert!(
+            grouped.contains(
+                "- **log** checkout \\| order \\*42\\* \\`\\`\\`ready\\`\\`\\` · 22:13:20 · `app/Order.php:42`"
+            ),
+            "{}",
+            grouped
+        );
+        assert!(grouped.contains("<details><summary>Details</summary>"));
+        assert!(grouped.contains("  ````\n"), "{}", grouped);
+
+        let single = report(&events[1..], "", generated, LocaleConfig::default());
+        assert!(!single.contains("## "), "{}", single);
+        assert!(!single.contains("filter"), "{}", single);
+    }
+}
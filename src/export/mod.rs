@@ -0,0 +1,5 @@
+//! Exports of the timeline to formats other tools read.
+
+pub mod har;
+pub mod html;
+pub mod markdown;
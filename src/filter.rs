@@ -0,0 +1,631 @@
+//! The filter language shared by the timeline filter bar, webhooks and saved
+//! filters, e.g. `kind:exception AND project:"shop" AND age<5m`.
+//!
+//! Terms are `field:value` comparisons (`kind`, `color`, `label`, `screen`,
+//! `host`, `project`, `client`; case-insensitive; `host` also matches
+//! `[hosts]` names, `client` the IP address a request came from),
+//! `age<5m`-style comparisons of how long ago an event arrived, or words
+//! searched for in the payload content.
+//! Terms next to each other must all match; `AND`, `OR` and `NOT` (upper case)
+//! combine them, parentheses group them, a leading `-` negates a term and
+//! double quotes group words (`\"` escapes a quote inside them).
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use thiserror::Error;
+
+use crate::{app::summarize_event, config::HostAliases, state::TimelineEvent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FilterError {
+    #[error(
+        "unknown field `{0}` (expected kind, color, label, screen, host, project, client, origin or age)"
+    )]
+    UnknownField(String),
+    #[error("`{0}:` needs a value")]
+    MissingValue(String),
+    #[error("unterminated quote")]
+    UnterminatedQuote,
+    #[error("unbalanced parenthesis")]
+    UnbalancedParenthesis,
+    #[error("`{0}` is missing a term")]
+    MissingOperand(String),
+    #[error("`{0}` is not an age comparison like age<5m or age>=2h")]
+    InvalidAge(String),
+}
+
+/// A parsed filter expression; the empty expression matches every event.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    source: String,
+    root: Node,
+    hosts: Arc<HostAliases>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    All(Vec<Node>),
+    Any(Vec<Node>),
+    Not(Box<Node>),
+    Field(Field, String),
+    Age(Comparison, Duration),
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Kind,
+    Color,
+    Label,
+    Screen,
+    Host,
+    Project,
+    Client,
+    Origin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, FilterError> {
+        let tokens = lex(expression)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let root = parser.any()?;
+        if parser.position < parser.tokens.len() {
+            return Err(FilterError::UnbalancedParenthesis);
+        }
+        Ok(Self {
+            source: expression.trim().to_string(),
+            root,
+            hosts: Arc::default(),
+        })
+    }
+
+    /// Lets `host:` terms match the friendly names in `hosts` too.
+    pub fn with_host_aliases(mut self, hosts: Arc<HostAliases>) -> Self {
+        self.hosts = hosts;
+        self
+    }
+
+    /// A filter for one field, e.g. `label:"Order placed"` from the event
+    /// actions menu.
+    pub fn field(name: &str, value: &str) -> Result<Self, FilterError> {
+        Self::parse(&format!("{}:{}", name, quote(value)))
+    }
+
+    /// The expression as typed.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(&self.root, Node::All(nodes) if nodes.is_empty())
+    }
+
+    pub fn matches(&self, event: &TimelineEvent) -> bool {
+        self.matches_at(event, SystemTime::now())
+    }
+
+    /// Whether `event` matches with `now` as the reference for `age` terms.
+    pub fn matches_at(&self, event: &TimelineEvent, now: SystemTime) -> bool {
+        let mut content = None;
+        self.root.matches(event, now, &self.hosts, &mut content)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Node {
+    /// `content` caches the lowercased payload JSON for text terms.
+    fn matches(
+        &self,
+        event: &TimelineEvent,
+        now: SystemTime,
+        hosts: &HostAliases,
+        content: &mut Option<String>,
+    ) -> bool {
+        match self {
+            Node::All(nodes) => nodes
+                .iter()
+                .all(|node| node.matches(event, now, hosts, content)),
+            Node::Any(nodes) => nodes
+                .iter()
+                .any(|node| node.matches(event, now, hosts, content)),
+            Node::Not(node) => !node.matches(event, now, hosts, content),
+            Node::Field(field, expected) => {
+                field_values(event, *field, hosts).any(|value| value.to_lowercase() == *expected)
+            }
+            Node::Age(comparison, limit) => {
+                let age = now.duration_since(event.received_at).unwrap_or_default();
+                match comparison {
+                    Comparison::Less => age < *limit,
+                    Comparison::LessOrEqual => age <= *limit,
+                    Comparison::Greater => age > *limit,
+                    Comparison::GreaterOrEqual => age >= *limit,
+                }
+            }
+            Node::Text(needle) => content
+                .get_or_insert_with(|| {
+                    serde_json::to_string(&event.request.payloads)
+                        .unwrap_or_default()
+                        .to_lowercase()
+                })
+                .contains(needle.as_str()),
+        }
+    }
+}
+
+/// `kind` matches the raw payload types as well as the kind the timeline
+/// shows, so both `kind:log` and `kind:query` work; `host` likewise matches
+/// the hostname and its friendly name, and `origin` a payload's origin file
+/// with or without its `:line`.
+fn field_values<'a>(
+    event: &'a TimelineEvent,
+    field: Field,
+    hosts: &HostAliases,
+) -> Box<dyn Iterator<Item = String> + 'a> {
+    let meta = |key: &'static str| {
+        event
+            .request
+            .meta
+            .get(key)
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+    match field {
+        Field::Kind => Box::new(
+            event
+                .request
+                .payloads
+                .iter()
+                .map(|payload| payload.kind.as_type().to_string())
+                .chain(std::iter::once(summarize_event(event).kind)),
+        ),
+        Field::Color => Box::new(event.color.clone().into_iter()),
+        Field::Label => Box::new(event.label.clone().into_iter()),
+        Field::Screen => Box::new(event.screen.clone().into_iter()),
+        Field::Host => {
+            let hostname = meta("hostname");
+            let alias = hostname
+                .as_deref()
+                .and_then(|hostname| hosts.get(hostname))
+                .map(str::to_string);
+            Box::new(hostname.into_iter().chain(alias))
+        }
+        Field::Project => Box::new(meta("project_name").into_iter()),
+        Field::Client => Box::new(
+            event
+                .remote_addr
+                .map(|addr| addr.ip().to_string())
+                .into_iter(),
+        ),
+        Field::Origin => Box::new(event.request.payloads.iter().flat_map(|payload| {
+            let origin = payload.origin.as_ref();
+            let file = origin.and_then(|origin| origin.file.clone());
+            let call_site = file.as_ref().and_then(|file| {
+                origin
+                    .and_then(|origin| origin.line_number)
+                    .map(|line| format!("{}:{}", file, line))
+            });
+            file.into_iter().chain(call_site)
+        })),
+    }
+}
+
+/// `value` as a single filter word, quoted when it has to be.
+pub fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value
+            .chars()
+            .any(|ch| ch.is_whitespace() || matches!(ch, '"' | '(' | ')' | '\\'));
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Not,
+    /// `quoted_from` is where the first quoted part starts in `text`.
+    Word {
+        text: String,
+        quoted_from: Option<usize>,
+    },
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word { text, quoted_from: None } if text == keyword)
+    }
+}
+
+fn lex(expression: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut quoted_from = None;
+    let mut in_quote = false;
+    let mut chars = expression.chars();
+
+    let flush = |tokens: &mut Vec<Token>, text: &mut String, quoted_from: &mut Option<usize>| {
+        if text.is_empty() && quoted_from.is_none() {
+            return;
+        }
+        let mut word = std::mem::take(text);
+        let mut quoted = quoted_from.take();
+        if word.len() > 1 && word.starts_with('-') && quoted != Some(0) {
+            tokens.push(Token::Not);
+            word.remove(0);
+            quoted = quoted.map(|start| start - 1);
+        }
+        tokens.push(Token::Word {
+            text: word,
+            quoted_from: quoted,
+        });
+    };
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                in_quote = !in_quote;
+                quoted_from.get_or_insert(text.len());
+            }
+            '\\' if in_quote => {
+                if let Some(escaped) = chars.next() {
+                    text.push(escaped);
+                }
+            }
+            '(' | ')' if !in_quote => {
+                flush(&mut tokens, &mut text, &mut quoted_from);
+                tokens.push(if ch == '(' { Token::Open } else { Token::Close });
+            }
+            ch if ch.is_whitespace() && !in_quote => {
+                flush(&mut tokens, &mut text, &mut quoted_from);
+            }
+            ch => text.push(ch),
+        }
+    }
+    if in_quote {
+        return Err(FilterError::UnterminatedQuote);
+    }
+    flush(&mut tokens, &mut text, &mut quoted_from);
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    /// `a OR b OR ...`
+    fn any(&mut self) -> Result<Node, FilterError> {
+        let mut nodes = vec![self.all()?];
+        while self.peek().is_some_and(|token| token.is_keyword("OR")) {
+            self.position += 1;
+            let node = self.all()?;
+            if matches!(&node, Node::All(nodes) if nodes.is_empty()) {
+                return Err(FilterError::MissingOperand("OR".into()));
+            }
+            nodes.push(node);
+        }
+        if nodes.len() > 1
+            && nodes
+                .iter()
+                .any(|node| matches!(node, Node::All(nodes) if nodes.is_empty()))
+        {
+            return Err(FilterError::MissingOperand("OR".into()));
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::Any(nodes),
+        })
+    }
+
+    /// `a AND b c ...`; adjacent terms are joined with AND.
+    fn all(&mut self) -> Result<Node, FilterError> {
+        let mut nodes = Vec::new();
+        while let Some(token) = self.peek() {
+            if matches!(token, Token::Close) || token.is_keyword("OR") {
+                break;
+            }
+            if token.is_keyword("AND") {
+                self.position += 1;
+                if nodes.is_empty()
+                    || self
+                        .peek()
+                        .is_none_or(|next| matches!(next, Token::Close) || next.is_keyword("OR"))
+                {
+                    return Err(FilterError::MissingOperand("AND".into()));
+                }
+                continue;
+            }
+            nodes.push(self.unary()?);
+        }
+        Ok(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => Node::All(nodes),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Node, FilterError> {
+        let Some(token) = self.peek() else {
+            return Err(FilterError::MissingOperand("NOT".into()));
+        };
+        if matches!(token, Token::Not) || token.is_keyword("NOT") {
+            self.position += 1;
+            if self
+                .peek()
+                .is_none_or(|next| matches!(next, Token::Close) || next.is_keyword("OR"))
+            {
+                return Err(FilterError::MissingOperand("NOT".into()));
+            }
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node, FilterError> {
+        let token = self.tokens[self.position].clone();
+        self.position += 1;
+        match token {
+            Token::Open => {
+                let node = self.any()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(FilterError::UnbalancedParenthesis);
+                }
+                self.position += 1;
+                if matches!(&node, Node::All(nodes) if nodes.is_empty()) {
+                    return Err(FilterError::MissingOperand("(".into()));
+                }
+                Ok(node)
+            }
+            Token::Close => Err(FilterError::UnbalancedParenthesis),
+            Token::Not => unreachable!("handled by unary"),
+            Token::Word { text, quoted_from } => term(&text, quoted_from),
+        }
+    }
+}
+
+fn term(text: &str, quoted_from: Option<usize>) -> Result<Node, FilterError> {
+    let unquoted = &text[..quoted_from.unwrap_or(text.len())];
+    if let Some(rest) = unquoted.strip_prefix("age")
+        && rest.starts_with(['<', '>'])
+    {
+        return age(text);
+    }
+    match unquoted.split_once(':') {
+        Some((name, _)) if !name.is_empty() && !name.contains(' ') => {
+            let value = &text[name.len() + 1..];
+            let field = match name.to_ascii_lowercase().as_str() {
+                "kind" | "type" => Field::Kind,
+                "color" => Field::Color,
+                "label" => Field::Label,
+                "screen" => Field::Screen,
+                "host" | "hostname" => Field::Host,
+                "project" => Field::Project,
+                "client" => Field::Client,
+                "origin" => Field::Origin,
+                "age" => return age(&format!("age{}", value)),
+                _ => return Err(FilterError::UnknownField(name.to_string())),
+            };
+            if value.is_empty() {
+                return Err(FilterError::MissingValue(name.to_string()));
+            }
+            Ok(Node::Field(field, value.to_lowercase()))
+        }
+        _ => Ok(Node::Text(text.to_lowercase())),
+    }
+}
+
+/// `age<5m`, `age>=2h`: units are s, m, h, d and w.
+fn age(text: &str) -> Result<Node, FilterError> {
+    let invalid = || FilterError::InvalidAge(text.to_string());
+    let rest = text.strip_prefix("age").ok_or_else(invalid)?;
+    let (comparison, amount) = if let Some(amount) = rest.strip_prefix("<=") {
+        (Comparison::LessOrEqual, amount)
+    } else if let Some(amount) = rest.strip_prefix(">=") {
+        (Comparison::GreaterOrEqual, amount)
+    } else if let Some(amount) = rest.strip_prefix('<') {
+        (Comparison::Less, amount)
+    } else if let Some(amount) = rest.strip_prefix('>') {
+        (Comparison::Greater, amount)
+    } else {
+        return Err(invalid());
+    };
+    let unit = amount.chars().last().ok_or_else(invalid)?;
+    let seconds = match unit.to_ascii_lowercase() {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let count: u64 = amount[..amount.len() - 1].parse().map_err(|_| invalid())?;
+    Ok(Node::Age(
+        comparison,
+        Duration::from_secs(count.saturating_mul(seconds)),
+    ))
+}
+
+/// Splits on whitespace outside double quotes, dropping the quotes.
+pub(crate) fn tokenize(expression: &str) -> Result<Vec<String>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for ch in expression.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            ch if ch.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            ch => current.push(ch),
+        }
+    }
+    if quoted {
+        return Err(FilterError::UnterminatedQuote);
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn event(kind: &str, text: &str, color: Option<&str>) -> TimelineEvent {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{ "type": kind, "content": { "values": [text] } }],
+            "meta": { "hostname": "web-1", "project_name": "shop" }
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.color = color.map(str::to_string);
+        event
+    }
+
+    #[test]
+    fn matches_fields_text_and_negation() {
+        let failure = event("exception", "Order 42 failed", Some("red"));
+        let info = event("log", "Order 42 shipped", Some("gray"));
+
+        let filter = Filter::parse("kind:exception").unwrap();
+        assert!(filter.matches(&failure));
+        assert!(!filter.matches(&info));
+
+        let filter = Filter::parse(r#"project:SHOP -color:gray "order 42""#).unwrap();
+        assert!(filter.matches(&failure));
+        assert!(!filter.matches(&info));
+
+        let filter = Filter::parse("host:web-1 shipped").unwrap();
+        assert!(!filter.matches(&failure));
+        assert!(filter.matches(&info));
+
+        let mut forwarded = event("log", "Order 42 shipped", None);
+        forwarded.remote_addr = Some(([203, 0, 113, 7], 0).into());
+        let filter = Filter::parse("client:203.0.113.7").unwrap();
+        assert!(filter.matches(&forwarded));
+        assert!(!filter.matches(&info));
+
+        let mut dumped = event("log", "Order 42 shipped", None);
+        let request = Arc::make_mut(&mut dumped.request);
+        request.payloads[0].origin =
+            serde_json::from_value(json!({ "file": "/srv/app/Orders.php", "line_number": 12 }))
+                .ok();
+        assert!(
+            Filter::parse("origin:/srv/app/Orders.php:12")
+                .unwrap()
+                .matches(&dumped)
+        );
+        assert!(
+            Filter::parse("origin:/srv/app/orders.php")
+                .unwrap()
+                .matches(&dumped)
+        );
+        assert!(
+            !Filter::parse("origin:/srv/app/Orders.php:13")
+                .unwrap()
+                .matches(&dumped)
+        );
+
+        assert!(Filter::parse("").unwrap().matches(&info));
+        assert_eq!(
+            Filter::parse("severity:high").unwrap_err(),
+            FilterError::UnknownField("severity".into())
+        );
+        assert_eq!(
+            Filter::parse("label:").unwrap_err(),
+            FilterError::MissingValue("label".into())
+        );
+        assert_eq!(
+            Filter::parse("\"open").unwrap_err(),
+            FilterError::UnterminatedQuote
+        );
+    }
+
+    #[test]
+    fn combines_terms_with_operators_groups_and_age() {
+        let failure = event("exception", "Order 42 failed", Some("red"));
+        let info = event("log", "Order 42 shipped", Some("gray"));
+        let now = failure.received_at + Duration::from_secs(120);
+
+        let filter = Filter::parse(r#"kind:exception AND project:"shop" AND age<5m"#).unwrap();
+        assert!(filter.matches_at(&failure, now));
+        assert!(!filter.matches_at(&info, now));
+        assert!(!filter.matches_at(&failure, now + Duration::from_secs(600)));
+
+        let filter = Filter::parse("(color:red OR color:gray) AND NOT shipped").unwrap();
+        assert!(filter.matches_at(&failure, now));
+        assert!(!filter.matches_at(&info, now));
+        assert!(Filter::parse("age>=1m").unwrap().matches_at(&info, now));
+
+        let label = Filter::field("label", r#"Say "hi" (twice)"#).unwrap();
+        let mut labelled = event("log", "hello", None);
+        labelled.label = Some(r#"say "HI" (twice)"#.into());
+        assert!(label.matches(&labelled));
+
+        assert_eq!(
+            Filter::parse("(kind:log").unwrap_err(),
+            FilterError::UnbalancedParenthesis
+        );
+        assert_eq!(
+            Filter::parse("kind:log OR").unwrap_err(),
+            FilterError::MissingOperand("OR".into())
+        );
+        assert_eq!(
+            Filter::parse("age<soon").unwrap_err(),
+            FilterError::InvalidAge("age<soon".into())
+        );
+    }
+
+    #[test]
+    fn host_terms_match_friendly_names() {
+        let info = event("log", "Order 42 shipped", None);
+        let hosts = Arc::new(HostAliases::new(
+            &[("WEB-1".to_string(), "Storefront".to_string())].into(),
+        ));
+
+        let filter = Filter::parse("host:storefront").unwrap();
+        assert!(!filter.matches(&info));
+        let filter = filter.with_host_aliases(Arc::clone(&hosts));
+        assert!(filter.matches(&info));
+
+        let filter = Filter::parse("host:web-1")
+            .unwrap()
+            .with_host_aliases(hosts);
+        assert!(filter.matches(&info));
+    }
+}
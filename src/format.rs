@@ -0,0 +1,33 @@
+//! Formatting shared by the UI and the parts of Raygun that answer clients,
+//! which always write numbers the same way whatever `[locale]` says.
+
+const BYTE_UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+/// `bytes` scaled down in 1024 steps, with the unit it ended up in.
+pub fn scaled_bytes(mut bytes: f64) -> (f64, &'static str) {
+    let mut unit_index = 0;
+    while bytes >= 1024.0 && unit_index + 1 < BYTE_UNITS.len() {
+        bytes /= 1024.0;
+        unit_index += 1;
+    }
+    (bytes, BYTE_UNITS[unit_index])
+}
+
+/// `1.50 MB` style byte count with a `.` decimal point.
+pub fn bytes(bytes: f64) -> String {
+    let (value, unit) = scaled_bytes(bytes);
+    format!("{:.2} {}", value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_byte_counts_by_1024() {
+        assert_eq!(bytes(512.0), "512.00 B");
+        assert_eq!(bytes(1536.0), "1.50 KB");
+        assert_eq!(bytes(5.0 * 1024.0 * 1024.0), "5.00 MB");
+        assert_eq!(scaled_bytes(2048.0 * 1024f64.powi(4)), (2048.0, "TB"));
+    }
+}
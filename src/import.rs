@@ -0,0 +1,380 @@
+//! Loads captured Ray requests back into the timeline (`--import`), so
+//! payloads saved with the Ray desktop app, `--debug-dump` or the event export
+//! can be browsed in Raygun, and reads requests piped into stdin (`--stdin`).
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{Value, json};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    task::JoinHandle,
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    protocol::{PayloadKind, RayRequest},
+    state::{AppState, Provenance, TimelineEvent},
+};
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("no Ray requests found in {path}")]
+    Empty { path: PathBuf },
+}
+
+/// Outcome of importing one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Imported {
+    pub requests: usize,
+    /// Lines or entries that looked like requests but could not be read.
+    pub skipped: usize,
+}
+
+/// Records every request found in `path`, in file order. Requests whose meta
+/// carries a client timestamp keep it as their receive time.
+pub async fn import_file(state: &AppState, path: &Path) -> Result<Imported, ImportError> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|source| ImportError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+    let (requests, skipped) = read_requests(&text);
+    if requests.is_empty() {
+        return Err(ImportError::Empty {
+            path: path.to_path_buf(),
+        });
+    }
+
+    let imported = Imported {
+        requests: requests.len(),
+        skipped,
+    };
+    for request in requests {
+        record(state, request).await;
+    }
+    Ok(imported)
+}
+
+async fn record(state: &AppState, request: RayRequest) {
+    let provenance = Provenance {
+        remote_addr: None,
+        proxy_addr: None,
+        received_at: request.sent_at().unwrap_or_else(SystemTime::now),
+        headers: request
+            .meta
+            .get("received_headers")
+            .and_then(|headers| serde_json::from_value(headers.clone()).ok())
+            .unwrap_or_default(),
+    };
+    state.record_request_from(request, provenance).await;
+}
+
+/// The event's request with its receive time as `meta.sent_at` when the
+/// client sent none, and its captured HTTP headers as
+/// `meta.received_headers`, so an import puts it back where it was. A label or
+/// color set after the request arrived replaces the request's `label` or
+/// `color` payloads.
+pub fn exported_request(event: &TimelineEvent) -> Value {
+    let mut request = serde_json::to_value(&*event.request).unwrap_or(Value::Null);
+    replace_tag(
+        &mut request,
+        event,
+        PayloadKind::Label,
+        event.label.as_deref(),
+    );
+    replace_tag(
+        &mut request,
+        event,
+        PayloadKind::Color,
+        event.color.as_deref(),
+    );
+    let Some(meta) = request.get_mut("meta").and_then(Value::as_object_mut) else {
+        return request;
+    };
+    if event.request.sent_at().is_none()
+        && let Ok(received) = event.received_at.duration_since(UNIX_EPOCH)
+    {
+        meta.insert("sent_at".into(), received.as_secs_f64().into());
+    }
+    if !event.headers.is_empty() {
+        meta.insert("received_headers".into(), json!(event.headers));
+    }
+    request
+}
+
+/// Rewrites the `label` or `color` payloads of `request` to carry `value` when
+/// the request itself sent a different one.
+fn replace_tag(request: &mut Value, event: &TimelineEvent, kind: PayloadKind, value: Option<&str>) {
+    let name = kind.as_type();
+    let sent = event
+        .request
+        .payloads
+        .iter()
+        .filter(|payload| payload.kind == kind)
+        .filter_map(|payload| payload.content_string(name))
+        .next_back();
+    if value == sent {
+        return;
+    }
+    if let Some(payloads) = request.get_mut("payloads").and_then(Value::as_array_mut) {
+        payloads.retain(|payload| payload["type"] != name);
+        if let Some(value) = value {
+            payloads.push(json!({ "type": name, "content": { name: value } }));
+        }
+    }
+}
+
+/// Feeds requests piped into stdin (`--stdin`) to the timeline as they arrive,
+/// until stdin closes.
+pub fn spawn_stdin(state: Arc<AppState>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stream = StreamReader::default();
+        let mut requests = 0;
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    for request in stream.push_line(&line) {
+                        requests += 1;
+                        record(&state, request).await;
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    warn!(?err, "failed to read stdin");
+                    break;
+                }
+            }
+        }
+        let skipped = stream.finish();
+        info!(requests, skipped, "stdin closed");
+    })
+}
+
+/// Splits a stream of lines into JSON documents: one per line (NDJSON), or
+/// documents spanning several lines such as a pretty-printed export.
+#[derive(Debug, Default)]
+struct StreamReader {
+    pending: String,
+    skipped: usize,
+}
+
+impl StreamReader {
+    /// Requests completed by `line`. A line that cannot continue the pending
+    /// document drops it and is counted as skipped.
+    fn push_line(&mut self, line: &str) -> Vec<RayRequest> {
+        if self.pending.is_empty() && line.trim().is_empty() {
+            return Vec::new();
+        }
+        self.pending.push_str(line);
+        self.pending.push('\n');
+
+        let mut requests = Vec::new();
+        match serde_json::from_str::<Value>(&self.pending) {
+            Ok(document) => collect(document, &mut requests, &mut self.skipped),
+            Err(err) if err.is_eof() => return requests,
+            Err(_) => self.skipped += 1,
+        }
+        self.pending.clear();
+        requests
+    }
+
+    /// Documents skipped over the whole stream, including one left unfinished.
+    fn finish(mut self) -> usize {
+        if !self.pending.trim().is_empty() {
+            self.skipped += 1;
+            self.pending.clear();
+        }
+        self.skipped
+    }
+}
+
+/// Extracts Ray requests from a capture and counts the entries it had to skip.
+///
+/// Accepts a single JSON document (one request, an array of them, or any
+/// object nesting such arrays, e.g. requests grouped by screen) or JSON Lines
+/// with one document per line. Requests saved without a `uuid` get a fresh one.
+pub fn read_requests(text: &str) -> (Vec<RayRequest>, usize) {
+    let mut requests = Vec::new();
+    let mut skipped = 0;
+
+    match serde_json::from_str::<Value>(text) {
+        Ok(document) => collect(document, &mut requests, &mut skipped),
+        Err(_) => {
+            for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                match serde_json::from_str::<Value>(line) {
+                    Ok(document) => collect(document, &mut requests, &mut skipped),
+                    Err(_) => skipped += 1,
+                }
+            }
+        }
+    }
+
+    (requests, skipped)
+}
+
+fn collect(value: Value, requests: &mut Vec<RayRequest>, skipped: &mut usize) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect(item, requests, skipped);
+            }
+        }
+        Value::Object(mut object) if object.get("payloads").is_some_and(Value::is_array) => {
+            if !object.get("uuid").is_some_and(Value::is_string) {
+                object.insert("uuid".into(), Value::String(Uuid::new_v4().to_string()));
+            }
+            match serde_json::from_value(Value::Object(object)) {
+                Ok(request) => requests.push(request),
+                Err(_) => *skipped += 1,
+            }
+        }
+        Value::Object(object) => {
+            for (_, value) in object {
+                if value.is_array() || value.is_object() {
+                    collect(value, requests, skipped);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_json_lines_and_nested_exports() {
+        let lines = concat!(
+            r#"{"uuid":"a","payloads":[{"type":"log","content":{"values":["one"]}}],"meta":{}}"#,
+            "\n\nnot json\n",
+            r#"{"uuid":"b","payloads":[{"type":"log","content":{"values":["two"]}}],"meta":{"sent_at":1700000000}}"#,
+            "\n",
+        );
+        let (requests, skipped) = read_requests(lines);
+        assert_eq!(skipped, 1);
+        let uuids: Vec<_> = requests
+            .iter()
+            .map(|request| request.uuid.as_str())
+            .collect();
+        assert_eq!(uuids, ["a", "b"]);
+        assert!(requests[1].sent_at().is_some());
+
+        let export = r#"{
+            "version": 2,
+            "screens": {
+                "Screen 1": [
+                    {"payloads": [{"type": "log", "content": {"values": ["saved"]}}]},
+                    {"uuid": "c", "payloads": [{"type": "color", "content": {"color": "red"}}]}
+                ]
+            }
+        }"#;
+        let (requests, skipped) = read_requests(export);
+        assert_eq!((requests.len(), skipped), (2, 0));
+        assert!(!requests[0].uuid.is_empty());
+        assert_eq!(requests[1].uuid, "c");
+    }
+
+    #[test]
+    fn exported_requests_keep_their_receive_time() {
+        let request: RayRequest = serde_json::from_value(serde_json::json!({
+            "uuid": "a",
+            "payloads": [{ "type": "log", "content": { "values": ["one"] } }],
+            "meta": {}
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.received_at = UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        event.headers = vec![("user-agent".into(), "GuzzleHttp/7".into())];
+
+        let line = serde_json::to_string(&exported_request(&event)).unwrap();
+        let (requests, _) = read_requests(&line);
+        assert_eq!(requests[0].sent_at(), Some(event.received_at));
+        assert_eq!(
+            requests[0].meta["received_headers"],
+            serde_json::json!([["user-agent", "GuzzleHttp/7"]])
+        );
+    }
+
+    #[tokio::test]
+    async fn imports_requests_with_unusable_timestamps() {
+        let line = r#"{"uuid":"a","payloads":[{"type":"log","content":{"values":["one"]}}],"meta":{"sent_at":1e300}}"#;
+        let (requests, _) = read_requests(line);
+        let state = AppState::default();
+        for request in requests {
+            record(&state, request).await;
+        }
+
+        let event = state.timeline_snapshot().await.remove(0);
+        assert_eq!(event.latency_micros(), None);
+        let exported = exported_request(&event);
+        let (requests, _) = read_requests(&exported.to_string());
+        assert!(requests[0].sent_at().is_some());
+    }
+
+    #[test]
+    fn exported_requests_carry_labels_and_colors_set_later() {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "a",
+            "payloads": [
+                { "type": "log", "content": { "values": ["one"] } },
+                { "type": "label", "content": { "label": "sent" } }
+            ],
+            "meta": { "sent_at": 1_700_000_000 }
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.label = Some("sent".into());
+        assert_eq!(
+            exported_request(&event)["payloads"][1]["content"]["label"],
+            "sent"
+        );
+
+        event.label = Some("checkout".into());
+        let exported = exported_request(&event);
+        assert_eq!(exported["payloads"].as_array().unwrap().len(), 2);
+        assert_eq!(exported["payloads"][1]["content"]["label"], "checkout");
+
+        event.label = None;
+        assert_eq!(
+            exported_request(&event)["payloads"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn streams_json_lines_and_multi_line_documents() {
+        let mut stream = StreamReader::default();
+        let line = r#"{"uuid":"a","payloads":[{"type":"log","content":{"values":["one"]}}]}"#;
+        assert_eq!(stream.push_line(line).len(), 1);
+        assert!(stream.push_line("").is_empty());
+
+        assert!(stream.push_line("{").is_empty());
+        assert!(stream.push_line(r#"  "uuid": "b","#).is_empty());
+        let requests = stream.push_line(r#"  "payloads": [] }"#);
+        assert_eq!(requests[0].uuid, "b");
+
+        assert!(stream.push_line("not json").is_empty());
+        assert_eq!(stream.push_line(line).len(), 1);
+        assert!(stream.push_line(r#"{"uuid": "c","#).is_empty());
+        assert_eq!(stream.finish(), 2);
+    }
+}
@@ -0,0 +1,148 @@
+//! Automatic archives: events received since the last archive are appended to
+//! a timestamped JSON Lines file every few minutes and before retention evicts
+//! them, so a session survives without a manual export.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use tokio::{
+    fs,
+    io::AsyncWriteExt,
+    select,
+    sync::{broadcast::error::RecvError, watch},
+    time::{self, Instant, Interval},
+};
+use tracing::{info, warn};
+
+use crate::{
+    app::timestamped_file_name,
+    config::ArchiveConfig,
+    import::exported_request,
+    state::{AppState, TimelineEvent, bus::BusMessage},
+};
+
+const FILE_STEM: &str = "raygun-archive";
+
+pub(super) fn spawn(state: Arc<AppState>, config: ArchiveConfig, stop: watch::Receiver<bool>) {
+    tokio::spawn(async move {
+        let (_, mut changes) = state.subscribe().await;
+        let stopped = super::stopped(stop);
+        tokio::pin!(stopped);
+        let dir = config.dir.clone().unwrap_or_else(|| PathBuf::from("."));
+        let mut interval = config.every_minutes.map(|minutes| {
+            let period = Duration::from_secs(minutes * 60);
+            time::interval_at(Instant::now() + period, period)
+        });
+        // Events not archived yet, oldest first.
+        let mut pending: Vec<TimelineEvent> = Vec::new();
+        loop {
+            select! {
+                change = changes.recv() => match change {
+                    Ok(BusMessage::Recorded { event }) => pending.push(event),
+                    Ok(BusMessage::Updated { event }) => {
+                        if let Some(slot) = pending.iter_mut().find(|pending| pending.id == event.id) {
+                            *slot = event;
+                        }
+                    }
+                    Ok(BusMessage::Removed { id }) => {
+                        let evicted = state.timeline_len().await >= state.retention();
+                        if config.on_evict && evicted && pending.iter().any(|event| event.id == id) {
+                            archive(&dir, &config, &mut pending).await;
+                        }
+                    }
+                    Ok(BusMessage::Cleared) => {
+                        if config.on_evict {
+                            archive(&dir, &config, &mut pending).await;
+                        }
+                    }
+                    Ok(BusMessage::LockChanged { .. }) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "archive fell behind; some events were not archived");
+                    }
+                    Err(RecvError::Closed) => {
+                        archive(&dir, &config, &mut pending).await;
+                        break;
+                    }
+                },
+                _ = tick(interval.as_mut()) => archive(&dir, &config, &mut pending).await,
+                _ = &mut stopped => {
+                    archive(&dir, &config, &mut pending).await;
+                    break;
+                }
+            }
+        }
+    });
+}
+
+async fn tick(interval: Option<&mut Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Appends `pending` to the archive file for the current second and prunes
+/// old archives. Events stay pending if the file cannot be written.
+async fn archive(dir: &Path, config: &ArchiveConfig, pending: &mut Vec<TimelineEvent>) {
+    if pending.is_empty() {
+        return;
+    }
+
+    let path = dir.join(timestamped_file_name(FILE_STEM, "jsonl", SystemTime::now()));
+    let lines: String = pending
+        .iter()
+        .filter_map(|event| serde_json::to_string(&exported_request(event)).ok())
+        .map(|line| line + "\n")
+        .collect();
+    let written = async {
+        fs::create_dir_all(dir).await?;
+        // Two archives in the same second share a file.
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        file.write_all(lines.as_bytes()).await
+    };
+    match written.await {
+        Ok(()) => {
+            info!(events = pending.len(), path = %path.display(), "archived timeline");
+            pending.clear();
+        }
+        Err(err) => {
+            warn!(?err, path = %path.display(), "failed to write timeline archive");
+            return;
+        }
+    }
+
+    if let Some(keep) = config.keep {
+        prune(dir, keep).await;
+    }
+}
+
+/// Deletes all but the newest `keep` archives in `dir`.
+async fn prune(dir: &Path, keep: usize) {
+    let Ok(mut entries) = fs::read_dir(dir).await else {
+        return;
+    };
+    let mut archives = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(FILE_STEM) && name.ends_with(".jsonl") {
+            archives.push(name);
+        }
+    }
+    // Timestamped names sort chronologically.
+    archives.sort();
+    let excess = archives.len().saturating_sub(keep);
+    for name in &archives[..excess] {
+        if let Err(err) = fs::remove_file(dir.join(name)).await {
+            warn!(?err, file = %name, "failed to delete an old archive");
+        }
+    }
+}
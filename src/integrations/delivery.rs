@@ -0,0 +1,85 @@
+//! The delivery queue shared by integrations: requests are POSTed one at a
+//! time, retrying failed deliveries with exponential backoff.
+
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Deliveries waiting to be sent; further ones are dropped while full.
+pub(super) const QUEUE_CAPACITY: usize = 256;
+const MAX_ATTEMPTS: u32 = 4;
+/// Delay before the first retry; doubled for every further attempt.
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(super) struct Delivery {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+/// Starts a worker draining a new queue and returns its sending half.
+pub(super) fn spawn() -> mpsc::Sender<Delivery> {
+    let (queue, pending) = mpsc::channel(QUEUE_CAPACITY);
+    tokio::spawn(deliver(pending, BASE_BACKOFF));
+    queue
+}
+
+/// Queues `delivery`, dropping it with a warning when the queue is full.
+pub(super) fn enqueue(queue: &mpsc::Sender<Delivery>, delivery: Delivery) {
+    if let Err(err) = queue.try_send(delivery) {
+        let delivery = err.into_inner();
+        warn!(url = %delivery.url, "delivery queue is full; dropping a request");
+    }
+}
+
+pub(super) async fn deliver(mut pending: mpsc::Receiver<Delivery>, base_backoff: Duration) {
+    let client = match reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("raygun/", env!("CARGO_PKG_VERSION")))
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            warn!(?err, "failed to create the integrations HTTP client");
+            return;
+        }
+    };
+
+    while let Some(delivery) = pending.recv().await {
+        let mut backoff = base_backoff;
+        for attempt in 1..=MAX_ATTEMPTS {
+            let mut request = client.post(&delivery.url).json(&delivery.body);
+            for (name, value) in &delivery.headers {
+                request = request.header(name, value);
+            }
+            let result = request.send().await;
+            let retry = match &result {
+                Ok(response) if response.status().is_success() => {
+                    debug!(url = %delivery.url, "delivered");
+                    break;
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(_) => true,
+            };
+
+            if !retry || attempt == MAX_ATTEMPTS {
+                match result {
+                    Ok(response) => {
+                        warn!(url = %delivery.url, status = %response.status(), attempt, "delivery rejected")
+                    }
+                    Err(err) => warn!(url = %delivery.url, %err, attempt, "delivery failed"),
+                }
+                break;
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
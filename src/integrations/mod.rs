@@ -0,0 +1,79 @@
+//! Outgoing integrations that forward timeline events to other services.
+//! Integrations follow the timeline on the event bus ([`AppState::subscribe`]),
+//! so they work the same under the TUI and `raygun daemon`.
+
+mod archive;
+mod delivery;
+mod otlp;
+mod webhook;
+
+use std::sync::Arc;
+
+use tokio::sync::watch;
+use tracing::warn;
+
+use crate::{
+    config::{FileConfig, HostAliases, WebhookFormat},
+    filter::Filter,
+    state::AppState,
+};
+
+/// The running integrations; they keep running if this is dropped.
+#[derive(Debug)]
+pub struct Integrations {
+    stop: watch::Sender<bool>,
+}
+
+impl Integrations {
+    /// Stops every integration, e.g. before starting them again from a
+    /// reloaded config. Archives write what they have first.
+    pub fn stop(self) {
+        let _ = self.stop.send(true);
+    }
+}
+
+/// Resolves once [`Integrations::stop`] is called; never if the handle is
+/// dropped instead.
+async fn stopped(mut stop: watch::Receiver<bool>) {
+    if stop.wait_for(|stop| *stop).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Starts every integration configured in `file_config`.
+pub fn spawn(state: &Arc<AppState>, file_config: &FileConfig) -> Integrations {
+    let (stop, stop_rx) = watch::channel(false);
+    let hosts = Arc::new(HostAliases::new(&file_config.hosts));
+    let hooks: Vec<webhook::Webhook> = file_config
+        .webhooks
+        .iter()
+        .filter_map(|config| match Filter::parse(&config.filter) {
+            Ok(matcher) => Some(webhook::Webhook {
+                format: config.format.unwrap_or_else(|| {
+                    if config.url.contains("hooks.slack.com") {
+                        WebhookFormat::Slack
+                    } else {
+                        WebhookFormat::Json
+                    }
+                }),
+                url: config.url.clone(),
+                matcher: matcher.with_host_aliases(Arc::clone(&hosts)),
+            }),
+            Err(err) => {
+                warn!(url = %config.url, %err, "skipping webhook with an invalid filter");
+                None
+            }
+        })
+        .collect();
+
+    if !hooks.is_empty() {
+        webhook::spawn(Arc::clone(state), hooks, stop_rx.clone());
+    }
+    if let Some(otlp) = &file_config.otlp {
+        otlp::spawn(Arc::clone(state), otlp.clone(), stop_rx.clone());
+    }
+    if let Some(archive) = &file_config.archive {
+        archive::spawn(Arc::clone(state), archive.clone(), stop_rx);
+    }
+    Integrations { stop }
+}
@@ -0,0 +1,455 @@
+//! OTLP/HTTP export: every event becomes a log record, `measure` payloads
+//! become spans covering the measured time and exceptions become error spans
+//! carrying an `exception` event. Batches are sent as OTLP JSON through the
+//! delivery queue.
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{Value, json};
+use tokio::{
+    select,
+    sync::{broadcast::error::RecvError, mpsc, watch},
+};
+use tracing::warn;
+
+use super::delivery::{self, Delivery};
+use crate::{
+    app::summarize_event,
+    config::OtlpConfig,
+    protocol::{Payload, PayloadKind},
+    state::{AppState, TimelineEvent, bus::BusMessage},
+};
+
+/// Events exported per request.
+const BATCH_SIZE: usize = 128;
+/// How long an incomplete batch waits before it is sent anyway.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// OTLP severity numbers and span status codes.
+const SEVERITY_INFO: u8 = 9;
+const SEVERITY_WARN: u8 = 13;
+const SEVERITY_ERROR: u8 = 17;
+const STATUS_ERROR: u8 = 2;
+const SPAN_KIND_INTERNAL: u8 = 1;
+
+pub(super) fn spawn(state: Arc<AppState>, config: OtlpConfig, stop: watch::Receiver<bool>) {
+    let queue = delivery::spawn();
+    tokio::spawn(async move {
+        let (_, mut changes) = state.subscribe().await;
+        let stopped = super::stopped(stop);
+        tokio::pin!(stopped);
+        let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+        let mut batch = Vec::new();
+        loop {
+            select! {
+                change = changes.recv() => match change {
+                    Ok(BusMessage::Recorded { event }) => {
+                        batch.push(event);
+                        if batch.len() >= BATCH_SIZE {
+                            export(&config, &std::mem::take(&mut batch), &queue);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "OTLP export fell behind; some events were not exported");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = flush.tick() => {
+                    if !batch.is_empty() {
+                        export(&config, &std::mem::take(&mut batch), &queue);
+                    }
+                }
+                _ = &mut stopped => {
+                    if !batch.is_empty() {
+                        export(&config, &batch, &queue);
+                    }
+                    break;
+                }
+            }
+        }
+    });
+}
+
+fn export(config: &OtlpConfig, events: &[TimelineEvent], queue: &mpsc::Sender<Delivery>) {
+    let endpoint = config.endpoint.trim_end_matches('/');
+    let headers: Vec<(String, String)> = config
+        .headers
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+
+    delivery::enqueue(
+        queue,
+        Delivery {
+            url: format!("{}/v1/logs", endpoint),
+            headers: headers.clone(),
+            body: logs_body(config, events),
+        },
+    );
+    if let Some(body) = traces_body(config, events) {
+        delivery::enqueue(
+            queue,
+            Delivery {
+                url: format!("{}/v1/traces", endpoint),
+                headers,
+                body,
+            },
+        );
+    }
+}
+
+fn logs_body(config: &OtlpConfig, events: &[TimelineEvent]) -> Value {
+    let records: Vec<Value> = events
+        .iter()
+        .map(|event| {
+            let entry = summarize_event(event);
+            let time = unix_nanos(event.received_at);
+            let severity = severity(event);
+            let mut record = json!({
+                "timeUnixNano": time,
+                "observedTimeUnixNano": time,
+                "severityNumber": severity,
+                "severityText": match severity {
+                    SEVERITY_ERROR => "ERROR",
+                    SEVERITY_WARN => "WARN",
+                    _ => "INFO",
+                },
+                "body": { "stringValue": entry.summary },
+                "attributes": event_attributes(event, &entry.kind),
+                "traceId": trace_id(event),
+            });
+            // Link the record to the event's first span, when it has one.
+            if spans(event).next().is_some() {
+                record["spanId"] = json!(span_id(event, 0));
+            }
+            record
+        })
+        .collect();
+
+    json!({
+        "resourceLogs": [{
+            "resource": resource(config),
+            "scopeLogs": [{ "scope": scope(), "logRecords": records }],
+        }]
+    })
+}
+
+fn traces_body(config: &OtlpConfig, events: &[TimelineEvent]) -> Option<Value> {
+    let spans: Vec<Value> = events
+        .iter()
+        .flat_map(|event| {
+            spans(event)
+                .enumerate()
+                .map(move |(index, payload)| span(event, index, payload))
+        })
+        .collect();
+    if spans.is_empty() {
+        return None;
+    }
+
+    Some(json!({
+        "resourceSpans": [{
+            "resource": resource(config),
+            "scopeSpans": [{ "scope": scope(), "spans": spans }],
+        }]
+    }))
+}
+
+/// Payloads exported as spans: finished measurements and exceptions.
+fn spans(event: &TimelineEvent) -> impl Iterator<Item = &Payload> {
+    event
+        .request
+        .payloads
+        .iter()
+        .filter(|payload| match payload.kind {
+            PayloadKind::Measure => measured_millis(payload).is_some(),
+            PayloadKind::Exception => true,
+            _ => false,
+        })
+}
+
+fn span(event: &TimelineEvent, index: usize, payload: &Payload) -> Value {
+    let end = unix_nanos(event.received_at);
+    let mut attributes = origin_attributes(payload);
+
+    if payload.kind == PayloadKind::Exception {
+        let class = payload.content_string("class").unwrap_or("Exception");
+        let message = payload.content_string("message").unwrap_or_default();
+        return json!({
+            "traceId": trace_id(event),
+            "spanId": span_id(event, index),
+            "name": format!("exception {}", class),
+            "kind": SPAN_KIND_INTERNAL,
+            "startTimeUnixNano": end,
+            "endTimeUnixNano": end,
+            "attributes": attributes,
+            "status": { "code": STATUS_ERROR, "message": message },
+            "events": [{
+                "name": "exception",
+                "timeUnixNano": end,
+                "attributes": [
+                    string_attribute("exception.type", class),
+                    string_attribute("exception.message", message),
+                    string_attribute("exception.stacktrace", &stacktrace(payload)),
+                ],
+            }],
+        });
+    }
+
+    let name = payload.content_string("name").unwrap_or("measure");
+    let millis = measured_millis(payload).unwrap_or_default();
+    let end_nanos = nanos_since_epoch(event.received_at);
+    let start = end_nanos.saturating_sub((millis * 1_000_000.0) as u64);
+    let content = payload.content();
+    for (key, attribute) in [
+        (
+            "max_memory_usage_during_total_time",
+            "raygun.measure.max_memory_bytes",
+        ),
+        (
+            "time_since_last_call",
+            "raygun.measure.time_since_last_call_ms",
+        ),
+    ] {
+        if let Some(value) = content.get(key).and_then(Value::as_f64) {
+            attributes.push(json!({ "key": attribute, "value": { "doubleValue": value } }));
+        }
+    }
+
+    json!({
+        "traceId": trace_id(event),
+        "spanId": span_id(event, index),
+        "name": name,
+        "kind": SPAN_KIND_INTERNAL,
+        "startTimeUnixNano": start.to_string(),
+        "endTimeUnixNano": end,
+        "attributes": attributes,
+    })
+}
+
+/// Milliseconds covered by a `measure` payload; `None` for a timer that was
+/// only just started.
+fn measured_millis(payload: &Payload) -> Option<f64> {
+    let content = payload.content();
+    if content.get("is_new_timer").and_then(Value::as_bool) == Some(true) {
+        return None;
+    }
+    content
+        .get("total_time")
+        .and_then(Value::as_f64)
+        .filter(|millis| millis.is_finite() && *millis >= 0.0)
+}
+
+fn severity(event: &TimelineEvent) -> u8 {
+    if event
+        .request
+        .payloads
+        .iter()
+        .any(|payload| payload.kind == PayloadKind::Exception)
+    {
+        return SEVERITY_ERROR;
+    }
+    match event.color.as_deref() {
+        Some("red") => SEVERITY_ERROR,
+        Some("orange") => SEVERITY_WARN,
+        _ => SEVERITY_INFO,
+    }
+}
+
+fn event_attributes(event: &TimelineEvent, kind: &str) -> Vec<Value> {
+    let mut attributes = vec![
+        string_attribute("raygun.event.id", &event.id.to_string()),
+        string_attribute("raygun.kind", kind),
+    ];
+    for (key, value) in [
+        ("raygun.color", event.color.as_deref()),
+        ("raygun.label", event.label.as_deref()),
+        ("raygun.screen", event.screen.as_deref()),
+        ("host.name", meta_string(event, "hostname")),
+        ("raygun.project", meta_string(event, "project_name")),
+        ("user_agent.original", event.header("user-agent")),
+    ] {
+        if let Some(value) = value {
+            attributes.push(string_attribute(key, value));
+        }
+    }
+    if let Some(addr) = event.remote_addr {
+        attributes.push(string_attribute("client.address", &addr.ip().to_string()));
+    }
+    if let Some(payload) = event.request.payloads.first() {
+        attributes.extend(origin_attributes(payload));
+    }
+    attributes
+}
+
+fn origin_attributes(payload: &Payload) -> Vec<Value> {
+    let mut attributes = Vec::new();
+    if let Some(origin) = &payload.origin {
+        if let Some(file) = &origin.file {
+            attributes.push(string_attribute("code.filepath", file));
+        }
+        if let Some(line) = origin.line_number {
+            attributes
+                .push(json!({ "key": "code.lineno", "value": { "intValue": line.to_string() } }));
+        }
+    }
+    attributes
+}
+
+/// PHP-style frames, innermost first: `#0 /app/User.php(12): App\User->save()`.
+fn stacktrace(payload: &Payload) -> String {
+    let Some(frames) = payload.content().get("frames").and_then(Value::as_array) else {
+        return String::new();
+    };
+    frames
+        .iter()
+        .enumerate()
+        .map(|(index, frame)| {
+            let field = |key: &str| frame.get(key).and_then(Value::as_str).unwrap_or_default();
+            let line = frame
+                .get("line_number")
+                .and_then(Value::as_u64)
+                .unwrap_or_default();
+            let call = match field("class") {
+                "" => field("method").to_string(),
+                class => format!("{}->{}", class, field("method")),
+            };
+            format!("#{} {}({}): {}()", index, field("file_name"), line, call)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn meta_string<'a>(event: &'a TimelineEvent, key: &str) -> Option<&'a str> {
+    event.request.meta.get(key).and_then(Value::as_str)
+}
+
+fn resource(config: &OtlpConfig) -> Value {
+    json!({ "attributes": [string_attribute("service.name", &config.service_name)] })
+}
+
+fn scope() -> Value {
+    json!({ "name": "raygun", "version": env!("CARGO_PKG_VERSION") })
+}
+
+fn string_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Each event is its own trace, identified by the event id.
+fn trace_id(event: &TimelineEvent) -> String {
+    event.id.simple().to_string()
+}
+
+fn span_id(event: &TimelineEvent, index: usize) -> String {
+    let bytes = event.id.as_bytes();
+    let low = u64::from_be_bytes(bytes[8..].try_into().expect("8 bytes"));
+    // All-zero span ids are invalid.
+    format!("{:016x}", low.wrapping_add(index as u64).max(1))
+}
+
+/// Nanoseconds since the epoch as a string, the way OTLP JSON encodes 64-bit
+/// integers.
+fn unix_nanos(time: SystemTime) -> String {
+    nanos_since_epoch(time).to_string()
+}
+
+fn nanos_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn event(payloads: Value) -> TimelineEvent {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": payloads,
+            "meta": { "hostname": "web-1" }
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.received_at = UNIX_EPOCH + Duration::from_secs(10);
+        event
+    }
+
+    fn config() -> OtlpConfig {
+        OtlpConfig {
+            endpoint: "http://localhost:4318".into(),
+            service_name: "shop".into(),
+            headers: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn measures_become_spans_and_exceptions_error_events() {
+        let measure = event(json!([{
+            "type": "measure",
+            "content": { "name": "import", "is_new_timer": false, "total_time": 1500.0 }
+        }]));
+        let timer_start = event(json!([{
+            "type": "measure",
+            "content": { "name": "import", "is_new_timer": true, "total_time": 0 }
+        }]));
+        let exception = event(json!([{
+            "type": "exception",
+            "content": {
+                "class": "RuntimeException",
+                "message": "boom",
+                "frames": [{ "file_name": "/app/User.php", "line_number": 12, "class": "App\\User", "method": "save" }]
+            },
+            "origin": { "file": "/app/User.php", "line_number": 12 }
+        }]));
+        let events = [measure, timer_start, exception];
+
+        let traces = traces_body(&config(), &events).expect("spans exported");
+        let spans = traces["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+
+        assert_eq!(spans[0]["name"], "import");
+        assert_eq!(spans[0]["startTimeUnixNano"], "8500000000");
+        assert_eq!(spans[0]["endTimeUnixNano"], "10000000000");
+        assert_eq!(spans[0]["traceId"], events[0].id.simple().to_string());
+
+        assert_eq!(spans[1]["name"], "exception RuntimeException");
+        assert_eq!(spans[1]["status"]["code"], STATUS_ERROR);
+        let exception_event = &spans[1]["events"][0];
+        assert_eq!(exception_event["name"], "exception");
+        assert_eq!(
+            exception_event["attributes"][2]["value"]["stringValue"],
+            "#0 /app/User.php(12): App\\User->save()"
+        );
+
+        let logs = logs_body(&config(), &events);
+        assert_eq!(
+            logs["resourceLogs"][0]["resource"]["attributes"][0]["value"]["stringValue"],
+            "shop"
+        );
+        let records = logs["resourceLogs"][0]["scopeLogs"][0]["logRecords"]
+            .as_array()
+            .unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["severityText"], "INFO");
+        assert!(records[1].get("spanId").is_none());
+        assert_eq!(records[2]["severityText"], "ERROR");
+        assert_eq!(records[2]["spanId"], spans[1]["spanId"]);
+
+        assert!(traces_body(&config(), &events[1..2]).is_none());
+    }
+}
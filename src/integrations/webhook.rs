@@ -0,0 +1,193 @@
+//! Webhooks: matching events are POSTed to the configured URLs through the
+//! delivery queue.
+
+use std::{sync::Arc, time::UNIX_EPOCH};
+
+use serde_json::{Value, json};
+use tokio::{
+    select,
+    sync::{broadcast::error::RecvError, mpsc, watch},
+};
+use tracing::warn;
+
+use super::delivery::{self, Delivery};
+use crate::{
+    app::summarize_event,
+    config::WebhookFormat,
+    filter::Filter,
+    state::{AppState, TimelineEvent, bus::BusMessage},
+};
+
+pub(super) struct Webhook {
+    pub url: String,
+    pub matcher: Filter,
+    pub format: WebhookFormat,
+}
+
+pub(super) fn spawn(state: Arc<AppState>, hooks: Vec<Webhook>, stop: watch::Receiver<bool>) {
+    let queue = delivery::spawn();
+    tokio::spawn(async move {
+        let (_, mut changes) = state.subscribe().await;
+        let stopped = super::stopped(stop);
+        tokio::pin!(stopped);
+        loop {
+            select! {
+                change = changes.recv() => match change {
+                    Ok(BusMessage::Recorded { event }) => enqueue(&hooks, &event, &queue),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "webhooks fell behind; some events were not sent");
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut stopped => break,
+            }
+        }
+    });
+}
+
+fn enqueue(hooks: &[Webhook], event: &TimelineEvent, queue: &mpsc::Sender<Delivery>) {
+    for hook in hooks.iter().filter(|hook| hook.matcher.matches(event)) {
+        let body = match hook.format {
+            WebhookFormat::Json => json_body(event),
+            WebhookFormat::Slack => slack_body(event),
+        };
+        delivery::enqueue(
+            queue,
+            Delivery {
+                url: hook.url.clone(),
+                headers: Vec::new(),
+                body,
+            },
+        );
+    }
+}
+
+/// The event as JSON, with its summary alongside the original Ray request.
+fn json_body(event: &TimelineEvent) -> Value {
+    let entry = summarize_event(event);
+    json!({
+        "id": event.id,
+        "received_at": event
+            .received_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64(),
+        "kind": entry.kind,
+        "summary": entry.summary,
+        "color": event.color,
+        "label": event.label,
+        "screen": event.screen,
+        "remote_addr": event.remote_addr,
+        "headers": event.headers,
+        "request": &*event.request,
+    })
+}
+
+/// A Slack incoming-webhook message: `*[kind]* summary (label)` plus the origin.
+fn slack_body(event: &TimelineEvent) -> Value {
+    let entry = summarize_event(event);
+    let mut text = format!("*[{}]* {}", entry.kind, entry.summary);
+    if let Some(label) = &event.label {
+        text.push_str(&format!(" ({})", label));
+    }
+    if let Some(origin) = event
+        .request
+        .payloads
+        .iter()
+        .find_map(|payload| payload.origin.as_ref())
+        && let Some(file) = &origin.file
+    {
+        match origin.line_number {
+            Some(line) => text.push_str(&format!("\n`{}:{}`", file, line)),
+            None => text.push_str(&format!("\n`{}`", file)),
+        }
+    }
+    json!({ "text": text })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    #[tokio::test]
+    async fn retries_failed_deliveries_until_accepted() {
+        // Fails twice, then accepts; records the bodies it accepted.
+        #[derive(Clone, Default)]
+        struct Endpoint {
+            calls: Arc<AtomicUsize>,
+            received: Arc<std::sync::Mutex<Vec<Value>>>,
+        }
+        async fn receive(State(endpoint): State<Endpoint>, Json(body): Json<Value>) -> StatusCode {
+            if endpoint.calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                return StatusCode::SERVICE_UNAVAILABLE;
+            }
+            endpoint.received.lock().unwrap().push(body);
+            StatusCode::OK
+        }
+
+        let endpoint = Endpoint::default();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/hook", post(receive))
+            .with_state(endpoint.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{
+                "type": "log",
+                "content": { "values": ["checkout failed"] },
+                "origin": { "file": "/app/Checkout.php", "line_number": 12 }
+            }],
+            "meta": {}
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.label = Some("orders".into());
+
+        let hooks = [Webhook {
+            url,
+            matcher: Filter::parse("checkout").unwrap(),
+            format: WebhookFormat::Slack,
+        }];
+        let (queue, pending) = mpsc::channel(delivery::QUEUE_CAPACITY);
+        let worker = tokio::spawn(delivery::deliver(pending, Duration::from_millis(5)));
+        enqueue(&hooks, &event, &queue);
+        drop(queue);
+        worker.await.unwrap();
+
+        assert_eq!(endpoint.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            *endpoint.received.lock().unwrap(),
+            vec![json!({
+                "text": "*[log]* checkout failed (orders)\n`/app/Checkout.php:12`"
+            })]
+        );
+    }
+
+    #[test]
+    fn json_body_carries_the_summary_and_request() {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "abc",
+            "payloads": [{ "type": "log", "content": { "values": ["hi"] } }],
+            "meta": {}
+        }))
+        .unwrap();
+        let body = json_body(&TimelineEvent::new(request, None));
+        assert_eq!(body["kind"], "log");
+        assert_eq!(body["summary"], "hi");
+        assert_eq!(body["request"]["uuid"], "abc");
+    }
+}
@@ -0,0 +1,537 @@
+use std::{collections::BTreeMap, fmt};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::KeyBinding;
+
+/// Remappable global actions. Navigation keys (arrows, `j`/`k`, paging, Enter,
+/// Space, Esc) and `Ctrl+C` stay fixed so the UI can always be driven.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    Help,
+    ToggleFocus,
+    CycleColorFilter,
+    CycleLayout,
+    ClearTimeline,
+    RawPayload,
+    Diagnostics,
+    Schema,
+    FocusFollow,
+    Fullscreen,
+    HistorySearch,
+    ExportHar,
+    ExportMarkdown,
+    MarkRange,
+    PreviousRequest,
+    NextRequest,
+    NextUnread,
+    CopyPath,
+    CopyValue,
+    Scratch,
+    SaveView,
+    Problems,
+    Logs,
+    UnknownKinds,
+    Trash,
+    Stats,
+    Profile,
+    EditLabel,
+    CycleColor,
+    CycleRenderer,
+    MoveToScreen,
+    RenameScreen,
+    CloseScreen,
+    ClearMatching,
+    HideNoise,
+    JsonQuery,
+    Origins,
+    Exceptions,
+    Rebind,
+    DetailFilter,
+}
+
+/// Where an action's keys apply. Detail actions apply while the details have
+/// focus, where they take over keys global actions are bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Global,
+    Detail,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Quit,
+        Action::Help,
+        Action::ToggleFocus,
+        Action::CycleColorFilter,
+        Action::CycleLayout,
+        Action::ClearTimeline,
+        Action::RawPayload,
+        Action::Diagnostics,
+        Action::Schema,
+        Action::FocusFollow,
+        Action::Fullscreen,
+        Action::HistorySearch,
+        Action::ExportHar,
+        Action::ExportMarkdown,
+        Action::MarkRange,
+        Action::PreviousRequest,
+        Action::NextRequest,
+        Action::NextUnread,
+        Action::CopyPath,
+        Action::CopyValue,
+        Action::Scratch,
+        Action::SaveView,
+        Action::Problems,
+        Action::Logs,
+        Action::UnknownKinds,
+        Action::Trash,
+        Action::Stats,
+        Action::Profile,
+        Action::EditLabel,
+        Action::CycleColor,
+        Action::CycleRenderer,
+        Action::MoveToScreen,
+        Action::RenameScreen,
+        Action::CloseScreen,
+        Action::ClearMatching,
+        Action::HideNoise,
+        Action::JsonQuery,
+        Action::Origins,
+        Action::Exceptions,
+        Action::Rebind,
+        Action::DetailFilter,
+    ];
+
+    /// Name used for the action in the `[keys]` config table.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::ToggleFocus => "toggle_focus",
+            Action::CycleColorFilter => "cycle_color_filter",
+            Action::CycleLayout => "cycle_layout",
+            Action::ClearTimeline => "clear_timeline",
+            Action::RawPayload => "raw_payload",
+            Action::Diagnostics => "diagnostics",
+            Action::Schema => "schema",
+            Action::FocusFollow => "focus_follow",
+            Action::Fullscreen => "fullscreen",
+            Action::HistorySearch => "history_search",
+            Action::ExportHar => "export_har",
+            Action::ExportMarkdown => "export_markdown",
+            Action::MarkRange => "mark_range",
+            Action::PreviousRequest => "previous_request",
+            Action::NextRequest => "next_request",
+            Action::NextUnread => "next_unread",
+            Action::CopyPath => "copy_path",
+            Action::CopyValue => "copy_value",
+            Action::Scratch => "scratch",
+            Action::SaveView => "save_view",
+            Action::Problems => "problems",
+            Action::Logs => "logs",
+            Action::UnknownKinds => "unknown_kinds",
+            Action::Trash => "trash",
+            Action::Stats => "stats",
+            Action::Profile => "profile",
+            Action::EditLabel => "edit_label",
+            Action::CycleColor => "cycle_color",
+            Action::CycleRenderer => "cycle_renderer",
+            Action::MoveToScreen => "move_to_screen",
+            Action::RenameScreen => "rename_screen",
+            Action::CloseScreen => "close_screen",
+            Action::ClearMatching => "clear_matching",
+            Action::HideNoise => "hide_noise",
+            Action::JsonQuery => "json_query",
+            Action::Origins => "origins",
+            Action::Exceptions => "exceptions",
+            Action::Rebind => "rebind",
+            Action::DetailFilter => "detail_filter",
+        }
+    }
+
+    pub fn scope(self) -> Scope {
+        match self {
+            Action::DetailFilter => Scope::Detail,
+            _ => Scope::Global,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q", "Q"],
+            Action::Help => &["?"],
+            Action::ToggleFocus => &["tab"],
+            Action::CycleColorFilter => &["f"],
+            Action::CycleLayout => &["ctrl+l"],
+            Action::ClearTimeline => &["ctrl+k"],
+            Action::RawPayload => &["ctrl+d"],
+            Action::Diagnostics => &["!"],
+            Action::Schema => &["s"],
+            Action::FocusFollow => &["p"],
+            Action::Fullscreen => &["z", "F"],
+            Action::HistorySearch => &["ctrl+f"],
+            Action::ExportHar => &["H"],
+            Action::ExportMarkdown => &["M"],
+            Action::MarkRange => &["m"],
+            Action::PreviousRequest => &["["],
+            Action::NextRequest => &["]"],
+            Action::NextUnread => &["u"],
+            Action::CopyPath => &["y"],
+            Action::CopyValue => &["c"],
+            Action::Scratch => &["v"],
+            Action::SaveView => &["S"],
+            Action::Problems => &["P"],
+            Action::Logs => &["L"],
+            Action::UnknownKinds => &["U"],
+            Action::Trash => &["T"],
+            Action::Stats => &["I"],
+            Action::Profile => &["ctrl+p"],
+            Action::EditLabel => &["E"],
+            Action::CycleColor => &["C"],
+            Action::CycleRenderer => &["R"],
+            Action::MoveToScreen => &["W"],
+            Action::RenameScreen => &["A"],
+            Action::CloseScreen => &["Z"],
+            Action::ClearMatching => &["X"],
+            Action::HideNoise => &["N"],
+            Action::JsonQuery => &["J"],
+            Action::Origins => &["O"],
+            Action::Exceptions => &["G"],
+            Action::Rebind => &["ctrl+b"],
+            Action::DetailFilter => &["f"],
+        }
+    }
+}
+
+/// Keys handled directly by the navigation code; bindings may not shadow them.
+/// `/` opens the filter of the focused pane before bindings are looked up.
+const RESERVED_KEYS: &[&str] = &[
+    "ctrl+c", "up", "down", "left", "right", "j", "k", "pageup", "pagedown", "home", "end",
+    "enter", "space", "esc", "backtab", "/", "1", "2", "3", "4", "5", "6", "7", "8", "9",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeySpec {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    /// Parses specs such as `q`, `?`, `tab`, `ctrl+k`, `alt+enter` or `f5`.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            return Err("empty key".to_string());
+        }
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = trimmed;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            let (prefix_len, modifier) = if lower.starts_with("ctrl+") {
+                (5, KeyModifiers::CONTROL)
+            } else if lower.starts_with("alt+") {
+                (4, KeyModifiers::ALT)
+            } else if lower.starts_with("shift+") && rest.len() > 6 {
+                (6, KeyModifiers::SHIFT)
+            } else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = &rest[prefix_len..];
+        }
+
+        let code = match rest.to_ascii_lowercase().as_str() {
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            function if function.len() > 1 && function.starts_with('f') => function[1..]
+                .parse::<u8>()
+                .ok()
+                .filter(|number| (1..=12).contains(number))
+                .map(KeyCode::F)
+                .ok_or_else(|| format!("unknown key `{}`", raw))?,
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => {
+                        // Shift is implied by the character itself (`Q`, `?`).
+                        let ch = if modifiers.contains(KeyModifiers::SHIFT) {
+                            modifiers.remove(KeyModifiers::SHIFT);
+                            ch.to_ascii_uppercase()
+                        } else {
+                            ch
+                        };
+                        // Terminals report ctrl chords with the lowercase letter.
+                        if modifiers.contains(KeyModifiers::CONTROL) {
+                            KeyCode::Char(ch.to_ascii_lowercase())
+                        } else {
+                            KeyCode::Char(ch)
+                        }
+                    }
+                    _ => return Err(format!("unknown key `{}`", raw)),
+                }
+            }
+        };
+
+        Ok(Self { code, modifiers })
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let mut modifiers = key.modifiers;
+        if matches!(key.code, KeyCode::Char(_)) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        let code = match key.code {
+            KeyCode::Char(ch) if modifiers.contains(KeyModifiers::CONTROL) => {
+                KeyCode::Char(ch.to_ascii_lowercase())
+            }
+            other => other,
+        };
+
+        code == self.code && modifiers == self.modifiers
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(ch) => write!(f, "{}", ch),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeySpec, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new()).unwrap_or_else(|_| Self {
+            bindings: Vec::new(),
+        })
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap from the `[keys]` config table, returning every
+    /// problem found (unknown actions, unparsable keys, conflicts).
+    pub fn from_config(overrides: &BTreeMap<String, KeyBinding>) -> Result<Self, Vec<String>> {
+        let mut errors = Vec::new();
+
+        for name in overrides.keys() {
+            if Action::from_name(name).is_none() {
+                let known = Action::ALL
+                    .iter()
+                    .map(|action| action.name())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                errors.push(format!(
+                    "[keys] unknown action `{}` (expected one of: {})",
+                    name, known
+                ));
+            }
+        }
+
+        let reserved: Vec<KeySpec> = RESERVED_KEYS
+            .iter()
+            .filter_map(|key| KeySpec::parse(key).ok())
+            .collect();
+
+        let mut bindings: Vec<(KeySpec, Action)> = Vec::new();
+        for action in Action::ALL {
+            let keys: Vec<String> = match overrides.get(action.name()) {
+                Some(binding) => binding.keys().to_vec(),
+                None => action
+                    .default_keys()
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect(),
+            };
+
+            for raw in keys {
+                let spec = match KeySpec::parse(&raw) {
+                    Ok(spec) => spec,
+                    Err(err) => {
+                        errors.push(format!("[keys] {}: {}", action.name(), err));
+                        continue;
+                    }
+                };
+
+                if reserved.contains(&spec) {
+                    errors.push(format!(
+                        "[keys] {}: `{}` is reserved for navigation",
+                        action.name(),
+                        raw
+                    ));
+                    continue;
+                }
+
+                if let Some((_, other)) = bindings
+                    .iter()
+                    .find(|(existing, other)| *existing == spec && other.scope() == action.scope())
+                {
+                    if *other != *action {
+                        errors.push(format!(
+                            "[keys] `{}` is bound to both `{}` and `{}`",
+                            raw,
+                            other.name(),
+                            action.name()
+                        ));
+                    }
+                    continue;
+                }
+
+                bindings.push((spec, *action));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self { bindings })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The action `key` triggers in `scope`; outside the global scope, the
+    /// scope's own bindings win over global ones.
+    pub fn action_for(&self, key: &KeyEvent, scope: Scope) -> Option<Action> {
+        let bound = |wanted: Scope| {
+            self.bindings
+                .iter()
+                .find(|(spec, action)| action.scope() == wanted && spec.matches(key))
+                .map(|(_, action)| *action)
+        };
+        bound(scope).or_else(|| bound(Scope::Global))
+    }
+
+    /// Human readable primary key for an action, used in hints and help.
+    pub fn hint(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == action)
+            .map(|(spec, _)| spec.to_string())
+            .unwrap_or_else(|| "(unbound)".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn default_keymap_matches_builtin_shortcuts() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action_for(
+                &key(KeyCode::Char('k'), KeyModifiers::CONTROL),
+                Scope::Global
+            ),
+            Some(Action::ClearTimeline)
+        );
+        assert_eq!(
+            keymap.action_for(&key(KeyCode::Char('Q'), KeyModifiers::SHIFT), Scope::Global),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keymap.action_for(&key(KeyCode::Char('k'), KeyModifiers::NONE), Scope::Global),
+            None
+        );
+        assert_eq!(keymap.hint(Action::CycleLayout), "ctrl+l");
+        assert_eq!(
+            keymap.action_for(&key(KeyCode::Char('F'), KeyModifiers::SHIFT), Scope::Global),
+            Some(Action::Fullscreen)
+        );
+        assert_eq!(
+            keymap.action_for(&key(KeyCode::Char('Z'), KeyModifiers::SHIFT), Scope::Global),
+            Some(Action::CloseScreen)
+        );
+
+        // `f` filters the details while they have focus.
+        let f = key(KeyCode::Char('f'), KeyModifiers::NONE);
+        assert_eq!(
+            keymap.action_for(&f, Scope::Global),
+            Some(Action::CycleColorFilter)
+        );
+        assert_eq!(
+            keymap.action_for(&f, Scope::Detail),
+            Some(Action::DetailFilter)
+        );
+        assert_eq!(
+            keymap.action_for(&key(KeyCode::Char('y'), KeyModifiers::NONE), Scope::Detail),
+            Some(Action::CopyPath)
+        );
+    }
+
+    #[test]
+    fn overrides_report_conflicts_and_reserved_keys() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("help".to_string(), KeyBinding::One("ctrl+k".to_string()));
+        overrides.insert("quit".to_string(), KeyBinding::One("j".to_string()));
+        overrides.insert("explode".to_string(), KeyBinding::One("x".to_string()));
+        overrides.insert("trash".to_string(), KeyBinding::One("/".to_string()));
+
+        let errors = Keymap::from_config(&overrides).expect_err("config should be rejected");
+        assert_eq!(errors.len(), 4, "unexpected errors: {:?}", errors);
+        assert!(
+            errors
+                .iter()
+                .any(|err| err.contains("unknown action `explode`"))
+        );
+        assert!(
+            errors
+                .iter()
+                .any(|err| err.contains("reserved for navigation"))
+        );
+        assert!(errors.iter().any(|err| err.contains("bound to both")));
+        assert!(
+            errors
+                .iter()
+                .any(|err| err.contains("trash: `/` is reserved for navigation"))
+        );
+    }
+}
@@ -0,0 +1,27 @@
+//! Raygun's library crate. The binary in `main.rs` is a thin wrapper around
+//! [`app::RaygunApp`]; the library exists so fuzz targets and other tooling can
+//! drive the protocol parser and renderers directly.
+
+pub mod alerts;
+pub mod app;
+pub mod config;
+pub mod daemon;
+pub mod delays;
+pub mod demo;
+pub mod export;
+pub mod filter;
+pub mod format;
+pub mod import;
+pub mod integrations;
+pub mod keymap;
+pub mod logs;
+pub mod plain;
+pub mod problems;
+pub mod protocol;
+pub mod recovery;
+pub mod scratch;
+pub mod selftest;
+pub mod server;
+pub mod state;
+pub mod tui;
+pub mod ui;
@@ -0,0 +1,133 @@
+//! Tracing output for the log viewer. While the UI owns the terminal, log
+//! lines are kept here instead of being written to stderr, which would draw
+//! over the alternate screen. The tracing filter can be changed at runtime.
+
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::SystemTime,
+};
+
+use once_cell::sync::{Lazy, OnceCell};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry,
+    fmt::{MakeWriter, writer::MakeWriterExt},
+    layer::Context,
+    reload,
+};
+
+use crate::problems::MessageVisitor;
+
+/// Lines kept before the oldest are dropped.
+const RETENTION: usize = 500;
+
+static LINES: Lazy<Mutex<VecDeque<LogLine>>> = Lazy::new(Mutex::default);
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+static FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub at: SystemTime,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Tracing layer that keeps every event passing the filter as a [`LogLine`].
+#[derive(Debug, Default)]
+pub struct LogLayer;
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let Ok(mut lines) = LINES.lock() else {
+            return;
+        };
+        lines.push_back(LogLine {
+            at: SystemTime::now(),
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message + &visitor.fields,
+        });
+        if lines.len() > RETENTION {
+            lines.pop_front();
+        }
+    }
+}
+
+/// Recent log lines, oldest first.
+pub fn snapshot() -> Vec<LogLine> {
+    match LINES.lock() {
+        Ok(lines) => lines.iter().cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Writer for the log output: stderr, except while [`capture`] is on.
+pub fn stderr() -> impl for<'a> MakeWriter<'a> {
+    io::stderr.with_filter(|_| !CAPTURING.load(Ordering::Relaxed))
+}
+
+/// Stops writing log output to stderr while the UI owns the terminal; the
+/// lines stay in the log viewer either way.
+pub fn capture(on: bool) {
+    CAPTURING.store(on, Ordering::Relaxed);
+}
+
+/// Wraps `filter` so [`set_filter`] can replace it later. Only the first
+/// filter installed this way can be changed.
+pub fn reloadable(filter: EnvFilter) -> reload::Layer<EnvFilter, Registry> {
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = FILTER.set(handle);
+    layer
+}
+
+/// The active filter directives, e.g. `raygun=info,raygun::app=debug`.
+pub fn filter() -> Option<String> {
+    FILTER.get()?.with_current(ToString::to_string).ok()
+}
+
+/// Replaces the tracing filter with `directives`, in `RUST_LOG` syntax.
+pub fn set_filter(directives: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(directives.trim()).map_err(|err| err.to_string())?;
+    FILTER
+        .get()
+        .ok_or_else(|| "the log filter cannot be changed in this process".to_string())?
+        .reload(filter)
+        .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn keeps_lines_that_pass_the_filter() {
+        let subscriber = tracing_subscriber::registry()
+            .with(EnvFilter::new("info"))
+            .with(LogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("filtered out");
+            tracing::info!(port = 7777, "listening");
+        });
+
+        let lines = snapshot();
+        assert!(lines.iter().any(|line| line.level == Level::INFO
+            && line.message == "listening port=7777"
+            && line.target == module_path!()));
+        assert!(lines.iter().all(|line| line.message != "filtered out"));
+    }
+
+    #[test]
+    fn rejects_invalid_filters() {
+        assert!(set_filter("raygun=loudest").is_err());
+    }
+}
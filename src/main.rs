@@ -1,38 +1,101 @@
-mod app;
-mod config;
-mod protocol;
-mod server;
-mod state;
-mod tui;
-mod ui;
-
 use clap::Parser;
 use color_eyre::{Result, eyre::eyre};
-use tracing_subscriber::EnvFilter;
+use raygun::{app, config, daemon, logs, plain, problems::ProblemLayer, selftest};
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
-    init_tracing()?;
-
     let config = config::Config::parse();
+    init_tracing(config.plain)?;
     if config.show_version {
         println!("raygun {}", env!("CARGO_PKG_VERSION"));
         return Ok(());
     }
 
-    let app = app::RaygunApp::bootstrap(config).await?;
+    if let Some(config::Command::Check) = &config.command {
+        let ok = run_check(&config);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    if let Some(config::Command::Selftest) = &config.command {
+        let ok = selftest::run(&config).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
+    let app = match &config.command {
+        Some(config::Command::Daemon) => return daemon::run(config).await,
+        _ if config.plain => return plain::run(config).await,
+        Some(config::Command::Attach { .. }) => app::RaygunApp::attach(config).await?,
+        _ => app::RaygunApp::bootstrap(config).await?,
+    };
     app.run().await
 }
 
-fn init_tracing() -> Result<()> {
+/// Prints config problems for `raygun check` to stderr; returns `false` on
+/// any error.
+fn run_check(config: &config::Config) -> bool {
+    let Some(path) = config.config_path() else {
+        println!("No config location could be determined; set --config or RAYGUN_CONFIG.");
+        return true;
+    };
+
+    if config.config_file.is_none() && !path.exists() {
+        println!("No config file at {} (using defaults).", path.display());
+        return true;
+    }
+
+    println!("Checking {}", path.display());
+
+    let file_config = match config::FileConfig::load(&path) {
+        Ok(file_config) => file_config,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return false;
+        }
+    };
+
+    let issues = file_config.validate();
+    let mut errors = 0;
+    for issue in &issues {
+        let prefix = match issue.severity {
+            config::Severity::Error => {
+                errors += 1;
+                "error"
+            }
+            config::Severity::Warning => "warning",
+        };
+        eprintln!("{}: {}", prefix, issue.message);
+    }
+
+    let warnings = issues.len() - errors;
+    if issues.is_empty() {
+        println!("Config is valid.");
+    } else {
+        eprintln!("{} error(s), {} warning(s).", errors, warnings);
+    }
+
+    errors == 0
+}
+
+/// `plain` leaves color escapes out of the log lines on stderr.
+fn init_tracing(plain: bool) -> Result<()> {
     let filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new("raygun=info,raygun::app=debug"))?;
 
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_target(false)
-        .compact()
+    tracing_subscriber::registry()
+        .with(logs::reloadable(filter))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .compact()
+                .with_ansi(!plain)
+                .with_writer(logs::stderr()),
+        )
+        // Everything also goes to the log viewer, and warnings and errors to
+        // the problems strip.
+        .with(logs::LogLayer)
+        .with(ProblemLayer)
         .try_init()
         .map_err(|err| eyre!(err))?;
 
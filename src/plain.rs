@@ -0,0 +1,362 @@
+//! Plain output mode (`--plain`): new events are printed as lines of text,
+//! without colors or cursor movement, so screen readers and sessions captured
+//! with `script` can follow them. Commands are read line by line from stdin.
+
+use std::{collections::HashMap, sync::Arc, time::UNIX_EPOCH};
+
+use color_eyre::{Result, eyre::eyre};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    select,
+    sync::broadcast::error::RecvError,
+};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::{
+    alerts::Alerts,
+    app::{self, build_detail_view_for_event, summarize_event},
+    config::{Command, Config, LocaleConfig},
+    daemon::{self, ClientCommand, DaemonClient},
+    state::{AppState, TimelineEvent, bus::BusMessage},
+    ui::detail::line_text,
+};
+
+/// Events `list` prints when not given a count.
+const LIST_DEFAULT: usize = 10;
+
+const HELP: &str = "Commands: list [n] prints the latest n events, show [n] prints the details of event n (the latest without n), clear empties the timeline, help repeats this, quit exits.";
+
+#[derive(Debug, PartialEq, Eq)]
+enum PlainCommand {
+    Help,
+    List(usize),
+    Show(Option<usize>),
+    Clear,
+    Quit,
+}
+
+impl PlainCommand {
+    /// `None` for a blank line.
+    fn parse(line: &str) -> Result<Option<Self>, String> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(None);
+        };
+        let number = words
+            .next()
+            .map(|word| {
+                word.trim_start_matches('#')
+                    .parse::<usize>()
+                    .map_err(|_| format!("`{}` is not an event number.", word))
+            })
+            .transpose()?;
+        let command = match name.to_ascii_lowercase().as_str() {
+            "help" | "h" | "?" => Self::Help,
+            "list" | "l" => Self::List(number.unwrap_or(LIST_DEFAULT)),
+            "show" | "s" => Self::Show(number),
+            "clear" | "c" => Self::Clear,
+            "quit" | "q" | "exit" => Self::Quit,
+            other => return Err(format!("Unknown command `{}`. {}", other, HELP)),
+        };
+        Ok(Some(command))
+    }
+}
+
+/// Numbers events in the order they are first printed, so `show` can refer
+/// to them.
+#[derive(Debug, Default)]
+struct Transcript {
+    numbers: HashMap<Uuid, usize>,
+    ids: Vec<Uuid>,
+    locale: LocaleConfig,
+}
+
+impl Transcript {
+    fn number(&mut self, id: Uuid) -> usize {
+        *self.numbers.entry(id).or_insert_with(|| {
+            self.ids.push(id);
+            self.ids.len()
+        })
+    }
+
+    /// The line announcing `message`, if it is worth one.
+    fn describe(&mut self, message: &BusMessage) -> Option<String> {
+        match message {
+            BusMessage::Recorded { event } => Some(self.summary(event)),
+            BusMessage::Updated { event } => {
+                let number = self.number(event.id);
+                let mut changes = Vec::new();
+                if let Some(label) = &event.label {
+                    changes.push(format!("label {}", label));
+                }
+                if let Some(color) = &event.color {
+                    changes.push(format!("color {}", color));
+                }
+                if let Some(screen) = &event.screen {
+                    changes.push(format!("screen {}", screen));
+                }
+                Some(format!("#{} updated: {}", number, changes.join(", ")))
+            }
+            // Evictions would drown out new events once the timeline is full.
+            BusMessage::Removed { .. } => None,
+            BusMessage::Cleared => Some("Timeline cleared.".to_string()),
+            BusMessage::LockChanged { name, held: true } => {
+                Some(format!("Paused: the client waits on lock {}.", name))
+            }
+            BusMessage::LockChanged { name, held: false } => {
+                Some(format!("Lock {} released.", name))
+            }
+        }
+    }
+
+    /// `#3 14:05:01 log: checkout started (label cron, color red)`.
+    fn summary(&mut self, event: &TimelineEvent) -> String {
+        let entry = summarize_event(event);
+        let secs = event
+            .received_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut line = format!(
+            "#{} {} {}: {}",
+            self.number(event.id),
+            self.locale.time_of_day(secs, None),
+            entry.kind,
+            entry.summary
+        );
+        let extras: Vec<String> = [
+            entry.label.map(|label| format!("label {}", label)),
+            entry.color.map(|color| format!("color {}", color)),
+            entry.host.map(|host| format!("from {}", host)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !extras.is_empty() {
+            line.push_str(&format!(" ({})", extras.join(", ")));
+        }
+        line
+    }
+
+    /// The details of `event`, one line per detail row, indented with spaces.
+    fn details(&mut self, event: &TimelineEvent) -> Vec<String> {
+        let detail = build_detail_view_for_event(event, None, self.locale);
+        let mut lines = vec![self.summary(event)];
+        lines.extend(detail.lines.iter().map(|line| {
+            format!("  {}{}", "  ".repeat(line.indent), line_text(line))
+                .trim_end()
+                .to_string()
+        }));
+        if !detail.footer.is_empty() {
+            lines.push(format!("  {}", detail.footer));
+        }
+        lines.push(format!("End of #{}.", self.number(event.id)));
+        lines
+    }
+}
+
+/// Runs plain output mode until `quit`, Ctrl+C or SIGTERM.
+pub async fn run(config: Config) -> Result<()> {
+    let file_config = app::load_file_config(&config)?;
+    let (state, addrs, server, _integrations, daemon) = match &config.command {
+        Some(Command::Attach { .. }) => {
+            let path = config.socket_path();
+            let attachment = daemon::attach(&path).await.map_err(|err| {
+                eyre!(
+                    "{}. Start one with `raygun daemon` or pass --socket <path>.",
+                    err
+                )
+            })?;
+            (
+                attachment.state,
+                attachment.addrs,
+                None,
+                None,
+                Some(attachment.client),
+            )
+        }
+        _ => {
+            let (state, server, integrations) = app::start_server(&config, &file_config).await?;
+            let addrs = server.addrs().to_vec();
+            (state, addrs, Some(server), Some(integrations), None)
+        }
+    };
+
+    let (existing, mut bus) = state.subscribe().await;
+    let addrs: Vec<String> = addrs.iter().map(ToString::to_string).collect();
+    println!(
+        "Raygun is listening on {}. {} event{} in the timeline. Type help for commands.",
+        addrs.join(", "),
+        existing.len(),
+        if existing.len() == 1 { "" } else { "s" }
+    );
+
+    let mut transcript = Transcript {
+        locale: file_config.locale,
+        ..Transcript::default()
+    };
+    let mut alerts = Alerts::new(&file_config.sounds);
+    let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+    // Without stdin (e.g. `< /dev/null`) events are still printed.
+    let mut reading = true;
+    loop {
+        select! {
+            message = bus.recv() => match message {
+                Ok(message) => {
+                    if let Some(line) = transcript.describe(&message) {
+                        println!("{}", line);
+                    }
+                    if let BusMessage::Recorded { event } = &message
+                        && let Err(err) = alerts.alert(&mut std::io::stdout(), event)
+                    {
+                        debug!(?err, "failed to play the sound for an event");
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    println!("{} updates were skipped; the output fell behind.", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            line = stdin.next_line(), if reading => match line {
+                Ok(Some(line)) => match PlainCommand::parse(&line) {
+                    Ok(Some(PlainCommand::Quit)) => break,
+                    Ok(Some(command)) => {
+                        let daemon = daemon.as_ref();
+                        for line in run_command(command, &state, daemon, &mut transcript).await {
+                            println!("{}", line);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(message) => println!("{}", message),
+                },
+                Ok(None) => reading = false,
+                Err(err) => {
+                    warn!(?err, "failed to read commands from stdin");
+                    reading = false;
+                }
+            },
+            signal = app::exit_signal() => {
+                if let Err(err) = signal {
+                    warn!(?err, "failed to listen for interrupt signals");
+                }
+                break;
+            }
+        }
+    }
+
+    if daemon.is_none() {
+        app::drain(&state, server, config.drain_timeout()).await?;
+    }
+    Ok(())
+}
+
+async fn run_command(
+    command: PlainCommand,
+    state: &Arc<AppState>,
+    daemon: Option<&DaemonClient>,
+    transcript: &mut Transcript,
+) -> Vec<String> {
+    match command {
+        PlainCommand::Help => vec![HELP.to_string()],
+        PlainCommand::List(count) => {
+            let events = state.timeline_snapshot().await;
+            if events.is_empty() {
+                return vec!["The timeline is empty.".to_string()];
+            }
+            let start = events.len().saturating_sub(count);
+            events[start..]
+                .iter()
+                .map(|event| transcript.summary(event))
+                .collect()
+        }
+        PlainCommand::Show(number) => {
+            let event = match number {
+                None => state.timeline_snapshot().await.pop(),
+                Some(number) => match transcript.ids.get(number.wrapping_sub(1)) {
+                    Some(&id) => state.event(id).await,
+                    None => return vec![format!("There is no event #{}.", number)],
+                },
+            };
+            match event {
+                Some(event) => transcript.details(&event),
+                None if number.is_some() => {
+                    vec!["That event is no longer in the timeline.".to_string()]
+                }
+                None => vec!["The timeline is empty.".to_string()],
+            }
+        }
+        PlainCommand::Clear => {
+            match daemon {
+                Some(daemon) => daemon.send(ClientCommand::Clear),
+                None => state.clear_timeline().await,
+            }
+            Vec::new()
+        }
+        PlainCommand::Quit => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn log(text: &str) -> RayRequest {
+        serde_json::from_value(json!({
+            "uuid": "plain",
+            "payloads": [{ "type": "log", "content": { "values": [text] } }],
+            "meta": {}
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn describes_events_and_answers_commands() {
+        let state = Arc::new(AppState::default());
+        let mut bus = state.bus().subscribe();
+        let mut transcript = Transcript::default();
+
+        state.record_request(log("checkout started")).await;
+        state.record_request(log("checkout failed")).await;
+        let mut lines = Vec::new();
+        while let Ok(message) = bus.try_recv() {
+            lines.extend(transcript.describe(&message));
+        }
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("#1 "), "{}", lines[0]);
+        assert!(lines[1].ends_with(" log: checkout failed"), "{}", lines[1]);
+
+        assert_eq!(PlainCommand::parse("  "), Ok(None));
+        assert_eq!(
+            PlainCommand::parse("show #1"),
+            Ok(Some(PlainCommand::Show(Some(1))))
+        );
+        assert!(PlainCommand::parse("show one").is_err());
+        assert!(PlainCommand::parse("dance").is_err());
+
+        let details = run_command(PlainCommand::Show(Some(1)), &state, None, &mut transcript).await;
+        assert!(details[0].starts_with("#1 "), "{:?}", details);
+        assert!(
+            details.iter().any(|line| line.contains("checkout started")),
+            "{:?}",
+            details
+        );
+        assert_eq!(details.last().map(String::as_str), Some("End of #1."));
+
+        let listed = run_command(PlainCommand::List(1), &state, None, &mut transcript).await;
+        assert_eq!(listed, lines[1..]);
+
+        assert_eq!(
+            run_command(PlainCommand::Show(Some(9)), &state, None, &mut transcript).await,
+            ["There is no event #9."]
+        );
+        run_command(PlainCommand::Clear, &state, None, &mut transcript).await;
+        assert_eq!(
+            transcript.describe(&bus.try_recv().unwrap()).as_deref(),
+            Some("Timeline cleared.")
+        );
+    }
+}
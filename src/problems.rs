@@ -0,0 +1,119 @@
+//! Server and ingest problems for the problems strip. Warnings and errors
+//! logged anywhere in the process are kept here, since the log output itself
+//! is hidden under the UI.
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use once_cell::sync::Lazy;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+/// Problems kept before the oldest are dropped.
+const RETENTION: usize = 100;
+
+static LOG: Lazy<Mutex<ProblemLog>> = Lazy::new(Mutex::default);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub at: SystemTime,
+    pub level: Level,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+struct ProblemLog {
+    problems: VecDeque<Problem>,
+    /// Problems recorded since the log was created, including dropped ones.
+    recorded: u64,
+}
+
+/// Tracing layer that records every warning and error as a [`Problem`].
+#[derive(Debug, Default)]
+pub struct ProblemLayer;
+
+impl<S: Subscriber> Layer<S> for ProblemLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if level > Level::WARN {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        record(level, visitor.message + &visitor.fields);
+    }
+}
+
+/// Formats an event as `message key=value ...`, like the log output.
+#[derive(Default)]
+pub(crate) struct MessageVisitor {
+    pub(crate) message: String,
+    /// ` key=value` for each other field.
+    pub(crate) fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+pub fn record(level: Level, message: String) {
+    let Ok(mut log) = LOG.lock() else {
+        return;
+    };
+    log.problems.push_back(Problem {
+        at: SystemTime::now(),
+        level,
+        message,
+    });
+    if log.problems.len() > RETENTION {
+        log.problems.pop_front();
+    }
+    log.recorded += 1;
+}
+
+/// Recent problems, oldest first, and how many have been recorded in total;
+/// the total tells which problems are new since a previous call.
+pub fn snapshot() -> (Vec<Problem>, u64) {
+    match LOG.lock() {
+        Ok(log) => (log.problems.iter().cloned().collect(), log.recorded),
+        Err(_) => (Vec::new(), 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn records_warnings_and_errors_with_their_fields() {
+        let subscriber = tracing_subscriber::registry().with(ProblemLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("not a problem");
+            tracing::warn!(size = 42, "rejected oversized payload");
+        });
+
+        let (problems, _) = snapshot();
+        assert!(problems.iter().any(|problem| problem.level == Level::WARN
+            && problem.message == "rejected oversized payload size=42"));
+        assert!(
+            problems
+                .iter()
+                .all(|problem| problem.message != "not a problem")
+        );
+    }
+}
@@ -1,10 +1,39 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProtocolError {
+    #[error("invalid Ray request: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl ProtocolError {
+    /// Whether the body was not JSON at all, as opposed to JSON of the wrong
+    /// shape.
+    pub fn is_syntax(&self) -> bool {
+        match self {
+            ProtocolError::Json(error) => error.is_syntax() || error.is_eof(),
+        }
+    }
+}
+
+/// Parses a raw `POST /` body into a [`RayRequest`].
+///
+/// This is the single entry point for untrusted input: it never panics, and
+/// serde_json's recursion limit turns absurdly nested documents into an error
+/// instead of a stack overflow. Fuzz targets call it directly.
+pub fn parse_ray_request(bytes: &[u8]) -> Result<RayRequest, ProtocolError> {
+    Ok(serde_json::from_slice(bytes)?)
+}
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RayRequest {
     pub uuid: String,
     #[serde(default)]
@@ -13,7 +42,38 @@ pub struct RayRequest {
     pub meta: BTreeMap<String, Value>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl RayRequest {
+    /// When the client sent the request, if its meta carries a timestamp
+    /// (`sent_at`, `timestamp` or `microtime`) in seconds, milliseconds or
+    /// microseconds since the Unix epoch.
+    pub fn sent_at(&self) -> Option<SystemTime> {
+        const KEYS: &[&str] = &["sent_at", "timestamp", "microtime"];
+        let seconds = KEYS.iter().find_map(|key| {
+            let value = self.meta.get(*key)?;
+            value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|text| text.trim().parse().ok()))
+        })?;
+
+        let seconds = if seconds > 1e14 {
+            seconds / 1e6
+        } else if seconds > 1e11 {
+            seconds / 1e3
+        } else {
+            seconds
+        };
+        // The value comes straight from the client, so anything a `Duration`
+        // or `SystemTime` can't hold is dropped rather than trusted.
+        if seconds <= 0.0 {
+            return None;
+        }
+        Duration::try_from_secs_f64(seconds)
+            .ok()
+            .and_then(|since_epoch| UNIX_EPOCH.checked_add(since_epoch))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Payload {
     #[serde(rename = "type")]
     pub kind: PayloadKind,
@@ -24,6 +84,10 @@ pub struct Payload {
 }
 
 impl Payload {
+    pub fn content(&self) -> &Value {
+        &self.content
+    }
+
     pub fn content_object(&self) -> Option<&serde_json::Map<String, Value>> {
         self.content.as_object()
     }
@@ -33,6 +97,64 @@ impl Payload {
             .and_then(|map| map.get(key))
             .and_then(|value| value.as_str())
     }
+
+    /// Level of an `application_log` entry, from its `level`.
+    pub fn log_level(&self) -> Option<LogLevel> {
+        LogLevel::from_name(self.content_string("level")?)
+    }
+}
+
+/// PSR-3 severity of a framework log entry, least severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LogLevel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.trim().to_ascii_lowercase().as_str() {
+            "debug" => Self::Debug,
+            "info" => Self::Info,
+            "notice" => Self::Notice,
+            "warning" | "warn" => Self::Warning,
+            "error" => Self::Error,
+            "critical" => Self::Critical,
+            "alert" => Self::Alert,
+            "emergency" => Self::Emergency,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Notice => "notice",
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Critical => "critical",
+            Self::Alert => "alert",
+            Self::Emergency => "emergency",
+        }
+    }
+
+    /// Ray color for entries at this level when the client sent none, so
+    /// errors stand out on the timeline.
+    pub fn color(self) -> &'static str {
+        match self {
+            Self::Debug => "gray",
+            Self::Info | Self::Notice => "blue",
+            Self::Warning => "orange",
+            Self::Error | Self::Critical | Self::Alert | Self::Emergency => "red",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -65,16 +187,18 @@ pub enum PayloadKind {
     HideApp,
     Ban,
     Charles,
+    ExecutedQuery,
+    ApplicationLog,
+    JobEvent,
+    Event,
     Unknown(String),
 }
 
-impl<'de> Deserialize<'de> for PayloadKind {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let input = String::deserialize(deserializer)?;
-        let kind = match input.as_str() {
+impl PayloadKind {
+    /// Maps a payload `type` string to its kind; unrecognised types are kept
+    /// as [`PayloadKind::Unknown`].
+    pub fn from_type(input: &str) -> Self {
+        match input {
             "log" => Self::Log,
             "custom" => Self::Custom,
             "create_lock" => Self::CreateLock,
@@ -103,15 +227,75 @@ impl<'de> Deserialize<'de> for PayloadKind {
             "hide_app" => Self::HideApp,
             "ban" => Self::Ban,
             "charles" => Self::Charles,
+            "executed_query" => Self::ExecutedQuery,
+            "application_log" => Self::ApplicationLog,
+            "job_event" => Self::JobEvent,
+            "event" => Self::Event,
             other => Self::Unknown(other.to_owned()),
-        };
+        }
+    }
+
+    /// The canonical `type` string for this kind.
+    pub fn as_type(&self) -> &str {
+        match self {
+            Self::Log => "log",
+            Self::Custom => "custom",
+            Self::CreateLock => "create_lock",
+            Self::ClearAll => "clear_all",
+            Self::Hide => "hide",
+            Self::ShowApp => "show_app",
+            Self::ShowBrowser => "show_browser",
+            Self::Notify => "notify",
+            Self::Separator => "separator",
+            Self::Exception => "exception",
+            Self::Table => "table",
+            Self::Text => "text",
+            Self::Image => "image",
+            Self::JsonString => "json_string",
+            Self::DecodedJson => "decoded_json",
+            Self::Boolean => "boolean",
+            Self::Size => "size",
+            Self::Color => "color",
+            Self::Label => "label",
+            Self::Trace => "trace",
+            Self::Caller => "caller",
+            Self::Measure => "measure",
+            Self::PhpInfo => "phpinfo",
+            Self::NewScreen => "new_screen",
+            Self::Remove => "remove",
+            Self::HideApp => "hide_app",
+            Self::Ban => "ban",
+            Self::Charles => "charles",
+            Self::ExecutedQuery => "executed_query",
+            Self::ApplicationLog => "application_log",
+            Self::JobEvent => "job_event",
+            Self::Event => "event",
+            Self::Unknown(other) => other,
+        }
+    }
+}
+
+impl Serialize for PayloadKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_type())
+    }
+}
 
-        Ok(kind)
+impl<'de> Deserialize<'de> for PayloadKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let input = String::deserialize(deserializer)?;
+        Ok(Self::from_type(&input))
     }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Origin {
     pub file: Option<String>,
     #[serde(default)]
@@ -120,9 +304,249 @@ pub struct Origin {
     pub hostname: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueSeverity {
+    /// The request cannot be interpreted; strict mode rejects it.
+    Error,
+    /// The request is usable but deviates from the Ray protocol.
+    Warning,
+}
+
+/// A deviation from the Ray protocol found by [`inspect_ray_request`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolIssue {
+    pub severity: IssueSeverity,
+    /// JSON path of the offending value, e.g. `payloads[0].origin.line_number`.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ProtocolIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Checks a request against the shape Ray clients are expected to send:
+/// missing required fields are errors, unknown keys, unknown payload types and
+/// mistyped optional fields are warnings.
+pub fn inspect_ray_request(request: &Value) -> Vec<ProtocolIssue> {
+    let mut issues = Vec::new();
+    let mut push = |severity, path: String, message: String| {
+        issues.push(ProtocolIssue {
+            severity,
+            path,
+            message,
+        })
+    };
+
+    let Some(root) = request.as_object() else {
+        push(
+            IssueSeverity::Error,
+            "$".to_string(),
+            format!("expected an object, got {}", json_type(request)),
+        );
+        return issues;
+    };
+
+    for key in root.keys() {
+        if !matches!(key.as_str(), "uuid" | "payloads" | "meta") {
+            push(
+                IssueSeverity::Warning,
+                key.clone(),
+                "unknown key".to_string(),
+            );
+        }
+    }
+
+    match root.get("uuid") {
+        None => push(
+            IssueSeverity::Error,
+            "uuid".to_string(),
+            "missing required field".to_string(),
+        ),
+        Some(Value::String(_)) => {}
+        Some(other) => push(
+            IssueSeverity::Error,
+            "uuid".to_string(),
+            format!("expected a string, got {}", json_type(other)),
+        ),
+    }
+
+    match root.get("meta") {
+        None | Some(Value::Object(_)) => {}
+        Some(other) => push(
+            IssueSeverity::Warning,
+            "meta".to_string(),
+            format!("expected an object, got {}", json_type(other)),
+        ),
+    }
+
+    let payloads = match root.get("payloads") {
+        None => {
+            push(
+                IssueSeverity::Error,
+                "payloads".to_string(),
+                "missing required field".to_string(),
+            );
+            return issues;
+        }
+        Some(Value::Array(payloads)) => payloads,
+        Some(other) => {
+            push(
+                IssueSeverity::Error,
+                "payloads".to_string(),
+                format!("expected an array, got {}", json_type(other)),
+            );
+            return issues;
+        }
+    };
+
+    for (index, payload) in payloads.iter().enumerate() {
+        let base = format!("payloads[{}]", index);
+        let Some(payload) = payload.as_object() else {
+            push(
+                IssueSeverity::Error,
+                base,
+                format!("expected an object, got {}", json_type(payload)),
+            );
+            continue;
+        };
+
+        for key in payload.keys() {
+            if !matches!(key.as_str(), "type" | "content" | "origin") {
+                push(
+                    IssueSeverity::Warning,
+                    format!("{}.{}", base, key),
+                    "unknown key".to_string(),
+                );
+            }
+        }
+
+        match payload.get("type") {
+            None => push(
+                IssueSeverity::Error,
+                format!("{}.type", base),
+                "missing required field".to_string(),
+            ),
+            Some(Value::String(kind)) => {
+                if let PayloadKind::Unknown(kind) = PayloadKind::from_type(kind) {
+                    push(
+                        IssueSeverity::Warning,
+                        format!("{}.type", base),
+                        format!("unknown payload type `{}`", kind),
+                    );
+                }
+            }
+            Some(other) => push(
+                IssueSeverity::Error,
+                format!("{}.type", base),
+                format!("expected a string, got {}", json_type(other)),
+            ),
+        }
+
+        if !payload.contains_key("content") {
+            push(
+                IssueSeverity::Error,
+                format!("{}.content", base),
+                "missing required field".to_string(),
+            );
+        }
+
+        match payload.get("origin") {
+            None | Some(Value::Null) => {}
+            Some(Value::Object(origin)) => {
+                let fields = [
+                    ("file", "a string"),
+                    ("line_number", "a non-negative integer"),
+                    ("hostname", "a string"),
+                ];
+                for (field, expected) in fields {
+                    let valid = match origin.get(field) {
+                        None | Some(Value::Null) => true,
+                        Some(Value::String(_)) => field != "line_number",
+                        Some(Value::Number(number)) => {
+                            field == "line_number"
+                                && number.as_u64().is_some_and(|n| n <= u32::MAX as u64)
+                        }
+                        Some(_) => false,
+                    };
+                    if !valid {
+                        let value = &origin[field];
+                        // The request is still rejected by the parser in this
+                        // case, so report it as an error.
+                        push(
+                            IssueSeverity::Error,
+                            format!("{}.origin.{}", base, field),
+                            format!("expected {}, got {}", expected, json_type(value)),
+                        );
+                    }
+                }
+            }
+            Some(other) => push(
+                IssueSeverity::Error,
+                format!("{}.origin", base),
+                format!("expected an object, got {}", json_type(other)),
+            ),
+        }
+    }
+
+    issues
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
+
+    fn request_sent_at(value: Value) -> RayRequest {
+        RayRequest {
+            uuid: "sent-at".into(),
+            payloads: Vec::new(),
+            meta: BTreeMap::from([("sent_at".to_string(), value)]),
+        }
+    }
+
+    #[test]
+    fn reads_sent_at_in_any_unit() {
+        let expected = Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        assert_eq!(request_sent_at(json!(1_700_000_000)).sent_at(), expected);
+        assert_eq!(
+            request_sent_at(json!(1_700_000_000_000u64)).sent_at(),
+            expected
+        );
+        assert_eq!(
+            request_sent_at(json!("1700000000000000")).sent_at(),
+            expected
+        );
+    }
+
+    #[test]
+    fn ignores_sent_at_values_that_are_not_a_time() {
+        for value in [
+            json!(1e300),
+            json!(-1_700_000_000),
+            json!(0),
+            json!("NaN"),
+            json!("inf"),
+            json!("-inf"),
+            // Fits in a `Duration` but lies past what `SystemTime` can hold.
+            json!(1e25),
+        ] {
+            assert_eq!(request_sent_at(value.clone()).sent_at(), None, "{value}");
+        }
+    }
 
     #[test]
     fn parses_minimal_request() {
@@ -204,4 +628,55 @@ mod tests {
             other => panic!("unexpected payload kind: {:?}", other),
         }
     }
+
+    #[test]
+    fn inspection_reports_errors_and_warnings() {
+        let request = serde_json::json!({
+            "uuid": 7,
+            "payloads": [
+                { "type": "log", "content": {}, "extra": true },
+                { "type": "log", "origin": { "line_number": "12" } },
+                "oops"
+            ],
+            "meta": [],
+            "version": 2
+        });
+
+        let issues: Vec<(IssueSeverity, String)> = inspect_ray_request(&request)
+            .into_iter()
+            .map(|issue| (issue.severity, issue.to_string()))
+            .collect();
+
+        assert_eq!(
+            issues,
+            vec![
+                (IssueSeverity::Warning, "version: unknown key".to_string()),
+                (
+                    IssueSeverity::Error,
+                    "uuid: expected a string, got a number".to_string()
+                ),
+                (
+                    IssueSeverity::Warning,
+                    "meta: expected an object, got an array".to_string()
+                ),
+                (
+                    IssueSeverity::Warning,
+                    "payloads[0].extra: unknown key".to_string()
+                ),
+                (
+                    IssueSeverity::Error,
+                    "payloads[1].content: missing required field".to_string()
+                ),
+                (
+                    IssueSeverity::Error,
+                    "payloads[1].origin.line_number: expected a non-negative integer, got a string"
+                        .to_string()
+                ),
+                (
+                    IssueSeverity::Error,
+                    "payloads[2]: expected an object, got a string".to_string()
+                ),
+            ]
+        );
+    }
 }
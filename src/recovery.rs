@@ -0,0 +1,146 @@
+//! Crash recovery: while the UI runs its own server, the timeline is written
+//! to a recovery file if Raygun panics, fails or is killed by a signal, and the
+//! next launch offers to restore it.
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use tracing::{info, warn};
+
+use crate::{import::exported_request, state::AppState};
+
+/// Per-user recovery file: `$XDG_STATE_HOME/raygun/recovery.jsonl`
+/// (`~/.local/state` by default) or `%LOCALAPPDATA%\raygun` on Windows.
+pub fn recovery_path() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_STATE_HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+            })
+    };
+
+    base.map(|dir| dir.join("raygun").join("recovery.jsonl"))
+}
+
+/// Requests in a recovery file left by an earlier session, if there is one.
+pub fn saved_requests(path: &Path) -> Option<usize> {
+    let text = fs::read_to_string(path).ok()?;
+    let count = text.lines().filter(|line| !line.trim().is_empty()).count();
+    (count > 0).then_some(count)
+}
+
+/// Deletes the recovery file once its session is restored or dismissed.
+pub fn discard(path: &Path) {
+    if let Err(err) = fs::remove_file(path)
+        && err.kind() != io::ErrorKind::NotFound
+    {
+        warn!(?err, path = %path.display(), "failed to delete the recovery file");
+    }
+}
+
+/// Writes the timeline to the recovery file when dropped, unless the session
+/// ended normally and [`SnapshotGuard::disarm`] was called. Dropping covers
+/// early returns on errors and unwinding panics alike.
+pub struct SnapshotGuard {
+    state: Arc<AppState>,
+    path: PathBuf,
+    armed: bool,
+}
+
+impl SnapshotGuard {
+    pub fn new(state: Arc<AppState>, path: PathBuf) -> Self {
+        Self {
+            state,
+            path,
+            armed: true,
+        }
+    }
+
+    pub fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for SnapshotGuard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        match write_snapshot(&self.state, &self.path) {
+            Ok(0) => {}
+            Ok(events) => {
+                info!(events, path = %self.path.display(), "saved the timeline for recovery")
+            }
+            Err(err) => {
+                warn!(?err, path = %self.path.display(), "failed to save the timeline for recovery")
+            }
+        }
+    }
+}
+
+/// Appends the timeline as JSON Lines and returns the number of events. An
+/// earlier session that was never restored stays in the file ahead of it.
+fn write_snapshot(state: &AppState, path: &Path) -> io::Result<usize> {
+    let events = state
+        .try_timeline_snapshot()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::WouldBlock, "the timeline is locked"))?;
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let lines: String = events
+        .iter()
+        .filter_map(|event| serde_json::to_string(&exported_request(event)).ok())
+        .map(|line| line + "\n")
+        .collect();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(lines.as_bytes())?;
+    Ok(events.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn armed_guard_saves_the_timeline() {
+        let dir = env::temp_dir().join(format!("raygun-recovery-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("recovery.jsonl");
+        let state = Arc::new(AppState::new(10));
+        state
+            .record_request(
+                serde_json::from_value(json!({
+                    "uuid": "a",
+                    "payloads": [{ "type": "log", "content": { "values": ["one"] } }],
+                    "meta": {}
+                }))
+                .unwrap(),
+            )
+            .await;
+
+        let mut guard = SnapshotGuard::new(Arc::clone(&state), path.clone());
+        guard.disarm();
+        drop(guard);
+        assert_eq!(saved_requests(&path), None);
+
+        drop(SnapshotGuard::new(state, path.clone()));
+        assert_eq!(saved_requests(&path), Some(1));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+//! The scratch pad: text pasted into the UI is turned into a timeline event
+//! so it goes through the same detail renderers as payloads sent by a client.
+
+use html_escape::encode_text;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{import, protocol::RayRequest};
+
+/// Label given to events created from the scratch pad.
+pub const SCRATCH_LABEL: &str = "Scratch";
+
+/// Requests for a paste. Captured Ray requests are replayed as they are;
+/// anything starting like JSON becomes a `json_string` payload, so malformed
+/// JSON still renders with a pointer at the error, and everything else is read
+/// as a var-dumper dump, HTML or plain.
+pub fn scratch_requests(text: &str) -> Vec<RayRequest> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let (requests, _) = import::read_requests(text);
+    if !requests.is_empty() {
+        return requests;
+    }
+
+    let payload = if text.starts_with(['{', '[']) {
+        json!({ "type": "json_string", "content": { "value": text } })
+    } else {
+        let dump = if text.contains("sf-dump") {
+            text.to_string()
+        } else {
+            format!("<pre class=sf-dump>{}</pre>", encode_text(text))
+        };
+        json!({ "type": "custom", "content": { "content": dump, "label": "" } })
+    };
+    let request = json!({
+        "uuid": Uuid::new_v4().to_string(),
+        "payloads": [
+            payload,
+            { "type": "label", "content": { "label": SCRATCH_LABEL } }
+        ],
+        "meta": {}
+    });
+    serde_json::from_value::<RayRequest>(request)
+        .map(|request| vec![request])
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::PayloadKind;
+
+    #[test]
+    fn pastes_become_json_dump_or_replayed_requests() {
+        let json = scratch_requests("  {\"user\": {\"id\": 1}  ");
+        assert_eq!(json.len(), 1);
+        assert_eq!(json[0].payloads[0].kind, PayloadKind::JsonString);
+        assert_eq!(json[0].payloads[1].kind, PayloadKind::Label);
+
+        let dump = scratch_requests("array:1 [\n  \"a\" => \"<b>\"\n]");
+        assert_eq!(dump[0].payloads[0].kind, PayloadKind::Custom);
+        assert_eq!(
+            dump[0].payloads[0].content_string("content"),
+            Some("<pre class=sf-dump>array:1 [\n  \"a\" =&gt; \"&lt;b&gt;\"\n]</pre>")
+        );
+
+        let replayed = scratch_requests(
+            r#"{"uuid":"a","payloads":[{"type":"log","content":{"values":["one"]}}],"meta":{}}"#,
+        );
+        assert_eq!(replayed[0].uuid, "a");
+        assert!(scratch_requests(" \n").is_empty());
+    }
+}
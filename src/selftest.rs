@@ -0,0 +1,321 @@
+//! Connectivity self-test (`raygun selftest`): binds the configured addresses,
+//! sends a payload to each over loopback as a Ray client would, and checks it
+//! comes out the other end of the ingest → state → summary pipeline. Meant for
+//! "nothing shows up" reports, where the answer is usually a port or a bind
+//! address.
+
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    app::{build_detail_view_for_event, summarize_event},
+    config::{Config, LocaleConfig},
+    server::{self, ServerConfig, ServerError},
+    state::AppState,
+};
+
+/// Port Ray clients send to unless configured otherwise.
+const RAY_PORT: u16 = 23_517;
+/// How long each request of the self-test may take.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Ok,
+    Hint,
+    Error,
+}
+
+/// One line of the diagnosis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub level: Level,
+    pub message: String,
+}
+
+impl Finding {
+    fn ok(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Ok,
+            message: message.into(),
+        }
+    }
+
+    fn hint(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Hint,
+            message: message.into(),
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: Level::Error,
+            message: message.into(),
+        }
+    }
+}
+
+/// Runs the self-test and prints the diagnosis, errors to stderr; returns
+/// `false` when a check failed.
+pub async fn run(config: &Config) -> bool {
+    let findings = diagnose(config).await;
+    for finding in &findings {
+        match finding.level {
+            Level::Ok => println!("ok: {}", finding.message),
+            Level::Hint => println!("hint: {}", finding.message),
+            Level::Error => eprintln!("error: {}", finding.message),
+        }
+    }
+
+    let failed = findings.iter().any(|finding| finding.level == Level::Error);
+    if failed {
+        eprintln!("Self-test failed.");
+    } else {
+        println!("Self-test passed; Ray clients on this machine can reach Raygun.");
+    }
+    !failed
+}
+
+pub async fn diagnose(config: &Config) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let client = match reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .no_proxy()
+        .user_agent(concat!("raygun/", env!("CARGO_PKG_VERSION")))
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            findings.push(Finding::error(format!(
+                "could not set up an HTTP client: {}",
+                err
+            )));
+            return findings;
+        }
+    };
+
+    let state = Arc::new(AppState::default());
+    let server_config = ServerConfig {
+        bind_addrs: config.bind_addrs.clone(),
+        max_body_size: config.max_body_size,
+        dual_stack: config.dual_stack,
+        drain_timeout: config.drain_timeout(),
+        trusted_proxies: config.trusted_proxies.clone(),
+        ..ServerConfig::default()
+    };
+    let server = match server::spawn(Arc::clone(&state), server_config).await {
+        Ok(server) => server,
+        Err(ServerError::Bind { addr, source }) => {
+            findings.push(bind_failure(&client, addr, source.kind()).await);
+            return findings;
+        }
+        Err(err) => {
+            findings.push(Finding::error(format!(
+                "could not start the HTTP server: {}",
+                err
+            )));
+            return findings;
+        }
+    };
+
+    for &addr in server.addrs() {
+        findings.push(Finding::ok(format!("bound {}", addr)));
+        findings.push(send_payload(&client, &state, addr).await);
+    }
+    if let Err(err) = server.shutdown().await {
+        findings.push(Finding::hint(format!(
+            "the test server did not shut down cleanly: {}",
+            err
+        )));
+    }
+
+    findings.extend(reach_hints(&config.bind_addrs));
+    findings
+}
+
+/// Explains why `addr` could not be bound. A port in use is probed with the
+/// availability check Ray clients send, to tell a running Raygun (or Ray) from
+/// an unrelated program.
+async fn bind_failure(client: &reqwest::Client, addr: SocketAddr, kind: ErrorKind) -> Finding {
+    match kind {
+        ErrorKind::AddrInUse => {
+            let url = format!("http://{}/_availability_check", loopback(addr));
+            let answer = client
+                .get(&url)
+                .send()
+                .await
+                .map(|response| response.status());
+            if matches!(answer, Ok(StatusCode::NOT_FOUND)) {
+                Finding::error(format!(
+                    "port {} is in use by another Raygun or Ray app, which is where payloads go now; quit it or pass --bind <addr:port>",
+                    addr.port()
+                ))
+            } else {
+                Finding::error(format!(
+                    "port {} is in use by a program that isn't Raygun; stop it or pass --bind <addr:port>",
+                    addr.port()
+                ))
+            }
+        }
+        ErrorKind::PermissionDenied => Finding::error(format!(
+            "not allowed to bind {}; ports below 1024 need elevated privileges",
+            addr
+        )),
+        ErrorKind::AddrNotAvailable => Finding::error(format!(
+            "{} is not an address of this machine; bind 127.0.0.1 or 0.0.0.0 instead",
+            addr.ip()
+        )),
+        _ => Finding::error(format!("could not bind {}: {:?}", addr, kind)),
+    }
+}
+
+/// Sends a log payload to `addr` and checks it is recorded and summarized.
+async fn send_payload(client: &reqwest::Client, state: &AppState, addr: SocketAddr) -> Finding {
+    let uuid = Uuid::new_v4().to_string();
+    let text = format!("Raygun self-test {}", &uuid[..8]);
+    let request = json!({
+        "uuid": uuid,
+        "payloads": [{
+            "type": "log",
+            "content": { "values": [text] },
+            "origin": { "file": "raygun selftest", "line_number": 1 }
+        }],
+        "meta": { "raygun_self_test": true }
+    });
+    let target = loopback(addr);
+
+    let status = match client
+        .post(format!("http://{}/", target))
+        .json(&request)
+        .send()
+        .await
+    {
+        Ok(response) => response.status(),
+        Err(err) if err.is_timeout() => {
+            return Finding::error(format!(
+                "no answer from {} within {}s; a firewall may be dropping loopback traffic",
+                target,
+                TIMEOUT.as_secs()
+            ));
+        }
+        Err(err) => {
+            return Finding::error(format!(
+                "could not send a payload to {}: {}; a firewall or security tool may be blocking it",
+                target, err
+            ));
+        }
+    };
+    if !status.is_success() {
+        return Finding::error(format!("{} answered the payload with {}", target, status));
+    }
+
+    let Some(event) = state
+        .timeline_snapshot()
+        .await
+        .into_iter()
+        .find(|event| event.request.uuid == uuid)
+    else {
+        return Finding::error(format!(
+            "{} accepted the payload but it never reached the timeline",
+            target
+        ));
+    };
+    let summary = summarize_event(&event).summary;
+    if summary != text
+        || build_detail_view_for_event(&event, None, LocaleConfig::default())
+            .lines
+            .is_empty()
+    {
+        return Finding::error(format!(
+            "the payload sent to {} was recorded but shows as `{}`",
+            target, summary
+        ));
+    }
+    Finding::ok(format!(
+        "a payload sent to {} reached the timeline as `{}`",
+        target, summary
+    ))
+}
+
+/// Who else can reach the configured addresses, and what they need to do so.
+fn reach_hints(bind_addrs: &[SocketAddr]) -> Vec<Finding> {
+    let mut hints = Vec::new();
+    if bind_addrs.iter().all(|addr| addr.ip().is_loopback()) {
+        hints.push(Finding::hint(
+            "only this machine can connect; for Docker containers, VMs or other machines pass --bind 0.0.0.0:<port> and point the client at this machine (e.g. host.docker.internal)",
+        ));
+    } else {
+        hints.push(Finding::hint(
+            "clients on other machines also need the port open in this machine's firewall",
+        ));
+    }
+    if let Some(addr) = bind_addrs.iter().find(|addr| addr.port() != RAY_PORT) {
+        hints.push(Finding::hint(format!(
+            "Ray clients send to port {} by default; set their port to {} (e.g. `port` in ray.php)",
+            RAY_PORT,
+            addr.port()
+        )));
+    }
+    hints
+}
+
+/// The address a client on this machine uses to reach `addr`.
+fn loopback(addr: SocketAddr) -> SocketAddr {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    SocketAddr::new(ip, addr.port())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn passes_on_a_free_port_and_explains_a_taken_one() {
+        let config = Config::parse_from(["raygun", "selftest", "--bind", "127.0.0.1:0"]);
+        let findings = diagnose(&config).await;
+        assert!(
+            findings.iter().all(|finding| finding.level != Level::Error),
+            "{:?}",
+            findings
+        );
+        assert!(
+            findings
+                .iter()
+                .any(|finding| finding.message.contains("reached the timeline")),
+            "{:?}",
+            findings
+        );
+
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = taken.local_addr().unwrap().to_string();
+        // Hang up on the availability probe, as a program that doesn't speak
+        // HTTP would.
+        std::thread::spawn(move || drop(taken.accept()));
+        let config = Config::parse_from(["raygun", "selftest", "--bind", addr.as_str()]);
+        let findings = diagnose(&config).await;
+        assert_eq!(findings.len(), 1, "{:?}", findings);
+        assert_eq!(findings[0].level, Level::Error);
+        assert!(
+            findings[0]
+                .message
+                .contains("in use by a program that isn't Raygun"),
+            "{:?}",
+            findings
+        );
+    }
+}
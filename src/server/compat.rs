@@ -0,0 +1,100 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use serde_json::Value;
+use tracing::warn;
+
+use crate::protocol::{PayloadKind, RayRequest};
+
+/// Warns about payload types Raygun doesn't understand, once per Ray client
+/// version and type, so a client that outgrew Raygun shows up in the problems
+/// strip instead of as a string of "unknown" entries.
+#[derive(Debug, Default)]
+pub struct CompatWarnings {
+    seen: Mutex<HashSet<(String, String)>>,
+}
+
+impl CompatWarnings {
+    /// Reports the unknown payload types in `request` that weren't reported
+    /// for its client version yet, and returns them.
+    pub fn check(&self, request: &RayRequest) -> Vec<String> {
+        let unknown: Vec<&str> = request
+            .payloads
+            .iter()
+            .filter_map(|payload| match &payload.kind {
+                PayloadKind::Unknown(kind) => Some(kind.as_str()),
+                _ => None,
+            })
+            .collect();
+        if unknown.is_empty() {
+            return Vec::new();
+        }
+
+        let client = client_version(request);
+        let Ok(mut seen) = self.seen.lock() else {
+            return Vec::new();
+        };
+        let mut reported = Vec::new();
+        for kind in unknown {
+            if !seen.insert((client.clone(), kind.to_string())) {
+                continue;
+            }
+            warn!(
+                "{} sent a `{}` payload, which this Raygun doesn't understand; it may need a newer Raygun (find them with kind:{})",
+                client,
+                kind,
+                crate::filter::quote(kind)
+            );
+            reported.push(kind.to_string());
+        }
+        reported
+    }
+}
+
+/// `ray 1.41.0` from `meta.ray_package_version`.
+fn client_version(request: &RayRequest) -> String {
+    match request.meta.get("ray_package_version") {
+        Some(Value::String(version)) if !version.trim().is_empty() => {
+            format!("ray {}", version.trim())
+        }
+        _ => "A Ray client of unknown version".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn request(version: &str, kinds: &[&str]) -> RayRequest {
+        serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": kinds
+                .iter()
+                .map(|kind| json!({ "type": kind, "content": {} }))
+                .collect::<Vec<_>>(),
+            "meta": { "ray_package_version": version }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn reports_each_unknown_type_once_per_client_version() {
+        let warnings = CompatWarnings::default();
+
+        assert_eq!(
+            warnings.check(&request("1.41.0", &["log", "quantum_flux"])),
+            vec!["quantum_flux"]
+        );
+        assert!(
+            warnings
+                .check(&request("1.41.0", &["quantum_flux"]))
+                .is_empty()
+        );
+        assert_eq!(
+            warnings.check(&request("1.42.0", &["quantum_flux", "hologram"])),
+            vec!["quantum_flux", "hologram"]
+        );
+        assert!(warnings.check(&request("1.42.0", &["table"])).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde_json::Value;
+use tracing::{info, warn};
+
+/// Writes the first request seen for every payload type to `<dir>/<type>.json`
+/// so real traffic can be turned into protocol test fixtures.
+#[derive(Debug)]
+pub struct FixtureRecorder {
+    dir: PathBuf,
+    seen: Mutex<HashSet<String>>,
+}
+
+impl FixtureRecorder {
+    pub fn new(dir: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            seen: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Records `body` for each payload type that has no fixture yet. Existing
+    /// files are never overwritten, so a curated corpus stays intact.
+    pub async fn capture(&self, body: &[u8]) {
+        let Ok(request) = serde_json::from_slice::<Value>(body) else {
+            return;
+        };
+
+        let kinds: Vec<String> = request
+            .get("payloads")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|payload| payload.get("type").and_then(Value::as_str))
+            .map(fixture_name)
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        let fresh: Vec<String> = {
+            let mut seen = self.seen.lock().expect("fixture set poisoned");
+            kinds
+                .into_iter()
+                .filter(|name| seen.insert(name.clone()))
+                .collect()
+        };
+
+        if fresh.is_empty() {
+            return;
+        }
+
+        let pretty = match serde_json::to_string_pretty(&request) {
+            Ok(pretty) => pretty,
+            Err(error) => {
+                warn!(?error, "failed to serialize fixture");
+                return;
+            }
+        };
+
+        for name in fresh {
+            let path = self.dir.join(format!("{}.json", name));
+            if let Err(error) = write_new(&path, &pretty).await {
+                warn!(?error, path = %path.display(), "failed to write fixture");
+            }
+        }
+    }
+}
+
+async fn write_new(path: &Path, contents: &str) -> std::io::Result<()> {
+    if tokio::fs::try_exists(path).await? {
+        return Ok(());
+    }
+
+    tokio::fs::write(path, format!("{}\n", contents)).await?;
+    info!(path = %path.display(), "captured fixture");
+    Ok(())
+}
+
+/// Keeps payload types safe to use as file names.
+fn fixture_name(kind: &str) -> String {
+    kind.chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn captures_one_file_per_payload_type() {
+        let dir = std::env::temp_dir().join(format!("raygun-fixtures-{}", uuid::Uuid::new_v4()));
+        let recorder = FixtureRecorder::new(dir.clone()).unwrap();
+
+        let first = json!({
+            "uuid": "1",
+            "payloads": [
+                { "type": "log", "content": { "values": ["first"] } },
+                { "type": "color", "content": { "color": "red" } }
+            ],
+            "meta": {}
+        });
+        let second = json!({
+            "uuid": "2",
+            "payloads": [{ "type": "log", "content": { "values": ["second"] } }],
+            "meta": {}
+        });
+        recorder.capture(first.to_string().as_bytes()).await;
+        recorder.capture(second.to_string().as_bytes()).await;
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["color.json", "log.json"]);
+
+        let log = std::fs::read_to_string(dir.join("log.json")).unwrap();
+        assert!(log.contains("first") && !log.contains("second"));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
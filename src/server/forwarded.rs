@@ -0,0 +1,133 @@
+//! The client behind a trusted reverse proxy or tunnel, from the
+//! `X-Forwarded-For` and `Forwarded` headers the proxy adds.
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+
+/// The address to show for a request from `peer`. Headers are only believed
+/// when `peer` is a trusted proxy; the client is then the last address in the
+/// forwarding chain that isn't itself a trusted proxy. Forwarded addresses
+/// usually come without a port, which is then `0`.
+pub fn client_addr(peer: SocketAddr, headers: &HeaderMap, trusted: &[IpNet]) -> SocketAddr {
+    let is_trusted = |ip: &IpAddr| is_trusted(*ip, trusted);
+    if !is_trusted(&peer.ip()) {
+        return peer;
+    }
+
+    let chain = forwarded_for(headers);
+    chain
+        .iter()
+        .rev()
+        .find(|addr| !is_trusted(&addr.ip()))
+        .or_else(|| chain.first())
+        .copied()
+        .unwrap_or(peer)
+}
+
+/// Whether forwarding headers from `ip` are believed.
+pub fn is_trusted(ip: IpAddr, trusted: &[IpNet]) -> bool {
+    trusted.iter().any(|network| network.contains(&ip))
+}
+
+/// Addresses the request was forwarded for, client first. `Forwarded`
+/// (RFC 7239) wins over `X-Forwarded-For` when both are present; entries that
+/// aren't addresses, like `unknown` or obfuscated names, are skipped.
+fn forwarded_for(headers: &HeaderMap) -> Vec<SocketAddr> {
+    let values = |name: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    let forwarded: Vec<SocketAddr> = values("forwarded")
+        .iter()
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                key.trim()
+                    .eq_ignore_ascii_case("for")
+                    .then(|| parse_node(value.trim().trim_matches('"')))
+                    .flatten()
+            })
+        })
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+    values("x-forwarded-for")
+        .iter()
+        .filter_map(|value| parse_node(value))
+        .collect()
+}
+
+/// `203.0.113.7`, `203.0.113.7:4711`, `2001:db8::1` or `[2001:db8::1]:4711`.
+fn parse_node(node: &str) -> Option<SocketAddr> {
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr);
+    }
+    let ip = node.trim_start_matches('[').trim_end_matches(']');
+    ip.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_ip_network;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn believes_forwarded_headers_from_trusted_proxies_only() {
+        let trusted = [
+            parse_ip_network("127.0.0.1").unwrap(),
+            parse_ip_network("10.0.0.0/8").unwrap(),
+        ];
+        let proxy: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let stranger: SocketAddr = "198.51.100.4:50000".parse().unwrap();
+        let forwarded = headers(&[("x-forwarded-for", "203.0.113.7, 10.1.2.3")]);
+
+        assert_eq!(
+            client_addr(proxy, &forwarded, &trusted),
+            "203.0.113.7:0".parse().unwrap()
+        );
+        assert_eq!(client_addr(stranger, &forwarded, &trusted), stranger);
+        assert_eq!(client_addr(proxy, &forwarded, &[]), proxy);
+        assert_eq!(client_addr(proxy, &HeaderMap::new(), &trusted), proxy);
+
+        // A client can't hide behind an address it prepends itself.
+        let spoofed = headers(&[
+            ("x-forwarded-for", "10.9.9.9"),
+            ("x-forwarded-for", "198.51.100.4"),
+        ]);
+        assert_eq!(
+            client_addr(proxy, &spoofed, &trusted),
+            "198.51.100.4:0".parse().unwrap()
+        );
+
+        let rfc = headers(&[
+            (
+                "forwarded",
+                r#"for="[2001:db8::1]:4711";proto=https, for=unknown"#,
+            ),
+            ("x-forwarded-for", "203.0.113.7"),
+        ]);
+        assert_eq!(
+            client_addr(proxy, &rfc, &trusted),
+            "[2001:db8::1]:4711".parse().unwrap()
+        );
+        assert!(parse_ip_network("proxy.local").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! End-to-end tests that replay captured Ray client traffic against a real
+//! listener and check what the UI would show for it.
+//!
+//! Fixtures live in `tests/fixtures/<client>/<name>.json`. A fixture is either
+//! a single Ray request or an array of requests sent in order (Ray clients
+//! often follow a payload with a separate `color`/`label` request).
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use super::{ServerConfig, ServerHandle, spawn};
+use crate::{
+    app::{build_detail_view_for_event, summarize_event},
+    config::{DEFAULT_DRAIN_TIMEOUT, DEFAULT_MAX_BODY_SIZE, LocaleConfig},
+    state::AppState,
+};
+
+struct Harness {
+    state: Arc<AppState>,
+    server: ServerHandle,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let state = Arc::new(AppState::default());
+        let config = ServerConfig {
+            bind_addrs: vec![SocketAddr::from(([127, 0, 0, 1], 0))],
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            dual_stack: false,
+            capture_fixtures: None,
+            strict_protocol: false,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            upstream: None,
+            trusted_proxies: Vec::new(),
+        };
+        let server = spawn(Arc::clone(&state), config)
+            .await
+            .expect("server should bind");
+
+        Self { state, server }
+    }
+
+    /// Posts every request in the fixture the way a Ray client would.
+    async fn replay(&self, fixture: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(fixture);
+        let raw = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("failed to read {}: {}", path.display(), err));
+        let requests = match serde_json::from_str::<Value>(&raw).expect("fixture is JSON") {
+            Value::Array(requests) => requests,
+            request => vec![request],
+        };
+
+        for request in requests {
+            let status = self.post(request.to_string().as_bytes()).await;
+            assert_eq!(status, 202, "{} was not accepted", fixture);
+        }
+    }
+
+    async fn post(&self, body: &[u8]) -> u16 {
+        let addr = self.server.addrs()[0];
+        let mut stream = TcpStream::connect(addr).await.expect("connect to server");
+        let head = format!(
+            "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            addr,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.write_all(body).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .expect("response has a status line")
+    }
+
+    /// Summary line and flattened detail text of the newest timeline event.
+    async fn latest(&self) -> (String, Vec<String>, Option<String>) {
+        let events = self.state.timeline_snapshot().await;
+        let event = events.last().expect("an event was recorded");
+        let entry = summarize_event(event);
+        let detail = build_detail_view_for_event(event, None, LocaleConfig::default());
+        let lines = detail
+            .lines
+            .iter()
+            .map(|line| {
+                line.segments
+                    .iter()
+                    .map(|segment| segment.text.as_str())
+                    .collect::<String>()
+            })
+            .chain(std::iter::once(detail.footer))
+            .collect();
+
+        (
+            format!("[{}] {}", entry.kind, entry.summary),
+            lines,
+            entry.color,
+        )
+    }
+
+    async fn stop(self) {
+        self.server
+            .shutdown()
+            .await
+            .expect("shutdown should succeed");
+    }
+}
+
+fn assert_has_line(lines: &[String], needle: &str) {
+    assert!(
+        lines.iter().any(|line| line.contains(needle)),
+        "expected a detail line containing {:?}, got {:#?}",
+        needle,
+        lines
+    );
+}
+
+#[tokio::test]
+async fn replays_laravel_fixtures() {
+    let harness = Harness::start().await;
+
+    harness.replay("laravel/log.json").await;
+    let (summary, lines, _) = harness.latest().await;
+    assert_eq!(summary, "[log] Hello from Laravel");
+    assert_has_line(&lines, "/var/www/html/routes/web.php:18");
+
+    harness.replay("laravel/dump_array.json").await;
+    let (_, lines, _) = harness.latest().await;
+    assert_has_line(&lines, "\"name\" => \"Ada\"");
+    assert_has_line(&lines, "\"admin\" => true");
+
+    harness.replay("laravel/exception.json").await;
+    let (summary, lines, color) = harness.latest().await;
+    assert!(summary.starts_with("[exception]"), "{}", summary);
+    assert_eq!(color.as_deref(), Some("red"));
+    assert_has_line(&lines, "ModelNotFoundException");
+    assert_has_line(&lines, "stack trace (2 frames)");
+
+    harness.replay("laravel/table.json").await;
+    let (_, lines, _) = harness.latest().await;
+    assert_has_line(&lines, "Label: Queue");
+    assert_has_line(&lines, "attempts: 3");
+
+    assert_eq!(harness.state.timeline_len().await, 4);
+    let events = harness.state.timeline_snapshot().await;
+    assert!(
+        events.iter().all(|event| event
+            .remote_addr
+            .is_some_and(|addr| addr.ip().is_loopback())),
+        "every event records the client's address"
+    );
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn replays_wordpress_fixtures() {
+    let harness = Harness::start().await;
+
+    harness.replay("wordpress/log_then_color.json").await;
+    let (summary, _, color) = harness.latest().await;
+    assert_eq!(summary, "[log] Post 42 saved");
+    assert_eq!(color.as_deref(), Some("green"));
+    assert_eq!(harness.state.timeline_len().await, 1);
+
+    harness.replay("wordpress/text.json").await;
+    let (_, lines, _) = harness.latest().await;
+    assert_has_line(&lines, "Query monitor: 37 queries");
+
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn replays_javascript_fixtures() {
+    let harness = Harness::start().await;
+
+    harness.replay("js/log.json").await;
+    let (summary, lines, _) = harness.latest().await;
+    assert_eq!(summary, "[log] hello from node");
+    assert_has_line(&lines, "/app/src/index.js:7");
+
+    harness.replay("js/json_with_label.json").await;
+    let events = harness.state.timeline_snapshot().await;
+    assert_eq!(
+        events.last().and_then(|event| event.label.as_deref()),
+        Some("api response")
+    );
+    let (_, lines, _) = harness.latest().await;
+    assert_has_line(&lines, "tags");
+
+    harness.stop().await;
+}
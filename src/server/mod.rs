@@ -1,137 +1,683 @@
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+mod compat;
+mod fixtures;
+mod forwarded;
+mod upstream;
+
+use std::{
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use axum::{
     Json, Router,
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::{Body, Bytes, to_bytes},
+    extract::{
+        ConnectInfo, DefaultBodyLimit, Path, Query, RawQuery, Request, State,
+        rejection::JsonRejection,
+    },
+    http::{
+        HeaderMap, StatusCode,
+        header::{CONTENT_LENGTH, HOST},
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
+use ipnet::IpNet;
 use serde::Deserialize;
 use serde_json::json;
+use socket2::{Domain, Protocol, Socket, Type};
 use thiserror::Error;
-use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle, time};
+use tokio::{net::TcpListener, sync::watch, task::JoinHandle, time};
+use tokio_stream::StreamExt;
 use tracing::{info, warn};
+use uuid::Uuid;
 
-use crate::{protocol::RayRequest, state::AppState};
+use crate::{
+    config::{DEFAULT_DRAIN_TIMEOUT, DEFAULT_MAX_BODY_SIZE},
+    format, import,
+    protocol::{IssueSeverity, RayRequest, inspect_ray_request, parse_ray_request},
+    state::{Annotation, AppState, Diagnostic, Provenance},
+};
+use compat::CompatWarnings;
+use fixtures::FixtureRecorder;
+use upstream::Upstream;
+
+/// Number of leading bytes of a rejected body kept for the timeline entry.
+const OVERSIZED_PREVIEW_BYTES: usize = 512;
 
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
-    pub bind_addr: SocketAddr,
+    pub bind_addrs: Vec<SocketAddr>,
+    pub max_body_size: usize,
+    /// Also bind the IPv4/IPv6 counterpart of every wildcard or loopback address.
+    pub dual_stack: bool,
+    /// Directory that receives one fixture file per payload type seen.
+    pub capture_fixtures: Option<PathBuf>,
+    /// Reject requests that deviate from the protocol and report diagnostics.
+    pub strict_protocol: bool,
+    /// How long shutdown waits for in-flight requests before aborting them.
+    pub drain_timeout: Duration,
+    /// Ray server that payloads and lock polls are passed on to (proxy mode).
+    pub upstream: Option<String>,
+    /// Proxies whose forwarding headers are believed.
+    pub trusted_proxies: Vec<IpNet>,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
-        let bind_addr = std::env::var("RAYGUN_BIND")
+        let bind_addrs = std::env::var("RAYGUN_BIND")
             .ok()
-            .and_then(|value| value.parse().ok())
-            .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 23_517)));
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter_map(|addr| addr.trim().parse().ok())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|addrs| !addrs.is_empty())
+            .unwrap_or_else(|| vec![SocketAddr::from(([127, 0, 0, 1], 23_517))]);
 
-        Self { bind_addr }
+        Self {
+            bind_addrs,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            dual_stack: false,
+            capture_fixtures: None,
+            strict_protocol: false,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            upstream: None,
+            trusted_proxies: Vec::new(),
+        }
     }
 }
 
 #[derive(Clone)]
 struct HttpState {
     app_state: Arc<AppState>,
+    max_body_size: usize,
+    fixtures: Option<Arc<FixtureRecorder>>,
+    strict_protocol: bool,
+    compat: Arc<CompatWarnings>,
+    upstream: Option<Arc<Upstream>>,
+    trusted_proxies: Arc<[IpNet]>,
 }
 
 #[derive(Debug)]
 pub struct ServerHandle {
-    addr: SocketAddr,
-    shutdown: Option<oneshot::Sender<()>>,
-    join_handle: Option<JoinHandle<Result<(), std::io::Error>>>,
+    state: Arc<AppState>,
+    config: ServerConfig,
+    addrs: Vec<SocketAddr>,
+    shutdown: Option<watch::Sender<bool>>,
+    join_handles: Vec<JoinHandle<Result<(), std::io::Error>>>,
 }
 
 impl ServerHandle {
-    pub fn addr(&self) -> SocketAddr {
-        self.addr
+    pub fn addrs(&self) -> &[SocketAddr] {
+        &self.addrs
     }
 
-    pub async fn shutdown(mut self) -> Result<(), ServerError> {
-        if let Some(tx) = self.shutdown.take() {
-            if tx.send(()).is_err() {
-                warn!("server shutdown signal receiver dropped");
+    /// Addresses as configured, before the dual-stack counterparts are added.
+    pub fn bind_addrs(&self) -> &[SocketAddr] {
+        &self.config.bind_addrs
+    }
+
+    /// Moves the server to `bind_addrs`, serving the same timeline. The new
+    /// addresses are bound while the old listeners still run; if one is in
+    /// use, possibly by those listeners, they are shut down first and brought
+    /// back when the new ones still cannot bind. The error comes with
+    /// whichever server is left running.
+    pub async fn rebind(
+        self,
+        bind_addrs: Vec<SocketAddr>,
+    ) -> Result<ServerHandle, Box<(ServerError, Option<ServerHandle>)>> {
+        let state = Arc::clone(&self.state);
+        let old_config = self.config.clone();
+        let config = ServerConfig {
+            bind_addrs,
+            ..old_config.clone()
+        };
+
+        match spawn(Arc::clone(&state), config.clone()).await {
+            Ok(server) => {
+                self.shutdown().await.ok();
+                return Ok(server);
             }
+            Err(ServerError::Bind { source, .. }) if source.kind() == ErrorKind::AddrInUse => {}
+            Err(error) => return Err(Box::new((error, Some(self)))),
         }
 
-        let mut join_handle = match self.join_handle.take() {
-            Some(handle) => handle,
-            None => return Ok(()),
-        };
+        self.shutdown().await.ok();
+        match spawn(Arc::clone(&state), config).await {
+            Ok(server) => Ok(server),
+            Err(error) => {
+                warn!(%error, "failed to rebind the HTTP server; restoring the old addresses");
+                let restored = spawn(state, old_config).await.ok();
+                Err(Box::new((error, restored)))
+            }
+        }
+    }
 
-        tokio::select! {
-            join_result = &mut join_handle => match join_result {
-                Ok(Ok(())) => Ok(()),
-                Ok(Err(error)) => Err(ServerError::Io(error)),
-                Err(error) => Err(ServerError::Join(error)),
-            },
-            _ = time::sleep(Duration::from_secs(2)) => {
-                warn!("HTTP server shutdown timed out; aborting");
-                join_handle.abort();
-                Ok(())
+    /// Stops accepting connections and waits up to the drain timeout for
+    /// requests in flight to finish.
+    pub async fn shutdown(mut self) -> Result<(), ServerError> {
+        if let Some(tx) = self.shutdown.take()
+            && tx.send(true).is_err()
+        {
+            warn!("server shutdown signal receivers dropped");
+        }
+
+        let deadline = time::Instant::now() + self.config.drain_timeout;
+        let mut outcome = Ok(());
+
+        for mut join_handle in self.join_handles.drain(..) {
+            match time::timeout_at(deadline, &mut join_handle).await {
+                Ok(Ok(Ok(()))) => {}
+                Ok(Ok(Err(error))) => outcome = Err(ServerError::Io(error)),
+                Ok(Err(error)) => outcome = Err(ServerError::Join(error)),
+                Err(_) => {
+                    warn!(timeout = ?self.config.drain_timeout, "requests still in flight after the drain timeout; aborting them");
+                    join_handle.abort();
+                }
             }
         }
+
+        outcome
     }
 }
 
 #[derive(Debug, Error)]
 pub enum ServerError {
+    #[error("failed to bind {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        source: std::io::Error,
+    },
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("server task failed to join: {0}")]
     Join(#[from] tokio::task::JoinError),
+    #[error("failed to set up forwarding to the upstream: {0}")]
+    Upstream(#[from] reqwest::Error),
 }
 
+/// Binds every configured address and serves the same router (and therefore the
+/// same `AppState`) on each of them. Either all listeners come up or none do.
 pub async fn spawn(
     state: Arc<AppState>,
     config: ServerConfig,
 ) -> Result<ServerHandle, ServerError> {
-    let listener = TcpListener::bind(config.bind_addr).await?;
+    let bind_addrs = if config.dual_stack {
+        dual_stack_addrs(&config.bind_addrs)
+    } else {
+        config.bind_addrs.clone()
+    };
+
+    let mut listeners = Vec::with_capacity(bind_addrs.len());
+    for (index, addr) in bind_addrs.iter().enumerate() {
+        // Ephemeral ports (`:0`) are legitimately repeated; anything else would
+        // just fail with "address in use" against our own listener.
+        if addr.port() != 0 && bind_addrs[..index].contains(addr) {
+            continue;
+        }
+
+        // When the same port is also bound on IPv4, keep the IPv6 socket v6-only
+        // so it doesn't claim v4-mapped traffic (the Linux default) and collide.
+        let v6_only = addr.is_ipv6()
+            && bind_addrs
+                .iter()
+                .any(|other| other.is_ipv4() && other.port() == addr.port());
+
+        let listener = bind_listener(*addr, v6_only).map_err(|source| ServerError::Bind {
+            addr: *addr,
+            source,
+        })?;
+        listeners.push(listener);
+    }
+
+    let fixtures = config
+        .capture_fixtures
+        .clone()
+        .map(FixtureRecorder::new)
+        .transpose()?
+        .map(Arc::new);
+    let upstream = config
+        .upstream
+        .as_deref()
+        .map(Upstream::new)
+        .transpose()?
+        .map(Arc::new);
 
     let http_state = HttpState {
         app_state: Arc::clone(&state),
+        max_body_size: config.max_body_size,
+        fixtures,
+        strict_protocol: config.strict_protocol,
+        compat: Arc::default(),
+        upstream,
+        trusted_proxies: config.trusted_proxies.clone().into(),
     };
 
-    let router = Router::new()
-        .route("/", post(ingest))
+    let router = router(http_state);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+    let mut addrs = Vec::with_capacity(listeners.len());
+    let mut join_handles = Vec::with_capacity(listeners.len());
+
+    for listener in listeners {
+        let addr = listener.local_addr()?;
+        let mut shutdown_rx = shutdown_rx.clone();
+
+        let server = axum::serve(
+            listener,
+            router
+                .clone()
+                .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move {
+            let _ = shutdown_rx.wait_for(|stop| *stop).await;
+        });
+
+        join_handles.push(tokio::spawn(async move {
+            if let Err(error) = server.await {
+                warn!(?error, %addr, "HTTP server terminated with error");
+                Err(error)
+            } else {
+                Ok(())
+            }
+        }));
+
+        info!(%addr, "HTTP server listening");
+        addrs.push(addr);
+    }
+
+    Ok(ServerHandle {
+        state,
+        config,
+        addrs,
+        shutdown: Some(shutdown_tx),
+        join_handles,
+    })
+}
+
+fn bind_listener(addr: SocketAddr, v6_only: bool) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if v6_only {
+        socket.set_only_v6(true)?;
+    }
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// Adds the other address family for wildcard and loopback binds, e.g.
+/// `0.0.0.0:23517` gains `[::]:23517` and `[::1]:23517` gains `127.0.0.1:23517`.
+fn dual_stack_addrs(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+    let mut expanded = Vec::with_capacity(addrs.len() * 2);
+
+    for addr in addrs {
+        expanded.push(*addr);
+
+        let counterpart = match addr.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+            IpAddr::V4(ip) if ip.is_loopback() => Some(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+            IpAddr::V6(ip) if ip.is_unspecified() => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpAddr::V6(ip) if ip.is_loopback() => Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            _ => None,
+        };
+
+        if let Some(ip) = counterpart {
+            let other = SocketAddr::new(ip, addr.port());
+            if !addrs.contains(&other) && !expanded.contains(&other) {
+                expanded.push(other);
+            }
+        }
+    }
+
+    expanded
+}
+
+fn router(http_state: HttpState) -> Router {
+    let ingest_route = post(ingest)
+        .route_layer(middleware::from_fn_with_state(
+            http_state.clone(),
+            capture_fixture,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            http_state.clone(),
+            enforce_body_limit,
+        ))
+        // The limit is enforced by `enforce_body_limit`; axum's 2 MB default would
+        // otherwise reject large dumps before we get to record them.
+        .route_layer(DefaultBodyLimit::disable());
+
+    Router::new()
+        .route("/", ingest_route)
         .route("/locks/:name", get(lock_exists))
+        .route("/api/events/:id", get(event_details))
+        .route("/api/events/:id/annotations", post(annotate_event))
         .route("/_availability_check", get(availability_check))
-        .with_state(http_state);
+        .layer(middleware::from_fn_with_state(
+            http_state.clone(),
+            detect_public_url,
+        ))
+        .with_state(http_state)
+}
+
+/// Remembers the externally visible URL when requests arrive through a tunnel or
+/// reverse proxy (ngrok, cloudflared, …) so the UI can show what clients target.
+async fn detect_public_url(
+    State(state): State<HttpState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    if let Some(url) = forwarded_public_url(peer, request.headers(), &state.trusted_proxies) {
+        state.app_state.note_public_url(url).await;
+    }
 
-    let (shutdown_tx, shutdown_rx) = oneshot::channel();
-    let addr = listener.local_addr()?;
+    next.run(request).await
+}
 
-    let server = axum::serve(listener, router.into_make_service()).with_graceful_shutdown(async {
-        let _ = shutdown_rx.await;
-    });
+/// Headers whose values are credentials and never kept with an event.
+const REDACTED_HEADERS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
 
-    let join_handle = tokio::spawn(async move {
-        if let Err(error) = server.await {
-            warn!(?error, "HTTP server terminated with error");
-            Err(error)
-        } else {
-            Ok(())
+/// The request headers kept with each event, in the order they arrived, with
+/// credentials redacted.
+fn captured_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                "[redacted]".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+/// The URL a trusted proxy says it was reached at. Forwarding headers from
+/// any other peer are ignored, so a client cannot change the URL shown to
+/// everyone else.
+fn forwarded_public_url(
+    peer: Option<SocketAddr>,
+    headers: &HeaderMap,
+    trusted: &[IpNet],
+) -> Option<String> {
+    if !peer.is_some_and(|peer| forwarded::is_trusted(peer.ip(), trusted)) {
+        return None;
+    }
+
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToOwned::to_owned)
+    };
+
+    let mut host = header("x-forwarded-host");
+    let mut proto = header("x-forwarded-proto");
+
+    if host.is_none()
+        && let Some(forwarded) = header("forwarded")
+    {
+        for pair in forwarded.split(';') {
+            if let Some((key, value)) = pair.split_once('=') {
+                let value = value.trim().trim_matches('"').to_string();
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "host" => host = Some(value),
+                    "proto" => proto = proto.or(Some(value)),
+                    _ => {}
+                }
+            }
         }
-    });
+    }
 
-    info!(%addr, "HTTP server listening");
+    let host = host?;
+    let proto = proto.unwrap_or_else(|| "http".to_string());
+    Some(format!("{}://{}", proto.to_ascii_lowercase(), host))
+}
 
-    Ok(ServerHandle {
-        addr,
-        shutdown: Some(shutdown_tx),
-        join_handle: Some(join_handle),
+/// The URL the client reached Raygun at, for links in responses.
+fn base_url(peer: Option<SocketAddr>, headers: &HeaderMap, trusted: &[IpNet]) -> Option<String> {
+    forwarded_public_url(peer, headers, trusted).or_else(|| {
+        let host = headers.get(HOST)?.to_str().ok()?;
+        Some(format!("http://{}", host))
     })
 }
 
+/// Buffers the request body up to the configured limit. Oversized bodies are
+/// answered with a JSON error and leave a placeholder entry in the timeline so
+/// the user can see that something was dropped.
+async fn enforce_body_limit(
+    State(state): State<HttpState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limit = state.max_body_size;
+    let declared_len = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    let declared_too_large = declared_len.is_some_and(|len| len > limit);
+
+    let (parts, body) = request.into_parts();
+    let mut stream = body.into_data_stream();
+    let mut buffer = Vec::new();
+    let mut received = 0usize;
+    let mut exceeded = declared_too_large;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(error) => {
+                warn!(?error, "failed to read request body");
+                return StatusCode::BAD_REQUEST.into_response();
+            }
+        };
+
+        received = received.saturating_add(chunk.len());
+        if received > limit {
+            exceeded = true;
+        }
+
+        if exceeded {
+            let room = OVERSIZED_PREVIEW_BYTES.saturating_sub(buffer.len());
+            buffer.extend_from_slice(&chunk[..room.min(chunk.len())]);
+            if buffer.len() >= OVERSIZED_PREVIEW_BYTES {
+                break;
+            }
+        } else {
+            buffer.extend_from_slice(&chunk);
+        }
+    }
+
+    if exceeded {
+        let size = declared_len.unwrap_or(received);
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+        let provenance = provenance(&state, peer, &parts.headers);
+        return reject_oversized(&state, size, limit, &buffer, provenance).await;
+    }
+
+    next.run(Request::from_parts(parts, Body::from(buffer)))
+        .await
+}
+
+/// Hands the (already size-checked) body to the fixture recorder, if enabled.
+async fn capture_fixture(State(state): State<HttpState>, request: Request, next: Next) -> Response {
+    let Some(fixtures) = state.fixtures.clone() else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            warn!(?error, "failed to read request body");
+            return StatusCode::BAD_REQUEST.into_response();
+        }
+    };
+
+    fixtures.capture(&bytes).await;
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+async fn reject_oversized(
+    state: &HttpState,
+    size: usize,
+    limit: usize,
+    preview: &[u8],
+    provenance: Provenance,
+) -> Response {
+    warn!(size, limit, remote = ?provenance.remote_addr, "rejected oversized payload");
+
+    let message = format!(
+        "Payload too large: {} exceeds the {} limit",
+        format::bytes(size as f64),
+        format::bytes(limit as f64)
+    );
+
+    let mut text = message.clone();
+    let preview = String::from_utf8_lossy(preview);
+    let preview = preview.trim();
+    if !preview.is_empty() {
+        text.push_str(&format!(
+            "\n\nFirst {} bytes:\n{}",
+            OVERSIZED_PREVIEW_BYTES, preview
+        ));
+    }
+
+    let placeholder = serde_json::from_value::<RayRequest>(json!({
+        "uuid": Uuid::new_v4().to_string(),
+        "payloads": [
+            { "type": "text", "content": { "content": text } },
+            { "type": "color", "content": { "color": "red" } },
+            { "type": "label", "content": { "label": "payload too large" } }
+        ],
+        "meta": {}
+    }));
+
+    let event_id = match placeholder {
+        Ok(request) => state
+            .app_state
+            .record_request_from(request, provenance)
+            .await
+            .map(|event| event.id),
+        Err(error) => {
+            warn!(?error, "failed to build oversized payload placeholder");
+            None
+        }
+    };
+
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(json!({
+            "recorded": false,
+            "error": message,
+            "size": size,
+            "limit": limit,
+            "event_id": event_id,
+        })),
+    )
+        .into_response()
+}
+
+/// Records a payload request and, in proxy mode, passes it on. Clients get the
+/// upstream's response while it answers so they can't tell Raygun is there.
 async fn ingest(
     State(state): State<HttpState>,
-    Json(request): Json<RayRequest>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let Some(upstream) = &state.upstream else {
+        return record(&state, connect_info, &headers, &body)
+            .await
+            .into_response();
+    };
+    let (recorded, forwarded) = tokio::join!(
+        record(&state, connect_info, &headers, &body),
+        upstream.forward_payload(&headers, body.clone()),
+    );
+    forwarded.unwrap_or_else(|| recorded.into_response())
+}
+
+/// Who sent a request: the client behind any trusted proxy, the proxy it came
+/// through and the headers worth keeping.
+fn provenance(state: &HttpState, peer: Option<SocketAddr>, headers: &HeaderMap) -> Provenance {
+    let client = peer.map(|peer| forwarded::client_addr(peer, headers, &state.trusted_proxies));
+    let mut provenance = Provenance::now(client);
+    provenance.proxy_addr = peer.filter(|peer| client != Some(*peer));
+    provenance.headers = captured_headers(headers);
+    provenance
+}
+
+async fn record(
+    state: &HttpState,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: &HeaderMap,
+    body: &Bytes,
 ) -> (StatusCode, Json<serde_json::Value>) {
-    let response = match state.app_state.record_request(request).await {
+    let peer = connect_info.map(|ConnectInfo(addr)| addr);
+    let provenance = provenance(state, peer, headers);
+
+    if state.strict_protocol
+        && let Some(rejection) = strict_check(state, body).await
+    {
+        return rejection;
+    }
+
+    let request = match parse_ray_request(body) {
+        Ok(request) => request,
+        Err(error) => {
+            warn!(%error, remote = ?provenance.remote_addr, "failed to parse Ray request");
+            let status = if error.is_syntax() {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::UNPROCESSABLE_ENTITY
+            };
+            return (
+                status,
+                Json(json!({
+                    "recorded": false,
+                    "error": error.to_string(),
+                })),
+            );
+        }
+    };
+
+    state.compat.check(&request);
+
+    let response = match state
+        .app_state
+        .record_request_from(request, provenance)
+        .await
+    {
         Some(event) => json!({
             "recorded": true,
             "event_id": event.id,
+            "url": base_url(peer, headers, &state.trusted_proxies)
+                .map(|base| format!("{}/api/events/{}", base, event.id)),
+            "screen": event.screen,
+            "label": event.label,
         }),
         None => json!({
             "recorded": false,
@@ -141,6 +687,49 @@ async fn ingest(
     (StatusCode::ACCEPTED, Json(response))
 }
 
+/// Inspects the raw request in strict protocol mode, recording any issues as a
+/// diagnostic. Returns the response to send when the request is rejected.
+async fn strict_check(
+    state: &HttpState,
+    body: &[u8],
+) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    // Bodies that aren't JSON at all are reported by the regular parser.
+    let value = serde_json::from_slice::<serde_json::Value>(body).ok()?;
+    let issues = inspect_ray_request(&value);
+    if issues.is_empty() {
+        return None;
+    }
+
+    let rejected = issues
+        .iter()
+        .any(|issue| issue.severity == IssueSeverity::Error);
+    let messages: Vec<String> = issues.iter().map(ToString::to_string).collect();
+    warn!(rejected, issues = ?messages, "protocol issues in request");
+
+    state
+        .app_state
+        .record_diagnostic(Diagnostic {
+            received_at: SystemTime::now(),
+            request_uuid: value
+                .get("uuid")
+                .and_then(|uuid| uuid.as_str())
+                .map(ToOwned::to_owned),
+            issues,
+            rejected,
+        })
+        .await;
+
+    rejected.then(|| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "recorded": false,
+                "errors": messages,
+            })),
+        )
+    })
+}
+
 #[derive(Debug, Deserialize)]
 struct LockQuery {
     hostname: Option<String>,
@@ -154,11 +743,20 @@ struct LockResponse {
     stop_execution: bool,
 }
 
+/// Answers a `pause` lock poll. In proxy mode the upstream holds the locks, so
+/// the poll is passed on and Raygun's own locks are only the fallback.
 async fn lock_exists(
     State(state): State<HttpState>,
     Path(name): Path<String>,
     Query(query): Query<LockQuery>,
-) -> impl IntoResponse {
+    RawQuery(raw_query): RawQuery,
+) -> Response {
+    if let Some(upstream) = &state.upstream
+        && let Some(response) = upstream.lock(&name, raw_query.as_deref()).await
+    {
+        return response;
+    }
+
     let active = state
         .app_state
         .lock_exists(
@@ -175,37 +773,149 @@ async fn lock_exists(
             stop_execution: false,
         }),
     )
+        .into_response()
+}
+
+/// One recorded event as exported (see [`import::exported_request`]), for
+/// the link in the ingest response.
+async fn event_details(State(state): State<HttpState>, Path(id): Path<String>) -> Response {
+    let event = match Uuid::parse_str(&id) {
+        Ok(id) => state.app_state.event(id).await,
+        Err(_) => None,
+    };
+    let Some(event) = event else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("no event `{}` on the timeline", id) })),
+        )
+            .into_response();
+    };
+    Json(json!({
+        "id": event.id,
+        "screen": event.screen,
+        "label": event.label,
+        "color": event.color,
+        "annotations": event.annotations,
+        "request": import::exported_request(&event),
+    }))
+    .into_response()
+}
+
+/// Body of `POST /api/events/:id/annotations`.
+#[derive(Debug, Deserialize)]
+struct AnnotationRequest {
+    text: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+}
+
+/// Lets test runners, CI and other tools attach a note or link to an event,
+/// shown in its detail view.
+async fn annotate_event(
+    State(state): State<HttpState>,
+    Path(id): Path<String>,
+    body: Result<Json<AnnotationRequest>, JsonRejection>,
+) -> Response {
+    let error = |status: StatusCode, message: String| {
+        (status, Json(json!({ "error": message }))).into_response()
+    };
+    let Ok(event_id) = Uuid::parse_str(&id) else {
+        return error(
+            StatusCode::NOT_FOUND,
+            format!("no event `{}` on the timeline", id),
+        );
+    };
+    let request = match body {
+        Ok(Json(request)) => request,
+        Err(rejection) => return error(rejection.status(), rejection.body_text()),
+    };
+    let text = request.text.trim();
+    if text.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "`text` must not be empty".into());
+    }
+    let non_empty = |value: Option<String>| {
+        value
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let url = non_empty(request.url);
+    if let Some(url) = &url
+        && !(url.starts_with("http://") || url.starts_with("https://"))
+    {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "`url` must be an http:// or https:// URL".into(),
+        );
+    }
+
+    let annotation = Annotation {
+        text: text.to_string(),
+        url,
+        source: non_empty(request.source),
+        added_at: SystemTime::now(),
+    };
+    if !state.app_state.annotate(event_id, annotation.clone()).await {
+        return error(
+            StatusCode::NOT_FOUND,
+            format!("no event `{}` on the timeline", id),
+        );
+    }
+    (
+        StatusCode::CREATED,
+        Json(json!({ "event_id": event_id, "annotation": annotation })),
+    )
+        .into_response()
 }
 
 async fn availability_check() -> impl IntoResponse {
     StatusCode::NOT_FOUND
 }
 
+#[cfg(test)]
+mod integration_tests;
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    /// Handler state with every option off, for tests to override the fields
+    /// they exercise.
+    fn test_http_state(app_state: &Arc<AppState>) -> HttpState {
+        HttpState {
+            app_state: Arc::clone(app_state),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            fixtures: None,
+            strict_protocol: false,
+            compat: Arc::default(),
+            upstream: None,
+            trusted_proxies: Arc::default(),
+        }
+    }
+
     #[tokio::test]
     async fn ingest_handler_records_payload() {
         let app_state = Arc::new(AppState::default());
-        let http_state = HttpState {
-            app_state: Arc::clone(&app_state),
-        };
+        let http_state = test_http_state(&app_state);
 
-        let request = RayRequest {
-            uuid: "demo".into(),
-            payloads: vec![
-                serde_json::from_value(json!({
-                    "type": "log",
-                    "content": { "values": ["hi"], "meta": [] }
-                }))
-                .unwrap(),
-            ],
-            meta: Default::default(),
-        };
+        let request = json!({
+            "uuid": "demo",
+            "payloads": [{
+                "type": "log",
+                "content": { "values": ["hi"], "meta": [] }
+            }],
+            "meta": {}
+        });
 
-        let (status, Json(body)) = ingest(State(http_state), Json(request)).await;
+        let (status, Json(body)) = record(
+            &http_state,
+            None,
+            &HeaderMap::new(),
+            &Bytes::from(request.to_string()),
+        )
+        .await;
         assert_eq!(status, StatusCode::ACCEPTED);
         assert_eq!(
             body.get("recorded").and_then(|value| value.as_bool()),
@@ -217,5 +927,452 @@ mod tests {
                 .is_some()
         );
         assert_eq!(app_state.timeline_len().await, 1);
+
+        let (status, _) = record(
+            &http_state,
+            None,
+            &HeaderMap::new(),
+            &Bytes::from_static(b"{not json"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = record(
+            &http_state,
+            None,
+            &HeaderMap::new(),
+            &Bytes::from_static(b"{\"payloads\": 1}"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(app_state.timeline_len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn strict_protocol_rejects_and_records_diagnostics() {
+        let app_state = Arc::new(AppState::default());
+        let http_state = HttpState {
+            strict_protocol: true,
+            ..test_http_state(&app_state)
+        };
+
+        let missing_content = json!({
+            "uuid": "strict",
+            "payloads": [{ "type": "log" }],
+        });
+        let (status, Json(body)) = record(
+            &http_state,
+            None,
+            &HeaderMap::new(),
+            &Bytes::from(missing_content.to_string()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            body["errors"][0].as_str(),
+            Some("payloads[0].content: missing required field")
+        );
+        assert_eq!(app_state.timeline_len().await, 0);
+
+        let unknown_key = json!({
+            "uuid": "lenient",
+            "payloads": [{ "type": "log", "content": { "values": ["hi"] }, "extra": 1 }],
+        });
+        let (status, _) = record(
+            &http_state,
+            None,
+            &HeaderMap::new(),
+            &Bytes::from(unknown_key.to_string()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(app_state.timeline_len().await, 1);
+
+        let diagnostics = app_state.diagnostics_snapshot().await;
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics[0].rejected);
+        assert!(!diagnostics[1].rejected);
+        assert_eq!(diagnostics[1].request_uuid.as_deref(), Some("lenient"));
+    }
+
+    #[test]
+    fn captured_headers_redact_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert("user-agent", "GuzzleHttp/7".parse().unwrap());
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        headers.insert("x-ray-client", "laravel".parse().unwrap());
+
+        assert_eq!(
+            captured_headers(&headers),
+            vec![
+                ("user-agent".to_string(), "GuzzleHttp/7".to_string()),
+                ("authorization".to_string(), "[redacted]".to_string()),
+                ("x-ray-client".to_string(), "laravel".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn forwarded_headers_produce_public_url() {
+        let proxy = Some(SocketAddr::from(([127, 0, 0, 1], 50_000)));
+        let trusted: Vec<IpNet> = vec!["127.0.0.1/32".parse().unwrap()];
+        let mut headers = HeaderMap::new();
+        assert_eq!(forwarded_public_url(proxy, &headers, &trusted), None);
+
+        headers.insert("x-forwarded-host", "abc.ngrok.app".parse().unwrap());
+        headers.insert("x-forwarded-proto", "https".parse().unwrap());
+        assert_eq!(
+            forwarded_public_url(proxy, &headers, &trusted).as_deref(),
+            Some("https://abc.ngrok.app")
+        );
+
+        // Anyone else sending the same headers can't change the URL.
+        let stranger = Some(SocketAddr::from(([203, 0, 113, 9], 50_000)));
+        assert_eq!(forwarded_public_url(stranger, &headers, &trusted), None);
+        assert_eq!(forwarded_public_url(None, &headers, &trusted), None);
+        assert_eq!(forwarded_public_url(proxy, &headers, &[]), None);
+        headers.insert(HOST, "localhost:23517".parse().unwrap());
+        assert_eq!(
+            base_url(stranger, &headers, &trusted).as_deref(),
+            Some("http://localhost:23517")
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "forwarded",
+            "for=10.0.0.1;host=tunnel.example.com;proto=https"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            forwarded_public_url(proxy, &headers, &trusted).as_deref(),
+            Some("https://tunnel.example.com")
+        );
+    }
+
+    #[test]
+    fn dual_stack_adds_counterpart_addresses() {
+        let addrs: Vec<SocketAddr> = vec![
+            "0.0.0.0:23517".parse().unwrap(),
+            "[::1]:9000".parse().unwrap(),
+            "192.168.1.10:23517".parse().unwrap(),
+        ];
+
+        let expanded = dual_stack_addrs(&addrs);
+        let expected: Vec<SocketAddr> = vec![
+            "0.0.0.0:23517".parse().unwrap(),
+            "[::]:23517".parse().unwrap(),
+            "[::1]:9000".parse().unwrap(),
+            "127.0.0.1:9000".parse().unwrap(),
+            "192.168.1.10:23517".parse().unwrap(),
+        ];
+        assert_eq!(expanded, expected);
+    }
+
+    #[tokio::test]
+    async fn spawn_listens_on_every_bind_address() {
+        let app_state = Arc::new(AppState::default());
+        let config = ServerConfig {
+            bind_addrs: vec![
+                SocketAddr::from(([127, 0, 0, 1], 0)),
+                SocketAddr::from(([127, 0, 0, 1], 0)),
+            ],
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            dual_stack: false,
+            capture_fixtures: None,
+            strict_protocol: false,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            upstream: None,
+            trusted_proxies: Vec::new(),
+        };
+
+        let handle = spawn(app_state, config).await.expect("server should bind");
+        assert_eq!(handle.addrs().len(), 2);
+        assert_ne!(handle.addrs()[0], handle.addrs()[1]);
+
+        handle.shutdown().await.expect("shutdown should succeed");
+    }
+
+    #[tokio::test]
+    async fn rebind_moves_listeners_and_reuses_their_own_port() {
+        let config = ServerConfig {
+            bind_addrs: vec![SocketAddr::from(([127, 0, 0, 1], 0))],
+            ..ServerConfig::default()
+        };
+        let handle = spawn(Arc::new(AppState::default()), config)
+            .await
+            .expect("server should bind");
+        let first = handle.addrs()[0];
+
+        let handle = handle
+            .rebind(vec![SocketAddr::from(([127, 0, 0, 1], 0))])
+            .await
+            .unwrap_or_else(|_| panic!("rebind to a free port should succeed"));
+        let second = handle.addrs()[0];
+        assert_ne!(second, first);
+
+        // The port is held by the listener being replaced.
+        let handle = handle
+            .rebind(vec![second])
+            .await
+            .unwrap_or_else(|_| panic!("rebind to the same port should succeed"));
+        assert_eq!(handle.addrs(), [second]);
+        assert_eq!(handle.bind_addrs(), [second]);
+
+        handle.shutdown().await.expect("shutdown should succeed");
+    }
+
+    #[tokio::test]
+    async fn oversized_body_is_rejected_with_placeholder_event() {
+        use axum::body::to_bytes;
+        use tower::ServiceExt;
+
+        let app_state = Arc::new(AppState::default());
+        let router = router(HttpState {
+            max_body_size: 64,
+            ..test_http_state(&app_state)
+        });
+
+        let body = json!({
+            "uuid": "big",
+            "payloads": [{ "type": "log", "content": { "values": ["x".repeat(256)] } }],
+            "meta": {}
+        })
+        .to_string();
+
+        let response = router
+            .oneshot(
+                Request::post("/")
+                    .header("content-type", "application/json")
+                    .extension(ConnectInfo(SocketAddr::from(([10, 0, 0, 7], 4321))))
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["recorded"], json!(false));
+        assert_eq!(body["limit"], json!(64));
+
+        let events = app_state.timeline_snapshot().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].label.as_deref(), Some("payload too large"));
+        assert_eq!(events[0].color.as_deref(), Some("red"));
+        assert_eq!(
+            events[0].remote_addr,
+            Some(SocketAddr::from(([10, 0, 0, 7], 4321)))
+        );
+    }
+
+    #[tokio::test]
+    async fn ingest_response_links_to_the_event() {
+        use axum::body::to_bytes;
+        use tower::ServiceExt;
+
+        let app_state = Arc::new(AppState::default());
+        let router = router(test_http_state(&app_state));
+        let body = json!({
+            "uuid": "linked",
+            "payloads": [
+                { "type": "log", "content": { "values": ["hi"] } },
+                { "type": "label", "content": { "label": "checkout" } }
+            ],
+            "meta": {}
+        });
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::post("/")
+                    .header("host", "localhost:23517")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let recorded: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let id = recorded["event_id"].as_str().unwrap();
+        let url = format!("http://localhost:23517/api/events/{}", id);
+        assert_eq!(recorded["url"], json!(url));
+        assert_eq!(recorded["label"], json!("checkout"));
+        assert_eq!(recorded["screen"], json!(null));
+
+        let path = url.trim_start_matches("http://localhost:23517");
+        let response = router
+            .clone()
+            .oneshot(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let event: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(event["id"], json!(id));
+        assert_eq!(event["request"]["uuid"], json!("linked"));
+
+        let response = router
+            .oneshot(
+                Request::get(format!("/api/events/{}", Uuid::new_v4()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn annotations_attach_to_existing_events() {
+        use axum::body::to_bytes;
+        use tower::ServiceExt;
+
+        let app_state = Arc::new(AppState::default());
+        let router = router(test_http_state(&app_state));
+        let event = app_state
+            .record_request(
+                serde_json::from_value(json!({
+                    "uuid": "annotated",
+                    "payloads": [{ "type": "log", "content": { "values": ["hi"] } }],
+                    "meta": {}
+                }))
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        let annotate = |id: String, body: serde_json::Value| {
+            Request::post(format!("/api/events/{}/annotations", id))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(annotate(
+                event.id.to_string(),
+                json!({
+                    "text": "CheckoutTest::test_pays failed",
+                    "url": "https://ci.example/runs/42",
+                    "source": "phpunit"
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["annotation"]["source"], json!("phpunit"));
+
+        let annotations = app_state.event(event.id).await.unwrap().annotations;
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].text, "CheckoutTest::test_pays failed");
+        assert_eq!(
+            annotations[0].url.as_deref(),
+            Some("https://ci.example/runs/42")
+        );
+
+        for (id, body, status) in [
+            (
+                Uuid::new_v4().to_string(),
+                json!({ "text": "lost" }),
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                event.id.to_string(),
+                json!({ "text": "  " }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                event.id.to_string(),
+                json!({ "text": "x", "url": "file:///etc/passwd" }),
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                event.id.to_string(),
+                json!({ "url": "https://ci.example" }),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+        ] {
+            let response = router.clone().oneshot(annotate(id, body)).await.unwrap();
+            assert_eq!(response.status(), status);
+        }
+        assert_eq!(
+            app_state.event(event.id).await.unwrap().annotations.len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn proxy_mode_forwards_payloads_and_lock_polls() {
+        use axum::body::to_bytes;
+        use tower::ServiceExt;
+
+        let (forwarded_tx, mut forwarded_rx) = tokio::sync::mpsc::unbounded_channel();
+        let desktop = Router::new()
+            .route(
+                "/",
+                post(move |headers: HeaderMap, body: Bytes| async move {
+                    let agent = headers.get("user-agent").cloned();
+                    forwarded_tx.send((agent, body)).unwrap();
+                    (StatusCode::OK, "desktop")
+                }),
+            )
+            .route(
+                "/locks/:name",
+                get(|RawQuery(query): RawQuery| async move {
+                    let active = query.as_deref() == Some("hostname=mac&project_name=shop");
+                    Json(json!({ "active": active, "stop_execution": true }))
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let desktop_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, desktop).await });
+
+        let app_state = Arc::new(AppState::default());
+        let router = router(HttpState {
+            upstream: Some(Arc::new(
+                Upstream::new(&format!("http://{}", desktop_addr)).unwrap(),
+            )),
+            ..test_http_state(&app_state)
+        });
+
+        let body = json!({
+            "uuid": "tee",
+            "payloads": [{ "type": "log", "content": { "values": ["hi"] } }],
+            "meta": {}
+        })
+        .to_string();
+        let response = router
+            .clone()
+            .oneshot(
+                Request::post("/")
+                    .header("content-type", "application/json")
+                    .header("user-agent", "GuzzleHttp/7")
+                    .body(Body::from(body.clone()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], b"desktop");
+        let (agent, forwarded) = forwarded_rx.recv().await.unwrap();
+        assert_eq!(agent.unwrap(), "GuzzleHttp/7");
+        assert_eq!(forwarded, body);
+        assert_eq!(app_state.timeline_len().await, 1);
+
+        let response = router
+            .oneshot(
+                Request::get("/locks/abc?hostname=mac&project_name=shop")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let lock: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(lock, json!({ "active": true, "stop_execution": true }));
     }
 }
@@ -0,0 +1,106 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{
+        HeaderMap, HeaderName,
+        header::{CONNECTION, CONTENT_LENGTH, HOST, TRANSFER_ENCODING, UPGRADE},
+    },
+    response::Response,
+};
+use tracing::{info, warn};
+
+/// How long a forwarded request may take before the client gets Raygun's own
+/// response instead.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Headers that describe the connection to Raygun rather than the request.
+const HOP_HEADERS: &[HeaderName] = &[HOST, CONTENT_LENGTH, CONNECTION, TRANSFER_ENCODING, UPGRADE];
+
+/// The Ray desktop app (or another Ray server) that payloads are passed on to
+/// in proxy mode (`--upstream`).
+#[derive(Debug)]
+pub struct Upstream {
+    client: reqwest::Client,
+    url: String,
+    /// Whether the last forwarded request got an answer, so an app that is
+    /// closed is reported once rather than for every payload.
+    reachable: AtomicBool,
+}
+
+impl Upstream {
+    pub fn new(url: &str) -> reqwest::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder().timeout(TIMEOUT).build()?,
+            url: url.trim_end_matches('/').to_string(),
+            reachable: AtomicBool::new(true),
+        })
+    }
+
+    /// Passes a payload request on unchanged. `None` when the upstream did not
+    /// answer.
+    pub async fn forward_payload(&self, headers: &HeaderMap, body: Bytes) -> Option<Response> {
+        let mut forwarded = headers.clone();
+        for name in HOP_HEADERS {
+            forwarded.remove(name);
+        }
+        let request = self
+            .client
+            .post(format!("{}/", self.url))
+            .headers(forwarded)
+            .body(body);
+        self.answer(request).await
+    }
+
+    /// Asks the upstream whether a `pause` lock is still held, so pausing and
+    /// continuing in the desktop app keep working for proxied clients.
+    pub async fn lock(&self, name: &str, query: Option<&str>) -> Option<Response> {
+        let mut url = format!("{}/locks/{}", self.url, name);
+        if let Some(query) = query {
+            url.push('?');
+            url.push_str(query);
+        }
+        self.answer(self.client.get(url)).await
+    }
+
+    async fn answer(&self, request: reqwest::RequestBuilder) -> Option<Response> {
+        let result = match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                let content_type = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .cloned();
+                response
+                    .bytes()
+                    .await
+                    .map(|body| (status, content_type, body))
+            }
+            Err(error) => Err(error),
+        };
+        match result {
+            Ok((status, content_type, body)) => {
+                if !self.reachable.swap(true, Ordering::Relaxed) {
+                    info!(upstream = %self.url, "upstream Ray app is reachable again");
+                }
+                let mut response = Response::new(Body::from(body));
+                *response.status_mut() = status;
+                if let Some(content_type) = content_type {
+                    response
+                        .headers_mut()
+                        .insert(reqwest::header::CONTENT_TYPE, content_type);
+                }
+                Some(response)
+            }
+            Err(error) => {
+                if self.reachable.swap(false, Ordering::Relaxed) {
+                    warn!(upstream = %self.url, %error, "failed to forward to the upstream Ray app");
+                }
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! The event bus: every change to the state, in the order it was made, for
+//! the consumers that follow it — the TUI, daemon clients and integrations.
+
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use super::{TimelineChange, TimelineEvent};
+
+/// Messages buffered per subscriber before it lags and has to resync.
+const BUFFER: usize = 256;
+
+/// One change published on the bus.
+#[derive(Debug, Clone)]
+pub enum BusMessage {
+    /// An event was added to the timeline.
+    Recorded {
+        event: TimelineEvent,
+    },
+    /// The color, label or screen of an existing event changed.
+    Updated {
+        event: TimelineEvent,
+    },
+    /// An event left the timeline: deleted, hidden or evicted.
+    Removed {
+        id: Uuid,
+    },
+    Cleared,
+    /// A `pause` lock was created (`held`) or released.
+    LockChanged {
+        name: String,
+        held: bool,
+    },
+}
+
+impl BusMessage {
+    /// The timeline part of this message, as daemon clients receive it; `None` for changes outside the timeline.
+    pub fn timeline_change(&self) -> Option<TimelineChange> {
+        Some(match self {
+            Self::Recorded { event } => TimelineChange::Added {
+                event: event.clone(),
+            },
+            Self::Updated { event } => TimelineChange::Updated {
+                event: event.clone(),
+            },
+            Self::Removed { id } => TimelineChange::Removed { id: *id },
+            Self::Cleared => TimelineChange::Cleared,
+            Self::LockChanged { .. } => return None,
+        })
+    }
+}
+
+impl From<TimelineChange> for BusMessage {
+    fn from(change: TimelineChange) -> Self {
+        match change {
+            TimelineChange::Added { event } => Self::Recorded { event },
+            TimelineChange::Updated { event } => Self::Updated { event },
+            TimelineChange::Removed { id } => Self::Removed { id },
+            TimelineChange::Cleared => Self::Cleared,
+        }
+    }
+}
+
+/// Fans state changes out to subscribers, which may lag and have to resync.
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusMessage>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(BUFFER).0,
+        }
+    }
+
+    /// Every message published after this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<BusMessage> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, message: BusMessage) {
+        // Nobody may be listening, e.g. integrations are off and no UI runs.
+        let _ = self.sender.send(message);
+    }
+}
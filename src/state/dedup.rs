@@ -0,0 +1,62 @@
+//! Recognizes retried deliveries: a client that timed out waiting for the
+//! response posts the same request again. Ray clients reuse a request's
+//! `uuid` for follow-ups such as `->color('red')`, so only a request equal to
+//! an earlier one with that `uuid` counts as a retry.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{Duration, SystemTime},
+};
+
+use uuid::Uuid;
+
+use crate::protocol::RayRequest;
+
+/// How long after a delivery the same request is treated as a retry.
+pub const WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Default)]
+pub struct RecentDeliveries {
+    /// Event recorded for each delivery in the window, if any.
+    seen: HashMap<u64, Option<Uuid>>,
+    order: VecDeque<(SystemTime, u64)>,
+    /// Retries dropped since startup.
+    duplicates: u64,
+}
+
+impl RecentDeliveries {
+    /// Identifies `request` by its `uuid` and content.
+    pub fn key(request: &RayRequest) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        request.uuid.hash(&mut hasher);
+        serde_json::to_string(request)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns the event recorded for the delivery `key` retries, counting
+    /// the retry, or `None` when it is not a retry.
+    pub fn duplicate_of(&mut self, key: u64, at: SystemTime) -> Option<Option<Uuid>> {
+        while let Some(&(delivered, oldest)) = self.order.front() {
+            if at.duration_since(delivered).unwrap_or_default() <= WINDOW {
+                break;
+            }
+            self.order.pop_front();
+            self.seen.remove(&oldest);
+        }
+        let event = *self.seen.get(&key)?;
+        self.duplicates += 1;
+        Some(event)
+    }
+
+    pub fn remember(&mut self, key: u64, at: SystemTime, event: Option<Uuid>) {
+        self.seen.insert(key, event);
+        self.order.push_back((at, key));
+    }
+
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
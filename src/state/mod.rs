@@ -1,23 +1,57 @@
+pub mod bus;
+mod dedup;
+pub mod routes;
+pub mod rules;
+pub mod store;
+pub mod summary;
+
 use std::{
     collections::{BTreeMap, HashMap, VecDeque},
+    net::SocketAddr,
     path::PathBuf,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     time::SystemTime,
 };
 
+use serde::{Deserialize, Serialize};
 use tokio::{
     fs::OpenOptions,
     io::AsyncWriteExt,
-    sync::{RwLock, mpsc},
+    sync::{RwLock, broadcast, mpsc, oneshot},
 };
 use tracing::warn;
 use uuid::Uuid;
 
-use crate::protocol::{PayloadKind, RayRequest};
+use self::{
+    bus::{BusMessage, EventBus},
+    dedup::RecentDeliveries,
+    routes::Routes,
+    rules::Rules,
+    store::{MemoryStore, TimelineStore},
+};
+use crate::protocol::{LogLevel, PayloadKind, ProtocolIssue, RayRequest};
 
 const DEFAULT_RETENTION: usize = 1_024;
+const DIAGNOSTIC_RETENTION: usize = 200;
+/// Deleted events kept for restoring; older deletions are forgotten.
+const TRASH_RETENTION: usize = 100;
+/// Annotations kept per event; the oldest make room for new ones.
+const ANNOTATION_RETENTION: usize = 20;
 
+/// Protocol issues found in one incoming request (strict protocol mode).
 #[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub received_at: SystemTime,
+    pub request_uuid: Option<String>,
+    pub issues: Vec<ProtocolIssue>,
+    /// Whether the request was rejected instead of recorded.
+    pub rejected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimelineEvent {
     pub id: Uuid,
     pub received_at: SystemTime,
@@ -25,6 +59,36 @@ pub struct TimelineEvent {
     pub screen: Option<String>,
     pub color: Option<String>,
     pub label: Option<String>,
+    /// Address of the client that sent the request: the peer of the HTTP
+    /// connection, or the client it forwarded for when that is a trusted proxy.
+    #[serde(default)]
+    pub remote_addr: Option<SocketAddr>,
+    /// The trusted proxy the request came through, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_addr: Option<SocketAddr>,
+    /// Headers of the HTTP request, in the order they were sent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// Notes external tools attached after the event arrived, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// Bumped every time the event changes after it was recorded, so views
+    /// can tell a changed event from one they have already laid out.
+    #[serde(skip)]
+    pub revision: u64,
+}
+
+/// A note attached to an event through `POST /api/events/:id/annotations`,
+/// e.g. the name of the failed test that produced a dump.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Who attached it, e.g. `phpunit` or `ci`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub added_at: SystemTime,
 }
 
 impl TimelineEvent {
@@ -36,10 +100,96 @@ impl TimelineEvent {
             screen,
             color: None,
             label: None,
+            remote_addr: None,
+            proxy_addr: None,
+            headers: Vec::new(),
+            annotations: Vec::new(),
+            revision: 0,
+        }
+    }
+
+    /// When the client sent the request, if its meta carries a timestamp
+    /// (`sent_at`, `timestamp` or `microtime`) in seconds, milliseconds or
+    /// microseconds since the Unix epoch.
+    pub fn client_sent_at(&self) -> Option<SystemTime> {
+        self.request.sent_at()
+    }
+
+    /// Client→Raygun latency in microseconds. Negative when the client's clock
+    /// runs ahead of ours.
+    pub fn latency_micros(&self) -> Option<i64> {
+        let sent_at = self.client_sent_at()?;
+        Some(match self.received_at.duration_since(sent_at) {
+            Ok(latency) => i64::try_from(latency.as_micros()).unwrap_or(i64::MAX),
+            Err(ahead) => -i64::try_from(ahead.duration().as_micros()).unwrap_or(i64::MAX),
+        })
+    }
+
+    /// Whether the event reports an error: an exception, a framework log entry
+    /// at `error` or above, or a Laravel job event for a failed job.
+    pub fn is_error(&self) -> bool {
+        self.request
+            .payloads
+            .iter()
+            .any(|payload| match &payload.kind {
+                PayloadKind::Exception => true,
+                PayloadKind::ApplicationLog => payload
+                    .log_level()
+                    .is_some_and(|level| level >= LogLevel::Error),
+                PayloadKind::JobEvent => payload
+                    .content_string("event_name")
+                    .is_some_and(|name| name.to_ascii_lowercase().contains("failed")),
+                _ => false,
+            })
+    }
+
+    /// Value of the first captured request header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Where and when the HTTP server received a request.
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    pub remote_addr: Option<SocketAddr>,
+    /// The trusted proxy `remote_addr` was taken from the headers of.
+    pub proxy_addr: Option<SocketAddr>,
+    pub received_at: SystemTime,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Provenance {
+    pub fn now(remote_addr: Option<SocketAddr>) -> Self {
+        Self {
+            remote_addr,
+            proxy_addr: None,
+            received_at: SystemTime::now(),
+            headers: Vec::new(),
         }
     }
 }
 
+/// One change to the timeline, as daemon clients receive it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum TimelineChange {
+    Added {
+        event: TimelineEvent,
+    },
+    /// The color or label of an existing event changed.
+    Updated {
+        event: TimelineEvent,
+    },
+    Removed {
+        id: Uuid,
+    },
+    Cleared,
+}
+
 #[derive(Debug, Clone)]
 pub struct LockRecord {
     pub hostname: Option<String>,
@@ -58,8 +208,14 @@ impl LockRecord {
 #[derive(Debug)]
 pub struct AppState {
     retention: usize,
+    /// Whether eviction skips errors while other events are left to evict.
+    pin_errors: AtomicBool,
+    /// Holds the timeline. Changed only while `inner` is locked, so changes
+    /// reach the bus in the order they were made.
+    store: Arc<dyn TimelineStore>,
     inner: RwLock<StateInner>,
     debug_logger: Option<Arc<PayloadLogger>>,
+    bus: EventBus,
 }
 
 impl Default for AppState {
@@ -81,35 +237,165 @@ impl AppState {
     pub fn with_debug_logger(retention: usize, debug_logger: Option<Arc<PayloadLogger>>) -> Self {
         Self {
             retention,
+            pin_errors: AtomicBool::new(true),
+            store: Arc::new(MemoryStore::new()),
             inner: RwLock::new(StateInner::default()),
             debug_logger,
+            bus: EventBus::new(),
         }
     }
 
+    /// Events kept in the timeline before the oldest are evicted.
+    pub fn retention(&self) -> usize {
+        self.retention
+    }
+
+    /// Lets retention evict errors like any other event when `pin` is false.
+    pub fn set_pin_errors(&self, pin: bool) {
+        self.pin_errors.store(pin, Ordering::Relaxed);
+    }
+
+    /// Keeps the timeline in `store` instead of memory alone, e.g. one with
+    /// a history on disk. Events already recorded stay behind.
+    pub fn with_store(mut self, store: Arc<dyn TimelineStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub fn store(&self) -> &Arc<dyn TimelineStore> {
+        &self.store
+    }
+
+    pub fn bus(&self) -> &EventBus {
+        &self.bus
+    }
+
+    /// Returns the timeline together with a stream of every later change, so
+    /// replaying the stream over the snapshot never misses or repeats one.
+    pub async fn subscribe(&self) -> (Vec<TimelineEvent>, broadcast::Receiver<BusMessage>) {
+        let _inner = self.inner.read().await;
+        let changes = self.bus.subscribe();
+        (self.store.snapshot(), changes)
+    }
+
+    /// Publishes the changes collected while `inner` was locked on the bus.
+    fn publish(&self, inner: &mut StateInner) {
+        for message in inner.changes.drain(..) {
+            self.bus.publish(message);
+        }
+    }
+
+    /// Applies a change made elsewhere, e.g. by the daemon this state mirrors,
+    /// and publishes it like a local one.
+    pub async fn apply_change(&self, change: TimelineChange) {
+        let mut inner = self.inner.write().await;
+        // This client restored the event itself before the publisher did.
+        if let TimelineChange::Added { event } = &change
+            && self.store.get(event.id).is_some()
+        {
+            return;
+        }
+        inner.changes.push(change.clone().into());
+        match change {
+            // The publisher sends what it evicted as `Removed`.
+            TimelineChange::Added { event } => self.store.push(event),
+            TimelineChange::Updated { event } => {
+                self.store.update(&mut |existing| {
+                    if existing.id != event.id {
+                        return false;
+                    }
+                    *existing = TimelineEvent {
+                        revision: existing.revision,
+                        ..event.clone()
+                    };
+                    true
+                });
+            }
+            TimelineChange::Removed { id } => {
+                self.store.remove(&mut |event| event.id == id);
+            }
+            TimelineChange::Cleared => {
+                self.store.clear();
+                inner.current_screen = None;
+            }
+        }
+        self.publish(&mut inner);
+    }
+
+    /// Replaces the whole timeline, e.g. with a daemon's snapshot.
+    pub async fn replace_timeline(&self, events: Vec<TimelineEvent>) {
+        let _inner = self.inner.write().await;
+        let excess = events.len().saturating_sub(self.retention);
+        self.store
+            .replace(events.into_iter().skip(excess).collect());
+    }
+
     pub async fn record_request(&self, request: RayRequest) -> Option<TimelineEvent> {
+        self.record_request_from(request, Provenance::now(None))
+            .await
+    }
+
+    /// Records a request with where and when it arrived. A request delivered
+    /// over HTTP again shortly after, as a client retrying does, is dropped
+    /// and the event recorded for the first delivery returned.
+    pub async fn record_request_from(
+        &self,
+        request: RayRequest,
+        provenance: Provenance,
+    ) -> Option<TimelineEvent> {
+        let delivery = provenance
+            .remote_addr
+            .is_some()
+            .then(|| RecentDeliveries::key(&request));
         let screen_hint = extract_screen_from_meta(&request.meta);
+        // A screen named by the request itself is never routed elsewhere.
+        let screen_requested = screen_hint.is_some()
+            || request
+                .payloads
+                .iter()
+                .any(|payload| matches!(payload.kind, PayloadKind::NewScreen));
         let mut event = TimelineEvent::new(request, screen_hint);
+        event.received_at = provenance.received_at;
+        event.remote_addr = provenance.remote_addr;
+        event.proxy_addr = provenance.proxy_addr;
+        event.headers = provenance.headers;
 
         let mut inner = self.inner.write().await;
-        let outcome = inner.apply_payloads(&mut event);
+        if let Some(key) = delivery
+            && let Some(first) = inner.deliveries.duplicate_of(key, event.received_at)
+        {
+            return first.and_then(|id| self.store.get(id));
+        }
+        let outcome = inner.apply_payloads(self.store.as_ref(), &mut event);
+        if let Some(key) = delivery {
+            let recorded = matches!(outcome, ApplyOutcome::Record).then_some(event.id);
+            inner.deliveries.remember(key, event.received_at, recorded);
+        }
 
         if matches!(outcome, ApplyOutcome::Record) {
-            inner.merge_previous_log_into_context(&mut event);
+            inner.merge_previous_log_into_context(self.store.as_ref(), &mut event);
         }
 
         if matches!(outcome, ApplyOutcome::Skip) {
+            self.publish(&mut inner);
             return None;
         }
+        inner.rules.apply(&mut event);
+        if !screen_requested && let Some(screen) = inner.routes.screen_for(&event) {
+            event.screen = Some(screen.to_string());
+        }
 
         if event.screen.is_none() {
             event.screen = inner.current_screen.clone();
         }
 
         let stored_event = event.clone();
-        inner.timeline.push_back(stored_event.clone());
-        if inner.timeline.len() > self.retention {
-            inner.timeline.pop_front();
-        }
+        self.store.push(stored_event.clone());
+        inner.changes.push(BusMessage::Recorded {
+            event: stored_event.clone(),
+        });
+        self.evict(&mut inner);
+        self.publish(&mut inner);
 
         let logger = self.debug_logger.clone();
         let log_request = stored_event.request.clone();
@@ -124,13 +410,31 @@ impl AppState {
     }
 
     pub async fn timeline_snapshot(&self) -> Vec<TimelineEvent> {
+        let _inner = self.inner.read().await;
+        self.store.snapshot()
+    }
+
+    /// Retried deliveries dropped since startup.
+    pub async fn duplicates(&self) -> u64 {
         let inner = self.inner.read().await;
-        inner.timeline.iter().cloned().collect()
+        inner.deliveries.duplicates()
+    }
+
+    pub async fn event(&self, id: Uuid) -> Option<TimelineEvent> {
+        let _inner = self.inner.read().await;
+        self.store.get(id)
+    }
+
+    /// The timeline without waiting for the lock, for code that cannot await
+    /// (such as a drop during a panic); `None` while a writer holds it.
+    pub fn try_timeline_snapshot(&self) -> Option<Vec<TimelineEvent>> {
+        let _inner = self.inner.try_read().ok()?;
+        Some(self.store.snapshot())
     }
 
     pub async fn timeline_len(&self) -> usize {
-        let inner = self.inner.read().await;
-        inner.timeline.len()
+        let _inner = self.inner.read().await;
+        self.store.len()
     }
 
     pub async fn lock_exists(
@@ -144,11 +448,9 @@ impl AppState {
             .locks
             .get(name)
             .map(|record| {
-                hostname.map_or(true, |expected| {
-                    record.hostname.as_deref() == Some(expected)
-                }) && project.map_or(true, |expected| {
-                    record.project_name.as_deref() == Some(expected)
-                })
+                hostname.is_none_or(|expected| record.hostname.as_deref() == Some(expected))
+                    && project
+                        .is_none_or(|expected| record.project_name.as_deref() == Some(expected))
             })
             .unwrap_or(false)
     }
@@ -156,26 +458,244 @@ impl AppState {
     #[allow(dead_code)]
     pub async fn clear_lock(&self, name: &str) {
         let mut inner = self.inner.write().await;
-        inner.locks.remove(name);
+        if inner.locks.remove(name).is_some() {
+            inner.changes.push(BusMessage::LockChanged {
+                name: name.to_owned(),
+                held: false,
+            });
+            self.publish(&mut inner);
+        }
+    }
+
+    /// Remembers the URL the latest request through a tunnel was sent to,
+    /// replacing an older one when the tunnel was restarted.
+    pub async fn note_public_url(&self, url: String) {
+        let mut inner = self.inner.write().await;
+        if inner.public_url.as_deref() != Some(url.as_str()) {
+            inner.public_url = Some(url);
+        }
+    }
+
+    pub async fn public_url(&self) -> Option<String> {
+        let inner = self.inner.read().await;
+        inner.public_url.clone()
+    }
+
+    pub async fn record_diagnostic(&self, diagnostic: Diagnostic) {
+        let mut inner = self.inner.write().await;
+        inner.diagnostics.push_back(diagnostic);
+        if inner.diagnostics.len() > DIAGNOSTIC_RETENTION {
+            inner.diagnostics.pop_front();
+        }
+    }
+
+    pub async fn diagnostics_snapshot(&self) -> Vec<Diagnostic> {
+        let inner = self.inner.read().await;
+        inner.diagnostics.iter().cloned().collect()
+    }
+
+    /// Moves one event from the timeline to the trash; returns whether it was
+    /// present.
+    pub async fn remove_event(&self, id: Uuid) -> bool {
+        let mut inner = self.inner.write().await;
+        let Some((index, event)) = self.store.remove(&mut |event| event.id == id).pop() else {
+            return false;
+        };
+        inner.discard(index, event);
+        inner.changes.push(BusMessage::Removed { id });
+        self.publish(&mut inner);
+        true
+    }
+
+    /// Deleted events that can still be restored, most recently deleted first.
+    pub async fn trash_snapshot(&self) -> Vec<TimelineEvent> {
+        let inner = self.inner.read().await;
+        inner
+            .trash
+            .iter()
+            .rev()
+            .map(|(_, event)| event.clone())
+            .collect()
+    }
+
+    /// Moves an event from the trash back to where it was in the timeline;
+    /// returns whether it was in the trash.
+    pub async fn restore_event(&self, id: Uuid) -> bool {
+        let mut inner = self.inner.write().await;
+        let Some(index) = inner.trash.iter().position(|(_, event)| event.id == id) else {
+            return false;
+        };
+        let Some((position, event)) = inner.trash.remove(index) else {
+            return false;
+        };
+        self.store.insert(position, event.clone());
+        inner.changes.push(BusMessage::Recorded { event });
+        self.evict(&mut inner);
+        self.publish(&mut inner);
+        true
+    }
+
+    /// Replaces the classification rules applied to events recorded from now
+    /// on.
+    pub async fn set_rules(&self, rules: Rules) {
+        self.inner.write().await.rules = rules;
+    }
+
+    /// Replaces the screen routes applied to events recorded from now on.
+    pub async fn set_routes(&self, routes: Routes) {
+        self.inner.write().await.routes = routes;
+    }
+
+    /// Replaces the label of one event, e.g. one typed in the UI; `None`
+    /// removes it. Returns whether the event was present.
+    pub async fn set_label(&self, id: Uuid, label: Option<String>) -> bool {
+        self.update_event(id, |event| event.label = label).await
+    }
+
+    /// Replaces the color of one event, e.g. one picked in the UI; `None`
+    /// removes it. Returns whether the event was present.
+    pub async fn set_color(&self, id: Uuid, color: Option<String>) -> bool {
+        self.update_event(id, |event| event.color = color).await
+    }
+
+    /// Moves one event to the screen `screen`, or off every screen with
+    /// `None`. Returns whether the event was present.
+    pub async fn set_screen(&self, id: Uuid, screen: Option<String>) -> bool {
+        let screen = screen.as_deref().map(sanitize_screen_name);
+        self.update_event(id, |event| event.screen = screen).await
+    }
+
+    /// Attaches `annotation` to one event. Returns whether the event was
+    /// present.
+    pub async fn annotate(&self, id: Uuid, annotation: Annotation) -> bool {
+        self.update_event(id, |event| {
+            event.annotations.push(annotation);
+            let excess = event.annotations.len().saturating_sub(ANNOTATION_RETENTION);
+            event.annotations.drain(..excess);
+        })
+        .await
+    }
+
+    /// Renames the screen `from` on every event in it, and for the events
+    /// still to come when it is the current screen. Returns the number of
+    /// events renamed.
+    pub async fn rename_screen(&self, from: &str, to: &str) -> usize {
+        let to = sanitize_screen_name(to);
+        let to = to.as_str();
+        let mut inner = self.inner.write().await;
+        let renamed = self.store.update(&mut |event| {
+            if event.screen.as_deref() != Some(from) {
+                return false;
+            }
+            event.screen = Some(to.to_string());
+            true
+        });
+        if inner.current_screen.as_deref() == Some(from) {
+            inner.current_screen = Some(to.to_string());
+        }
+        let count = renamed.len();
+        inner.changes.extend(
+            renamed
+                .into_iter()
+                .map(|event| BusMessage::Updated { event }),
+        );
+        self.publish(&mut inner);
+        count
+    }
+
+    /// Moves every event on the screen `name` to the trash; events still to
+    /// come land on no screen when it was the current one. Returns the number
+    /// moved.
+    pub async fn close_screen(&self, name: &str) -> usize {
+        let mut inner = self.inner.write().await;
+        let mut closed = Vec::new();
+        for (index, event) in self
+            .store
+            .remove(&mut |event| event.screen.as_deref() == Some(name))
+        {
+            closed.push(event.id);
+            inner.discard(index, event);
+        }
+        if inner.current_screen.as_deref() == Some(name) {
+            inner.current_screen = None;
+        }
+        let count = closed.len();
+        inner
+            .changes
+            .extend(closed.into_iter().map(|id| BusMessage::Removed { id }));
+        self.publish(&mut inner);
+        count
+    }
+
+    async fn update_event(&self, id: Uuid, update: impl FnOnce(&mut TimelineEvent)) -> bool {
+        let mut inner = self.inner.write().await;
+        let mut update = Some(update);
+        let updated = self.store.update(&mut |event| {
+            event.id == id && update.take().map(|update| update(event)).is_some()
+        });
+        let Some(event) = updated.into_iter().next() else {
+            return false;
+        };
+        inner.changes.push(BusMessage::Updated { event });
+        self.publish(&mut inner);
+        true
+    }
+
+    /// Drops the event retention evicts, if any, after one was added.
+    fn evict(&self, inner: &mut StateInner) {
+        let pin_errors = self.pin_errors.load(Ordering::Relaxed);
+        if let Some(dropped) = self.store.evict(self.retention, pin_errors) {
+            inner.changes.push(BusMessage::Removed { id: dropped.id });
+        }
+    }
+
+    /// Waits for writes still queued for the `--debug-dump` file.
+    pub async fn drain(&self) {
+        if let Some(logger) = &self.debug_logger {
+            logger.flush().await;
+        }
     }
 
     pub async fn clear_timeline(&self) {
         let mut inner = self.inner.write().await;
-        inner.timeline.clear();
+        self.store.clear();
         inner.current_screen = None;
+        inner.changes.push(BusMessage::Cleared);
+        self.publish(&mut inner);
     }
 }
 
 #[derive(Debug, Default)]
 struct StateInner {
-    timeline: VecDeque<TimelineEvent>,
     locks: HashMap<String, LockRecord>,
     current_screen: Option<String>,
+    public_url: Option<String>,
+    diagnostics: VecDeque<Diagnostic>,
+    /// Changes made under the current write lock, not yet published.
+    changes: Vec<BusMessage>,
+    /// Classification rules applied to every recorded event.
+    rules: Rules,
+    /// Screen routes applied to every recorded event after the rules.
+    routes: Routes,
+    /// Requests delivered over HTTP lately, to drop retried deliveries.
+    deliveries: RecentDeliveries,
+    /// Deleted events with their position in the timeline, oldest deletion
+    /// first.
+    trash: VecDeque<(usize, TimelineEvent)>,
 }
 
+/// Appends every recorded request to the `--debug-dump` file as one line of
+/// JSON, the format `--import` reads back.
 #[derive(Debug)]
 pub struct PayloadLogger {
-    sender: mpsc::UnboundedSender<Arc<RayRequest>>,
+    sender: mpsc::UnboundedSender<LogMessage>,
+}
+
+#[derive(Debug)]
+enum LogMessage {
+    Request(Arc<RayRequest>),
+    /// Answered once everything queued before it is on disk.
+    Flush(oneshot::Sender<()>),
 }
 
 impl PayloadLogger {
@@ -192,8 +712,25 @@ impl PayloadLogger {
                 .await
             {
                 Ok(mut file) => {
-                    while let Some(request) = rx.recv().await {
-                        let dump = format!("{:#?}\n", request);
+                    while let Some(message) = rx.recv().await {
+                        let request = match message {
+                            LogMessage::Request(request) => request,
+                            LogMessage::Flush(done) => {
+                                if let Err(err) = file.flush().await {
+                                    warn!(?err, "failed to flush payload dump");
+                                }
+                                let _ = done.send(());
+                                continue;
+                            }
+                        };
+                        let mut dump = match serde_json::to_string(&*request) {
+                            Ok(dump) => dump,
+                            Err(err) => {
+                                warn!(?err, "failed to serialize payload dump");
+                                continue;
+                            }
+                        };
+                        dump.push('\n');
                         if let Err(err) = file.write_all(dump.as_bytes()).await {
                             warn!(?err, "failed to write payload dump");
                             break;
@@ -213,12 +750,34 @@ impl PayloadLogger {
     }
 
     pub fn log(&self, request: Arc<RayRequest>) {
-        let _ = self.sender.send(request);
+        let _ = self.sender.send(LogMessage::Request(request));
+    }
+
+    /// Waits until every request logged so far has been written.
+    pub async fn flush(&self) {
+        let (done, written) = oneshot::channel();
+        if self.sender.send(LogMessage::Flush(done)).is_ok() {
+            // The writer is gone if it failed; there is nothing left to wait for.
+            let _ = written.await;
+        }
     }
 }
 
 impl StateInner {
-    fn apply_payloads(&mut self, event: &mut TimelineEvent) -> ApplyOutcome {
+    /// Puts an event removed from `index` of the timeline in the trash,
+    /// dropping the oldest deletion once the trash is full.
+    fn discard(&mut self, index: usize, event: TimelineEvent) {
+        self.trash.push_back((index, event));
+        if self.trash.len() > TRASH_RETENTION {
+            self.trash.pop_front();
+        }
+    }
+
+    fn apply_payloads(
+        &mut self,
+        timeline: &dyn TimelineStore,
+        event: &mut TimelineEvent,
+    ) -> ApplyOutcome {
         let mut displayable = false;
         let mut outcome = ApplyOutcome::Record;
         let mut pending_color: Option<String> = None;
@@ -242,23 +801,36 @@ impl StateInner {
                             .map(ToOwned::to_owned);
                         self.locks
                             .insert(name.to_owned(), LockRecord::new(hostname, project));
+                        self.changes.push(BusMessage::LockChanged {
+                            name: name.to_owned(),
+                            held: true,
+                        });
                     }
                 }
                 PayloadKind::ClearAll => {
-                    self.timeline.clear();
-                    self.locks.clear();
+                    timeline.clear();
                     self.current_screen = None;
+                    self.changes.push(BusMessage::Cleared);
+                    for (name, _) in self.locks.drain() {
+                        self.changes
+                            .push(BusMessage::LockChanged { name, held: false });
+                    }
                     outcome = ApplyOutcome::Skip;
                 }
                 PayloadKind::Remove => {
-                    if let Some(name) = payload.content_string("name") {
-                        self.locks.remove(name);
+                    if let Some(name) = payload.content_string("name")
+                        && self.locks.remove(name).is_some()
+                    {
+                        self.changes.push(BusMessage::LockChanged {
+                            name: name.to_owned(),
+                            held: false,
+                        });
                     }
-                    self.timeline.pop_back();
+                    self.pop_last_event(timeline);
                     outcome = ApplyOutcome::Skip;
                 }
                 PayloadKind::Hide => {
-                    self.timeline.pop_back();
+                    self.pop_last_event(timeline);
                     outcome = ApplyOutcome::Skip;
                 }
                 PayloadKind::NewScreen => {
@@ -276,6 +848,10 @@ impl StateInner {
                         pending_color = Some(color_value);
                     }
                 }
+                // An explicit color, before or after, wins over the level's.
+                PayloadKind::ApplicationLog if event.color.is_none() => {
+                    event.color = payload.log_level().map(|level| level.color().to_string());
+                }
                 PayloadKind::Label => {
                     if let Some(value) = payload.content_string("label") {
                         let label_value = value.to_owned();
@@ -308,22 +884,38 @@ impl StateInner {
                     | PayloadKind::HideApp
                     | PayloadKind::Ban
                     | PayloadKind::Charles
+                    | PayloadKind::ExecutedQuery
+                    | PayloadKind::ApplicationLog
+                    | PayloadKind::JobEvent
+                    | PayloadKind::Event
                     | PayloadKind::NewScreen
+                    | PayloadKind::Unknown(_)
             ) {
                 displayable = true;
             }
         }
 
         if !displayable {
-            if let Some(color_value) = pending_color {
-                if let Some(last) = self.timeline.back_mut() {
-                    last.color = Some(color_value);
-                }
-            }
-            if let Some(label_value) = pending_label {
-                if let Some(last) = self.timeline.back_mut() {
-                    last.label = Some(label_value);
-                }
+            if (pending_color.is_some() || pending_label.is_some())
+                && let Some(last) = timeline.last()
+            {
+                let updated = timeline.update(&mut |event| {
+                    if event.id != last.id {
+                        return false;
+                    }
+                    if let Some(color_value) = pending_color.take() {
+                        event.color = Some(color_value);
+                    }
+                    if let Some(label_value) = pending_label.take() {
+                        event.label = Some(label_value);
+                    }
+                    true
+                });
+                self.changes.extend(
+                    updated
+                        .into_iter()
+                        .map(|event| BusMessage::Updated { event }),
+                );
             }
             outcome = ApplyOutcome::Skip;
         }
@@ -335,7 +927,18 @@ impl StateInner {
         outcome
     }
 
-    fn merge_previous_log_into_context(&mut self, event: &mut TimelineEvent) {
+    fn pop_last_event(&mut self, timeline: &dyn TimelineStore) {
+        if let Some(last) = timeline.last() {
+            timeline.remove(&mut |event| event.id == last.id);
+            self.changes.push(BusMessage::Removed { id: last.id });
+        }
+    }
+
+    fn merge_previous_log_into_context(
+        &mut self,
+        timeline: &dyn TimelineStore,
+        event: &mut TimelineEvent,
+    ) {
         if !event
             .request
             .payloads
@@ -345,10 +948,13 @@ impl StateInner {
             return;
         }
 
-        let last_message = self.timeline.back().and_then(extract_single_log_message);
+        let last_message = timeline
+            .last()
+            .as_ref()
+            .and_then(extract_single_log_message);
 
         if let Some(message) = last_message {
-            self.timeline.pop_back();
+            self.pop_last_event(timeline);
             if event.label.is_none() {
                 event.label = Some(message);
             }
@@ -435,7 +1041,10 @@ mod tests {
     use super::*;
     use crate::protocol::{Payload, RayRequest};
     use serde_json::json;
-    use std::collections::BTreeMap;
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, UNIX_EPOCH},
+    };
 
     fn make_payload(value: serde_json::Value) -> Payload {
         serde_json::from_value(value).expect("payload should deserialize")
@@ -485,6 +1094,398 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn records_executed_queries() {
+        let state = AppState::new(10);
+        let payload = make_payload(json!({
+            "type": "executed_query",
+            "content": { "sql": "select 1", "bindings": [], "time": 0.4 }
+        }));
+        assert!(
+            state
+                .record_request(request_with_payload(payload))
+                .await
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn application_logs_take_their_level_color() {
+        let state = AppState::new(10);
+        let log = |level: &str| {
+            make_payload(json!({
+                "type": "application_log",
+                "content": { "value": "Payment failed", "level": level, "context": {} }
+            }))
+        };
+
+        let error = state
+            .record_request(request_with_payload(log("error")))
+            .await
+            .unwrap();
+        assert_eq!(error.color.as_deref(), Some("red"));
+        assert!(error.is_error());
+
+        let debug = state
+            .record_request(request_with_payload(log("debug")))
+            .await
+            .unwrap();
+        assert_eq!(debug.color.as_deref(), Some("gray"));
+        assert!(!debug.is_error());
+
+        let mut request = request_with_payload(log("warning"));
+        request.payloads.insert(
+            0,
+            make_payload(json!({ "type": "color", "content": { "color": "green" } })),
+        );
+        let colored = state.record_request(request).await.unwrap();
+        assert_eq!(colored.color.as_deref(), Some("green"));
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_the_debug_dump() {
+        let path = std::env::temp_dir().join(format!("raygun-dump-{}.jsonl", Uuid::new_v4()));
+        let state = AppState::with_logger(Some(PayloadLogger::new(path.clone())));
+        let payload = make_payload(json!({
+            "type": "log",
+            "content": { "values": ["last"], "meta": [] }
+        }));
+        for _ in 0..3 {
+            state
+                .record_request(request_with_payload(payload.clone()))
+                .await;
+        }
+
+        state.drain().await;
+        let dump = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(dump.lines().count(), 3);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn retention_keeps_errors_while_other_events_can_go() {
+        let state = AppState::new(2);
+        let log = make_payload(json!({
+            "type": "log",
+            "content": { "values": ["a"], "meta": [] }
+        }));
+        let exception = make_payload(json!({
+            "type": "exception",
+            "content": { "class": "RuntimeException", "message": "boom", "frames": [] }
+        }));
+        let failed_job = make_payload(json!({
+            "type": "job_event",
+            "content": { "event_name": "JobFailed", "job": "SendInvoice" }
+        }));
+
+        let error = state
+            .record_request(request_with_payload(exception.clone()))
+            .await
+            .expect("event recorded");
+        for _ in 0..3 {
+            state
+                .record_request(request_with_payload(log.clone()))
+                .await
+                .expect("event recorded");
+        }
+        let events = state.timeline_snapshot().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id, error.id);
+
+        let job = state
+            .record_request(request_with_payload(failed_job))
+            .await
+            .expect("event recorded");
+        state
+            .record_request(request_with_payload(exception.clone()))
+            .await
+            .expect("event recorded");
+        let events = state.timeline_snapshot().await;
+        assert!(events.iter().all(TimelineEvent::is_error));
+        assert_ne!(events[0].id, error.id, "all errors: the oldest goes");
+        assert_eq!(events[0].id, job.id);
+
+        state.set_pin_errors(false);
+        state
+            .record_request(request_with_payload(log))
+            .await
+            .expect("event recorded");
+        assert!(!state.timeline_snapshot().await[1].is_error());
+    }
+
+    #[tokio::test]
+    async fn removes_single_event() {
+        let state = AppState::default();
+        let payload = make_payload(json!({
+            "type": "log",
+            "content": { "values": ["a"], "meta": [] }
+        }));
+
+        let first = state
+            .record_request(request_with_payload(payload.clone()))
+            .await
+            .expect("event recorded");
+        state
+            .record_request(request_with_payload(payload))
+            .await
+            .expect("event recorded");
+
+        assert!(state.remove_event(first.id).await);
+        assert!(!state.remove_event(first.id).await);
+        let events = state.timeline_snapshot().await;
+        assert_eq!(events.len(), 1);
+        assert_ne!(events[0].id, first.id);
+
+        let trash = state.trash_snapshot().await;
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, first.id);
+        assert!(state.restore_event(first.id).await);
+        assert!(!state.restore_event(first.id).await);
+        let events = state.timeline_snapshot().await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id, first.id, "restored to where it was");
+        assert!(state.trash_snapshot().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn routes_send_events_to_screens_unless_the_request_names_one() {
+        let state = AppState::default();
+        state
+            .set_routes(Routes::new(
+                &[crate::config::RouteConfig {
+                    screen: "Queues".into(),
+                    filter: "project:worker".into(),
+                }],
+                &BTreeMap::new(),
+            ))
+            .await;
+        let log = make_payload(json!({ "type": "log", "content": { "values": ["a"] } }));
+        let from_worker = |payload: Payload| RayRequest {
+            meta: BTreeMap::from([("project_name".to_string(), json!("worker"))]),
+            ..request_with_payload(payload)
+        };
+
+        state
+            .record_request(request_with_payload(make_payload(
+                json!({ "type": "new_screen", "content": { "name": "checkout" } }),
+            )))
+            .await;
+        let routed = state
+            .record_request(from_worker(log.clone()))
+            .await
+            .expect("event recorded");
+        assert_eq!(routed.screen.as_deref(), Some("Queues"));
+        let other = state
+            .record_request(request_with_payload(log.clone()))
+            .await
+            .expect("event recorded");
+        assert_eq!(other.screen.as_deref(), Some("checkout"));
+
+        let mut named = from_worker(log);
+        named.meta.insert("screen".into(), json!("jobs"));
+        let named = state.record_request(named).await.expect("event recorded");
+        assert_eq!(named.screen.as_deref(), Some("jobs"));
+    }
+
+    #[tokio::test]
+    async fn moves_renames_and_closes_screens() {
+        let state = AppState::default();
+        let screen =
+            make_payload(json!({ "type": "new_screen", "content": { "name": "checkout" } }));
+        let log = make_payload(json!({
+            "type": "log",
+            "content": { "values": ["a"], "meta": [] }
+        }));
+
+        state.record_request(request_with_payload(screen)).await;
+        let first = state
+            .record_request(request_with_payload(log.clone()))
+            .await
+            .expect("event recorded");
+        assert_eq!(first.screen.as_deref(), Some("checkout"));
+        let moved = state
+            .record_request(request_with_payload(log.clone()))
+            .await
+            .expect("event recorded");
+        assert!(state.set_screen(moved.id, Some("  later ".into())).await);
+        let events = state.timeline_snapshot().await;
+        let moved_event = events.iter().find(|event| event.id == moved.id);
+        assert_eq!(moved_event.unwrap().screen.as_deref(), Some("later"));
+
+        assert_eq!(state.rename_screen("checkout", " cart ").await, 2);
+        let next = state
+            .record_request(request_with_payload(log.clone()))
+            .await
+            .expect("event recorded");
+        assert_eq!(next.screen.as_deref(), Some("cart"));
+
+        assert_eq!(state.close_screen("cart").await, 3);
+        let events = state.timeline_snapshot().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, moved.id);
+        // Closed events can be restored from the trash.
+        assert_eq!(state.trash_snapshot().await.len(), 3);
+        assert!(state.restore_event(first.id).await);
+        let after = state
+            .record_request(request_with_payload(log))
+            .await
+            .expect("event recorded");
+        assert_eq!(after.screen, None);
+    }
+
+    #[tokio::test]
+    async fn tags_an_event_and_publishes_it() {
+        let state = AppState::default();
+        let (_, mut changes) = state.subscribe().await;
+        let payload = make_payload(json!({
+            "type": "log",
+            "content": { "values": ["a"], "meta": [] }
+        }));
+        let event = state
+            .record_request(request_with_payload(payload))
+            .await
+            .expect("event recorded");
+
+        assert!(state.set_label(event.id, Some("checkout".into())).await);
+        assert!(!state.set_label(Uuid::new_v4(), None).await);
+        assert!(state.set_color(event.id, Some("green".into())).await);
+        let events = state.timeline_snapshot().await;
+        assert_eq!(events[0].label.as_deref(), Some("checkout"));
+        assert_eq!(events[0].color.as_deref(), Some("green"));
+
+        assert!(matches!(
+            changes.try_recv(),
+            Ok(BusMessage::Recorded { .. })
+        ));
+        assert!(matches!(
+            changes.try_recv(),
+            Ok(BusMessage::Updated { event }) if event.label.as_deref() == Some("checkout")
+        ));
+    }
+
+    #[tokio::test]
+    async fn publishes_changes_that_replay_onto_a_mirror() {
+        let state = AppState::default();
+        let mirror = AppState::default();
+        let (snapshot, mut changes) = state.subscribe().await;
+        assert!(snapshot.is_empty());
+
+        let log = make_payload(json!({
+            "type": "log",
+            "content": { "values": ["a"], "meta": [] }
+        }));
+        let color = make_payload(json!({ "type": "color", "content": { "color": "red" } }));
+        let hide = make_payload(json!({ "type": "hide", "content": {} }));
+
+        state
+            .record_request(request_with_payload(log.clone()))
+            .await
+            .expect("event recorded");
+        let kept = state
+            .record_request(request_with_payload(log))
+            .await
+            .expect("event recorded");
+        state.record_request(request_with_payload(color)).await;
+        state.record_request(request_with_payload(hide)).await;
+
+        while let Ok(message) = changes.try_recv() {
+            if let Some(change) = message.timeline_change() {
+                mirror.apply_change(change).await;
+            }
+        }
+        let expected = state.timeline_snapshot().await;
+        let mirrored = mirror.timeline_snapshot().await;
+        assert_eq!(mirrored.len(), 1);
+        assert_eq!(mirrored[0].id, expected[0].id);
+        assert_ne!(mirrored[0].id, kept.id, "hide removed the last event");
+
+        state.clear_timeline().await;
+        assert!(matches!(changes.try_recv(), Ok(BusMessage::Cleared)));
+    }
+
+    #[tokio::test]
+    async fn records_provenance_and_client_latency() {
+        let payload = make_payload(json!({
+            "type": "log",
+            "content": { "values": ["a"], "meta": [] }
+        }));
+        let mut request = request_with_payload(payload);
+        request
+            .meta
+            .insert("sent_at".into(), json!(1_700_000_000.25));
+        let provenance = Provenance {
+            remote_addr: Some(SocketAddr::from(([10, 0, 0, 7], 50_123))),
+            proxy_addr: None,
+            received_at: UNIX_EPOCH + Duration::from_micros(1_700_000_000_251_500),
+            headers: vec![("user-agent".into(), "GuzzleHttp/7".into())],
+        };
+
+        let state = AppState::default();
+        let event = state
+            .record_request_from(request, provenance.clone())
+            .await
+            .expect("event recorded");
+        assert_eq!(event.remote_addr, provenance.remote_addr);
+        assert_eq!(event.headers, provenance.headers);
+        assert_eq!(event.received_at, provenance.received_at);
+        assert_eq!(event.latency_micros(), Some(1_500));
+
+        let mut ahead = event.clone();
+        let mut request = (*ahead.request).clone();
+        request
+            .meta
+            .insert("sent_at".into(), json!("1700000000252"));
+        ahead.request = Arc::new(request);
+        assert_eq!(ahead.latency_micros(), Some(-500));
+    }
+
+    #[tokio::test]
+    async fn drops_retried_deliveries() {
+        let request = request_with_payload(make_payload(json!({
+            "type": "log",
+            "content": { "values": ["a"], "meta": [] }
+        })));
+        let color = request_with_payload(make_payload(json!({
+            "type": "color",
+            "content": { "color": "red" }
+        })));
+        let at = |secs| Provenance {
+            remote_addr: Some(SocketAddr::from(([127, 0, 0, 1], 50_123))),
+            proxy_addr: None,
+            received_at: UNIX_EPOCH + Duration::from_secs(secs),
+            headers: Vec::new(),
+        };
+
+        let state = AppState::default();
+        let first = state
+            .record_request_from(request.clone(), at(100))
+            .await
+            .expect("event recorded");
+        let retry = state.record_request_from(request.clone(), at(101)).await;
+        assert_eq!(retry.map(|event| event.id), Some(first.id));
+        // A follow-up with the same uuid but other content is no retry.
+        state.record_request_from(color.clone(), at(101)).await;
+        state.record_request_from(color, at(102)).await;
+        assert_eq!(state.duplicates().await, 2);
+        let events = state.timeline_snapshot().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].color.as_deref(), Some("red"));
+
+        // Past the window, and for requests not delivered over HTTP, the same
+        // request is recorded again.
+        let window = dedup::WINDOW.as_secs();
+        assert!(
+            state
+                .record_request_from(request.clone(), at(101 + window))
+                .await
+                .is_some()
+        );
+        assert!(state.record_request(request.clone()).await.is_some());
+        assert!(state.record_request(request).await.is_some());
+        assert_eq!(state.timeline_len().await, 4);
+        assert_eq!(state.duplicates().await, 2);
+    }
+
     #[tokio::test]
     async fn tracks_locks_from_payloads_without_recording_event() {
         let state = AppState::default();
@@ -513,6 +1514,47 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn publishes_lock_changes_on_the_bus() {
+        let state = AppState::default();
+        let mut bus = state.bus().subscribe();
+        let lock = |kind: &str| {
+            request_with_payload(make_payload(json!({
+                "type": kind,
+                "content": { "name": "pause-lock" }
+            })))
+        };
+
+        state.record_request(lock("create_lock")).await;
+        state.clear_lock("pause-lock").await;
+        state.clear_lock("pause-lock").await;
+        state.record_request(lock("create_lock")).await;
+        state
+            .record_request(request_with_payload(make_payload(
+                json!({ "type": "clear_all", "content": {} }),
+            )))
+            .await;
+
+        let mut messages = Vec::new();
+        while let Ok(message) = bus.try_recv() {
+            messages.push(match message {
+                BusMessage::LockChanged { name, held } => format!("{name} {held}"),
+                BusMessage::Cleared => "cleared".to_owned(),
+                other => format!("{other:?}"),
+            });
+        }
+        assert_eq!(
+            messages,
+            [
+                "pause-lock true",
+                "pause-lock false",
+                "pause-lock true",
+                "cleared",
+                "pause-lock false",
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn clear_all_purges_timeline() {
         let state = AppState::default();
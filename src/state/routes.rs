@@ -0,0 +1,98 @@
+//! Screen routing from the config's `[[routes]]`, which sends events from
+//! matching projects, hosts or labels to a named screen as they are recorded,
+//! so several apps sharing one Raygun each keep to their own screen.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use super::TimelineEvent;
+use crate::{
+    config::{HostAliases, RouteConfig},
+    filter::Filter,
+};
+
+/// The routes in config order; the first match wins.
+#[derive(Debug, Clone, Default)]
+pub struct Routes(Vec<(Filter, String)>);
+
+impl Routes {
+    /// Parses `configs`, skipping routes with an invalid filter or no screen
+    /// (validation reports those before Raygun starts). `hosts` lets `host:`
+    /// terms match friendly names.
+    pub fn new(configs: &[RouteConfig], hosts: &BTreeMap<String, String>) -> Self {
+        let hosts = Arc::new(HostAliases::new(hosts));
+        Self(
+            configs
+                .iter()
+                .filter(|config| !config.screen.trim().is_empty())
+                .filter_map(|config| {
+                    let filter = Filter::parse(&config.filter)
+                        .ok()?
+                        .with_host_aliases(Arc::clone(&hosts));
+                    Some((filter, config.screen.trim().to_string()))
+                })
+                .collect(),
+        )
+    }
+
+    /// The screen of the first route matching `event`, if any.
+    pub fn screen_for(&self, event: &TimelineEvent) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(filter, _)| filter.matches(event))
+            .map(|(_, screen)| screen.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn event(hostname: &str, label: Option<&str>) -> TimelineEvent {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{ "type": "log", "content": { "values": ["x"] } }],
+            "meta": { "hostname": hostname, "project_name": "shop" }
+        }))
+        .unwrap();
+        let mut event = TimelineEvent::new(request, None);
+        event.label = label.map(str::to_string);
+        event
+    }
+
+    #[test]
+    fn first_matching_route_picks_the_screen() {
+        let hosts = BTreeMap::from([("f3a9c1".to_string(), "worker".to_string())]);
+        let routes = Routes::new(
+            &[
+                RouteConfig {
+                    screen: "Queues".into(),
+                    filter: "host:worker".into(),
+                },
+                RouteConfig {
+                    screen: "  ".into(),
+                    filter: "label:slow".into(),
+                },
+                RouteConfig {
+                    screen: "Slow".into(),
+                    filter: "label:slow".into(),
+                },
+                RouteConfig {
+                    screen: "Shop".into(),
+                    filter: "project:shop".into(),
+                },
+            ],
+            &hosts,
+        );
+
+        assert_eq!(
+            routes.screen_for(&event("f3a9c1", Some("slow"))),
+            Some("Queues")
+        );
+        assert_eq!(routes.screen_for(&event("web", Some("slow"))), Some("Slow"));
+        assert_eq!(routes.screen_for(&event("web", None)), Some("Shop"));
+        assert_eq!(Routes::default().screen_for(&event("web", None)), None);
+    }
+}
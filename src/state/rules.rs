@@ -0,0 +1,211 @@
+//! Classification rules from the config's `[[rules]]`, which color and label
+//! events as they are recorded.
+
+use regex::Regex;
+
+use super::TimelineEvent;
+use crate::{config::RuleConfig, protocol::Payload};
+
+/// The rules in config order; the first match that sets a color or label wins.
+#[derive(Debug, Clone, Default)]
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    /// Compiles `configs`, skipping rules that fail to (validation reports
+    /// those before Raygun starts).
+    pub fn new(configs: &[RuleConfig]) -> Self {
+        Self(
+            configs
+                .iter()
+                .filter_map(|config| Rule::new(config).ok())
+                .collect(),
+        )
+    }
+
+    /// Colors and labels `event` from the first matching rules that set
+    /// them. A color or label the client sent is kept.
+    pub fn apply(&self, event: &mut TimelineEvent) {
+        for rule in &self.0 {
+            if event.color.is_some() && event.label.is_some() {
+                return;
+            }
+            if !event
+                .request
+                .payloads
+                .iter()
+                .any(|payload| rule.matches(payload))
+            {
+                continue;
+            }
+            if event.color.is_none() {
+                event.color = rule.color.clone();
+            }
+            if event.label.is_none() {
+                event.label = rule.label.clone();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    kind: Option<String>,
+    origin: Option<Regex>,
+    content: Option<Regex>,
+    color: Option<String>,
+    label: Option<String>,
+}
+
+impl Rule {
+    pub fn new(config: &RuleConfig) -> Result<Self, String> {
+        let origin = config
+            .origin
+            .as_deref()
+            .map(|glob| {
+                glob_regex(glob).map_err(|err| format!("invalid `origin` glob `{}`: {}", glob, err))
+            })
+            .transpose()?;
+        let content = config
+            .content
+            .as_deref()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|err| format!("invalid `content` regex `{}`: {}", pattern, err))
+            })
+            .transpose()?;
+        Ok(Self {
+            kind: config
+                .kind
+                .as_deref()
+                .map(|kind| kind.trim().to_lowercase()),
+            origin,
+            content,
+            color: config.color.clone(),
+            label: config
+                .label
+                .as_deref()
+                .map(|label| label.trim().to_string()),
+        })
+    }
+
+    fn matches(&self, payload: &Payload) -> bool {
+        if let Some(kind) = &self.kind
+            && !payload.kind.as_type().eq_ignore_ascii_case(kind)
+        {
+            return false;
+        }
+        if let Some(origin) = &self.origin {
+            let file = payload
+                .origin
+                .as_ref()
+                .and_then(|origin| origin.file.as_deref());
+            if !file.is_some_and(|file| origin.is_match(&file.replace('\\', "/"))) {
+                return false;
+            }
+        }
+        if let Some(content) = &self.content
+            && !content.is_match(&payload.content().to_string())
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// `*` matches within a path segment, `**` across segments and `?` one
+/// character. Globs without a leading `/` match after any `/`, so
+/// `app/Payments/**` matches `/var/www/app/Payments/Stripe.php`.
+fn glob_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let glob = glob.trim().replace('\\', "/");
+    let mut pattern = String::from(if glob.starts_with('/') {
+        "^"
+    } else {
+        "(?:^|/)"
+    });
+    let mut chars = glob.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                pattern.push_str(".*");
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            other => pattern.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::protocol::RayRequest;
+
+    fn event(payload: serde_json::Value) -> TimelineEvent {
+        let request: RayRequest = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [payload],
+            "meta": {}
+        }))
+        .unwrap();
+        TimelineEvent::new(request, None)
+    }
+
+    #[test]
+    fn first_matching_rules_color_and_label_events() {
+        let rules = Rules::new(&[
+            RuleConfig {
+                origin: Some("app/Payments/**".into()),
+                color: Some("purple".into()),
+                ..RuleConfig::default()
+            },
+            RuleConfig {
+                kind: Some("log".into()),
+                content: Some("(?i)stripe".into()),
+                color: Some("green".into()),
+                label: Some("stripe".into()),
+                ..RuleConfig::default()
+            },
+        ]);
+
+        let mut payment = event(json!({
+            "type": "log",
+            "content": { "values": ["Stripe charge"] },
+            "origin": { "file": "/var/www/app/Payments/Gateway/Stripe.php", "line_number": 3 }
+        }));
+        rules.apply(&mut payment);
+        assert_eq!(payment.color.as_deref(), Some("purple"));
+        assert_eq!(payment.label.as_deref(), Some("stripe"));
+
+        let mut elsewhere = event(json!({
+            "type": "log",
+            "content": { "values": ["hi"] },
+            "origin": { "file": "/var/www/app/Models/Payments.php", "line_number": 3 }
+        }));
+        rules.apply(&mut elsewhere);
+        assert_eq!(elsewhere.color, None);
+        assert_eq!(elsewhere.label, None);
+
+        let mut sent = event(json!({ "type": "log", "content": { "values": ["STRIPE"] } }));
+        sent.color = Some("red".into());
+        rules.apply(&mut sent);
+        assert_eq!(sent.color.as_deref(), Some("red"));
+        assert_eq!(sent.label.as_deref(), Some("stripe"));
+    }
+
+    #[test]
+    fn globs_match_path_segments() {
+        let glob = glob_regex("app/*.php").unwrap();
+        assert!(glob.is_match("/srv/app/User.php"));
+        assert!(!glob.is_match("/srv/app/Models/User.php"));
+        assert!(!glob.is_match("/srv/webapp/User.php"));
+
+        let absolute = glob_regex("/srv/**/Jobs/?.php").unwrap();
+        assert!(absolute.is_match("/srv/app/Jobs/A.php"));
+        assert!(!absolute.is_match("/other/srv/app/Jobs/A.php"));
+    }
+}
@@ -0,0 +1,262 @@
+//! The in-memory backend: the timeline itself, and with `--store memory` a
+//! history of the latest events this run recorded, including ones cleared or
+//! deleted from the timeline, without a file. Nothing outlives the process.
+
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{SessionInfo, StoreError, StoreQuery, StoredEvent, TimelineEvent, TimelineStore};
+
+/// The only session of a memory store.
+const SESSION: i64 = 1;
+
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    /// The timeline, oldest first.
+    timeline: Mutex<VecDeque<TimelineEvent>>,
+    history: Option<History>,
+}
+
+#[derive(Debug)]
+struct History {
+    started_at: SystemTime,
+    /// Most events kept; the oldest go first.
+    retention: usize,
+    /// The latest recorded events, oldest first, each once.
+    events: Mutex<VecDeque<TimelineEvent>>,
+}
+
+impl MemoryStore {
+    /// A timeline without a history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A timeline whose history keeps the latest `retention` events.
+    pub fn with_history(retention: usize) -> Self {
+        Self {
+            timeline: Mutex::default(),
+            history: Some(History {
+                started_at: SystemTime::now(),
+                retention,
+                events: Mutex::default(),
+            }),
+        }
+    }
+
+    fn timeline(&self) -> MutexGuard<'_, VecDeque<TimelineEvent>> {
+        self.timeline.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Adds `event` to the history, or replaces the copy kept of it.
+    fn keep(&self, event: &TimelineEvent) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        let mut events = history.events.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(existing) = events.iter_mut().rev().find(|e| e.id == event.id) {
+            *existing = event.clone();
+            return;
+        }
+        events.push_back(event.clone());
+        while events.len() > history.retention {
+            events.pop_front();
+        }
+    }
+}
+
+#[async_trait]
+impl TimelineStore for MemoryStore {
+    fn push(&self, event: TimelineEvent) {
+        self.keep(&event);
+        self.timeline().push_back(event);
+    }
+
+    fn insert(&self, index: usize, event: TimelineEvent) {
+        self.keep(&event);
+        let mut timeline = self.timeline();
+        let index = index.min(timeline.len());
+        timeline.insert(index, event);
+    }
+
+    fn update(&self, update: &mut dyn FnMut(&mut TimelineEvent) -> bool) -> Vec<TimelineEvent> {
+        let mut changed = Vec::new();
+        for event in self.timeline().iter_mut() {
+            if update(event) {
+                event.revision += 1;
+                changed.push(event.clone());
+            }
+        }
+        for event in &changed {
+            self.keep(event);
+        }
+        changed
+    }
+
+    fn remove(
+        &self,
+        remove: &mut dyn FnMut(&TimelineEvent) -> bool,
+    ) -> Vec<(usize, TimelineEvent)> {
+        let mut timeline = self.timeline();
+        let mut removed = Vec::new();
+        let mut index = 0;
+        while index < timeline.len() {
+            if !remove(&timeline[index]) {
+                index += 1;
+                continue;
+            }
+            if let Some(event) = timeline.remove(index) {
+                removed.push((index, event));
+            }
+        }
+        removed
+    }
+
+    fn evict(&self, retention: usize, pin_errors: bool) -> Option<TimelineEvent> {
+        let mut timeline = self.timeline();
+        if timeline.len() <= retention {
+            return None;
+        }
+        let newest = timeline.len() - 1;
+        let index = pin_errors
+            .then(|| {
+                timeline
+                    .iter()
+                    .take(newest)
+                    .position(|event| !event.is_error())
+            })
+            .flatten()
+            .unwrap_or(0);
+        timeline.remove(index)
+    }
+
+    fn clear(&self) {
+        self.timeline().clear();
+    }
+
+    fn replace(&self, events: Vec<TimelineEvent>) {
+        let mut timeline = self.timeline();
+        let revision = timeline.iter().map(|event| event.revision).max();
+        let revision = revision.map_or(0, |revision| revision + 1);
+        *timeline = events
+            .into_iter()
+            .map(|event| TimelineEvent { revision, ..event })
+            .collect();
+    }
+
+    fn get(&self, id: Uuid) -> Option<TimelineEvent> {
+        self.timeline().iter().find(|event| event.id == id).cloned()
+    }
+
+    fn last(&self) -> Option<TimelineEvent> {
+        self.timeline().back().cloned()
+    }
+
+    fn snapshot(&self) -> Vec<TimelineEvent> {
+        self.timeline().iter().cloned().collect()
+    }
+
+    fn len(&self) -> usize {
+        self.timeline().len()
+    }
+
+    fn keeps_history(&self) -> bool {
+        self.history.is_some()
+    }
+
+    fn session(&self) -> Option<i64> {
+        self.history.as_ref().map(|_| SESSION)
+    }
+
+    async fn flush(&self) {}
+
+    async fn search(&self, query: &StoreQuery) -> Result<Vec<StoredEvent>, StoreError> {
+        let Some(history) = &self.history else {
+            return Ok(Vec::new());
+        };
+        let events = history.events.lock().unwrap_or_else(|err| err.into_inner());
+        Ok(query.select(events.iter().map(|event| (SESSION, event))))
+    }
+
+    async fn sessions(&self) -> Result<Vec<SessionInfo>, StoreError> {
+        let Some(history) = &self.history else {
+            return Ok(Vec::new());
+        };
+        let events = history
+            .events
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .len();
+        Ok((events > 0)
+            .then_some(SessionInfo {
+                id: SESSION,
+                started_at: history.started_at,
+                events,
+            })
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn log_event(value: &str) -> TimelineEvent {
+        let request = serde_json::from_value(json!({
+            "uuid": value,
+            "payloads": [{ "type": "log", "content": { "values": [value] } }],
+            "meta": {}
+        }))
+        .unwrap();
+        TimelineEvent::new(request, None)
+    }
+
+    #[tokio::test]
+    async fn history_keeps_only_the_latest_events() {
+        let store = MemoryStore::with_history(2);
+        let mut ids = Vec::new();
+        for value in ["one", "two", "three"] {
+            let event = log_event(value);
+            ids.push(event.id);
+            store.push(event);
+        }
+
+        let found = store.search(&StoreQuery::default()).await.unwrap();
+        let found: Vec<_> = found.iter().map(|stored| stored.event.id).collect();
+        assert_eq!(found.len(), 2);
+        assert!(!found.contains(&ids[0]));
+        assert_eq!(store.sessions().await.unwrap()[0].events, 2);
+        assert_eq!(store.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn history_keeps_each_event_once() {
+        let store = MemoryStore::with_history(16);
+        let event = log_event("one");
+        let id = event.id;
+        store.push(event);
+        let updated = store.update(&mut |event| {
+            event.label = Some("first".into());
+            true
+        });
+        assert_eq!(updated[0].revision, 1);
+
+        // Deleting and restoring an event records it again.
+        let (index, event) = store.remove(&mut |event| event.id == id).remove(0);
+        store.insert(index, event);
+
+        let found = store.search(&StoreQuery::default()).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].event.label.as_deref(), Some("first"));
+        assert_eq!(store.sessions().await.unwrap()[0].events, 1);
+    }
+}
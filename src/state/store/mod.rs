@@ -0,0 +1,573 @@
+//! The timeline store.
+//!
+//! A [`TimelineStore`] holds the timeline, the events the UI and clients see
+//! within the retention limit. Backends selected with `--store` also keep a
+//! history of every event recorded, tagged with the session (one run of
+//! Raygun) it arrived in, including events later cleared, deleted or evicted
+//! from the timeline, and answer history searches by kind, label, project,
+//! time range and text:
+//!
+//! - `sqlite:<path>` ([`SqliteStore`]) indexes events in a SQLite database;
+//! - `spill:<path>` ([`SpillStore`]) appends them to a JSON Lines file and
+//!   scans it to answer queries;
+//! - `memory` ([`MemoryStore::with_history`]) keeps the latest of them in
+//!   memory for the current run.
+//!
+//! Without `--store` the timeline lives in a [`MemoryStore`] with no history.
+
+mod memory;
+mod spill;
+mod sqlite;
+
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::{TimelineEvent, summary::summarize};
+use crate::filter::tokenize;
+pub use memory::MemoryStore;
+pub use spill::SpillStore;
+pub use sqlite::SqliteStore;
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("failed to open event store {path}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: rusqlite::Error,
+    },
+    #[error("event store query failed: {0}")]
+    Query(#[from] rusqlite::Error),
+    #[error("failed to open event store {path}: {source}")]
+    OpenFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to read the event store: {0}")]
+    Read(#[source] std::io::Error),
+    #[error("failed to start the event store writer: {0}")]
+    Writer(#[source] std::io::Error),
+    #[error("the memory store only lives in the process recording it; use sqlite: or spill:")]
+    NotShared,
+    #[error("event store query stopped: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+/// Holds the timeline, oldest event first, and the history behind it.
+/// `AppState` changes the timeline only through these methods, while it holds
+/// its own lock, so they must not wait for the disk: backends that persist
+/// events hand them to a writer thread. History searches keep blocking reads
+/// off the async runtime.
+#[async_trait]
+pub trait TimelineStore: fmt::Debug + Send + Sync {
+    /// Adds `event` as the newest.
+    fn push(&self, event: TimelineEvent);
+
+    /// Puts `event` at `index`, or last when the timeline is shorter.
+    fn insert(&self, index: usize, event: TimelineEvent);
+
+    /// Runs `update` on every event and returns the ones it changed (returned
+    /// `true` for), with their revision bumped.
+    fn update(&self, update: &mut dyn FnMut(&mut TimelineEvent) -> bool) -> Vec<TimelineEvent>;
+
+    /// Takes every event `remove` picks out of the timeline, each with the
+    /// index it had when it was taken. The history keeps them.
+    fn remove(&self, remove: &mut dyn FnMut(&TimelineEvent) -> bool)
+    -> Vec<(usize, TimelineEvent)>;
+
+    /// Drops the oldest event once the timeline outgrows `retention`. With
+    /// `pin_errors`, the oldest event that isn't an error goes instead, as
+    /// long as there is one besides the newest.
+    fn evict(&self, retention: usize, pin_errors: bool) -> Option<TimelineEvent>;
+
+    fn clear(&self);
+
+    /// Replaces the timeline with `events`, oldest first, e.g. a daemon's
+    /// snapshot. Any event may have changed, so each gets a revision none
+    /// had yet. The history is left alone.
+    fn replace(&self, events: Vec<TimelineEvent>);
+
+    fn get(&self, id: Uuid) -> Option<TimelineEvent>;
+
+    /// The newest event.
+    fn last(&self) -> Option<TimelineEvent>;
+
+    fn snapshot(&self) -> Vec<TimelineEvent>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether [`search`](Self::search) reaches beyond the timeline.
+    fn keeps_history(&self) -> bool;
+
+    /// The session events recorded through this handle belong to; `None`
+    /// without a history or for a read-only handle.
+    fn session(&self) -> Option<i64>;
+
+    /// Waits until every event recorded so far has been written.
+    async fn flush(&self);
+
+    /// Events in the history matching `query`, newest first.
+    async fn search(&self, query: &StoreQuery) -> Result<Vec<StoredEvent>, StoreError>;
+
+    /// Every session with at least one event, newest first.
+    async fn sessions(&self) -> Result<Vec<SessionInfo>, StoreError>;
+}
+
+/// Which backend `--store` selects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreSpec {
+    Sqlite(PathBuf),
+    Spill(PathBuf),
+    Memory,
+}
+
+impl StoreSpec {
+    /// Opens the store for recording. The memory backend's history keeps at
+    /// most `retention` events.
+    pub fn open(&self, retention: usize) -> Result<Arc<dyn TimelineStore>, StoreError> {
+        Ok(match self {
+            StoreSpec::Sqlite(path) => Arc::new(SqliteStore::open(path)?),
+            StoreSpec::Spill(path) => Arc::new(SpillStore::open(path)?),
+            StoreSpec::Memory => Arc::new(MemoryStore::with_history(retention)),
+        })
+    }
+
+    /// Opens the store for queries only, e.g. to search a daemon's store
+    /// from an attached client.
+    pub fn open_read_only(&self) -> Result<Arc<dyn TimelineStore>, StoreError> {
+        Ok(match self {
+            StoreSpec::Sqlite(path) => Arc::new(SqliteStore::open_read_only(path)?),
+            StoreSpec::Spill(path) => Arc::new(SpillStore::open_read_only(path)),
+            StoreSpec::Memory => return Err(StoreError::NotShared),
+        })
+    }
+}
+
+/// Filters for [`TimelineStore::search`]; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct StoreQuery {
+    /// Words that must all appear (as word prefixes) in the summary, the
+    /// payload content or the origin.
+    pub text: Option<String>,
+    /// Timeline kind, as shown in the timeline (`log`, `exception`, ...).
+    pub kind: Option<String>,
+    pub label: Option<String>,
+    pub project: Option<String>,
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+    pub session: Option<i64>,
+    /// Most recent matches returned; all of them when unset.
+    pub limit: Option<usize>,
+}
+
+impl StoreQuery {
+    /// Parses a search such as `checkout kind:exception since:2d`.
+    ///
+    /// `kind:`, `label:`, `project:` and `session:` set the matching filter;
+    /// `since:` and `until:` take a UTC date (`2024-03-09`), a UTC date and
+    /// time (`2024-03-09T14:05`) or an age (`30m`, `2h`, `7d`, `2w`) relative
+    /// to `now`. A date-only `until:` includes that whole day. Every other
+    /// word is searched for in the event text.
+    pub fn parse(input: &str, now: SystemTime) -> Result<Self, String> {
+        let mut query = StoreQuery::default();
+        let mut words = Vec::new();
+        for token in tokenize(input).map_err(|err| err.to_string())? {
+            let Some((field, value)) = token.split_once(':') else {
+                words.push(token);
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                return Err(format!("`{}:` needs a value", field));
+            }
+            match field.to_ascii_lowercase().as_str() {
+                "kind" | "type" => query.kind = Some(value.to_string()),
+                "label" => query.label = Some(value.to_string()),
+                "project" => query.project = Some(value.to_string()),
+                "session" => {
+                    query.session = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("`{}` is not a session number", value))?,
+                    );
+                }
+                "since" => query.since = Some(parse_time(value, now, false)?),
+                "until" => query.until = Some(parse_time(value, now, true)?),
+                // Not a filter, e.g. a URL or `Class::method`.
+                _ => words.push(token),
+            }
+        }
+        if !words.is_empty() {
+            query.text = Some(words.join(" "));
+        }
+        Ok(query)
+    }
+
+    /// Picks the matches among `events` (oldest first) for the backends that
+    /// don't index, newest first like SQLite's results.
+    fn select<'a>(
+        &self,
+        events: impl Iterator<Item = (i64, &'a TimelineEvent)>,
+    ) -> Vec<StoredEvent> {
+        let mut found: Vec<StoredEvent> = events
+            .filter(|(session, event)| self.matches(*session, event))
+            .map(|(session, event)| StoredEvent {
+                session,
+                event: event.clone(),
+            })
+            .collect();
+        found.reverse();
+        // Stable, so events received at the same time stay newest first.
+        found.sort_by_key(|stored| std::cmp::Reverse(stored.event.received_at));
+        if let Some(limit) = self.limit {
+            found.truncate(limit);
+        }
+        found
+    }
+
+    fn matches(&self, session: i64, event: &TimelineEvent) -> bool {
+        let same = |expected: &Option<String>, actual: Option<&str>| {
+            expected.as_deref().is_none_or(|expected| {
+                actual.is_some_and(|actual| actual.eq_ignore_ascii_case(expected))
+            })
+        };
+        if self.session.is_some_and(|expected| expected != session)
+            || self.since.is_some_and(|since| event.received_at < since)
+            || self.until.is_some_and(|until| event.received_at >= until)
+            || !same(&self.label, event.label.as_deref())
+            || !same(
+                &self.project,
+                event
+                    .request
+                    .meta
+                    .get("project_name")
+                    .and_then(Value::as_str),
+            )
+        {
+            return false;
+        }
+        let entry = summarize(event);
+        if self.kind.as_ref().is_some_and(|kind| *kind != entry.kind) {
+            return false;
+        }
+        let Some(text) = &self.text else {
+            return true;
+        };
+        let haystack = words(&search_text(event, &entry.summary));
+        text.split_whitespace().all(|word| {
+            let terms = words(word);
+            let Some((last, rest)) = terms.split_last() else {
+                return true;
+            };
+            haystack.windows(terms.len()).any(|window| {
+                window[..rest.len()] == *rest && window[rest.len()].starts_with(last.as_str())
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredEvent {
+    pub session: i64,
+    pub event: TimelineEvent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    pub id: i64,
+    pub started_at: SystemTime,
+    pub events: usize,
+}
+
+/// The summary, every string in the payload content and the origin files.
+fn search_text(event: &TimelineEvent, summary: &str) -> String {
+    fn collect(value: &Value, out: &mut String) {
+        match value {
+            Value::String(text) => {
+                out.push('\n');
+                out.push_str(text);
+            }
+            Value::Array(items) => items.iter().for_each(|item| collect(item, out)),
+            Value::Object(map) => map.values().for_each(|item| collect(item, out)),
+            _ => {}
+        }
+    }
+
+    let mut text = summary.to_string();
+    for payload in &event.request.payloads {
+        collect(payload.content(), &mut text);
+        if let Some(file) = payload
+            .origin
+            .as_ref()
+            .and_then(|origin| origin.file.as_ref())
+        {
+            text.push('\n');
+            text.push_str(file);
+        }
+    }
+    text
+}
+
+/// Lowercased runs of letters and digits, the words SQLite's full text index
+/// splits text into.
+fn words(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Parses a `since:`/`until:` value; `end_of_day` makes a bare date cover the
+/// whole day.
+fn parse_time(raw: &str, now: SystemTime, end_of_day: bool) -> Result<SystemTime, String> {
+    let invalid = || {
+        format!(
+            "`{}` is not a date (2024-03-09 or 2024-03-09T14:05) or an age (30m, 2h, 7d)",
+            raw
+        )
+    };
+
+    if let Some(unit) = raw.chars().last().filter(char::is_ascii_alphabetic) {
+        let amount: u64 = raw[..raw.len() - 1].parse().map_err(|_| invalid())?;
+        let seconds = match unit.to_ascii_lowercase() {
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let age = Duration::from_secs(amount.saturating_mul(seconds));
+        return Ok(now.checked_sub(age).unwrap_or(UNIX_EPOCH));
+    }
+
+    let (date, time) = match raw.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (raw, None),
+    };
+    let number = |part: Option<&str>, range: std::ops::RangeInclusive<i64>| {
+        part.and_then(|part| part.parse::<i64>().ok())
+            .filter(|value| range.contains(value))
+            .ok_or_else(invalid)
+    };
+    let mut date_parts = date.split('-');
+    let year = number(date_parts.next(), 1970..=9999)?;
+    let month = number(date_parts.next(), 1..=12)?;
+    let day = number(date_parts.next(), 1..=31)?;
+    if date_parts.next().is_some() {
+        return Err(invalid());
+    }
+
+    let mut seconds = days_from_civil(year, month, day) * 24 * 60 * 60;
+    match time {
+        Some(time) => {
+            let mut time_parts = time.split(':');
+            seconds += number(time_parts.next(), 0..=23)? * 60 * 60;
+            seconds += number(time_parts.next(), 0..=59)? * 60;
+            if let Some(second) = time_parts.next() {
+                seconds += number(Some(second), 0..=59)?;
+            }
+            if time_parts.next().is_some() {
+                return Err(invalid());
+            }
+        }
+        None if end_of_day => seconds += 24 * 60 * 60,
+        None => {}
+    }
+    Ok(UNIX_EPOCH + Duration::from_secs(seconds as u64))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+pub(super) fn micros_since_epoch(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_micros() as i64)
+        .unwrap_or_default()
+}
+
+pub(super) fn time_from_micros(micros: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_micros(micros.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{protocol::RayRequest, state::AppState};
+
+    fn request(kind: &str, text: &str, project: &str) -> RayRequest {
+        let content = match kind {
+            "exception" => json!({ "class": "RuntimeException", "message": text }),
+            _ => json!({ "values": [text] }),
+        };
+        serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{ "type": kind, "content": content }],
+            "meta": { "project_name": project }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn persists_events_beyond_retention_and_queries_them() {
+        for backend in ["sqlite", "spill", "memory"] {
+            let path = std::env::temp_dir().join(format!("raygun-store-{}", uuid::Uuid::new_v4()));
+            let spec = match backend {
+                "sqlite" => StoreSpec::Sqlite(path.clone()),
+                "spill" => StoreSpec::Spill(path.clone()),
+                _ => StoreSpec::Memory,
+            };
+            let store = spec.open(16).unwrap();
+            let state = AppState::new(1).with_store(Arc::clone(&store));
+
+            state
+                .record_request(request("log", "checkout started", "shop"))
+                .await;
+            state
+                .record_request(request("exception", "checkout failed", "shop"))
+                .await;
+            state
+                .record_request(request("log", "nightly import done", "etl"))
+                .await;
+            let label: RayRequest = serde_json::from_value(json!({
+                "uuid": "test",
+                "payloads": [{ "type": "label", "content": { "label": "cron" } }],
+                "meta": {}
+            }))
+            .unwrap();
+            state.record_request(label).await;
+            store.flush().await;
+
+            assert_eq!(state.timeline_len().await, 1);
+            let all = store.search(&StoreQuery::default()).await.unwrap();
+            assert_eq!(all.len(), 3, "{}", backend);
+            assert_eq!(all[0].event.label.as_deref(), Some("cron"), "{}", backend);
+            assert!(
+                all.iter()
+                    .all(|stored| Some(stored.session) == store.session())
+            );
+
+            let mut summaries = Vec::new();
+            for query in [
+                StoreQuery {
+                    text: Some("check".into()),
+                    ..Default::default()
+                },
+                StoreQuery {
+                    text: Some("checkout".into()),
+                    kind: Some("exception".into()),
+                    ..Default::default()
+                },
+                StoreQuery {
+                    project: Some("ETL".into()),
+                    label: Some("cron".into()),
+                    ..Default::default()
+                },
+                StoreQuery {
+                    since: Some(SystemTime::now() + Duration::from_secs(60)),
+                    ..Default::default()
+                },
+            ] {
+                let found = store.search(&query).await.unwrap();
+                summaries.push(
+                    found
+                        .iter()
+                        .map(|stored| summarize(&stored.event).summary)
+                        .collect::<Vec<_>>(),
+                );
+            }
+            assert_eq!(
+                summaries,
+                [
+                    vec!["checkout failed", "checkout started"],
+                    vec!["checkout failed"],
+                    vec!["nightly import done"],
+                    vec![],
+                ],
+                "{}",
+                backend
+            );
+
+            drop(state);
+            if spec == StoreSpec::Memory {
+                assert!(spec.open_read_only().is_err());
+                assert_eq!(store.sessions().await.unwrap()[0].events, 3);
+                continue;
+            }
+
+            // A second run appends a new session to the same file.
+            let reopened = spec.open(16).unwrap();
+            assert_ne!(reopened.session(), store.session());
+            let sessions = reopened.sessions().await.unwrap();
+            assert_eq!(sessions.len(), 1, "{}", backend);
+            assert_eq!(Some(sessions[0].id), store.session());
+            assert_eq!(sessions[0].events, 3);
+
+            let reader = spec.open_read_only().unwrap();
+            assert_eq!(reader.session(), None);
+            assert_eq!(
+                reader.search(&StoreQuery::default()).await.unwrap().len(),
+                3
+            );
+
+            drop((store, reopened, reader));
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+    }
+
+    #[test]
+    fn parses_search_queries() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_710_000_000);
+        let query = StoreQuery::parse(
+            r#"kind:exception "order failed" project:shop since:2h until:2024-03-09 App::run"#,
+            now,
+        )
+        .unwrap();
+        assert_eq!(query.kind.as_deref(), Some("exception"));
+        assert_eq!(query.project.as_deref(), Some("shop"));
+        assert_eq!(query.text.as_deref(), Some("order failed App::run"));
+        assert_eq!(query.since, Some(now - Duration::from_secs(2 * 60 * 60)));
+        // 2024-03-10T00:00:00Z: a date-only `until` covers the whole day.
+        assert_eq!(
+            query.until,
+            Some(UNIX_EPOCH + Duration::from_secs(1_710_028_800))
+        );
+
+        let query = StoreQuery::parse("since:2024-03-09T14:05 session:3", now).unwrap();
+        assert_eq!(
+            query.since,
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_993_100))
+        );
+        assert_eq!(query.session, Some(3));
+        assert_eq!(query.text, None);
+
+        assert!(StoreQuery::parse("since:yesterday", now).is_err());
+        assert!(StoreQuery::parse("until:2024-13-01", now).is_err());
+        assert!(StoreQuery::parse("label:", now).is_err());
+        assert!(StoreQuery::parse("session:abc", now).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! The spill-to-disk backend (`--store spill:<path>`): events are appended to
+//! a JSON Lines file, and queries read the whole file back. Cheaper to write
+//! than SQLite and easy to inspect or ship elsewhere; slower to search once
+//! the file grows large.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{
+    MemoryStore, SessionInfo, StoreError, StoreQuery, StoredEvent, TimelineEvent, TimelineStore,
+    micros_since_epoch, time_from_micros,
+};
+
+/// One line of the file: the start of a session, or an event recorded in
+/// one. A later line for the same event replaces the earlier ones.
+#[derive(Debug, Serialize, Deserialize)]
+struct SpillLine {
+    session: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    started_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    event: Option<TimelineEvent>,
+}
+
+enum SpillOp {
+    Write(Box<SpillLine>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// A timeline in memory with its history in a JSON Lines file.
+#[derive(Debug)]
+pub struct SpillStore {
+    timeline: MemoryStore,
+    path: PathBuf,
+    /// The session and writer of a handle that records; `None` when read-only.
+    writer: Option<(i64, mpsc::Sender<SpillOp>)>,
+}
+
+impl SpillStore {
+    /// Opens (or creates) the file at `path` and starts a new session.
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        let open_error = |source| StoreError::OpenFile {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        let session = match File::open(path) {
+            Ok(file) => last_session(file).map_err(open_error)? + 1,
+            Err(err) if err.kind() == ErrorKind::NotFound => 1,
+            Err(err) => return Err(open_error(err)),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(open_error)?;
+        let mut writer = BufWriter::new(file);
+        write_line(
+            &mut writer,
+            &SpillLine {
+                session,
+                started_at: Some(micros_since_epoch(SystemTime::now())),
+                event: None,
+            },
+        )
+        .and_then(|()| writer.flush())
+        .map_err(open_error)?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("raygun-spill".into())
+            .spawn(move || write_loop(&mut writer, receiver))
+            .map_err(StoreError::Writer)?;
+
+        Ok(Self {
+            timeline: MemoryStore::new(),
+            path: path.to_path_buf(),
+            writer: Some((session, sender)),
+        })
+    }
+
+    /// Reads an existing file for queries only; no session is started.
+    pub fn open_read_only(path: &Path) -> Self {
+        Self {
+            timeline: MemoryStore::new(),
+            path: path.to_path_buf(),
+            writer: None,
+        }
+    }
+
+    /// Queues a line for `event`, which replaces any earlier one for it.
+    fn write(&self, event: &TimelineEvent) {
+        if let Some((session, writer)) = &self.writer {
+            let _ = writer.send(SpillOp::Write(Box::new(SpillLine {
+                session: *session,
+                started_at: None,
+                event: Some(event.clone()),
+            })));
+        }
+    }
+
+    /// Reads the file on tokio's blocking pool and hands its lines to `scan`.
+    async fn scan<T: Send + 'static>(
+        &self,
+        scan: impl FnOnce(&mut dyn Iterator<Item = SpillLine>) -> T + Send + 'static,
+    ) -> Result<T, StoreError> {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || Ok(scan(&mut lines(&path)?))).await?
+    }
+}
+
+#[async_trait]
+impl TimelineStore for SpillStore {
+    fn push(&self, event: TimelineEvent) {
+        self.write(&event);
+        self.timeline.push(event);
+    }
+
+    fn insert(&self, index: usize, event: TimelineEvent) {
+        self.write(&event);
+        self.timeline.insert(index, event);
+    }
+
+    fn update(&self, update: &mut dyn FnMut(&mut TimelineEvent) -> bool) -> Vec<TimelineEvent> {
+        let changed = self.timeline.update(update);
+        changed.iter().for_each(|event| self.write(event));
+        changed
+    }
+
+    fn remove(
+        &self,
+        remove: &mut dyn FnMut(&TimelineEvent) -> bool,
+    ) -> Vec<(usize, TimelineEvent)> {
+        self.timeline.remove(remove)
+    }
+
+    fn evict(&self, retention: usize, pin_errors: bool) -> Option<TimelineEvent> {
+        self.timeline.evict(retention, pin_errors)
+    }
+
+    fn clear(&self) {
+        self.timeline.clear();
+    }
+
+    fn replace(&self, events: Vec<TimelineEvent>) {
+        self.timeline.replace(events);
+    }
+
+    fn get(&self, id: Uuid) -> Option<TimelineEvent> {
+        self.timeline.get(id)
+    }
+
+    fn last(&self) -> Option<TimelineEvent> {
+        self.timeline.last()
+    }
+
+    fn snapshot(&self) -> Vec<TimelineEvent> {
+        self.timeline.snapshot()
+    }
+
+    fn len(&self) -> usize {
+        self.timeline.len()
+    }
+
+    fn keeps_history(&self) -> bool {
+        true
+    }
+
+    fn session(&self) -> Option<i64> {
+        self.writer.as_ref().map(|(session, _)| *session)
+    }
+
+    async fn flush(&self) {
+        let Some((_, writer)) = &self.writer else {
+            return;
+        };
+        let (done, wait) = oneshot::channel();
+        if writer.send(SpillOp::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    async fn search(&self, query: &StoreQuery) -> Result<Vec<StoredEvent>, StoreError> {
+        let query = query.clone();
+        self.scan(move |lines| {
+            let mut events: Vec<(i64, TimelineEvent)> = Vec::new();
+            let mut positions = HashMap::new();
+            for line in lines {
+                let Some(event) = line.event else {
+                    continue;
+                };
+                match positions.get(&event.id) {
+                    Some(&position) => events[position] = (line.session, event),
+                    None => {
+                        positions.insert(event.id, events.len());
+                        events.push((line.session, event));
+                    }
+                }
+            }
+            query.select(events.iter().map(|(session, event)| (*session, event)))
+        })
+        .await
+    }
+
+    async fn sessions(&self) -> Result<Vec<SessionInfo>, StoreError> {
+        self.scan(|lines| {
+            let mut started = BTreeMap::new();
+            let mut events: BTreeMap<i64, HashSet<_>> = BTreeMap::new();
+            for line in lines {
+                if let Some(started_at) = line.started_at {
+                    started.insert(line.session, started_at);
+                }
+                if let Some(event) = line.event {
+                    events.entry(line.session).or_default().insert(event.id);
+                }
+            }
+            events
+                .into_iter()
+                .rev()
+                .map(|(id, events)| SessionInfo {
+                    id,
+                    started_at: time_from_micros(started.get(&id).copied().unwrap_or_default()),
+                    events: events.len(),
+                })
+                .collect()
+        })
+        .await
+    }
+}
+
+/// Every line of the file at `path` that could be read, in order.
+fn lines(path: &Path) -> Result<impl Iterator<Item = SpillLine> + use<>, StoreError> {
+    let file = File::open(path).map_err(StoreError::Read)?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| match serde_json::from_str(&line) {
+            Ok(line) => Some(line),
+            Err(err) => {
+                warn!(?err, "skipping unreadable stored event");
+                None
+            }
+        }))
+}
+
+/// The highest session number in an existing file.
+fn last_session(file: File) -> std::io::Result<i64> {
+    #[derive(Deserialize)]
+    struct Session {
+        session: i64,
+    }
+
+    let mut last = 0;
+    for line in BufReader::new(file).lines() {
+        if let Ok(line) = serde_json::from_str::<Session>(&line?) {
+            last = last.max(line.session);
+        }
+    }
+    Ok(last)
+}
+
+fn write_loop(writer: &mut BufWriter<File>, receiver: mpsc::Receiver<SpillOp>) {
+    // Write whatever has queued up before flushing.
+    while let Ok(first) = receiver.recv() {
+        let mut flushed = Vec::new();
+        let result = (|| -> std::io::Result<()> {
+            for op in std::iter::once(first).chain(receiver.try_iter()) {
+                match op {
+                    SpillOp::Write(line) => write_line(writer, &line)?,
+                    SpillOp::Flush(done) => flushed.push(done),
+                }
+            }
+            writer.flush()
+        })();
+        if let Err(err) = result {
+            warn!(?err, "failed to write events to the store");
+        }
+        for done in flushed {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn write_line(writer: &mut impl Write, line: &SpillLine) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *writer, line)?;
+    writer.write_all(b"\n")
+}
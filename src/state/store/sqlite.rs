@@ -0,0 +1,474 @@
+//! The SQLite backend (`--store sqlite:<path>`), which indexes events so
+//! queries don't have to read them all.
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, mpsc},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use rusqlite::{
+    Connection, OpenFlags, OptionalExtension, params, params_from_iter, types::Value as SqlValue,
+};
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{
+    MemoryStore, SessionInfo, StoreError, StoreQuery, StoredEvent, TimelineEvent, TimelineStore,
+    micros_since_epoch, search_text, summarize, time_from_micros,
+};
+
+/// Waits this long for a locked database before giving up on a statement.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id INTEGER PRIMARY KEY,
+        started_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS events (
+        seq INTEGER PRIMARY KEY,
+        id TEXT NOT NULL UNIQUE,
+        session_id INTEGER NOT NULL REFERENCES sessions(id),
+        received_at INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        label TEXT,
+        color TEXT,
+        project TEXT,
+        hostname TEXT,
+        summary TEXT NOT NULL,
+        event TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_received_at ON events(received_at);
+    CREATE INDEX IF NOT EXISTS events_kind ON events(kind, received_at);
+    CREATE INDEX IF NOT EXISTS events_label ON events(label COLLATE NOCASE);
+    CREATE INDEX IF NOT EXISTS events_project ON events(project COLLATE NOCASE);
+    CREATE VIRTUAL TABLE IF NOT EXISTS events_text USING fts5(body, content='');
+";
+
+enum StoreOp {
+    Upsert(Box<TimelineEvent>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// A timeline in memory with its history in a SQLite database. Writes go
+/// through a background thread so recording never waits for the disk; queries
+/// run on tokio's blocking pool.
+#[derive(Debug)]
+pub struct SqliteStore {
+    timeline: MemoryStore,
+    /// The session and writer of a handle that records; `None` when read-only.
+    writer: Option<(i64, mpsc::Sender<StoreOp>)>,
+    reader: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    /// Opens (or creates) the database at `path` and starts a new session.
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        let open_error = |source| StoreError::Open {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        let mut writer = connect(path, OpenFlags::default()).map_err(open_error)?;
+        writer.execute_batch(SCHEMA).map_err(open_error)?;
+        writer
+            .execute(
+                "INSERT INTO sessions (started_at) VALUES (?1)",
+                params![micros_since_epoch(SystemTime::now())],
+            )
+            .map_err(open_error)?;
+        let session = writer.last_insert_rowid();
+        let reader = connect(path, OpenFlags::default()).map_err(open_error)?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("raygun-store".into())
+            .spawn(move || write_loop(&mut writer, session, receiver))
+            .map_err(StoreError::Writer)?;
+
+        Ok(Self {
+            timeline: MemoryStore::new(),
+            writer: Some((session, sender)),
+            reader: Arc::new(Mutex::new(reader)),
+        })
+    }
+
+    /// Opens an existing database for queries only; no session is started.
+    pub fn open_read_only(path: &Path) -> Result<Self, StoreError> {
+        let reader =
+            connect(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|source| StoreError::Open {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        Ok(Self {
+            timeline: MemoryStore::new(),
+            writer: None,
+            reader: Arc::new(Mutex::new(reader)),
+        })
+    }
+}
+
+#[async_trait]
+impl TimelineStore for SqliteStore {
+    fn push(&self, event: TimelineEvent) {
+        self.write(&event);
+        self.timeline.push(event);
+    }
+
+    fn insert(&self, index: usize, event: TimelineEvent) {
+        self.write(&event);
+        self.timeline.insert(index, event);
+    }
+
+    fn update(&self, update: &mut dyn FnMut(&mut TimelineEvent) -> bool) -> Vec<TimelineEvent> {
+        let changed = self.timeline.update(update);
+        changed.iter().for_each(|event| self.write(event));
+        changed
+    }
+
+    fn remove(
+        &self,
+        remove: &mut dyn FnMut(&TimelineEvent) -> bool,
+    ) -> Vec<(usize, TimelineEvent)> {
+        self.timeline.remove(remove)
+    }
+
+    fn evict(&self, retention: usize, pin_errors: bool) -> Option<TimelineEvent> {
+        self.timeline.evict(retention, pin_errors)
+    }
+
+    fn clear(&self) {
+        self.timeline.clear();
+    }
+
+    fn replace(&self, events: Vec<TimelineEvent>) {
+        self.timeline.replace(events);
+    }
+
+    fn get(&self, id: Uuid) -> Option<TimelineEvent> {
+        self.timeline.get(id)
+    }
+
+    fn last(&self) -> Option<TimelineEvent> {
+        self.timeline.last()
+    }
+
+    fn snapshot(&self) -> Vec<TimelineEvent> {
+        self.timeline.snapshot()
+    }
+
+    fn len(&self) -> usize {
+        self.timeline.len()
+    }
+
+    fn keeps_history(&self) -> bool {
+        true
+    }
+
+    fn session(&self) -> Option<i64> {
+        self.writer.as_ref().map(|(session, _)| *session)
+    }
+
+    async fn flush(&self) {
+        let Some((_, writer)) = &self.writer else {
+            return;
+        };
+        let (done, wait) = oneshot::channel();
+        if writer.send(StoreOp::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+
+    async fn search(&self, query: &StoreQuery) -> Result<Vec<StoredEvent>, StoreError> {
+        let mut sql = String::from("SELECT session_id, event FROM events WHERE 1 = 1");
+        let mut values: Vec<SqlValue> = Vec::new();
+        let mut filter = |clause: &str, value: SqlValue| {
+            sql.push_str(" AND ");
+            sql.push_str(clause);
+            values.push(value);
+        };
+
+        if let Some(text) = query.text.as_deref().and_then(fts_query) {
+            filter(
+                "seq IN (SELECT rowid FROM events_text WHERE events_text MATCH ?)",
+                SqlValue::Text(text),
+            );
+        }
+        if let Some(kind) = &query.kind {
+            filter("kind = ?", SqlValue::Text(kind.clone()));
+        }
+        if let Some(label) = &query.label {
+            filter("label = ? COLLATE NOCASE", SqlValue::Text(label.clone()));
+        }
+        if let Some(project) = &query.project {
+            filter(
+                "project = ? COLLATE NOCASE",
+                SqlValue::Text(project.clone()),
+            );
+        }
+        if let Some(since) = query.since {
+            filter(
+                "received_at >= ?",
+                SqlValue::Integer(micros_since_epoch(since)),
+            );
+        }
+        if let Some(until) = query.until {
+            filter(
+                "received_at < ?",
+                SqlValue::Integer(micros_since_epoch(until)),
+            );
+        }
+        if let Some(session) = query.session {
+            filter("session_id = ?", SqlValue::Integer(session));
+        }
+        sql.push_str(" ORDER BY received_at DESC, seq DESC");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        self.read(move |reader| {
+            let mut statement = reader.prepare(&sql)?;
+            let rows = statement.query_map(params_from_iter(values), |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?;
+
+            let mut events = Vec::new();
+            for row in rows {
+                let (session, json) = row?;
+                match serde_json::from_str(&json) {
+                    Ok(event) => events.push(StoredEvent { session, event }),
+                    Err(err) => warn!(?err, "skipping unreadable stored event"),
+                }
+            }
+            Ok(events)
+        })
+        .await
+    }
+
+    async fn sessions(&self) -> Result<Vec<SessionInfo>, StoreError> {
+        self.read(|reader| {
+            let mut statement = reader.prepare(
+                "SELECT sessions.id, sessions.started_at, COUNT(events.seq)
+                 FROM sessions JOIN events ON events.session_id = sessions.id
+                 GROUP BY sessions.id ORDER BY sessions.id DESC",
+            )?;
+            let sessions = statement
+                .query_map([], |row| {
+                    Ok(SessionInfo {
+                        id: row.get(0)?,
+                        started_at: time_from_micros(row.get(1)?),
+                        events: row.get::<_, i64>(2)? as usize,
+                    })
+                })?
+                .collect::<Result<_, _>>()?;
+            Ok(sessions)
+        })
+        .await
+    }
+}
+
+impl SqliteStore {
+    /// Queues `event` to be inserted, or to replace the row stored for it.
+    fn write(&self, event: &TimelineEvent) {
+        if let Some((_, writer)) = &self.writer {
+            let _ = writer.send(StoreOp::Upsert(Box::new(event.clone())));
+        }
+    }
+
+    /// Runs `query` on the reader connection on tokio's blocking pool.
+    async fn read<T: Send + 'static>(
+        &self,
+        query: impl FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+    ) -> Result<T, StoreError> {
+        let reader = Arc::clone(&self.reader);
+        tokio::task::spawn_blocking(move || {
+            let reader = reader.lock().unwrap_or_else(|err| err.into_inner());
+            query(&reader)
+        })
+        .await?
+        .map_err(StoreError::from)
+    }
+}
+
+fn connect(path: &Path, flags: OpenFlags) -> rusqlite::Result<Connection> {
+    let connection = Connection::open_with_flags(path, flags)?;
+    connection.busy_timeout(BUSY_TIMEOUT)?;
+    if !flags.contains(OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        connection.pragma_update(None, "journal_mode", "wal")?;
+    }
+    Ok(connection)
+}
+
+fn write_loop(connection: &mut Connection, session: i64, receiver: mpsc::Receiver<StoreOp>) {
+    // Write whatever has queued up in one transaction.
+    while let Ok(first) = receiver.recv() {
+        let mut flushed = Vec::new();
+        let result = (|| -> rusqlite::Result<()> {
+            let transaction = connection.transaction()?;
+            for op in std::iter::once(first).chain(receiver.try_iter()) {
+                match op {
+                    StoreOp::Upsert(event) => upsert(&transaction, session, &event)?,
+                    StoreOp::Flush(done) => flushed.push(done),
+                }
+            }
+            transaction.commit()
+        })();
+        if let Err(err) = result {
+            warn!(?err, "failed to write events to the store");
+        }
+        for done in flushed {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn upsert(connection: &Connection, session: i64, event: &TimelineEvent) -> rusqlite::Result<()> {
+    let entry = summarize(event);
+    let json = serde_json::to_string(event)
+        .map_err(|err| rusqlite::Error::ToSqlConversionFailure(Box::new(err)))?;
+    let id = event.id.to_string();
+
+    let meta = |key: &str| {
+        event
+            .request
+            .meta
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    let body = search_text(event, &entry.summary);
+
+    let existing: Option<(i64, String)> = connection
+        .query_row(
+            "SELECT seq, event FROM events WHERE id = ?1",
+            [&id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    if let Some((seq, previous)) = existing {
+        connection.execute(
+            "UPDATE events SET received_at = ?2, kind = ?3, label = ?4, color = ?5,
+                project = ?6, hostname = ?7, summary = ?8, event = ?9
+             WHERE seq = ?1",
+            params![
+                seq,
+                micros_since_epoch(event.received_at),
+                entry.kind,
+                event.label,
+                event.color,
+                meta("project_name"),
+                meta("hostname"),
+                entry.summary,
+                json,
+            ],
+        )?;
+        // The text index keeps no content, so removing the old row takes the
+        // text it was indexed with.
+        if let Ok(previous) = serde_json::from_str::<TimelineEvent>(&previous) {
+            let previous_body = search_text(&previous, &summarize(&previous).summary);
+            connection.execute(
+                "INSERT INTO events_text (events_text, rowid, body) VALUES ('delete', ?1, ?2)",
+                params![seq, previous_body],
+            )?;
+        }
+        connection.execute(
+            "INSERT INTO events_text (rowid, body) VALUES (?1, ?2)",
+            params![seq, body],
+        )?;
+        return Ok(());
+    }
+
+    connection.execute(
+        "INSERT INTO events
+            (id, session_id, received_at, kind, label, color, project, hostname, summary, event)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            id,
+            session,
+            micros_since_epoch(event.received_at),
+            entry.kind,
+            event.label,
+            event.color,
+            meta("project_name"),
+            meta("hostname"),
+            entry.summary,
+            json,
+        ],
+    )?;
+    connection.execute(
+        "INSERT INTO events_text (rowid, body) VALUES (?1, ?2)",
+        params![connection.last_insert_rowid(), body],
+    )?;
+    Ok(())
+}
+
+/// Turns free text into an FTS5 query matching every word as a prefix.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn log_event(text: &str, project: &str) -> TimelineEvent {
+        let request = serde_json::from_value(json!({
+            "uuid": "test",
+            "payloads": [{ "type": "log", "content": { "values": [text] } }],
+            "meta": { "project_name": project }
+        }))
+        .unwrap();
+        TimelineEvent::new(request, None)
+    }
+
+    #[tokio::test]
+    async fn updates_rewrite_every_column_and_the_text_index() {
+        let path = std::env::temp_dir().join(format!("raygun-store-{}", uuid::Uuid::new_v4()));
+        let store = SqliteStore::open(&path).unwrap();
+
+        store.push(log_event("checkout started", "shop"));
+        let replacement = log_event("nightly import", "etl");
+        store.update(&mut |event| {
+            *event = TimelineEvent {
+                id: event.id,
+                label: Some("cron".into()),
+                ..replacement.clone()
+            };
+            true
+        });
+        store.flush().await;
+
+        let search = |query: StoreQuery| {
+            let store = &store;
+            async move { store.search(&query).await.unwrap().len() }
+        };
+        let text = |text: &str| StoreQuery {
+            text: Some(text.into()),
+            ..Default::default()
+        };
+        assert_eq!(search(text("checkout")).await, 0);
+        assert_eq!(search(text("nightly")).await, 1);
+        let project = |project: &str| StoreQuery {
+            project: Some(project.into()),
+            ..Default::default()
+        };
+        assert_eq!(search(project("shop")).await, 0);
+        assert_eq!(search(project("etl")).await, 1);
+
+        drop(store);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+}
@@ -0,0 +1,547 @@
+//! What the timeline shows for an event: its kind, a one-line summary and its
+//! label. The store indexes events under the same kind and summary, so a
+//! history search finds them by what the timeline showed.
+
+use html_escape::decode_html_entities;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{Number, Value};
+
+use super::TimelineEvent;
+use crate::{
+    protocol::{Origin, Payload, PayloadKind},
+    ui::{detail, format::ContentFormat, text},
+};
+
+#[derive(Debug, Clone)]
+pub struct EventSummary {
+    /// Timeline kind (`log`, `exception`, `query`, ...).
+    pub kind: String,
+    /// One line describing the event, after the name of its screen.
+    pub summary: String,
+    pub label: Option<String>,
+    /// Format badge of the content, unless it is plain text or the kind
+    /// already says it.
+    pub format: Option<ContentFormat>,
+}
+
+pub fn summarize(event: &TimelineEvent) -> EventSummary {
+    let aggregated = aggregated_log_payload(event);
+    let payload_ref = aggregated
+        .as_ref()
+        .map(|payload| payload as &Payload)
+        .or_else(|| primary_payload(event));
+
+    let mut label = event.label.clone().or_else(|| {
+        aggregated
+            .as_ref()
+            .and_then(|payload| payload.content_string("label"))
+            .map(|label| label.to_string())
+    });
+
+    let format = payload_ref
+        .and_then(detail::content_format)
+        .filter(|format| *format != ContentFormat::Plain);
+    let (kind, mut summary) = if let Some(payload) = payload_ref {
+        if label.is_none() {
+            label = payload
+                .content_string("label")
+                .map(|label| label.trim().to_string())
+                .filter(|label| !label.is_empty());
+        }
+
+        (payload_kind_label(payload), payload_summary(payload))
+    } else {
+        ("empty".to_string(), "Request without payloads".to_string())
+    };
+
+    if label.as_deref().map(is_default_html_label).unwrap_or(false) {
+        label = None;
+    }
+
+    if let Some(screen) = event.screen.as_deref() {
+        summary = format!("{} | {}", screen, summary);
+    }
+
+    // `[html]` needs no `HTML` badge.
+    let format = format.filter(|format| !format.badge().eq_ignore_ascii_case(&kind));
+
+    EventSummary {
+        kind,
+        summary,
+        label,
+        format,
+    }
+}
+
+pub(crate) fn primary_payload(event: &TimelineEvent) -> Option<&Payload> {
+    event
+        .request
+        .payloads
+        .iter()
+        .find(|payload| is_primary_payload_kind(&payload.kind))
+        .or_else(|| event.request.payloads.first())
+}
+
+fn is_primary_payload_kind(kind: &PayloadKind) -> bool {
+    !matches!(kind, PayloadKind::Color | PayloadKind::Label)
+}
+
+pub(crate) fn aggregated_log_payload(event: &TimelineEvent) -> Option<Payload> {
+    use serde_json::Map;
+
+    let mut values: Vec<Value> = Vec::new();
+    let mut label: Option<String> = None;
+    let mut meta_snapshot: Option<Value> = None;
+    let mut origin_snapshot: Option<&Origin> = None;
+
+    for payload in &event.request.payloads {
+        match payload.kind {
+            PayloadKind::Log => {
+                if origin_snapshot.is_none() {
+                    origin_snapshot = payload.origin.as_ref();
+                }
+
+                if let Some(object) = payload.content_object() {
+                    if let Some(array) = object.get("values").and_then(|value| value.as_array()) {
+                        values.extend(array.iter().cloned());
+                    }
+
+                    if label.is_none()
+                        && let Some(found) = object
+                            .get("label")
+                            .and_then(|value| value.as_str())
+                            .map(|text| text.trim())
+                            .filter(|text| !text.is_empty())
+                        && !is_default_html_label(found)
+                    {
+                        label = Some(found.to_string());
+                    }
+
+                    if meta_snapshot.is_none()
+                        && let Some(meta) = object.get("meta")
+                    {
+                        meta_snapshot = Some(meta.clone());
+                    }
+                }
+            }
+            PayloadKind::Label if label.is_none() => {
+                label = payload
+                    .content_object()
+                    .and_then(|map| map.get("label"))
+                    .and_then(|value| value.as_str())
+                    .map(|text| text.trim().to_string())
+                    .filter(|text| !text.is_empty())
+                    .filter(|text| !is_default_html_label(text));
+            }
+            _ => {}
+        }
+    }
+
+    // Only merge when logs are present; a label next to e.g. a `json_string`
+    // payload must not replace that payload's own renderer.
+    let has_log = event
+        .request
+        .payloads
+        .iter()
+        .any(|payload| payload.kind == PayloadKind::Log);
+    if !has_log || (values.is_empty() && label.is_none()) {
+        return None;
+    }
+
+    if label.is_none() {
+        label = event
+            .request
+            .payloads
+            .iter()
+            .find_map(|payload| payload.content_string("label"))
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .filter(|text| !is_default_html_label(text));
+    }
+
+    let mut content = Map::new();
+    content.insert("values".to_string(), Value::Array(values));
+    if let Some(label_value) = label.clone().filter(|label| !is_default_html_label(label)) {
+        content.insert("label".to_string(), Value::String(label_value));
+    }
+
+    if let Some(meta) = meta_snapshot {
+        content.insert("meta".to_string(), meta);
+    }
+
+    let mut root = Map::new();
+    root.insert("type".to_string(), Value::String("log".to_string()));
+    root.insert("content".to_string(), Value::Object(content));
+
+    if let Some(origin) = origin_snapshot {
+        let mut origin_map = Map::new();
+        if let Some(file) = &origin.file {
+            origin_map.insert("file".to_string(), Value::String(file.clone()));
+        }
+        if let Some(line) = origin.line_number {
+            origin_map.insert("line_number".to_string(), Value::Number(Number::from(line)));
+        }
+        if let Some(host) = &origin.hostname {
+            origin_map.insert("hostname".to_string(), Value::String(host.clone()));
+        }
+        root.insert("origin".to_string(), Value::Object(origin_map));
+    }
+
+    serde_json::from_value(Value::Object(root)).ok()
+}
+
+pub(crate) fn payload_kind_label(payload: &Payload) -> String {
+    match &payload.kind {
+        PayloadKind::Log => "log".to_string(),
+        PayloadKind::Custom => custom_payload_type(payload).unwrap_or_else(|| "custom".to_string()),
+        PayloadKind::CreateLock => "create_lock".to_string(),
+        PayloadKind::ClearAll => "clear_all".to_string(),
+        PayloadKind::Hide => "hide".to_string(),
+        PayloadKind::ShowApp => "show_app".to_string(),
+        PayloadKind::ShowBrowser => "show_browser".to_string(),
+        PayloadKind::Notify => "notify".to_string(),
+        PayloadKind::Separator => "separator".to_string(),
+        PayloadKind::Exception => "exception".to_string(),
+        PayloadKind::Table => "table".to_string(),
+        PayloadKind::Text => "text".to_string(),
+        PayloadKind::Image => "image".to_string(),
+        PayloadKind::JsonString => "json_string".to_string(),
+        PayloadKind::DecodedJson => "decoded_json".to_string(),
+        PayloadKind::Boolean => "boolean".to_string(),
+        PayloadKind::Size => "size".to_string(),
+        PayloadKind::Color => "color".to_string(),
+        PayloadKind::Label => "label".to_string(),
+        PayloadKind::Trace => "trace".to_string(),
+        PayloadKind::Caller => "caller".to_string(),
+        PayloadKind::Measure => "measure".to_string(),
+        PayloadKind::PhpInfo => "phpinfo".to_string(),
+        PayloadKind::NewScreen => "new_screen".to_string(),
+        PayloadKind::Remove => "remove".to_string(),
+        PayloadKind::HideApp => "hide_app".to_string(),
+        PayloadKind::Ban => "ban".to_string(),
+        PayloadKind::Charles => "charles".to_string(),
+        PayloadKind::ExecutedQuery => "query".to_string(),
+        PayloadKind::ApplicationLog => "application_log".to_string(),
+        PayloadKind::JobEvent => "job".to_string(),
+        PayloadKind::Event => "event".to_string(),
+        PayloadKind::Unknown(value) => value.as_str().to_string(),
+    }
+}
+
+fn payload_summary(payload: &Payload) -> String {
+    match &payload.kind {
+        PayloadKind::Log => summarize_log(payload).unwrap_or_else(|| "log payload".to_string()),
+        PayloadKind::Custom => summarize_custom(payload),
+        PayloadKind::Boolean => {
+            if let Some(scalar) = detail::scalar_content(payload) {
+                return clip(&scalar.summary(), SUMMARY_MAX_CHARS);
+            }
+
+            let label = payload.content_string("label");
+            let body = payload
+                .content_object()
+                .and_then(|map| map.get("content"))
+                .map(value_preview)
+                .unwrap_or_else(|| "custom payload".to_string());
+
+            match label {
+                Some(label) if !label.is_empty() => {
+                    clip(&format!("{}: {}", label, body), SUMMARY_MAX_CHARS)
+                }
+                _ => clip(&body, SUMMARY_MAX_CHARS),
+            }
+        }
+        PayloadKind::CreateLock => {
+            let name = payload.content_string("name").unwrap_or("unknown");
+            format!("create lock `{}`", name)
+        }
+        PayloadKind::ClearAll => "clear all".to_string(),
+        PayloadKind::Hide => "hide payload".to_string(),
+        PayloadKind::ShowApp => "show app".to_string(),
+        PayloadKind::ShowBrowser => "show browser".to_string(),
+        PayloadKind::Notify => payload
+            .content_string("text")
+            .map(|text| clip(text, SUMMARY_MAX_CHARS))
+            .unwrap_or_else(|| "notification".to_string()),
+        PayloadKind::Separator => "separator".to_string(),
+        PayloadKind::Exception => payload
+            .content_object()
+            .and_then(|map| map.get("message"))
+            .map(value_preview)
+            .unwrap_or_else(|| "exception".to_string()),
+        PayloadKind::Table => payload
+            .content_string("label")
+            .map(|text| clip(text, SUMMARY_MAX_CHARS))
+            .unwrap_or_else(|| "table".to_string()),
+        PayloadKind::Text => payload
+            .content_string("content")
+            .map(|text| clip(text, SUMMARY_MAX_CHARS))
+            .unwrap_or_else(|| "text".to_string()),
+        PayloadKind::Image => "image".to_string(),
+        PayloadKind::JsonString => match detail::json_string_error(payload) {
+            Some(err) => format!(
+                "invalid json string (line {}, column {})",
+                err.line(),
+                err.column()
+            ),
+            None => "json string".to_string(),
+        },
+        PayloadKind::DecodedJson => payload
+            .content_object()
+            .map(|map| {
+                let json = Value::Object(map.clone()).to_string();
+                clip(&flatten(&json), SUMMARY_MAX_CHARS)
+            })
+            .unwrap_or_else(|| "json".to_string()),
+        PayloadKind::Size => match detail::size_bytes(payload) {
+            Some(bytes) => format!("size {}", crate::format::bytes(bytes)),
+            None => payload
+                .content_string("size")
+                .map(|value| format!("size {}", value))
+                .unwrap_or_else(|| "size".to_string()),
+        },
+        PayloadKind::Color => payload
+            .content_string("color")
+            .map(|value| format!("color {}", value))
+            .unwrap_or_else(|| "color".to_string()),
+        PayloadKind::Label => payload
+            .content_string("label")
+            .map(|value| format!("label {}", value))
+            .unwrap_or_else(|| "label".to_string()),
+        PayloadKind::Trace => "stack trace".to_string(),
+        PayloadKind::Caller => "caller".to_string(),
+        PayloadKind::Measure => payload
+            .content_object()
+            .and_then(|map| map.get("name"))
+            .map(value_preview)
+            .map(|name| format!("measure {}", name))
+            .unwrap_or_else(|| "measure".to_string()),
+        PayloadKind::PhpInfo => "phpinfo".to_string(),
+        PayloadKind::NewScreen => payload
+            .content_string("name")
+            .map(|name| format!("new screen `{}`", name))
+            .unwrap_or_else(|| "new screen".to_string()),
+        PayloadKind::Remove => "remove".to_string(),
+        PayloadKind::HideApp => "hide app".to_string(),
+        PayloadKind::Ban => "ban".to_string(),
+        PayloadKind::Charles => "charles".to_string(),
+        PayloadKind::ExecutedQuery => payload
+            .content_string("sql")
+            .map(|sql| {
+                clip(
+                    &flatten(&detail::query_sql(payload, sql)),
+                    SUMMARY_MAX_CHARS,
+                )
+            })
+            .unwrap_or_else(|| "query".to_string()),
+        PayloadKind::ApplicationLog => {
+            let message = payload
+                .content_object()
+                .and_then(|map| map.get("value").or_else(|| map.get("message")))
+                .map(value_preview)
+                .unwrap_or_else(|| "application log".to_string());
+            match payload.log_level() {
+                Some(level) => clip(
+                    &format!("{}: {}", level.as_str(), message),
+                    SUMMARY_MAX_CHARS,
+                ),
+                None => message,
+            }
+        }
+        PayloadKind::JobEvent => {
+            let event = payload
+                .content_string("event_name")
+                .unwrap_or("job event")
+                .to_string();
+            match detail::job_class(payload) {
+                Some(class) => clip(&format!("{} {}", event, class), SUMMARY_MAX_CHARS),
+                None => event,
+            }
+        }
+        PayloadKind::Event => payload
+            .content_string("name")
+            .map(|name| clip(name, SUMMARY_MAX_CHARS))
+            .unwrap_or_else(|| "event".to_string()),
+        PayloadKind::Unknown(name) => format!("{} payload", name),
+    }
+}
+
+fn custom_payload_type(payload: &Payload) -> Option<String> {
+    let raw_label = payload
+        .content_string("label")
+        .map(|label| label.trim())
+        .filter(|label| !label.is_empty());
+
+    if let Some(label) = raw_label {
+        if label.eq_ignore_ascii_case("image") {
+            return Some("image".to_string());
+        }
+        if label.eq_ignore_ascii_case("json") {
+            return Some("json".to_string());
+        }
+        if is_default_html_label(label) {
+            return Some("html".to_string());
+        }
+        return Some(label.to_string());
+    }
+
+    if let Some(content) = payload
+        .content_object()
+        .and_then(|map| map.get("content"))
+        .and_then(|value| value.as_str())
+    {
+        if contains_image_tag(content) {
+            return Some("image".to_string());
+        }
+        if contains_sf_dump(content) {
+            return Some("json".to_string());
+        }
+        if looks_like_html_snippet(content) {
+            return Some("html".to_string());
+        }
+        if looks_like_json_snippet(content) {
+            return Some("json".to_string());
+        }
+    }
+
+    None
+}
+
+fn summarize_custom(payload: &Payload) -> String {
+    if let Some(scalar) = detail::scalar_content(payload) {
+        return clip(&scalar.summary(), SUMMARY_MAX_CHARS);
+    }
+
+    let type_hint = custom_payload_type(payload);
+
+    let content_value = payload.content_object().and_then(|map| map.get("content"));
+
+    if type_hint.as_deref() == Some("image") {
+        let src = content_value
+            .and_then(|value| value.as_str())
+            .and_then(extract_image_src)
+            .or_else(|| content_value.and_then(|value| value.as_str()))
+            .unwrap_or("image payload");
+        return clip(&format!("image: {}", src), SUMMARY_MAX_CHARS);
+    }
+
+    if type_hint.as_deref() == Some("json") {
+        return payload
+            .content_string("label")
+            .map(|label| clip(label, SUMMARY_MAX_CHARS))
+            .unwrap_or_else(|| "json payload".to_string());
+    }
+
+    let body = content_value
+        .map(|value| match (value, type_hint.as_deref()) {
+            (Value::String(text), Some("html")) => strip_html_tags(text),
+            (other, _) => value_preview(other),
+        })
+        .unwrap_or_else(|| "custom payload".to_string());
+
+    match type_hint.as_deref() {
+        Some("html") => clip(&body, SUMMARY_MAX_CHARS),
+        Some(other) => clip(&format!("{}: {}", other, body), SUMMARY_MAX_CHARS),
+        None => clip(&body, SUMMARY_MAX_CHARS),
+    }
+}
+
+fn summarize_log(payload: &Payload) -> Option<String> {
+    let meta_clipboard = payload
+        .content_object()
+        .and_then(|map| map.get("meta"))
+        .and_then(|meta| meta.as_array())
+        .and_then(|meta| meta.first())
+        .and_then(|entry| entry.get("clipboard_data"))
+        .and_then(|value| value.as_str())
+        .map(flatten);
+
+    if let Some(clipboard) = meta_clipboard
+        && !clipboard.is_empty()
+    {
+        return Some(clip(&clipboard, SUMMARY_MAX_CHARS));
+    }
+
+    payload
+        .content_object()
+        .and_then(|map| map.get("values"))
+        .and_then(|values| values.as_array())
+        .and_then(|values| {
+            let mut previews: Vec<String> = values.iter().map(value_preview).collect();
+            previews.retain(|value| !value.is_empty());
+            if previews.is_empty() {
+                None
+            } else {
+                let joined = previews.join(" | ");
+                Some(clip(&joined, SUMMARY_MAX_CHARS))
+            }
+        })
+}
+
+pub(crate) fn value_preview(value: &Value) -> String {
+    match value {
+        Value::String(text) => clip(&flatten(text), SUMMARY_MAX_CHARS),
+        Value::Bool(boolean) => boolean.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Null => "null".to_string(),
+        Value::Array(_) | Value::Object(_) => clip(&flatten(&value.to_string()), SUMMARY_MAX_CHARS),
+    }
+}
+
+/// Upper bound for timeline summaries; the timeline clips them further to the
+/// width of the pane when rendering.
+pub(crate) const SUMMARY_MAX_CHARS: usize = 240;
+
+pub(crate) fn clip(text: &str, max_chars: usize) -> String {
+    text::clip(text, max_chars, "…")
+}
+
+pub(crate) fn flatten(text: &str) -> String {
+    let decoded = decode_html_entities(text).into_owned();
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+static HTML_SCRIPT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<script[^>]*>.*?</script>").unwrap());
+
+static HTML_IMG_SRC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r##"(?is)<img[^>]*src\s*=\s*['"]([^'"]+)['"]"##).unwrap());
+
+pub(crate) fn is_default_html_label(label: &str) -> bool {
+    label.trim().eq_ignore_ascii_case("html")
+}
+
+fn looks_like_html_snippet(text: &str) -> bool {
+    let trimmed = text.trim();
+    trimmed.starts_with('<') && trimmed.contains('>')
+}
+
+fn looks_like_json_snippet(text: &str) -> bool {
+    let trimmed = text.trim();
+    (trimmed.starts_with('{') && trimmed.ends_with('}'))
+        || (trimmed.starts_with('[') && trimmed.ends_with(']'))
+}
+
+fn contains_image_tag(html: &str) -> bool {
+    HTML_IMG_SRC_RE.is_match(html)
+}
+
+fn extract_image_src(html: &str) -> Option<&str> {
+    HTML_IMG_SRC_RE
+        .captures(html)
+        .and_then(|capture| capture.get(1))
+        .map(|m| m.as_str())
+}
+
+fn strip_html_tags(text: &str) -> String {
+    let without_script = HTML_SCRIPT_RE.replace_all(text, "");
+    let stripped = HTML_TAG_RE.replace_all(&without_script, " ").into_owned();
+    flatten(stripped.trim())
+}
+
+fn contains_sf_dump(text: &str) -> bool {
+    text.contains("sf-dump")
+}
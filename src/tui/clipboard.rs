@@ -0,0 +1,13 @@
+use std::io::{self, Write};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// Copies `text` to the system clipboard using the OSC 52 escape sequence.
+///
+/// Works over SSH and inside tmux (with `set-clipboard on`) because the
+/// terminal emulator, not Raygun, owns the clipboard.
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
@@ -0,0 +1,258 @@
+//! The details pane's lines wrapped to the pane width ahead of drawing. The
+//! wrapped rows only change with the event, the collapsed lines or the width,
+//! so the app keeps them between frames and each frame styles and draws just
+//! the rows on screen instead of rewrapping a tall payload from the top.
+
+use std::collections::HashSet;
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use unicode_segmentation::UnicodeSegmentation;
+use uuid::Uuid;
+
+use super::style_for_segment;
+use crate::ui::detail::{self, DetailViewModel};
+
+/// One screen row of the details pane's content.
+#[derive(Debug, Clone)]
+pub struct DetailRow {
+    /// Position of the row's line among the visible lines, which is what the
+    /// detail cursor counts.
+    pub position: usize,
+    pub line: Line<'static>,
+}
+
+/// What cached rows were built from. Cheap to compare, so telling whether
+/// the rows still fit takes no walk over the detail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetailRowsKey {
+    pub event: Uuid,
+    /// Changes whenever the detail the rows wrap is rebuilt.
+    pub build: u64,
+    /// Changes whenever a line of the event's detail is collapsed or expanded.
+    pub collapse_generation: u64,
+    pub width: u16,
+}
+
+/// The visible detail lines wrapped to `width` columns.
+#[derive(Debug, Clone)]
+pub struct DetailRows {
+    key: Option<DetailRowsKey>,
+    width: u16,
+    rows: Vec<DetailRow>,
+}
+
+impl DetailRows {
+    /// Rows to keep between frames, current for as long as `key` is.
+    pub fn keyed(
+        key: DetailRowsKey,
+        detail: &DetailViewModel,
+        collapsed: Option<&HashSet<usize>>,
+    ) -> Self {
+        Self {
+            key: Some(key),
+            ..Self::new(detail, collapsed, key.width)
+        }
+    }
+
+    pub fn new(detail: &DetailViewModel, collapsed: Option<&HashSet<usize>>, width: u16) -> Self {
+        let (visible, has_children) = detail::visible_indices_with_children(detail, collapsed);
+        let indent_style = Style::default().fg(Color::DarkGray);
+        let mut rows = Vec::new();
+        for (position, &index) in visible.iter().enumerate() {
+            let line = &detail.lines[index];
+            let icon = match (has_children[index], is_collapsed(collapsed, index)) {
+                (true, true) => "+ ",
+                (true, false) => "- ",
+                (false, _) => "  ",
+            };
+            let mut spans = Vec::with_capacity(line.segments.len() + 2);
+            if line.indent > 0 {
+                spans.push(Span::styled("  ".repeat(line.indent), indent_style));
+            }
+            spans.push(Span::styled(icon, indent_style));
+            spans.extend(
+                line.segments
+                    .iter()
+                    .map(|segment| Span::styled(segment.text.clone(), style_for_segment(segment))),
+            );
+            rows.extend(
+                wrap_line(&Line::from(spans), width)
+                    .into_iter()
+                    .map(|line| DetailRow { position, line }),
+            );
+        }
+
+        Self {
+            key: None,
+            width,
+            rows,
+        }
+    }
+
+    pub fn is_current(&self, key: &DetailRowsKey) -> bool {
+        self.key.as_ref() == Some(key)
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn rows(&self) -> &[DetailRow] {
+        &self.rows
+    }
+}
+
+fn is_collapsed(collapsed: Option<&HashSet<usize>>, index: usize) -> bool {
+    collapsed.is_some_and(|set| set.contains(&index))
+}
+
+/// `line` broken into rows of at most `width` columns, after the last space
+/// that fits when there is one. Like the paragraph wrapping it replaces,
+/// spaces are kept and an empty line still takes a row.
+pub(super) fn wrap_line(line: &Line<'_>, width: u16) -> Vec<Line<'static>> {
+    let width = usize::from(width.max(1));
+    let graphemes: Vec<(&str, Style, usize)> = line
+        .spans
+        .iter()
+        .flat_map(|span| {
+            span.content
+                .graphemes(true)
+                .map(move |symbol| (symbol, span.style, Span::raw(symbol).width()))
+        })
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut row_width = 0;
+    let mut last_space = None;
+    for (index, &(symbol, _, symbol_width)) in graphemes.iter().enumerate() {
+        if row_width + symbol_width > width && index > start {
+            let end = match last_space {
+                Some(space) if space + 1 < index => space + 1,
+                _ => index,
+            };
+            rows.push(row(&graphemes[start..end]));
+            row_width = graphemes[end..index]
+                .iter()
+                .map(|(_, _, width)| width)
+                .sum();
+            start = end;
+            last_space = None;
+        }
+        if symbol.trim().is_empty() {
+            last_space = Some(index);
+        }
+        row_width += symbol_width;
+    }
+    rows.push(row(&graphemes[start..]));
+    rows
+}
+
+/// Graphemes joined back into one span per run of equal style.
+fn row(graphemes: &[(&str, Style, usize)]) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    for &(symbol, style, _) in graphemes {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(symbol),
+            _ => spans.push(Span::styled(symbol.to_string(), style)),
+        }
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::detail::{DetailLine, DetailSegment, SegmentStyle};
+
+    fn text(line: &Line<'_>) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn wraps_at_spaces_and_stays_current_until_the_key_changes() {
+        let wrapped = wrap_line(&Line::from("alpha beta gamma"), 11);
+        assert_eq!(
+            wrapped.iter().map(text).collect::<Vec<_>>(),
+            ["alpha beta ", "gamma"]
+        );
+        let hard = wrap_line(&Line::from("abcdefgh"), 3);
+        assert_eq!(
+            hard.iter().map(text).collect::<Vec<_>>(),
+            ["abc", "def", "gh"]
+        );
+        assert_eq!(wrap_line(&Line::default(), 10).len(), 1);
+
+        let detail = DetailViewModel {
+            header: String::new(),
+            footer: String::new(),
+            lines: vec![
+                DetailLine {
+                    indent: 0,
+                    segments: vec![DetailSegment {
+                        text: "user: {".to_string(),
+                        style: SegmentStyle::Key,
+                    }],
+                },
+                DetailLine {
+                    indent: 1,
+                    segments: vec![DetailSegment {
+                        text: "name: Ada Lovelace".to_string(),
+                        style: SegmentStyle::String,
+                    }],
+                },
+            ],
+            origins: Default::default(),
+            focus_line: None,
+        };
+        let key = DetailRowsKey {
+            event: Uuid::nil(),
+            build: 0,
+            collapse_generation: 0,
+            width: 12,
+        };
+        let rows = DetailRows::keyed(key, &detail, None);
+        let lines: Vec<(usize, String)> = rows
+            .rows()
+            .iter()
+            .map(|row| (row.position, text(&row.line)))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (0, "- user: {".to_string()),
+                (1, "    name: ".to_string()),
+                (1, "Ada Lovelace".to_string()),
+            ]
+        );
+
+        assert!(rows.is_current(&key));
+        for changed in [
+            DetailRowsKey { build: 1, ..key },
+            DetailRowsKey {
+                collapse_generation: 1,
+                ..key
+            },
+            DetailRowsKey { width: 20, ..key },
+            DetailRowsKey {
+                event: Uuid::new_v4(),
+                ..key
+            },
+        ] {
+            assert!(!rows.is_current(&changed));
+        }
+        assert!(!DetailRows::new(&detail, None, 12).is_current(&key));
+
+        let collapsed = HashSet::from([0]);
+        assert_eq!(
+            DetailRows::new(&detail, Some(&collapsed), 12).rows().len(),
+            1
+        );
+    }
+}
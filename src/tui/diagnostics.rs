@@ -0,0 +1,97 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+};
+
+use crate::protocol::{IssueSeverity, ProtocolIssue};
+
+/// One inspected request, pre-formatted for the diagnostics panel.
+#[derive(Debug, Clone)]
+pub struct DiagnosticEntry {
+    pub age: String,
+    pub request_uuid: Option<String>,
+    pub rejected: bool,
+    pub issues: Vec<ProtocolIssue>,
+}
+
+/// Diagnostics panel state handed to the renderer, newest entry first.
+#[derive(Debug, Clone)]
+pub struct DiagnosticsView {
+    pub entries: Vec<DiagnosticEntry>,
+    pub strict_protocol: bool,
+    pub scroll: usize,
+}
+
+pub(super) fn render_diagnostics_overlay(
+    frame: &mut Frame<'_>,
+    diagnostics: &DiagnosticsView,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+
+    if !diagnostics.strict_protocol {
+        lines.push(Line::from(Span::styled(
+            "Protocol checks are off. Restart with `--strict-protocol` to validate incoming requests.",
+            muted,
+        )));
+    } else if diagnostics.entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No protocol issues so far.",
+            muted,
+        )));
+    }
+
+    for entry in &diagnostics.entries {
+        let (status, status_style) = if entry.rejected {
+            ("REJECTED", Style::default().fg(Color::LightRed))
+        } else {
+            ("accepted", Style::default().fg(Color::Yellow))
+        };
+        lines.push(Line::from(vec![
+            Span::styled(status, status_style.add_modifier(Modifier::BOLD)),
+            Span::raw(" "),
+            Span::raw(
+                entry
+                    .request_uuid
+                    .clone()
+                    .unwrap_or_else(|| "<no uuid>".to_string()),
+            ),
+            Span::styled(format!(" · {}", entry.age), muted),
+        ]));
+        for issue in &entry.issues {
+            let (marker, style) = match issue.severity {
+                IssueSeverity::Error => ("error", Style::default().fg(Color::LightRed)),
+                IssueSeverity::Warning => ("warning", Style::default().fg(Color::Yellow)),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}: ", marker), style),
+                Span::styled(issue.path.clone(), Style::default().fg(Color::Cyan)),
+                Span::raw(format!(" {}", issue.message)),
+            ]));
+        }
+        lines.push(Line::raw(""));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((diagnostics.scroll.min(u16::MAX as usize) as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Protocol Diagnostics ({} or Esc to close)",
+                    close_hint
+                ))
+                .padding(Padding::uniform(1))
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+    frame.render_widget(paragraph, area);
+}
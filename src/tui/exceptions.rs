@@ -0,0 +1,105 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+/// Exceptions of one class whose messages differ only in their numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionRow {
+    pub class: String,
+    /// Message of the newest exception in the group.
+    pub message: String,
+    pub count: usize,
+    /// How long ago the oldest and newest exceptions arrived, e.g. `5m 02s ago`.
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+/// Exceptions overlay state handed to the renderer, most frequent first.
+#[derive(Debug, Clone)]
+pub struct ExceptionsView {
+    pub rows: Vec<ExceptionRow>,
+    pub cursor: usize,
+}
+
+pub(super) fn render_exceptions_overlay(
+    frame: &mut Frame<'_>,
+    view: &ExceptionsView,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+
+    if view.rows.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No exceptions in the timeline yet.",
+            muted,
+        )));
+    } else {
+        let total: usize = view.rows.iter().map(|row| row.count).sum();
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{} exception{} in {} group{}",
+                total,
+                if total == 1 { "" } else { "s" },
+                view.rows.len(),
+                if view.rows.len() == 1 { "" } else { "s" }
+            ),
+            muted,
+        )));
+        lines.push(Line::default());
+    }
+
+    let count_width = view
+        .rows
+        .first()
+        .map_or(1, |row| row.count.to_string().len());
+    // Each group takes two rows; keep the cursor's on screen.
+    let groups = (area.height.saturating_sub(6) as usize / 2).max(1);
+    let start = view.cursor.saturating_sub(groups - 1);
+    for (index, row) in view.rows.iter().enumerate().skip(start).take(groups) {
+        let selected = index == view.cursor;
+        let row_style = if selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(if selected { "▸ " } else { "  " }, row_style),
+            Span::styled(
+                format!("{:>width$}× ", row.count, width = count_width),
+                row_style.add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(row.class.clone(), row_style.fg(Color::LightRed)),
+            Span::styled(format!("  {}", row.message), row_style),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "  {}  first {} · last {}",
+                " ".repeat(count_width + 1),
+                row.first_seen,
+                row.last_seen
+            ),
+            muted,
+        )));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Exceptions (Enter selects the newest · {} or Esc to close)",
+                close_hint
+            ))
+            .padding(Padding::uniform(1))
+            .border_style(Style::default().fg(Color::LightRed)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
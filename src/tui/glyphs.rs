@@ -0,0 +1,31 @@
+//! Timeline glyphs that tell payload kinds apart at a glance.
+
+use crate::config::GlyphSet;
+
+/// Glyph for a timeline kind in `set`; kinds without one, such as `log`, keep
+/// the plain bullet.
+pub fn kind_glyph(set: GlyphSet, kind: &str) -> Option<&'static str> {
+    let [unicode, nerd_font, ascii] = match kind.trim().to_lowercase().as_str() {
+        "exception" => ["⚠", "\u{f071}", "!"],
+        "measure" => ["⏱", "\u{f2f2}", "t"],
+        "table" => ["▤", "\u{f0ce}", "#"],
+        "mail" | "mailable" => ["✉", "\u{f0e0}", "@"],
+        "query" => ["⛁", "\u{f1c0}", "Q"],
+        "http" | "request" | "response" => ["⇄", "\u{f0ac}", "~"],
+        "json" | "json_string" | "decoded_json" => ["❴", "\u{e60b}", "{"],
+        "trace" | "caller" => ["↳", "\u{f0cb}", ">"],
+        "notify" => ["⚑", "\u{f0f3}", "^"],
+        "image" => ["▣", "\u{f03e}", "%"],
+        "html" => ["◊", "\u{f13b}", "<"],
+        "text" => ["¶", "\u{f031}", "T"],
+        "boolean" => ["◑", "\u{f205}", "?"],
+        "separator" => ["─", "\u{f068}", "-"],
+        _ => return None,
+    };
+    match set {
+        GlyphSet::Unicode => Some(unicode),
+        GlyphSet::NerdFont => Some(nerd_font),
+        GlyphSet::Ascii => Some(ascii),
+        GlyphSet::None => None,
+    }
+}
@@ -0,0 +1,55 @@
+use ratatui::{Frame, layout::Rect};
+
+use super::prompt::PromptView;
+
+/// History search state handed to the renderer.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryView {
+    /// Query being typed while the search prompt is open.
+    pub prompt: Option<String>,
+    /// Why the last query could not run.
+    pub error: Option<String>,
+    /// Query whose matches currently replace the timeline.
+    pub results_for: Option<String>,
+    pub matches: usize,
+    /// The match limit was hit; older matches were left out.
+    pub truncated: bool,
+}
+
+impl HistoryView {
+    /// Timeline title while results are shown, e.g. `History: checkout (12 matches)`.
+    pub fn title(&self) -> Option<String> {
+        let query = self.results_for.as_deref()?;
+        let query = if query.is_empty() {
+            "everything"
+        } else {
+            query
+        };
+        Some(format!(
+            "History: {} ({}{} match{}, read-only)",
+            query,
+            if self.truncated { "latest " } else { "" },
+            self.matches,
+            if self.matches == 1 { "" } else { "es" }
+        ))
+    }
+}
+
+pub(super) fn render_history_prompt(
+    frame: &mut Frame<'_>,
+    input: &str,
+    error: Option<&str>,
+    area: Rect,
+) {
+    super::prompt::render_prompt(
+        frame,
+        &PromptView {
+            title: "Search history (Enter search · Esc cancel)".to_string(),
+            hint: "words · kind: label: project: session: · since:/until: 2024-03-09, 2h, 7d"
+                .to_string(),
+            input: input.to_string(),
+            error: error.map(str::to_string),
+        },
+        area,
+    );
+}
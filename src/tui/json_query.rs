@@ -0,0 +1,71 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+/// JSON query results handed to the renderer, each value pretty-printed.
+#[derive(Debug, Clone)]
+pub struct JsonQueryView {
+    pub query: String,
+    pub results: Vec<String>,
+    pub scroll: usize,
+}
+
+pub(super) fn render_json_query_overlay(
+    frame: &mut Frame<'_>,
+    view: &JsonQueryView,
+    query_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![Line::from(vec![
+        Span::styled(
+            "> ",
+            Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(view.query.clone()),
+        Span::styled(
+            match view.results.len() {
+                1 => "  · 1 match".to_string(),
+                count => format!("  · {} matches", count),
+            },
+            muted,
+        ),
+    ])];
+    lines.push(Line::default());
+
+    if view.results.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Nothing in the selected event matches the query.",
+            muted,
+        )));
+    }
+    for (index, result) in view.results.iter().enumerate() {
+        if index > 0 {
+            lines.push(Line::from(Span::styled("──", muted)));
+        }
+        lines.extend(result.lines().map(|line| Line::from(line.to_string())));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .scroll((view.scroll.min(u16::MAX as usize) as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "JSON Query (y copies · {} edits the query · Esc to close)",
+                    query_hint
+                ))
+                .padding(Padding::uniform(1))
+                .border_style(Style::default().fg(Color::LightBlue)),
+        );
+
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,90 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+};
+use tracing::Level;
+
+/// One log line, pre-formatted for the viewer.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Wall-clock time it was logged, e.g. `14:05:01`.
+    pub time: String,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Log viewer state handed to the renderer, newest entry first.
+#[derive(Debug, Clone)]
+pub struct LogViewerView {
+    pub entries: Vec<LogEntry>,
+    /// Active tracing filter directives, when they can be changed.
+    pub filter: Option<String>,
+    pub scroll: usize,
+}
+
+pub(super) fn render_log_viewer_overlay(
+    frame: &mut Frame<'_>,
+    viewer: &LogViewerView,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![Line::from(match &viewer.filter {
+        Some(filter) => vec![
+            Span::styled("filter ", muted),
+            Span::styled(filter.clone(), Style::default().fg(Color::Cyan)),
+            Span::styled(" · / changes it", muted),
+        ],
+        None => vec![Span::styled("The filter cannot be changed here.", muted)],
+    })];
+    lines.push(Line::raw(""));
+
+    if viewer.entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Nothing logged with this filter yet.",
+            muted,
+        )));
+    }
+
+    for entry in &viewer.entries {
+        let style = match entry.level {
+            Level::ERROR => Style::default().fg(Color::LightRed),
+            Level::WARN => Style::default().fg(Color::Yellow),
+            Level::INFO => Style::default().fg(Color::Green),
+            Level::DEBUG => Style::default().fg(Color::Blue),
+            Level::TRACE => Style::default().fg(Color::Magenta),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} ", entry.time), muted),
+            Span::styled(
+                format!("{:<5}", entry.level),
+                style.add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" {}:", entry.target), muted),
+            Span::raw(format!(" {}", entry.message)),
+        ]));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((viewer.scroll.min(u16::MAX as usize) as u16, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Logs ({}, newest first · {} or Esc to close)",
+                    viewer.entries.len(),
+                    close_hint
+                ))
+                .padding(Padding::uniform(1))
+                .border_style(Style::default().fg(Color::Blue)),
+        );
+
+    frame.render_widget(paragraph, area);
+}
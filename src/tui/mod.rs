@@ -1,30 +1,78 @@
+pub mod clipboard;
+pub mod detail_rows;
+pub mod diagnostics;
+pub mod exceptions;
+pub mod glyphs;
+pub mod history;
+pub mod json_query;
+pub mod log_viewer;
+pub mod onboarding;
+pub mod origins;
+pub mod problems;
+pub mod profile;
+pub mod prompt;
+pub mod quick_actions;
+pub mod stats;
+pub mod title;
+pub mod trash;
+pub mod unknown_kinds;
+
 use std::{
-    collections::HashSet,
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     io::{self, Stdout},
     net::SocketAddr,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use crate::ui::detail::{self, DetailSegment, DetailViewModel, SegmentStyle};
+use crate::{
+    config::{FileConfig, GlyphSet, LocaleConfig, Palette},
+    keymap::{Action, Keymap},
+    logs,
+    ui::{
+        color::{self, ThemeColor},
+        detail::{DetailLine, DetailSegment, DetailViewModel, SegmentStyle},
+        diff::{DiffLine, DiffOp},
+        format::ContentFormat,
+        text::{self, grapheme_count},
+    },
+};
 use color_eyre::Result;
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event as CrosstermEvent, KeyEvent,
-        MouseEvent,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event as CrosstermEvent, KeyCode, KeyEvent, KeyEventKind, MouseEvent,
     },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
+use detail_rows::DetailRows;
+use diagnostics::DiagnosticsView;
+use exceptions::ExceptionsView;
+use history::HistoryView;
+use json_query::JsonQueryView;
+use log_viewer::LogViewerView;
+use onboarding::OnboardingView;
+use origins::OriginsView;
+use problems::ProblemsView;
+use profile::ProfileView;
+use prompt::PromptView;
+use quick_actions::QuickActionsView;
 use ratatui::{
     Frame, Terminal,
-    backend::CrosstermBackend,
+    backend::{CrosstermBackend, TestBackend},
+    buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, List, ListItem, Padding, Paragraph, Wrap},
 };
+use stats::StatsView;
 use tokio::{sync::mpsc, task};
 use tracing::{debug, error};
+use trash::TrashView;
+use unknown_kinds::UnknownKindsView;
 use uuid::Uuid;
 
 static RAYGUN_BANNER: &[&str] = &[
@@ -42,6 +90,24 @@ pub enum Event {
     Tick,
     Resize(u16, u16),
     Mouse(MouseEvent),
+    /// Text pasted in one piece by a terminal with bracketed paste.
+    Paste(String),
+}
+
+impl Event {
+    /// Whether the event only moves a cursor or scrolls, so a run of them
+    /// (a held arrow key) can be handled together before the next frame.
+    pub fn is_navigation(&self) -> bool {
+        matches!(
+            self,
+            Event::Input(key)
+                if key.modifiers.is_empty()
+                    && matches!(
+                        key.code,
+                        KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown
+                    )
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,24 +118,273 @@ pub struct TimelineEntry {
     pub age: String,
     pub color: Option<String>,
     pub label: Option<String>,
+    /// Other attached clients that have this event selected.
+    pub viewers: usize,
+    /// Where the event sits in the marked range, if it is part of it.
+    pub range: Option<RangeMark>,
+    /// The HTTP request this event reports as handled, e.g. `GET /cart 200`;
+    /// a boundary above it separates the request's events from newer ones.
+    pub request: Option<String>,
+    /// Friendly name of the host that sent the event, from `[hosts]`.
+    pub host: Option<String>,
+    /// `file:line` of the call site that sent the event, after path mappings.
+    pub origin: Option<String>,
+    /// Language of a text payload, shown as a badge; `None` for plain text
+    /// and kinds that already name it, such as `html`.
+    pub format: Option<ContentFormat>,
+    /// Arrived since the last key press, click or paste.
+    pub unread: bool,
+}
+
+/// Position of a timeline row in the range marked with `mark_range`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeMark {
+    /// The start was marked; the end is still to be chosen.
+    Pending,
+    /// Oldest event of the range.
+    First,
+    Inside,
+    /// Newest event of the range.
+    Last,
+    /// The range is this one event.
+    Only,
+}
+
+impl RangeMark {
+    /// Gutter glyph; the timeline lists newest first, so the range opens at
+    /// the top with its last event.
+    fn glyph(self) -> &'static str {
+        match self {
+            RangeMark::Pending => "▸",
+            RangeMark::First => "╰",
+            RangeMark::Inside => "│",
+            RangeMark::Last => "╭",
+            RangeMark::Only => "─",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct AppViewModel {
     pub total_events: usize,
-    pub bind_addr: SocketAddr,
+    /// Events that arrived since the last key press, click or paste.
+    pub unread: usize,
+    pub bind_addrs: Vec<SocketAddr>,
+    pub public_url: Option<String>,
     pub timeline: Vec<TimelineEntry>,
     pub selected: Option<usize>,
-    pub detail: Option<DetailViewModel>,
+    pub detail: Option<Arc<DetailViewModel>>,
+    /// Location of the frame under the detail cursor in focus-follow mode,
+    /// shown in place of the detail's own footer.
+    pub detail_footer: Option<String>,
     pub focus_detail: bool,
     pub detail_scroll: usize,
     pub layout: LayoutConfig,
     pub detail_state: Option<DetailStateView>,
+    /// Detail lines wrapped on an earlier frame; rebuilt while drawing when
+    /// missing or wrapped for another width.
+    pub detail_rows: Option<Arc<DetailRows>>,
     pub active_color_filter: Option<String>,
+    /// Active timeline filter expression, e.g. `kind:exception AND age<5m`.
+    pub event_filter: Option<String>,
+    /// Events matching the `noise` filter are hidden on top of the filters.
+    pub noise_hidden: bool,
+    /// Open text prompt, such as the timeline filter bar.
+    pub prompt: Option<PromptView>,
+    /// The problems strip, while it is expanded or has unseen problems.
+    pub problems: Option<ProblemsView>,
     pub available_colors: Vec<String>,
+    /// Names of the saved views, in number-key order.
+    pub views: Vec<String>,
     pub show_help: bool,
+    pub onboarding: Option<OnboardingView>,
     pub debug_json: Option<String>,
     pub debug_scroll: usize,
+    /// Inferred content schema of the selected event, when the overlay is open.
+    pub schema: Option<Vec<DetailLine>>,
+    pub schema_scroll: usize,
+    pub quick_actions: Option<QuickActionsView>,
+    pub diff: Option<DiffView>,
+    pub diff_scroll: usize,
+    /// Transient feedback for the last action, shown in the footer.
+    pub status: Option<String>,
+    /// Protocol issues recorded so far, shown in the header.
+    pub diagnostic_count: usize,
+    pub diagnostics: Option<DiagnosticsView>,
+    /// Recent log output and the tracing filter, when the log viewer is open.
+    pub logs: Option<LogViewerView>,
+    /// Payload types Raygun doesn't know, when their overlay is open.
+    pub unknown_kinds: Option<UnknownKindsView>,
+    /// Deleted events that can be restored, when the trash is open.
+    pub trash: Option<TrashView>,
+    /// Call sites by the number of events they sent, when that overlay is open.
+    pub origins: Option<OriginsView>,
+    /// Exceptions grouped by class and message, when that overlay is open.
+    pub exceptions: Option<ExceptionsView>,
+    /// Results of the JSON query run on the selected event.
+    pub json_query: Option<JsonQueryView>,
+    /// Processing delays of recent events, when the stats overlay is open.
+    pub stats: Option<StatsView>,
+    /// Timings of the last frame, when profiling is on.
+    pub profile: Option<ProfileView>,
+    /// Source around the focused frame, shown below the details in focus-follow mode.
+    pub source_preview: Option<SourcePreview>,
+    /// The focused pane covers the whole frame.
+    pub fullscreen: bool,
+    /// Clients attached to the daemon, including this one (`raygun attach`).
+    pub viewers: Option<usize>,
+    /// History search, while its prompt is open or its results are shown.
+    pub history: Option<HistoryView>,
+    /// Filter box of the selected event's detail view, when one is set.
+    pub detail_filter: Option<DetailFilterView>,
+    /// Key path of the detail line under the cursor, e.g. `["user", "roles", "[0]"]`.
+    pub detail_breadcrumb: Vec<String>,
+    pub settings: Arc<UiSettings>,
+}
+
+/// Line diff of two events shown by the quick actions menu.
+#[derive(Debug, Clone)]
+pub struct DiffView {
+    pub title: String,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Numbered source lines around a frame's location; `message` explains why
+/// no lines could be loaded.
+#[derive(Debug, Clone)]
+pub struct SourcePreview {
+    pub location: String,
+    pub lines: Vec<(u32, String)>,
+    pub highlight: Option<u32>,
+    pub message: Option<String>,
+}
+
+/// Config file customisations that affect rendering and input handling.
+#[derive(Debug, Clone, Default)]
+pub struct UiSettings {
+    pub keymap: Keymap,
+    pub palette: Palette,
+    /// Replace the UI's Unicode glyphs with ASCII after rendering (`--ascii`).
+    pub ascii: bool,
+    pub glyphs: GlyphSet,
+    /// How numbers and times of day are written (`[locale]`).
+    pub locale: LocaleConfig,
+    colors: HashMap<String, Color>,
+    kind_glyphs: HashMap<String, String>,
+}
+
+impl UiSettings {
+    /// Builds settings from an already validated config; invalid entries fall
+    /// back to the defaults.
+    pub fn new(file_config: &FileConfig) -> Self {
+        let colors = file_config
+            .theme
+            .colors
+            .iter()
+            .filter_map(|(name, value)| {
+                color_from_name(value).map(|color| (name.trim().to_lowercase(), color))
+            })
+            .collect();
+        let kind_glyphs = file_config
+            .theme
+            .kind_glyphs
+            .iter()
+            .filter(|(_, glyph)| glyph.chars().count() == 1)
+            .map(|(kind, glyph)| (kind.trim().to_lowercase(), glyph.clone()))
+            .collect();
+
+        Self {
+            keymap: Keymap::from_config(&file_config.keys).unwrap_or_default(),
+            palette: file_config.theme.palette,
+            ascii: false,
+            glyphs: file_config.theme.glyphs,
+            locale: file_config.locale,
+            colors,
+            kind_glyphs,
+        }
+    }
+
+    /// Resolves a Ray color name, honouring `[theme.colors]` overrides.
+    pub fn color(&self, name: &str) -> Option<Color> {
+        self.colors
+            .get(&name.trim().to_lowercase())
+            .copied()
+            .or_else(|| palette_color(self.palette, name))
+            .or_else(|| color_from_name(name))
+    }
+
+    /// Timeline marker for an entry's color. The default palette relies on
+    /// color alone; the accessible palettes give each Ray color its own shape.
+    pub fn color_marker(&self, name: Option<&str>) -> &'static str {
+        if self.palette == Palette::Default {
+            return "⬤";
+        }
+        match name.map(|name| name.trim().to_lowercase()).as_deref() {
+            Some("red") => "■",
+            Some("orange") => "▲",
+            Some("green") => "●",
+            Some("blue") => "◆",
+            Some("purple") => "★",
+            Some("gray" | "grey") => "○",
+            Some(_) => "◇",
+            None => "·",
+        }
+    }
+
+    /// Glyph for a timeline kind: a `[theme.kind_glyphs]` override, then the
+    /// configured set. `--ascii` switches to the ASCII set and drops
+    /// overrides it could not draw.
+    pub fn kind_glyph(&self, kind: &str) -> Option<&str> {
+        let set = if self.ascii {
+            GlyphSet::Ascii
+        } else {
+            self.glyphs
+        };
+        self.kind_glyphs
+            .get(&kind.trim().to_lowercase())
+            .map(String::as_str)
+            .filter(|glyph| set != GlyphSet::Ascii || glyph.is_ascii())
+            .or_else(|| glyphs::kind_glyph(set, kind))
+    }
+
+    /// Leading marker of a timeline entry. The default palette shows the
+    /// kind's glyph in the entry's color; the accessible palettes keep their
+    /// color shape and add the glyph after it.
+    pub fn timeline_marker(&self, color: Option<&str>, kind: &str) -> String {
+        let glyph = self.kind_glyph(kind);
+        if self.palette == Palette::Default {
+            return glyph.unwrap_or("⬤").to_string();
+        }
+        let shape = self.color_marker(color);
+        match glyph {
+            Some(glyph) => format!("{}{}", shape, glyph),
+            None if self.glyphs == GlyphSet::None => shape.to_string(),
+            // Keep the kinds aligned with the entries that have a glyph.
+            None => format!("{} ", shape),
+        }
+    }
+}
+
+/// Palette-specific values for the Ray color names; `None` defers to
+/// [`color_from_name`].
+fn palette_color(palette: Palette, name: &str) -> Option<Color> {
+    let color = match (palette, name.trim().to_lowercase().as_str()) {
+        (Palette::Default, _) => return None,
+        (Palette::HighContrast, "red") => Color::Rgb(255, 85, 85),
+        (Palette::HighContrast, "green") => Color::Rgb(0, 255, 0),
+        (Palette::HighContrast, "blue") => Color::Rgb(85, 170, 255),
+        (Palette::HighContrast, "orange") => Color::Rgb(255, 170, 0),
+        (Palette::HighContrast, "purple") => Color::Rgb(255, 85, 255),
+        (Palette::HighContrast, "gray" | "grey") => Color::White,
+        (Palette::Colorblind, "red") => Color::Rgb(213, 94, 0),
+        (Palette::Colorblind, "green") => Color::Rgb(0, 158, 115),
+        (Palette::Colorblind, "blue") => Color::Rgb(0, 114, 178),
+        (Palette::Colorblind, "orange") => Color::Rgb(230, 159, 0),
+        (Palette::Colorblind, "purple") => Color::Rgb(204, 121, 167),
+        (Palette::Colorblind, "gray" | "grey") => Color::Rgb(153, 153, 153),
+        _ => return None,
+    };
+    Some(color)
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -78,6 +393,15 @@ pub struct LayoutConfig {
     pub detail_percent: u16,
 }
 
+/// Query typed into the detail filter box and how many entries it kept.
+#[derive(Debug, Clone)]
+pub struct DetailFilterView {
+    pub query: String,
+    pub editing: bool,
+    pub matches: usize,
+    pub total: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct DetailStateView {
     pub cursor: usize,
@@ -91,13 +415,30 @@ pub struct TerminalGuard {
 impl TerminalGuard {
     pub fn new() -> Result<Self> {
         enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-        terminal.hide_cursor()?;
+        // Leave the terminal usable if any later step fails; `Drop` only runs
+        // once the guard exists.
+        let setup = || -> Result<Terminal<CrosstermBackend<Stdout>>> {
+            let mut stdout = io::stdout();
+            execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+            // Optional: without it a paste arrives as a stream of key presses.
+            let _ = execute!(stdout, EnableBracketedPaste);
+            let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+            terminal.hide_cursor()?;
+            Ok(terminal)
+        };
 
-        Ok(Self { terminal })
+        match setup() {
+            Ok(terminal) => {
+                // Log output to stderr would draw over the UI.
+                logs::capture(true);
+                Ok(Self { terminal })
+            }
+            Err(err) => {
+                let _ = execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen);
+                let _ = disable_raw_mode();
+                Err(err)
+            }
+        }
     }
 
     pub fn draw<F, R>(&mut self, f: F) -> Result<R>
@@ -114,11 +455,13 @@ impl TerminalGuard {
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
+        logs::capture(false);
         if let Err(err) = disable_raw_mode() {
             error!(?err, "failed to disable raw mode");
         }
 
         let mut stdout = io::stdout();
+        let _ = execute!(stdout, DisableBracketedPaste);
         if let Err(err) = execute!(stdout, DisableMouseCapture, LeaveAlternateScreen) {
             error!(?err, "failed to leave alternate screen");
         }
@@ -129,9 +472,12 @@ impl Drop for TerminalGuard {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct AppRenderMetadata {
     pub timeline_inner: Rect,
+    /// Timeline entry drawn on each row of `timeline_inner`; `None` for
+    /// request boundaries.
+    pub timeline_rows: Vec<Option<usize>>,
     pub detail_inner: Rect,
     pub overlay: Option<OverlayArea>,
 }
@@ -139,7 +485,21 @@ pub struct AppRenderMetadata {
 #[derive(Debug, Clone, Copy)]
 pub enum OverlayArea {
     Help(Rect),
+    Onboarding(Rect),
     Debug(Rect),
+    Diagnostics(Rect),
+    Logs(Rect),
+    UnknownKinds(Rect),
+    Trash(Rect),
+    Origins(Rect),
+    Exceptions(Rect),
+    JsonQuery(Rect),
+    Stats(Rect),
+    Schema(Rect),
+    QuickActions(Rect),
+    Diff(Rect),
+    History(Rect),
+    Prompt(Rect),
 }
 
 pub fn spawn_event_loop(
@@ -156,6 +516,9 @@ pub fn spawn_event_loop(
 
             match event::poll(timeout) {
                 Ok(true) => match event::read() {
+                    // Windows consoles report key releases too; handling them
+                    // would run every shortcut twice.
+                    Ok(CrosstermEvent::Key(key)) if key.kind == KeyEventKind::Release => {}
                     Ok(CrosstermEvent::Key(key)) => {
                         if tx.send(Event::Input(key)).is_err() {
                             break;
@@ -171,6 +534,11 @@ pub fn spawn_event_loop(
                             break;
                         }
                     }
+                    Ok(CrosstermEvent::Paste(text)) => {
+                        if tx.send(Event::Paste(text)).is_err() {
+                            break;
+                        }
+                    }
                     Ok(_) => {}
                     Err(err) => {
                         error!(?err, "failed to read terminal event");
@@ -196,37 +564,237 @@ pub fn spawn_event_loop(
     })
 }
 
+/// Renders a view model into an off-screen buffer of `width`×`height` cells
+/// without touching the real terminal, for snapshot tests and tooling.
+pub fn render_to_buffer(
+    view_model: &AppViewModel,
+    width: u16,
+    height: u16,
+) -> Result<(Buffer, AppRenderMetadata)> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    let mut metadata = None;
+    let completed = terminal.draw(|frame| {
+        metadata = Some(render_app(frame, view_model));
+    })?;
+    let buffer = completed.buffer.clone();
+
+    Ok((
+        buffer,
+        metadata.expect("render_app did not produce render metadata"),
+    ))
+}
+
+/// Flattens a buffer into plain text, one line per row, with trailing
+/// whitespace removed so snapshots stay stable across styling changes.
+pub fn buffer_to_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let mut row = String::new();
+        for x in area.left()..area.right() {
+            row.push_str(buffer.get(x, y).symbol());
+        }
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Below this size the split layout becomes unreadable, so only one pane is
+/// shown at a time: the timeline, or the details while they have focus.
+pub const MIN_SPLIT_WIDTH: u16 = 60;
+pub const MIN_SPLIT_HEIGHT: u16 = 15;
+
+pub fn is_compact(width: u16, height: u16) -> bool {
+    width < MIN_SPLIT_WIDTH || height < MIN_SPLIT_HEIGHT
+}
+
+/// How the timeline and detail panes share the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaneMode {
+    Split,
+    /// Terminal too small to split; the focused pane fills the body.
+    Compact,
+    /// The focused pane was zoomed to cover the whole frame.
+    Fullscreen,
+}
+
 pub fn render_app(frame: &mut Frame<'_>, view_model: &AppViewModel) -> AppRenderMetadata {
     let frame_rect = frame.size();
+    let mode = if view_model.fullscreen {
+        PaneMode::Fullscreen
+    } else if is_compact(frame_rect.width, frame_rect.height) {
+        PaneMode::Compact
+    } else {
+        PaneMode::Split
+    };
+    let problems_height = match (&view_model.problems, mode) {
+        (Some(problems), PaneMode::Split | PaneMode::Compact) => problems.height(),
+        _ => 0,
+    };
     let layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Percentage(view_model.layout.timeline_percent),
-            Constraint::Percentage(view_model.layout.detail_percent),
-            Constraint::Length(2),
-        ])
+        .constraints(match mode {
+            PaneMode::Split => [
+                Constraint::Length(1),
+                Constraint::Percentage(view_model.layout.timeline_percent),
+                Constraint::Percentage(view_model.layout.detail_percent),
+                Constraint::Length(problems_height),
+                Constraint::Length(2),
+            ],
+            PaneMode::Compact => [
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(0),
+                Constraint::Length(problems_height),
+                Constraint::Length(0),
+            ],
+            PaneMode::Fullscreen => [
+                Constraint::Length(0),
+                Constraint::Min(0),
+                Constraint::Length(0),
+                Constraint::Length(0),
+                Constraint::Length(0),
+            ],
+        })
         .split(frame_rect);
 
-    render_header(frame, layout[0], view_model);
-    render_timeline(frame, layout[1], view_model);
-    render_detail(frame, layout[2], view_model);
-    render_footer(frame, layout[3]);
+    let (timeline_area, detail_area) = match (mode, view_model.focus_detail) {
+        (PaneMode::Split, _) => (layout[1], layout[2]),
+        (_, false) => (layout[1], Rect::default()),
+        (_, true) => (Rect::default(), layout[1]),
+    };
+
+    let detail_area = match &view_model.source_preview {
+        Some(preview) if mode != PaneMode::Compact => {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(3),
+                    Constraint::Length(SOURCE_PREVIEW_HEIGHT),
+                ])
+                .split(detail_area);
+            render_source_preview(frame, split[1], preview);
+            split[0]
+        }
+        _ => detail_area,
+    };
+
+    if !layout[0].is_empty() {
+        render_header(frame, layout[0], view_model);
+    }
+    let timeline_rows = if timeline_area.is_empty() {
+        Vec::new()
+    } else {
+        render_timeline(frame, timeline_area, view_model, mode)
+    };
+    if !detail_area.is_empty() {
+        render_detail(frame, detail_area, view_model, mode);
+    }
+    if let Some(problems) = &view_model.problems
+        && !layout[3].is_empty()
+    {
+        let toggle_hint = view_model.settings.keymap.hint(Action::Problems);
+        problems::render_problems_strip(frame, problems, &toggle_hint, layout[3]);
+    }
+    if !layout[4].is_empty() {
+        render_footer(frame, layout[4], view_model);
+    }
 
     let mut overlay = None;
     if view_model.show_help {
         let area = centered_rect(80, 70, frame_rect);
         render_help_overlay(frame, view_model, area);
         overlay = Some(OverlayArea::Help(area));
+    } else if let Some(onboarding) = &view_model.onboarding {
+        let area = centered_rect(80, 80, frame_rect);
+        onboarding::render_onboarding_overlay(frame, view_model, onboarding, area);
+        overlay = Some(OverlayArea::Onboarding(area));
+    } else if let Some((input, error)) = view_model.history.as_ref().and_then(|history| {
+        history
+            .prompt
+            .as_deref()
+            .map(|input| (input, history.error.as_deref()))
+    }) {
+        let area = fixed_centered_rect(72, 4, frame_rect);
+        history::render_history_prompt(frame, input, error, area);
+        overlay = Some(OverlayArea::History(area));
+    } else if let Some(prompt) = &view_model.prompt {
+        let area = fixed_centered_rect(72, 4, frame_rect);
+        prompt::render_prompt(frame, prompt, area);
+        overlay = Some(OverlayArea::Prompt(area));
+    } else if let Some(menu) = &view_model.quick_actions {
+        let height = menu.items.len() as u16 + 2;
+        let area = fixed_centered_rect(72, height, frame_rect);
+        quick_actions::render_quick_actions_overlay(frame, menu, area);
+        overlay = Some(OverlayArea::QuickActions(area));
+    } else if let Some(diagnostics) = &view_model.diagnostics {
+        let area = centered_rect(90, 80, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::Diagnostics);
+        diagnostics::render_diagnostics_overlay(frame, diagnostics, &close_hint, area);
+        overlay = Some(OverlayArea::Diagnostics(area));
+    } else if let Some(logs) = &view_model.logs {
+        let area = centered_rect(90, 80, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::Logs);
+        log_viewer::render_log_viewer_overlay(frame, logs, &close_hint, area);
+        overlay = Some(OverlayArea::Logs(area));
+    } else if let Some(unknown_kinds) = &view_model.unknown_kinds {
+        let area = centered_rect(90, 80, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::UnknownKinds);
+        unknown_kinds::render_unknown_kinds_overlay(frame, unknown_kinds, &close_hint, area);
+        overlay = Some(OverlayArea::UnknownKinds(area));
+    } else if let Some(trash) = &view_model.trash {
+        let area = centered_rect(80, 70, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::Trash);
+        trash::render_trash_overlay(frame, trash, &close_hint, area);
+        overlay = Some(OverlayArea::Trash(area));
+    } else if let Some(origins) = &view_model.origins {
+        let area = centered_rect(80, 70, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::Origins);
+        origins::render_origins_overlay(frame, origins, &close_hint, area);
+        overlay = Some(OverlayArea::Origins(area));
+    } else if let Some(exceptions) = &view_model.exceptions {
+        let area = centered_rect(80, 70, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::Exceptions);
+        exceptions::render_exceptions_overlay(frame, exceptions, &close_hint, area);
+        overlay = Some(OverlayArea::Exceptions(area));
+    } else if let Some(query) = &view_model.json_query {
+        let area = centered_rect(80, 80, frame_rect);
+        let query_hint = view_model.settings.keymap.hint(Action::JsonQuery);
+        json_query::render_json_query_overlay(frame, query, &query_hint, area);
+        overlay = Some(OverlayArea::JsonQuery(area));
+    } else if let Some(stats) = &view_model.stats {
+        let area = centered_rect(70, 50, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::Stats);
+        stats::render_stats_overlay(frame, stats, &close_hint, area);
+        overlay = Some(OverlayArea::Stats(area));
+    } else if let Some(schema) = view_model.schema.as_deref() {
+        let area = centered_rect(80, 80, frame_rect);
+        let close_hint = view_model.settings.keymap.hint(Action::Schema);
+        render_schema_overlay(frame, schema, view_model.schema_scroll, &close_hint, area);
+        overlay = Some(OverlayArea::Schema(area));
+    } else if let Some(diff) = &view_model.diff {
+        let area = centered_rect(90, 80, frame_rect);
+        render_diff_overlay(frame, diff, view_model.diff_scroll, area);
+        overlay = Some(OverlayArea::Diff(area));
     } else if let Some(json) = view_model.debug_json.as_deref() {
         let area = centered_rect(90, 80, frame_rect);
-        render_debug_overlay(frame, json, view_model.debug_scroll, area);
+        let close_hint = view_model.settings.keymap.hint(Action::RawPayload);
+        render_debug_overlay(frame, json, view_model.debug_scroll, &close_hint, area);
         overlay = Some(OverlayArea::Debug(area));
     }
+    if let Some(profile) = &view_model.profile {
+        profile::render_profile_panel(frame, profile, frame_rect);
+    }
+
+    if view_model.settings.ascii {
+        asciify(frame.buffer_mut());
+    }
 
     AppRenderMetadata {
-        timeline_inner: inner(layout[1]),
-        detail_inner: inner(layout[2]),
+        timeline_inner: inner(timeline_area),
+        timeline_rows,
+        detail_inner: inner(detail_area),
         overlay,
     }
 }
@@ -234,13 +802,58 @@ pub fn render_app(frame: &mut Frame<'_>, view_model: &AppViewModel) -> AppRender
 fn render_header(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel) {
     let mut title = format!(
         "Raygun — waiting for payloads ({} total) @ {}",
-        view_model.total_events, view_model.bind_addr
+        view_model.total_events,
+        format_addrs(&view_model.bind_addrs)
     );
 
+    if view_model.unread > 0 {
+        title.push_str(&format!(
+            " | {} unread ({})",
+            view_model.unread,
+            view_model.settings.keymap.hint(Action::NextUnread)
+        ));
+    }
+
+    if let Some(url) = &view_model.public_url {
+        title.push_str(&format!(" | public: {}", url));
+    }
+
     if let Some(color) = &view_model.active_color_filter {
         title.push_str(&format!(" | color filter: {}", color));
     }
 
+    if let Some(filter) = &view_model.event_filter {
+        title.push_str(&format!(" | filter: {}", filter));
+    }
+
+    if view_model.noise_hidden {
+        title.push_str(&format!(
+            " | noise hidden ({})",
+            view_model.settings.keymap.hint(Action::HideNoise)
+        ));
+    }
+
+    if let Some(viewers) = view_model.viewers {
+        title.push_str(&format!(
+            " | {} viewer{}",
+            viewers,
+            if viewers == 1 { "" } else { "s" }
+        ));
+    }
+
+    if view_model.diagnostic_count > 0 {
+        title.push_str(&format!(
+            " | ⚠ {} protocol issue{} ({})",
+            view_model.diagnostic_count,
+            if view_model.diagnostic_count == 1 {
+                ""
+            } else {
+                "s"
+            },
+            view_model.settings.keymap.hint(Action::Diagnostics)
+        ));
+    }
+
     let block = Block::default()
         .borders(Borders::BOTTOM)
         .title(title)
@@ -249,11 +862,36 @@ fn render_header(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel) {
     frame.render_widget(block, area);
 }
 
-fn render_timeline(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel) {
+/// Draws the timeline and returns the entry shown on each row.
+fn render_timeline(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    view_model: &AppViewModel,
+    mode: PaneMode,
+) -> Vec<Option<usize>> {
     let mut title = "Timeline".to_string();
     if let Some(filter) = &view_model.active_color_filter {
         title = format!("Timeline (color = {})", filter);
     }
+    if let Some(history) = view_model.history.as_ref().and_then(HistoryView::title) {
+        title = match &view_model.active_color_filter {
+            Some(filter) => format!("{} (color = {})", history, filter),
+            None => history,
+        };
+    }
+    let keymap = &view_model.settings.keymap;
+    match mode {
+        PaneMode::Split => {}
+        PaneMode::Compact => {
+            title.push_str(&format!(" · {} details", keymap.hint(Action::ToggleFocus)));
+        }
+        PaneMode::Fullscreen => {
+            title.push_str(&format!(
+                " · {}/Esc restore",
+                keymap.hint(Action::Fullscreen)
+            ));
+        }
+    }
 
     let block = Block::default()
         .title(title)
@@ -273,24 +911,65 @@ fn render_timeline(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel)
 
     let inner_area = inner(area);
     if inner_area.height == 0 {
-        return;
+        return Vec::new();
     }
 
     if view_model.timeline.is_empty() {
         EmptyTimelineMessage::new(view_model).render(frame, inner_area);
-        return;
+        return Vec::new();
     }
 
+    // Each entry takes a row, preceded by a boundary row when it closes a
+    // request.
+    let rows: Vec<Option<usize>> = view_model
+        .timeline
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, entry)| {
+            entry
+                .request
+                .as_ref()
+                .map(|_| None)
+                .into_iter()
+                .chain([Some(idx)])
+        })
+        .collect();
     let view_height = inner_area.height as usize;
     let selected = view_model.selected.unwrap_or(0);
-    let total = view_model.timeline.len();
+    let selected_row = rows
+        .iter()
+        .position(|row| *row == Some(selected))
+        .unwrap_or(0);
+    let total = rows.len();
     let max_start = total.saturating_sub(view_height);
-    let start = selected
+    let start = selected_row
         .saturating_sub(view_height.saturating_sub(1))
         .min(max_start);
+    let visible_rows = rows[start..(start + view_height).min(total)].to_vec();
 
+    let range_gutter = view_model
+        .timeline
+        .iter()
+        .any(|entry| entry.range.is_some());
     let mut items = Vec::new();
-    for idx in start..(start + view_height).min(total) {
+    for (row, entry_idx) in visible_rows.iter().enumerate() {
+        let Some(idx) = *entry_idx else {
+            // The boundary belongs to the entry on the next row.
+            let Some(entry) = visible_rows
+                .get(row + 1)
+                .copied()
+                .flatten()
+                .and_then(|idx| view_model.timeline.get(idx))
+            else {
+                continue;
+            };
+            items.push(ListItem::new(request_boundary(
+                entry,
+                range_gutter,
+                inner_area.width as usize,
+            )));
+            continue;
+        };
         if let Some(entry) = view_model.timeline.get(idx) {
             let is_selected = Some(idx) == view_model.selected;
             let highlight_style = if is_selected {
@@ -306,7 +985,7 @@ fn render_timeline(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel)
             let bullet_color = entry
                 .color
                 .as_deref()
-                .and_then(color_from_name)
+                .and_then(|name| view_model.settings.color(name))
                 .unwrap_or(Color::DarkGray);
 
             let mut bullet_style = Style::default()
@@ -319,7 +998,23 @@ fn render_timeline(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel)
             }
 
             let mut spans = Vec::new();
-            spans.push(Span::styled("⬤", bullet_style));
+            if range_gutter {
+                let mut range_style = Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD);
+                if let Some(style) = highlight_style {
+                    range_style = range_style.patch(style);
+                }
+                spans.push(Span::styled(
+                    entry.range.map_or(" ", RangeMark::glyph),
+                    range_style,
+                ));
+            }
+            let marker = view_model
+                .settings
+                .timeline_marker(entry.color.as_deref(), &entry.kind);
+            let marker_width = marker.chars().count();
+            spans.push(Span::styled(marker, bullet_style));
             spans.push(Span::raw(" "));
 
             let mut bracket_style = text_style;
@@ -334,8 +1029,54 @@ fn render_timeline(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel)
             spans.push(Span::styled("[", bracket_style));
             spans.push(Span::styled(entry.kind.clone(), kind_style));
             spans.push(Span::styled("] ", bracket_style));
+            let badge = entry.format.map(|format| format!(" {} ", format.badge()));
+            if let Some(badge) = &badge {
+                spans.push(Span::styled(badge.clone(), format_badge_style()));
+                spans.push(Span::styled(" ", bracket_style));
+            }
 
-            spans.push(Span::styled(entry.summary.clone(), text_style));
+            // Clip the summary to what's left of the row so the age and label
+            // stay visible at any terminal width.
+            let reserved = marker_width
+                + 1
+                + usize::from(range_gutter)
+                + grapheme_count(&entry.kind)
+                + 3
+                + badge.as_ref().map_or(0, |badge| badge.len() + 1)
+                + 3
+                + grapheme_count(&entry.age)
+                + entry
+                    .label
+                    .as_deref()
+                    .map_or(0, |label| grapheme_count(label) + 3)
+                + entry
+                    .host
+                    .as_deref()
+                    .map_or(0, |host| grapheme_count(host) + 2)
+                + viewer_marker(entry.viewers).chars().count()
+                + if entry.unread { UNREAD_MARKER.len() } else { 0 };
+            let available = (inner_area.width as usize).saturating_sub(reserved);
+            let origin = entry
+                .origin
+                .as_deref()
+                .and_then(|origin| origin_column(origin, &entry.summary, available));
+            let summary_room = available.saturating_sub(
+                origin
+                    .as_deref()
+                    .map_or(0, |origin| grapheme_count(origin) + ORIGIN_GAP.len()),
+            );
+            spans.push(Span::styled(
+                clip_to_width(&entry.summary, summary_room),
+                text_style,
+            ));
+            if let Some(origin) = origin {
+                let mut origin_style = Style::default().fg(Color::DarkGray);
+                if let Some(style) = highlight_style {
+                    origin_style = origin_style.patch(style);
+                }
+                spans.push(Span::styled(ORIGIN_GAP, text_style));
+                spans.push(Span::styled(origin, origin_style));
+            }
 
             let mut separator_style = text_style;
             if let Some(style) = highlight_style {
@@ -349,6 +1090,34 @@ fn render_timeline(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel)
             }
             spans.push(Span::styled(entry.age.clone(), age_style));
 
+            if entry.unread {
+                let mut unread_style = Style::default()
+                    .fg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD);
+                if let Some(style) = highlight_style {
+                    unread_style = unread_style.patch(style);
+                }
+                spans.push(Span::styled(UNREAD_MARKER, unread_style));
+            }
+
+            if let Some(host) = entry.host.as_deref() {
+                let mut host_style = Style::default().fg(Color::Cyan);
+                if let Some(style) = highlight_style {
+                    host_style = host_style.patch(style);
+                }
+                spans.push(Span::styled(format!(" @{}", host), host_style));
+            }
+
+            if entry.viewers > 0 {
+                let mut viewer_style = Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD);
+                if let Some(style) = highlight_style {
+                    viewer_style = viewer_style.patch(style);
+                }
+                spans.push(Span::styled(viewer_marker(entry.viewers), viewer_style));
+            }
+
             if let Some(label) = entry.label.as_deref() {
                 let mut label_style = Style::default().fg(Color::DarkGray);
                 if let Some(style) = highlight_style {
@@ -364,11 +1133,101 @@ fn render_timeline(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel)
 
     let list = List::new(items).block(Block::default());
     frame.render_widget(list, inner_area);
+    visible_rows
+}
+
+/// Language badge of a text payload's timeline row; it keeps its colors on
+/// the selected row.
+fn format_badge_style() -> Style {
+    Style::default()
+        .fg(Color::Black)
+        .bg(Color::LightBlue)
+        .add_modifier(Modifier::BOLD)
+}
+
+/// Space between a timeline summary and its call site.
+const ORIGIN_GAP: &str = "  ";
+/// Columns a summary keeps before its call site may take any of them.
+const MIN_SUMMARY_WIDTH: usize = 24;
+
+/// The call site as it fits next to `summary` in `available` columns: all
+/// the room the summary leaves, or, when the summary is long, room for just
+/// the file name taken from the summary while it keeps [`MIN_SUMMARY_WIDTH`]
+/// columns. `None` when not even the file name fits.
+fn origin_column(origin: &str, summary: &str, available: usize) -> Option<String> {
+    let file = origin.rsplit('/').next().unwrap_or(origin);
+    let slack = available.saturating_sub(grapheme_count(summary));
+    let stolen = available
+        .saturating_sub(MIN_SUMMARY_WIDTH)
+        .min(grapheme_count(file) + ORIGIN_GAP.len());
+    let room = slack.max(stolen).checked_sub(ORIGIN_GAP.len())?;
+    shorten_origin(origin, room)
 }
 
-fn render_detail(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel) {
+/// `origin` in at most `width` columns, dropping leading directories behind
+/// `…/`, e.g. `…/Http/Controllers/OrderController.php:88`. The file name and
+/// line are always kept; `None` when they alone are too wide.
+fn shorten_origin(origin: &str, width: usize) -> Option<String> {
+    if grapheme_count(origin) <= width {
+        return Some(origin.to_string());
+    }
+    let segments: Vec<&str> = origin.split('/').collect();
+    (1..segments.len())
+        .rev()
+        .map(|keep| format!("…/{}", segments[segments.len() - keep..].join("/")))
+        .chain(segments.last().map(|file| file.to_string()))
+        .find(|text| grapheme_count(text) <= width)
+}
+
+/// `── GET /cart 200 ─────` above the event that closes a request.
+fn request_boundary(entry: &TimelineEntry, range_gutter: bool, width: usize) -> Line<'static> {
+    let mut spans = Vec::new();
+    if range_gutter {
+        let inside = matches!(entry.range, Some(RangeMark::Inside | RangeMark::First));
+        spans.push(Span::styled(
+            if inside { "│" } else { " " },
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    let request = entry.request.as_deref().unwrap_or_default();
+    let label = clip_to_width(request, width.saturating_sub(6 + usize::from(range_gutter)));
+    let fill = width.saturating_sub(4 + label.chars().count() + usize::from(range_gutter));
+    spans.push(Span::styled(
+        format!("── {} {}", label, "─".repeat(fill)),
+        Style::default().fg(Color::DarkGray),
+    ));
+    Line::from(spans)
+}
+
+fn render_detail(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel, mode: PaneMode) {
+    let keymap = &view_model.settings.keymap;
+    let mut title = match mode {
+        PaneMode::Split if view_model.detail.is_some() && view_model.focus_detail => {
+            if view_model.detail_breadcrumb.is_empty() {
+                "Details · f filter".to_string()
+            } else {
+                "Details".to_string()
+            }
+        }
+        PaneMode::Split => "Details".to_string(),
+        PaneMode::Compact => format!("Details · {}/Esc back", keymap.hint(Action::ToggleFocus)),
+        PaneMode::Fullscreen => {
+            format!("Details · {}/Esc restore", keymap.hint(Action::Fullscreen))
+        }
+    };
+    if view_model.focus_detail && !view_model.detail_breadcrumb.is_empty() {
+        // Borders and the separator take 5 columns; the path keeps its tail.
+        let room = usize::from(area.width).saturating_sub(title.chars().count() + 5);
+        title = format!(
+            "{} · {}",
+            title,
+            fit_breadcrumb(&view_model.detail_breadcrumb, room)
+        );
+    }
     let block = Block::default()
-        .title("Details")
+        .title(title)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(if view_model.focus_detail {
             Color::Cyan
@@ -387,88 +1246,73 @@ fn render_detail(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel) {
 
     if let Some(detail) = &view_model.detail {
         let state_view = view_model.detail_state.as_ref();
-        let (visible_indices, has_children) =
-            detail::visible_indices_with_children(detail, state_view.map(|state| &state.collapsed));
-
-        let mut lines: Vec<Line> = Vec::new();
+        let collapsed = state_view.map(|state| &state.collapsed);
+        let rows = match &view_model.detail_rows {
+            Some(rows) if rows.width() == inner_area.width => Cow::Borrowed(rows.as_ref()),
+            _ => Cow::Owned(DetailRows::new(detail, collapsed, inner_area.width)),
+        };
+        let visible_len = rows.rows().last().map_or(0, |row| row.position + 1);
 
+        let mut head: Vec<Line> = Vec::new();
         if !detail.header.is_empty() {
-            lines.push(Line::from(vec![Span::styled(
+            head.push(Line::from(vec![Span::styled(
                 detail.header.clone(),
                 Style::default()
                     .fg(Color::LightBlue)
                     .add_modifier(Modifier::BOLD),
             )]));
-            lines.push(Line::default());
+            head.push(match &view_model.detail_filter {
+                Some(filter) => filter_box_line(filter),
+                None => Line::default(),
+            });
         }
-
-        let highlight_target = state_view
-            .filter(|_| view_model.focus_detail)
-            .map(|state| state.cursor.min(visible_indices.len().saturating_sub(1)));
-
-        for (position, &line_index) in visible_indices.iter().enumerate() {
-            let detail_line = &detail.lines[line_index];
-            let mut spans = Vec::new();
-
-            let is_selected = highlight_target == Some(position);
-
-            let highlight_style = if is_selected {
-                Some(
-                    Style::default()
-                        .bg(Color::DarkGray)
-                        .add_modifier(Modifier::BOLD),
-                )
-            } else {
-                None
-            };
-
-            let collapsed_here = state_view
-                .map(|state| state.collapsed.contains(&line_index))
-                .unwrap_or(false);
-
-            let icon = if has_children[line_index] {
-                if collapsed_here { "+ " } else { "- " }
-            } else {
-                "  "
-            };
-
-            let mut indent_style = Style::default().fg(Color::DarkGray);
-            if let Some(style) = highlight_style {
-                indent_style = indent_style.patch(style);
-            }
-
-            if detail_line.indent > 0 {
-                spans.push(Span::styled("  ".repeat(detail_line.indent), indent_style));
-            }
-
-            spans.push(Span::styled(icon.to_string(), indent_style));
-
-            for segment in &detail_line.segments {
-                let mut style = style_for_segment(segment);
-                if let Some(highlight) = highlight_style {
-                    style = style.patch(highlight);
-                }
-                spans.push(Span::styled(segment.text.clone(), style));
-            }
-
-            lines.push(Line::from(spans));
-        }
-
-        if !detail.footer.is_empty() {
-            lines.push(Line::default());
-            lines.push(Line::from(vec![Span::styled(
-                detail.footer.clone(),
+        let mut tail: Vec<Line> = Vec::new();
+        let footer = view_model.detail_footer.as_ref().unwrap_or(&detail.footer);
+        if !footer.is_empty() {
+            tail.push(Line::default());
+            tail.push(Line::from(vec![Span::styled(
+                footer.clone(),
                 Style::default()
                     .fg(Color::DarkGray)
                     .add_modifier(Modifier::ITALIC),
             )]));
         }
+        let wrap = |lines: Vec<Line>| -> Vec<Line> {
+            lines
+                .iter()
+                .flat_map(|line| detail_rows::wrap_line(line, inner_area.width))
+                .collect()
+        };
 
-        let scroll = view_model.detail_scroll.min(u16::MAX as usize) as u16;
-        let paragraph = Paragraph::new(lines)
-            .wrap(Wrap { trim: false })
-            .scroll((scroll, 0));
-        frame.render_widget(paragraph, inner_area);
+        let highlight_target = state_view
+            .filter(|_| view_model.focus_detail)
+            .map(|state| state.cursor.min(visible_len.saturating_sub(1)));
+        let highlight = Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD);
+        let (head, body, tail) = (wrap(head), rows.rows(), wrap(tail));
+
+        // Only the rows on screen are styled and handed to the paragraph.
+        let scroll = view_model.detail_scroll;
+        let head_len = head.len();
+        let body_start = scroll.saturating_sub(head_len).min(body.len());
+        let lines: Vec<Line> = head
+            .into_iter()
+            .skip(scroll)
+            .chain(body[body_start..].iter().map(|row| {
+                if highlight_target == Some(row.position) {
+                    row.line.clone().patch_style(highlight)
+                } else {
+                    row.line.clone()
+                }
+            }))
+            .chain(
+                tail.into_iter()
+                    .skip(scroll.saturating_sub(head_len + body.len())),
+            )
+            .take(usize::from(inner_area.height))
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner_area);
     } else {
         let paragraph =
             Paragraph::new("No event selected").style(Style::default().fg(Color::DarkGray));
@@ -476,6 +1320,43 @@ fn render_detail(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel) {
     }
 }
 
+/// `user → roles → [0]`, dropping leading segments behind `…` until it
+/// fits in `width` columns.
+fn fit_breadcrumb(breadcrumb: &[String], width: usize) -> String {
+    for skip in 0..breadcrumb.len() {
+        let mut text = breadcrumb[skip..].join(" → ");
+        if skip > 0 {
+            text = format!("… → {}", text);
+        }
+        if grapheme_count(&text) <= width {
+            return text;
+        }
+    }
+    let last = breadcrumb.last().map(String::as_str).unwrap_or_default();
+    format!("…{}", text::clip_start(last, width.saturating_sub(1), ""))
+}
+
+/// `/ memory▏ 3 of 42` in place of the blank line under the detail header.
+fn filter_box_line(filter: &DetailFilterView) -> Line<'static> {
+    let accent = Style::default().fg(Color::LightBlue);
+    let mut spans = vec![
+        Span::styled("/ ", accent.add_modifier(Modifier::BOLD)),
+        Span::raw(filter.query.clone()),
+    ];
+    if filter.editing {
+        spans.push(Span::styled("▏", accent));
+    }
+    spans.push(Span::styled(
+        format!("  {} of {}", filter.matches, filter.total),
+        Style::default().fg(if filter.matches == 0 {
+            Color::LightRed
+        } else {
+            Color::DarkGray
+        }),
+    ));
+    Line::from(spans)
+}
+
 struct EmptyTimelineMessage<'a> {
     view_model: &'a AppViewModel,
 }
@@ -486,8 +1367,15 @@ impl<'a> EmptyTimelineMessage<'a> {
     }
 
     fn render(self, frame: &mut Frame<'_>, area: Rect) {
+        // A wrapped banner is just noise, so narrow panes skip it.
+        let banner_width = RAYGUN_BANNER[0].chars().count();
+        let banner = if usize::from(area.width) >= banner_width {
+            RAYGUN_BANNER
+        } else {
+            &[]
+        };
         let mut lines: Vec<Line> = std::iter::once(Line::default())
-            .chain(RAYGUN_BANNER.iter().map(|line| {
+            .chain(banner.iter().map(|line| {
                 Line::styled(
                     *line,
                     Style::default()
@@ -499,18 +1387,41 @@ impl<'a> EmptyTimelineMessage<'a> {
 
         lines.push(Line::default());
 
-        if let Some(filter) = &self.view_model.active_color_filter {
+        let history = self
+            .view_model
+            .history
+            .as_ref()
+            .and_then(|history| history.results_for.as_deref());
+        if let Some(query) = history {
+            lines.push(Line::from(vec![Span::styled(
+                format!("No stored events match `{}`.", query),
+                Style::default().fg(Color::Gray),
+            )]));
+            lines.push(Line::from(vec![Span::styled(
+                format!(
+                    "Press `{}` to search again or Esc to return to the live timeline.",
+                    self.view_model.settings.keymap.hint(Action::HistorySearch)
+                ),
+                Style::default().fg(Color::DarkGray),
+            )]));
+        } else if let Some(filter) = &self.view_model.active_color_filter {
             lines.push(Line::from(vec![Span::styled(
                 format!("No payloads match color filter `{}`.", filter),
                 Style::default().fg(Color::Gray),
             )]));
             lines.push(Line::from(vec![Span::styled(
-                "Press `f` to clear the filter or send a payload.",
+                format!(
+                    "Press `{}` to clear the filter or send a payload.",
+                    self.view_model
+                        .settings
+                        .keymap
+                        .hint(Action::CycleColorFilter)
+                ),
                 Style::default().fg(Color::DarkGray),
             )]));
         } else {
             lines.push(Line::from(vec![Span::styled(
-                format!("Listening on {}", self.view_model.bind_addr),
+                format!("Listening on {}", format_addrs(&self.view_model.bind_addrs)),
                 Style::default().fg(Color::Gray),
             )]));
             lines.push(Line::from(vec![Span::styled(
@@ -518,7 +1429,10 @@ impl<'a> EmptyTimelineMessage<'a> {
                 Style::default().fg(Color::DarkGray),
             )]));
             lines.push(Line::from(vec![Span::styled(
-                "Press `q` to exit.",
+                format!(
+                    "Press `{}` to exit.",
+                    self.view_model.settings.keymap.hint(Action::Quit)
+                ),
                 Style::default().fg(Color::DarkGray),
             )]));
         }
@@ -531,13 +1445,78 @@ impl<'a> EmptyTimelineMessage<'a> {
     }
 }
 
-fn render_footer(frame: &mut Frame<'_>, area: Rect) {
-    let block = Block::default()
-        .borders(Borders::TOP)
-        .title("Keymap")
-        .style(Style::default().fg(Color::DarkGray));
+/// Seven source lines plus the border.
+const SOURCE_PREVIEW_HEIGHT: u16 = 9;
+
+fn render_source_preview(frame: &mut Frame<'_>, area: Rect, preview: &SourcePreview) {
+    let muted = Style::default().fg(Color::DarkGray);
+    let width = preview
+        .lines
+        .last()
+        .map(|(number, _)| number.to_string().len())
+        .unwrap_or(1);
+
+    let mut lines: Vec<Line> = preview
+        .lines
+        .iter()
+        .map(|(number, text)| {
+            let current = preview.highlight == Some(*number);
+            let style = if current {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{}{:>width$} ", if current { "▶" } else { " " }, number),
+                    muted,
+                ),
+                Span::styled(text.replace('\t', "    "), style),
+            ])
+        })
+        .collect();
+    if let Some(message) = &preview.message {
+        lines.push(Line::from(Span::styled(message.clone(), muted)));
+    }
 
-    let content = Paragraph::new("? help · f cycle color · ctrl+l cycle layout · ctrl+k clear timeline · ctrl+d raw payload · Tab focus detail · ↑/↓ navigate · PgUp/PgDn jump · Enter/→ expand · ← collapse · Space toggle · q quit · ctrl+c force quit")
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Source · {}", preview.location))
+            .title_style(Style::default().fg(Color::LightBlue))
+            .border_style(muted),
+    );
+    frame.render_widget(paragraph, area);
+}
+
+fn render_footer(frame: &mut Frame<'_>, area: Rect, view_model: &AppViewModel) {
+    let block = match &view_model.status {
+        Some(status) => Block::default()
+            .borders(Borders::TOP)
+            .title(Span::styled(
+                status.clone(),
+                Style::default().fg(Color::Yellow),
+            ))
+            .style(Style::default().fg(Color::DarkGray)),
+        None => Block::default()
+            .borders(Borders::TOP)
+            .title("Keymap")
+            .style(Style::default().fg(Color::DarkGray)),
+    };
+
+    let keymap = &view_model.settings.keymap;
+    let content = Paragraph::new(format!(
+        "{} help · {} cycle color · {} cycle layout · {} clear timeline · {} raw payload · {} focus detail · ↑/↓ navigate · PgUp/PgDn jump · Enter/→ expand · ← collapse · Space toggle · {} quit · ctrl+c force quit",
+        keymap.hint(Action::Help),
+        keymap.hint(Action::CycleColorFilter),
+        keymap.hint(Action::CycleLayout),
+        keymap.hint(Action::ClearTimeline),
+        keymap.hint(Action::RawPayload),
+        keymap.hint(Action::ToggleFocus),
+        keymap.hint(Action::Quit),
+    ))
     .style(Style::default().fg(Color::DarkGray));
 
     frame.render_widget(block, area);
@@ -553,6 +1532,69 @@ fn render_footer(frame: &mut Frame<'_>, area: Rect) {
     }
 }
 
+/// Rewrites the glyphs the UI draws (borders, bullets, markers, arrows, the
+/// banner) to ASCII, one cell for one cell so the layout is unchanged.
+fn asciify(buffer: &mut Buffer) {
+    let area = buffer.area;
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            let cell = buffer.get_mut(x, y);
+            if let Some(ascii) = ascii_glyph(cell.symbol()) {
+                cell.set_symbol(ascii);
+            }
+        }
+    }
+}
+
+fn ascii_glyph(symbol: &str) -> Option<&'static str> {
+    let glyph = match symbol {
+        "─" | "━" | "═" | "╌" => "-",
+        "│" | "┃" | "║" | "╎" | "▏" => "|",
+        "┌" | "┐" | "└" | "┘" | "├" | "┤" | "┬" | "┴" | "┼" | "╭" | "╮" | "╯" | "╰" | "╔" | "╗"
+        | "╚" | "╝" | "╠" | "╣" | "╦" | "╩" | "╬" => "+",
+        "█" | "■" => "#",
+        "⬤" | "●" | "•" => "*",
+        "▲" | "↑" => "^",
+        "↓" => "v",
+        "◆" => "@",
+        "★" => "%",
+        "○" => "o",
+        "◇" => "+",
+        "·" | "—" | "–" => "-",
+        "…" => ".",
+        "▶" | "▸" | "→" => ">",
+        "←" | "◂" => "<",
+        "⚠" => "!",
+        "×" => "x",
+        _ => return None,
+    };
+    Some(glyph)
+}
+
+/// Marks a timeline row other attached clients have selected, e.g. ` ◂2`.
+/// Follows the age of events that arrived since the last interaction.
+const UNREAD_MARKER: &str = " new";
+
+fn viewer_marker(viewers: usize) -> String {
+    match viewers {
+        0 => String::new(),
+        1 => " ◂".to_string(),
+        count => format!(" ◂{}", count),
+    }
+}
+
+fn clip_to_width(text: &str, width: usize) -> String {
+    text::clip(text, width, "…")
+}
+
+fn format_addrs(addrs: &[SocketAddr]) -> String {
+    addrs
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn inner(area: Rect) -> Rect {
     Rect {
         x: area.x + 1,
@@ -582,33 +1624,108 @@ fn centered_rect(width_percent: u16, height_percent: u16, area: Rect) -> Rect {
         .split(vertical[1])[1]
 }
 
+/// A rectangle of at most `width` x `height` cells centered in `area`.
+fn fixed_centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 fn render_help_overlay(frame: &mut Frame<'_>, view_model: &AppViewModel, area: Rect) {
     frame.render_widget(Clear, area);
 
-    let mut lines = Vec::new();
-    lines.push(Line::from(Span::styled(
-        "Keymap & Controls",
-        Style::default()
-            .fg(Color::LightBlue)
-            .add_modifier(Modifier::BOLD),
-    )));
-    lines.push(Line::raw(""));
-    lines.push(Line::from(vec![
-        Span::styled(
-            "Navigation: ",
-            Style::default().add_modifier(Modifier::BOLD),
-        ),
-        Span::raw("↑/↓, j/k move · PgUp/PgDn jump · Home/End to bounds · Tab switches focus"),
-    ]));
-    lines.push(Line::from(vec![
-        Span::styled("Details: ", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw("Enter/→ expand · ← collapse · Space toggle · Ctrl+L cycle layout"),
-    ]));
+    let keymap = &view_model.settings.keymap;
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Keymap & Controls",
+            Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::raw(""),
+        Line::from(vec![
+            Span::styled(
+                "Navigation: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                "↑/↓, j/k move · PgUp/PgDn jump · Home/End to bounds · {} switches focus · / filters the timeline · Enter opens event actions",
+                keymap.hint(Action::ToggleFocus)
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("Details: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!(
+                "Enter/→ expand · ← collapse · Space toggle · {} (or /) filter · {} copy key path · {} copy value as raw/JSON/PHP · {} cycle layout",
+                keymap.hint(Action::DetailFilter),
+                keymap.hint(Action::CopyPath),
+                keymap.hint(Action::CopyValue),
+                keymap.hint(Action::CycleLayout)
+            )),
+        ]),
+        Line::from(vec![
+            Span::styled("Global: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!(
+                "{} cycle color filter · {} clear timeline · {} clear events matching the filters · {} hide noise · {} search history · {} export HTTP calls as HAR · {} export a Markdown report · {} scratch pad for pasted dumps · {} mark range start/end · {}/{} previous/next request · {} next unread event · {} raw payload · {} payload schema · {} query the payload JSON · {} protocol diagnostics · {} server problems · {} logs and log filter · {} unknown payload types · {} trash · {} busiest call sites · {} exceptions by class and message · {} processing delays · {} frame profiling · {} edit the event's label · {} cycle the event's color · {} cycle the event's renderer · {} move the event to a screen · {} rename its screen · {} close its screen · {} move the HTTP server · {} focus-follow · {} fullscreen pane · Esc closes overlays · {} close help · {} quit · Ctrl+C force quit",
+                keymap.hint(Action::CycleColorFilter),
+                keymap.hint(Action::ClearTimeline),
+                keymap.hint(Action::ClearMatching),
+                keymap.hint(Action::HideNoise),
+                keymap.hint(Action::HistorySearch),
+                keymap.hint(Action::ExportHar),
+                keymap.hint(Action::ExportMarkdown),
+                keymap.hint(Action::Scratch),
+                keymap.hint(Action::MarkRange),
+                keymap.hint(Action::PreviousRequest),
+                keymap.hint(Action::NextRequest),
+                keymap.hint(Action::NextUnread),
+                keymap.hint(Action::RawPayload),
+                keymap.hint(Action::Schema),
+                keymap.hint(Action::JsonQuery),
+                keymap.hint(Action::Diagnostics),
+                keymap.hint(Action::Problems),
+                keymap.hint(Action::Logs),
+                keymap.hint(Action::UnknownKinds),
+                keymap.hint(Action::Trash),
+                keymap.hint(Action::Origins),
+                keymap.hint(Action::Exceptions),
+                keymap.hint(Action::Stats),
+                keymap.hint(Action::Profile),
+                keymap.hint(Action::EditLabel),
+                keymap.hint(Action::CycleColor),
+                keymap.hint(Action::CycleRenderer),
+                keymap.hint(Action::MoveToScreen),
+                keymap.hint(Action::RenameScreen),
+                keymap.hint(Action::CloseScreen),
+                keymap.hint(Action::Rebind),
+                keymap.hint(Action::FocusFollow),
+                keymap.hint(Action::Fullscreen),
+                keymap.hint(Action::Help),
+                keymap.hint(Action::Quit),
+            )),
+        ]),
+    ];
+
     lines.push(Line::from(vec![
-        Span::styled("Global: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled("Views: ", Style::default().add_modifier(Modifier::BOLD)),
         Span::raw(
-            "f cycle color filter · ctrl+k clear timeline · ctrl+d raw payload · Esc closes overlays · ? close help · q quit · Ctrl+C force quit",
+            view_model
+                .views
+                .iter()
+                .enumerate()
+                .map(|(index, name)| format!("{} {} · ", index + 1, name))
+                .collect::<String>(),
         ),
+        Span::raw(format!(
+            "{} saves the current filters as a view",
+            keymap.hint(Action::SaveView)
+        )),
     ]));
 
     if !view_model.available_colors.is_empty() {
@@ -619,19 +1736,46 @@ fn render_help_overlay(frame: &mut Frame<'_>, view_model: &AppViewModel, area: R
             Style::default().add_modifier(Modifier::BOLD),
         ));
         for color in &view_model.available_colors {
-            let block_style = color_from_name(color)
+            let block_style = view_model
+                .settings
+                .color(color)
                 .map(|color| Style::default().bg(color).fg(Color::Black))
                 .unwrap_or_else(|| Style::default().bg(Color::DarkGray).fg(Color::Black));
             spans.push(Span::styled("  ", block_style));
+            if view_model.settings.palette != Palette::Default {
+                spans.push(Span::raw(format!(
+                    " {}",
+                    view_model.settings.color_marker(Some(color))
+                )));
+            }
             spans.push(Span::raw(format!(" {}  ", color)));
         }
         lines.push(Line::from(spans));
     }
 
     lines.push(Line::raw(""));
-    lines.push(Line::from(
-        "Tips: use `f` repeatedly to cycle colors; when no color matches the filter, the timeline shows a hint.",
-    ));
+    lines.push(Line::from(format!(
+        "Tips: use `{}` repeatedly to cycle colors; when no color matches the filter, the timeline shows a hint.",
+        keymap.hint(Action::CycleColorFilter)
+    )));
+
+    lines.push(Line::raw(""));
+    let mut endpoint_spans = vec![
+        Span::styled("Listening: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format_addrs(&view_model.bind_addrs)),
+    ];
+    if let Some(url) = &view_model.public_url {
+        endpoint_spans.push(Span::raw(" · "));
+        endpoint_spans.push(Span::styled(
+            "Public URL: ",
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+        endpoint_spans.push(Span::styled(
+            url.clone(),
+            Style::default().fg(Color::LightGreen),
+        ));
+    }
+    lines.push(Line::from(endpoint_spans));
 
     lines.push(Line::raw(""));
     lines.push(Line::from(vec![
@@ -650,12 +1794,18 @@ fn render_help_overlay(frame: &mut Frame<'_>, view_model: &AppViewModel, area: R
     frame.render_widget(paragraph, area);
 }
 
-fn render_debug_overlay(frame: &mut Frame<'_>, json: &str, scroll: usize, area: Rect) {
+fn render_debug_overlay(
+    frame: &mut Frame<'_>,
+    json: &str,
+    scroll: usize,
+    close_hint: &str,
+    area: Rect,
+) {
     frame.render_widget(Clear, area);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .title("Raw Payload (Ctrl+D or Esc to close)")
+        .title(format!("Raw Payload ({} or Esc to close)", close_hint))
         .padding(Padding::uniform(1))
         .border_style(Style::default().fg(Color::Magenta));
 
@@ -668,38 +1818,82 @@ fn render_debug_overlay(frame: &mut Frame<'_>, json: &str, scroll: usize, area:
     frame.render_widget(paragraph, area);
 }
 
-fn color_from_name(name: &str) -> Option<Color> {
-    let normalized = name.trim().to_lowercase();
-    match normalized.as_str() {
-        "red" => Some(Color::Rgb(255, 82, 82)),
-        "green" => Some(Color::Rgb(48, 209, 88)),
-        "blue" => Some(Color::Rgb(64, 156, 255)),
-        "yellow" => Some(Color::Rgb(255, 214, 10)),
-        "orange" => Some(Color::Rgb(255, 159, 10)),
-        "purple" | "magenta" => Some(Color::Rgb(191, 90, 242)),
-        "pink" => Some(Color::Rgb(255, 55, 95)),
-        "gray" | "grey" => Some(Color::Rgb(138, 141, 165)),
-        "white" => Some(Color::White),
-        "black" => Some(Color::Black),
-        "cyan" => Some(Color::Rgb(100, 210, 255)),
-        "teal" => Some(Color::Rgb(64, 200, 224)),
-        "lightblue" => Some(Color::Rgb(173, 216, 230)),
-        "lightgreen" => Some(Color::Rgb(144, 238, 144)),
-        "brown" => Some(Color::Rgb(141, 110, 99)),
-        _ => {
-            let hex = normalized.strip_prefix('#').unwrap_or(&normalized);
-            if hex.len() == 6 && hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
-                if let (Ok(r), Ok(g), Ok(b)) = (
-                    u8::from_str_radix(&hex[0..2], 16),
-                    u8::from_str_radix(&hex[2..4], 16),
-                    u8::from_str_radix(&hex[4..6], 16),
-                ) {
-                    return Some(Color::Rgb(r, g, b));
-                }
-            }
-            None
-        }
-    }
+fn render_schema_overlay(
+    frame: &mut Frame<'_>,
+    schema: &[DetailLine],
+    scroll: usize,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> =
+        if schema.is_empty() {
+            vec![Line::from(Span::styled(
+                "The selected event has no payload content.",
+                Style::default().fg(Color::DarkGray),
+            ))]
+        } else {
+            schema
+                .iter()
+                .map(|line| {
+                    let mut spans = vec![Span::raw("  ".repeat(line.indent))];
+                    spans.extend(line.segments.iter().map(|segment| {
+                        Span::styled(segment.text.clone(), style_for_segment(segment))
+                    }));
+                    Line::from(spans)
+                })
+                .collect()
+        };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Payload Schema ({} or Esc to close)", close_hint))
+        .padding(Padding::uniform(1))
+        .border_style(Style::default().fg(Color::LightBlue));
+
+    let paragraph = Paragraph::new(lines)
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0))
+        .block(block);
+
+    frame.render_widget(paragraph, area);
+}
+
+fn render_diff_overlay(frame: &mut Frame<'_>, diff: &DiffView, scroll: usize, area: Rect) {
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = diff
+        .lines
+        .iter()
+        .map(|line| {
+            let (marker, style) = match line.op {
+                DiffOp::Same => (' ', Style::default().fg(Color::Gray)),
+                DiffOp::Added => ('+', Style::default().fg(Color::LightGreen)),
+                DiffOp::Removed => ('-', Style::default().fg(Color::LightRed)),
+            };
+            Line::from(Span::styled(format!("{} {}", marker, line.text), style))
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("{} (Esc to close)", diff.title))
+        .padding(Padding::uniform(1))
+        .border_style(Style::default().fg(Color::Magenta));
+
+    let paragraph = Paragraph::new(lines)
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0))
+        .block(block);
+
+    frame.render_widget(paragraph, area);
+}
+
+pub(crate) fn color_from_name(name: &str) -> Option<Color> {
+    color::parse(name).map(|color| match color {
+        ThemeColor::White => Color::White,
+        ThemeColor::Black => Color::Black,
+        ThemeColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    })
 }
 
 fn style_for_segment(segment: &DetailSegment) -> Style {
@@ -711,5 +1905,888 @@ fn style_for_segment(segment: &DetailSegment) -> Style {
         SegmentStyle::Number => Style::default().fg(Color::LightMagenta),
         SegmentStyle::Boolean => Style::default().fg(Color::LightBlue),
         SegmentStyle::Null => Style::default().fg(Color::DarkGray),
+        SegmentStyle::Notice => Style::default()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::BOLD),
+        SegmentStyle::Badge => Style::default()
+            .fg(Color::Black)
+            .bg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+        SegmentStyle::Added => Style::default().fg(Color::LightGreen),
+        SegmentStyle::Removed => Style::default().fg(Color::LightRed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, time::UNIX_EPOCH};
+
+    use serde_json::json;
+
+    use super::{exceptions::ExceptionRow, origins::OriginRow, *};
+    use crate::{protocol::Payload, ui::detail::build_detail_view};
+
+    fn base_view_model() -> AppViewModel {
+        AppViewModel {
+            total_events: 0,
+            noise_hidden: false,
+            unread: 0,
+            bind_addrs: vec!["127.0.0.1:23517".parse().unwrap()],
+            public_url: None,
+            timeline: Vec::new(),
+            selected: None,
+            detail: None,
+            detail_footer: None,
+            focus_detail: false,
+            detail_scroll: 0,
+            layout: LayoutConfig {
+                timeline_percent: 50,
+                detail_percent: 50,
+            },
+            detail_state: None,
+            detail_rows: None,
+            active_color_filter: None,
+            event_filter: None,
+            prompt: None,
+            problems: None,
+            available_colors: Vec::new(),
+            views: Vec::new(),
+            show_help: false,
+            onboarding: None,
+            debug_json: None,
+            debug_scroll: 0,
+            schema: None,
+            schema_scroll: 0,
+            quick_actions: None,
+            diff: None,
+            diff_scroll: 0,
+            status: None,
+            diagnostic_count: 0,
+            diagnostics: None,
+            logs: None,
+            unknown_kinds: None,
+            trash: None,
+            origins: None,
+            exceptions: None,
+            json_query: None,
+            stats: None,
+            profile: None,
+            source_preview: None,
+            fullscreen: false,
+            viewers: None,
+            history: None,
+            detail_filter: None,
+            detail_breadcrumb: Vec::new(),
+            settings: Arc::new(UiSettings::default()),
+        }
+    }
+
+    /// Compares `actual` with `src/tui/snapshots/<name>.txt`. Run the tests
+    /// with `RAYGUN_UPDATE_SNAPSHOTS=1` to accept new output.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/tui/snapshots")
+            .join(format!("{}.txt", name));
+
+        if std::env::var_os("RAYGUN_UPDATE_SNAPSHOTS").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, actual).unwrap();
+            return;
+        }
+
+        let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+            panic!(
+                "missing snapshot {} ({}); rerun with RAYGUN_UPDATE_SNAPSHOTS=1",
+                path.display(),
+                err
+            )
+        });
+        assert!(
+            expected == actual,
+            "snapshot `{}` changed; rerun with RAYGUN_UPDATE_SNAPSHOTS=1 if intended\n--- expected\n{}\n--- actual\n{}",
+            name,
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn snapshot_empty_timeline() {
+        let (buffer, metadata) = render_to_buffer(&base_view_model(), 100, 30).unwrap();
+
+        assert!(metadata.overlay.is_none());
+        assert_snapshot("empty_timeline", &buffer_to_text(&buffer));
+    }
+
+    #[test]
+    fn snapshot_timeline_with_detail() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "table",
+            "content": {
+                "values": { "method": "POST", "uri": "/checkout", "status": 201 },
+                "label": "Request"
+            },
+            "origin": { "file": "/var/www/app/Http/Middleware/LogRequest.php", "line_number": 21 }
+        }))
+        .unwrap();
+        let received_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let detail = build_detail_view(&payload, received_at);
+
+        let mut view_model = base_view_model();
+        view_model.total_events = 2;
+        view_model.timeline = vec![
+            TimelineEntry {
+                id: Uuid::nil(),
+                kind: "table".to_string(),
+                summary: "Request".to_string(),
+                age: "2s".to_string(),
+                color: Some("blue".to_string()),
+                label: Some("Request".to_string()),
+                viewers: 0,
+                range: None,
+                request: None,
+                host: None,
+                origin: None,
+                format: None,
+                unread: false,
+            },
+            TimelineEntry {
+                id: Uuid::from_u128(1),
+                kind: "log".to_string(),
+                summary: "\"hello\"".to_string(),
+                age: "1m".to_string(),
+                color: None,
+                label: None,
+                viewers: 0,
+                range: None,
+                request: None,
+                host: None,
+                origin: None,
+                format: None,
+                unread: false,
+            },
+        ];
+        view_model.selected = Some(0);
+        view_model.detail = Some(Arc::new(detail));
+        view_model.detail_state = Some(DetailStateView {
+            cursor: 0,
+            collapsed: HashSet::new(),
+        });
+
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        assert_snapshot("timeline_with_detail", &buffer_to_text(&buffer));
+    }
+
+    #[test]
+    fn long_summaries_are_clipped_to_the_timeline_width() {
+        let mut view_model = base_view_model();
+        view_model.total_events = 1;
+        view_model.timeline = vec![TimelineEntry {
+            id: Uuid::nil(),
+            kind: "log".to_string(),
+            summary: "x".repeat(200),
+            age: "3s".to_string(),
+            color: None,
+            label: Some("Users".to_string()),
+            viewers: 0,
+            range: None,
+            request: None,
+            host: None,
+            origin: None,
+            format: None,
+            unread: false,
+        }];
+        view_model.selected = Some(0);
+
+        for width in [60, 140] {
+            let (buffer, _) = render_to_buffer(&view_model, width, 30).unwrap();
+            let text = buffer_to_text(&buffer);
+            let row = text
+                .lines()
+                .find(|line| line.contains("[log]"))
+                .expect("timeline row should render");
+            assert!(row.contains("x… · 3s (Users)"), "row: {}", row);
+        }
+    }
+
+    #[test]
+    fn timeline_rows_show_the_call_site_as_room_allows() {
+        let mut view_model = base_view_model();
+        view_model.total_events = 1;
+        let entry = |summary: &str| TimelineEntry {
+            id: Uuid::nil(),
+            kind: "log".to_string(),
+            summary: summary.to_string(),
+            age: "3s".to_string(),
+            color: None,
+            label: None,
+            viewers: 0,
+            range: None,
+            request: None,
+            host: None,
+            origin: Some("app/Http/Controllers/OrderController.php:88".to_string()),
+            format: None,
+            unread: false,
+        };
+        view_model.selected = Some(0);
+
+        let row = |view_model: &AppViewModel, width| {
+            let (buffer, _) = render_to_buffer(view_model, width, 30).unwrap();
+            buffer_to_text(&buffer)
+                .lines()
+                .find(|line| line.contains("[log]"))
+                .expect("timeline row should render")
+                .to_string()
+        };
+
+        view_model.timeline = vec![entry("\"paid\"")];
+        let wide = row(&view_model, 140);
+        assert!(
+            wide.contains("\"paid\"  app/Http/Controllers/OrderController.php:88 · 3s"),
+            "{}",
+            wide
+        );
+        let narrow = row(&view_model, 60);
+        assert!(
+            narrow.contains("\"paid\"  …/Controllers/OrderController.php:88 · 3s"),
+            "{}",
+            narrow
+        );
+
+        // A long summary gives up room for the file name only.
+        view_model.timeline = vec![entry(&"x".repeat(200))];
+        let long = row(&view_model, 100);
+        assert!(long.contains("x…  OrderController.php:88 · 3s"), "{}", long);
+        assert!(!row(&view_model, 60).contains("OrderController"));
+
+        assert_eq!(
+            shorten_origin("app/Http/Controllers/OrderController.php:88", 41).as_deref(),
+            Some("…/Http/Controllers/OrderController.php:88")
+        );
+        assert_eq!(shorten_origin("OrderController.php:88", 5), None);
+    }
+
+    #[test]
+    fn attached_clients_show_viewers_and_their_cursors() {
+        let mut view_model = base_view_model();
+        view_model.total_events = 1;
+        view_model.viewers = Some(3);
+        view_model.timeline = vec![TimelineEntry {
+            id: Uuid::nil(),
+            kind: "log".to_string(),
+            summary: "\"hello\"".to_string(),
+            age: "3s".to_string(),
+            color: None,
+            label: None,
+            viewers: 2,
+            range: None,
+            request: None,
+            host: None,
+            origin: None,
+            format: None,
+            unread: false,
+        }];
+        view_model.selected = Some(0);
+
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("| 3 viewers"), "{}", text);
+        assert!(text.contains("3s ◂2"), "{}", text);
+    }
+
+    #[test]
+    fn marks_unread_events_and_counts_them_in_the_header() {
+        let mut view_model = base_view_model();
+        view_model.total_events = 2;
+        view_model.unread = 1;
+        let entry = |summary: &str, unread| TimelineEntry {
+            id: Uuid::new_v4(),
+            kind: "log".to_string(),
+            summary: summary.to_string(),
+            age: "1s".to_string(),
+            color: None,
+            label: None,
+            viewers: 0,
+            range: None,
+            request: None,
+            host: None,
+            origin: None,
+            format: None,
+            unread,
+        };
+        view_model.timeline = vec![entry("fresh", true), entry("seen", false)];
+        view_model.selected = Some(1);
+
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("1 unread (u)"), "{}", text);
+        assert!(text.contains("fresh · 1s new"), "{}", text);
+        assert!(!text.contains("seen · 1s new"), "{}", text);
+    }
+
+    #[test]
+    fn origins_overlay_ranks_call_sites() {
+        let mut view_model = base_view_model();
+        view_model.origins = Some(OriginsView {
+            rows: vec![
+                OriginRow {
+                    location: "app/Jobs/Sync.php:12".to_string(),
+                    kind: "log".to_string(),
+                    count: 30,
+                },
+                OriginRow {
+                    location: "app/Order.php:40".to_string(),
+                    kind: "query".to_string(),
+                    count: 3,
+                },
+            ],
+            total: 40,
+            cursor: 1,
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(matches!(metadata.overlay, Some(OverlayArea::Origins(_))));
+        assert!(text.contains("Origins (Enter shows only"), "{}", text);
+        assert!(
+            text.contains("  ████████████ 30  75% app/Jobs/Sync.php:12  [log]"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("▸ ██            3   7% app/Order.php:40  [query]"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn exceptions_overlay_groups_by_class_and_message() {
+        let mut view_model = base_view_model();
+        view_model.exceptions = Some(ExceptionsView {
+            rows: vec![
+                ExceptionRow {
+                    class: "ModelNotFoundException".to_string(),
+                    message: "No query results for model [App\\Order] 42".to_string(),
+                    count: 12,
+                    first_seen: "5m 02s ago".to_string(),
+                    last_seen: "3s ago".to_string(),
+                },
+                ExceptionRow {
+                    class: "RuntimeException".to_string(),
+                    message: "boom".to_string(),
+                    count: 1,
+                    first_seen: "40s ago".to_string(),
+                    last_seen: "40s ago".to_string(),
+                },
+            ],
+            cursor: 1,
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(matches!(metadata.overlay, Some(OverlayArea::Exceptions(_))));
+        assert!(text.contains("13 exceptions in 2 groups"), "{}", text);
+        assert!(
+            text.contains(
+                "  12× ModelNotFoundException  No query results for model [App\\Order] 42"
+            ),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("       first 5m 02s ago · last 3s ago"),
+            "{}",
+            text
+        );
+        assert!(text.contains("▸  1× RuntimeException  boom"), "{}", text);
+    }
+
+    #[test]
+    fn trash_overlay_lists_deleted_events() {
+        let mut view_model = base_view_model();
+        view_model.trash = Some(TrashView {
+            entries: vec![TimelineEntry {
+                id: Uuid::nil(),
+                kind: "log".to_string(),
+                summary: "deleted by accident".to_string(),
+                age: "2m".to_string(),
+                color: None,
+                label: None,
+                viewers: 0,
+                range: None,
+                request: None,
+                host: None,
+                origin: None,
+                format: None,
+                unread: false,
+            }],
+            cursor: 0,
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(matches!(metadata.overlay, Some(OverlayArea::Trash(_))));
+        assert!(
+            text.contains("Trash (Enter restores · T or Esc"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("▸ [log] deleted by accident · 2m"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn json_query_overlay_shows_each_match() {
+        let mut view_model = base_view_model();
+        view_model.json_query = Some(JsonQueryView {
+            query: "$..file".to_string(),
+            results: vec![
+                "\"app/Order.php\"".to_string(),
+                "{\n  \"line\": 7\n}".to_string(),
+            ],
+            scroll: 0,
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(matches!(metadata.overlay, Some(OverlayArea::JsonQuery(_))));
+        assert!(text.contains("JSON Query (y copies · J edits"), "{}", text);
+        assert!(text.contains("> $..file  · 2 matches"), "{}", text);
+        assert!(text.contains("\"app/Order.php\""), "{}", text);
+        assert!(text.contains("\"line\": 7"), "{}", text);
+    }
+
+    #[test]
+    fn timeline_names_the_sending_host() {
+        let mut view_model = base_view_model();
+        view_model.total_events = 1;
+        view_model.timeline = vec![TimelineEntry {
+            id: Uuid::nil(),
+            kind: "log".to_string(),
+            summary: "x".repeat(200),
+            age: "1s".to_string(),
+            color: None,
+            label: Some("Queue".to_string()),
+            viewers: 0,
+            range: None,
+            request: None,
+            host: Some("worker".to_string()),
+            origin: None,
+            format: None,
+            unread: false,
+        }];
+        view_model.selected = Some(0);
+
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("… · 1s @worker (Queue)│"), "{}", text);
+    }
+
+    #[test]
+    fn marked_range_draws_a_gutter_beside_its_events() {
+        let mut view_model = base_view_model();
+        let entry = |summary: &str, range| TimelineEntry {
+            id: Uuid::new_v4(),
+            kind: "log".to_string(),
+            summary: summary.to_string(),
+            age: "1s".to_string(),
+            color: None,
+            label: None,
+            viewers: 0,
+            range,
+            request: None,
+            host: None,
+            origin: None,
+            format: None,
+            unread: false,
+        };
+        view_model.total_events = 4;
+        view_model.timeline = vec![
+            entry("after", None),
+            entry("last", Some(RangeMark::Last)),
+            entry("middle", Some(RangeMark::Inside)),
+            entry("first", Some(RangeMark::First)),
+        ];
+        view_model.selected = Some(0);
+
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("│ ⬤ [log] after"), "{}", text);
+        assert!(text.contains("│╭⬤ [log] last"), "{}", text);
+        assert!(text.contains("││⬤ [log] middle"), "{}", text);
+        assert!(text.contains("│╰⬤ [log] first"), "{}", text);
+    }
+
+    #[test]
+    fn request_boundaries_take_their_own_rows() {
+        let mut view_model = base_view_model();
+        let entry = |summary: &str, request: Option<&str>| TimelineEntry {
+            id: Uuid::new_v4(),
+            kind: "log".to_string(),
+            summary: summary.to_string(),
+            age: "1s".to_string(),
+            color: None,
+            label: None,
+            viewers: 0,
+            range: None,
+            request: request.map(str::to_string),
+            host: None,
+            origin: None,
+            format: None,
+            unread: false,
+        };
+        view_model.total_events = 3;
+        view_model.timeline = vec![
+            entry("in flight", None),
+            entry("handled", Some("GET /cart 200")),
+            entry("dumped during /cart", None),
+        ];
+        view_model.selected = Some(2);
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("── GET /cart 200 ───"), "{}", text);
+        assert_eq!(
+            &metadata.timeline_rows[..4],
+            &[Some(0), None, Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn history_search_shows_its_prompt_and_read_only_results() {
+        let mut view_model = base_view_model();
+        view_model.history = Some(HistoryView {
+            prompt: Some("kind:exception since:2h".to_string()),
+            error: Some("`soon` is not a date".to_string()),
+            ..HistoryView::default()
+        });
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("> kind:exception since:2h"), "{}", text);
+        assert!(text.contains("`soon` is not a date"), "{}", text);
+        assert!(matches!(metadata.overlay, Some(OverlayArea::History(_))));
+
+        view_model.history = Some(HistoryView {
+            results_for: Some("checkout".to_string()),
+            ..HistoryView::default()
+        });
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(
+            text.contains("History: checkout (0 matches, read-only)"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("No stored events match `checkout`."),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn small_terminals_show_one_pane_at_a_time() {
+        let mut view_model = base_view_model();
+        view_model.detail = Some(Arc::new(DetailViewModel {
+            header: "log".to_string(),
+            footer: String::new(),
+            lines: Vec::new(),
+            origins: Default::default(),
+            focus_line: None,
+        }));
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 50, 12).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("Timeline · Tab details"), "{}", text);
+        assert!(!text.contains("Details"));
+        assert_eq!(metadata.detail_inner.area(), 0);
+
+        view_model.focus_detail = true;
+        let (buffer, metadata) = render_to_buffer(&view_model, 50, 12).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.contains("Details · Tab/Esc back"), "{}", text);
+        assert!(!text.contains("Timeline"));
+        assert_eq!(metadata.timeline_inner.area(), 0);
+        assert_eq!(metadata.detail_inner.height, 9);
+    }
+
+    #[test]
+    fn problems_strip_sits_above_the_footer() {
+        let mut view_model = base_view_model();
+        let mut problems = ProblemsView {
+            entries: vec![problems::ProblemEntry {
+                time: "14:05:01".to_string(),
+                error: false,
+                message: "failed to parse Ray request".to_string(),
+            }],
+            unseen: 1,
+            expanded: false,
+        };
+        view_model.problems = Some(problems.clone());
+
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        let strip = text.lines().nth(27).unwrap();
+        assert!(
+            strip.starts_with(
+                "⚠ 1 new problem · 14:05:01 failed to parse Ray request · P shows all"
+            ),
+            "{}",
+            text
+        );
+
+        problems.expanded = true;
+        view_model.problems = Some(problems);
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(
+            lines[26].starts_with("Problems (1 of 1, newest first · P hides)"),
+            "{}",
+            text
+        );
+        assert_eq!(lines[27], "14:05:01 warn  failed to parse Ray request");
+    }
+
+    #[test]
+    fn unknown_kinds_overlay_shows_the_selected_example() {
+        let mut view_model = base_view_model();
+        view_model.unknown_kinds = Some(UnknownKindsView {
+            entries: vec![
+                unknown_kinds::UnknownKindEntry {
+                    kind: "hologram".to_string(),
+                    count: 3,
+                    client: Some("ray 1.42.0".to_string()),
+                    renderer: "text",
+                    example: vec![
+                        "{".to_string(),
+                        "  \"beam\": true".to_string(),
+                        "}".to_string(),
+                    ],
+                },
+                unknown_kinds::UnknownKindEntry {
+                    kind: "warp".to_string(),
+                    count: 1,
+                    client: None,
+                    renderer: "json",
+                    example: vec!["1".to_string()],
+                },
+            ],
+            cursor: 0,
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(matches!(
+            metadata.overlay,
+            Some(OverlayArea::UnknownKinds(_))
+        ));
+        assert!(
+            text.contains("▸ hologram · 3 events · ray 1.42.0 · drawn as text"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("  warp · 1 event · drawn as json"),
+            "{}",
+            text
+        );
+        assert!(text.contains("Newest `hologram` payload:"), "{}", text);
+        assert!(text.contains("\"beam\": true"), "{}", text);
+    }
+
+    #[test]
+    fn stats_overlay_lists_processing_delays() {
+        let mut view_model = base_view_model();
+        view_model.stats = Some(StatsView {
+            samples: 2,
+            rows: vec![stats::DelayRow {
+                name: "queueing",
+                p50: "10.0 ms".to_string(),
+                p95: "100.0 ms".to_string(),
+                max: "100.0 ms".to_string(),
+            }],
+            duplicates: Some(3),
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(matches!(metadata.overlay, Some(OverlayArea::Stats(_))));
+        assert!(
+            text.contains("Processing Delays (I or Esc to close)"),
+            "{}",
+            text
+        );
+        assert!(text.contains("over the last 2"), "{}", text);
+        assert!(text.contains("Retried deliveries dropped: 3"), "{}", text);
+        assert!(
+            text.contains("queueing       10.0 ms    100.0 ms    100.0 ms"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn profile_panel_marks_timings_over_budget() {
+        let mut view_model = base_view_model();
+        view_model.profile = Some(ProfileView {
+            budget: "16 ms".to_string(),
+            rows: vec![
+                profile::ProfileRow {
+                    name: "view model",
+                    time: "0.4 ms".to_string(),
+                    over_budget: false,
+                },
+                profile::ProfileRow {
+                    name: "selected payload",
+                    time: "48.0 ms".to_string(),
+                    over_budget: true,
+                },
+            ],
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        // The panel is not an overlay: keys and clicks still reach the panes.
+        assert!(metadata.overlay.is_none());
+        assert!(text.contains("Frame (budget 16 ms)"), "{}", text);
+        assert!(text.contains("view model"), "{}", text);
+        let (x, y) = (0..30)
+            .flat_map(|y| (0..100).map(move |x| (x, y)))
+            .find(|&(x, y)| {
+                buffer.get(x, y).symbol() == "4" && buffer.get(x + 1, y).symbol() == "8"
+            })
+            .expect("slow timing drawn");
+        assert_eq!(buffer.get(x, y).fg, Color::Red);
+    }
+
+    #[test]
+    fn log_viewer_shows_the_filter_and_newest_lines() {
+        let mut view_model = base_view_model();
+        view_model.logs = Some(LogViewerView {
+            entries: vec![log_viewer::LogEntry {
+                time: "14:05:01".to_string(),
+                level: tracing::Level::DEBUG,
+                target: "raygun::server".to_string(),
+                message: "accepted payload".to_string(),
+            }],
+            filter: Some("raygun=debug".to_string()),
+            scroll: 0,
+        });
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(matches!(metadata.overlay, Some(OverlayArea::Logs(_))));
+        assert!(
+            text.contains("Logs (1, newest first · L or Esc to close)"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("filter raygun=debug · / changes it"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("14:05:01 DEBUG raygun::server: accepted payload"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn fullscreen_gives_the_focused_pane_the_whole_frame() {
+        let mut view_model = base_view_model();
+        view_model.fullscreen = true;
+
+        let (buffer, metadata) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.starts_with("┌Timeline · z/Esc restore"), "{}", text);
+        assert!(!text.contains("waiting for payloads"));
+        assert_eq!(metadata.timeline_inner, Rect::new(1, 1, 98, 28));
+        assert_eq!(metadata.detail_inner.area(), 0);
+    }
+
+    #[test]
+    fn accessible_palettes_add_per_color_markers() {
+        let config: FileConfig = toml::from_str(
+            r##"
+            [theme]
+            palette = "colorblind"
+
+            [theme.colors]
+            blue = "#123456"
+            "##,
+        )
+        .unwrap();
+        let settings = UiSettings::new(&config);
+
+        assert_eq!(settings.color("red"), Some(Color::Rgb(213, 94, 0)));
+        assert_eq!(settings.color("blue"), Some(Color::Rgb(0x12, 0x34, 0x56)));
+        assert_eq!(settings.color("cyan"), color_from_name("cyan"));
+        assert_ne!(
+            settings.color_marker(Some("red")),
+            settings.color_marker(Some("green"))
+        );
+        assert_eq!(UiSettings::default().color_marker(Some("red")), "⬤");
+        assert_eq!(settings.timeline_marker(Some("red"), "exception"), "■⚠");
+        assert_eq!(settings.timeline_marker(Some("red"), "log"), "■ ");
+    }
+
+    #[test]
+    fn timeline_markers_follow_the_glyph_set_and_overrides() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            [theme]
+            glyphs = "nerd-font"
+
+            [theme.kind_glyphs]
+            Query = "Q"
+            "#,
+        )
+        .unwrap();
+        let mut settings = UiSettings::new(&config);
+
+        assert_eq!(settings.timeline_marker(None, "exception"), "\u{f071}");
+        assert_eq!(settings.timeline_marker(None, "query"), "Q");
+        assert_eq!(settings.timeline_marker(None, "log"), "⬤");
+        settings.ascii = true;
+        assert_eq!(settings.timeline_marker(None, "measure"), "t");
+        assert_eq!(UiSettings::default().timeline_marker(None, "table"), "▤");
+    }
+
+    #[test]
+    fn ascii_mode_leaves_no_ui_glyphs() {
+        let mut view_model = base_view_model();
+        view_model.settings = Arc::new(UiSettings {
+            ascii: true,
+            ..UiSettings::default()
+        });
+        view_model.timeline = vec![TimelineEntry {
+            id: Uuid::nil(),
+            kind: "log".to_string(),
+            summary: "x".repeat(200),
+            age: "3s".to_string(),
+            color: Some("red".to_string()),
+            label: None,
+            viewers: 0,
+            range: None,
+            request: None,
+            host: None,
+            origin: None,
+            format: None,
+            unread: false,
+        }];
+        view_model.selected = Some(0);
+
+        let (buffer, _) = render_to_buffer(&view_model, 100, 30).unwrap();
+        let text = buffer_to_text(&buffer);
+        assert!(text.is_ascii(), "{}", text);
+        assert!(text.contains("+Timeline-"), "{}", text);
+        assert!(text.contains("* [log] xxx"), "{}", text);
     }
 }
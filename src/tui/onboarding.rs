@@ -0,0 +1,181 @@
+use std::net::{IpAddr, SocketAddr};
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+};
+
+use super::AppViewModel;
+
+/// Client configuration snippets pointing at the address Raygun listens on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnboardingSnippets {
+    pub env: String,
+    pub docker_env: String,
+    pub ray_php: String,
+    /// Set when every bind address is loopback, so containers cannot connect.
+    pub loopback_only: bool,
+}
+
+impl OnboardingSnippets {
+    pub fn new(bind_addrs: &[SocketAddr]) -> Self {
+        // Prefer IPv4: PHP clients are far more likely to be configured for it.
+        let addr = bind_addrs
+            .iter()
+            .find(|addr| addr.is_ipv4())
+            .or_else(|| bind_addrs.first())
+            .copied()
+            .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 23517)));
+
+        let host = match addr.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => "127.0.0.1".to_string(),
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) if ip.is_unspecified() => "[::1]".to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip),
+        };
+        let port = addr.port();
+
+        Self {
+            env: format!("RAY_HOST={}\nRAY_PORT={}", host, port),
+            docker_env: format!("RAY_HOST=host.docker.internal\nRAY_PORT={}", port),
+            ray_php: format!(
+                "'host' => env('RAY_HOST', '{}'),\n'port' => env('RAY_PORT', {}),",
+                host, port
+            ),
+            loopback_only: !bind_addrs.is_empty()
+                && bind_addrs.iter().all(|addr| addr.ip().is_loopback()),
+        }
+    }
+
+    /// Snippet copied by the `1`/`2`/`3` shortcuts in the overlay.
+    pub fn by_index(&self, index: usize) -> Option<(&'static str, &str)> {
+        match index {
+            1 => Some((".env", &self.env)),
+            2 => Some(("Docker .env", &self.docker_env)),
+            3 => Some(("ray.php", &self.ray_php)),
+            _ => None,
+        }
+    }
+}
+
+/// Onboarding overlay state handed to the renderer.
+#[derive(Debug, Clone)]
+pub struct OnboardingView {
+    pub snippets: OnboardingSnippets,
+    pub copied: Option<&'static str>,
+}
+
+pub(super) fn render_onboarding_overlay(
+    frame: &mut Frame<'_>,
+    view_model: &AppViewModel,
+    onboarding: &OnboardingView,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let heading = Style::default().add_modifier(Modifier::BOLD);
+    let code = Style::default().fg(Color::LightGreen);
+    let muted = Style::default().fg(Color::DarkGray);
+    let snippets = &onboarding.snippets;
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Welcome to Raygun",
+            Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::raw(""),
+        Line::from(format!(
+            "Raygun is listening on {}. Point your Ray client at it:",
+            super::format_addrs(&view_model.bind_addrs)
+        )),
+        Line::raw(""),
+        Line::from(Span::styled("[1] .env (app on this machine)", heading)),
+    ];
+    lines.extend(
+        snippets
+            .env
+            .lines()
+            .map(|line| Line::from(Span::styled(format!("    {}", line), code))),
+    );
+    lines.push(Line::raw(""));
+    lines.push(Line::from(Span::styled(
+        "[2] .env (app in Docker)",
+        heading,
+    )));
+    lines.extend(
+        snippets
+            .docker_env
+            .lines()
+            .map(|line| Line::from(Span::styled(format!("    {}", line), code))),
+    );
+    lines.push(Line::from(Span::styled(
+        "    On Linux add `extra_hosts: [\"host.docker.internal:host-gateway\"]` to the service.",
+        muted,
+    )));
+    if snippets.loopback_only {
+        lines.push(Line::from(Span::styled(
+            "    Raygun only listens on loopback; restart with `--bind 0.0.0.0:<port>` so containers can connect.",
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines.push(Line::raw(""));
+    lines.push(Line::from(Span::styled(
+        "[3] config/ray.php (or ray.php in your project root)",
+        heading,
+    )));
+    lines.extend(
+        snippets
+            .ray_php
+            .lines()
+            .map(|line| Line::from(Span::styled(format!("    {}", line), code))),
+    );
+    lines.push(Line::raw(""));
+
+    match onboarding.copied {
+        Some(name) => lines.push(Line::from(Span::styled(
+            format!("Copied the {} snippet to the clipboard.", name),
+            Style::default().fg(Color::LightGreen),
+        ))),
+        None => lines.push(Line::from(Span::styled(
+            "Press 1, 2 or 3 to copy a snippet · Esc to close. This screen disappears once the first payload arrives.",
+            muted,
+        ))),
+    }
+
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Getting Started")
+            .padding(Padding::uniform(1))
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_resolve_wildcard_binds_to_loopback() {
+        let addrs: Vec<SocketAddr> = vec![
+            "[::]:23517".parse().unwrap(),
+            "0.0.0.0:23517".parse().unwrap(),
+        ];
+        let snippets = OnboardingSnippets::new(&addrs);
+
+        assert_eq!(snippets.env, "RAY_HOST=127.0.0.1\nRAY_PORT=23517");
+        assert!(snippets.docker_env.contains("host.docker.internal"));
+        assert!(!snippets.loopback_only);
+
+        let loopback = OnboardingSnippets::new(&["127.0.0.1:9000".parse().unwrap()]);
+        assert!(loopback.loopback_only);
+        assert!(loopback.ray_php.contains("env('RAY_PORT', 9000)"));
+    }
+}
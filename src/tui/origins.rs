@@ -0,0 +1,101 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+/// Width of the frequency bar at the start of each row.
+const BAR_WIDTH: usize = 12;
+
+/// One call site in the origins overlay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginRow {
+    /// `file:line` after path mappings.
+    pub location: String,
+    /// Kind the timeline shows for the call site's most recent event.
+    pub kind: String,
+    pub count: usize,
+}
+
+/// Origins overlay state handed to the renderer, busiest call site first.
+#[derive(Debug, Clone)]
+pub struct OriginsView {
+    pub rows: Vec<OriginRow>,
+    /// Events in the timeline, with or without an origin.
+    pub total: usize,
+    pub cursor: usize,
+}
+
+pub(super) fn render_origins_overlay(
+    frame: &mut Frame<'_>,
+    view: &OriginsView,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+
+    if view.rows.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No event in the timeline has an origin yet.",
+            muted,
+        )));
+    }
+
+    let busiest = view.rows.first().map_or(1, |row| row.count.max(1));
+    let count_width = busiest.to_string().len();
+    // Keep the cursor on screen; the rest of the overlay is the list.
+    let rows = area.height.saturating_sub(4) as usize;
+    let start = view.cursor.saturating_sub(rows.saturating_sub(1));
+    for (index, row) in view.rows.iter().enumerate().skip(start).take(rows) {
+        let selected = index == view.cursor;
+        let row_style = if selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        let filled = (row.count * BAR_WIDTH)
+            .div_ceil(busiest)
+            .clamp(1, BAR_WIDTH);
+        let share = row.count * 100 / view.total.max(1);
+        lines.push(Line::from(vec![
+            Span::styled(if selected { "▸ " } else { "  " }, row_style),
+            Span::styled("█".repeat(filled), row_style.fg(heat(share))),
+            Span::styled(" ".repeat(BAR_WIDTH - filled), row_style),
+            Span::styled(
+                format!(" {:>width$} ", row.count, width = count_width),
+                row_style.add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("{:>3}% ", share), row_style.fg(Color::DarkGray)),
+            Span::styled(row.location.clone(), row_style),
+            Span::styled(format!("  [{}]", row.kind), row_style.fg(Color::LightCyan)),
+        ]));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Origins (Enter shows only · h hides · o opens · {} or Esc to close)",
+                close_hint
+            ))
+            .padding(Padding::uniform(1))
+            .border_style(Style::default().fg(Color::LightRed)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
+
+/// Bar color for a call site sending `share` percent of the events.
+fn heat(share: usize) -> Color {
+    match share {
+        25.. => Color::Red,
+        10.. => Color::LightRed,
+        5.. => Color::Yellow,
+        _ => Color::Green,
+    }
+}
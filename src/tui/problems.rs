@@ -0,0 +1,104 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+
+/// Rows the expanded strip lists before older problems are cut off.
+const EXPANDED_ROWS: usize = 6;
+
+/// One problem, pre-formatted for the strip.
+#[derive(Debug, Clone)]
+pub struct ProblemEntry {
+    /// Wall-clock time it happened, e.g. `14:05:01`.
+    pub time: String,
+    pub error: bool,
+    pub message: String,
+}
+
+/// Problems strip state handed to the renderer, newest entry first.
+#[derive(Debug, Clone)]
+pub struct ProblemsView {
+    pub entries: Vec<ProblemEntry>,
+    /// Problems recorded since the strip was last expanded.
+    pub unseen: usize,
+    pub expanded: bool,
+}
+
+impl ProblemsView {
+    /// Rows the strip takes up: one line collapsed, a border and a line per
+    /// problem expanded.
+    pub fn height(&self) -> u16 {
+        if self.expanded {
+            self.entries.len().clamp(1, EXPANDED_ROWS) as u16 + 1
+        } else {
+            1
+        }
+    }
+}
+
+pub(super) fn render_problems_strip(
+    frame: &mut Frame<'_>,
+    problems: &ProblemsView,
+    toggle_hint: &str,
+    area: Rect,
+) {
+    let muted = Style::default().fg(Color::DarkGray);
+    let warning = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    if !problems.expanded {
+        let mut spans = vec![Span::styled(
+            format!(
+                "⚠ {} new problem{}",
+                problems.unseen,
+                if problems.unseen == 1 { "" } else { "s" }
+            ),
+            warning,
+        )];
+        if let Some(latest) = problems.entries.first() {
+            spans.push(Span::styled(format!(" · {} ", latest.time), muted));
+            spans.push(Span::raw(latest.message.clone()));
+        }
+        spans.push(Span::styled(format!(" · {} shows all", toggle_hint), muted));
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+        return;
+    }
+
+    let mut lines: Vec<Line> = problems
+        .entries
+        .iter()
+        .take(EXPANDED_ROWS)
+        .map(|entry| {
+            let (level, style) = if entry.error {
+                ("error", Style::default().fg(Color::LightRed))
+            } else {
+                ("warn ", Style::default().fg(Color::Yellow))
+            };
+            Line::from(vec![
+                Span::styled(format!("{} ", entry.time), muted),
+                Span::styled(level, style),
+                Span::raw(format!(" {}", entry.message)),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled("No problems so far.", muted)));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .title(format!(
+                "Problems ({} of {}, newest first · {} hides)",
+                problems.entries.len().min(EXPANDED_ROWS),
+                problems.entries.len(),
+                toggle_hint
+            ))
+            .border_style(warning),
+    );
+    frame.render_widget(paragraph, area);
+}
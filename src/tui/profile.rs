@@ -0,0 +1,68 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// One timing of the last frame, pre-formatted for the profiling panel.
+#[derive(Debug, Clone)]
+pub struct ProfileRow {
+    pub name: &'static str,
+    pub time: String,
+    pub over_budget: bool,
+}
+
+/// Profiling panel state handed to the renderer.
+#[derive(Debug, Clone)]
+pub struct ProfileView {
+    /// Time a frame may take, e.g. `16 ms`.
+    pub budget: String,
+    pub rows: Vec<ProfileRow>,
+}
+
+const WIDTH: u16 = 32;
+
+/// Draws the panel in the top-right corner of `area`. It takes no input, so
+/// everything underneath stays usable.
+pub(super) fn render_profile_panel(frame: &mut Frame<'_>, view: &ProfileView, area: Rect) {
+    let height = view.rows.len() as u16 + 2;
+    if area.width < WIDTH || area.height < height {
+        return;
+    }
+    let panel = Rect {
+        x: area.x + area.width - WIDTH,
+        y: area.y,
+        width: WIDTH,
+        height,
+    };
+    frame.render_widget(Clear, panel);
+
+    let lines: Vec<Line> = view
+        .rows
+        .iter()
+        .map(|row| {
+            let style = if row.over_budget {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:<16}", row.name),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(format!("{:>12}", row.time), style),
+            ])
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Frame (budget {})", view.budget))
+            .border_style(Style::default().fg(Color::Magenta)),
+    );
+    frame.render_widget(paragraph, panel);
+}
@@ -0,0 +1,54 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+/// Single-line input prompt state handed to the renderer.
+#[derive(Debug, Clone, Default)]
+pub struct PromptView {
+    /// Box title, naming the keys that submit and cancel.
+    pub title: String,
+    /// Muted line under the input explaining what to type.
+    pub hint: String,
+    pub input: String,
+    /// Why the input was rejected; replaces the hint line.
+    pub error: Option<String>,
+}
+
+/// A boxed input line with a hint (or the error) underneath.
+pub(super) fn render_prompt(frame: &mut Frame<'_>, prompt: &PromptView, area: Rect) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(
+                "> ",
+                Style::default()
+                    .fg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(prompt.input.clone()),
+            Span::styled("▏", Style::default().fg(Color::LightBlue)),
+        ]),
+        match &prompt.error {
+            Some(error) => Line::from(Span::styled(
+                error.clone(),
+                Style::default().fg(Color::LightRed),
+            )),
+            None => Line::from(Span::styled(prompt.hint.clone(), muted)),
+        },
+    ];
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(prompt.title.clone())
+            .padding(Padding::horizontal(1))
+            .border_style(Style::default().fg(Color::LightBlue)),
+    );
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,173 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+/// Per-event actions offered by the `Enter` menu on the timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuickAction {
+    CopyJson,
+    Export,
+    ExportHtml,
+    OpenOrigin,
+    CopyPermalink,
+    Resend,
+    EditLabel,
+    MoveToScreen,
+    RenameScreen,
+    CloseScreen,
+    FilterColor,
+    FilterLabel,
+    FilterKind,
+    ClearFilters,
+    DiffPrevious,
+    Delete,
+    RangeStats,
+    RangeExport,
+    RangeKeep,
+    RangeUnmark,
+}
+
+impl QuickAction {
+    pub const ALL: &'static [QuickAction] = &[
+        QuickAction::CopyJson,
+        QuickAction::Export,
+        QuickAction::ExportHtml,
+        QuickAction::OpenOrigin,
+        QuickAction::CopyPermalink,
+        QuickAction::Resend,
+        QuickAction::EditLabel,
+        QuickAction::MoveToScreen,
+        QuickAction::RenameScreen,
+        QuickAction::CloseScreen,
+        QuickAction::FilterColor,
+        QuickAction::FilterLabel,
+        QuickAction::FilterKind,
+        QuickAction::ClearFilters,
+        QuickAction::DiffPrevious,
+        QuickAction::Delete,
+        QuickAction::RangeStats,
+        QuickAction::RangeExport,
+        QuickAction::RangeKeep,
+        QuickAction::RangeUnmark,
+    ];
+
+    /// Shortcut that runs the action while the menu is open.
+    pub fn key(self) -> char {
+        match self {
+            QuickAction::CopyJson => 'c',
+            QuickAction::Export => 'e',
+            QuickAction::ExportHtml => 'h',
+            QuickAction::OpenOrigin => 'o',
+            QuickAction::CopyPermalink => 'g',
+            QuickAction::Resend => 's',
+            QuickAction::EditLabel => 'n',
+            QuickAction::MoveToScreen => 'm',
+            QuickAction::RenameScreen => 'a',
+            QuickAction::CloseScreen => 'z',
+            QuickAction::FilterColor => 'f',
+            QuickAction::FilterLabel => 'l',
+            QuickAction::FilterKind => 't',
+            QuickAction::ClearFilters => 'r',
+            QuickAction::DiffPrevious => 'd',
+            QuickAction::Delete => 'x',
+            QuickAction::RangeStats => 'i',
+            QuickAction::RangeExport => 'w',
+            QuickAction::RangeKeep => 'k',
+            QuickAction::RangeUnmark => 'u',
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            QuickAction::CopyJson => "Copy JSON",
+            QuickAction::Export => "Export to file",
+            QuickAction::ExportHtml => "Export as HTML page",
+            QuickAction::OpenOrigin => "Open origin in editor",
+            QuickAction::CopyPermalink => "Copy permalink",
+            QuickAction::Resend => "Re-send payload",
+            QuickAction::EditLabel => "Edit label",
+            QuickAction::MoveToScreen => "Move to screen",
+            QuickAction::RenameScreen => "Rename screen",
+            QuickAction::CloseScreen => "Close screen and trash its events",
+            QuickAction::FilterColor => "Only show this color",
+            QuickAction::FilterLabel => "Only show this label",
+            QuickAction::FilterKind => "Only show this kind",
+            QuickAction::ClearFilters => "Clear filters",
+            QuickAction::DiffPrevious => "Diff against previous event",
+            QuickAction::Delete => "Delete event",
+            QuickAction::RangeStats => "Marked range stats",
+            QuickAction::RangeExport => "Export marked range",
+            QuickAction::RangeKeep => "Clear everything outside the range",
+            QuickAction::RangeUnmark => "Unmark range",
+        }
+    }
+}
+
+/// One menu row; `detail` names what the action applies to, and disabled rows
+/// are shown dimmed and cannot be run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickActionItem {
+    pub action: QuickAction,
+    pub detail: Option<String>,
+    pub enabled: bool,
+}
+
+/// Quick actions menu state handed to the renderer.
+#[derive(Debug, Clone)]
+pub struct QuickActionsView {
+    pub items: Vec<QuickActionItem>,
+    pub cursor: usize,
+}
+
+pub(super) fn render_quick_actions_overlay(
+    frame: &mut Frame<'_>,
+    menu: &QuickActionsView,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = menu
+        .items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let selected = index == menu.cursor;
+            let base = if !item.enabled {
+                muted
+            } else if selected {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            let mut spans = vec![
+                Span::styled(if selected { "▶ " } else { "  " }, base),
+                Span::styled(
+                    format!("{}  {}", item.action.key(), item.action.title()),
+                    base,
+                ),
+            ];
+            if let Some(detail) = &item.detail {
+                spans.push(Span::styled(format!("  {}", detail), muted));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Event Actions (Enter to run · Esc to close)")
+            .padding(Padding::horizontal(1))
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
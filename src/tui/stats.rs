@@ -0,0 +1,93 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+};
+
+/// One kind of processing delay, pre-formatted for the stats overlay.
+#[derive(Debug, Clone)]
+pub struct DelayRow {
+    pub name: &'static str,
+    pub p50: String,
+    pub p95: String,
+    pub max: String,
+}
+
+/// Stats overlay state handed to the renderer.
+#[derive(Debug, Clone)]
+pub struct StatsView {
+    /// Events the delays were measured on.
+    pub samples: usize,
+    pub rows: Vec<DelayRow>,
+    /// Retried deliveries that were dropped; `None` when attached to a daemon,
+    /// which drops them before they reach this client.
+    pub duplicates: Option<u64>,
+}
+
+pub(super) fn render_stats_overlay(
+    frame: &mut Frame<'_>,
+    view: &StatsView,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled(
+        format!(
+            "Time from receiving a request to drawing it, over the last {} event{} received over HTTP.",
+            view.samples,
+            if view.samples == 1 { "" } else { "s" }
+        ),
+        muted,
+    ))];
+    lines.push(Line::raw(""));
+
+    if view.rows.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No events received since Raygun started.",
+            muted,
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!("{:<10}{:>12}{:>12}{:>12}", "", "p50", "p95", "max"),
+            bold,
+        )));
+        for row in &view.rows {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<10}", row.name), bold),
+                Span::raw(format!("{:>12}{:>12}{:>12}", row.p50, row.p95, row.max)),
+            ]));
+        }
+    }
+
+    if let Some(duplicates) = view.duplicates {
+        lines.push(Line::raw(""));
+        lines.push(Line::from(vec![
+            Span::styled("Retried deliveries dropped: ", bold),
+            Span::raw(duplicates.to_string()),
+        ]));
+    }
+
+    lines.push(Line::raw(""));
+    lines.push(Line::from(Span::styled(
+        "Queueing runs until the UI picks the event up, which it does as soon as the event is recorded unless it is still drawing; render is building and drawing that frame.",
+        muted,
+    )));
+
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Processing Delays ({} or Esc to close)",
+                close_hint
+            ))
+            .padding(Padding::uniform(1))
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
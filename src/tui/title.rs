@@ -0,0 +1,135 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::config::TerminalConfig;
+
+/// How long the progress indicator keeps pulsing after an event arrives.
+const PULSE: Duration = Duration::from_secs(3);
+
+/// `raygun`, or `raygun (3 new)` while events are unread.
+pub fn title(new_events: usize) -> String {
+    match new_events {
+        0 => "raygun".to_string(),
+        count => format!("raygun ({} new)", count),
+    }
+}
+
+/// Keeps the terminal's title and progress indicator in step with the
+/// timeline, writing escape sequences only when what they show changes.
+#[derive(Debug, Default)]
+pub struct TerminalStatus {
+    /// Title last written; `Some` also means the previous title was saved.
+    title: Option<String>,
+    pulsing: bool,
+}
+
+impl TerminalStatus {
+    pub fn update(
+        &mut self,
+        writer: &mut impl Write,
+        config: TerminalConfig,
+        new_events: usize,
+        last_arrival: Option<Instant>,
+    ) -> io::Result<()> {
+        let mut out = String::new();
+
+        if config.title {
+            let title = title(new_events);
+            if self.title.as_ref() != Some(&title) {
+                if self.title.is_none() {
+                    // Saved on the terminal's title stack, restored in `reset`.
+                    out.push_str("\x1b[22;0t");
+                }
+                out.push_str(&format!("\x1b]2;{}\x07", title));
+                self.title = Some(title);
+            }
+        } else if self.title.take().is_some() {
+            out.push_str("\x1b[23;0t");
+        }
+
+        let pulsing =
+            config.progress && last_arrival.is_some_and(|arrived| arrived.elapsed() < PULSE);
+        if pulsing != self.pulsing {
+            // State 3 is an indeterminate bar, 0 removes it.
+            out.push_str(if pulsing {
+                "\x1b]9;4;3;0\x07"
+            } else {
+                "\x1b]9;4;0;0\x07"
+            });
+            self.pulsing = pulsing;
+        }
+
+        write(writer, &out)
+    }
+
+    /// Clears the progress indicator and restores the title Raygun started
+    /// with.
+    pub fn reset(&mut self, writer: &mut impl Write) -> io::Result<()> {
+        let mut out = String::new();
+        if std::mem::take(&mut self.pulsing) {
+            out.push_str("\x1b]9;4;0;0\x07");
+        }
+        if self.title.take().is_some() {
+            out.push_str("\x1b[23;0t");
+        }
+        write(writer, &out)
+    }
+}
+
+fn write(writer: &mut impl Write, out: &str) -> io::Result<()> {
+    if out.is_empty() {
+        return Ok(());
+    }
+    writer.write_all(out.as_bytes())?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(
+        status: &mut TerminalStatus,
+        config: TerminalConfig,
+        new_events: usize,
+        last_arrival: Option<Instant>,
+    ) -> String {
+        let mut out = Vec::new();
+        status
+            .update(&mut out, config, new_events, last_arrival)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_title_and_progress_only_when_they_change() {
+        let config = TerminalConfig {
+            title: true,
+            progress: true,
+        };
+        let mut status = TerminalStatus::default();
+
+        assert_eq!(
+            update(&mut status, config, 0, None),
+            "\x1b[22;0t\x1b]2;raygun\x07"
+        );
+        assert_eq!(update(&mut status, config, 0, None), "");
+        assert_eq!(
+            update(&mut status, config, 2, Some(Instant::now())),
+            "\x1b]2;raygun (2 new)\x07\x1b]9;4;3;0\x07"
+        );
+        let long_ago = Instant::now().checked_sub(PULSE * 2);
+        assert_eq!(update(&mut status, config, 2, long_ago), "\x1b]9;4;0;0\x07");
+
+        let quiet = TerminalConfig {
+            title: false,
+            progress: false,
+        };
+        assert_eq!(update(&mut status, quiet, 3, None), "\x1b[23;0t");
+        let mut out = Vec::new();
+        status.reset(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+}
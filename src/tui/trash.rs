@@ -0,0 +1,69 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+use super::TimelineEntry;
+
+/// Trash overlay state handed to the renderer, most recently deleted first.
+#[derive(Debug, Clone)]
+pub struct TrashView {
+    pub entries: Vec<TimelineEntry>,
+    pub cursor: usize,
+}
+
+pub(super) fn render_trash_overlay(
+    frame: &mut Frame<'_>,
+    view: &TrashView,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+
+    if view.entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "The trash is empty. Deleted events wait here until they are restored.",
+            muted,
+        )));
+    }
+
+    // Keep the cursor on screen; the rest of the overlay is the list.
+    let rows = area.height.saturating_sub(4) as usize;
+    let start = view.cursor.saturating_sub(rows.saturating_sub(1));
+    for (index, entry) in view.entries.iter().enumerate().skip(start).take(rows) {
+        let selected = index == view.cursor;
+        let row_style = if selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(if selected { "▸ " } else { "  " }, row_style),
+            Span::styled(
+                format!("[{}] ", entry.kind),
+                row_style.fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(entry.summary.clone(), row_style),
+            Span::styled(format!(" · {}", entry.age), row_style.fg(Color::DarkGray)),
+        ]));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Trash (Enter restores · {} or Esc to close)",
+                close_hint
+            ))
+            .padding(Padding::uniform(1))
+            .border_style(Style::default().fg(Color::Yellow)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,110 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Padding, Paragraph},
+};
+
+/// Example lines shown for the selected type.
+const EXAMPLE_ROWS: usize = 12;
+
+/// One payload type Raygun doesn't know, as seen on the timeline.
+#[derive(Debug, Clone)]
+pub struct UnknownKindEntry {
+    pub kind: String,
+    pub count: usize,
+    /// Ray client that sent the newest one, e.g. `ray 1.42.0`.
+    pub client: Option<String>,
+    /// Name of the assigned renderer, e.g. `json`.
+    pub renderer: &'static str,
+    /// Content of the newest one as pretty-printed JSON lines.
+    pub example: Vec<String>,
+}
+
+/// Unknown payload types overlay state handed to the renderer, most frequent
+/// type first.
+#[derive(Debug, Clone)]
+pub struct UnknownKindsView {
+    pub entries: Vec<UnknownKindEntry>,
+    pub cursor: usize,
+}
+
+pub(super) fn render_unknown_kinds_overlay(
+    frame: &mut Frame<'_>,
+    view: &UnknownKindsView,
+    close_hint: &str,
+    area: Rect,
+) {
+    frame.render_widget(Clear, area);
+
+    let muted = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+
+    if view.entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Every payload type on the timeline is one Raygun knows.",
+            muted,
+        )));
+    }
+
+    for (index, entry) in view.entries.iter().enumerate() {
+        let selected = index == view.cursor;
+        let row_style = if selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        let mut spans = vec![
+            Span::styled(if selected { "▸ " } else { "  " }, row_style),
+            Span::styled(entry.kind.clone(), row_style.add_modifier(Modifier::BOLD)),
+            Span::styled(
+                format!(
+                    " · {} event{}",
+                    entry.count,
+                    if entry.count == 1 { "" } else { "s" }
+                ),
+                row_style,
+            ),
+        ];
+        if let Some(client) = &entry.client {
+            spans.push(Span::styled(format!(" · {}", client), row_style));
+        }
+        spans.push(Span::styled(
+            format!(" · drawn as {}", entry.renderer),
+            row_style.fg(Color::Cyan),
+        ));
+        lines.push(Line::from(spans));
+    }
+
+    if let Some(entry) = view.entries.get(view.cursor) {
+        lines.push(Line::raw(""));
+        lines.push(Line::from(Span::styled(
+            format!("Newest `{}` payload:", entry.kind),
+            muted,
+        )));
+        lines.extend(
+            entry
+                .example
+                .iter()
+                .take(EXAMPLE_ROWS)
+                .map(|line| Line::raw(line.clone())),
+        );
+        if entry.example.len() > EXAMPLE_ROWS {
+            lines.push(Line::from(Span::styled("…", muted)));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                "Unknown Payload Types (Enter cycles json/text/table · {} or Esc to close)",
+                close_hint
+            ))
+            .padding(Padding::uniform(1))
+            .border_style(Style::default().fg(Color::Yellow)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
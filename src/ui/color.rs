@@ -0,0 +1,48 @@
+//! Color names accepted in `[theme.colors]`, parsed without tying them to a
+//! renderer so config validation and the TUI agree on what is a color.
+
+/// A color from the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeColor {
+    /// The terminal's own white.
+    White,
+    /// The terminal's own black.
+    Black,
+    Rgb(u8, u8, u8),
+}
+
+/// Parses a color name like `red` or a hex value like `#ff5555`.
+pub fn parse(name: &str) -> Option<ThemeColor> {
+    let normalized = name.trim().to_lowercase();
+    match normalized.as_str() {
+        "red" => Some(ThemeColor::Rgb(255, 82, 82)),
+        "green" => Some(ThemeColor::Rgb(48, 209, 88)),
+        "blue" => Some(ThemeColor::Rgb(64, 156, 255)),
+        "yellow" => Some(ThemeColor::Rgb(255, 214, 10)),
+        "orange" => Some(ThemeColor::Rgb(255, 159, 10)),
+        "purple" | "magenta" => Some(ThemeColor::Rgb(191, 90, 242)),
+        "pink" => Some(ThemeColor::Rgb(255, 55, 95)),
+        "gray" | "grey" => Some(ThemeColor::Rgb(138, 141, 165)),
+        "white" => Some(ThemeColor::White),
+        "black" => Some(ThemeColor::Black),
+        "cyan" => Some(ThemeColor::Rgb(100, 210, 255)),
+        "teal" => Some(ThemeColor::Rgb(64, 200, 224)),
+        "lightblue" => Some(ThemeColor::Rgb(173, 216, 230)),
+        "lightgreen" => Some(ThemeColor::Rgb(144, 238, 144)),
+        "brown" => Some(ThemeColor::Rgb(141, 110, 99)),
+        _ => {
+            let hex = normalized.strip_prefix('#').unwrap_or(&normalized);
+            if hex.len() == 6
+                && hex.chars().all(|ch| ch.is_ascii_hexdigit())
+                && let (Ok(r), Ok(g), Ok(b)) = (
+                    u8::from_str_radix(&hex[0..2], 16),
+                    u8::from_str_radix(&hex[2..4], 16),
+                    u8::from_str_radix(&hex[4..6], 16),
+                )
+            {
+                return Some(ThemeColor::Rgb(r, g, b));
+            }
+            None
+        }
+    }
+}
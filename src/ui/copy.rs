@@ -0,0 +1,232 @@
+use serde_json::{Map, Value};
+
+use super::detail::{DetailLine, line_text, path_key};
+
+/// How a detail node is written to the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// The lines as shown; a single string value without its quotes.
+    Raw,
+    Json,
+    /// A PHP array literal, ready for a test or a tinker session.
+    Php,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 3] = [CopyFormat::Raw, CopyFormat::Json, CopyFormat::Php];
+
+    pub fn key(self) -> char {
+        match self {
+            CopyFormat::Raw => 'r',
+            CopyFormat::Json => 'j',
+            CopyFormat::Php => 'p',
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyFormat::Raw => "raw",
+            CopyFormat::Json => "JSON",
+            CopyFormat::Php => "PHP",
+        }
+    }
+}
+
+/// The node at line `index` with everything nested under it, in `format`.
+pub fn format_node(lines: &[DetailLine], index: usize, format: CopyFormat) -> String {
+    match format {
+        CopyFormat::Raw => match node_value(lines, index) {
+            Value::String(text) if children(lines, index).is_empty() => text,
+            _ => node_text(lines, index),
+        },
+        CopyFormat::Json => {
+            serde_json::to_string_pretty(&node_value(lines, index)).unwrap_or_default()
+        }
+        CopyFormat::Php => php_literal(&node_value(lines, index)),
+    }
+}
+
+/// The node's lines as displayed, re-indented relative to the node.
+fn node_text(lines: &[DetailLine], index: usize) -> String {
+    let base = lines[index].indent;
+    std::iter::once(&lines[index])
+        .chain(subtree(lines, index))
+        .map(|line| {
+            format!(
+                "{}{}",
+                "  ".repeat(line.indent.saturating_sub(base)),
+                line_text(line)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn subtree(lines: &[DetailLine], index: usize) -> &[DetailLine] {
+    let indent = lines[index].indent;
+    let len = lines[index + 1..]
+        .iter()
+        .take_while(|line| line.indent > indent)
+        .count();
+    &lines[index + 1..index + 1 + len]
+}
+
+/// Direct children of a node, skipping blank lines and closing brackets.
+fn children(lines: &[DetailLine], index: usize) -> Vec<usize> {
+    let nested = subtree(lines, index);
+    let Some(depth) = nested.iter().map(|line| line.indent).min() else {
+        return Vec::new();
+    };
+    nested
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.indent == depth)
+        .filter(|(_, line)| {
+            let text = line_text(line);
+            let text = text.trim();
+            !text.is_empty() && !text.starts_with(['}', ']'])
+        })
+        .map(|(offset, _)| index + 1 + offset)
+        .collect()
+}
+
+/// Rebuilds a JSON value from a detail subtree: keyed children become an
+/// object, keyless or numbered ones a list, and leaves are read as JSON
+/// scalars where they parse and as strings otherwise.
+fn node_value(lines: &[DetailLine], index: usize) -> Value {
+    let nodes = children(lines, index);
+    if nodes.is_empty() {
+        let text = line_text(&lines[index]);
+        let value = value_text(text.trim());
+        return serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    }
+
+    let keys: Vec<Option<String>> = nodes
+        .iter()
+        .map(|&child| path_key(line_text(&lines[child]).trim()).map(str::to_string))
+        .collect();
+    if keys
+        .iter()
+        .all(|key| key.as_ref().is_none_or(|key| key.parse::<usize>().is_ok()))
+    {
+        return Value::Array(
+            nodes
+                .iter()
+                .map(|&child| node_value(lines, child))
+                .collect(),
+        );
+    }
+
+    let mut object = Map::new();
+    for (position, (&child, key)) in nodes.iter().zip(keys).enumerate() {
+        object.insert(
+            key.unwrap_or_else(|| position.to_string()),
+            node_value(lines, child),
+        );
+    }
+    Value::Object(object)
+}
+
+/// The value half of a line: what follows `=>` in dumps or the key's colon
+/// elsewhere, without a trailing comma.
+fn value_text(text: &str) -> &str {
+    let value = match path_key(text) {
+        Some(_) if text.contains(" => ") => text.split_once(" => ").map(|(_, value)| value),
+        Some(_) if text.starts_with('"') => text[1..]
+            .find('"')
+            .and_then(|end| text[end + 2..].strip_prefix(':')),
+        Some(_) => text.split_once(':').map(|(_, value)| value),
+        None => None,
+    };
+    value.unwrap_or(text).trim().trim_end_matches(',')
+}
+
+/// PHP short-array literal for a JSON value, indented by four spaces a level.
+pub fn php_literal(value: &Value) -> String {
+    let mut out = String::new();
+    write_php(value, 0, &mut out);
+    out
+}
+
+fn write_php(value: &Value, depth: usize, out: &mut String) {
+    let pad = |depth: usize| "    ".repeat(depth);
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(flag) => out.push_str(if *flag { "true" } else { "false" }),
+        Value::Number(number) => out.push_str(&number.to_string()),
+        Value::String(text) => out.push_str(&php_string(text)),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Object(map) if map.is_empty() => out.push_str("[]"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for item in items {
+                out.push_str(&pad(depth + 1));
+                write_php(item, depth + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&pad(depth));
+            out.push(']');
+        }
+        Value::Object(map) => {
+            out.push_str("[\n");
+            for (key, item) in map {
+                out.push_str(&pad(depth + 1));
+                match key.parse::<i64>() {
+                    Ok(number) => out.push_str(&number.to_string()),
+                    Err(_) => out.push_str(&php_string(key)),
+                }
+                out.push_str(" => ");
+                write_php(item, depth + 1, out);
+                out.push_str(",\n");
+            }
+            out.push_str(&pad(depth));
+            out.push(']');
+        }
+    }
+}
+
+fn php_string(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{protocol::Payload, ui::detail::build_detail_view};
+
+    #[test]
+    fn copies_nodes_as_raw_json_and_php() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "decoded_json",
+            "content": { "user": { "name": "O'Brien", "roles": ["admin"], "active": true } }
+        }))
+        .unwrap();
+        let lines = build_detail_view(&payload, std::time::UNIX_EPOCH).lines;
+        let user = lines
+            .iter()
+            .position(|line| line_text(line).starts_with("\"user\""))
+            .unwrap();
+
+        assert_eq!(
+            format_node(&lines, user, CopyFormat::Php),
+            "[\n    'active' => true,\n    'name' => 'O\\'Brien',\n    'roles' => [\n        'admin',\n    ],\n]"
+        );
+        let json: Value =
+            serde_json::from_str(&format_node(&lines, user, CopyFormat::Json)).unwrap();
+        assert_eq!(
+            json,
+            json!({ "name": "O'Brien", "roles": ["admin"], "active": true })
+        );
+
+        let name = lines
+            .iter()
+            .position(|line| line_text(line).starts_with("\"name\""))
+            .unwrap();
+        assert_eq!(format_node(&lines, name, CopyFormat::Raw), "O'Brien");
+        assert!(
+            format_node(&lines, user, CopyFormat::Raw).starts_with("\"user\": {\n  \"active\"")
+        );
+    }
+}
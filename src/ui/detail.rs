@@ -6,13 +6,33 @@ use regex::Regex;
 use serde_json::{Map, Value};
 use std::collections::{BTreeMap, HashSet};
 
-use crate::protocol::{Payload, PayloadKind};
+use crate::{
+    config::{LocaleConfig, UnknownRenderer},
+    protocol::{Payload, PayloadKind},
+    state::Annotation,
+    ui::{
+        diff::{ChangeOp, DiffOp, line_diff, value_diff},
+        format::ContentFormat,
+        text::{clip, clip_width, display_width},
+    },
+};
 
 #[derive(Debug, Clone)]
 pub struct DetailViewModel {
     pub header: String,
     pub footer: String,
     pub lines: Vec<DetailLine>,
+    /// Source locations of stack frame lines, keyed by line index.
+    pub origins: BTreeMap<usize, SourceLocation>,
+    /// Line to bring into view first: the message of an exception or the
+    /// first value of a log.
+    pub focus_line: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -36,14 +56,48 @@ pub enum SegmentStyle {
     Number,
     Boolean,
     Null,
+    /// Warnings about the content itself, e.g. a dump the client truncated.
+    Notice,
+    /// Type tag set apart from the value it describes, e.g. ` bool `.
+    Badge,
+    /// Diff markers and values only present on the new side.
+    Added,
+    /// Diff markers and values only present on the old side.
+    Removed,
 }
 
 pub fn build_detail_view(payload: &Payload, received_at: SystemTime) -> DetailViewModel {
-    let header = format!(
-        "{} • {}",
-        payload_label(payload),
-        humanize_timestamp(received_at)
-    );
+    build_detail_view_with_width(payload, received_at, None)
+}
+
+/// Like [`build_detail_view`], but fits width-sensitive content such as table
+/// columns into `width` terminal columns.
+pub fn build_detail_view_with_width(
+    payload: &Payload,
+    received_at: SystemTime,
+    width: Option<usize>,
+) -> DetailViewModel {
+    build_labelled_detail_view(payload, received_at, width, None, LocaleConfig::default())
+}
+
+/// Like [`build_detail_view_with_width`], for a payload whose request carries
+/// `label`: `ray($old, $new)->label('diff')` labels the request, not the
+/// payload. Durations and sizes are written for `locale`.
+pub fn build_labelled_detail_view(
+    payload: &Payload,
+    received_at: SystemTime,
+    width: Option<usize>,
+    label: Option<&str>,
+    locale: LocaleConfig,
+) -> DetailViewModel {
+    let mut header = payload_label(payload);
+    if let Some(format) = content_format(payload)
+        && format != ContentFormat::Plain
+        && !format.badge().eq_ignore_ascii_case(&header)
+    {
+        header = format!("{} • {}", header, format.badge());
+    }
+    let header = format!("{} • {}", header, humanize_timestamp(received_at));
 
     let footer = payload
         .origin
@@ -55,24 +109,111 @@ pub fn build_detail_view(payload: &Payload, received_at: SystemTime) -> DetailVi
         })
         .unwrap_or_default();
 
-    let lines = match &payload.kind {
-        PayloadKind::Log => render_log(payload),
-        PayloadKind::Text => render_text(payload),
-        PayloadKind::Table => render_table(payload),
-        PayloadKind::Custom => render_custom(payload),
-        PayloadKind::Label => render_label(payload),
-        PayloadKind::Trace => render_trace(payload),
-        PayloadKind::Exception => render_exception(payload),
-        PayloadKind::Measure => render_measure(payload),
-        PayloadKind::Caller => render_caller(payload),
-        PayloadKind::DecodedJson | PayloadKind::JsonString => render_json(payload),
-        _ => fallback_lines(payload),
+    let mut origins = BTreeMap::new();
+    let lines = if let Some((old, new)) = diff_sides(payload, label) {
+        render_value_diff(&old, &new)
+    } else {
+        match &payload.kind {
+            _ if is_phpinfo(payload) => render_phpinfo(payload),
+            PayloadKind::Log => render_log(payload),
+            PayloadKind::Text => render_text(payload),
+            PayloadKind::Table => render_table(payload, width),
+            PayloadKind::Custom => render_custom(payload),
+            PayloadKind::Label => render_label(payload),
+            PayloadKind::Trace => render_trace(payload, &mut origins),
+            PayloadKind::Exception => render_exception(payload, &mut origins),
+            PayloadKind::Measure => render_measure(payload, locale),
+            PayloadKind::Size => render_size(payload, locale),
+            PayloadKind::ExecutedQuery => render_executed_query(payload, locale),
+            PayloadKind::ApplicationLog => render_application_log(payload),
+            PayloadKind::JobEvent => render_job_event(payload),
+            PayloadKind::Event => render_event(payload),
+            PayloadKind::Boolean => match scalar_content(payload) {
+                Some(scalar) => render_scalar(&scalar),
+                None => fallback_lines(payload),
+            },
+            PayloadKind::Caller => render_caller(payload, &mut origins),
+            PayloadKind::JsonString => render_json_string(payload),
+            PayloadKind::DecodedJson => render_json(payload),
+            _ => fallback_lines(payload),
+        }
     };
 
+    let focus_line = focus_line(&payload.kind, &lines);
+
     DetailViewModel {
         header,
         footer,
         lines,
+        origins,
+        focus_line,
+    }
+}
+
+/// Collapse rule matching stack frames flagged as vendor code.
+pub const VENDOR_FRAMES_RULE: &str = "vendor_frames";
+
+/// Lines to collapse when an event is first shown: every expandable line whose
+/// key (`meta`, `stack trace`, ...) is listed in `rules`, plus vendor frames for
+/// [`VENDOR_FRAMES_RULE`].
+pub fn default_collapsed(detail: &DetailViewModel, rules: &[String]) -> HashSet<usize> {
+    if rules.is_empty() {
+        return HashSet::new();
+    }
+
+    let vendor_frames = rules.iter().any(|rule| rule == VENDOR_FRAMES_RULE);
+    let has_children = compute_has_children(&detail.lines);
+
+    detail
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| has_children[*index])
+        .filter(|(_, line)| {
+            let is_vendor_frame = line.segments.iter().any(|segment| {
+                segment.style == SegmentStyle::Boolean && segment.text == " [vendor]"
+            });
+            (vendor_frames && is_vendor_frame)
+                || line_key(line).is_some_and(|key| rules.iter().any(|rule| rule == key))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Name of the section a line opens: its first segment up to a `:`, `(` or
+/// `=>`, without quotes.
+fn line_key(line: &DetailLine) -> Option<&str> {
+    let text = line.segments.first()?.text.as_str();
+    let end = text.find([':', '(', '=']).unwrap_or(text.len());
+    let key = text[..end].trim().trim_matches('"');
+    (!key.is_empty()).then_some(key)
+}
+
+fn focus_line(kind: &PayloadKind, lines: &[DetailLine]) -> Option<usize> {
+    let starts_with = |line: &DetailLine, prefix: &str| {
+        line.segments
+            .first()
+            .is_some_and(|segment| segment.text.starts_with(prefix))
+    };
+
+    match kind {
+        PayloadKind::Exception => lines
+            .iter()
+            .position(|line| line.indent == 1 && starts_with(line, "message")),
+        PayloadKind::Log => lines
+            .iter()
+            .position(|line| starts_with(line, "- "))
+            .or_else(|| {
+                lines.iter().position(|line| {
+                    !starts_with(line, "Label: ")
+                        && line.segments.iter().any(|segment| !segment.text.is_empty())
+                        && line
+                            .segments
+                            .iter()
+                            .all(|segment| segment.style != SegmentStyle::Notice)
+                })
+            }),
+        _ => None,
     }
 }
 
@@ -105,6 +246,10 @@ pub fn visible_indices_with_children(
 }
 
 fn payload_label(payload: &Payload) -> String {
+    if is_phpinfo(payload) {
+        return "phpinfo".to_string();
+    }
+
     match payload.kind {
         PayloadKind::Log => "log".to_string(),
         PayloadKind::Custom => {
@@ -166,6 +311,10 @@ fn payload_label(payload: &Payload) -> String {
         PayloadKind::HideApp => "hide_app".to_string(),
         PayloadKind::Ban => "ban".to_string(),
         PayloadKind::Charles => "charles".to_string(),
+        PayloadKind::ExecutedQuery => "query".to_string(),
+        PayloadKind::ApplicationLog => "application_log".to_string(),
+        PayloadKind::JobEvent => "job".to_string(),
+        PayloadKind::Event => "event".to_string(),
         PayloadKind::Unknown(_) => "unknown".to_string(),
     }
 }
@@ -179,7 +328,7 @@ fn render_log(payload: &Payload) -> Vec<DetailLine> {
         .and_then(|entry| entry.get("clipboard_data"))
         .and_then(|value| value.as_str())
     {
-        return parse_sf_dump(clipboard);
+        return parse_dump_with_notice(clipboard, max_depth_hint(payload));
     }
 
     if let Some(values) = payload
@@ -212,160 +361,1163 @@ fn render_log(payload: &Payload) -> Vec<DetailLine> {
 }
 
 fn render_text(payload: &Payload) -> Vec<DetailLine> {
-    payload
-        .content_string("content")
-        .map(|text| text.lines().map(parse_plain_line).collect())
-        .unwrap_or_else(|| fallback_lines(payload))
+    match (payload.content_string("content"), content_format(payload)) {
+        (Some(text), Some(format)) => render_formatted(format, text, None),
+        _ => fallback_lines(payload),
+    }
+}
+
+/// Language of the text in a `text` or custom payload, which picks its badge
+/// and renderer. Dumps, images, counts and scalars are not free-form text.
+pub fn content_format(payload: &Payload) -> Option<ContentFormat> {
+    let text = payload.content_string("content")?;
+    match payload.kind {
+        PayloadKind::Text => Some(ContentFormat::detect(text)),
+        PayloadKind::Custom => {
+            let label = payload.content_string("label").map_or("", str::trim);
+            if contains_sf_dump(text)
+                || contains_image_tag(text)
+                || label.eq_ignore_ascii_case("image")
+                || label.eq_ignore_ascii_case("count")
+            {
+                None
+            } else if label.eq_ignore_ascii_case("html") {
+                Some(ContentFormat::Html)
+            } else {
+                Some(ContentFormat::detect(text))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// `text` through the renderer for its language, under `label` if any.
+fn render_formatted(format: ContentFormat, text: &str, label: Option<&str>) -> Vec<DetailLine> {
+    let mut lines = Vec::new();
+    if let Some(label) = label {
+        lines.push(parse_plain_line(&format!("Label: {}", label)));
+        lines.push(parse_plain_line(""));
+    }
+    lines.extend(match format {
+        ContentFormat::Html | ContentFormat::Xml => render_html(None, text),
+        ContentFormat::Json => match serde_json::from_str::<Value>(text) {
+            Ok(value) => serde_json::to_string_pretty(&value)
+                .unwrap_or_default()
+                .lines()
+                .map(parse_plain_line)
+                .collect(),
+            Err(err) => json_error_lines(text, &err),
+        },
+        ContentFormat::Sql => sql_lines(text.trim(), 0),
+        ContentFormat::Plain => text.lines().map(parse_plain_line).collect(),
+    });
+    lines
 }
 
 fn render_custom(payload: &Payload) -> Vec<DetailLine> {
-    if let Some(object) = payload.content_object() {
-        if let Some(content) = object.get("content").and_then(|value| value.as_str()) {
-            let raw_label = object
-                .get("label")
-                .and_then(|value| value.as_str())
-                .map(str::trim)
-                .filter(|label| !label.is_empty());
+    if let Some(scalar) = scalar_content(payload) {
+        return render_scalar(&scalar);
+    }
+
+    if let Some(object) = payload.content_object()
+        && let Some(content) = object.get("content").and_then(|value| value.as_str())
+    {
+        let raw_label = object
+            .get("label")
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|label| !label.is_empty());
+
+        if contains_sf_dump(content) {
+            return render_sf_dump(content, raw_label, max_depth_hint(payload));
+        }
+
+        let is_default_count_label = raw_label
+            .map(|label| label.eq_ignore_ascii_case("count"))
+            .unwrap_or(false);
+
+        if is_default_count_label {
+            return render_count(content, raw_label);
+        }
+
+        let is_default_image_label = raw_label
+            .map(|label| label.eq_ignore_ascii_case("image"))
+            .unwrap_or(false);
 
-            if contains_sf_dump(content) {
-                return render_sf_dump(content, raw_label);
+        if is_default_image_label || contains_image_tag(content) {
+            let src = extract_image_src(content).unwrap_or_else(|| content.trim());
+            return vec![parse_plain_line(src)];
+        }
+
+        if let Some(format) = content_format(payload) {
+            let label = raw_label.filter(|label| !label.eq_ignore_ascii_case("html"));
+            return render_formatted(format, content, label);
+        }
+    }
+
+    fallback_lines(payload)
+}
+
+/// The two values of a diff payload: `old`/`new` content keys, or the two
+/// values of a log labelled `diff` (`label` is the request's label, if any).
+/// String sides holding JSON are decoded; Symfony dumps are reduced to text.
+pub fn diff_sides(payload: &Payload, label: Option<&str>) -> Option<(Value, Value)> {
+    let content = payload.content_object()?;
+    if let (Some(old), Some(new)) = (content.get("old"), content.get("new")) {
+        return Some((decode_diff_side(old), decode_diff_side(new)));
+    }
+
+    let labelled_diff = label
+        .or_else(|| payload.content_string("label"))
+        .is_some_and(|label| label.trim().eq_ignore_ascii_case("diff"));
+    if !labelled_diff {
+        return None;
+    }
+    match content.get("values")?.as_array()?.as_slice() {
+        [old, new] => Some((decode_diff_side(old), decode_diff_side(new))),
+        _ => None,
+    }
+}
+
+fn decode_diff_side(value: &Value) -> Value {
+    let Value::String(text) = value else {
+        return value.clone();
+    };
+    if contains_sf_dump(text) {
+        return Value::String(sanitize_sf_dump(text));
+    }
+    match serde_json::from_str::<Value>(text) {
+        Ok(decoded @ (Value::Object(_) | Value::Array(_))) => decoded,
+        _ => value.clone(),
+    }
+}
+
+/// Longest value preview in a diff line before it is cut.
+const DIFF_PREVIEW_CHARS: usize = 80;
+
+/// Added, removed and changed paths between two values, or a line diff when
+/// both are multi-line text.
+fn render_value_diff(old: &Value, new: &Value) -> Vec<DetailLine> {
+    let segment = |text: String, style| DetailSegment { text, style };
+
+    if let (Value::String(old), Value::String(new)) = (old, new)
+        && (old.contains('\n') || new.contains('\n'))
+    {
+        let diff = line_diff(old, new);
+        let count = |op| diff.iter().filter(|line| line.op == op).count();
+        let mut lines = vec![
+            parse_plain_line(&format!(
+                "Lines: {} added, {} removed",
+                count(DiffOp::Added),
+                count(DiffOp::Removed)
+            )),
+            empty_line(0),
+        ];
+        lines.extend(diff.into_iter().map(|line| {
+            let (marker, style) = match line.op {
+                DiffOp::Same => ("  ", SegmentStyle::Plain),
+                DiffOp::Added => ("+ ", SegmentStyle::Added),
+                DiffOp::Removed => ("- ", SegmentStyle::Removed),
+            };
+            DetailLine {
+                indent: 0,
+                segments: vec![
+                    segment(marker.to_string(), style),
+                    segment(line.text, style),
+                ],
             }
+        }));
+        return lines;
+    }
 
-            let is_default_count_label = raw_label
-                .map(|label| label.eq_ignore_ascii_case("count"))
-                .unwrap_or(false);
+    let changes = value_diff(old, new);
+    if changes.is_empty() {
+        return vec![parse_plain_line("No differences")];
+    }
+
+    let count = |op| changes.iter().filter(|change| change.op == op).count();
+    let mut lines = vec![
+        parse_plain_line(&format!(
+            "{} change{}: {} added, {} removed, {} changed",
+            changes.len(),
+            if changes.len() == 1 { "" } else { "s" },
+            count(ChangeOp::Added),
+            count(ChangeOp::Removed),
+            count(ChangeOp::Changed)
+        )),
+        empty_line(0),
+    ];
+
+    let preview = |value: &Option<Value>| {
+        let text = value.as_ref().map(Value::to_string).unwrap_or_default();
+        clip(&text, DIFF_PREVIEW_CHARS, "…")
+    };
+
+    for change in &changes {
+        let path = if change.path.is_empty() {
+            "value".to_string()
+        } else {
+            change.path.clone()
+        };
+        let key = segment(format!("{}: ", path), SegmentStyle::Key);
+        let segments = match change.op {
+            ChangeOp::Added => vec![
+                segment("+ ".to_string(), SegmentStyle::Added),
+                key,
+                segment(preview(&change.new), SegmentStyle::Added),
+            ],
+            ChangeOp::Removed => vec![
+                segment("- ".to_string(), SegmentStyle::Removed),
+                key,
+                segment(preview(&change.old), SegmentStyle::Removed),
+            ],
+            ChangeOp::Changed => vec![
+                segment("~ ".to_string(), SegmentStyle::Type),
+                key,
+                segment(preview(&change.old), SegmentStyle::Removed),
+                segment(" → ".to_string(), SegmentStyle::Plain),
+                segment(preview(&change.new), SegmentStyle::Added),
+            ],
+        };
+        lines.push(DetailLine {
+            indent: 0,
+            segments,
+        });
+    }
+    lines
+}
+
+/// A bare scalar sent with `ray(true)`, `ray(null)` or `ray(42)`.
+#[derive(Debug, Clone, Copy)]
+pub struct ScalarContent<'a> {
+    pub value: &'a Value,
+    /// `bool`, `null`, `int` or `float`.
+    pub type_name: &'static str,
+    /// Label set by the caller; Ray's own `Boolean`/`Null` labels are dropped.
+    pub label: Option<&'a str>,
+}
+
+impl ScalarContent<'_> {
+    /// `true`, or `flag: true` when the caller labelled it.
+    pub fn summary(&self) -> String {
+        match self.label {
+            Some(label) => format!("{}: {}", label, self.value),
+            None => self.value.to_string(),
+        }
+    }
+}
+
+pub fn scalar_content(payload: &Payload) -> Option<ScalarContent<'_>> {
+    let content = payload.content_object()?;
+    let value = content.get("content")?;
+    let type_name = match value {
+        Value::Bool(_) => "bool",
+        Value::Null => "null",
+        Value::Number(number) if number.is_f64() => "float",
+        Value::Number(_) => "int",
+        _ => return None,
+    };
+    let label = content
+        .get("label")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|label| {
+            !label.is_empty()
+                && !label.eq_ignore_ascii_case("boolean")
+                && !label.eq_ignore_ascii_case("null")
+        });
+    Some(ScalarContent {
+        value,
+        type_name,
+        label,
+    })
+}
+
+fn render_scalar(scalar: &ScalarContent<'_>) -> Vec<DetailLine> {
+    let style = match scalar.value {
+        Value::Bool(_) => SegmentStyle::Boolean,
+        Value::Null => SegmentStyle::Null,
+        _ => SegmentStyle::Number,
+    };
+
+    let mut lines = Vec::new();
+    if let Some(label) = scalar.label {
+        lines.push(detail_key_value("Label", label));
+    }
+    lines.push(DetailLine {
+        indent: 0,
+        segments: vec![
+            DetailSegment {
+                text: scalar.value.to_string(),
+                style,
+            },
+            DetailSegment {
+                text: "  ".to_string(),
+                style: SegmentStyle::Plain,
+            },
+            DetailSegment {
+                text: format!(" {} ", scalar.type_name),
+                style: SegmentStyle::Badge,
+            },
+        ],
+    });
+    lines
+}
+
+fn render_label(payload: &Payload) -> Vec<DetailLine> {
+    let label = payload
+        .content_string("label")
+        .map(|label| label.trim())
+        .filter(|label| !label.is_empty())
+        .unwrap_or("label payload");
+
+    vec![parse_plain_line(label)]
+}
+
+fn render_count(content: &str, raw_label: Option<&str>) -> Vec<DetailLine> {
+    let mut lines = Vec::new();
+
+    if let Some(label) = raw_label
+        .map(|label| label.trim())
+        .filter(|label| !label.is_empty())
+        .filter(|label| !label.eq_ignore_ascii_case("count"))
+    {
+        lines.push(parse_plain_line(&format!("Label: {}", label)));
+        lines.push(parse_plain_line(""));
+    }
+
+    lines.push(parse_plain_line(content.trim()));
+    lines
+}
+
+fn render_sf_dump(
+    content: &str,
+    raw_label: Option<&str>,
+    max_depth: Option<u64>,
+) -> Vec<DetailLine> {
+    let mut lines = Vec::new();
+
+    if let Some(label) = raw_label
+        .map(|label| label.trim())
+        .filter(|label| !label.is_empty())
+        .filter(|label| !label.eq_ignore_ascii_case("json"))
+    {
+        lines.push(parse_plain_line(&format!("Label: {}", label)));
+        lines.push(parse_plain_line(""));
+    }
+
+    let mut dump_lines = parse_dump_with_notice(content, max_depth);
+    if dump_lines.is_empty() {
+        dump_lines.push(parse_plain_line(content.trim()));
+    }
+    lines.extend(dump_lines);
+    lines
+}
+
+/// `max_depth` the client reports for its dump, from the payload content or
+/// its `meta` (an object, or the list Ray's log payloads send).
+fn max_depth_hint(payload: &Payload) -> Option<u64> {
+    let content = payload.content_object()?;
+    let meta = content.get("meta").and_then(|meta| match meta {
+        Value::Array(entries) => entries.first(),
+        other => Some(other),
+    });
+    [
+        content.get("max_depth"),
+        meta.and_then(|meta| meta.get("max_depth")),
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|value| {
+        value
+            .as_u64()
+            .or_else(|| value.as_str().and_then(|text| text.trim().parse().ok()))
+    })
+}
 
-            if is_default_count_label {
-                return render_count(content, raw_label);
+/// Parses a VarDumper dump, led by a notice when the client cut it short.
+fn parse_dump_with_notice(dump: &str, max_depth: Option<u64>) -> Vec<DetailLine> {
+    let mut lines = parse_sf_dump(dump);
+    if let Some(notice) = truncation_notice(&lines, max_depth) {
+        lines.splice(0..0, [notice, parse_plain_line("")]);
+    }
+    lines
+}
+
+/// VarDumper elides values past its depth, item and string limits with `…`
+/// markers (`[ …3]`, `{#12 …}`, a `…5` row, `"abc"…120`). Says so explicitly
+/// instead of letting the cut-off tree pass for the whole value.
+fn truncation_notice(lines: &[DetailLine], max_depth: Option<u64>) -> Option<DetailLine> {
+    let mut cut_depth: Option<usize> = None;
+    let mut shortened = false;
+    for line in lines {
+        let text: String = line
+            .segments
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect();
+        if DEPTH_CUT_RE.is_match(&text) {
+            cut_depth = Some(cut_depth.map_or(line.indent, |depth| depth.min(line.indent)));
+        } else if SHORTENED_RE.is_match(text.trim()) {
+            shortened = true;
+        }
+    }
+
+    let text = match (cut_depth, shortened) {
+        (None, false) => return None,
+        (Some(depth), shortened) => format!(
+            "⚠ dump truncated by client (depth {}){}",
+            max_depth.unwrap_or(depth as u64),
+            if shortened {
+                "; long arrays and strings shortened"
+            } else {
+                ""
             }
+        ),
+        (None, true) => {
+            "⚠ dump truncated by client (long arrays and strings shortened)".to_string()
+        }
+    };
 
-            let is_default_image_label = raw_label
-                .map(|label| label.eq_ignore_ascii_case("image"))
-                .unwrap_or(false);
+    Some(DetailLine {
+        indent: 0,
+        segments: vec![DetailSegment {
+            text,
+            style: SegmentStyle::Notice,
+        }],
+    })
+}
+
+fn render_measure(payload: &Payload, locale: LocaleConfig) -> Vec<DetailLine> {
+    let content = match payload.content_object() {
+        Some(object) => object,
+        None => return fallback_lines(payload),
+    };
+
+    let mut lines = Vec::new();
+
+    if let Some(name) = content.get("name").and_then(|value| value.as_str()) {
+        lines.push(detail_key_value("Name", name));
+    }
+
+    if let Some(value) = content.get("total_time")
+        && let Some(formatted) = format_duration(value, locale)
+    {
+        lines.push(detail_key_value("Total time", &formatted));
+    }
+
+    if let Some(value) = content.get("time_since_last_call")
+        && let Some(formatted) = format_duration(value, locale)
+    {
+        lines.push(detail_key_value("Since last call", &formatted));
+    }
+
+    if let Some(value) = content.get("max_memory_usage_during_total_time")
+        && let Some(formatted) = format_bytes(value, locale)
+    {
+        lines.push(detail_key_value("Max memory (total)", &formatted));
+    }
+
+    if let Some(value) = content.get("max_memory_usage_since_last_call")
+        && let Some(formatted) = format_bytes(value, locale)
+    {
+        lines.push(detail_key_value("Max memory (delta)", &formatted));
+    }
 
-            if is_default_image_label || contains_image_tag(content) {
-                let src = extract_image_src(content).unwrap_or_else(|| content.trim());
-                return vec![parse_plain_line(src)];
+    if let Some(new_timer) = content
+        .get("is_new_timer")
+        .and_then(|value| value.as_bool())
+    {
+        lines.push(detail_key_value(
+            "New timer",
+            if new_timer { "yes" } else { "no" },
+        ));
+    }
+
+    if lines.is_empty() {
+        fallback_lines(payload)
+    } else {
+        lines
+    }
+}
+
+/// A framework log entry forwarded by Laravel: level, message and context.
+fn render_application_log(payload: &Payload) -> Vec<DetailLine> {
+    let Some(content) = payload.content_object() else {
+        return fallback_lines(payload);
+    };
+
+    let mut lines = Vec::new();
+    if let Some(level) = content.get("level").and_then(|value| value.as_str()) {
+        lines.push(detail_key_value("Level", level));
+    }
+    if let Some(message) = content.get("value").or_else(|| content.get("message")) {
+        push_value_lines(&mut lines, 0, "Message", message);
+    }
+    match content.get("context") {
+        Some(Value::Object(context)) if !context.is_empty() => {
+            push_section_header(&mut lines, "Context");
+            for (key, value) in context {
+                push_value_lines(&mut lines, 1, key, value);
+            }
+        }
+        Some(Value::Array(context)) if !context.is_empty() => {
+            push_section_header(&mut lines, "Context");
+            for (index, value) in context.iter().enumerate() {
+                push_value_lines(&mut lines, 1, &index.to_string(), value);
             }
+        }
+        _ => {}
+    }
 
-            let is_default_html_label = raw_label
-                .map(|label| label.eq_ignore_ascii_case("html"))
-                .unwrap_or(false);
+    if lines.is_empty() {
+        fallback_lines(payload)
+    } else {
+        lines
+    }
+}
 
-            if looks_like_html(content) || is_default_html_label {
-                let label = if is_default_html_label {
-                    None
-                } else {
-                    raw_label
-                };
-                return render_html(label, content);
+/// A queue job event from Laravel: which event, the job's class, then the
+/// dumped job and the exception a failed job threw.
+fn render_job_event(payload: &Payload) -> Vec<DetailLine> {
+    let Some(content) = payload.content_object() else {
+        return fallback_lines(payload);
+    };
+
+    let mut lines = Vec::new();
+    if let Some(event) = content.get("event_name").and_then(|value| value.as_str()) {
+        lines.push(detail_key_value("Event", event));
+    }
+    if let Some(class) = job_class(payload) {
+        lines.push(detail_key_value("Job", &class));
+    }
+    for (key, name) in [("job", "Payload"), ("exception", "Exception")] {
+        push_dump_section(&mut lines, name, content.get(key));
+    }
+
+    if lines.is_empty() {
+        fallback_lines(payload)
+    } else {
+        lines
+    }
+}
+
+/// An event dispatched in Laravel: its name, then the event object for
+/// class-based events and the payload it was dispatched with.
+fn render_event(payload: &Payload) -> Vec<DetailLine> {
+    let Some(content) = payload.content_object() else {
+        return fallback_lines(payload);
+    };
+
+    let mut lines = Vec::new();
+    if let Some(name) = content.get("name").and_then(|value| value.as_str()) {
+        lines.push(detail_key_value("Event", name));
+    }
+    push_dump_section(&mut lines, "Event data", content.get("event"));
+    push_dump_section(&mut lines, "Payload", content.get("payload"));
+
+    if lines.is_empty() {
+        fallback_lines(payload)
+    } else {
+        lines
+    }
+}
+
+/// A section called `name` holding `value`, a var-dumper dump or plain JSON;
+/// nothing when the client sent no value.
+fn push_dump_section(lines: &mut Vec<DetailLine>, name: &str, value: Option<&Value>) {
+    match value {
+        None | Some(Value::Null) => {}
+        Some(Value::String(dump)) if contains_sf_dump(dump) => {
+            push_section_header(lines, name);
+            lines.extend(parse_sf_dump(dump).into_iter().map(|mut line| {
+                line.indent += 1;
+                line
+            }));
+        }
+        Some(Value::Object(fields)) => {
+            push_section_header(lines, name);
+            for (field, value) in fields {
+                push_value_lines(lines, 1, field, value);
+            }
+        }
+        Some(Value::Array(items)) => {
+            push_section_header(lines, name);
+            for (index, value) in items.iter().enumerate() {
+                push_value_lines(lines, 1, &index.to_string(), value);
+            }
+        }
+        Some(value) => {
+            push_section_header(lines, name);
+            push_value_lines(lines, 1, "value", value);
+        }
+    }
+}
+
+/// Class of the job in a `job_event`, read from the head of its dump
+/// (`SendInvoice {#1234 …`) or from a `class`/`displayName` field.
+pub fn job_class(payload: &Payload) -> Option<String> {
+    match payload.content_object()?.get("job")? {
+        Value::String(dump) => {
+            let text = if contains_sf_dump(dump) {
+                sanitize_sf_dump(dump)
+            } else {
+                dump.clone()
+            };
+            let head = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+            JOB_CLASS_RE
+                .captures(head)
+                .map(|captures| captures[1].to_string())
+        }
+        Value::Object(fields) => ["class", "displayName"]
+            .iter()
+            .find_map(|key| fields.get(*key).and_then(|value| value.as_str()))
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Laravel's `executed_query`: the SQL with its bindings filled in, then the
+/// raw bindings, duration and connection.
+fn render_executed_query(payload: &Payload, locale: LocaleConfig) -> Vec<DetailLine> {
+    let Some(content) = payload.content_object() else {
+        return fallback_lines(payload);
+    };
+    let Some(sql) = content.get("sql").and_then(|value| value.as_str()) else {
+        return fallback_lines(payload);
+    };
+
+    let mut lines = Vec::new();
+    push_section_header(&mut lines, "SQL");
+    lines.extend(sql_lines(&query_sql(payload, sql), 1));
+    lines.push(empty_line(0));
+
+    if let Some(value) = content.get("time")
+        && let Some(formatted) = format_duration(value, locale)
+    {
+        lines.push(detail_key_value("Time", &formatted));
+    }
+    if let Some(connection) = content
+        .get("connection_name")
+        .and_then(|value| value.as_str())
+    {
+        lines.push(detail_key_value("Connection", connection));
+    }
+
+    match content.get("bindings") {
+        Some(Value::Array(bindings)) if !bindings.is_empty() => {
+            push_section_header(&mut lines, "Bindings");
+            for (index, value) in bindings.iter().enumerate() {
+                push_value_lines(&mut lines, 1, &index.to_string(), value);
             }
         }
+        Some(Value::Object(bindings)) if !bindings.is_empty() => {
+            push_section_header(&mut lines, "Bindings");
+            for (name, value) in bindings {
+                push_value_lines(&mut lines, 1, name, value);
+            }
+        }
+        _ => {}
+    }
+
+    lines
+}
+
+fn sql_lines(sql: &str, indent: usize) -> Vec<DetailLine> {
+    sql.lines()
+        .map(|line| DetailLine {
+            indent,
+            segments: vec![DetailSegment {
+                text: line.to_string(),
+                style: SegmentStyle::String,
+            }],
+        })
+        .collect()
+}
+
+/// `sql` with the payload's bindings substituted for its `?` placeholders,
+/// or for `:name` ones when the bindings are keyed. Placeholders inside quoted
+/// literals are left alone, as are the extra ones when bindings run out.
+/// Newer Laravel versions send the SQL already filled in and no bindings.
+pub fn query_sql(payload: &Payload, sql: &str) -> String {
+    let bindings = payload.content_object().and_then(|map| map.get("bindings"));
+    let mut positional = match bindings {
+        Some(Value::Array(values)) => values.iter(),
+        _ => [].iter(),
+    };
+    let named = match bindings {
+        Some(Value::Object(map)) => Some(map),
+        _ => None,
+    };
+
+    let mut out = String::with_capacity(sql.len());
+    let mut quote: Option<char> = None;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((index, ch)) = chars.next() {
+        match (quote, ch) {
+            (Some(_), '\\') => {
+                out.push(ch);
+                if let Some((_, escaped)) = chars.next() {
+                    out.push(escaped);
+                }
+                continue;
+            }
+            (Some(open), _) if ch == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(ch),
+            (None, '?') => {
+                if let Some(value) = positional.next() {
+                    out.push_str(&sql_literal(value));
+                    continue;
+                }
+            }
+            (None, ':') if named.is_some() => {
+                let name: String = sql[index + 1..]
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                    .collect();
+                if let Some(value) = named.and_then(|map| map.get(&name)) {
+                    out.push_str(&sql_literal(value));
+                    for _ in 0..name.len() {
+                        chars.next();
+                    }
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// A binding as Laravel's `toRawSql` writes it.
+fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(flag) => if *flag { "1" } else { "0" }.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => format!("'{}'", text.replace('\'', "''")),
+        other => format!("'{}'", other.to_string().replace('\'', "''")),
+    }
+}
+
+fn render_size(payload: &Payload, locale: LocaleConfig) -> Vec<DetailLine> {
+    if let Some(bytes) = size_bytes(payload) {
+        return vec![
+            detail_key_value("Size", &humanize_bytes(bytes, locale)),
+            detail_key_value("Bytes", &format!("{}", bytes)),
+        ];
+    }
+
+    match payload.content_string("size") {
+        Some("sm") => vec![detail_key_value("Text size", "small")],
+        Some("lg") => vec![detail_key_value("Text size", "large")],
+        _ => fallback_lines(payload),
+    }
+}
+
+/// Byte count carried by a `size` payload. Ray's `small()`/`large()` send
+/// `sm`/`lg` text sizes instead, which yield `None`.
+pub fn size_bytes(payload: &Payload) -> Option<f64> {
+    let value = payload.content_object()?.get("size")?;
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|text| text.trim().parse().ok()))
+        .filter(|bytes: &f64| bytes.is_finite())
+}
+
+/// Lines appended under a memory or size reading that repeats an earlier
+/// label: how many readings there were, the previous one and the change.
+/// `readings` runs oldest first and ends with the reading on screen.
+pub fn reading_history_lines(
+    label: &str,
+    readings: &[f64],
+    locale: LocaleConfig,
+) -> Vec<DetailLine> {
+    let [.., previous, current] = readings else {
+        return Vec::new();
+    };
+
+    let change = current - previous;
+    let sign = if change < 0.0 { "-" } else { "+" };
+    let mut change_text = format!("{}{}", sign, humanize_bytes(change.abs(), locale));
+    if *previous != 0.0 {
+        change_text.push_str(&format!(
+            " ({}{}%)",
+            sign,
+            locale.decimal((change / previous * 100.0).abs(), 1)
+        ));
+    }
+
+    vec![
+        empty_line(0),
+        detail_key_value("Readings", &format!("{} for \"{}\"", readings.len(), label)),
+        detail_key_value("Previous", &humanize_bytes(*previous, locale)),
+        detail_key_value("Change", &change_text),
+    ]
+}
+
+/// Lines appended under an event's content for the notes tools attached to
+/// it, each as `source: text` with its link underneath.
+pub fn annotation_lines(annotations: &[Annotation]) -> Vec<DetailLine> {
+    let mut lines = Vec::new();
+    for annotation in annotations {
+        if lines.is_empty() {
+            lines.push(empty_line(0));
+        }
+        lines.push(detail_key_value(
+            annotation.source.as_deref().unwrap_or("Note"),
+            &annotation.text,
+        ));
+        if let Some(url) = &annotation.url {
+            let mut line = detail_key_value("Link", url);
+            line.indent = 1;
+            lines.push(line);
+        }
     }
+    lines
+}
 
-    fallback_lines(payload)
+/// `ray()->phpinfo()` arrives as a table labelled `PHPInfo`; some clients send
+/// a dedicated `phpinfo` type instead.
+pub fn is_phpinfo(payload: &Payload) -> bool {
+    let values_map = payload
+        .content_object()
+        .and_then(|content| content.get("values"))
+        .is_some_and(Value::is_object);
+    match payload.kind {
+        PayloadKind::PhpInfo => values_map,
+        PayloadKind::Table => {
+            values_map
+                && payload
+                    .content_string("label")
+                    .is_some_and(|label| label.trim().eq_ignore_ascii_case("phpinfo"))
+        }
+        _ => false,
+    }
 }
 
-fn render_label(payload: &Payload) -> Vec<DetailLine> {
-    let label = payload
-        .content_string("label")
-        .map(|label| label.trim())
-        .filter(|label| !label.is_empty())
-        .unwrap_or("label payload");
+/// Sections of the phpinfo view, in display order, with their keys by
+/// priority. Keys listed nowhere go to a section named after their ini prefix
+/// (`xdebug.mode` under `xdebug`) or, without one, to `PHP`.
+const PHPINFO_SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "PHP",
+        &[
+            "PHP version",
+            "display_errors",
+            "error_reporting",
+            "date.timezone",
+        ],
+    ),
+    (
+        "Limits",
+        &[
+            "Memory limit",
+            "memory_limit",
+            "Max post size",
+            "post_max_size",
+            "Max file upload size",
+            "upload_max_filesize",
+            "max_execution_time",
+            "max_input_time",
+            "max_input_vars",
+        ],
+    ),
+    (
+        "Configuration files",
+        &["PHP ini file", "PHP scanned ini file"],
+    ),
+];
 
-    vec![parse_plain_line(label)]
+fn phpinfo_section(key: &str) -> (String, usize) {
+    for (name, keys) in PHPINFO_SECTIONS {
+        if let Some(priority) = keys.iter().position(|known| *known == key) {
+            return (name.to_string(), priority);
+        }
+    }
+    match key.split_once('.') {
+        Some((prefix, _)) if !prefix.is_empty() => (prefix.to_string(), usize::MAX),
+        _ => ("PHP".to_string(), usize::MAX),
+    }
 }
 
-fn render_count(content: &str, raw_label: Option<&str>) -> Vec<DetailLine> {
-    let mut lines = Vec::new();
+/// A phpinfo section and its entries as `(priority, key, value)`.
+type PhpInfoSection<'a> = (String, Vec<(usize, &'a str, &'a Value)>);
 
-    if let Some(label) = raw_label
-        .map(|label| label.trim())
-        .filter(|label| !label.is_empty())
-        .filter(|label| !label.eq_ignore_ascii_case("count"))
-    {
-        lines.push(parse_plain_line(&format!("Label: {}", label)));
-        lines.push(parse_plain_line(""));
+fn render_phpinfo(payload: &Payload) -> Vec<DetailLine> {
+    let Some(values) = payload
+        .content_object()
+        .and_then(|content| content.get("values"))
+        .and_then(Value::as_object)
+    else {
+        return fallback_lines(payload);
+    };
+
+    let mut sections: Vec<PhpInfoSection> = PHPINFO_SECTIONS
+        .iter()
+        .map(|(name, _)| (name.to_string(), Vec::new()))
+        .collect();
+    let mut extensions = Vec::new();
+
+    for (key, value) in values {
+        if key == "Extensions" {
+            extensions = match value {
+                Value::String(list) => list
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                Value::Array(items) => items.iter().map(json_value_preview).collect(),
+                other => vec![json_value_preview(other)],
+            };
+            continue;
+        }
+
+        let (section, priority) = phpinfo_section(key);
+        match sections.iter_mut().find(|(name, _)| *name == section) {
+            Some((_, entries)) => entries.push((priority, key, value)),
+            None => sections.push((section, vec![(priority, key, value)])),
+        }
     }
 
-    lines.push(parse_plain_line(content.trim()));
-    lines
-}
+    // Ini prefix sections follow the fixed ones alphabetically.
+    sections[PHPINFO_SECTIONS.len()..].sort_by(|a, b| a.0.cmp(&b.0));
 
-fn render_sf_dump(content: &str, raw_label: Option<&str>) -> Vec<DetailLine> {
     let mut lines = Vec::new();
+    for (name, mut entries) in sections {
+        if entries.is_empty() {
+            continue;
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+        push_section_header(&mut lines, &name);
+        for (_, key, value) in entries {
+            let text = json_value_preview(value);
+            let files: Vec<&str> = text
+                .split(',')
+                .map(str::trim)
+                .filter(|file| !file.is_empty())
+                .collect();
+            if key == "PHP scanned ini file" && files.len() > 1 {
+                let mut line = detail_key_value(key, &format!("{} files", files.len()));
+                line.indent = 1;
+                lines.push(line);
+                for file in files {
+                    let mut line = parse_plain_line(file);
+                    line.indent = 2;
+                    lines.push(line);
+                }
+            } else {
+                let mut line = detail_key_value(key, &text);
+                line.indent = 1;
+                lines.push(line);
+            }
+        }
+    }
 
-    if let Some(label) = raw_label
-        .map(|label| label.trim())
-        .filter(|label| !label.is_empty())
-        .filter(|label| !label.eq_ignore_ascii_case("json"))
-    {
-        lines.push(parse_plain_line(&format!("Label: {}", label)));
-        lines.push(parse_plain_line(""));
+    if !extensions.is_empty() {
+        extensions.sort_by_key(|name| name.to_lowercase());
+        push_section_header(&mut lines, &format!("Extensions ({})", extensions.len()));
+        for name in extensions {
+            let mut line = parse_plain_line(&name);
+            line.indent = 1;
+            lines.push(line);
+        }
     }
 
-    let mut dump_lines = parse_sf_dump(content);
-    if dump_lines.is_empty() {
-        dump_lines.push(parse_plain_line(content.trim()));
+    if lines.is_empty() {
+        fallback_lines(payload)
+    } else {
+        lines
     }
-    lines.extend(dump_lines);
-    lines
 }
 
-fn render_measure(payload: &Payload) -> Vec<DetailLine> {
-    let content = match payload.content_object() {
-        Some(object) => object,
-        None => return fallback_lines(payload),
+fn push_section_header(lines: &mut Vec<DetailLine>, name: &str) {
+    if !lines.is_empty() {
+        lines.push(empty_line(0));
+    }
+    lines.push(DetailLine {
+        indent: 0,
+        segments: vec![DetailSegment {
+            text: name.to_string(),
+            style: SegmentStyle::Type,
+        }],
+    });
+}
+
+pub(crate) fn line_text(line: &DetailLine) -> String {
+    line.segments
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
+
+/// Path from the root to line `index`: object keys and `[n]` array
+/// positions, e.g. `["user", "roles", "[0]"]`. Lines without a key of their
+/// own, such as a dump's `array:3 [` root, add nothing.
+pub fn breadcrumb(lines: &[DetailLine], index: usize) -> Vec<String> {
+    let parent_of = |child: usize| {
+        let indent = lines[child].indent;
+        (0..child)
+            .rev()
+            .find(|&line| lines[line].indent < indent && !line_text(&lines[line]).trim().is_empty())
     };
 
-    let mut lines = Vec::new();
+    let mut path = Vec::new();
+    let mut current = (index < lines.len()).then_some(index);
+    while let Some(line) = current {
+        let parent = parent_of(line);
+        let text = line_text(&lines[line]);
+        let segment = match path_key(text.trim()) {
+            Some(key) if key.parse::<usize>().is_ok() => Some(format!("[{}]", key)),
+            Some(key) => Some(key.to_string()),
+            // Keyless items of a list, e.g. JSON array values.
+            None => parent.map(|parent| {
+                let position = lines[parent + 1..line]
+                    .iter()
+                    .filter(|sibling| sibling.indent == lines[line].indent)
+                    .map(line_text)
+                    .filter(|text| {
+                        let text = text.trim();
+                        !text.is_empty() && !text.starts_with(['}', ']'])
+                    })
+                    .count();
+                format!("[{}]", position)
+            }),
+        };
+        path.extend(segment);
+        current = parent;
+    }
+    path.reverse();
+    path
+}
 
-    if let Some(name) = content.get("name").and_then(|value| value.as_str()) {
-        lines.push(detail_key_value("Name", name));
+/// `user.roles[0]`: the breadcrumb as a path to paste into code.
+pub fn dotted_path(breadcrumb: &[String]) -> String {
+    let mut path = String::new();
+    for segment in breadcrumb {
+        if !path.is_empty() && !segment.starts_with('[') {
+            path.push('.');
+        }
+        path.push_str(segment);
     }
+    path
+}
 
-    if let Some(value) = content.get("total_time") {
-        if let Some(formatted) = format_duration(value) {
-            lines.push(detail_key_value("Total time", &formatted));
-        }
+/// The key a line starts with: `"user" => …` and `0 => …` in dumps, `+name:` or
+/// `-secret:` object properties, `"name": …` in JSON and `Name: …` pairs.
+pub(crate) fn path_key(text: &str) -> Option<&str> {
+    fn unquote(key: &str) -> &str {
+        key.trim().trim_matches(['"', '\''])
     }
 
-    if let Some(value) = content.get("time_since_last_call") {
-        if let Some(formatted) = format_duration(value) {
-            lines.push(detail_key_value("Since last call", &formatted));
-        }
+    if let Some((key, _)) = text.split_once(" => ") {
+        return Some(unquote(key)).filter(|key| !key.is_empty());
+    }
+    if let Some(property) = text.strip_prefix(['+', '-', '#']) {
+        return property
+            .split_once(':')
+            .map(|(key, _)| unquote(key))
+            .filter(|key| !key.is_empty());
     }
+    if let Some(quoted) = text.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        return quoted[end + 1..].starts_with(':').then_some(&quoted[..end]);
+    }
+    let (key, _) = text
+        .split_once(": ")
+        .or_else(|| text.strip_suffix(':').map(|key| (key, "")))?;
+    (!key.is_empty() && !key.contains(['"', '\'', '{', '['])).then_some(key)
+}
 
-    if let Some(value) = content.get("max_memory_usage_during_total_time") {
-        if let Some(formatted) = format_bytes(value) {
-            lines.push(detail_key_value("Max memory (total)", &formatted));
-        }
+/// Lines with text, i.e. what a detail filter counts and searches.
+pub fn content_line_count(detail: &DetailViewModel) -> usize {
+    detail
+        .lines
+        .iter()
+        .filter(|line| !line_text(line).trim().is_empty())
+        .count()
+}
+
+/// Narrows the view to the lines containing `query` (case-insensitive), the
+/// lines nested under them, and their ancestors so every match keeps its
+/// path. Returns how many lines matched.
+pub fn filter_lines(detail: &mut DetailViewModel, query: &str) -> usize {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return content_line_count(detail);
     }
 
-    if let Some(value) = content.get("max_memory_usage_since_last_call") {
-        if let Some(formatted) = format_bytes(value) {
-            lines.push(detail_key_value("Max memory (delta)", &formatted));
+    let texts: Vec<String> = detail.lines.iter().map(line_text).collect();
+    let mut parents = vec![None; texts.len()];
+    let mut open: Vec<usize> = Vec::new();
+    for (index, line) in detail.lines.iter().enumerate() {
+        if texts[index].trim().is_empty() {
+            continue;
+        }
+        while open
+            .last()
+            .is_some_and(|&parent| detail.lines[parent].indent >= line.indent)
+        {
+            open.pop();
         }
+        parents[index] = open.last().copied();
+        open.push(index);
     }
 
-    if let Some(new_timer) = content
-        .get("is_new_timer")
-        .and_then(|value| value.as_bool())
-    {
-        lines.push(detail_key_value(
-            "New timer",
-            if new_timer { "yes" } else { "no" },
-        ));
+    let mut keep = vec![false; texts.len()];
+    let mut matched = 0;
+    let mut index = 0;
+    while index < texts.len() {
+        if texts[index].trim().is_empty() || !texts[index].to_lowercase().contains(&query) {
+            index += 1;
+            continue;
+        }
+        matched += 1;
+        keep[index] = true;
+        let mut parent = parents[index];
+        while let Some(ancestor) = parent.filter(|&ancestor| !keep[ancestor]) {
+            keep[ancestor] = true;
+            parent = parents[ancestor];
+        }
+        let indent = detail.lines[index].indent;
+        index += 1;
+        while index < texts.len() && detail.lines[index].indent > indent {
+            if !texts[index].trim().is_empty() {
+                keep[index] = true;
+                if texts[index].to_lowercase().contains(&query) {
+                    matched += 1;
+                }
+            }
+            index += 1;
+        }
     }
 
-    if lines.is_empty() {
-        fallback_lines(payload)
-    } else {
-        lines
-    }
+    let mut renumbered = BTreeMap::new();
+    let mut lines = Vec::new();
+    for (index, line) in std::mem::take(&mut detail.lines).into_iter().enumerate() {
+        if keep[index] {
+            renumbered.insert(index, lines.len());
+            lines.push(line);
+        }
+    }
+    detail.lines = lines;
+    detail.origins = std::mem::take(&mut detail.origins)
+        .into_iter()
+        .filter_map(|(index, origin)| Some((*renumbered.get(&index)?, origin)))
+        .collect();
+    detail.focus_line = detail
+        .focus_line
+        .and_then(|index| renumbered.get(&index).copied());
+    matched
 }
 
-fn render_trace(payload: &Payload) -> Vec<DetailLine> {
+fn render_trace(
+    payload: &Payload,
+    origins: &mut BTreeMap<usize, SourceLocation>,
+) -> Vec<DetailLine> {
     let mut lines = Vec::new();
 
     if let Some(label) = payload
@@ -392,22 +1544,26 @@ fn render_trace(payload: &Payload) -> Vec<DetailLine> {
 
     for (index, frame) in frames.iter().enumerate() {
         if let Some(frame) = frame.as_object() {
-            push_frame_lines(index, frame, 0, &mut lines);
+            push_frame_lines(index, frame, 0, &mut lines, origins);
             lines.push(parse_plain_line(""));
         }
     }
 
-    if let Some(last) = lines.last() {
-        if last.segments.len() == 1 && last.segments[0].text.is_empty() {
-            // remove trailing blank line
-            lines.pop();
-        }
+    if let Some(last) = lines.last()
+        && last.segments.len() == 1
+        && last.segments[0].text.is_empty()
+    {
+        // remove trailing blank line
+        lines.pop();
     }
 
     lines
 }
 
-fn render_exception(payload: &Payload) -> Vec<DetailLine> {
+fn render_exception(
+    payload: &Payload,
+    origins: &mut BTreeMap<usize, SourceLocation>,
+) -> Vec<DetailLine> {
     let content = match payload.content_object() {
         Some(content) => content,
         None => return fallback_lines(payload),
@@ -498,6 +1654,15 @@ fn render_exception(payload: &Payload) -> Vec<DetailLine> {
                     });
                 }
 
+                if let Some(file) = file {
+                    origins.insert(
+                        lines.len(),
+                        SourceLocation {
+                            file: file.to_string(),
+                            line: line_number.and_then(|line| u32::try_from(line).ok()),
+                        },
+                    );
+                }
                 lines.push(DetailLine {
                     indent: 1,
                     segments,
@@ -517,7 +1682,7 @@ fn render_exception(payload: &Payload) -> Vec<DetailLine> {
             let frame_count = frames.len();
             for (index, frame) in frames.iter().enumerate() {
                 if let Some(frame) = frame.as_object() {
-                    push_frame_lines(index, frame, 2, &mut lines);
+                    push_frame_lines(index, frame, 2, &mut lines, origins);
                     if index + 1 < frame_count {
                         lines.push(empty_line(2));
                     }
@@ -526,12 +1691,11 @@ fn render_exception(payload: &Payload) -> Vec<DetailLine> {
         }
     }
 
-    if let Some(meta) = content.get("meta") {
-        if !meta.is_null() {
-            if !(matches!(meta, Value::Array(items) if items.is_empty())) {
-                push_value_lines(&mut lines, 1, "meta", meta);
-            }
-        }
+    if let Some(meta) = content.get("meta")
+        && !meta.is_null()
+        && !(matches!(meta, Value::Array(items) if items.is_empty()))
+    {
+        push_value_lines(&mut lines, 1, "meta", meta);
     }
 
     for (key, value) in content {
@@ -546,7 +1710,10 @@ fn render_exception(payload: &Payload) -> Vec<DetailLine> {
     lines
 }
 
-fn render_caller(payload: &Payload) -> Vec<DetailLine> {
+fn render_caller(
+    payload: &Payload,
+    origins: &mut BTreeMap<usize, SourceLocation>,
+) -> Vec<DetailLine> {
     let mut lines = Vec::new();
 
     if let Some(label) = payload
@@ -564,7 +1731,7 @@ fn render_caller(payload: &Payload) -> Vec<DetailLine> {
         .and_then(|value| value.as_object());
 
     if let Some(frame) = frame {
-        push_frame_lines(0, frame, 0, &mut lines);
+        push_frame_lines(0, frame, 0, &mut lines, origins);
     } else {
         return fallback_lines(payload);
     }
@@ -577,6 +1744,7 @@ fn push_frame_lines(
     frame: &Map<String, Value>,
     base_indent: usize,
     lines: &mut Vec<DetailLine>,
+    origins: &mut BTreeMap<usize, SourceLocation>,
 ) {
     let class = frame
         .get("class")
@@ -594,10 +1762,7 @@ fn push_frame_lines(
         .and_then(|value| value.as_str())
         .map(str::trim)
         .filter(|value| !value.is_empty());
-    let line_number = frame
-        .get("line_number")
-        .and_then(|value| value.as_i64())
-        .map(|number| number.to_string());
+    let line_number = frame.get("line_number").and_then(|value| value.as_i64());
 
     let vendor = frame
         .get("vendor_frame")
@@ -629,6 +1794,15 @@ fn push_frame_lines(
             style: SegmentStyle::Boolean,
         });
     }
+    if let Some(file) = file {
+        // Both the frame header and its location line point at the source.
+        let location = SourceLocation {
+            file: file.to_string(),
+            line: line_number.and_then(|line| u32::try_from(line).ok()),
+        };
+        origins.insert(lines.len(), location.clone());
+        origins.insert(lines.len() + 1, location);
+    }
     lines.push(DetailLine {
         indent: base_indent,
         segments: header_segments,
@@ -646,7 +1820,7 @@ fn push_frame_lines(
                 style: SegmentStyle::Plain,
             });
             location_segments.push(DetailSegment {
-                text: line_number,
+                text: line_number.to_string(),
                 style: SegmentStyle::Number,
             });
         }
@@ -818,24 +1992,24 @@ fn detail_key_value(label: &str, value: &str) -> DetailLine {
     }
 }
 
-fn format_duration(value: &Value) -> Option<String> {
+fn format_duration(value: &Value, locale: LocaleConfig) -> Option<String> {
     let number = value
         .as_f64()
         .or_else(|| value.as_i64().map(|n| n as f64))?;
-    Some(format!("{:.3} ms", number))
+    Some(format!("{} ms", locale.decimal(number, 3)))
 }
 
-fn format_bytes(value: &Value) -> Option<String> {
-    let mut bytes = value
+fn format_bytes(value: &Value, locale: LocaleConfig) -> Option<String> {
+    let bytes = value
         .as_f64()
         .or_else(|| value.as_i64().map(|n| n as f64))?;
-    let units = ["B", "KB", "MB", "GB", "TB"];
-    let mut unit_index = 0;
-    while bytes >= 1024.0 && unit_index + 1 < units.len() {
-        bytes /= 1024.0;
-        unit_index += 1;
-    }
-    Some(format!("{:.2} {}", bytes, units[unit_index]))
+    Some(humanize_bytes(bytes, locale))
+}
+
+/// `1.50 MB` style byte count, in 1024 steps.
+pub fn humanize_bytes(bytes: f64, locale: LocaleConfig) -> String {
+    let (value, unit) = crate::format::scaled_bytes(bytes);
+    format!("{} {}", locale.decimal(value, 2), unit)
 }
 
 fn json_value_preview(value: &Value) -> String {
@@ -936,7 +2110,120 @@ fn render_json(payload: &Payload) -> Vec<DetailLine> {
         .unwrap_or_else(|| fallback_lines(payload))
 }
 
-fn render_table(payload: &Payload) -> Vec<DetailLine> {
+/// The text of a `json_string` payload (`ray()->json()` sends it as `value`).
+fn json_string_text(payload: &Payload) -> Option<&str> {
+    payload
+        .content_string("value")
+        .or_else(|| payload.content_string("content"))
+}
+
+/// Why a `json_string` payload's text is not valid JSON, if it is not.
+pub fn json_string_error(payload: &Payload) -> Option<serde_json::Error> {
+    serde_json::from_str::<Value>(json_string_text(payload)?).err()
+}
+
+fn render_json_string(payload: &Payload) -> Vec<DetailLine> {
+    let Some(text) = json_string_text(payload) else {
+        return render_json(payload);
+    };
+    match serde_json::from_str::<Value>(text) {
+        Ok(value) => serde_json::to_string_pretty(&value)
+            .unwrap_or_default()
+            .lines()
+            .map(parse_plain_line)
+            .collect(),
+        Err(err) => json_error_lines(text, &err),
+    }
+}
+
+/// Characters shown either side of the error column on long lines.
+const JSON_ERROR_WINDOW: usize = 36;
+
+/// The parse error, then the lines leading up to it with a caret under the
+/// offending column. Long (e.g. minified) lines are cut to a window around it.
+fn json_error_lines(text: &str, err: &serde_json::Error) -> Vec<DetailLine> {
+    let source: Vec<&str> = text.lines().collect();
+    let mut lines = vec![
+        DetailLine {
+            indent: 0,
+            segments: vec![DetailSegment {
+                text: format!("⚠ invalid JSON: {}", err),
+                style: SegmentStyle::Notice,
+            }],
+        },
+        empty_line(0),
+    ];
+    if source.is_empty() {
+        return lines;
+    }
+
+    // serde_json reports one-based lines and byte columns; errors at the end
+    // of the input can point one past the last line.
+    let line_index = err.line().saturating_sub(1).min(source.len() - 1);
+    let error_line = source[line_index];
+    let byte = if err.line() > source.len() {
+        error_line.len()
+    } else {
+        err.column().saturating_sub(1).min(error_line.len())
+    };
+    let column = error_line
+        .char_indices()
+        .take_while(|(index, _)| *index < byte)
+        .count();
+
+    let first = line_index.saturating_sub(2);
+    let gutter = (line_index + 1).to_string().len();
+    let window_start = column.saturating_sub(JSON_ERROR_WINDOW);
+    let mut caret = column;
+
+    for (index, line) in source.iter().enumerate().take(line_index + 1).skip(first) {
+        let chars: Vec<char> = line
+            .chars()
+            .map(|ch| if ch == '\t' { ' ' } else { ch })
+            .collect();
+        let mut snippet = String::new();
+        if window_start > 0 && chars.len() > window_start {
+            snippet.push('…');
+        }
+        snippet.extend(chars.iter().skip(window_start).take(JSON_ERROR_WINDOW * 2));
+        if chars.len() > window_start + JSON_ERROR_WINDOW * 2 {
+            snippet.push('…');
+        }
+        if index == line_index {
+            caret = column - window_start + usize::from(window_start > 0);
+        }
+        lines.push(DetailLine {
+            indent: 0,
+            segments: vec![
+                DetailSegment {
+                    text: format!("{:>width$} │ ", index + 1, width = gutter),
+                    style: SegmentStyle::Null,
+                },
+                DetailSegment {
+                    text: snippet,
+                    style: SegmentStyle::String,
+                },
+            ],
+        });
+    }
+
+    lines.push(DetailLine {
+        indent: 0,
+        segments: vec![
+            DetailSegment {
+                text: format!("{} │ {}", " ".repeat(gutter), " ".repeat(caret)),
+                style: SegmentStyle::Null,
+            },
+            DetailSegment {
+                text: "^".to_string(),
+                style: SegmentStyle::Notice,
+            },
+        ],
+    });
+    lines
+}
+
+fn render_table(payload: &Payload, width: Option<usize>) -> Vec<DetailLine> {
     let content = match payload.content_object() {
         Some(content) => content,
         None => return fallback_lines(payload),
@@ -947,7 +2234,7 @@ fn render_table(payload: &Payload) -> Vec<DetailLine> {
             .iter()
             .find_map(|value| value.as_str().and_then(TableModel::from_html))
         {
-            return render_table_model(payload, model);
+            return render_table_model(payload, model, width);
         }
 
         if values.is_empty() {
@@ -955,7 +2242,7 @@ fn render_table(payload: &Payload) -> Vec<DetailLine> {
         }
 
         if let Some(table) = TableModel::from_values(values) {
-            return render_table_model(payload, table);
+            return render_table_model(payload, table, width);
         }
 
         return fallback_lines(payload);
@@ -989,7 +2276,11 @@ fn render_table(payload: &Payload) -> Vec<DetailLine> {
     fallback_lines(payload)
 }
 
-fn render_table_model(payload: &Payload, table: TableModel) -> Vec<DetailLine> {
+fn render_table_model(
+    payload: &Payload,
+    table: TableModel,
+    width: Option<usize>,
+) -> Vec<DetailLine> {
     let mut lines = Vec::new();
 
     if let Some(label) = payload
@@ -1001,13 +2292,146 @@ fn render_table_model(payload: &Payload, table: TableModel) -> Vec<DetailLine> {
         lines.push(parse_plain_line(""));
     }
 
-    for line in table.to_lines() {
+    for line in table.to_lines(width) {
         lines.push(parse_plain_line(&line));
     }
 
     lines
 }
 
+/// Lines for a payload type Raygun doesn't know, drawn with the renderer
+/// assigned to the type.
+pub fn render_unknown(
+    payload: &Payload,
+    renderer: UnknownRenderer,
+    width: Option<usize>,
+) -> Vec<DetailLine> {
+    let content = payload.content();
+    let json_lines = || {
+        serde_json::to_string_pretty(content)
+            .unwrap_or_default()
+            .lines()
+            .map(parse_plain_line)
+            .collect()
+    };
+    match renderer {
+        UnknownRenderer::Json => json_lines(),
+        UnknownRenderer::Text => payload_text(payload)
+            .map(|text| text.lines().map(parse_plain_line).collect())
+            .unwrap_or_else(json_lines),
+        UnknownRenderer::Table => {
+            let rows = content
+                .as_array()
+                .or_else(|| content.get("values").and_then(Value::as_array));
+            if let Some(table) = rows.and_then(|rows| TableModel::from_values(rows)) {
+                return render_table_model(payload, table, width);
+            }
+            match content.as_object().filter(|map| !map.is_empty()) {
+                Some(map) => ordered_map_entries(map)
+                    .into_iter()
+                    .map(|(key, value)| detail_key_value(key, &json_value_preview(value)))
+                    .collect(),
+                None => json_lines(),
+            }
+        }
+    }
+}
+
+/// Renderer picked by hand for one event when the automatic one, chosen by
+/// payload kind and content, guesses wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderOverride {
+    #[default]
+    Auto,
+    Json,
+    Text,
+    Hex,
+}
+
+impl RenderOverride {
+    /// The next renderer in the cycle, back to `Auto` after `Hex`.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::Json,
+            Self::Json => Self::Text,
+            Self::Text => Self::Hex,
+            Self::Hex => Self::Auto,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "automatic",
+            Self::Json => "raw JSON",
+            Self::Text => "plain text",
+            Self::Hex => "hex dump",
+        }
+    }
+}
+
+/// `payload` drawn by `mode`; `None` for [`RenderOverride::Auto`]. Plain text
+/// and hex show the payload's text, or its JSON when it has none.
+pub fn render_override(payload: &Payload, mode: RenderOverride) -> Option<Vec<DetailLine>> {
+    Some(match mode {
+        RenderOverride::Auto => return None,
+        RenderOverride::Json => render_unknown(payload, UnknownRenderer::Json, None),
+        RenderOverride::Text => render_unknown(payload, UnknownRenderer::Text, None),
+        RenderOverride::Hex => {
+            let text = payload_text(payload)
+                .map(str::to_string)
+                .unwrap_or_else(|| payload.content().to_string());
+            hex_lines(text.as_bytes())
+        }
+    })
+}
+
+/// The text a payload carries, for renderers that show it as is.
+fn payload_text(payload: &Payload) -> Option<&str> {
+    payload.content().as_str().or_else(|| {
+        ["content", "text", "value", "message"]
+            .iter()
+            .find_map(|key| payload.content_string(key))
+    })
+}
+
+/// `00000000  73 65 6c 65  |sele|`, sixteen bytes a row.
+fn hex_lines(bytes: &[u8]) -> Vec<DetailLine> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            DetailLine {
+                indent: 0,
+                segments: vec![
+                    DetailSegment {
+                        text: format!("{:08x}  ", row * 16),
+                        style: SegmentStyle::Number,
+                    },
+                    DetailSegment {
+                        text: format!("{:<47}  ", hex.join(" ")),
+                        style: SegmentStyle::Plain,
+                    },
+                    DetailSegment {
+                        text: format!("|{}|", ascii),
+                        style: SegmentStyle::String,
+                    },
+                ],
+            }
+        })
+        .collect()
+}
+
 fn fallback_lines(payload: &Payload) -> Vec<DetailLine> {
     let content = payload.content_object().cloned().unwrap_or_default();
     serde_json::to_string_pretty(&Value::Object(content))
@@ -1071,70 +2495,70 @@ fn parse_highlighted_line(line: &str, indent: usize) -> DetailLine {
     while cursor < trimmed.len() {
         let rest = &trimmed[cursor..];
 
-        if let Some(mat) = KEY_RE.find(rest) {
-            if mat.start() == 0 {
-                segments.push(DetailSegment {
-                    text: mat.as_str().to_string(),
-                    style: SegmentStyle::Key,
-                });
-                cursor += mat.end();
-                continue;
-            }
+        if let Some(mat) = KEY_RE.find(rest)
+            && mat.start() == 0
+        {
+            segments.push(DetailSegment {
+                text: mat.as_str().to_string(),
+                style: SegmentStyle::Key,
+            });
+            cursor += mat.end();
+            continue;
         }
 
-        if rest.starts_with('"') || rest.starts_with('\'') {
-            if let Some((token, len)) = extract_string(rest) {
-                segments.push(DetailSegment {
-                    text: token,
-                    style: SegmentStyle::String,
-                });
-                cursor += len;
-                continue;
-            }
+        if (rest.starts_with('"') || rest.starts_with('\''))
+            && let Some((token, len)) = extract_string(rest)
+        {
+            segments.push(DetailSegment {
+                text: token,
+                style: SegmentStyle::String,
+            });
+            cursor += len;
+            continue;
         }
 
-        if let Some(mat) = TYPE_RE.find(rest) {
-            if mat.start() == 0 {
-                segments.push(DetailSegment {
-                    text: mat.as_str().to_string(),
-                    style: SegmentStyle::Type,
-                });
-                cursor += mat.end();
-                continue;
-            }
+        if let Some(mat) = TYPE_RE.find(rest)
+            && mat.start() == 0
+        {
+            segments.push(DetailSegment {
+                text: mat.as_str().to_string(),
+                style: SegmentStyle::Type,
+            });
+            cursor += mat.end();
+            continue;
         }
 
-        if let Some(mat) = BOOL_RE.find(rest) {
-            if mat.start() == 0 {
-                segments.push(DetailSegment {
-                    text: mat.as_str().to_string(),
-                    style: SegmentStyle::Boolean,
-                });
-                cursor += mat.end();
-                continue;
-            }
+        if let Some(mat) = BOOL_RE.find(rest)
+            && mat.start() == 0
+        {
+            segments.push(DetailSegment {
+                text: mat.as_str().to_string(),
+                style: SegmentStyle::Boolean,
+            });
+            cursor += mat.end();
+            continue;
         }
 
-        if let Some(mat) = NULL_RE.find(rest) {
-            if mat.start() == 0 {
-                segments.push(DetailSegment {
-                    text: mat.as_str().to_string(),
-                    style: SegmentStyle::Null,
-                });
-                cursor += mat.end();
-                continue;
-            }
+        if let Some(mat) = NULL_RE.find(rest)
+            && mat.start() == 0
+        {
+            segments.push(DetailSegment {
+                text: mat.as_str().to_string(),
+                style: SegmentStyle::Null,
+            });
+            cursor += mat.end();
+            continue;
         }
 
-        if let Some(mat) = NUMBER_RE.find(rest) {
-            if mat.start() == 0 {
-                segments.push(DetailSegment {
-                    text: mat.as_str().to_string(),
-                    style: SegmentStyle::Number,
-                });
-                cursor += mat.end();
-                continue;
-            }
+        if let Some(mat) = NUMBER_RE.find(rest)
+            && mat.start() == 0
+        {
+            segments.push(DetailSegment {
+                text: mat.as_str().to_string(),
+                style: SegmentStyle::Number,
+            });
+            cursor += mat.end();
+            continue;
         }
 
         let ch_len = rest.chars().next().map(|ch| ch.len_utf8()).unwrap_or(1);
@@ -1150,11 +2574,11 @@ fn push_plain(text: &str, segments: &mut Vec<DetailSegment>) {
         return;
     }
 
-    if let Some(last) = segments.last_mut() {
-        if last.style == SegmentStyle::Plain {
-            last.text.push_str(text);
-            return;
-        }
+    if let Some(last) = segments.last_mut()
+        && last.style == SegmentStyle::Plain
+    {
+        last.text.push_str(text);
+        return;
     }
 
     segments.push(DetailSegment {
@@ -1190,7 +2614,8 @@ fn extract_string(input: &str) -> Option<(String, usize)> {
     None
 }
 
-fn sanitize_sf_dump(input: &str) -> String {
+/// Plain text of a Symfony VarDumper HTML dump, without markup or entities.
+pub(crate) fn sanitize_sf_dump(input: &str) -> String {
     let without_script = SF_SCRIPT_RE.replace_all(input, "");
     let without_style = SF_STYLE_RE.replace_all(&without_script, "");
 
@@ -1248,7 +2673,12 @@ fn is_parenthesis_close(line: &str) -> bool {
     line == ")" || line == "),"
 }
 
+static DEPTH_CUT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[\[{](?:#\d+)?\s*…\d*\s*[\]}]").unwrap());
+static SHORTENED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"^…\d+$|"…\d+"#).unwrap());
 static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+static JOB_CLASS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Za-z_][\w\\]*)(?:\s*\{|$)").unwrap());
 static SF_SCRIPT_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?is)<script[^>]*>.*?</script>").unwrap());
 static SF_STYLE_RE: Lazy<Regex> =
@@ -1440,7 +2870,7 @@ impl TableModel {
         Some(Self { headers, rows })
     }
 
-    fn to_lines(&self) -> Vec<String> {
+    fn to_lines(&self, max_width: Option<usize>) -> Vec<String> {
         let mut widths: Vec<usize> = self
             .headers
             .iter()
@@ -1455,6 +2885,21 @@ impl TableModel {
             }
         }
 
+        if let Some(max_width) = max_width {
+            // Every column adds a space either side and a trailing `|`.
+            let overhead = widths.len() * 3 + 1;
+            while widths.iter().sum::<usize>() + overhead > max_width {
+                let Some(widest) = widths
+                    .iter_mut()
+                    .filter(|width| **width > MIN_COLUMN_WIDTH)
+                    .max_by_key(|width| **width)
+                else {
+                    break;
+                };
+                *widest -= 1;
+            }
+        }
+
         let border = format_border(&widths, '-');
         let separator = format_border(&widths, '=');
         let header_line = format_row(&self.headers, &widths);
@@ -1567,20 +3012,13 @@ fn extract_image_src(html: &str) -> Option<&str> {
         .map(|m| m.as_str())
 }
 
-fn display_width(text: &str) -> usize {
-    text.chars().count()
-}
-
 fn truncate(text: &str, max_chars: usize) -> String {
-    let flat = text.replace('\n', " ");
-    if flat.chars().count() <= max_chars {
-        return flat;
-    }
-
-    let truncated: String = flat.chars().take(max_chars.saturating_sub(3)).collect();
-    format!("{}...", truncated)
+    clip(&text.replace('\n', " "), max_chars, "...")
 }
 
+/// Narrowest a table column gets when shrinking a table to fit the terminal.
+const MIN_COLUMN_WIDTH: usize = 5;
+
 fn format_border(widths: &[usize], fill: char) -> String {
     let mut line = String::from("+");
     for width in widths {
@@ -1593,9 +3031,13 @@ fn format_border(widths: &[usize], fill: char) -> String {
 fn format_row(cells: &[String], widths: &[usize]) -> String {
     let mut line = String::from("|");
     for (idx, width) in widths.iter().enumerate() {
-        let value = cells.get(idx).map(|cell| cell.as_str()).unwrap_or("");
+        let cell = cells.get(idx).map(|cell| cell.as_str()).unwrap_or("");
+        let value = clip_width(&cell.replace('\n', " "), *width, "...");
         line.push(' ');
-        line.push_str(&format!("{value:<width$}", value = value, width = *width));
+        // Pad by cells on screen; `format!` would count `char`s.
+        let padding = width.saturating_sub(display_width(&value));
+        line.push_str(&value);
+        line.push_str(&" ".repeat(padding));
         line.push(' ');
         line.push('|');
     }
@@ -1658,6 +3100,53 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn truncated_dumps_lead_with_a_notice() {
+        let dump = r#"<pre class=sf-dump>array:3 [<br />
+  "user" => App\User {#12 &hellip;4}<br />
+  "tags" => array:2 [<br />
+    0 => array:5 [ &hellip;5]<br />
+  ]<br />
+  "bio" => "Lorem ipsum"&hellip;480<br />
+]<br /></pre>"#;
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "custom",
+            "content": { "content": dump, "label": "" }
+        }))
+        .unwrap();
+
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert_eq!(view.lines[0].segments[0].style, SegmentStyle::Notice);
+        assert_eq!(
+            view.lines[0].segments[0].text,
+            "⚠ dump truncated by client (depth 1); long arrays and strings shortened"
+        );
+
+        // The client's own max_depth wins over the depth the markers imply.
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "log",
+            "content": {
+                "values": [],
+                "meta": [{ "clipboard_data": dump, "max_depth": 3 }]
+            }
+        }))
+        .unwrap();
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert!(
+            view.lines[0].segments[0]
+                .text
+                .starts_with("⚠ dump truncated by client (depth 3)")
+        );
+        assert_ne!(view.focus_line, Some(0));
+
+        let complete = parse_dump_with_notice("<pre class=sf-dump>\"short\"</pre>", None);
+        assert!(
+            complete
+                .iter()
+                .all(|line| line.segments[0].style != SegmentStyle::Notice)
+        );
+    }
+
     #[test]
     fn parses_nested_sf_dump_with_object_markers() {
         let dump = r#"
@@ -1715,7 +3204,7 @@ mod tests {
         }))
         .expect("payload should deserialize");
 
-        let lines = render_table(&payload);
+        let lines = render_table(&payload, None);
         assert_eq!(lines[0].segments[0].text, "Label: Users");
         let rendered: Vec<String> = lines
             .iter()
@@ -1732,6 +3221,51 @@ mod tests {
         assert!(rendered.iter().any(|line| line.contains("Alice")));
     }
 
+    #[test]
+    fn table_columns_shrink_to_fit_width() {
+        let table = TableModel {
+            headers: vec!["Name".to_string(), "Email".to_string()],
+            rows: vec![
+                vec!["Alice".to_string(), "alice@example.com".to_string()],
+                vec!["Bob".to_string(), "bob@example.com".to_string()],
+            ],
+        };
+
+        let natural = table.to_lines(None);
+        assert_eq!(natural[3], "| Alice | alice@example.com |");
+
+        let fitted = table.to_lines(Some(24));
+        assert!(fitted.iter().all(|line| display_width(line) <= 24));
+        assert_eq!(fitted[3], "| Alice | alice@exa... |");
+    }
+
+    #[test]
+    fn table_columns_line_up_wide_characters() {
+        let table = TableModel {
+            headers: vec!["名前".to_string(), "Email".to_string()],
+            rows: vec![
+                vec!["Alice".to_string(), "alice@example.com".to_string()],
+                vec!["東京 🎉".to_string(), "tokyo@example.com".to_string()],
+            ],
+        };
+
+        let natural = table.to_lines(None);
+        assert_eq!(natural[1], "| 名前    | Email             |");
+        assert_eq!(natural[4], "| 東京 🎉 | tokyo@example.com |");
+        assert!(
+            natural
+                .iter()
+                .all(|line| display_width(line) == display_width(&natural[0]))
+        );
+
+        let fitted = table.to_lines(Some(20));
+        assert!(
+            fitted
+                .iter()
+                .all(|line| display_width(line) == display_width(&fitted[0]))
+        );
+    }
+
     #[test]
     fn renders_log_prefers_clipboard_data_over_script() {
         let payload_json = r#"
@@ -1762,4 +3296,753 @@ mod tests {
         );
         assert!(!joined.contains("sf-dump"), "script leak: {}", joined);
     }
+
+    #[test]
+    fn exception_records_frame_origins_and_focuses_message() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "exception",
+            "content": {
+                "class": "RuntimeException",
+                "message": "boom",
+                "frames": [
+                    { "class": "App\\Job", "method": "handle", "file_name": "/app/Job.php", "line_number": 42 },
+                    { "class": "App\\Kernel", "method": "run" }
+                ]
+            }
+        }))
+        .expect("payload should deserialize");
+
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        let focus = view.focus_line.expect("exception should have a focus line");
+        assert!(view.lines[focus].segments[0].text.starts_with("message"));
+
+        let job = SourceLocation {
+            file: "/app/Job.php".to_string(),
+            line: Some(42),
+        };
+        let located: Vec<(usize, &SourceLocation)> = view
+            .origins
+            .iter()
+            .map(|(index, origin)| (*index, origin))
+            .collect();
+        // The summary location line plus the first frame's header and location.
+        assert_eq!(located.len(), 3);
+        assert!(located.iter().all(|(_, origin)| **origin == job));
+        let header = located[1].0;
+        assert!(view.lines[header].segments[0].text.starts_with("#1"));
+    }
+
+    #[test]
+    fn default_collapse_rules_match_keys_and_vendor_frames() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "exception",
+            "content": {
+                "class": "RuntimeException",
+                "message": "boom",
+                "frames": [
+                    { "class": "App\\Job", "file_name": "/app/Job.php", "line_number": 1 },
+                    { "class": "Illuminate\\Pipeline", "file_name": "/vendor/p.php", "line_number": 2, "vendor_frame": true }
+                ],
+                "meta": { "php": "8.3" }
+            }
+        }))
+        .expect("payload should deserialize");
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        let keys = |collapsed: &HashSet<usize>| {
+            let mut texts: Vec<String> = collapsed
+                .iter()
+                .map(|index| {
+                    view.lines[*index]
+                        .segments
+                        .iter()
+                        .map(|segment| segment.text.as_str())
+                        .collect()
+                })
+                .collect();
+            texts.sort();
+            texts
+        };
+
+        let rules = vec![VENDOR_FRAMES_RULE.to_string(), "meta".to_string()];
+        assert_eq!(
+            keys(&default_collapsed(&view, &rules)),
+            vec!["#2  Illuminate\\Pipeline [vendor]", "meta: {"]
+        );
+
+        let rules = vec!["stack trace".to_string()];
+        assert_eq!(
+            keys(&default_collapsed(&view, &rules)),
+            vec!["stack trace (2 frames)"]
+        );
+        assert!(default_collapsed(&view, &[]).is_empty());
+    }
+
+    #[test]
+    fn malformed_content_renders_without_panicking() {
+        let nested = (0..100).fold(json!("leaf"), |inner, _| json!({ "values": [inner] }));
+        let contents = [
+            json!(null),
+            json!("plain string"),
+            json!(42),
+            json!(["é", { "frames": "not-an-array" }]),
+            json!({ "values": "🎉\"unterminated", "frames": [1, null, "x"], "meta": 7 }),
+            json!({ "content": "<pre class=sf-dump>array:1 [<br />  \"é\" => {#1 ▼<br /></pre>" }),
+            nested,
+        ];
+        let kinds = [
+            "log",
+            "custom",
+            "text",
+            "table",
+            "exception",
+            "trace",
+            "caller",
+            "measure",
+            "label",
+            "json_string",
+            "decoded_json",
+            "size",
+            "unknown_kind",
+        ];
+
+        for kind in kinds {
+            for content in &contents {
+                let payload: Payload =
+                    serde_json::from_value(json!({ "type": kind, "content": content }))
+                        .expect("payload should deserialize");
+                let view = build_detail_view(&payload, UNIX_EPOCH);
+                assert!(!view.header.is_empty(), "{} rendered no header", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_kinds_render_with_their_assigned_renderer() {
+        let text = |lines: Vec<DetailLine>| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| {
+                    line.segments
+                        .iter()
+                        .map(|segment| segment.text.as_str())
+                        .collect()
+                })
+                .collect()
+        };
+        let payload = |content: Value| -> Payload {
+            serde_json::from_value(json!({ "type": "hologram", "content": content })).unwrap()
+        };
+
+        let message = payload(json!({ "message": "beam me up\nnow", "level": 3 }));
+        assert_eq!(
+            text(render_unknown(&message, UnknownRenderer::Text, None)),
+            vec!["beam me up", "now"]
+        );
+        assert_eq!(
+            text(render_unknown(&message, UnknownRenderer::Table, None)).len(),
+            2
+        );
+        assert_eq!(
+            text(render_unknown(&message, UnknownRenderer::Json, None))[0],
+            "{"
+        );
+
+        let rows = payload(json!([{ "id": 1, "name": "a" }, { "id": 2, "name": "b" }]));
+        let table = text(render_unknown(&rows, UnknownRenderer::Table, None)).join("\n");
+        assert!(table.contains("name"), "{}", table);
+        assert!(table.contains('b'), "{}", table);
+
+        let number = payload(json!(42));
+        assert_eq!(
+            text(render_unknown(&number, UnknownRenderer::Text, None)),
+            vec!["42"]
+        );
+    }
+
+    #[test]
+    fn sizes_are_humanized_and_compared_with_earlier_readings() {
+        let text = |lines: &[DetailLine]| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| {
+                    line.segments
+                        .iter()
+                        .map(|segment| segment.text.as_str())
+                        .collect()
+                })
+                .collect()
+        };
+
+        let payload: Payload =
+            serde_json::from_value(json!({ "type": "size", "content": { "size": "1572864" } }))
+                .unwrap();
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert_eq!(text(&view.lines), ["Size: 1.50 MB", "Bytes: 1572864"]);
+
+        let payload: Payload =
+            serde_json::from_value(json!({ "type": "size", "content": { "size": "lg" } })).unwrap();
+        assert_eq!(size_bytes(&payload), None);
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert_eq!(text(&view.lines), ["Text size: large"]);
+
+        assert!(reading_history_lines("import", &[2048.0], LocaleConfig::default()).is_empty());
+        assert_eq!(
+            text(&reading_history_lines(
+                "import",
+                &[1024.0, 2048.0, 1536.0],
+                LocaleConfig::default()
+            )),
+            [
+                "",
+                "Readings: 3 for \"import\"",
+                "Previous: 2.00 KB",
+                "Change: -512.00 B (-25.0%)"
+            ]
+        );
+    }
+
+    #[test]
+    fn phpinfo_is_grouped_into_sections() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "table",
+            "content": {
+                "label": "PHPInfo",
+                "values": {
+                    "PHP version": "8.3.4",
+                    "Memory limit": "128M",
+                    "Max post size": "8M",
+                    "Max file upload size": "2M",
+                    "PHP ini file": "/etc/php/8.3/cli/php.ini",
+                    "PHP scanned ini file": "/etc/php/8.3/cli/conf.d/10-opcache.ini,\n/etc/php/8.3/cli/conf.d/20-xdebug.ini",
+                    "Extensions": "Core, date, xdebug, PDO",
+                    "xdebug.mode": "debug"
+                }
+            }
+        }))
+        .unwrap();
+
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert!(view.header.starts_with("phpinfo"));
+        let text: Vec<String> = view
+            .lines
+            .iter()
+            .map(|line| {
+                let text: String = line.segments.iter().map(|s| s.text.as_str()).collect();
+                format!("{}{}", "  ".repeat(line.indent), text)
+            })
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "PHP",
+                "  PHP version: 8.3.4",
+                "",
+                "Limits",
+                "  Memory limit: 128M",
+                "  Max post size: 8M",
+                "  Max file upload size: 2M",
+                "",
+                "Configuration files",
+                "  PHP ini file: /etc/php/8.3/cli/php.ini",
+                "  PHP scanned ini file: 2 files",
+                "    /etc/php/8.3/cli/conf.d/10-opcache.ini",
+                "    /etc/php/8.3/cli/conf.d/20-xdebug.ini",
+                "",
+                "xdebug",
+                "  xdebug.mode: debug",
+                "",
+                "Extensions (4)",
+                "  Core",
+                "  date",
+                "  PDO",
+                "  xdebug",
+            ]
+        );
+    }
+
+    #[test]
+    fn scalars_render_compactly_with_a_type_badge() {
+        let render = |content: Value| {
+            let payload: Payload =
+                serde_json::from_value(json!({ "type": "custom", "content": content })).unwrap();
+            build_detail_view(&payload, UNIX_EPOCH)
+                .lines
+                .iter()
+                .map(|line| {
+                    line.segments
+                        .iter()
+                        .map(|segment| (segment.text.clone(), segment.style))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let lines = render(json!({ "content": true, "label": "Boolean" }));
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0][0], ("true".to_string(), SegmentStyle::Boolean));
+        assert_eq!(lines[0][2], (" bool ".to_string(), SegmentStyle::Badge));
+
+        let lines = render(json!({ "content": null, "label": "Null" }));
+        assert_eq!(lines[0][0], ("null".to_string(), SegmentStyle::Null));
+
+        let lines = render(json!({ "content": 1.5, "label": "ratio" }));
+        assert_eq!(lines[0][1].0, "ratio");
+        assert_eq!(lines[1][2].0, " float ");
+
+        // Strings keep their existing renderers.
+        let payload: Payload = serde_json::from_value(
+            json!({ "type": "custom", "content": { "content": "true", "label": "" } }),
+        )
+        .unwrap();
+        assert!(scalar_content(&payload).is_none());
+    }
+
+    #[test]
+    fn malformed_json_strings_point_at_the_error() {
+        let text = |lines: &[DetailLine]| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| line.segments.iter().map(|s| s.text.as_str()).collect())
+                .collect()
+        };
+        let payload = |value: &str| -> Payload {
+            serde_json::from_value(json!({ "type": "json_string", "content": { "value": value } }))
+                .unwrap()
+        };
+
+        let valid = payload(r#"{"id":1}"#);
+        assert!(json_string_error(&valid).is_none());
+        let view = build_detail_view(&valid, UNIX_EPOCH);
+        assert_eq!(text(&view.lines), ["{", "\"id\": 1", "}"]);
+        assert_eq!(view.lines[1].indent, 1);
+
+        let broken = payload("{\n  \"id\": 1\n  \"name\": \"x\"\n}");
+        let view = build_detail_view(&broken, UNIX_EPOCH);
+        assert_eq!(view.lines[0].segments[0].style, SegmentStyle::Notice);
+        assert_eq!(
+            text(&view.lines),
+            [
+                "⚠ invalid JSON: expected `,` or `}` at line 3 column 3",
+                "",
+                "1 │ {",
+                "2 │   \"id\": 1",
+                "3 │   \"name\": \"x\"",
+                "  │   ^",
+            ]
+        );
+
+        // Minified input is cut to a window around the column.
+        let minified = format!("[{}x{}]", "1,".repeat(60), ",1".repeat(40));
+        let lines = text(&build_detail_view(&payload(&minified), UNIX_EPOCH).lines);
+        let snippet = &lines[2];
+        let caret = &lines[3];
+        assert!(snippet.starts_with("1 │ …") && snippet.ends_with('…'));
+        let offset = caret.chars().count() - 1;
+        assert_eq!(snippet.chars().nth(offset), Some('x'));
+    }
+
+    #[test]
+    fn diff_payloads_list_changed_paths() {
+        let text = |view: &DetailViewModel| -> Vec<String> {
+            view.lines
+                .iter()
+                .map(|line| line.segments.iter().map(|s| s.text.as_str()).collect())
+                .collect()
+        };
+
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "custom",
+            "content": {
+                "old": { "name": "Ada", "age": 36 },
+                "new": "{\"name\":\"Ada L.\",\"email\":\"ada@example.com\"}"
+            }
+        }))
+        .unwrap();
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert_eq!(
+            text(&view),
+            [
+                "3 changes: 1 added, 1 removed, 1 changed",
+                "",
+                "- age: 36",
+                "~ name: \"Ada\" → \"Ada L.\"",
+                "+ email: \"ada@example.com\"",
+            ]
+        );
+        assert_eq!(view.lines[2].segments[0].style, SegmentStyle::Removed);
+
+        // Two log values only diff when the request is labelled `diff`.
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "log",
+            "content": { "values": ["a\nb", "a\nc"] }
+        }))
+        .unwrap();
+        assert!(diff_sides(&payload, None).is_none());
+        let view = build_labelled_detail_view(
+            &payload,
+            UNIX_EPOCH,
+            None,
+            Some("Diff"),
+            LocaleConfig::default(),
+        );
+        assert_eq!(
+            text(&view),
+            ["Lines: 1 added, 1 removed", "", "  a", "- b", "+ c"]
+        );
+    }
+
+    #[test]
+    fn filtering_keeps_matches_with_their_ancestors() {
+        let plain = |indent: usize, text: &str| DetailLine {
+            indent,
+            segments: vec![DetailSegment {
+                text: text.to_string(),
+                style: SegmentStyle::Plain,
+            }],
+        };
+        let mut detail = DetailViewModel {
+            header: "log".to_string(),
+            footer: String::new(),
+            lines: vec![
+                plain(0, "user"),
+                plain(1, "name: Ada"),
+                plain(1, "roles"),
+                plain(2, "0: admin"),
+                plain(2, "1: billing"),
+                plain(0, ""),
+                plain(0, "billing"),
+                plain(1, "plan: pro"),
+                plain(0, "meta"),
+            ],
+            origins: BTreeMap::from([(
+                7,
+                SourceLocation {
+                    file: "app.php".to_string(),
+                    line: Some(3),
+                },
+            )]),
+            focus_line: Some(1),
+        };
+        assert_eq!(content_line_count(&detail), 8);
+
+        assert_eq!(filter_lines(&mut detail, "BILLING"), 2);
+        let kept: Vec<(usize, String)> = detail
+            .lines
+            .iter()
+            .map(|line| (line.indent, line_text(line)))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                (0, "user".to_string()),
+                (1, "roles".to_string()),
+                (2, "1: billing".to_string()),
+                (0, "billing".to_string()),
+                (1, "plan: pro".to_string()),
+            ]
+        );
+        assert_eq!(detail.origins.keys().copied().collect::<Vec<_>>(), [4]);
+        assert_eq!(detail.focus_line, None);
+    }
+
+    #[test]
+    fn breadcrumbs_follow_keys_and_list_positions() {
+        let json = serde_json::to_string_pretty(&json!({
+            "user": { "name": "Ada", "roles": ["admin", "billing"] }
+        }))
+        .unwrap();
+        let lines: Vec<DetailLine> = json.lines().map(parse_plain_line).collect();
+        let billing = lines
+            .iter()
+            .position(|line| line_text(line).contains("billing"))
+            .unwrap();
+        let path = breadcrumb(&lines, billing);
+        assert_eq!(path, ["user", "roles", "[1]"]);
+        assert_eq!(dotted_path(&path), "user.roles[1]");
+
+        let dump = parse_sf_dump(concat!(
+            "<pre class=sf-dump>array:1 [<br />",
+            "  \"user\" => App\\User {#12 ▼<br />",
+            "    +\"tags\": array:1 [<br />",
+            "      0 => \"php\"<br />",
+            "    ]<br />",
+            "  }<br />",
+            "]<br /></pre>",
+        ));
+        let php = dump
+            .iter()
+            .position(|line| line_text(line).contains("\"php\""))
+            .unwrap();
+        assert_eq!(dotted_path(&breadcrumb(&dump, php)), "user.tags[0]");
+    }
+
+    #[test]
+    fn text_payloads_render_in_their_language() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "text",
+            "content": { "content": "select id\nfrom users" }
+        }))
+        .unwrap();
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert!(view.header.starts_with("text • SQL • "), "{}", view.header);
+        assert_eq!(view.lines[1].segments[0].style, SegmentStyle::String);
+
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "custom",
+            "content": { "content": "{\"id\":7}", "label": "Order" }
+        }))
+        .unwrap();
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        assert!(view.header.contains("• JSON •"), "{}", view.header);
+        let text: Vec<String> = view.lines.iter().map(line_text).collect();
+        assert_eq!(text, ["Label: Order", "", "{", "\"id\": 7", "}"]);
+    }
+
+    #[test]
+    fn render_overrides_show_json_text_and_hex() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "custom",
+            "content": { "content": "<b>hi</b>\u{1}", "label": "" }
+        }))
+        .unwrap();
+        assert!(render_override(&payload, RenderOverride::Auto).is_none());
+
+        let json: Vec<String> = render_override(&payload, RenderOverride::Json)
+            .unwrap()
+            .iter()
+            .map(line_text)
+            .collect();
+        assert!(json.contains(&"\"label\": \"\"".to_string()), "{:?}", json);
+
+        let text = render_override(&payload, RenderOverride::Text).unwrap();
+        assert_eq!(line_text(&text[0]), "<b>hi</b>\u{1}");
+
+        let hex = render_override(&payload, RenderOverride::Hex).unwrap();
+        assert_eq!(
+            line_text(&hex[0]),
+            "00000000  3c 62 3e 68 69 3c 2f 62 3e 01                    |<b>hi</b>.|"
+        );
+
+        let mut mode = RenderOverride::Auto;
+        for _ in 0..4 {
+            mode = mode.next();
+        }
+        assert_eq!(mode, RenderOverride::Auto);
+    }
+
+    #[test]
+    fn job_events_show_the_event_class_and_job() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "job_event",
+            "content": {
+                "event_name": "JobFailed",
+                "job": "<pre class=sf-dump id=sf-dump-1 data-indent-pad=\"  \"><abbr title=\"App\\Jobs\\SendInvoice\" class=sf-dump-note>SendInvoice</abbr> {<a class=sf-dump-ref>#812</a><samp data-depth=1 class=sf-dump-expanded>\n  +<span class=sf-dump-public>invoiceId</span>: <span class=sf-dump-num>42</span>\n</samp>}\n</pre>",
+                "exception": null
+            }
+        }))
+        .unwrap();
+        assert_eq!(job_class(&payload).as_deref(), Some("SendInvoice"));
+
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        let text: Vec<String> = view.lines.iter().map(line_text).collect();
+        assert_eq!(text[..2], ["Event: JobFailed", "Job: SendInvoice"]);
+        assert!(text.contains(&"Payload".to_string()), "{:?}", text);
+        assert!(!text.contains(&"Exception".to_string()), "{:?}", text);
+        assert!(view.header.starts_with("job"), "{}", view.header);
+    }
+
+    #[test]
+    fn laravel_events_show_their_name_and_data() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "event",
+            "content": {
+                "name": "App\\Events\\OrderShipped",
+                "event": { "orderId": 42 },
+                "payload": null,
+                "class_based_event": true
+            }
+        }))
+        .unwrap();
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        let text: Vec<String> = view.lines.iter().map(line_text).collect();
+        assert_eq!(
+            text,
+            [
+                "Event: App\\Events\\OrderShipped",
+                "",
+                "Event data",
+                "orderId: 42"
+            ]
+        );
+        assert!(view.header.starts_with("event"), "{}", view.header);
+    }
+
+    #[test]
+    fn executed_queries_show_sql_with_bindings_filled_in() {
+        let payload: Payload = serde_json::from_value(json!({
+            "type": "executed_query",
+            "content": {
+                "sql": "select * from `users` where `name` = ? and `note` != '?' and `active` = ? and `team_id` is ?",
+                "bindings": ["O'Brien", true, null],
+                "time": 1.25,
+                "connection_name": "mysql"
+            }
+        }))
+        .unwrap();
+        let view = build_detail_view(&payload, UNIX_EPOCH);
+        let text: Vec<String> = view.lines.iter().map(line_text).collect();
+        assert_eq!(
+            text[1],
+            "select * from `users` where `name` = 'O''Brien' and `note` != '?' and `active` = 1 and `team_id` is null"
+        );
+        assert!(text.contains(&"Time: 1.250 ms".to_string()), "{:?}", text);
+        assert!(
+            text.contains(&"Connection: mysql".to_string()),
+            "{:?}",
+            text
+        );
+        assert!(text.contains(&"0: O'Brien".to_string()), "{:?}", text);
+        assert!(view.header.starts_with("query"), "{}", view.header);
+
+        let named: Payload = serde_json::from_value(json!({
+            "type": "executed_query",
+            "content": {
+                "sql": "update posts set title = :title where id = :id",
+                "bindings": { "title": "Hi", "id": 7 }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            query_sql(&named, "update posts set title = :title where id = :id"),
+            "update posts set title = 'Hi' where id = 7"
+        );
+    }
+
+    mod tree {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        /// Indents of a well-formed tree: each line is at most one level
+        /// deeper than the line before it.
+        fn indents() -> impl Strategy<Value = Vec<usize>> {
+            prop::collection::vec(0..4usize, 0..40).prop_map(|steps| {
+                let mut indents: Vec<usize> = Vec::with_capacity(steps.len());
+                for step in steps {
+                    let deepest = indents.last().map_or(0, |indent| indent + 1);
+                    indents.push(step.min(deepest));
+                }
+                indents
+            })
+        }
+
+        /// A tree together with a set of collapsed line indices, some of
+        /// which may point past the end or at lines without children.
+        fn collapsed_tree() -> impl Strategy<Value = (Vec<usize>, HashSet<usize>)> {
+            indents().prop_flat_map(|indents| {
+                let len = indents.len() + 2;
+                (Just(indents), prop::collection::hash_set(0..len, 0..len))
+            })
+        }
+
+        fn detail(indents: &[usize]) -> DetailViewModel {
+            DetailViewModel {
+                header: String::new(),
+                footer: String::new(),
+                lines: indents
+                    .iter()
+                    .map(|&indent| DetailLine {
+                        indent,
+                        segments: Vec::new(),
+                    })
+                    .collect(),
+                origins: BTreeMap::new(),
+                focus_line: None,
+            }
+        }
+
+        fn visible(detail: &DetailViewModel, collapsed: &HashSet<usize>) -> Vec<usize> {
+            visible_indices_with_children(detail, Some(collapsed)).0
+        }
+
+        proptest! {
+            #[test]
+            fn hides_exactly_the_descendants_of_collapsed_parents(
+                (indents, collapsed) in collapsed_tree()
+            ) {
+                let detail = detail(&indents);
+                let (visible, has_children) =
+                    visible_indices_with_children(&detail, Some(&collapsed));
+
+                prop_assert_eq!(has_children.len(), indents.len());
+                for (index, &indent) in indents.iter().enumerate() {
+                    prop_assert_eq!(
+                        has_children[index],
+                        indents.get(index + 1) == Some(&(indent + 1))
+                    );
+                }
+
+                let expected: Vec<usize> = (0..indents.len())
+                    .filter(|&line| {
+                        !collapsed.iter().any(|&parent| {
+                            parent < line
+                                && has_children[parent]
+                                && indents[parent + 1..=line]
+                                    .iter()
+                                    .all(|&indent| indent > indents[parent])
+                        })
+                    })
+                    .collect();
+                prop_assert_eq!(&visible, &expected);
+                prop_assert_eq!(
+                    visible_indices_with_children(&detail, None).0,
+                    (0..indents.len()).collect::<Vec<_>>()
+                );
+            }
+
+            #[test]
+            fn collapsing_only_hides_and_expanding_only_reveals(
+                (indents, collapsed) in collapsed_tree(),
+                extra in 0..42usize,
+            ) {
+                let detail = detail(&indents);
+                let before = visible(&detail, &collapsed);
+
+                let mut more = collapsed.clone();
+                more.insert(extra);
+                let after = visible(&detail, &more);
+                prop_assert!(after.iter().all(|line| before.contains(line)));
+
+                let mut fewer = collapsed.clone();
+                fewer.remove(&extra);
+                let expanded = visible(&detail, &fewer);
+                prop_assert!(before.iter().all(|line| expanded.contains(line)));
+            }
+
+            #[test]
+            fn collapsing_keeps_the_cursor_on_its_line(
+                (indents, collapsed) in collapsed_tree(),
+                cursor in 0..42usize,
+            ) {
+                prop_assume!(!indents.is_empty());
+                let detail = detail(&indents);
+                let before = visible(&detail, &collapsed);
+                // The cursor is clamped to the visible lines, as the app does.
+                let cursor = cursor.min(before.len() - 1);
+                let line = before[cursor];
+
+                let mut more = collapsed.clone();
+                more.insert(line);
+                let after = visible(&detail, &more);
+                prop_assert_eq!(after.get(cursor), Some(&line));
+                prop_assert_eq!(&after[..cursor], &before[..cursor]);
+
+                // Expanding it again restores the lines it hid.
+                more.remove(&line);
+                if !collapsed.contains(&line) {
+                    prop_assert_eq!(visible(&detail, &more), before);
+                }
+            }
+        }
+    }
 }
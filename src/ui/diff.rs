@@ -0,0 +1,212 @@
+use serde_json::Value;
+
+/// Whether a diff line is shared by both sides or only present in one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Above this many line pairs the LCS table gets too large to build on every
+/// frame; the diff degrades to "everything removed, everything added".
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Line-based diff of `old` against `new` using a longest common subsequence.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let line = |op, text: &str| DiffLine {
+        op,
+        text: text.to_string(),
+    };
+
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|text| line(DiffOp::Removed, text))
+            .chain(new.iter().map(|text| line(DiffOp::Added, text)))
+            .collect();
+    }
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(line(DiffOp::Same, old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(line(DiffOp::Removed, old[i]));
+            i += 1;
+        } else {
+            lines.push(line(DiffOp::Added, new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|text| line(DiffOp::Removed, text)));
+    lines.extend(new[j..].iter().map(|text| line(DiffOp::Added, text)));
+    lines
+}
+
+/// How a value differs between the two sides of a structural diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeOp {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One difference found by [`value_diff`]; `path` is empty for the root value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueChange {
+    pub path: String,
+    pub op: ChangeOp,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+/// Structural diff of two JSON values: object keys are matched by name and
+/// array items by position. Keys keep the order of `old`, with keys only
+/// present in `new` after them.
+pub fn value_diff(old: &Value, new: &Value) -> Vec<ValueChange> {
+    let mut changes = Vec::new();
+    diff_into(String::new(), old, new, &mut changes);
+    changes
+}
+
+fn diff_into(path: String, old: &Value, new: &Value, changes: &mut Vec<ValueChange>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let child = child_path(&path, key);
+                match new.get(key) {
+                    Some(new_value) => diff_into(child, old_value, new_value, changes),
+                    None => changes.push(ValueChange {
+                        path: child,
+                        op: ChangeOp::Removed,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, new_value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                changes.push(ValueChange {
+                    path: child_path(&path, key),
+                    op: ChangeOp::Added,
+                    old: None,
+                    new: Some(new_value.clone()),
+                });
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for index in 0..old.len().max(new.len()) {
+                let child = format!("{}[{}]", path, index);
+                match (old.get(index), new.get(index)) {
+                    (Some(old_value), Some(new_value)) => {
+                        diff_into(child, old_value, new_value, changes)
+                    }
+                    (Some(old_value), None) => changes.push(ValueChange {
+                        path: child,
+                        op: ChangeOp::Removed,
+                        old: Some(old_value.clone()),
+                        new: None,
+                    }),
+                    (None, Some(new_value)) => changes.push(ValueChange {
+                        path: child,
+                        op: ChangeOp::Added,
+                        old: None,
+                        new: Some(new_value.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old == new => {}
+        _ => changes.push(ValueChange {
+            path,
+            op: ChangeOp::Changed,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+    }
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn reports_added_removed_and_changed_keys() {
+        let old = json!({ "name": "Ada", "age": 36, "roles": ["admin", "dev"] });
+        let new = json!({ "name": "Ada L.", "roles": ["admin"], "email": "ada@example.com" });
+        let diff = value_diff(&old, &new);
+        let changes: Vec<(&str, ChangeOp)> = diff
+            .iter()
+            .map(|change| (change.path.as_str(), change.op))
+            .collect();
+
+        assert_eq!(
+            changes,
+            [
+                ("age", ChangeOp::Removed),
+                ("name", ChangeOp::Changed),
+                ("roles[1]", ChangeOp::Removed),
+                ("email", ChangeOp::Added),
+            ]
+        );
+        assert!(value_diff(&json!(1), &json!(1)).is_empty());
+        assert_eq!(value_diff(&json!(1), &json!("1"))[0].path, "");
+    }
+
+    #[test]
+    fn reports_changed_lines_between_shared_context() {
+        let diff = line_diff(
+            "{\n  \"a\": 1,\n  \"b\": 2\n}",
+            "{\n  \"a\": 1,\n  \"b\": 3\n}",
+        );
+        let ops: Vec<(DiffOp, &str)> = diff
+            .iter()
+            .map(|line| (line.op, line.text.as_str()))
+            .collect();
+
+        assert_eq!(
+            ops,
+            vec![
+                (DiffOp::Same, "{"),
+                (DiffOp::Same, "  \"a\": 1,"),
+                (DiffOp::Removed, "  \"b\": 2"),
+                (DiffOp::Added, "  \"b\": 3"),
+                (DiffOp::Same, "}"),
+            ]
+        );
+    }
+}
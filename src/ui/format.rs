@@ -0,0 +1,120 @@
+//! Guessing the language of free-form text sent with `ray()->text()` or a
+//! custom payload, so it can get a badge and the matching renderer instead of
+//! showing as one long string.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// What a payload's text looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentFormat {
+    Sql,
+    Html,
+    Json,
+    Xml,
+    Plain,
+}
+
+/// Statements that open with a keyword followed by what makes them SQL rather
+/// than prose starting with the same word: a column list, `INTO`, `SET`, ...
+static SQL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?is)^\s*(select\s+(distinct\s+)?([*\d@`"]|[\w.]+\s*(,|\(|\s+from\s|\s+as\s))|insert\s+into\s|update\s+\S+\s+set\s|delete\s+from\s|(create|alter|drop)\s+(temporary\s+)?(table|index|view|database|schema)\s|with\s+\S+\s+as\s*\(|explain\s+select\s)"#,
+    )
+    .unwrap()
+});
+
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^<\s*([A-Za-z][A-Za-z0-9:_-]*)").unwrap());
+
+/// Elements whose presence as the first tag means HTML rather than XML.
+const HTML_TAGS: &[&str] = &[
+    "a", "article", "b", "body", "br", "button", "code", "div", "em", "footer", "form", "h1", "h2",
+    "h3", "h4", "h5", "h6", "head", "header", "hr", "html", "i", "img", "input", "label", "li",
+    "main", "nav", "ol", "option", "p", "pre", "script", "section", "select", "small", "span",
+    "strong", "style", "table", "tbody", "td", "textarea", "th", "thead", "tr", "u", "ul",
+];
+
+impl ContentFormat {
+    pub fn detect(text: &str) -> Self {
+        let trimmed = text.trim();
+        if trimmed.starts_with("<?xml") {
+            return Self::Xml;
+        }
+        if trimmed.starts_with('<') && trimmed.ends_with('>') {
+            if trimmed
+                .get(..9)
+                .is_some_and(|start| start.eq_ignore_ascii_case("<!doctype"))
+            {
+                return Self::Html;
+            }
+            if let Some(tag) = TAG_RE
+                .captures(trimmed)
+                .and_then(|captures| captures.get(1))
+            {
+                let tag = tag.as_str().to_ascii_lowercase();
+                return if HTML_TAGS.contains(&tag.as_str()) {
+                    Self::Html
+                } else {
+                    Self::Xml
+                };
+            }
+        }
+        if ((trimmed.starts_with('{') && trimmed.ends_with('}'))
+            || (trimmed.starts_with('[') && trimmed.ends_with(']')))
+            && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+        {
+            return Self::Json;
+        }
+        if SQL_RE.is_match(trimmed) {
+            return Self::Sql;
+        }
+        Self::Plain
+    }
+
+    /// Short name shown in the badge.
+    pub fn badge(self) -> &'static str {
+        match self {
+            Self::Sql => "SQL",
+            Self::Html => "HTML",
+            Self::Json => "JSON",
+            Self::Xml => "XML",
+            Self::Plain => "TEXT",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_languages_apart() {
+        let cases = [
+            ("select * from users where id = 1", ContentFormat::Sql),
+            ("  UPDATE orders SET paid = 1", ContentFormat::Sql),
+            (
+                "with recent as (select 1) select * from recent",
+                ContentFormat::Sql,
+            ),
+            ("SELECT count(*) FROM jobs", ContentFormat::Sql),
+            (
+                "Select a shipping method from the list",
+                ContentFormat::Plain,
+            ),
+            ("Update your profile", ContentFormat::Plain),
+            (
+                "<div class=\"cart\"><b>3</b> items</div>",
+                ContentFormat::Html,
+            ),
+            ("<!DOCTYPE html><html></html>", ContentFormat::Html),
+            ("<?xml version=\"1.0\"?><order/>", ContentFormat::Xml),
+            ("<order id=\"7\"><line/></order>", ContentFormat::Xml),
+            ("{\"id\": 7, \"tags\": []}", ContentFormat::Json),
+            ("[1, 2", ContentFormat::Plain),
+            ("Payment failed", ContentFormat::Plain),
+        ];
+        for (text, format) in cases {
+            assert_eq!(ContentFormat::detect(text), format, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Numbers and times of day written the way `[locale]` in the config file
+//! asks. Renderers get the locale from [`UiSettings`](crate::tui::UiSettings)
+//! or from whoever asked for the output.
+
+use crate::config::{ClockFormat, LocaleConfig};
+
+impl LocaleConfig {
+    /// `value` with `places` decimals, e.g. `1.50` or `1,50`.
+    pub fn decimal(self, value: f64, places: usize) -> String {
+        let formatted = format!("{:.*}", places, value);
+        if self.decimal_separator == '.' {
+            formatted
+        } else {
+            formatted.replacen('.', &self.decimal_separator.to_string(), 1)
+        }
+    }
+
+    /// A time of day given in seconds since midnight, e.g. `14:05:01` or
+    /// `02:05:01 PM`. `fraction` holds digits shown after the seconds.
+    pub fn time_of_day(self, seconds: u64, fraction: Option<&str>) -> String {
+        let hours = seconds % 86_400 / 3_600;
+        let (hours, suffix) = match self.clock {
+            ClockFormat::TwentyFourHour => (hours, ""),
+            ClockFormat::TwelveHour => (
+                (hours + 11) % 12 + 1,
+                if hours < 12 { " AM" } else { " PM" },
+            ),
+        };
+        let fraction = fraction
+            .map(|digits| format!("{}{}", self.decimal_separator, digits))
+            .unwrap_or_default();
+        format!(
+            "{:02}:{:02}:{:02}{}{}",
+            hours,
+            seconds % 3_600 / 60,
+            seconds % 60,
+            fraction,
+            suffix
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_for_the_configured_locale() {
+        let european = LocaleConfig {
+            decimal_separator: ',',
+            clock: ClockFormat::TwentyFourHour,
+        };
+        let american = LocaleConfig {
+            decimal_separator: '.',
+            clock: ClockFormat::TwelveHour,
+        };
+
+        assert_eq!(european.decimal(1.5, 2), "1,50");
+        assert_eq!(american.decimal(1.5, 2), "1.50");
+
+        let afternoon = 14 * 3_600 + 5 * 60 + 1;
+        assert_eq!(european.time_of_day(afternoon, None), "14:05:01");
+        assert_eq!(
+            european.time_of_day(afternoon, Some("250000")),
+            "14:05:01,250000"
+        );
+        assert_eq!(american.time_of_day(afternoon, None), "02:05:01 PM");
+        assert_eq!(american.time_of_day(30, None), "12:00:30 AM");
+        assert_eq!(american.time_of_day(12 * 3_600, None), "12:00:00 PM");
+    }
+}
@@ -1 +1,9 @@
+pub mod color;
+pub mod copy;
 pub mod detail;
+pub mod diff;
+pub mod format;
+pub mod locale;
+pub mod query;
+pub mod schema;
+pub mod text;
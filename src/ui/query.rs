@@ -0,0 +1,228 @@
+//! JSONPath-style queries over an event's JSON, as typed into the query prompt:
+//! `$.payloads[0].content.values[*]`, `..file` or, jq-style, `.meta.php_version`.
+//!
+//! Supported steps are `.name` and `["name"]` for object keys, `[2]` and `[-1]`
+//! for array elements, `.*`, `[*]` and `[]` for every child, and `..name` or
+//! `..*` for matches at any depth. The leading `$` is optional.
+
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum QueryError {
+    #[error("a query starts with `$` or `.`, e.g. $.payloads[0].content")]
+    MissingRoot,
+    #[error("expected a key after `{0}` at column {1}")]
+    MissingKey(String, usize),
+    #[error("unterminated `[` at column {0}")]
+    UnterminatedBracket(usize),
+    #[error("`[{0}]` is neither an index, a quoted key nor *")]
+    InvalidSubscript(String),
+    #[error("unexpected `{0}` at column {1}")]
+    Unexpected(char, usize),
+}
+
+/// A parsed query; [`Query::run`] evaluates it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Children,
+    /// `..`: the value itself and everything below it, before the next step.
+    Descendants,
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Self, QueryError> {
+        let trimmed = source.trim();
+        let chars: Vec<char> = trimmed.chars().collect();
+        let mut position = match chars.first() {
+            Some('$') => 1,
+            Some('.') => 0,
+            _ => return Err(QueryError::MissingRoot),
+        };
+
+        let mut steps = Vec::new();
+        while position < chars.len() {
+            match chars[position] {
+                '.' => {
+                    let recursive = chars.get(position + 1) == Some(&'.');
+                    let dots = if recursive { 2 } else { 1 };
+                    position += dots;
+                    if recursive {
+                        steps.push(Step::Descendants);
+                    }
+                    match chars.get(position) {
+                        Some('*') => {
+                            steps.push(Step::Children);
+                            position += 1;
+                        }
+                        Some('[') if recursive => {}
+                        // A lone `.` is jq's identity: the whole document.
+                        None if chars.len() == 1 => {}
+                        _ => {
+                            let name: String = chars[position..]
+                                .iter()
+                                .take_while(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '-'))
+                                .collect();
+                            if name.is_empty() {
+                                return Err(QueryError::MissingKey(".".repeat(dots), position + 1));
+                            }
+                            position += name.chars().count();
+                            steps.push(Step::Key(name));
+                        }
+                    }
+                }
+                '[' => {
+                    let start = position;
+                    let close = chars[start..]
+                        .iter()
+                        .position(|ch| *ch == ']')
+                        .map(|offset| start + offset)
+                        .ok_or(QueryError::UnterminatedBracket(start + 1))?;
+                    let inner: String = chars[start + 1..close].iter().collect();
+                    steps.push(subscript(inner.trim())?);
+                    position = close + 1;
+                }
+                ch => return Err(QueryError::Unexpected(ch, position + 1)),
+            }
+        }
+
+        Ok(Self {
+            source: trimmed.to_string(),
+            steps,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Every value the query selects in `document`, in document order.
+    pub fn run<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![document];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for value in current {
+                match step {
+                    Step::Key(key) => next.extend(value.get(key.as_str())),
+                    Step::Index(index) => {
+                        if let Value::Array(items) = value {
+                            let resolved = if *index < 0 {
+                                items.len().checked_sub(index.unsigned_abs() as usize)
+                            } else {
+                                Some(*index as usize)
+                            };
+                            next.extend(resolved.and_then(|index| items.get(index)));
+                        }
+                    }
+                    Step::Children => match value {
+                        Value::Array(items) => next.extend(items),
+                        Value::Object(map) => next.extend(map.values()),
+                        _ => {}
+                    },
+                    Step::Descendants => descendants(value, &mut next),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+fn subscript(inner: &str) -> Result<Step, QueryError> {
+    if inner.is_empty() || inner == "*" {
+        return Ok(Step::Children);
+    }
+    if let Ok(index) = inner.parse::<i64>() {
+        return Ok(Step::Index(index));
+    }
+    let quoted = inner.len() >= 2
+        && ((inner.starts_with('"') && inner.ends_with('"'))
+            || (inner.starts_with('\'') && inner.ends_with('\'')));
+    if quoted {
+        return Ok(Step::Key(inner[1..inner.len() - 1].to_string()));
+    }
+    Err(QueryError::InvalidSubscript(inner.to_string()))
+}
+
+/// `value` and everything nested in it, parents before their children.
+fn descendants<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(value);
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| descendants(item, out)),
+        Value::Object(map) => map.values().for_each(|item| descendants(item, out)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn run(query: &str, document: &Value) -> Vec<Value> {
+        Query::parse(query)
+            .unwrap()
+            .run(document)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn selects_keys_indices_wildcards_and_descendants() {
+        let document = json!({
+            "payloads": [
+                { "type": "log", "content": { "values": ["a", "b"] }, "origin": { "file": "x.php" } },
+                { "type": "exception", "content": { "class": "E" }, "origin": { "file": "y.php" } }
+            ],
+            "meta": { "php-version": "8.3" }
+        });
+
+        assert_eq!(
+            run("$.payloads[0].content.values[*]", &document),
+            vec![json!("a"), json!("b")]
+        );
+        assert_eq!(
+            run(".payloads[-1].type", &document),
+            vec![json!("exception")]
+        );
+        assert_eq!(
+            run("$..file", &document),
+            vec![json!("x.php"), json!("y.php")]
+        );
+        assert_eq!(
+            run(r#"$["meta"]['php-version']"#, &document),
+            vec![json!("8.3")]
+        );
+        assert_eq!(run(".payloads[].type", &document).len(), 2);
+        assert_eq!(run(".", &document), vec![document.clone()]);
+        assert!(run("$.payloads[5].type", &document).is_empty());
+        assert!(run("$.meta.missing", &document).is_empty());
+
+        assert_eq!(
+            Query::parse("payloads").unwrap_err(),
+            QueryError::MissingRoot
+        );
+        assert_eq!(
+            Query::parse("$.payloads[0").unwrap_err(),
+            QueryError::UnterminatedBracket(11)
+        );
+        assert_eq!(
+            Query::parse("$.payloads[first]").unwrap_err(),
+            QueryError::InvalidSubscript("first".into())
+        );
+        assert_eq!(
+            Query::parse("$.").unwrap_err(),
+            QueryError::MissingKey(".".into(), 3)
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use super::detail::{DetailLine, DetailSegment, SegmentStyle};
+
+/// Inferred shape of one or more JSON values. Array elements are merged into a
+/// single node, so a list of rows is described once with the union of their
+/// keys and types.
+#[derive(Debug, Clone, Default)]
+struct SchemaNode {
+    types: BTreeSet<&'static str>,
+    /// Number of objects observed, used to flag keys missing from some of them.
+    objects: usize,
+    fields: Vec<(String, usize, SchemaNode)>,
+    array_lengths: Option<(usize, usize)>,
+    items: Option<Box<SchemaNode>>,
+}
+
+impl SchemaNode {
+    fn observe(&mut self, value: &Value) {
+        match value {
+            Value::Null => {
+                self.types.insert("null");
+            }
+            Value::Bool(_) => {
+                self.types.insert("boolean");
+            }
+            Value::Number(number) => {
+                self.types
+                    .insert(if number.is_f64() { "float" } else { "integer" });
+            }
+            Value::String(_) => {
+                self.types.insert("string");
+            }
+            Value::Array(values) => {
+                self.types.insert("array");
+                let len = values.len();
+                self.array_lengths = Some(match self.array_lengths {
+                    Some((min, max)) => (min.min(len), max.max(len)),
+                    None => (len, len),
+                });
+                for value in values {
+                    self.items.get_or_insert_default().observe(value);
+                }
+            }
+            Value::Object(map) => {
+                self.types.insert("object");
+                self.objects += 1;
+                for (key, value) in map {
+                    let index = match self.fields.iter().position(|(name, _, _)| name == key) {
+                        Some(index) => index,
+                        None => {
+                            self.fields.push((key.clone(), 0, SchemaNode::default()));
+                            self.fields.len() - 1
+                        }
+                    };
+                    let (_, seen, node) = &mut self.fields[index];
+                    *seen += 1;
+                    node.observe(value);
+                }
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.types
+            .iter()
+            .map(|kind| match *kind {
+                "array" => match self.array_lengths {
+                    Some((min, max)) if min == max => format!("array[{}]", min),
+                    Some((min, max)) => format!("array[{}..{}]", min, max),
+                    None => "array".to_string(),
+                },
+                "object" => match self.fields.len() {
+                    1 => "object (1 key)".to_string(),
+                    count => format!("object ({} keys)", count),
+                },
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    fn push_lines(&self, lines: &mut Vec<DetailLine>, indent: usize, name: &str, optional: bool) {
+        let mut segments = vec![DetailSegment {
+            text: name.to_string(),
+            style: SegmentStyle::Key,
+        }];
+        if optional {
+            segments.push(DetailSegment {
+                text: "?".to_string(),
+                style: SegmentStyle::Null,
+            });
+        }
+        segments.push(DetailSegment {
+            text: ": ".to_string(),
+            style: SegmentStyle::Plain,
+        });
+        segments.push(DetailSegment {
+            text: self.describe(),
+            style: SegmentStyle::Type,
+        });
+        lines.push(DetailLine { indent, segments });
+
+        for (key, seen, node) in &self.fields {
+            node.push_lines(lines, indent + 1, key, *seen < self.objects);
+        }
+        if let Some(items) = &self.items {
+            items.push_lines(lines, indent + 1, "[]", false);
+        }
+    }
+}
+
+/// Describes the structure of `value` — keys, types and array lengths, but no
+/// values — as detail lines rooted at `name`.
+pub fn describe_schema(name: &str, value: &Value, indent: usize) -> Vec<DetailLine> {
+    let mut node = SchemaNode::default();
+    node.observe(value);
+
+    let mut lines = Vec::new();
+    node.push_lines(&mut lines, indent, name, false);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(lines: &[DetailLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                let text: String = line.segments.iter().map(|s| s.text.as_str()).collect();
+                format!("{}{}", "  ".repeat(line.indent), text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merges_array_elements_into_one_shape() {
+        let value = json!({
+            "label": "Users",
+            "values": [
+                { "id": 1, "email": "a@example.com", "score": 1.5 },
+                { "id": 2, "email": null },
+            ],
+            "meta": []
+        });
+
+        assert_eq!(
+            render(&describe_schema("content", &value, 0)),
+            vec![
+                "content: object (3 keys)",
+                "  label: string",
+                "  meta: array[0]",
+                "  values: array[2]",
+                "    []: object (3 keys)",
+                "      email: null | string",
+                "      id: integer",
+                "      score?: float",
+            ]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Clipping text to a number of characters. Characters here are grapheme
+//! clusters, not `char`s, so an emoji with its modifiers or a letter with its
+//! combining accents is kept or cut as a whole and never renders as a broken
+//! fragment. Where text is lined up in columns, [`display_width`] and
+//! [`clip_width`] count terminal cells instead, two for wide characters.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Characters in `text` as a reader counts them.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Keeps the start of `text`, at most `max` characters including the
+/// `ellipsis` that marks a cut. Too narrow for the ellipsis, the text is cut
+/// without one.
+pub fn clip(text: &str, max: usize, ellipsis: &str) -> String {
+    if grapheme_count(text) <= max {
+        return text.to_string();
+    }
+    let Some(keep) = max.checked_sub(grapheme_count(ellipsis)) else {
+        return text.graphemes(true).take(max).collect();
+    };
+    let mut clipped: String = text.graphemes(true).take(keep).collect();
+    clipped.push_str(ellipsis);
+    clipped
+}
+
+/// Terminal cells `text` takes up: two for CJK characters and most emoji,
+/// one for other characters.
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// A cluster joined from several wide characters (a family emoji, a flag)
+/// still takes two cells.
+fn grapheme_width(grapheme: &str) -> usize {
+    grapheme.width().min(2)
+}
+
+/// Like [`clip`], but keeps at most `max` terminal cells, for text lined up
+/// in columns.
+pub fn clip_width(text: &str, max: usize, ellipsis: &str) -> String {
+    if display_width(text) <= max {
+        return text.to_string();
+    }
+    let budget = max.checked_sub(display_width(ellipsis));
+    let mut clipped = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let width = grapheme_width(grapheme);
+        if used + width > budget.unwrap_or(max) {
+            break;
+        }
+        used += width;
+        clipped.push_str(grapheme);
+    }
+    if budget.is_some() {
+        clipped.push_str(ellipsis);
+    }
+    clipped
+}
+
+/// Like [`clip`], but keeps the end of `text` and marks the cut at the start.
+pub fn clip_start(text: &str, max: usize, ellipsis: &str) -> String {
+    let count = grapheme_count(text);
+    if count <= max {
+        return text.to_string();
+    }
+    let Some(keep) = max.checked_sub(grapheme_count(ellipsis)) else {
+        return text.graphemes(true).skip(count - max).collect();
+    };
+    let tail: String = text.graphemes(true).skip(count - keep).collect();
+    format!("{}{}", ellipsis, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_between_grapheme_clusters() {
+        // A family emoji joined from four people, a flag and an accent
+        // combined with its letter: each is one character on screen.
+        let text = "👨‍👩‍👧‍👦🇳🇴e\u{301}abc";
+        assert_eq!(grapheme_count(text), 6);
+        assert_eq!(clip(text, 6, "…"), text);
+        assert_eq!(clip(text, 3, "…"), "👨‍👩‍👧‍👦🇳🇴…");
+        assert_eq!(clip(text, 4, "..."), "👨‍👩‍👧‍👦...");
+        assert_eq!(clip(text, 2, "..."), "👨‍👩‍👧‍👦🇳🇴");
+        assert_eq!(clip(text, 0, "…"), "");
+        assert_eq!(clip_start(text, 5, "…"), "…e\u{301}abc");
+        assert_eq!(clip_start(text, 1, "…"), "…");
+    }
+
+    #[test]
+    fn counts_wide_characters_as_two_cells() {
+        assert_eq!(display_width("名前"), 4);
+        assert_eq!(display_width("👨‍👩‍👧‍👦🇳🇴e\u{301}"), 5);
+        assert_eq!(clip_width("東京都庁", 5, "…"), "東京…");
+        assert_eq!(clip_width("東京都庁", 8, "…"), "東京都庁");
+        assert_eq!(clip_width("東京", 1, "…"), "…");
+    }
+}
@@ -0,0 +1,127 @@
+//! Drives the real binary through a pseudo terminal (a Unix PTY, or ConPTY on
+//! Windows) to check terminal setup and teardown end to end.
+
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use portable_pty::{CommandBuilder, PtySize, native_pty_system};
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("a free local port")
+}
+
+/// Collects PTY output on a background thread so reads never block the test.
+struct Output {
+    received: mpsc::Receiver<Vec<u8>>,
+    text: String,
+}
+
+impl Output {
+    fn new(mut reader: Box<dyn Read + Send>) -> Self {
+        let (sender, received) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            while let Ok(read) = reader.read(&mut buffer) {
+                if read == 0 || sender.send(buffer[..read].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            received,
+            text: String::new(),
+        }
+    }
+
+    fn wait_for(&mut self, needle: &str) -> bool {
+        let deadline = Instant::now() + TIMEOUT;
+        while !self.text.contains(needle) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.received.recv_timeout(remaining) {
+                Ok(chunk) => self.text.push_str(&String::from_utf8_lossy(&chunk)),
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+}
+
+#[test]
+fn starts_and_quits_cleanly_in_a_pseudo_terminal() {
+    let config_dir = std::env::temp_dir().join(format!("raygun-pty-{}", std::process::id()));
+    std::fs::create_dir_all(&config_dir).unwrap();
+    // An existing config file skips the first-run overlay.
+    let config_path = config_dir.join("config.toml");
+    std::fs::write(&config_path, "").unwrap();
+
+    let pty = native_pty_system()
+        .openpty(PtySize {
+            rows: 30,
+            cols: 100,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .expect("open a pseudo terminal");
+
+    let mut command = CommandBuilder::new(env!("CARGO_BIN_EXE_raygun"));
+    command.arg("--bind");
+    command.arg(format!("127.0.0.1:{}", free_port()));
+    command.arg("--config");
+    command.arg(&config_path);
+    command.env("RUST_LOG", "off");
+    let mut child = pty
+        .slave
+        .spawn_command(command)
+        .expect("spawn raygun in the pseudo terminal");
+    drop(pty.slave);
+
+    let mut output = Output::new(pty.master.try_clone_reader().unwrap());
+    let mut input = pty.master.take_writer().unwrap();
+
+    assert!(
+        output.wait_for("waiting for payloads"),
+        "the UI never rendered:\n{}",
+        output.text
+    );
+    if cfg!(unix) {
+        // ConPTY re-renders the screen instead of passing these through.
+        assert!(output.text.contains("\x1b[?1049h"), "no alternate screen");
+        assert!(output.text.contains("\x1b[?1000h"), "no mouse capture");
+    }
+
+    input.write_all(b"q").unwrap();
+    input.flush().unwrap();
+
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().ok();
+            panic!("raygun did not exit after `q`:\n{}", output.text);
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert!(status.success(), "raygun exited with {:?}", status);
+
+    if cfg!(unix) {
+        output.wait_for("\x1b[?1049l");
+        assert!(
+            output.text.contains("\x1b[?1049l"),
+            "alternate screen left active"
+        );
+    }
+
+    std::fs::remove_dir_all(&config_dir).ok();
+}